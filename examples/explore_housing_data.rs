@@ -9,7 +9,7 @@ const INSTALL_DIR: &str = r"G:\最终幻想XIV";
 
 fn main() {
     let game = GameData::new(Path::new(INSTALL_DIR));
-    let all_items = game.load_all_items();
+    let all_items = game.load_all_items(&game.load_ui_category_names());
     let sgb_paths = game.load_housing_sgb_paths();
 
     // 筛选房屋外装物品
@@ -89,7 +89,7 @@ async fn run() {
             .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
             .collect();
 
-        let mut renderer = ModelRenderer::new(&device);
+        let mut renderer = ModelRenderer::new(&device).expect("创建渲染器失败");
         renderer.set_model_type(ModelType::Background);
         renderer.set_mesh_data(&device, &queue, &geometry, &load_result.mesh_textures);
 
@@ -35,7 +35,7 @@ async fn run() {
         .expect("无法获取 GPU device");
 
     let game = GameData::new(Path::new(INSTALL_DIR));
-    let all_items = game.load_all_items();
+    let all_items = game.load_all_items(&game.load_ui_category_names());
     let sgb_map = game.load_housing_sgb_paths();
 
     // 筛选房屋外装物品
@@ -109,7 +109,7 @@ async fn run() {
             .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
             .collect();
 
-        let mut renderer = ModelRenderer::new(&device);
+        let mut renderer = ModelRenderer::new(&device).expect("创建渲染器失败");
         renderer.set_model_type(ModelType::Background);
         renderer.set_mesh_data(&device, &queue, &geometry, &load_result.mesh_textures);
 
@@ -1,16 +1,21 @@
-use crate::math::{look_at, mat4_mul, perspective};
+use crate::math::{cross, look_at, mat4_mul, normalize, perspective, project_point, sub};
 use crate::types::BoundingBox;
 
+/// 垂直视场角
+const FOV_Y: f32 = std::f32::consts::FRAC_PI_4;
+/// 未对焦时假定的场景尺寸 (角色模型量级)
+const DEFAULT_SCENE_SIZE: f32 = 2.0;
+/// 最小缩放距离
+const MIN_DISTANCE: f32 = 0.1;
+
 /// 轨道相机
 pub struct Camera {
     pub distance: f32,
     pub yaw: f32,
     pub pitch: f32,
     pub target: [f32; 3],
-    /// 远裁面距离，根据场景大小动态调整
-    pub far: f32,
-    /// 最大缩放距离，根据场景大小动态调整
-    pub max_distance: f32,
+    /// 场景包围盒对角线长度，裁剪面、缩放范围与平移速度均据此推导
+    pub scene_size: f32,
 }
 
 impl Default for Camera {
@@ -20,8 +25,7 @@ impl Default for Camera {
             yaw: std::f32::consts::FRAC_PI_2,
             pitch: 0.3,
             target: [0.0, 0.8, 0.0],
-            far: 100.0,
-            max_distance: 20.0,
+            scene_size: DEFAULT_SCENE_SIZE,
         }
     }
 }
@@ -38,28 +42,131 @@ impl Camera {
     pub fn view_proj(&self, aspect: f32) -> [[f32; 4]; 4] {
         let eye = self.eye_position();
         let view = look_at(eye, self.target, [0.0, 1.0, 0.0]);
-        let proj = perspective(std::f32::consts::FRAC_PI_4, aspect, 0.1, self.far);
+        let proj = perspective(FOV_Y, aspect, self.near(), self.far());
         mat4_mul(proj, view)
     }
 
-    /// 根据包围盒自动对焦，同时调整远裁面和缩放范围
+    /// 近裁面随相机距离缩放，远距离观察大模型时保留深度精度
+    pub fn near(&self) -> f32 {
+        (self.distance * 0.01).max(0.01)
+    }
+
+    /// 远裁面覆盖相机到目标的距离再加两倍场景尺寸，保证整个场景在视锥内
+    pub fn far(&self) -> f32 {
+        self.distance + self.scene_size * 2.0
+    }
+
+    /// 最大缩放距离，与场景尺寸成比例
+    pub fn max_distance(&self) -> f32 {
+        self.scene_size.max(DEFAULT_SCENE_SIZE) * 5.0
+    }
+
+    /// 滚轮缩放，`amount` 为正时拉近
+    pub fn zoom(&mut self, amount: f32) {
+        self.distance = (self.distance - amount).clamp(MIN_DISTANCE, self.max_distance());
+    }
+
+    /// 世界坐标投影到 NDC (x, y ∈ [-1, 1]，y 向上)，位于相机后方时返回 None
+    pub fn project(&self, p: [f32; 3], aspect: f32) -> Option<[f32; 2]> {
+        project_point(self.view_proj(aspect), p).map(|ndc| [ndc[0], ndc[1]])
+    }
+
+    /// 由 NDC 坐标生成拾取射线，返回 (起点, 单位方向)
+    pub fn screen_ray(&self, ndc: [f32; 2], aspect: f32) -> ([f32; 3], [f32; 3]) {
+        let eye = self.eye_position();
+        let f = normalize(sub(self.target, eye));
+        let r = normalize(cross(f, [0.0, 1.0, 0.0]));
+        let u = cross(r, f);
+        let tan_half = (FOV_Y / 2.0).tan();
+        let x = ndc[0] * tan_half * aspect;
+        let y = ndc[1] * tan_half;
+        let dir = normalize([
+            f[0] + r[0] * x + u[0] * y,
+            f[1] + r[1] * x + u[1] * y,
+            f[2] + r[2] * x + u[2] * y,
+        ]);
+        (eye, dir)
+    }
+
+    /// 根据包围盒自动对焦，同时记录场景尺寸 (决定裁剪面与缩放范围)
     pub fn focus_on(&mut self, bbox: &BoundingBox) {
         self.target = bbox.center();
         let size = bbox.size();
-        self.distance = if size > 0.01 { size * 1.2 } else { 3.0 };
-        self.far = (size * 10.0).max(100.0);
-        self.max_distance = (size * 5.0).max(20.0);
+        if size > 0.01 {
+            self.distance = size * 1.2;
+            self.scene_size = size;
+        } else {
+            self.distance = 3.0;
+            self.scene_size = DEFAULT_SCENE_SIZE;
+        }
         self.yaw = std::f32::consts::FRAC_PI_2;
         self.pitch = 0.15;
     }
 
-    /// 右键拖拽平移
+    /// 右键拖拽平移，速度随相机距离与场景尺寸缩放 (贴近大模型时也不会过慢)
     pub fn pan(&mut self, dx: f32, dy: f32) {
         let right = [self.yaw.sin(), 0.0, -self.yaw.cos()];
         let up = [0.0, 1.0, 0.0];
-        let scale = self.distance * 0.002;
+        let scale = self.distance.max(self.scene_size * 0.25) * 0.002;
         for i in 0..3 {
             self.target[i] += -right[i] * dx * scale + up[i] * dy * scale;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corners(bbox: &BoundingBox) -> Vec<[f32; 3]> {
+        (0..8)
+            .map(|i| {
+                let pick = |axis: usize| {
+                    if i & (1 << axis) == 0 {
+                        bbox.min[axis]
+                    } else {
+                        bbox.max[axis]
+                    }
+                };
+                [pick(0), pick(1), pick(2)]
+            })
+            .collect()
+    }
+
+    /// 包围盒的 8 个角点都在视锥内: x/y ∈ [-1, 1]，深度 ∈ [0, 1]
+    fn assert_in_clip_volume(camera: &Camera, bbox: &BoundingBox, aspect: f32) {
+        let view_proj = camera.view_proj(aspect);
+        for corner in corners(bbox) {
+            let ndc = project_point(view_proj, corner).expect("角点位于相机后方");
+            assert!(
+                ndc[0].abs() <= 1.0 && ndc[1].abs() <= 1.0,
+                "角点 {:?} 超出画面: {:?}",
+                corner,
+                ndc
+            );
+            assert!(
+                (0.0..=1.0).contains(&ndc[2]),
+                "角点 {:?} 被裁剪面裁掉: 深度 {}",
+                corner,
+                ndc[2]
+            );
+            assert_eq!(camera.project(corner, aspect), Some([ndc[0], ndc[1]]));
+        }
+    }
+
+    #[test]
+    fn focused_large_bbox_stays_inside_clip_volume() {
+        // 50 米宽的房屋外装
+        let bbox = BoundingBox {
+            min: [-25.0, 0.0, -10.0],
+            max: [25.0, 15.0, 10.0],
+        };
+        let aspect = 16.0 / 9.0;
+        let mut camera = Camera::default();
+        camera.focus_on(&bbox);
+        assert_in_clip_volume(&camera, &bbox, aspect);
+
+        camera.distance = camera.max_distance();
+        assert_in_clip_volume(&camera, &bbox, aspect);
+    }
+}
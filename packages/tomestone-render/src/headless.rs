@@ -0,0 +1,72 @@
+//! 无窗口渲染: 把模型渲染为 RGBA 像素
+//!
+//! 可使用调用方已有的 wgpu 设备 (图形界面的缩略图队列)，也可自行创建 (命令行)。
+//! egui 纹理注册不在这里，由应用层处理。
+
+use crate::{Camera, MeshTextures, ModelRenderer, ModelType, SceneSettings, TextureData, Vertex};
+
+pub struct HeadlessRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    renderer: ModelRenderer,
+}
+
+impl HeadlessRenderer {
+    /// 使用已有的设备与队列，渲染器创建失败时返回 Err
+    pub fn new(device: wgpu::Device, queue: wgpu::Queue) -> Result<Self, String> {
+        let renderer = ModelRenderer::new(&device)?;
+        Ok(Self {
+            device,
+            queue,
+            renderer,
+        })
+    }
+
+    /// 自行创建 wgpu 实例、适配器与设备，阻塞直到设备可用
+    pub fn headless() -> Result<Self, String> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .map_err(|e| format!("没有可用的 GPU 适配器: {}", e))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("tomestone_headless"),
+            ..Default::default()
+        }))
+        .map_err(|e| format!("创建 GPU 设备失败: {}", e))?;
+        Self::new(device, queue)
+    }
+
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    pub fn set_model_type(&mut self, model_type: ModelType) {
+        self.renderer.set_model_type(model_type);
+    }
+
+    /// 上传网格、渲染一帧并读回 RGBA8 像素；结束后释放网格，渲染目标保留供下次复用。
+    /// 没有非空网格或读回失败时返回 None
+    pub fn render_to_image(
+        &mut self,
+        meshes: &[(&[Vertex], &[u16])],
+        textures: &[MeshTextures],
+        camera: &Camera,
+        scene: &SceneSettings,
+        size: [u32; 2],
+    ) -> Option<TextureData> {
+        let (device, queue) = (&self.device, &self.queue);
+        self.renderer.set_mesh_data(device, queue, meshes, textures);
+        let drawable = self.renderer.has_mesh() && size[0] > 0 && size[1] > 0;
+        let pixels = drawable.then(|| {
+            self.renderer
+                .render_offscreen(device, queue, size[0], size[1], 1.0, camera, scene);
+            self.renderer.read_pixels(device, queue)
+        });
+        self.renderer.set_mesh_data(device, queue, &[], &[]);
+        pixels.flatten()
+    }
+}
@@ -1,8 +1,13 @@
 mod camera;
+mod headless;
 mod math;
 mod model_renderer;
 mod types;
 
 pub use camera::Camera;
-pub use model_renderer::ModelRenderer;
-pub use types::{BoundingBox, MeshTextures, ModelType, SceneSettings, TextureData, Vertex};
+pub use headless::HeadlessRenderer;
+pub use model_renderer::{ModelRenderer, RetainedMeshData};
+pub use types::{
+    BoundingBox, ColorTableTexture, MeshTextures, ModelType, SceneSettings, TextureData, Vertex,
+    MAX_COLOR_TABLE_ROWS,
+};
@@ -56,3 +56,53 @@ pub(crate) fn normalize(v: [f32; 3]) -> [f32; 3] {
     }
     [v[0] / len, v[1] / len, v[2] / len]
 }
+
+/// 世界坐标 -> NDC (x, y ∈ [-1, 1]，y 向上；z 为深度)，位于相机后方时返回 None
+pub(crate) fn project_point(view_proj: [[f32; 4]; 4], p: [f32; 3]) -> Option<[f32; 3]> {
+    let mut clip = [0.0f32; 4];
+    for (j, c) in clip.iter_mut().enumerate() {
+        *c = view_proj[0][j] * p[0]
+            + view_proj[1][j] * p[1]
+            + view_proj[2][j] * p[2]
+            + view_proj[3][j];
+    }
+    if clip[3] <= 1e-6 {
+        return None;
+    }
+    Some([clip[0] / clip[3], clip[1] / clip[3], clip[2] / clip[3]])
+}
+
+/// Möller–Trumbore 射线-三角形求交，返回沿射线的距离 t
+pub(crate) fn ray_triangle(
+    origin: [f32; 3],
+    dir: [f32; 3],
+    a: [f32; 3],
+    b: [f32; 3],
+    c: [f32; 3],
+) -> Option<f32> {
+    let e1 = sub(b, a);
+    let e2 = sub(c, a);
+    let p = cross(dir, e2);
+    let det = dot(e1, p);
+    // 双面求交
+    if det.abs() < 1e-8 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = sub(origin, a);
+    let u = dot(s, p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = cross(s, e1);
+    let v = dot(dir, q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = dot(e2, q) * inv_det;
+    if t > 1e-5 {
+        Some(t)
+    } else {
+        None
+    }
+}
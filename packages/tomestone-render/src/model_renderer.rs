@@ -1,6 +1,8 @@
 use crate::camera::Camera;
-use crate::math::{normalize, sub};
-use crate::types::{MeshTextures, ModelType, SceneSettings, TextureData, Vertex};
+use crate::math::{normalize, ray_triangle, sub};
+use crate::types::{
+    MeshTextures, ModelType, SceneSettings, TextureData, Vertex, MAX_COLOR_TABLE_ROWS,
+};
 
 /// Uniform buffer 数据 (16-byte aligned fields, 匹配 WGSL Uniforms 布局)
 #[repr(C)]
@@ -18,12 +20,41 @@ struct Uniforms {
     ambient_ground: [f32; 3],
     fresnel_intensity: f32,
     /// bit0: 1=Equipment(使用顶点颜色遮罩+法线alpha裁剪), 0=Background
+    /// bit1: 1=调试着色 (直接输出调试顶点颜色)
+    /// bit2: 1=忽略顶点颜色漫反射遮罩
+    /// bit3: 1=忽略遮罩贴图 AO
     model_flags: u32,
     _pad4: [f32; 3],
 }
 
+/// 颜色表 uniform (匹配 WGSL ColorTable 布局)
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ColorTableUniform {
+    rows: [[f32; 4]; MAX_COLOR_TABLE_ROWS],
+    /// 0 = 不使用颜色表
+    row_count: u32,
+    _pad: [u32; 3],
+}
+
+impl ColorTableUniform {
+    fn new(row_count: u32, rows: &[[f32; 3]]) -> Self {
+        let mut uniform: Self = bytemuck::Zeroable::zeroed();
+        for (dst, src) in uniform.rows.iter_mut().zip(rows) {
+            *dst = [src[0], src[1], src[2], 1.0];
+        }
+        uniform.row_count = row_count.min(MAX_COLOR_TABLE_ROWS as u32);
+        uniform
+    }
+}
+
 struct GpuMesh {
     vertex_buffer: wgpu::Buffer,
+    /// 调试着色用的逐顶点颜色 (location 5)，默认全零
+    debug_color_buffer: wgpu::Buffer,
+    vertex_count: usize,
+    /// 在 set_mesh_data 输入中的下标 (空网格会被跳过，因此与 meshes 下标不一定一致)
+    source_index: usize,
     index_buffer: wgpu::Buffer,
     index_count: u32,
     texture_bind_group: wgpu::BindGroup,
@@ -33,6 +64,49 @@ struct GpuMesh {
     mask_view: wgpu::TextureView,
     _emissive_tex: wgpu::Texture,
     emissive_view: wgpu::TextureView,
+    _color_id_tex: wgpu::Texture,
+    color_id_view: wgpu::TextureView,
+    /// ColorTableUniform，染色时只重写该缓冲
+    color_table_buffer: wgpu::Buffer,
+}
+
+/// set_mesh_data 输入的 CPU 端副本，释放 GPU 资源后据此重新上传，
+/// 导出等需要原始网格的功能通过 `ModelRenderer::retained_mesh_data` 只读访问
+#[derive(Default, Clone)]
+pub struct RetainedMeshData {
+    geometry: Vec<(Vec<Vertex>, Vec<u16>)>,
+    /// 与 geometry 一一对应
+    textures: Vec<Option<MeshTextures>>,
+}
+
+impl RetainedMeshData {
+    /// 网格数量 (含空网格，与 set_mesh_data 的输入一一对应)
+    pub fn len(&self) -> usize {
+        self.geometry.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.geometry.is_empty()
+    }
+
+    /// 第 `index` 个网格的 (顶点, 索引)
+    pub fn geometry(&self, index: usize) -> Option<(&[Vertex], &[u16])> {
+        self.geometry
+            .get(index)
+            .map(|(v, i)| (v.as_slice(), i.as_slice()))
+    }
+
+    /// 第 `index` 个网格的贴图，diffuse 为最近一次 update_textures 写入的结果
+    pub fn textures(&self, index: usize) -> Option<&MeshTextures> {
+        self.textures.get(index)?.as_ref()
+    }
+}
+
+/// 对比快照: 截取时的一整组已上传网格，与实时网格互换即可切换显示
+struct MeshSnapshot {
+    meshes: Vec<GpuMesh>,
+    pick_geometry: Vec<(Vec<[f32; 3]>, Vec<u16>)>,
+    retained: RetainedMeshData,
 }
 
 /// 1×1 默认法线贴图 (flat normal)
@@ -41,6 +115,8 @@ const DEFAULT_NORMAL: [u8; 4] = [128, 128, 255, 255];
 const DEFAULT_MASK: [u8; 4] = [0, 128, 255, 255];
 /// 1×1 默认自发光贴图 (黑)
 const DEFAULT_EMISSIVE: [u8; 4] = [0, 0, 0, 255];
+/// 1×1 默认 ID 贴图 (不使用颜色表时绑定)
+const DEFAULT_COLOR_ID: [u8; 4] = [0, 0, 0, 255];
 
 /// 离屏模型渲染器
 pub struct ModelRenderer {
@@ -53,11 +129,22 @@ pub struct ModelRenderer {
     depth_texture: Option<(wgpu::Texture, wgpu::TextureView)>,
     target_size: [u32; 2],
     meshes: Vec<GpuMesh>,
+    /// CPU 端几何 (顶点位置, 索引)，用于鼠标拾取，与 meshes 一一对应
+    pick_geometry: Vec<(Vec<[f32; 3]>, Vec<u16>)>,
     model_type: ModelType,
+    debug_overlay: bool,
+    retained: RetainedMeshData,
+    /// GPU 资源已释放 (release_gpu)，下次渲染前需要 restore_gpu
+    gpu_released: bool,
+    /// 对比快照；显示快照期间与 meshes/pick_geometry/retained 互换
+    snapshot: Option<MeshSnapshot>,
+    snapshot_visible: bool,
 }
 
 impl ModelRenderer {
-    pub fn new(device: &wgpu::Device) -> Self {
+    /// 创建着色器、管线与共享资源；后端不支持 (如部分 GL 驱动) 产生校验错误时返回 Err
+    pub fn new(device: &wgpu::Device) -> Result<Self, String> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("model_shader"),
             source: wgpu::ShaderSource::Wgsl(include_str!("shaders/model.wgsl").into()),
@@ -118,6 +205,17 @@ impl ModelRenderer {
                     tex_entry(2), // normal
                     tex_entry(3), // mask
                     tex_entry(4), // emissive
+                    tex_entry(5), // 颜色表 ID
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
             });
 
@@ -143,37 +241,48 @@ impl ModelRenderer {
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: Some("vs_main"),
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: std::mem::size_of::<Vertex>() as u64,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x3,
-                            offset: 0,
-                            shader_location: 0,
-                        },
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x3,
-                            offset: 12,
-                            shader_location: 1,
-                        },
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x2,
-                            offset: 24,
-                            shader_location: 2,
-                        },
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x4,
-                            offset: 32,
-                            shader_location: 3,
-                        },
-                        wgpu::VertexAttribute {
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<Vertex>() as u64,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x3,
+                                offset: 0,
+                                shader_location: 0,
+                            },
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x3,
+                                offset: 12,
+                                shader_location: 1,
+                            },
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x2,
+                                offset: 24,
+                                shader_location: 2,
+                            },
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x4,
+                                offset: 32,
+                                shader_location: 3,
+                            },
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x4,
+                                offset: 48,
+                                shader_location: 4,
+                            },
+                        ],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<[f32; 4]>() as u64,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[wgpu::VertexAttribute {
                             format: wgpu::VertexFormat::Float32x4,
-                            offset: 48,
-                            shader_location: 4,
-                        },
-                    ],
-                }],
+                            offset: 0,
+                            shader_location: 5,
+                        }],
+                    },
+                ],
                 compilation_options: Default::default(),
             },
             fragment: Some(wgpu::FragmentState {
@@ -202,8 +311,11 @@ impl ModelRenderer {
             multiview: None,
             cache: None,
         });
+        if let Some(e) = pollster::block_on(device.pop_error_scope()) {
+            return Err(format!("创建渲染管线失败: {}", e));
+        }
 
-        Self {
+        Ok(Self {
             pipeline,
             uniform_buffer,
             uniform_bind_group,
@@ -213,8 +325,14 @@ impl ModelRenderer {
             depth_texture: None,
             target_size: [0, 0],
             meshes: Vec::new(),
+            pick_geometry: Vec::new(),
             model_type: ModelType::Equipment,
-        }
+            debug_overlay: false,
+            retained: RetainedMeshData::default(),
+            gpu_released: false,
+            snapshot: None,
+            snapshot_visible: false,
+        })
     }
 
     // ---- 纹理上传 ----
@@ -261,6 +379,7 @@ impl ModelRenderer {
         (texture, view)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn create_texture_bind_group(
         &self,
         device: &wgpu::Device,
@@ -268,6 +387,8 @@ impl ModelRenderer {
         normal_view: &wgpu::TextureView,
         mask_view: &wgpu::TextureView,
         emissive_view: &wgpu::TextureView,
+        color_id_view: &wgpu::TextureView,
+        color_table_buffer: &wgpu::Buffer,
     ) -> wgpu::BindGroup {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("texture_bg"),
@@ -293,21 +414,190 @@ impl ModelRenderer {
                     binding: 4,
                     resource: wgpu::BindingResource::TextureView(emissive_view),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(color_id_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: color_table_buffer.as_entire_binding(),
+                },
             ],
         })
     }
 
     // ---- 公开 API ----
 
-    /// 上传网格几何体和纹理到 GPU
+    /// 上传网格几何体和纹理到 GPU，同时保留一份 CPU 端副本供 restore_gpu 使用
     pub fn set_mesh_data(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         mesh_geometry: &[(&[Vertex], &[u16])],
         mesh_textures: &[MeshTextures],
+    ) {
+        self.retained = RetainedMeshData {
+            geometry: mesh_geometry
+                .iter()
+                .map(|(v, i)| (v.to_vec(), i.to_vec()))
+                .collect(),
+            textures: (0..mesh_geometry.len())
+                .map(|i| mesh_textures.get(i).cloned())
+                .collect(),
+        };
+        self.gpu_released = false;
+        self.upload_retained(device, queue);
+    }
+
+    /// set_mesh_data 上传的网格与贴图的 CPU 端副本 (显示对比快照期间为快照的网格)
+    pub fn retained_mesh_data(&self) -> &RetainedMeshData {
+        &self.retained
+    }
+
+    /// 释放网格、纹理与渲染目标占用的 GPU 资源，保留 CPU 端数据 (拾取几何、网格副本)。
+    /// 之后调用 restore_gpu 可不经游戏数据重新上传
+    pub fn release_gpu(&mut self) {
+        if self.gpu_released {
+            return;
+        }
+        self.set_snapshot_visible(false);
+        if let Some(snapshot) = self.snapshot.as_mut() {
+            snapshot.meshes.clear();
+        }
+        self.meshes.clear();
+        self.color_texture = None;
+        self.depth_texture = None;
+        self.target_size = [0, 0];
+        self.gpu_released = !self.retained.geometry.is_empty() || self.snapshot.is_some();
+    }
+
+    pub fn is_gpu_released(&self) -> bool {
+        self.gpu_released
+    }
+
+    /// 从保留的 CPU 端副本重新上传 GPU 资源 (调试顶点颜色需调用方重新设置)
+    pub fn restore_gpu(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if !self.gpu_released {
+            return;
+        }
+        self.gpu_released = false;
+        self.upload_retained(device, queue);
+        if self.snapshot.is_some() {
+            self.swap_snapshot();
+            self.upload_retained(device, queue);
+            self.swap_snapshot();
+        }
+    }
+
+    // ---- 对比快照 ----
+
+    /// 把当前显示的网格保留为对比快照 (沿用已上传的 GPU 资源，包括当前的染色结果)，
+    /// 实时网格从 CPU 端副本重新上传。之后对实时网格的修改不影响快照
+    pub fn capture_snapshot(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.set_snapshot_visible(false);
+        self.restore_gpu(device, queue);
+        self.snapshot = Some(MeshSnapshot {
+            meshes: std::mem::take(&mut self.meshes),
+            pick_geometry: std::mem::take(&mut self.pick_geometry),
+            retained: self.retained.clone(),
+        });
+        self.upload_retained(device, queue);
+    }
+
+    pub fn clear_snapshot(&mut self) {
+        self.set_snapshot_visible(false);
+        self.snapshot = None;
+    }
+
+    pub fn has_snapshot(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    pub fn is_snapshot_visible(&self) -> bool {
+        self.snapshot_visible
+    }
+
+    /// 切换显示快照或实时网格，返回显示内容是否改变 (改变时需重新渲染)。
+    /// 显示快照期间的 set_mesh_data / update_textures 等修改会作用于快照，调用方应推迟到切回之后
+    pub fn set_snapshot_visible(&mut self, visible: bool) -> bool {
+        let visible = visible && self.snapshot.is_some();
+        if visible == self.snapshot_visible {
+            return false;
+        }
+        self.swap_snapshot();
+        self.snapshot_visible = visible;
+        true
+    }
+
+    fn swap_snapshot(&mut self) {
+        if let Some(snapshot) = self.snapshot.as_mut() {
+            std::mem::swap(&mut self.meshes, &mut snapshot.meshes);
+            std::mem::swap(&mut self.pick_geometry, &mut snapshot.pick_geometry);
+            std::mem::swap(&mut self.retained, &mut snapshot.retained);
+        }
+    }
+
+    /// 估算当前占用的 GPU 内存 (字节): 顶点/索引缓冲、网格纹理与渲染目标
+    pub fn gpu_memory_bytes(&self) -> u64 {
+        let [w, h] = self.target_size;
+        // 颜色 RGBA8 + 深度 Depth32Float
+        let mut total = if self.color_texture.is_some() {
+            w as u64 * h as u64 * 8
+        } else {
+            0
+        };
+        if self.gpu_released {
+            return total;
+        }
+        total += Self::mesh_set_bytes(&self.meshes, &self.retained);
+        if let Some(snapshot) = &self.snapshot {
+            total += Self::mesh_set_bytes(&snapshot.meshes, &snapshot.retained);
+        }
+        total
+    }
+
+    fn mesh_set_bytes(meshes: &[GpuMesh], retained: &RetainedMeshData) -> u64 {
+        let tex_bytes =
+            |t: Option<&TextureData>| t.map_or(4, |t| t.width as u64 * t.height as u64 * 4);
+        let mut total = 0;
+        for mesh in meshes {
+            let mt = retained
+                .textures
+                .get(mesh.source_index)
+                .and_then(|t| t.as_ref());
+            total += mesh.vertex_count as u64
+                * (std::mem::size_of::<Vertex>() + std::mem::size_of::<[f32; 4]>()) as u64;
+            total += mesh.index_count as u64 * 2;
+            total += tex_bytes(mt.map(|m| &m.diffuse));
+            total += tex_bytes(mt.and_then(|m| m.normal.as_ref()));
+            total += tex_bytes(mt.and_then(|m| m.mask.as_ref()));
+            total += tex_bytes(mt.and_then(|m| m.emissive.as_ref()));
+            total += tex_bytes(mt.and_then(|m| m.color_table.as_ref().map(|c| &c.id_texture)));
+            total += std::mem::size_of::<ColorTableUniform>() as u64;
+        }
+        total
+    }
+
+    fn upload_retained(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let retained = std::mem::take(&mut self.retained);
+        let geometry: Vec<(&[Vertex], &[u16])> = retained
+            .geometry
+            .iter()
+            .map(|(v, i)| (v.as_slice(), i.as_slice()))
+            .collect();
+        self.upload_meshes(device, queue, &geometry, &retained.textures);
+        self.retained = retained;
+    }
+
+    fn upload_meshes(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mesh_geometry: &[(&[Vertex], &[u16])],
+        mesh_textures: &[Option<MeshTextures>],
     ) {
         self.meshes.clear();
+        self.pick_geometry.clear();
         let white = TextureData {
             rgba: std::sync::Arc::new(vec![255, 255, 255, 255]),
             width: 1,
@@ -318,19 +608,29 @@ impl ModelRenderer {
             if vertices.is_empty() || indices.is_empty() {
                 continue;
             }
+            self.pick_geometry.push((
+                vertices.iter().map(|v| v.position).collect(),
+                indices.to_vec(),
+            ));
             use wgpu::util::DeviceExt;
             let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("vertex_buf"),
                 contents: bytemuck::cast_slice(vertices),
                 usage: wgpu::BufferUsages::VERTEX,
             });
+            let debug_color_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("debug_color_buf"),
+                size: (vertices.len() * std::mem::size_of::<[f32; 4]>()) as u64,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
             let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("index_buf"),
                 contents: bytemuck::cast_slice(indices),
                 usage: wgpu::BufferUsages::INDEX,
             });
 
-            let mt = mesh_textures.get(i);
+            let mt = mesh_textures.get(i).and_then(|t| t.as_ref());
             let diffuse_data = mt.map(|m| &m.diffuse).unwrap_or(&white);
 
             let (_, diffuse_view) = Self::upload_gpu_texture(
@@ -399,16 +699,50 @@ impl ModelRenderer {
                 ),
             };
 
+            let color_table = mt.and_then(|m| m.color_table.as_ref());
+            let (color_id_tex, color_id_view) = match color_table {
+                Some(ct) => Self::upload_gpu_texture(
+                    device,
+                    queue,
+                    &ct.id_texture.rgba,
+                    ct.id_texture.width,
+                    ct.id_texture.height,
+                    wgpu::TextureFormat::Rgba8Unorm,
+                ),
+                None => Self::upload_gpu_texture(
+                    device,
+                    queue,
+                    &DEFAULT_COLOR_ID,
+                    1,
+                    1,
+                    wgpu::TextureFormat::Rgba8Unorm,
+                ),
+            };
+            let color_table_uniform = match color_table {
+                Some(ct) => ColorTableUniform::new(ct.row_count, &ct.rows),
+                None => ColorTableUniform::new(0, &[]),
+            };
+            let color_table_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("color_table_buf"),
+                contents: bytemuck::bytes_of(&color_table_uniform),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
             let texture_bind_group = self.create_texture_bind_group(
                 device,
                 &diffuse_view,
                 &normal_view,
                 &mask_view,
                 &emissive_view,
+                &color_id_view,
+                &color_table_buffer,
             );
 
             self.meshes.push(GpuMesh {
                 vertex_buffer,
+                debug_color_buffer,
+                vertex_count: vertices.len(),
+                source_index: i,
                 index_buffer,
                 index_count: indices.len() as u32,
                 texture_bind_group,
@@ -418,10 +752,30 @@ impl ModelRenderer {
                 mask_view,
                 _emissive_tex: emissive_tex,
                 emissive_view,
+                _color_id_tex: color_id_tex,
+                color_id_view,
+                color_table_buffer,
             });
         }
     }
 
+    /// 上传调试着色用的逐顶点颜色，`colors` 与 set_mesh_data 的输入网格一一对应。
+    /// 顶点数不匹配的网格保持原样
+    pub fn set_debug_colors(&mut self, queue: &wgpu::Queue, colors: &[Vec<[f32; 4]>]) {
+        for mesh in &self.meshes {
+            if let Some(c) = colors.get(mesh.source_index) {
+                if c.len() == mesh.vertex_count {
+                    queue.write_buffer(&mesh.debug_color_buffer, 0, bytemuck::cast_slice(c));
+                }
+            }
+        }
+    }
+
+    /// 开启后 shader 直接输出调试顶点颜色 (仅保留简单的漫反射明暗)
+    pub fn set_debug_overlay(&mut self, enabled: bool) {
+        self.debug_overlay = enabled;
+    }
+
     /// 仅更新指定 mesh 的 diffuse 纹理（染色重烘焙），保留 normal/mask/emissive。
     /// `textures[i] == None` 表示不更新该 mesh。
     pub fn update_textures(
@@ -430,6 +784,32 @@ impl ModelRenderer {
         queue: &wgpu::Queue,
         textures: &[Option<TextureData>],
     ) {
+        // 同步更新 CPU 端副本 (下标与 GPU 网格一致，即跳过空网格后的顺序)
+        let sources = self
+            .retained
+            .geometry
+            .iter()
+            .enumerate()
+            .filter(|(_, (v, i))| !v.is_empty() && !i.is_empty())
+            .map(|(src, _)| src);
+        for (i, src) in sources.enumerate() {
+            let Some(Some(tex)) = textures.get(i) else {
+                continue;
+            };
+            match self.retained.textures.get_mut(src) {
+                Some(Some(mt)) => mt.diffuse = tex.clone(),
+                Some(slot) => {
+                    *slot = Some(MeshTextures {
+                        diffuse: tex.clone(),
+                        normal: None,
+                        mask: None,
+                        emissive: None,
+                        color_table: None,
+                    })
+                }
+                None => {}
+            }
+        }
         for (i, gpu_mesh) in self.meshes.iter_mut().enumerate() {
             if let Some(Some(tex)) = textures.get(i) {
                 let (_, diffuse_view) = Self::upload_gpu_texture(
@@ -467,26 +847,67 @@ impl ModelRenderer {
                                     &gpu_mesh.emissive_view,
                                 ),
                             },
+                            wgpu::BindGroupEntry {
+                                binding: 5,
+                                resource: wgpu::BindingResource::TextureView(
+                                    &gpu_mesh.color_id_view,
+                                ),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 6,
+                                resource: gpu_mesh.color_table_buffer.as_entire_binding(),
+                            },
                         ],
                     });
             }
         }
     }
 
+    /// 仅更新使用 GPU 颜色表着色的 mesh 的颜色表行 (染色切换)，不触碰任何贴图。
+    /// 下标约定与 update_textures 相同；`rows[i] == None` 或该 mesh 未启用颜色表时不更新
+    pub fn update_color_tables(&mut self, queue: &wgpu::Queue, rows: &[Option<Vec<[f32; 3]>>]) {
+        for (i, gpu_mesh) in self.meshes.iter().enumerate() {
+            let Some(Some(new_rows)) = rows.get(i) else {
+                continue;
+            };
+            let Some(Some(mt)) = self.retained.textures.get_mut(gpu_mesh.source_index) else {
+                continue;
+            };
+            let Some(ct) = mt.color_table.as_mut() else {
+                continue;
+            };
+            ct.rows = new_rows.clone();
+            let uniform = ColorTableUniform::new(ct.row_count, &ct.rows);
+            queue.write_buffer(
+                &gpu_mesh.color_table_buffer,
+                0,
+                bytemuck::bytes_of(&uniform),
+            );
+        }
+    }
+
     /// 离屏渲染模型
+    ///
+    /// `render_scale` < 1 时只渲染到目标左上角的缩小区域 (目标本身保持全尺寸，避免反复重建)，
+    /// 返回实际使用区域的 UV 范围，显示时按此范围采样
+    #[allow(clippy::too_many_arguments)]
     pub fn render_offscreen(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         width: u32,
         height: u32,
+        render_scale: f32,
         camera: &Camera,
         scene: &SceneSettings,
-    ) {
+    ) -> [f32; 2] {
         if self.meshes.is_empty() || width == 0 || height == 0 {
-            return;
+            return [1.0, 1.0];
         }
         self.ensure_targets(device, width, height);
+        let scale = render_scale.clamp(0.1, 1.0);
+        let region_w = ((width as f32 * scale).round() as u32).clamp(1, width);
+        let region_h = ((height as f32 * scale).round() as u32).clamp(1, height);
 
         let aspect = width as f32 / height as f32;
         let vp = camera.view_proj(aspect);
@@ -495,10 +916,19 @@ impl ModelRenderer {
         let to_target = normalize(sub(camera.target, eye));
         let light_dir = normalize(SceneSettings::light_dir_from_camera(to_target));
 
-        let model_flags = match self.model_type {
+        let mut model_flags = match self.model_type {
             ModelType::Equipment => 1u32,
             ModelType::Background => 0u32,
         };
+        if self.debug_overlay {
+            model_flags |= 2;
+        }
+        if scene.ignore_vertex_color_mask {
+            model_flags |= 4;
+        }
+        if scene.ignore_ao {
+            model_flags |= 8;
+        }
 
         let uniforms = Uniforms {
             view_proj: vp,
@@ -550,16 +980,22 @@ impl ModelRenderer {
                 ..Default::default()
             });
 
+            pass.set_viewport(0.0, 0.0, region_w as f32, region_h as f32, 0.0, 1.0);
             pass.set_pipeline(&self.pipeline);
             pass.set_bind_group(0, &self.uniform_bind_group, &[]);
             for mesh in &self.meshes {
                 pass.set_bind_group(1, &mesh.texture_bind_group, &[]);
                 pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                pass.set_vertex_buffer(1, mesh.debug_color_buffer.slice(..));
                 pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
                 pass.draw_indexed(0..mesh.index_count, 0, 0..1);
             }
         }
         queue.submit(std::iter::once(encoder.finish()));
+        [
+            region_w as f32 / width as f32,
+            region_h as f32 / height as f32,
+        ]
     }
 
     /// 获取离屏渲染结果的 TextureView
@@ -572,17 +1008,146 @@ impl ModelRenderer {
         self.color_texture.as_ref().map(|(t, _)| t)
     }
 
+    /// 将最近一次离屏渲染结果读回 CPU (阻塞等待 GPU 完成)，返回 RGBA8 像素
+    pub fn read_pixels(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<TextureData> {
+        let texture = self.color_texture_ref()?;
+        let [width, height] = self.target_size;
+        let bytes_per_row = align_to(width * 4, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback_staging"),
+            size: (bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &staging,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        let submission = queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = staging.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        device
+            .poll(wgpu::PollType::Wait {
+                timeout: Some(std::time::Duration::from_secs(10)),
+                submission_index: Some(submission),
+            })
+            .ok()?;
+        rx.recv().ok()?.ok()?;
+
+        let mapped = slice.get_mapped_range();
+        let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+        for row in 0..height {
+            let start = (row * bytes_per_row) as usize;
+            rgba.extend_from_slice(&mapped[start..start + (width * 4) as usize]);
+        }
+        drop(mapped);
+        staging.unmap();
+
+        Some(TextureData {
+            rgba: std::sync::Arc::new(rgba),
+            width,
+            height,
+        })
+    }
+
     /// 设置模型类型，影响 shader 中的光照和材质处理方式
     pub fn set_model_type(&mut self, model_type: ModelType) {
         self.model_type = model_type;
     }
 
     pub fn has_mesh(&self) -> bool {
-        !self.meshes.is_empty()
+        self.mesh_count() > 0
     }
 
+    /// 网格数量 (跳过空网格)，GPU 资源释放期间按保留的 CPU 端数据计算
     pub fn mesh_count(&self) -> usize {
-        self.meshes.len()
+        if self.gpu_released {
+            self.retained
+                .geometry
+                .iter()
+                .filter(|(v, i)| !v.is_empty() && !i.is_empty())
+                .count()
+        } else {
+            self.meshes.len()
+        }
+    }
+
+    /// 射线拾取: 返回与射线最近相交三角形上的命中点
+    pub fn raycast(&self, origin: [f32; 3], dir: [f32; 3]) -> Option<[f32; 3]> {
+        self.raycast_triangle(origin, dir).map(|(_, _, p)| p)
+    }
+
+    /// 射线拾取: 返回命中三角形上离命中点最近的顶点 (输入网格下标, 顶点下标)
+    pub fn raycast_vertex(&self, origin: [f32; 3], dir: [f32; 3]) -> Option<(usize, usize)> {
+        let (mesh_idx, tri, hit) = self.raycast_triangle(origin, dir)?;
+        let positions = &self.pick_geometry[mesh_idx].0;
+        let dist2 = |i: u16| {
+            let d = sub(positions[i as usize], hit);
+            d[0] * d[0] + d[1] * d[1] + d[2] * d[2]
+        };
+        let nearest = tri
+            .into_iter()
+            .min_by(|&a, &b| dist2(a).total_cmp(&dist2(b)))?;
+        Some((self.meshes[mesh_idx].source_index, nearest as usize))
+    }
+
+    /// 返回 (meshes 下标, 三角形顶点索引, 命中点)
+    fn raycast_triangle(
+        &self,
+        origin: [f32; 3],
+        dir: [f32; 3],
+    ) -> Option<(usize, [u16; 3], [f32; 3])> {
+        let mut nearest: Option<(f32, usize, [u16; 3])> = None;
+        for (mesh_idx, (positions, indices)) in self.pick_geometry.iter().enumerate() {
+            for tri in indices.chunks_exact(3) {
+                let (Some(&a), Some(&b), Some(&c)) = (
+                    positions.get(tri[0] as usize),
+                    positions.get(tri[1] as usize),
+                    positions.get(tri[2] as usize),
+                ) else {
+                    continue;
+                };
+                if let Some(t) = ray_triangle(origin, dir, a, b, c) {
+                    if nearest.map_or(true, |(n, _, _)| t < n) {
+                        nearest = Some((t, mesh_idx, [tri[0], tri[1], tri[2]]));
+                    }
+                }
+            }
+        }
+        nearest.map(|(t, mesh_idx, tri)| {
+            (
+                mesh_idx,
+                tri,
+                [
+                    origin[0] + dir[0] * t,
+                    origin[1] + dir[1] * t,
+                    origin[2] + dir[2] * t,
+                ],
+            )
+        })
     }
 
     // ---- 内部 ----
@@ -628,3 +1193,7 @@ impl ModelRenderer {
         self.target_size = [w, h];
     }
 }
+
+fn align_to(value: u32, alignment: u32) -> u32 {
+    (value + alignment - 1) & !(alignment - 1)
+}
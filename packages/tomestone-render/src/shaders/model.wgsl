@@ -9,15 +9,25 @@ struct Uniforms {
     ambient_ground: vec3<f32>,
     fresnel_intensity: f32,
     // bit0: 1=Equipment(顶点颜色遮罩+法线alpha裁剪), 0=Background
+    // bit1: 1=调试着色 (输出 debug_color)
+    // bit2: 1=忽略顶点颜色漫反射遮罩
+    // bit3: 1=忽略遮罩贴图 AO
     model_flags: u32,
 };
 
+// 颜色表着色 (row_count = 0 时直接使用 diffuse 贴图)
+struct ColorTable {
+    rows: array<vec4<f32>, 32>,
+    row_count: u32,
+};
+
 struct VsIn {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) uv: vec2<f32>,
     @location(3) color: vec4<f32>,
     @location(4) tangent: vec4<f32>,
+    @location(5) debug_color: vec4<f32>,
 };
 
 struct VsOut {
@@ -28,6 +38,7 @@ struct VsOut {
     @location(3) world_pos: vec3<f32>,
     @location(4) world_tangent: vec3<f32>,
     @location(5) tangent_w: f32,
+    @location(6) debug_color: vec4<f32>,
 };
 
 // ── Bindings ──
@@ -38,6 +49,23 @@ struct VsOut {
 @group(1) @binding(2) var t_normal: texture_2d<f32>;
 @group(1) @binding(3) var t_mask: texture_2d<f32>;
 @group(1) @binding(4) var t_emissive: texture_2d<f32>;
+@group(1) @binding(5) var t_color_id: texture_2d<f32>;
+@group(1) @binding(6) var<uniform> color_table: ColorTable;
+
+// 按 ID 贴图 R 通道查颜色表行，映射与 CPU 烘焙 (bake_color_table_texture) 一致。
+// 用 textureLoad 取最近的纹素，避免过滤在行边界处混合出错误的行号
+fn color_table_diffuse(uv: vec2<f32>) -> vec3<f32> {
+    let dims = textureDimensions(t_color_id);
+    let texel = min(vec2<u32>(fract(uv) * vec2<f32>(dims)), dims - vec2<u32>(1u));
+    let id = u32(round(textureLoad(t_color_id, vec2<i32>(texel), 0).r * 255.0));
+    var row: u32;
+    if color_table.row_count == 32u {
+        row = min(id * 32u / 256u, 31u);
+    } else {
+        row = min(id / 17u, 15u);
+    }
+    return clamp(color_table.rows[row].rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+}
 
 // ── Vertex ──
 
@@ -51,6 +79,7 @@ struct VsOut {
     out.world_pos = world_pos;
     out.world_tangent = v.tangent.xyz;
     out.tangent_w = v.tangent.w;
+    out.debug_color = v.debug_color;
     return out;
 }
 
@@ -60,7 +89,7 @@ struct VsOut {
     let is_equipment = (u.model_flags & 1u) != 0u;
 
     // 采样纹理
-    let diffuse_sample = textureSample(t_diffuse, s_shared, f.uv);
+    var diffuse_sample = textureSample(t_diffuse, s_shared, f.uv);
     let normal_sample = textureSample(t_normal, s_shared, f.uv);
     let mask_sample = textureSample(t_mask, s_shared, f.uv);
     let emissive_sample = textureSample(t_emissive, s_shared, f.uv);
@@ -70,6 +99,16 @@ struct VsOut {
         discard;
     }
 
+    if color_table.row_count > 0u {
+        diffuse_sample = vec4<f32>(color_table_diffuse(f.uv), 1.0);
+    }
+
+    // 调试着色: 忽略材质，仅保留简单明暗以便看清形体
+    if (u.model_flags & 2u) != 0u {
+        let shade = 0.35 + 0.65 * max(dot(normalize(f.world_normal), normalize(u.light_dir)), 0.0);
+        return vec4<f32>(f.debug_color.rgb * shade, 1.0);
+    }
+
     // ---- 法线贴图 ----
     let N = normalize(f.world_normal);
     let T = normalize(f.world_tangent - N * dot(f.world_tangent, N)); // Gram-Schmidt 正交化
@@ -89,7 +128,7 @@ struct VsOut {
     let mask_spec = mask_sample.r;
     let mask_rough = mask_sample.g;
     var mask_ao: f32;
-    if is_equipment {
+    if is_equipment && (u.model_flags & 8u) == 0u {
         mask_ao = mask_sample.b;
     } else {
         mask_ao = 1.0;
@@ -104,7 +143,11 @@ struct VsOut {
     if is_equipment {
         vc_spec_mask = f.color.r;
         vc_roughness = f.color.g;
-        vc_diffuse_mask = f.color.b;
+        if (u.model_flags & 4u) != 0u {
+            vc_diffuse_mask = 1.0;
+        } else {
+            vc_diffuse_mask = f.color.b;
+        }
     } else {
         vc_spec_mask = 1.0;
         vc_roughness = 1.0;
@@ -35,6 +35,14 @@ impl BoundingBox {
         ]
     }
 
+    /// 整体平移后的包围盒
+    pub fn translated(&self, offset: [f32; 3]) -> BoundingBox {
+        BoundingBox {
+            min: [0, 1, 2].map(|i| self.min[i] + offset[i]),
+            max: [0, 1, 2].map(|i| self.max[i] + offset[i]),
+        }
+    }
+
     pub fn size(&self) -> f32 {
         let dx = self.max[0] - self.min[0];
         let dy = self.max[1] - self.min[1];
@@ -51,6 +59,20 @@ pub struct TextureData {
     pub height: u32,
 }
 
+/// GPU 颜色表着色支持的最大行数 (Dawntrail 颜色表为 32 行)
+pub const MAX_COLOR_TABLE_ROWS: usize = 32;
+
+/// 在 shader 中应用的颜色表: 片元按 ID 贴图 R 通道查表得到漫反射颜色，
+/// 染色时只需更新 `rows`，不再重新烘焙贴图
+#[derive(Clone)]
+pub struct ColorTableTexture {
+    pub id_texture: TextureData,
+    /// 颜色表行数 (16 或 32)，决定 ID 值到行号的映射
+    pub row_count: u32,
+    /// 每行的漫反射颜色 (线性空间)，最多 MAX_COLOR_TABLE_ROWS 行
+    pub rows: Vec<[f32; 3]>,
+}
+
 /// 单个 mesh 的全部纹理数据
 #[derive(Clone)]
 pub struct MeshTextures {
@@ -58,6 +80,8 @@ pub struct MeshTextures {
     pub normal: Option<TextureData>,
     pub mask: Option<TextureData>,
     pub emissive: Option<TextureData>,
+    /// 设置后 shader 用颜色表代替 diffuse 贴图
+    pub color_table: Option<ColorTableTexture>,
 }
 
 /// 场景设置：光照、环境光、背景色等可配置参数
@@ -75,6 +99,10 @@ pub struct SceneSettings {
     pub background_color: [f64; 4],
     /// 菲涅尔边缘光强度 (0.0~1.0)
     pub fresnel_intensity: f32,
+    /// 调试: 忽略装备顶点颜色的漫反射遮罩 (B 通道按 1.0 处理)
+    pub ignore_vertex_color_mask: bool,
+    /// 调试: 忽略遮罩贴图的 AO (按 1.0 处理)
+    pub ignore_ao: bool,
 }
 
 impl Default for SceneSettings {
@@ -86,6 +114,8 @@ impl Default for SceneSettings {
             ambient_ground: [0.35, 0.32, 0.30],
             background_color: [0.12, 0.12, 0.14, 1.0],
             fresnel_intensity: 0.15,
+            ignore_vertex_color_mask: false,
+            ignore_ao: false,
         }
     }
 }
@@ -0,0 +1,126 @@
+//! 无窗口渲染冒烟测试: 渲染几个三角形并确认画面上出现了背景色以外的像素
+//!
+//! 没有可用的 GPU 适配器 (如无显卡的 CI) 时跳过。
+
+use std::sync::Arc;
+
+use tomestone_render::{
+    Camera, HeadlessRenderer, MeshTextures, ModelType, SceneSettings, TextureData, Vertex,
+};
+
+const SIZE: u32 = 64;
+
+fn solid_texture(rgba: [u8; 4]) -> TextureData {
+    TextureData {
+        rgba: Arc::new(rgba.repeat(4 * 4)),
+        width: 4,
+        height: 4,
+    }
+}
+
+fn vertex(position: [f32; 3], normal: [f32; 3]) -> Vertex {
+    Vertex {
+        position,
+        normal,
+        uv: [0.5, 0.5],
+        color: [1.0, 1.0, 1.0, 1.0],
+        tangent: [1.0, 0.0, 0.0, 1.0],
+    }
+}
+
+/// 以 `center` 为中心、分别位于三个坐标平面的三角形，两种绕序都画，
+/// 任意视角下至少有一个不是侧对相机
+fn triangles(center: [f32; 3]) -> (Vec<Vertex>, Vec<u16>) {
+    let r = 0.6;
+    let [x, y, z] = center;
+    let planes = [
+        (
+            [0.0, 0.0, 1.0],
+            [[x - r, y - r, z], [x + r, y - r, z], [x, y + r, z]],
+        ),
+        (
+            [1.0, 0.0, 0.0],
+            [[x, y - r, z - r], [x, y - r, z + r], [x, y + r, z]],
+        ),
+        (
+            [0.0, 1.0, 0.0],
+            [[x - r, y, z - r], [x + r, y, z - r], [x, y, z + r]],
+        ),
+    ];
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (normal, corners) in planes {
+        let start = vertices.len() as u16;
+        vertices.extend(corners.map(|p| vertex(p, normal)));
+        indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 1]);
+    }
+    (vertices, indices)
+}
+
+#[test]
+fn headless_triangle_differs_from_background() {
+    let mut renderer = match HeadlessRenderer::headless() {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("跳过: {}", e);
+            return;
+        }
+    };
+    renderer.set_model_type(ModelType::Background);
+
+    let camera = Camera::default();
+    let (vertices, indices) = triangles(camera.target);
+    let textures = [MeshTextures {
+        diffuse: solid_texture([255, 255, 255, 255]),
+        normal: None,
+        mask: None,
+        emissive: None,
+        color_table: None,
+    }];
+    let scene = SceneSettings {
+        background_color: [0.0, 0.0, 0.0, 1.0],
+        ..Default::default()
+    };
+
+    let image = renderer
+        .render_to_image(
+            &[(&vertices, &indices)],
+            &textures,
+            &camera,
+            &scene,
+            [SIZE, SIZE],
+        )
+        .expect("有适配器时应能读回像素");
+    assert_eq!((image.width, image.height), (SIZE, SIZE));
+    assert_eq!(image.rgba.len(), (SIZE * SIZE * 4) as usize);
+
+    // 角落只有背景，与之不同的像素即为三角形
+    let background = &image.rgba[..4];
+    let differing = image
+        .rgba
+        .chunks_exact(4)
+        .filter(|px| px != &background)
+        .count();
+    assert!(differing > 0, "画面全部是背景色");
+    // 三角形不应覆盖整个画面
+    assert!(differing < (SIZE * SIZE) as usize);
+}
+
+#[test]
+fn headless_without_mesh_returns_none() {
+    let mut renderer = match HeadlessRenderer::headless() {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("跳过: {}", e);
+            return;
+        }
+    };
+    let image = renderer.render_to_image(
+        &[],
+        &[],
+        &Camera::default(),
+        &SceneSettings::default(),
+        [SIZE, SIZE],
+    );
+    assert!(image.is_none());
+}
@@ -0,0 +1,126 @@
+//! 堆分配统计: 包装系统分配器，记录当前占用、峰值与分配次数
+//!
+//! 只在测试或启用 `alloc-stats` 特性时注册为全局分配器，否则各计数始终为 0。
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// 计数分配器是否已注册
+pub const ENABLED: bool = cfg!(any(test, feature = "alloc-stats"));
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // const 初始化且无析构，分配器中访问不会再分配
+    static THREAD_ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+pub struct CountingAlloc;
+
+fn record_grow(size: usize) {
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+}
+
+fn record_allocation() {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    let _ = THREAD_ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record_grow(layout.size());
+            record_allocation();
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_grow(layout.size());
+            record_allocation();
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            if new_size >= layout.size() {
+                record_grow(new_size - layout.size());
+            } else {
+                CURRENT.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+            }
+            record_allocation();
+        }
+        new_ptr
+    }
+}
+
+/// 进程范围的分配统计 (字节)
+#[derive(Debug, Clone, Copy)]
+pub struct AllocStats {
+    pub current: usize,
+    /// 上次 reset_peak 以来的最高占用
+    pub peak: usize,
+    /// 累计分配次数 (含 realloc)
+    pub allocations: u64,
+}
+
+pub fn snapshot() -> AllocStats {
+    AllocStats {
+        current: CURRENT.load(Ordering::Relaxed),
+        peak: PEAK.load(Ordering::Relaxed),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+    }
+}
+
+/// 把峰值重置为当前占用，之后的 snapshot().peak 只反映这之后的最高值
+pub fn reset_peak() {
+    PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// 当前线程的累计分配次数，不受其他线程 (如并行运行的测试) 影响
+pub fn thread_allocations() -> u64 {
+    THREAD_ALLOCATIONS.with(Cell::get)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_allocations_on_this_thread() {
+        let before = thread_allocations();
+        let v: Vec<u8> = Vec::with_capacity(64);
+        let after_one = thread_allocations();
+        drop(v);
+        assert_eq!(after_one - before, 1);
+
+        let start = thread_allocations();
+        let sum: u32 = (0..100u32).sum();
+        assert_eq!(sum, 4950);
+        assert_eq!(thread_allocations(), start);
+    }
+
+    #[test]
+    fn peak_tracks_large_allocation() {
+        reset_peak();
+        let base = snapshot().current;
+        let v = vec![0u8; 8 << 20];
+        // 其他线程并行分配可能抬高峰值，只断言下限
+        assert!(snapshot().peak >= base + v.len());
+        drop(v);
+    }
+}
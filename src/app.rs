@@ -5,16 +5,22 @@ use std::sync::mpsc::Receiver;
 use eframe::egui;
 
 use crate::config;
+use crate::crash;
+use crate::domain::CollectionKind;
 use crate::domain::EquipSlot;
 use crate::domain::ExteriorPartType;
+use crate::domain::HousingSize;
 use crate::domain::HousingSubTab;
 use crate::domain::SourceChoice;
 use crate::domain::ViewMode;
-use crate::game::{CachedMaterial, GameData, MeshData};
+use crate::game::{CachedMaterial, DiagnosticReport, GameData, MaterialDiagnostic, SkeletonCache};
 use crate::glamour;
 use crate::loading::*;
+use crate::nav::{NavEntry, NavHistory};
+use crate::thumbnail::ThumbnailCache;
 use crate::ui::components::equipment_list::EquipmentListState;
-use crate::ui::components::item_list::ItemListState;
+use crate::ui::components::item_list::{self, FilterCache, IconCache, ItemListState};
+use crate::ui::components::toast::Toasts;
 use crate::ui::components::viewport::ViewportState;
 use crate::ui::components::{show_progress_bar, ProgressTracker};
 
@@ -30,69 +36,252 @@ pub enum AppPhase {
     Ready,
 }
 
+/// 幻化编辑器有未保存修改时被暂缓的离开操作，待用户在确认框中选择后执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingLeave {
+    Page(crate::domain::AppPage),
+    Back,
+    Forward,
+    Setup,
+    /// 编辑器自身的 "返回" 按钮
+    CloseEditor,
+    /// 在编辑器中打开另一个组合 (gs.glamour_sets 索引)
+    OpenGlamour(usize),
+    /// 跳转到页面并选中物品 (快速切换)
+    Navigate(NavEntry),
+    /// 游戏更新后重新加载数据
+    ReloadData,
+    CloseWindow,
+}
+
+/// 两次写入配置文件的最小间隔 (秒)
+const CONFIG_SAVE_INTERVAL: f64 = 1.0;
+
 pub struct App {
     pub phase: AppPhase,
     pub config: config::AppConfig,
-    pub render_state: egui_wgpu::RenderState,
-    pub viewport: ViewportState,
+    /// None 表示以无 3D 模式运行 (没有可用的 wgpu 适配器)
+    pub render_state: Option<egui_wgpu::RenderState>,
+    /// 浏览页视口，无 3D 模式或渲染器创建失败时为 None
+    pub viewport: Option<ViewportState>,
     pub game_state: Option<GameState>,
+    /// 设置界面自动检测到的安装目录，首次显示设置界面时扫描 (None = 尚未扫描)
+    pub install_candidates: Option<Vec<crate::install_detect::InstallCandidate>>,
     pub current_page: crate::domain::AppPage,
+    /// 上一帧实际显示的页面，与 current_page 不同时触发 on_page_hidden
+    visible_page: crate::domain::AppPage,
+    /// 启动页设置只在首次加载完成时生效 (重新选择目录后不再跳转)
+    startup_routed: bool,
+    /// 游戏安装目录的更新检测 (演示数据时为 None)
+    version_watcher: Option<GameVersionWatcher>,
+    /// 已检测到游戏更新，显示重新加载提示
+    game_update_detected: bool,
+    /// 用户已关闭部分表加载失败的提示 (重新加载后重置)
+    partial_load_dismissed: bool,
+    /// 关闭图标加载失败提示时的失败数，之后有新的失败才再次提示
+    icon_failures_dismissed: usize,
+    /// 本帧结束后重新加载游戏数据
+    reload_requested: bool,
     pub equipment_list: EquipmentListState,
     pub selected_slot: Option<EquipSlot>,
-    pub selected_item: Option<usize>,
+    /// 装备浏览选中的物品 row_id
+    pub selected_item_id: Option<u32>,
+    /// 套装素材导出选项窗口
+    pub kit_export_dialog: Option<crate::ui::pages::browser::KitExportDialog>,
+    /// 后台运行中的套装素材导出
+    pub kit_export_job: Option<crate::ui::pages::browser::KitExportJob>,
     pub cached_materials: HashMap<u16, CachedMaterial>,
-    pub cached_meshes: Vec<MeshData>,
-    pub loaded_model_idx: Option<usize>,
+    /// 已上传各网格的材质下标 (重新染色只需要这些，网格数据已在 GPU)
+    pub cached_mesh_materials: Vec<u16>,
+    pub texture_diagnostics: Vec<MaterialDiagnostic>,
+    /// 调色板数据面板中选中的材质索引
+    pub color_table_material: Option<u16>,
+    /// 已加载模型的物品 row_id
+    pub loaded_model_id: Option<u32>,
+    /// 装备浏览最近预览过的物品 (切换页面后保留)
+    pub recent_items: crate::ui::pages::browser::RecentItems,
+    /// 等待加载模型的物品: (row_id, 加载时间; egui 时间，秒)，快速切换时只保留最后一次选择
+    pub pending_model_load: Option<(u32, f64)>,
+    /// `--stress-select` 快速切换选中物品的压力测试 (见 SelectionStress)
+    #[cfg(feature = "stress-select")]
+    pub selection_stress: Option<crate::ui::pages::selection_stress::SelectionStress>,
     pub selected_stain_ids: [u32; 2],
+    /// 当前染料来自默认预览染料设置 (用户尚未手动选择)
+    pub stains_from_default: bool,
     pub active_dye_channel: usize,
     pub selected_shade: u8,
     pub is_dual_dye: bool,
+    /// 以伪彩色显示染色通道区域 (代替染色结果)
+    pub show_dye_regions: bool,
+    /// 饰品预览时在后面显示灰色的胸像/耳朵/手作为尺寸参照
+    pub show_accessory_anchor: bool,
+    /// 装备浏览器预览用的骨架 (放置饰品参照网格、种族对比的蒙皮适配)
+    pub skeleton_cache: SkeletonCache,
+    /// 种族对比模式 (None = 关闭)
+    pub race_compare: Option<crate::ui::pages::race_compare::RaceCompareState>,
     pub needs_rebake: bool,
     pub new_glamour_name: String,
     pub renaming_glamour_idx: Option<usize>,
     pub rename_buffer: String,
     pub glamour_editor: Option<glamour::GlamourEditor>,
     pub editing_glamour_idx: Option<usize>,
+    /// 正在查看校验详情的幻化组合 id
+    pub glamour_issue_dialog: Option<String>,
+    /// 拖放导入的结果 (逐文件)
+    pub glamour_import_report: Option<Vec<glamour::import::ImportOutcome>>,
+    /// 幻化管理列表的筛选 (由统计面板设置)
+    pub glamour_filter: Option<glamour::GlamourFilter>,
+    /// 幻化管理列表的搜索词 (匹配名称、说明与槽位备注)
+    pub glamour_search: String,
+    /// 幻化管理中正在编辑说明与备注的组合
+    pub glamour_notes_edit: Option<crate::ui::pages::glamour::GlamourNotesEdit>,
+    /// 保存时发现的重复组合，等待用户选择覆盖/另存/取消
+    pub glamour_duplicate: Option<glamour::GlamourDuplicate>,
+    /// 浏览页快速新建的组合，与已有组合重复时暂存于此等待确认 (None 表示重复的是编辑器内容)
+    pub glamour_quick_add: Option<glamour::GlamourSet>,
+    /// 页面请求在编辑器中打开的组合索引，本帧末尾按未保存修改规则处理
+    pub open_glamour_request: Option<usize>,
+    /// 设置页请求返回安装目录选择界面，本帧末尾按未保存修改规则处理
+    pub setup_request: bool,
+    pub settings_page: crate::ui::pages::settings::SettingsPageState,
+    /// 配置有尚未提交写入的修改
+    config_dirty: bool,
+    /// 下一次允许提交配置写入的时间 (egui 时间，秒)
+    config_next_save: f64,
+    config_writer: config::ConfigWriter,
+    /// 编辑器有未保存修改时，到该时间 (egui 时间，秒) 写入自动保存
+    pub glamour_autosave_due: Option<f64>,
+    /// 上次自动保存内容的哈希，内容未变时跳过写入
+    pub glamour_autosave_hash: Option<u64>,
+    /// 启动时发现的自动保存，等待用户选择恢复或放弃: (组合下标, 恢复内容)
+    pub glamour_recovery_offer: Option<(usize, glamour::GlamourRecovery)>,
+    /// 上次运行崩溃留下的报告，关闭对话框后清除
+    crash_report: Option<crash::CrashReport>,
+    /// 最近一次捕获的界面错误，同一错误连续出现时不重复提示
+    last_caught_panic: Option<String>,
+    /// 幻化组合备份文件夹的后台读写
+    pub glamour_backup: glamour::backup::GlamourBackup,
+    /// 备份文件夹中较新的组合，等待用户选择导入: (候选, 是否选中)
+    pub glamour_backup_offer: Option<Vec<(glamour::backup::BackupCandidate, bool)>>,
+    /// 设置页请求与备份文件夹同步，本帧末尾执行
+    pub glamour_backup_sync_requested: bool,
+    /// 设置页修改了染料分组覆盖，本帧末尾重新分配染料分组
+    pub shade_overrides_changed: bool,
+    /// 保存时存在空装备槽位，等待用户确认
+    pub glamour_empty_slot_warning: Option<crate::ui::pages::glamour::EmptySlotWarning>,
     pub test_progress: ProgressTracker,
     pub test_total: u64,
     pub test_current: u64,
-    pub icon_cache: HashMap<u32, Option<egui::TextureHandle>>,
+    pub icon_cache: IconCache,
+    /// 装备网格的模型缩略图
+    pub thumbnails: ThumbnailCache,
     // 房屋浏览器状态
-    pub housing_viewport: ViewportState,
+    pub housing_viewport: Option<ViewportState>,
     pub housing_sub_tab: HousingSubTab,
     pub housing_selected_part_type: Option<ExteriorPartType>,
     pub housing_selected_ui_category: Option<u8>,
-    pub housing_selected_item: Option<usize>,
-    pub housing_loaded_model_idx: Option<usize>,
+    pub housing_selected_size: Option<HousingSize>,
+    pub housing_selected_item_id: Option<u32>,
+    pub housing_loaded_model_id: Option<u32>,
     pub housing_list: ItemListState,
+    pub housing_filter_cache: FilterCache<crate::ui::pages::housing::HousingFilterKey, Vec<usize>>,
     pub housing_cached_materials: HashMap<u16, CachedMaterial>,
-    pub housing_cached_meshes: Vec<MeshData>,
+    pub housing_cached_mesh_materials: Vec<u16>,
     pub housing_stain_ids: [u32; 2],
     pub housing_active_dye_channel: usize,
     pub housing_selected_shade: u8,
     pub housing_is_dual_dye: bool,
     pub housing_needs_rebake: bool,
+    // 收藏品浏览器状态
+    pub collection_kind: CollectionKind,
+    pub collection_selected_item_id: Option<u32>,
+    pub collection_list: ItemListState,
     // 合成检索状态
     pub crafting_list: ItemListState,
+    /// 各职业可制作物品的搜索结果缓存 (搜索词, 物品列表标识) -> 按职业分组的条目
+    pub crafting_filter_cache: FilterCache<(String, (usize, usize)), Vec<Vec<(usize, usize)>>>,
     pub crafting_selected_craft_type: Option<u8>,
-    pub crafting_selected_item: Option<usize>,
+    pub crafting_selected_item_id: Option<u32>,
     pub crafting_selected_node_item: Option<u32>,
     pub crafting_selected_node_amount: u32,
+    /// 合成树展开深度上限
+    pub crafting_tree_max_depth: usize,
     /// 用户对素材来源的手动选择 (item_id -> SourceChoice)
     pub crafting_source_overrides: HashMap<u32, SourceChoice>,
+    /// 部分持有的素材数量 (item_id -> 已持有数)，汇总时从需求量中扣除
+    pub crafting_owned_amounts: HashMap<u32, u32>,
+    /// 合成树中勾选为 HQ 的叶子素材: (父节点物品 ID, 素材物品 ID)，只影响清单标注
+    pub crafting_hq_ingredients: std::collections::HashSet<(u32, u32)>,
+    /// 本帧素材汇总中需要 HQ 的数量 (item_id -> 数量)，由 crafting_hq_ingredients 汇总
+    pub crafting_hq_amounts: HashMap<u32, u32>,
+    /// 背包清单导入对话框
+    pub crafting_inventory_import: crate::ui::pages::crafting::InventoryImport,
+    /// 素材汇总按来源分组显示
+    pub crafting_group_by_source: bool,
+    /// 素材汇总的多选状态
+    pub crafting_material_selection: crate::ui::pages::crafting::MaterialSelection,
+    /// 制作数量，即合成树根节点的需求量 (素材汇总、成本与导出都按该数量计算)
+    pub crafting_quantity: u32,
+    /// 英文物品名 (Teamcraft 导出时按需加载)
+    pub crafting_english_names: Option<HashMap<u32, String>>,
+    /// 当前显示的合成树 ((根物品 row_id, 制作数量, 展开深度), 树)，深层节点在展开时才构建
+    pub crafting_tree: Option<((u32, u32, usize), crate::domain::CraftTreeNode)>,
+    /// 默认来源制作成本缓存 ((根物品 row_id, 制作数量, 展开深度) -> 该数量的成本)
+    pub crafting_cost_cache: HashMap<(u32, u32, usize), crate::domain::CraftCostEstimate>,
+    /// 后台计算中的制作成本，完成后写入 crafting_cost_cache
+    pub crafting_cost_job: Option<crate::ui::pages::crafting::CraftCostJob>,
+    /// 下一帧需要强制展开的职业分组 (导航跳转时使用)
+    pub crafting_reveal_craft_type: Option<u8>,
+    /// 下一次绘制列表时滚动到选中的物品 (导航跳转或点击定位时使用)
+    pub crafting_scroll_to_selected: bool,
     // 工具箱: 自动制作
     pub auto_craft: crate::ui::pages::toolbox::AutoCraftUi,
     // 工具箱: 模板编辑器
     pub template_editor: crate::ui::components::template_editor::TemplateEditorState,
+    // 自检报告
+    pub diagnostics_open: bool,
+    pub diagnostics_report: Option<DiagnosticReport>,
+    /// 后台自检线程，完成后结果写入 diagnostics_report
+    pub diagnostics_rx: Option<Receiver<DiagnosticReport>>,
+    // 全局提示
+    pub toasts: Toasts,
+    // 跨页面导航历史
+    pub nav_history: NavHistory,
+    /// 等待未保存修改确认的离开操作
+    pub pending_leave: Option<PendingLeave>,
+    /// Ctrl+P 快速切换，打开时为 Some
+    pub quick_switcher: Option<crate::ui::pages::quick_switcher::QuickSwitcherState>,
+    /// 用户已确认关闭窗口，不再拦截关闭请求
+    pub close_confirmed: bool,
 }
 
 impl App {
-    pub fn new(render_state: egui_wgpu::RenderState) -> Self {
+    /// `render_state` 为 None 时以无 3D 模式运行: 各视口显示占位，缩略图回退到图标
+    pub fn new(render_state: Option<egui_wgpu::RenderState>) -> Self {
+        crash::install_panic_hook();
         let config = config::load_config();
-        let viewport = ViewportState::new(render_state.clone());
-        let housing_viewport = ViewportState::new(render_state.clone());
+        crate::i18n::set_lang(config.language);
+        let viewport = ViewportState::create(render_state.as_ref());
+        let mut housing_viewport = ViewportState::create(render_state.as_ref());
+        // 部分房屋模型原点远离几何体，默认平移到包围盒中心
+        if let Some(vp) = &mut housing_viewport {
+            vp.recenter_origin = Some(true);
+        }
+        let thumbnails = ThumbnailCache::new(render_state.clone());
+        let mut equipment_list = EquipmentListState::new();
+        equipment_list.kit_export_enabled = true;
 
-        let phase = if let Some(dir) = &config.game_install_dir {
+        // --mock: 使用内置演示数据启动，不需要游戏安装
+        let phase = if std::env::args().any(|arg| arg == "--mock") {
+            let (tx, rx) = std::sync::mpsc::channel();
+            std::thread::spawn(move || {
+                load_mock_data_thread(tx);
+            });
+            AppPhase::Loading {
+                status: "正在初始化...".to_string(),
+                receiver: rx,
+            }
+        } else if let Some(dir) = &config.game_install_dir {
             let (tx, rx) = std::sync::mpsc::channel();
             let dir = dir.clone();
             std::thread::spawn(move || {
@@ -115,49 +304,124 @@ impl App {
             render_state,
             viewport,
             game_state: None,
+            install_candidates: None,
             current_page: crate::domain::AppPage::Browser,
-            equipment_list: EquipmentListState::new(),
+            visible_page: crate::domain::AppPage::Browser,
+            startup_routed: false,
+            version_watcher: None,
+            game_update_detected: false,
+            partial_load_dismissed: false,
+            icon_failures_dismissed: 0,
+            reload_requested: false,
+            equipment_list,
             selected_slot: None,
-            selected_item: None,
-            loaded_model_idx: None,
+            selected_item_id: None,
+            kit_export_dialog: None,
+            kit_export_job: None,
+            loaded_model_id: None,
+            recent_items: Default::default(),
+            pending_model_load: None,
+            #[cfg(feature = "stress-select")]
+            selection_stress: std::env::args()
+                .any(|arg| arg == "--stress-select")
+                .then(crate::ui::pages::selection_stress::SelectionStress::default),
             cached_materials: HashMap::new(),
-            cached_meshes: Vec::new(),
+            cached_mesh_materials: Vec::new(),
+            texture_diagnostics: Vec::new(),
+            color_table_material: None,
             selected_stain_ids: [0, 0],
+            stains_from_default: false,
             active_dye_channel: 0,
             selected_shade: 2,
             is_dual_dye: false,
+            show_dye_regions: false,
+            show_accessory_anchor: true,
+            skeleton_cache: SkeletonCache::new(),
+            race_compare: None,
             needs_rebake: false,
             new_glamour_name: String::new(),
             renaming_glamour_idx: None,
             rename_buffer: String::new(),
             glamour_editor: None,
             editing_glamour_idx: None,
+            glamour_issue_dialog: None,
+            glamour_import_report: None,
+            glamour_filter: None,
+            glamour_search: String::new(),
+            glamour_notes_edit: None,
+            glamour_duplicate: None,
+            glamour_quick_add: None,
+            open_glamour_request: None,
+            setup_request: false,
+            settings_page: Default::default(),
+            config_dirty: false,
+            config_next_save: 0.0,
+            config_writer: config::ConfigWriter::new(),
+            glamour_autosave_due: None,
+            glamour_autosave_hash: None,
+            glamour_recovery_offer: None,
+            crash_report: crash::take_pending_report(),
+            last_caught_panic: None,
+            glamour_backup: glamour::backup::GlamourBackup::new(),
+            glamour_backup_offer: None,
+            glamour_backup_sync_requested: false,
+            shade_overrides_changed: false,
+            glamour_empty_slot_warning: None,
             test_progress: ProgressTracker::new(),
             test_total: 100,
             test_current: 0,
-            icon_cache: HashMap::new(),
+            icon_cache: IconCache::default(),
+            thumbnails,
             housing_viewport,
             housing_sub_tab: HousingSubTab::Exterior,
             housing_selected_part_type: None,
             housing_selected_ui_category: None,
-            housing_selected_item: None,
-            housing_loaded_model_idx: None,
+            housing_selected_size: None,
+            housing_selected_item_id: None,
+            housing_loaded_model_id: None,
             housing_list: ItemListState::new(ViewMode::Grid),
+            housing_filter_cache: FilterCache::default(),
             housing_cached_materials: HashMap::new(),
-            housing_cached_meshes: Vec::new(),
+            housing_cached_mesh_materials: Vec::new(),
             housing_stain_ids: [0, 0],
             housing_active_dye_channel: 0,
             housing_selected_shade: 2,
             housing_is_dual_dye: false,
             housing_needs_rebake: false,
+            collection_kind: CollectionKind::Orchestrion,
+            collection_selected_item_id: None,
+            collection_list: ItemListState::new(ViewMode::Grid),
             crafting_list: ItemListState::new(ViewMode::List),
+            crafting_filter_cache: FilterCache::default(),
             crafting_selected_craft_type: None,
-            crafting_selected_item: None,
+            crafting_selected_item_id: None,
             crafting_selected_node_item: None,
             crafting_selected_node_amount: 0,
+            crafting_tree_max_depth: crate::domain::DEFAULT_CRAFT_TREE_DEPTH,
             crafting_source_overrides: HashMap::new(),
+            crafting_owned_amounts: HashMap::new(),
+            crafting_hq_ingredients: Default::default(),
+            crafting_hq_amounts: HashMap::new(),
+            crafting_inventory_import: Default::default(),
+            crafting_group_by_source: false,
+            crafting_material_selection: Default::default(),
+            crafting_quantity: 1,
+            crafting_english_names: None,
+            crafting_tree: None,
+            crafting_cost_cache: HashMap::new(),
+            crafting_cost_job: None,
+            crafting_reveal_craft_type: None,
+            crafting_scroll_to_selected: false,
             auto_craft: Default::default(),
             template_editor: Default::default(),
+            diagnostics_open: false,
+            diagnostics_report: None,
+            diagnostics_rx: None,
+            toasts: Toasts::default(),
+            nav_history: NavHistory::default(),
+            pending_leave: None,
+            close_confirmed: false,
+            quick_switcher: None,
         }
     }
 
@@ -167,51 +431,61 @@ impl App {
         gs: &GameData,
         icon_id: u32,
     ) -> Option<egui::TextureHandle> {
-        if icon_id == 0 {
-            return None;
-        }
-
-        if let Some(cached) = self.icon_cache.get(&icon_id) {
-            return cached.clone();
-        }
-
-        let result = gs.load_icon(icon_id).map(|tex_data| {
-            let size = [tex_data.width as _, tex_data.height as _];
-            let pixels: Vec<egui::Color32> = tex_data
-                .rgba
-                .chunks_exact(4)
-                .map(|p| egui::Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                .collect();
-
-            let color_image = egui::ColorImage {
-                size,
-                pixels,
-                source_size: egui::Vec2::new(40.0, 40.0),
-            };
-            ctx.load_texture(
-                format!("icon_{}", icon_id),
-                color_image,
-                egui::TextureOptions::default(),
-            )
-        });
-
-        self.icon_cache.insert(icon_id, result.clone());
-        result
+        item_list::get_or_load_icon(&mut self.icon_cache, ctx, gs, icon_id)
     }
 
     pub fn start_loading(&mut self, install_dir: PathBuf) {
-        self.game_state = None;
-        self.loaded_model_idx = None;
-        self.viewport.free_texture();
-        self.housing_loaded_model_idx = None;
-        self.housing_viewport.free_texture();
         let (tx, rx) = std::sync::mpsc::channel();
         std::thread::spawn(move || {
             load_game_data_thread(install_dir, tx);
         });
+        self.begin_loading(rx);
+    }
+
+    /// 演示模式: 加载内置合成数据
+    pub fn start_mock_loading(&mut self) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            load_mock_data_thread(tx);
+        });
+        self.begin_loading(rx);
+    }
+
+    fn begin_loading(&mut self, receiver: Receiver<LoadProgress>) {
+        self.game_state = None;
+        self.loaded_model_id = None;
+        self.pending_model_load = None;
+        if let Some(vp) = &mut self.viewport {
+            vp.free_texture();
+        }
+        if let Some(mut compare) = self.race_compare.take() {
+            compare.viewport.release_gpu();
+        }
+        self.housing_loaded_model_id = None;
+        if let Some(vp) = &mut self.housing_viewport {
+            vp.free_texture();
+        }
+        self.thumbnails = ThumbnailCache::new(self.render_state.clone());
+        self.glamour_issue_dialog = None;
+        self.glamour_import_report = None;
+        self.glamour_filter = None;
+        self.glamour_notes_edit = None;
+        self.glamour_duplicate = None;
+        self.glamour_quick_add = None;
+        self.glamour_empty_slot_warning = None;
+        self.glamour_editor = None;
+        self.editing_glamour_idx = None;
+        self.pending_leave = None;
+        self.nav_history.clear();
+        self.crafting_tree = None;
+        self.crafting_cost_cache.clear();
+        self.crafting_cost_job = None;
+        self.crafting_english_names = None;
+        self.crafting_reveal_craft_type = None;
+        self.crafting_scroll_to_selected = false;
         self.phase = AppPhase::Loading {
             status: "正在初始化...".to_string(),
-            receiver: rx,
+            receiver,
         };
     }
 
@@ -248,9 +522,32 @@ impl App {
         }
 
         match transition {
-            Some(Ok(data)) => {
-                self.game_state = Some(GameState::from_loaded_data(*data));
+            Some(Ok(mut data)) => {
+                if let Some(report) = data.initial_diagnostics.take() {
+                    self.diagnostics_report = Some(report);
+                    self.diagnostics_open = true;
+                }
+                let mut gs = GameState::from_loaded_data(*data);
+                crate::domain::apply_shade_overrides(
+                    &mut gs.stains,
+                    &self.config.stain_shade_overrides,
+                );
+                gs.game
+                    .set_checkerboard_missing_textures(self.config.checkerboard_missing_textures);
+                self.version_watcher = self
+                    .config
+                    .game_install_dir
+                    .as_deref()
+                    .filter(|_| !gs.game.is_mock())
+                    .map(GameVersionWatcher::spawn);
+                self.game_update_detected = false;
+                self.partial_load_dismissed = false;
+                self.game_state = Some(gs);
                 self.phase = AppPhase::Ready;
+                if !self.startup_routed {
+                    self.startup_routed = true;
+                    self.route_startup_page();
+                }
             }
             Some(Err(e)) => {
                 self.phase = AppPhase::Setup {
@@ -269,60 +566,319 @@ impl App {
         ctx.request_repaint();
     }
 
+    /// 游戏更新后重新加载: 先释放当前页面的 GPU 资源，begin_loading 丢弃 GameState
+    /// (同时断开后台染色探测)，选中状态以 row_id 保存，加载完成后仍然有效
+    fn reload_game_data(&mut self) {
+        let Some(install_dir) = self.config.game_install_dir.clone() else {
+            return;
+        };
+        self.on_page_hidden(self.visible_page);
+        if let Some(gs) = &mut self.game_state {
+            gs.dye_probe_rx = None;
+        }
+        self.version_watcher = None;
+        self.game_update_detected = false;
+        self.start_loading(install_dir);
+    }
+
+    /// 按设置跳转到启动页；需要在 GameState 就绪后调用 (编辑器依赖物品表)
+    fn route_startup_page(&mut self) {
+        self.current_page = self.config.startup_page;
+        crash::set_game_version(self.game_state.as_ref().map(|gs| gs.game.game_version()));
+        if let Some(gs) = &self.game_state {
+            self.glamour_recovery_offer = glamour::pending_recovery(&gs.glamour_sets);
+            self.glamour_backup
+                .set_dir(self.config.glamour_backup_dir.as_deref());
+            self.glamour_backup.sync(&gs.glamour_sets);
+        }
+        if !self.config.reopen_last_glamour {
+            return;
+        }
+        let Some(gs) = &self.game_state else {
+            return;
+        };
+        let Some(idx) = glamour::most_recently_edited(&gs.glamour_sets) else {
+            self.current_page = crate::domain::AppPage::GlamourManager;
+            self.toasts.push("没有找到最近编辑的幻化组合");
+            return;
+        };
+        let set = &gs.glamour_sets[idx];
+        if gs.glamour_validation.get(&set.id).is_some_and(|v| !v.is_ok()) {
+            self.current_page = crate::domain::AppPage::GlamourManager;
+            self.toasts.push(format!(
+                "最近编辑的幻化组合「{}」存在缺失物品或染料，未自动打开",
+                set.name
+            ));
+            return;
+        }
+        self.glamour_editor = Some(glamour::GlamourEditor::new(
+            set.clone(),
+            self.render_state.clone(),
+        ));
+        self.editing_glamour_idx = Some(idx);
+        self.current_page = crate::domain::AppPage::GlamourManager;
+    }
+
     pub fn show_ready_ui(&mut self, ctx: &egui::Context, gs: &mut GameState) {
-        let mut goto_setup = false;
+        // 后台染色探测仍在进行时持续刷新，以便筛选结果及时更新
+        if gs.poll_dye_probe() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+        // 派生索引同理，界面语言切换后也在这里触发重建
+        if gs.derived.poll() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+        let adaptive_resolution = !self.config.disable_adaptive_resolution;
+        let viewport_input = self.config.viewport_input;
+        for vp in [&mut self.viewport, &mut self.housing_viewport]
+            .into_iter()
+            .flatten()
+        {
+            vp.adaptive_resolution = adaptive_resolution;
+            vp.input = viewport_input;
+        }
+        if let Some(compare) = &mut self.race_compare {
+            compare.viewport.adaptive_resolution = adaptive_resolution;
+            compare.viewport.input = viewport_input;
+        }
+        self.glamour_backup
+            .set_dir(self.config.glamour_backup_dir.as_deref());
+        if let Some(editor) = &mut self.glamour_editor {
+            editor.set_adaptive_resolution(adaptive_resolution);
+            editor.set_viewport_input(viewport_input);
+        }
+        // 关闭窗口: 编辑器有未保存修改时先取消关闭，等待用户确认
+        if ctx.input(|i| i.viewport().close_requested())
+            && !self.close_confirmed
+            && self.leave_needs_confirm(PendingLeave::CloseWindow)
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.pending_leave = Some(PendingLeave::CloseWindow);
+        }
+        if self
+            .version_watcher
+            .as_ref()
+            .is_some_and(|w| w.poll_changed())
+        {
+            self.game_update_detected = true;
+        }
+        let mut reload_data = false;
+        if self.game_update_detected {
+            egui::TopBottomPanel::top("game_update_banner").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "{} 检测到游戏更新，需要重新加载数据",
+                            egui_phosphor::regular::WARNING
+                        ),
+                    );
+                    if ui.button("重新加载").clicked() {
+                        reload_data = true;
+                    }
+                    if ui.small_button("稍后").clicked() {
+                        self.game_update_detected = false;
+                    }
+                });
+            });
+        }
+        // 部分页损坏 (如补丁未完成) 时其余数据仍可用，提示哪些表不完整
+        if !gs.partial_loads.is_empty() && !self.partial_load_dismissed {
+            egui::TopBottomPanel::top("partial_load_banner").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "{} 部分数据页读取失败，相关列表可能不完整",
+                            egui_phosphor::regular::WARNING
+                        ),
+                    );
+                    if ui.small_button("关闭").clicked() {
+                        self.partial_load_dismissed = true;
+                    }
+                });
+                for summary in &gs.partial_loads {
+                    ui.label(egui::RichText::new(summary).small());
+                }
+            });
+        }
+        // 图标失败只汇总为一条提示，逐个原因放在悬停提示中
+        let icon_failures = self.icon_cache.failure_count();
+        // 重试成功后失败数减少，同步下调以便之后的新失败能再次提示
+        self.icon_failures_dismissed = self.icon_failures_dismissed.min(icon_failures);
+        if icon_failures > self.icon_failures_dismissed {
+            egui::TopBottomPanel::top("icon_failure_banner").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "{} {} 个图标加载失败",
+                            egui_phosphor::regular::IMAGE_BROKEN,
+                            icon_failures
+                        ),
+                    )
+                    .on_hover_ui(|ui| {
+                        for (icon_id, reason) in self.icon_cache.failures().take(20) {
+                            ui.label(format!("{}: {}", icon_id, reason));
+                        }
+                        if icon_failures > 20 {
+                            ui.weak(format!("…另有 {} 个", icon_failures - 20));
+                        }
+                        ui.weak("在列表中右键图标可重试加载");
+                    });
+                    if ui.small_button("关闭").clicked() {
+                        self.icon_failures_dismissed = icon_failures;
+                    }
+                });
+            });
+        }
+        let mut page = self.current_page;
+        // 鼠标侧键: 后退 / 前进
+        let (mut go_back, mut go_forward) = ctx.input(|i| {
+            (
+                i.pointer.button_pressed(egui::PointerButton::Extra1),
+                i.pointer.button_pressed(egui::PointerButton::Extra2),
+            )
+        });
         egui::TopBottomPanel::top("top_tab_bar").show(ctx, |ui| {
             ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        self.nav_history.can_go_back(),
+                        egui::Button::new(egui_phosphor::regular::ARROW_LEFT).small(),
+                    )
+                    .on_hover_text(tr!("nav.back"))
+                    .clicked()
+                {
+                    go_back = true;
+                }
+                if ui
+                    .add_enabled(
+                        self.nav_history.can_go_forward(),
+                        egui::Button::new(egui_phosphor::regular::ARROW_RIGHT).small(),
+                    )
+                    .on_hover_text(tr!("nav.forward"))
+                    .clicked()
+                {
+                    go_forward = true;
+                }
+                ui.separator();
                 ui.selectable_value(
-                    &mut self.current_page,
+                    &mut page,
                     crate::domain::AppPage::Browser,
-                    "装备浏览器",
+                    tr!("page.browser"),
                 );
                 ui.selectable_value(
-                    &mut self.current_page,
+                    &mut page,
                     crate::domain::AppPage::GlamourManager,
-                    "幻化管理",
+                    tr!("page.glamour"),
                 );
                 ui.selectable_value(
-                    &mut self.current_page,
+                    &mut page,
                     crate::domain::AppPage::HousingBrowser,
-                    "房屋",
+                    tr!("page.housing"),
                 );
                 ui.selectable_value(
-                    &mut self.current_page,
+                    &mut page,
                     crate::domain::AppPage::CraftingBrowser,
-                    "合成检索",
+                    tr!("page.crafting"),
+                );
+                ui.selectable_value(
+                    &mut page,
+                    crate::domain::AppPage::CollectionBrowser,
+                    tr!("page.collection"),
                 );
                 ui.selectable_value(
-                    &mut self.current_page,
+                    &mut page,
                     crate::domain::AppPage::Toolbox,
-                    "工具箱",
+                    tr!("page.toolbox"),
                 );
                 ui.selectable_value(
-                    &mut self.current_page,
+                    &mut page,
                     crate::domain::AppPage::ResourceBrowser,
-                    "EXD 浏览器",
+                    tr!("page.resource"),
                 );
-                ui.selectable_value(&mut self.current_page, crate::domain::AppPage::Test, "测试");
+                ui.selectable_value(&mut page, crate::domain::AppPage::Test, tr!("page.test"));
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if ui.button("设置").clicked() {
-                        goto_setup = true;
+                    ui.selectable_value(
+                        &mut page,
+                        crate::domain::AppPage::Settings,
+                        tr!("button.settings"),
+                    );
+                    ui.label(
+                        egui::RichText::new(crate::gpu::adapter_summary(
+                            self.render_state.as_ref(),
+                        ))
+                        .small()
+                        .weak(),
+                    )
+                    .on_hover_text(tr!("setup.graphics_backend"));
+                    ui.label(
+                        egui::RichText::new(egui_phosphor::regular::DATABASE)
+                            .small()
+                            .weak(),
+                    )
+                    .on_hover_ui(|ui| {
+                        ui.label(format!("游戏版本: {}", gs.game.game_version()));
+                        ui.label(format!("数据仓库: {}", gs.game.repositories().join(", ")));
+                        let missing = gs.game.missing_expansions();
+                        if !missing.is_empty() {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                format!("未检测到: {}", missing.join(", ")),
+                            );
+                        }
+                    });
+                    if let Some((done, total, stage)) = gs.derived.progress() {
+                        ui.label(
+                            egui::RichText::new(format!("正在建立索引 {}/{}", done, total))
+                                .small()
+                                .weak(),
+                        )
+                        .on_hover_text(format!(
+                            "正在构建: {}\n完成前搜索仍可使用，速度可能较慢",
+                            stage.label()
+                        ));
+                        ui.spinner();
                     }
                 });
             });
         });
 
-        if goto_setup {
-            self.phase = AppPhase::Setup {
-                dir_input: self
-                    .config
-                    .game_install_dir
-                    .as_ref()
-                    .map(|p| p.display().to_string())
-                    .unwrap_or_default(),
-                error: None,
-            };
-            return;
+        let switched = self.show_quick_switcher(ctx, gs);
+        let leave = if reload_data {
+            Some(PendingLeave::ReloadData)
+        } else if switched.is_some() {
+            switched
+        } else if go_back {
+            Some(PendingLeave::Back)
+        } else if go_forward {
+            Some(PendingLeave::Forward)
+        } else if page != self.current_page {
+            Some(PendingLeave::Page(page))
+        } else {
+            None
+        };
+        if let Some(leave) = leave {
+            if self.leave_needs_confirm(leave) {
+                self.pending_leave = Some(leave);
+            } else {
+                self.perform_leave(leave, ctx, gs);
+                if !matches!(self.phase, AppPhase::Ready) {
+                    return;
+                }
+            }
+        }
+
+        // 任意页面拖放文件均可导入幻化组合
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        if !dropped.is_empty() {
+            self.import_dropped_glamour_files(&dropped, gs);
+        }
+
+        if self.current_page != self.visible_page {
+            self.on_page_hidden(self.visible_page);
+            self.visible_page = self.current_page;
         }
 
         match self.current_page {
@@ -330,10 +886,415 @@ impl App {
             crate::domain::AppPage::GlamourManager => self.show_glamour_manager_page(ctx, gs),
             crate::domain::AppPage::HousingBrowser => self.show_housing_page(ctx, gs),
             crate::domain::AppPage::CraftingBrowser => self.show_crafting_page(ctx, gs),
+            crate::domain::AppPage::CollectionBrowser => self.show_collection_page(ctx, gs),
             crate::domain::AppPage::Toolbox => self.show_toolbox_page(ctx),
             crate::domain::AppPage::ResourceBrowser => gs.resource_browser.show(ctx, &gs.game),
+            crate::domain::AppPage::Settings => self.show_settings_page(ctx, gs),
             crate::domain::AppPage::Test => self.show_test_page(ctx),
         }
+
+        if std::mem::take(&mut self.setup_request) {
+            let leave = PendingLeave::Setup;
+            if self.leave_needs_confirm(leave) {
+                self.pending_leave = Some(leave);
+            } else {
+                self.perform_leave(leave, ctx, gs);
+                if !matches!(self.phase, AppPhase::Ready) {
+                    return;
+                }
+            }
+        }
+
+        if std::mem::take(&mut self.shade_overrides_changed) {
+            gs.reapply_shade_overrides(&self.config.stain_shade_overrides);
+        }
+
+        if std::mem::take(&mut self.glamour_backup_sync_requested) {
+            if self.glamour_backup.is_enabled() {
+                self.glamour_backup.sync(&gs.glamour_sets);
+            } else {
+                self.toasts.push("尚未设置幻化组合备份文件夹");
+            }
+        }
+
+        if let Some(idx) = self.open_glamour_request.take() {
+            let leave = PendingLeave::OpenGlamour(idx);
+            if self.leave_needs_confirm(leave) {
+                self.pending_leave = Some(leave);
+            } else {
+                self.perform_leave(leave, ctx, gs);
+            }
+        }
+
+        // 页面或选中物品变化时记录导航历史
+        let entry = self.current_nav_entry();
+        self.nav_history.record(entry);
+
+        if self.diagnostics_open {
+            self.show_diagnostics_window(ctx, gs);
+        }
+
+        if self.pending_leave.is_some() {
+            self.show_unsaved_changes_dialog(ctx, gs);
+        }
+
+        self.autosave_glamour_editor(ctx);
+        // 崩溃报告对话框中同样可以恢复，关闭后再单独询问
+        if self.glamour_recovery_offer.is_some() && self.crash_report.is_none() {
+            self.show_glamour_recovery_dialog(ctx, gs);
+        }
+
+        self.poll_glamour_backup(ctx);
+        if self.glamour_backup_offer.is_some() && self.glamour_recovery_offer.is_none() {
+            self.show_glamour_backup_dialog(ctx, gs);
+        }
+
+        if self.glamour_import_report.is_some() {
+            self.show_glamour_import_dialog(ctx);
+        }
+
+        self.toasts.show(ctx);
+    }
+
+    /// 离开页面时释放该页面的 GPU 资源 (模型网格/纹理、视口纹理)，CPU 端缓存保留，
+    /// 返回页面时视口在下一帧据此重新上传
+    fn on_page_hidden(&mut self, page: crate::domain::AppPage) {
+        match page {
+            crate::domain::AppPage::Browser => {
+                if let Some(vp) = &mut self.viewport {
+                    vp.release_gpu();
+                }
+                if let Some(compare) = &mut self.race_compare {
+                    compare.viewport.release_gpu();
+                }
+            }
+            crate::domain::AppPage::HousingBrowser => {
+                if let Some(vp) = &mut self.housing_viewport {
+                    vp.release_gpu();
+                }
+            }
+            crate::domain::AppPage::GlamourManager => {
+                if let Some(editor) = &mut self.glamour_editor {
+                    editor.on_page_hidden();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // ── 崩溃报告 ──
+
+    /// 界面更新中捕获的错误: 提示并记录，出错的模型已加入跳过列表
+    fn report_caught_panic(&mut self, caught: crash::CaughtPanic) {
+        if self.last_caught_panic.as_deref() == Some(caught.message.as_str()) {
+            return;
+        }
+        let message = match caught.item_id {
+            Some(id) => format!("加载物品 {} 时出错，已跳过该物品: {}", id, caught.message),
+            None => format!("界面发生错误: {}", caught.message),
+        };
+        self.toasts.push(message);
+        self.last_caught_panic = Some(caught.message);
+    }
+
+    /// 上次运行崩溃: 显示报告位置，可直接恢复崩溃前未保存的幻化编辑
+    fn show_crash_report_dialog(&mut self, ctx: &egui::Context) {
+        let Some(report) = &self.crash_report else {
+            return;
+        };
+        let mut restore = false;
+        let mut close = false;
+        egui::Modal::new(egui::Id::new("crash_report")).show(ctx, |ui| {
+            ui.heading(tr!("crash.title"));
+            ui.label(tr!("crash.message"));
+            ui.label(egui::RichText::new(&report.message).monospace().small());
+            ui.label(format!(
+                "{}: {}",
+                tr!("crash.report_path"),
+                report.path.display()
+            ));
+            if report.recovery_saved && self.glamour_recovery_offer.is_none() {
+                ui.weak(tr!("crash.restore_pending"));
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button(tr!("crash.open_folder")).clicked() {
+                    if let Some(dir) = report.path.parent() {
+                        let _ = open::that(dir);
+                    }
+                }
+                if self.glamour_recovery_offer.is_some()
+                    && ui.button(tr!("crash.restore")).clicked()
+                {
+                    restore = true;
+                }
+                if ui.button(tr!("crash.close")).clicked() {
+                    close = true;
+                }
+            });
+        });
+        if restore {
+            self.restore_glamour_recovery();
+            self.crash_report = None;
+        } else if close {
+            self.crash_report = None;
+        }
+    }
+
+    // ── 配置保存 ──
+
+    /// 配置已修改: 标记为待保存，由后台线程写入，每 CONFIG_SAVE_INTERVAL 秒最多写一次，
+    /// 避免拖动/连续点击时频繁写盘
+    pub fn schedule_config_save(&mut self, ctx: &egui::Context) {
+        self.config_dirty = true;
+        // 崩溃时写入最新的配置，不必等到写入间隔
+        if let Ok(json) = config::serialize_config(&self.config) {
+            crash::set_config_snapshot(json);
+        }
+        let wait = self.config_next_save - ctx.input(|i| i.time);
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64(wait.max(0.0)));
+    }
+
+    /// 立即写入配置 (须马上落盘的场合，如安装目录、保存幻化组合)
+    pub fn flush_config(&mut self) {
+        self.config_dirty = false;
+        match config::serialize_config(&self.config) {
+            Ok(json) => {
+                self.config_writer.flush(&json);
+                crash::set_config_snapshot(json);
+            }
+            Err(e) => self.toasts.push(format!("保存配置失败: {}", e)),
+        }
+    }
+
+    /// 间隔已到 (或窗口即将关闭) 时把待保存的配置交给后台线程，并报告写入错误
+    fn submit_config_save(&mut self, ctx: &egui::Context) {
+        if let Some(e) = self.config_writer.take_error() {
+            self.toasts.push(format!("保存配置失败: {}", e));
+        }
+        if !self.config_dirty {
+            return;
+        }
+        let (now, closing) = ctx.input(|i| (i.time, i.viewport().close_requested()));
+        if now < self.config_next_save && !closing {
+            return;
+        }
+        self.config_dirty = false;
+        self.config_next_save = now + CONFIG_SAVE_INTERVAL;
+        match config::serialize_config(&self.config) {
+            Ok(json) => {
+                crash::set_config_snapshot(json.clone());
+                self.config_writer.submit(json);
+            }
+            Err(e) => self.toasts.push(format!("保存配置失败: {}", e)),
+        }
+    }
+
+    // ── 未保存修改确认 ──
+
+    /// 幻化编辑器有未保存修改时，离开操作需要先确认
+    fn leave_needs_confirm(&self, leave: PendingLeave) -> bool {
+        let dirty = self.glamour_editor.as_ref().is_some_and(|e| e.dirty);
+        match leave {
+            // 编辑器保留在 App 中，仅离开幻化页面时需要确认
+            PendingLeave::Page(_)
+            | PendingLeave::Back
+            | PendingLeave::Forward
+            | PendingLeave::Navigate(_) => {
+                dirty && self.current_page == crate::domain::AppPage::GlamourManager
+            }
+            PendingLeave::Setup
+            | PendingLeave::CloseEditor
+            | PendingLeave::CloseWindow
+            | PendingLeave::ReloadData => dirty,
+            PendingLeave::OpenGlamour(idx) => dirty && self.editing_glamour_idx != Some(idx),
+        }
+    }
+
+    /// 执行 (已确认或无需确认的) 离开操作
+    fn perform_leave(&mut self, leave: PendingLeave, ctx: &egui::Context, gs: &GameState) {
+        match leave {
+            PendingLeave::Page(page) => self.current_page = page,
+            PendingLeave::Back => {
+                if let Some(entry) = self.nav_history.back() {
+                    self.restore_navigation(entry, gs);
+                }
+            }
+            PendingLeave::Forward => {
+                if let Some(entry) = self.nav_history.forward() {
+                    self.restore_navigation(entry, gs);
+                }
+            }
+            PendingLeave::Navigate(entry) => self.restore_navigation(entry, gs),
+            PendingLeave::Setup => {
+                self.phase = AppPhase::Setup {
+                    dir_input: self
+                        .config
+                        .game_install_dir
+                        .as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default(),
+                    error: None,
+                };
+            }
+            PendingLeave::CloseEditor => {
+                self.glamour_editor = None;
+                self.editing_glamour_idx = None;
+            }
+            PendingLeave::OpenGlamour(idx) => {
+                self.current_page = crate::domain::AppPage::GlamourManager;
+                if self.editing_glamour_idx != Some(idx) {
+                    if let Some(set) = gs.glamour_sets.get(idx) {
+                        self.glamour_editor =
+                            Some(glamour::GlamourEditor::new(set.clone(), self.render_state.clone()));
+                        self.editing_glamour_idx = Some(idx);
+                    }
+                }
+            }
+            PendingLeave::ReloadData => {
+                // GameState 此时被 update 临时取出，在其放回后再开始加载
+                self.reload_requested = true;
+            }
+            PendingLeave::CloseWindow => {
+                self.close_confirmed = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
+    }
+
+    /// 未保存修改确认框: 保存并离开 / 放弃更改 / 取消
+    fn show_unsaved_changes_dialog(&mut self, ctx: &egui::Context, gs: &mut GameState) {
+        let Some(leave) = self.pending_leave else {
+            return;
+        };
+        let set_name = self
+            .glamour_editor
+            .as_ref()
+            .map(|e| e.glamour_set.name.clone())
+            .unwrap_or_default();
+
+        // Some(true): 保存并离开, Some(false): 放弃更改
+        let mut choice: Option<bool> = None;
+        let mut cancel = false;
+        let modal = egui::Modal::new(egui::Id::new("unsaved_glamour_changes")).show(ctx, |ui| {
+            ui.heading(tr!("unsaved.title"));
+            ui.label(tr!("unsaved.message"));
+            ui.label(egui::RichText::new(&set_name).strong());
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button(tr!("unsaved.save")).clicked() {
+                    choice = Some(true);
+                }
+                if ui.button(tr!("unsaved.discard")).clicked() {
+                    choice = Some(false);
+                }
+                if ui.button(tr!("button.cancel")).clicked() {
+                    cancel = true;
+                }
+            });
+        });
+        if cancel || (choice.is_none() && modal.should_close()) {
+            self.pending_leave = None;
+            return;
+        }
+
+        match choice {
+            Some(true) => {
+                self.pending_leave = None;
+                // 与编辑器保存按钮走同一路径 (写回、落盘、重新校验)
+                match self.save_glamour_editor(gs) {
+                    Ok(()) => self.perform_leave(leave, ctx, gs),
+                    Err(e) => {
+                        eprintln!("保存失败: {}", e);
+                        self.toasts.push(format!("保存失败: {}", e));
+                    }
+                }
+            }
+            Some(false) => {
+                self.pending_leave = None;
+                self.glamour_editor = None;
+                self.editing_glamour_idx = None;
+                self.discard_glamour_autosave();
+                self.perform_leave(leave, ctx, gs);
+            }
+            None => {}
+        }
+    }
+
+    // ── 导航历史 ──
+
+    /// 当前页面及其选中物品
+    fn current_nav_entry(&self) -> NavEntry {
+        let row_id = match self.current_page {
+            crate::domain::AppPage::Browser => self.selected_item_id,
+            crate::domain::AppPage::HousingBrowser => self.housing_selected_item_id,
+            crate::domain::AppPage::CraftingBrowser => self.crafting_selected_item_id,
+            crate::domain::AppPage::CollectionBrowser => self.collection_selected_item_id,
+            _ => None,
+        };
+        NavEntry {
+            page: self.current_page,
+            row_id,
+        }
+    }
+
+    /// 跳转到历史记录: 切换页面并按 row_id 恢复选中，必要时调整筛选/展开分组使其可见
+    fn restore_navigation(&mut self, entry: NavEntry, gs: &GameState) {
+        self.current_page = entry.page;
+        let idx = entry
+            .row_id
+            .and_then(|row_id| gs.item_id_map.get(&row_id).copied());
+        let row_id = idx.and(entry.row_id);
+        match entry.page {
+            crate::domain::AppPage::Browser => {
+                self.selected_item_id = row_id;
+                if let Some(item) = idx.and_then(|i| gs.all_items.get(i)) {
+                    if self.selected_slot.is_some() && self.selected_slot != item.equip_slot() {
+                        self.selected_slot = item.equip_slot();
+                    }
+                    self.equipment_list.reveal(item.row_id);
+                }
+            }
+            crate::domain::AppPage::HousingBrowser => {
+                if let Some(i) = idx {
+                    let sub_tab = if gs.housing_ext_indices.contains(&i) {
+                        Some(HousingSubTab::Exterior)
+                    } else if gs.housing_yard_indices.contains(&i) {
+                        Some(HousingSubTab::Yard)
+                    } else if gs.housing_indoor_indices.contains(&i) {
+                        Some(HousingSubTab::Indoor)
+                    } else {
+                        None
+                    };
+                    if let Some(tab) = sub_tab {
+                        if tab != self.housing_sub_tab {
+                            self.housing_sub_tab = tab;
+                            self.housing_selected_part_type = None;
+                            self.housing_selected_ui_category = None;
+                            self.housing_selected_size = None;
+                        }
+                    }
+                }
+                self.housing_selected_item_id = row_id;
+            }
+            crate::domain::AppPage::CraftingBrowser => {
+                if self.crafting_selected_item_id != row_id {
+                    self.crafting_selected_item_id = row_id;
+                    self.crafting_selected_node_item = None;
+                    self.crafting_source_overrides.clear();
+                    self.crafting_owned_amounts.clear();
+                    self.crafting_hq_ingredients.clear();
+                }
+                self.reveal_crafting_selection(gs);
+            }
+            crate::domain::AppPage::CollectionBrowser => {
+                if let Some(kind) = idx.and_then(|i| gs.all_items.get(i)?.collection_kind()) {
+                    self.collection_kind = kind;
+                }
+                self.collection_selected_item_id = row_id;
+            }
+            _ => {}
+        }
     }
 
     fn show_test_page(&mut self, ctx: &egui::Context) {
@@ -429,13 +1390,23 @@ impl App {
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.submit_config_save(ctx);
         if matches!(self.phase, AppPhase::Setup { .. }) {
             self.show_setup_ui(ctx);
         } else if matches!(self.phase, AppPhase::Loading { .. }) {
             self.show_loading_ui(ctx);
         } else if let Some(mut gs) = self.game_state.take() {
-            self.show_ready_ui(ctx, &mut gs);
+            crash::set_page(self.current_page);
+            // 单帧内的错误 (如损坏的模型数据) 只提示，不结束程序
+            match crash::guard(|| self.show_ready_ui(ctx, &mut gs)) {
+                Ok(()) => self.last_caught_panic = None,
+                Err(caught) => self.report_caught_panic(caught),
+            }
             self.game_state = Some(gs);
+            if self.reload_requested {
+                self.reload_requested = false;
+                self.reload_game_data();
+            }
         } else {
             self.phase = AppPhase::Setup {
                 dir_input: self
@@ -448,5 +1419,8 @@ impl eframe::App for App {
             };
             self.show_setup_ui(ctx);
         }
+        if self.crash_report.is_some() {
+            self.show_crash_report_dialog(ctx);
+        }
     }
 }
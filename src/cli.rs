@@ -0,0 +1,598 @@
+//! 命令行子命令: 不创建窗口，直接读取游戏数据并导出
+//!
+//! 没有子命令时返回 None，由 main 照常启动图形界面。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde_json::json;
+
+use crate::domain::{
+    build_craft_tree, build_craft_tree_lazy, CraftTreeNode, DEFAULT_CRAFT_TREE_DEPTH, RACE_CODES,
+};
+use crate::game::{compute_bounding_box, load_mdl_with_fallback, load_mesh_textures, GameData};
+use crate::gltf::{ExportPrimitive, GlbBuilder};
+use crate::ui::components::item_list::FilterCache;
+
+pub const USAGE: &str = "\
+用法:
+  tomestone [--backend <后端>] [--mock]          启动图形界面
+  tomestone dump items [--out items.json]        导出全部物品
+  tomestone dump recipes [--out recipes.json]    导出全部配方
+  tomestone export-model <e0123|a0123> <槽位> [--race c0201] [--out model.glb]
+  tomestone render-model <e0123|a0123> <槽位> [--race c0201] [--size 512] [--out model.png]
+  tomestone icon <图标 ID> [--out icon.png]
+  tomestone mem-profile                          统计读取 Item/Recipe 表的堆内存峰值
+                                                 (需以 --features alloc-stats 编译)
+  tomestone craft-profile [物品 ID]              统计选中配方时构建合成树的耗时
+                                                 (默认取合成树最大的配方)
+  tomestone search-profile                       统计全表搜索与筛选缓存的耗时
+
+通用参数:
+  --game-dir <目录>    游戏安装目录 (默认读取配置中的目录)
+
+图形界面调试参数:
+  --stress-select      进入浏览页后快速连续切换装备，输出模型加载次数与视口显存估算
+                       (需以 --features stress-select 编译)";
+
+pub enum Command {
+    DumpItems { out: PathBuf },
+    DumpRecipes { out: PathBuf },
+    ExportModel {
+        /// 'e' 装备 / 'a' 饰品
+        kind: char,
+        set_id: u16,
+        /// 槽位缩写 (top/dwn/glv/sho/met/ear/nek/wrs/rir/ril)
+        slot: String,
+        race: Option<String>,
+        out: PathBuf,
+    },
+    /// 不创建窗口渲染模型预览图
+    RenderModel {
+        kind: char,
+        set_id: u16,
+        slot: String,
+        race: Option<String>,
+        /// 正方形图像边长 (像素)
+        size: u32,
+        out: PathBuf,
+    },
+    Icon { icon_id: u32, out: PathBuf },
+    /// 对比逐页解析与整表读取的堆内存峰值
+    MemProfile,
+    /// 对比完整构建与延迟构建合成树的耗时，None 表示取合成树最大的配方
+    CraftProfile { item_id: Option<u32> },
+    /// 对比逐项转小写与预计算小写名的全表搜索耗时，以及筛选缓存的命中耗时
+    SearchProfile,
+}
+
+const SUBCOMMANDS: [&str; 7] = [
+    "dump",
+    "export-model",
+    "render-model",
+    "icon",
+    "mem-profile",
+    "craft-profile",
+    "search-profile",
+];
+
+/// 预览图默认边长
+const DEFAULT_RENDER_SIZE: u32 = 512;
+
+/// 解析 e0123 / a0123 形式的模型编号
+fn parse_model_id(model: &str) -> Result<(char, u16), String> {
+    let invalid = || format!("模型编号应为 e0123 或 a0123 形式: {}", model);
+    let mut chars = model.chars();
+    let kind = chars.next().map(|c| c.to_ascii_lowercase());
+    let Some(kind) = kind.filter(|k| matches!(k, 'e' | 'a')) else {
+        return Err(invalid());
+    };
+    let set_id: u16 = chars.as_str().parse().map_err(|_| invalid())?;
+    Ok((kind, set_id))
+}
+
+pub struct CliArgs {
+    pub game_dir: Option<PathBuf>,
+    pub command: Command,
+}
+
+/// 解析命令行 (不含程序名)；第一个非选项参数不是子命令时返回 Ok(None)
+pub fn parse_args(args: &[String]) -> Result<Option<CliArgs>, String> {
+    let mut positional: Vec<&str> = Vec::new();
+    let mut unknown_flags: Vec<&str> = Vec::new();
+    let mut game_dir = None;
+    let mut out = None;
+    let mut race = None;
+    let mut size = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} 缺少参数值", name))
+        };
+        match arg.as_str() {
+            "--game-dir" => game_dir = Some(PathBuf::from(value("--game-dir")?)),
+            "--out" | "-o" => out = Some(PathBuf::from(value("--out")?)),
+            "--race" => race = Some(value("--race")?),
+            "--size" => {
+                let text = value("--size")?;
+                let parsed = text.parse::<u32>().ok().filter(|s| (1..=4096).contains(s));
+                size = Some(parsed.ok_or_else(|| format!("无效的图像尺寸: {}", text))?);
+            }
+            // 图形界面参数，子命令模式下忽略
+            "--backend" => {
+                value("--backend")?;
+            }
+            other if other.starts_with('-') => unknown_flags.push(other),
+            other => positional.push(other),
+        }
+    }
+
+    let is_subcommand = positional.first().is_some_and(|p| SUBCOMMANDS.contains(p));
+    if !is_subcommand {
+        return Ok(None);
+    }
+    if let Some(unknown) = unknown_flags.first() {
+        return Err(format!("未知参数: {}", unknown));
+    }
+
+    let command = match positional.as_slice() {
+        ["dump", "items"] => Command::DumpItems {
+            out: out.unwrap_or_else(|| PathBuf::from("items.json")),
+        },
+        ["dump", "recipes"] => Command::DumpRecipes {
+            out: out.unwrap_or_else(|| PathBuf::from("recipes.json")),
+        },
+        ["dump", ..] => return Err("dump 需要指定 items 或 recipes".to_string()),
+        ["export-model", model, slot] => {
+            let (kind, set_id) = parse_model_id(model)?;
+            Command::ExportModel {
+                kind,
+                set_id,
+                slot: slot.to_ascii_lowercase(),
+                out: out.unwrap_or_else(|| PathBuf::from(format!("{}_{}.glb", model, slot))),
+                race,
+            }
+        }
+        ["export-model", ..] => return Err("export-model 需要模型编号与槽位".to_string()),
+        ["render-model", model, slot] => {
+            let (kind, set_id) = parse_model_id(model)?;
+            Command::RenderModel {
+                kind,
+                set_id,
+                slot: slot.to_ascii_lowercase(),
+                size: size.unwrap_or(DEFAULT_RENDER_SIZE),
+                out: out.unwrap_or_else(|| PathBuf::from(format!("{}_{}.png", model, slot))),
+                race,
+            }
+        }
+        ["render-model", ..] => return Err("render-model 需要模型编号与槽位".to_string()),
+        ["icon", id] => {
+            let icon_id: u32 = id.parse().map_err(|_| format!("无效的图标 ID: {}", id))?;
+            Command::Icon {
+                icon_id,
+                out: out.unwrap_or_else(|| PathBuf::from(format!("{}.png", icon_id))),
+            }
+        }
+        ["mem-profile"] => Command::MemProfile,
+        ["craft-profile"] => Command::CraftProfile { item_id: None },
+        ["craft-profile", id] => Command::CraftProfile {
+            item_id: Some(id.parse().map_err(|_| format!("无效的物品 ID: {}", id))?),
+        },
+        ["search-profile"] => Command::SearchProfile,
+        _ => return Err("参数数量不正确".to_string()),
+    };
+    Ok(Some(CliArgs { game_dir, command }))
+}
+
+/// 执行子命令，进度输出到 stdout，错误由调用方输出到 stderr
+pub fn run(args: CliArgs) -> Result<(), String> {
+    let install_dir = args
+        .game_dir
+        .or_else(|| crate::config::load_config().game_install_dir)
+        .ok_or("未指定游戏安装目录，请使用 --game-dir 或先在图形界面中设置")?;
+    if !install_dir.join("game").is_dir() {
+        return Err(format!(
+            "{} 不是有效的游戏安装目录 (缺少 game 文件夹)",
+            install_dir.display()
+        ));
+    }
+    let game = GameData::new(&install_dir);
+
+    match args.command {
+        Command::DumpItems { out } => dump_items(&game, &out),
+        Command::DumpRecipes { out } => dump_recipes(&game, &out),
+        Command::ExportModel {
+            kind,
+            set_id,
+            slot,
+            race,
+            out,
+        } => export_model(&game, kind, set_id, &slot, race.as_deref(), &out),
+        Command::RenderModel {
+            kind,
+            set_id,
+            slot,
+            race,
+            size,
+            out,
+        } => render_model(&game, kind, set_id, &slot, race.as_deref(), size, &out),
+        Command::Icon { icon_id, out } => export_icon(&game, icon_id, &out),
+        Command::MemProfile => mem_profile(&game),
+        Command::CraftProfile { item_id } => craft_profile(&game, item_id),
+        Command::SearchProfile => search_profile(&game),
+    }
+}
+
+fn write_json(out: &Path, value: &serde_json::Value) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    std::fs::write(out, text).map_err(|e| format!("写入 {} 失败: {}", out.display(), e))
+}
+
+fn dump_items(game: &GameData, out: &Path) -> Result<(), String> {
+    println!("读取 Item 表...");
+    let items = game.load_all_items(&game.load_ui_category_names());
+    if items.is_empty() {
+        return Err("没有读取到任何物品".to_string());
+    }
+    let rows: Vec<serde_json::Value> = items
+        .iter()
+        .map(|item| {
+            json!({
+                "row_id": item.row_id,
+                "name": item.name,
+                "icon_id": item.icon_id,
+                "item_ui_category": item.item_ui_category,
+                "equip_slot_category": item.equip_slot_category,
+                "model_main": item.model_main,
+                "price_mid": item.price_mid,
+                "price_low": item.price_low,
+                "stack_size": item.stack_size,
+                "description": &*item.description,
+            })
+        })
+        .collect();
+    write_json(out, &serde_json::Value::Array(rows))?;
+    println!("已导出 {} 件物品到 {}", items.len(), out.display());
+    Ok(())
+}
+
+fn dump_recipes(game: &GameData, out: &Path) -> Result<(), String> {
+    println!("读取 Recipe 表...");
+    let recipes = game.load_recipes();
+    if recipes.is_empty() {
+        return Err("没有读取到任何配方".to_string());
+    }
+    let rows: Vec<serde_json::Value> = recipes
+        .iter()
+        .map(|recipe| {
+            json!({
+                "row_id": recipe.row_id,
+                "result_item_id": recipe.result_item_id,
+                "result_amount": recipe.result_amount,
+                "craft_type": recipe.craft_type,
+                "recipe_level_table_id": recipe.recipe_level_table_id,
+                "ingredients": recipe
+                    .ingredients
+                    .iter()
+                    .map(|&(item_id, amount)| json!({ "item_id": item_id, "amount": amount }))
+                    .collect::<Vec<_>>(),
+                "secret_recipe_book": recipe.secret_recipe_book,
+            })
+        })
+        .collect();
+    write_json(out, &serde_json::Value::Array(rows))?;
+    println!("已导出 {} 条配方到 {}", recipes.len(), out.display());
+    Ok(())
+}
+
+/// 模型路径: 指定种族时只用该种族，否则按种族回退顺序排列
+fn model_paths(kind: char, set_id: u16, slot: &str, race: Option<&str>) -> Vec<String> {
+    let races: Vec<&str> = match race {
+        Some(race) => vec![race],
+        None => RACE_CODES.to_vec(),
+    };
+    races
+        .iter()
+        .map(|race| match kind {
+            'a' => format!(
+                "chara/accessory/a{:04}/model/{}a{:04}_{}.mdl",
+                set_id, race, set_id, slot
+            ),
+            _ => format!(
+                "chara/equipment/e{:04}/model/{}e{:04}_{}.mdl",
+                set_id, race, set_id, slot
+            ),
+        })
+        .collect()
+}
+
+fn export_model(
+    game: &GameData,
+    kind: char,
+    set_id: u16,
+    slot: &str,
+    race: Option<&str>,
+    out: &Path,
+) -> Result<(), String> {
+    let paths = model_paths(kind, set_id, slot, race);
+    println!("加载模型 {}{:04} {}...", kind, set_id, slot);
+    let result = load_mdl_with_fallback(game, &paths)?;
+    let textures = load_mesh_textures(game, &result.material_names, &result.meshes, set_id, 1);
+    let missing = textures.missing_count();
+    if missing > 0 {
+        println!("{} 个材质的贴图缺失", missing);
+    }
+
+    let primitives: Vec<ExportPrimitive<'_>> = result
+        .meshes
+        .iter()
+        .enumerate()
+        .map(|(i, mesh)| {
+            let tex = textures.mesh_textures.get(i);
+            ExportPrimitive {
+                vertices: &mesh.vertices,
+                indices: &mesh.indices,
+                diffuse: tex.map(|t| &t.diffuse),
+                normal: tex.and_then(|t| t.normal.as_ref()),
+                mask: tex.and_then(|t| t.mask.as_ref()),
+            }
+        })
+        .collect();
+    let name = format!("{}{:04}_{}", kind, set_id, slot);
+    let mut builder = GlbBuilder::new();
+    builder.add_node(&name, &primitives)?;
+    let glb = builder.finish(&name, json!({ "set_id": set_id, "slot": slot }))?;
+    std::fs::write(out, glb).map_err(|e| format!("写入 {} 失败: {}", out.display(), e))?;
+    println!("已导出 {} 个网格到 {}", result.meshes.len(), out.display());
+    Ok(())
+}
+
+fn render_model(
+    game: &GameData,
+    kind: char,
+    set_id: u16,
+    slot: &str,
+    race: Option<&str>,
+    size: u32,
+    out: &Path,
+) -> Result<(), String> {
+    let paths = model_paths(kind, set_id, slot, race);
+    println!("加载模型 {}{:04} {}...", kind, set_id, slot);
+    let result = load_mdl_with_fallback(game, &paths)?;
+    let textures = load_mesh_textures(game, &result.material_names, &result.meshes, set_id, 1);
+    let geometry: Vec<(&[tomestone_render::Vertex], &[u16])> = result
+        .meshes
+        .iter()
+        .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
+        .collect();
+
+    let mut renderer = tomestone_render::HeadlessRenderer::headless()?;
+    renderer.set_model_type(tomestone_render::ModelType::Equipment);
+    let mut camera = tomestone_render::Camera::default();
+    camera.focus_on(&compute_bounding_box(&result.meshes));
+    let pixels = renderer
+        .render_to_image(
+            &geometry,
+            &textures.mesh_textures,
+            &camera,
+            &tomestone_render::SceneSettings::default(),
+            [size, size],
+        )
+        .ok_or("渲染失败: 模型没有可绘制的网格或读回像素失败")?;
+    image::save_buffer(out, &pixels.rgba, size, size, image::ColorType::Rgba8)
+        .map_err(|e| format!("写入 {} 失败: {}", out.display(), e))?;
+    println!("已渲染 {}x{} 预览图到 {}", size, size, out.display());
+    Ok(())
+}
+
+fn export_icon(game: &GameData, icon_id: u32, out: &Path) -> Result<(), String> {
+    let icon = game
+        .load_icon(icon_id)
+        .ok_or_else(|| format!("找不到图标 {}", icon_id))?;
+    image::save_buffer(out, &icon.rgba, icon.width, icon.height, image::ColorType::Rgba8)
+        .map_err(|e| format!("写入 {} 失败: {}", out.display(), e))?;
+    println!(
+        "已导出图标 {} ({}x{}) 到 {}",
+        icon_id,
+        icon.width,
+        icon.height,
+        out.display()
+    );
+    Ok(())
+}
+
+/// 运行 `run` 期间相对开始时的堆内存峰值增量 (字节) 与其返回值
+fn measure_peak<T>(run: impl FnOnce() -> T) -> (usize, T) {
+    let base = crate::alloc_stats::snapshot().current;
+    crate::alloc_stats::reset_peak();
+    let value = run();
+    let peak = crate::alloc_stats::snapshot().peak;
+    (peak.saturating_sub(base), value)
+}
+
+/// 加载器逐页解析 (读完一页即释放) 与把整张表的原始行全部读入内存的峰值对比；
+/// 后者是改为逐页读取之前加载器峰值的下限 (当时还要再加上解析结果)
+fn mem_profile(game: &GameData) -> Result<(), String> {
+    if !crate::alloc_stats::ENABLED {
+        return Err("需要以 --features alloc-stats 编译才能统计内存".to_string());
+    }
+    let mib = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+
+    let ui_category_names = game.load_ui_category_names();
+    let (items_peak, items) = measure_peak(|| game.load_all_items(&ui_category_names));
+    let (item_rows_peak, item_rows) =
+        measure_peak(|| game.read_sheet_rows("Item", physis::Language::ChineseSimplified));
+    let item_rows = item_rows?.len();
+    let (recipes_peak, recipes) = measure_peak(|| game.load_recipes());
+    let (recipe_rows_peak, recipe_rows) =
+        measure_peak(|| game.read_sheet_rows("Recipe", physis::Language::None));
+    let recipe_rows = recipe_rows?.len();
+
+    println!("{:<24} {:>8} {:>12}", "", "行数", "峰值增量");
+    for (label, rows, peak) in [
+        ("Item 逐页解析", items.len(), items_peak),
+        ("Item 整表原始行", item_rows, item_rows_peak),
+        ("Recipe 逐页解析", recipes.len(), recipes_peak),
+        ("Recipe 整表原始行", recipe_rows, recipe_rows_peak),
+    ] {
+        println!("{:<24} {:>8} {:>9.1} MiB", label, rows, mib(peak));
+    }
+    Ok(())
+}
+
+/// 选中配方时构建合成树的耗时: 完整构建 (改为延迟构建之前每次选中都要做，现在只在成本估算缓存未命中时做一次)
+/// 与只构建默认展开部分的延迟构建，各取多次运行的平均值
+fn craft_profile(game: &GameData, item_id: Option<u32>) -> Result<(), String> {
+    const RUNS: u32 = 20;
+    println!("读取 Recipe 表...");
+    let recipes = game.load_recipes();
+    let mut item_to_recipes: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (idx, recipe) in recipes.iter().enumerate() {
+        item_to_recipes
+            .entry(recipe.result_item_id)
+            .or_default()
+            .push(idx);
+    }
+    let depth = DEFAULT_CRAFT_TREE_DEPTH;
+    let full = |id| build_craft_tree(id, 1, &recipes, &item_to_recipes, depth);
+    let lazy = |id| build_craft_tree_lazy(id, 1, &recipes, &item_to_recipes, depth);
+
+    let item_id = match item_id {
+        Some(id) if item_to_recipes.contains_key(&id) => id,
+        Some(id) => return Err(format!("物品 {} 没有配方", id)),
+        None => item_to_recipes
+            .keys()
+            .copied()
+            .max_by_key(|&id| (full(id).node_count(), std::cmp::Reverse(id)))
+            .ok_or("没有读取到任何配方")?,
+    };
+
+    let time = |build: &dyn Fn(u32) -> CraftTreeNode| {
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            std::hint::black_box(build(item_id));
+        }
+        (build(item_id).node_count(), start.elapsed() / RUNS)
+    };
+    let (full_nodes, full_time) = time(&full);
+    let (lazy_nodes, lazy_time) = time(&lazy);
+    println!("物品 {} 的合成树 (深度上限 {}):", item_id, depth);
+    println!("  完整构建: {:>6} 个节点, {:?}", full_nodes, full_time);
+    println!("  延迟构建: {:>6} 个节点, {:?}", lazy_nodes, lazy_time);
+    Ok(())
+}
+
+/// 对比逐项 to_lowercase 与预计算 search_key 的全表搜索耗时 (单字查询，即输入第一个字时的最坏情况)，
+/// 以及 FilterCache 首次筛选、查询不变时 (之后每一帧) 直接复用结果与复制结果的耗时
+fn search_profile(game: &GameData) -> Result<(), String> {
+    println!("读取 Item 表...");
+    let items = game.load_all_items(&game.load_ui_category_names());
+    let query = items
+        .iter()
+        .find_map(|item| item.search_key.chars().next())
+        .map(String::from)
+        .ok_or("没有读取到任何物品名")?;
+    let start = Instant::now();
+    let naive_hits = items
+        .iter()
+        .filter(|item| item.name.to_lowercase().contains(&query))
+        .count();
+    let naive_time = start.elapsed();
+    let start = Instant::now();
+    let cached_hits = items
+        .iter()
+        .filter(|item| item.matches_search(&query))
+        .count();
+    let cached_time = start.elapsed();
+    debug_assert_eq!(naive_hits, cached_hits);
+    println!(
+        "搜索耗时 ({} 件物品, 查询 \"{}\", 命中 {}): 逐项转小写 {:?}, 预计算小写名 {:?}",
+        items.len(),
+        query,
+        cached_hits,
+        naive_time,
+        cached_time
+    );
+
+    let filter = || -> Vec<usize> {
+        items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.matches_search(&query))
+            .map(|(idx, _)| idx)
+            .collect()
+    };
+    let mut cache = FilterCache::default();
+    let start = Instant::now();
+    let computed = cache.get_or_compute(query.clone(), filter);
+    let miss_time = start.elapsed();
+    let start = Instant::now();
+    let reused = cache.get_or_compute(query.clone(), filter);
+    let hit_time = start.elapsed();
+    debug_assert!(Arc::ptr_eq(&computed, &reused));
+    // 缓存改为共享 Arc 之前，每次命中都要复制整个结果
+    let start = Instant::now();
+    let copied = Vec::clone(&reused);
+    let copy_time = start.elapsed();
+    println!(
+        "筛选缓存 (结果 {} 项): 首次筛选 {:?}, 查询不变时复用 {:?} (复制结果需 {:?})",
+        copied.len(),
+        miss_time,
+        hit_time,
+        copy_time
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<CliArgs>, String> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn craft_profile_takes_optional_item_id() {
+        let Some(CliArgs { command, .. }) = parse(&["craft-profile"]).unwrap() else {
+            panic!("应解析为子命令");
+        };
+        assert!(matches!(command, Command::CraftProfile { item_id: None }));
+
+        let Some(CliArgs { command, game_dir }) =
+            parse(&["--game-dir", "ffxiv", "craft-profile", "37742"]).unwrap()
+        else {
+            panic!("应解析为子命令");
+        };
+        assert!(matches!(
+            command,
+            Command::CraftProfile {
+                item_id: Some(37742)
+            }
+        ));
+        assert_eq!(game_dir, Some(PathBuf::from("ffxiv")));
+
+        assert!(parse(&["craft-profile", "abc"]).is_err());
+        assert!(parse(&["craft-profile", "1", "2"]).is_err());
+    }
+
+    #[test]
+    fn search_profile_takes_no_arguments() {
+        let Some(CliArgs { command, .. }) = parse(&["search-profile"]).unwrap() else {
+            panic!("应解析为子命令");
+        };
+        assert!(matches!(command, Command::SearchProfile));
+        assert!(parse(&["search-profile", "1"]).is_err());
+    }
+
+    #[test]
+    fn gui_arguments_are_not_subcommands() {
+        assert!(parse(&[]).unwrap().is_none());
+        assert!(parse(&["--backend", "vulkan", "--mock"]).unwrap().is_none());
+    }
+}
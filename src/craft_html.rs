@@ -0,0 +1,313 @@
+//! 合成计划的 HTML 导出 (适合打印): 合成目标、制作步骤、按来源分组的素材汇总与合成树
+//!
+//! 这里只负责排版，数据由合成页按当前折叠状态与来源选择整理好后传入；
+//! 图标为 base64 编码的 PNG，没有图标 (加载或编码失败) 的行只显示文字。
+
+use crate::teamcraft::base64_encode;
+
+/// 导出文档的全部内容
+#[derive(Debug, Clone, Default)]
+pub struct CraftPlanDoc {
+    /// 合成目标名称
+    pub title: String,
+    pub icon: Option<String>,
+    /// 标题下方的说明行 (职业、等级等)
+    pub meta: Vec<String>,
+    pub steps: Vec<PlanStepRow>,
+    pub groups: Vec<PlanMaterialGroup>,
+    pub tree: PlanTreeNode,
+}
+
+/// 制作步骤表中的一行
+#[derive(Debug, Clone, Default)]
+pub struct PlanStepRow {
+    pub name: String,
+    pub icon: Option<String>,
+    pub job: String,
+    pub amount: String,
+    pub crafts: u32,
+}
+
+/// 素材汇总中的一个来源分组
+#[derive(Debug, Clone, Default)]
+pub struct PlanMaterialGroup {
+    /// 分组标题 (含种数与小计)
+    pub header: String,
+    pub rows: Vec<PlanMaterialRow>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlanMaterialRow {
+    pub name: String,
+    pub icon: Option<String>,
+    pub amount: String,
+    /// 费用或来源说明
+    pub cost: String,
+}
+
+/// 合成树节点，折叠的节点不含子节点
+#[derive(Debug, Clone, Default)]
+pub struct PlanTreeNode {
+    pub name: String,
+    pub amount: u32,
+    /// 附注 (已折叠、循环引用等)
+    pub note: Option<String>,
+    pub children: Vec<PlanTreeNode>,
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { display: flex; align-items: center; gap: 0.4em; margin-bottom: 0.2em; }
+h2 { border-bottom: 1px solid #ccc; padding-bottom: 0.2em; margin-top: 1.6em; }
+h3 { margin: 1em 0 0.4em; font-size: 1em; }
+.meta { color: #666; margin: 0.1em 0; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #ddd; padding: 0.25em 0.5em; text-align: left; vertical-align: middle; }
+th { background: #f4f4f4; }
+td.num { text-align: right; white-space: nowrap; }
+img.icon { width: 20px; height: 20px; vertical-align: middle; margin-right: 0.3em; }
+h1 img.icon { width: 40px; height: 40px; }
+ul.tree, ul.tree ul { list-style: none; padding-left: 1.2em; border-left: 1px dotted #bbb; }
+ul.tree { border-left: none; padding-left: 0; }
+.note { color: #888; font-size: 0.9em; }
+@media print {
+  body { margin: 0; }
+  h2 { break-after: avoid; }
+  tr, li { break-inside: avoid; }
+}
+";
+
+/// 转义 HTML 文本中的特殊字符
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 图标 + 名称，没有图标时只有名称
+fn name_cell(name: &str, icon: Option<&str>) -> String {
+    match icon {
+        Some(png) => format!(
+            "<img class=\"icon\" alt=\"\" src=\"data:image/png;base64,{}\">{}",
+            png,
+            escape(name)
+        ),
+        None => escape(name),
+    }
+}
+
+fn render_tree(node: &PlanTreeNode, out: &mut String) {
+    out.push_str(&format!("<li>{} ×{}", escape(&node.name), node.amount));
+    if let Some(note) = &node.note {
+        out.push_str(&format!(" <span class=\"note\">({})</span>", escape(note)));
+    }
+    if !node.children.is_empty() {
+        out.push_str("\n<ul>\n");
+        for child in &node.children {
+            render_tree(child, out);
+        }
+        out.push_str("</ul>\n");
+    }
+    out.push_str("</li>\n");
+}
+
+/// 生成完整的 HTML 文档
+pub fn render_craft_plan_html(doc: &CraftPlanDoc) -> String {
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"zh\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!(
+        "<title>{} - 制作计划</title>\n",
+        escape(&doc.title)
+    ));
+    out.push_str(&format!("<style>\n{}</style>\n</head>\n<body>\n", STYLE));
+
+    out.push_str(&format!(
+        "<h1>{}</h1>\n",
+        name_cell(&doc.title, doc.icon.as_deref())
+    ));
+    for line in &doc.meta {
+        out.push_str(&format!("<p class=\"meta\">{}</p>\n", escape(line)));
+    }
+
+    out.push_str("<h2>制作步骤</h2>\n");
+    if doc.steps.is_empty() {
+        out.push_str("<p class=\"note\">无</p>\n");
+    } else {
+        out.push_str(
+            "<table>\n<tr><th>#</th><th>物品</th><th>职业</th><th>数量</th><th>制作次数</th></tr>\n",
+        );
+        for (i, step) in doc.steps.iter().enumerate() {
+            out.push_str(&format!(
+                "<tr><td class=\"num\">{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+                i + 1,
+                name_cell(&step.name, step.icon.as_deref()),
+                escape(&step.job),
+                escape(&step.amount),
+                step.crafts
+            ));
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("<h2>素材汇总</h2>\n");
+    if doc.groups.is_empty() {
+        out.push_str("<p class=\"note\">无原始素材</p>\n");
+    }
+    for group in &doc.groups {
+        out.push_str(&format!("<h3>{}</h3>\n", escape(&group.header)));
+        out.push_str("<table>\n<tr><th>素材</th><th>数量</th><th>费用 / 来源</th></tr>\n");
+        for row in &group.rows {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td class=\"num\">{}</td><td>{}</td></tr>\n",
+                name_cell(&row.name, row.icon.as_deref()),
+                escape(&row.amount),
+                escape(&row.cost)
+            ));
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("<h2>合成树</h2>\n<ul class=\"tree\">\n");
+    render_tree(&doc.tree, &mut out);
+    out.push_str("</ul>\n</body>\n</html>\n");
+    out
+}
+
+/// 把 RGBA 图标编码为 base64 PNG，失败时返回 None (该行退化为纯文字)
+pub fn encode_icon_png(rgba: &[u8], width: u32, height: u32) -> Option<String> {
+    use image::ImageEncoder;
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(rgba, width, height, image::ExtendedColorType::Rgba8)
+        .ok()?;
+    Some(base64_encode(&png))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 期望的完整输出；排版有意改动时用测试失败时打印的实际输出替换
+    const GOLDEN: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/craft_html/plan.html"
+    ));
+
+    fn leaf(name: &str, amount: u32) -> PlanTreeNode {
+        PlanTreeNode {
+            name: name.to_string(),
+            amount,
+            ..Default::default()
+        }
+    }
+
+    /// 覆盖有图标/无图标的行、需转义的文本、折叠附注与多层合成树
+    fn sample_doc() -> CraftPlanDoc {
+        CraftPlanDoc {
+            title: "Rarefied <Tacos> & \"Salsa\"".to_string(),
+            icon: Some("AAAA".to_string()),
+            meta: vec!["烹调师 Lv.90".to_string(), "数量 3".to_string()],
+            steps: vec![
+                PlanStepRow {
+                    name: "Tortilla".to_string(),
+                    icon: None,
+                    job: "烹调师".to_string(),
+                    amount: "6".to_string(),
+                    crafts: 2,
+                },
+                PlanStepRow {
+                    name: "Rarefied <Tacos> & \"Salsa\"".to_string(),
+                    icon: Some("BBBB".to_string()),
+                    job: "烹调师".to_string(),
+                    amount: "3".to_string(),
+                    crafts: 3,
+                },
+            ],
+            groups: vec![
+                PlanMaterialGroup {
+                    header: "NPC 商店 (2 种, 1,200 金币)".to_string(),
+                    rows: vec![PlanMaterialRow {
+                        name: "Salt".to_string(),
+                        icon: Some("CCCC".to_string()),
+                        amount: "12".to_string(),
+                        cost: "100 金币 × 12".to_string(),
+                    }],
+                },
+                PlanMaterialGroup {
+                    header: "采集 (1 种)".to_string(),
+                    rows: vec![PlanMaterialRow {
+                        name: "O'Ghomoro Berries".to_string(),
+                        icon: None,
+                        amount: "9 (HQ 3)".to_string(),
+                        cost: "园艺工".to_string(),
+                    }],
+                },
+            ],
+            tree: PlanTreeNode {
+                name: "Rarefied <Tacos> & \"Salsa\"".to_string(),
+                amount: 3,
+                note: None,
+                children: vec![
+                    PlanTreeNode {
+                        name: "Tortilla".to_string(),
+                        amount: 6,
+                        note: Some("已折叠".to_string()),
+                        children: Vec::new(),
+                    },
+                    PlanTreeNode {
+                        children: vec![leaf("Salt", 12)],
+                        ..leaf("Salsa", 3)
+                    },
+                ],
+            },
+        }
+    }
+
+    #[test]
+    fn matches_golden_file() {
+        let html = render_craft_plan_html(&sample_doc());
+        assert!(
+            html == GOLDEN,
+            "HTML 输出与 tests/fixtures/craft_html/plan.html 不一致，实际输出:\n{html}"
+        );
+    }
+
+    #[test]
+    fn empty_plan_has_placeholders() {
+        let html = render_craft_plan_html(&CraftPlanDoc {
+            title: "Water".to_string(),
+            tree: leaf("Water", 1),
+            ..Default::default()
+        });
+        assert!(html.contains("<h1>Water</h1>"));
+        assert!(html.contains("<h2>制作步骤</h2>\n<p class=\"note\">无</p>"));
+        assert!(html.contains("<h2>素材汇总</h2>\n<p class=\"note\">无原始素材</p>"));
+        assert!(html.contains("<li>Water ×1</li>"));
+        assert!(!html.contains("<img"));
+    }
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+        assert_eq!(escape("以太之光"), "以太之光");
+    }
+
+    #[test]
+    fn icon_encodes_as_png() {
+        let png = encode_icon_png(&[255, 0, 0, 255, 0, 0, 255, 255], 2, 1).unwrap();
+        // base64("\x89PNG\r\n\x1a\n") 的前缀
+        assert!(png.starts_with("iVBORw0KGgo"));
+    }
+}
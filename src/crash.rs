@@ -0,0 +1,266 @@
+//! 崩溃保护: panic 钩子在进程退出前写入配置与幻化编辑器的恢复文件，并生成崩溃报告；
+//! 每帧的界面更新在 [`guard`] 中运行，单次出错 (如损坏的模型数据) 只提示而不退出
+//!
+//! 钩子运行在出错的线程上，拿不到 App，所需的状态 (配置、恢复内容、当前页面等) 由界面线程
+//! 随时写入 [`CrashContext`] 快照。后台线程的 panic 不会结束进程，只交给默认钩子打印。
+
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashSet, VecDeque};
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::AppPage;
+use crate::glamour::{self, GlamourRecovery};
+
+/// 崩溃报告中保留的最近提示条数
+const RECENT_MESSAGE_COUNT: usize = 8;
+
+/// 界面线程维护的状态快照，供 panic 钩子使用
+#[derive(Default)]
+struct CrashContext {
+    game_version: Option<String>,
+    page: Option<AppPage>,
+    /// 最近的提示 (最新的在后)
+    recent_messages: VecDeque<String>,
+    /// 最新的配置内容 (已序列化)
+    config_json: Option<String>,
+    /// 幻化编辑器有未保存修改时的恢复内容
+    recovery: Option<GlamourRecovery>,
+}
+
+static CONTEXT: LazyLock<Mutex<CrashContext>> = LazyLock::new(Default::default);
+
+/// 加载时出过错的物品 row_id，本次运行中不再加载其模型
+static SKIPPED_ITEMS: LazyLock<Mutex<HashSet<u32>>> = LazyLock::new(Default::default);
+
+thread_local! {
+    /// 当前线程处于 [`guard`] 中的层数
+    static GUARD_DEPTH: Cell<u32> = const { Cell::new(0) };
+    /// guard 中捕获的 panic，由 guard 取回
+    static CAUGHT: RefCell<Option<CaughtPanic>> = const { RefCell::new(None) };
+    /// 当前线程正在加载模型的物品
+    static LOADING_ITEM: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// [`guard`] 捕获到的 panic
+#[derive(Debug, Clone)]
+pub struct CaughtPanic {
+    pub message: String,
+    /// 出错时正在加载模型的物品 (已加入跳过列表)
+    pub item_id: Option<u32>,
+}
+
+/// 上次崩溃留下的报告，启动时读取一次
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub path: PathBuf,
+    pub message: String,
+    /// 崩溃时写入了幻化编辑器的恢复文件
+    pub recovery_saved: bool,
+}
+
+fn reports_dir() -> PathBuf {
+    crate::config::data_subdir("crash_reports")
+}
+
+/// 尚未向用户展示的崩溃报告记录
+fn pending_path() -> PathBuf {
+    reports_dir().join("pending.json")
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn with_context(f: impl FnOnce(&mut CrashContext)) {
+    let mut ctx = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut ctx);
+}
+
+pub fn set_game_version(version: Option<String>) {
+    with_context(|ctx| ctx.game_version = version);
+}
+
+pub fn set_page(page: AppPage) {
+    with_context(|ctx| ctx.page = Some(page));
+}
+
+/// 记录一条提示 (报告中列出最近几条)
+pub fn record_message(message: &str) {
+    with_context(|ctx| {
+        if ctx.recent_messages.len() == RECENT_MESSAGE_COUNT {
+            ctx.recent_messages.pop_front();
+        }
+        ctx.recent_messages.push_back(message.to_string());
+    });
+}
+
+pub fn set_config_snapshot(json: String) {
+    with_context(|ctx| ctx.config_json = Some(json));
+}
+
+/// 幻化编辑器的恢复内容，没有未保存修改时为 None
+pub fn set_recovery_snapshot(recovery: Option<GlamourRecovery>) {
+    with_context(|ctx| ctx.recovery = recovery);
+}
+
+/// 物品的模型加载曾经出错
+pub fn is_item_skipped(row_id: u32) -> bool {
+    SKIPPED_ITEMS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&row_id)
+}
+
+/// 模型加载期间持有: 加载中 panic 时把物品加入跳过列表，之后再选中不会重复出错
+pub struct ItemLoadGuard {
+    previous: Option<u32>,
+    row_id: u32,
+}
+
+impl ItemLoadGuard {
+    pub fn new(row_id: u32) -> Self {
+        let previous = LOADING_ITEM.with(|item| item.replace(Some(row_id)));
+        Self { previous, row_id }
+    }
+}
+
+impl Drop for ItemLoadGuard {
+    fn drop(&mut self) {
+        LOADING_ITEM.with(|item| item.set(self.previous));
+        if std::thread::panicking() {
+            SKIPPED_ITEMS
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(self.row_id);
+        }
+    }
+}
+
+/// 运行 `f`，其中的 panic 被捕获并返回，不写崩溃报告
+pub fn guard<R>(f: impl FnOnce() -> R) -> Result<R, CaughtPanic> {
+    GUARD_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    GUARD_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    result.map_err(|_| {
+        CAUGHT
+            .with(|caught| caught.borrow_mut().take())
+            .unwrap_or(CaughtPanic {
+                message: "未知错误".to_string(),
+                item_id: None,
+            })
+    })
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    let text = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("未知错误");
+    match info.location() {
+        Some(loc) => format!("{} ({}:{})", text, loc.file(), loc.line()),
+        None => text.to_string(),
+    }
+}
+
+/// 安装 panic 钩子 (启动时调用一次)
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let message = panic_message(info);
+        let item_id = LOADING_ITEM.with(Cell::get);
+        if GUARD_DEPTH.with(Cell::get) > 0 {
+            // 调用栈只打印到终端，界面上只提示错误信息
+            eprintln!("界面更新出错: {}\n{}", message, Backtrace::force_capture());
+            CAUGHT.with(|caught| *caught.borrow_mut() = Some(CaughtPanic { message, item_id }));
+            return;
+        }
+        default_hook(info);
+        if std::thread::current().name() != Some("main") {
+            return;
+        }
+        let backtrace = Backtrace::force_capture().to_string();
+        match write_crash_report(&message, &backtrace, item_id) {
+            Ok(path) => eprintln!("崩溃报告已写入 {}", path.display()),
+            Err(e) => eprintln!("写入崩溃报告失败: {}", e),
+        }
+    }));
+}
+
+/// 保存快照中的配置与恢复内容，写入报告文件并记录待展示
+fn write_crash_report(
+    message: &str,
+    backtrace: &str,
+    item_id: Option<u32>,
+) -> Result<PathBuf, String> {
+    // 出错时可能正持有锁，拿不到就只写报告
+    let ctx = CONTEXT.try_lock().ok();
+    let mut recovery_saved = false;
+    if let Some(ctx) = &ctx {
+        if let Some(json) = &ctx.config_json {
+            if let Err(e) = crate::config::write_config_file(json) {
+                eprintln!("保存配置失败: {}", e);
+            }
+        }
+        if let Some(recovery) = &ctx.recovery {
+            match glamour::save_recovery(recovery) {
+                Ok(()) => recovery_saved = true,
+                Err(e) => eprintln!("写入恢复文件失败: {}", e),
+            }
+        }
+    }
+
+    let mut text = String::from("tomestone 崩溃报告\n\n");
+    text.push_str(&format!("程序版本: {}\n", env!("CARGO_PKG_VERSION")));
+    text.push_str(&format!("时间戳: {}\n", now_millis()));
+    if let Some(ctx) = &ctx {
+        if let Some(version) = &ctx.game_version {
+            text.push_str(&format!("游戏版本: {}\n", version));
+        }
+        if let Some(page) = ctx.page {
+            text.push_str(&format!("当前页面: {:?}\n", page));
+        }
+    }
+    if let Some(id) = item_id {
+        text.push_str(&format!("正在加载的物品: {}\n", id));
+    }
+    text.push_str(&format!("错误: {}\n", message));
+    if let Some(ctx) = &ctx {
+        if !ctx.recent_messages.is_empty() {
+            text.push_str("\n最近的提示:\n");
+            for msg in &ctx.recent_messages {
+                text.push_str(&format!("- {}\n", msg));
+            }
+        }
+    }
+    text.push_str(&format!("\n调用栈:\n{}\n", backtrace));
+
+    let path = reports_dir().join(format!("crash-{}.txt", now_millis()));
+    std::fs::write(&path, text).map_err(|e| format!("写入 {} 失败: {}", path.display(), e))?;
+    let report = CrashReport {
+        path: path.clone(),
+        message: message.to_string(),
+        recovery_saved,
+    };
+    let json = serde_json::to_string(&report).map_err(|e| e.to_string())?;
+    std::fs::write(pending_path(), json).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// 取出上次崩溃留下的报告 (只返回一次)
+pub fn take_pending_report() -> Option<CrashReport> {
+    let path = pending_path();
+    let content = std::fs::read_to_string(&path).ok()?;
+    let _ = std::fs::remove_file(&path);
+    serde_json::from_str(&content).ok()
+}
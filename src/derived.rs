@@ -0,0 +1,395 @@
+//! 派生索引: GameState 就绪后在后台线程按阶段构建的附加数据 (名称查找表、快速切换候选)，
+//! 界面不必等待；某个索引就绪前，用到它的功能退回逐项计算
+//!
+//! 重新加载游戏数据时 GameState 连同本结构一起丢弃: 后台线程看到取消标志后退出，
+//! 尚未取回的结果随通道一同丢弃。切换界面语言后整体重建 (快速切换的页面候选使用界面语言的名称)。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+
+use crate::domain::{AppPage, GameItem};
+use crate::fuzzy::FuzzyText;
+use crate::i18n::{self, Lang};
+
+/// 每处理这么多件物品检查一次取消标志
+const CANCEL_CHECK_INTERVAL: usize = 1024;
+
+/// 构建阶段，按 [`DerivedStage::ALL`] 的顺序执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerivedStage {
+    NameLookup,
+    SwitcherCandidates,
+}
+
+impl DerivedStage {
+    pub const ALL: [Self; 2] = [Self::NameLookup, Self::SwitcherCandidates];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::NameLookup => "物品名称查找表",
+            Self::SwitcherCandidates => "快速切换候选",
+        }
+    }
+}
+
+/// 物品名称 -> row_id (同名取表中靠前的)，先精确匹配再忽略大小写
+#[derive(Debug, Default)]
+pub struct NameLookup {
+    exact: HashMap<String, u32>,
+    lower: HashMap<String, u32>,
+}
+
+impl NameLookup {
+    /// 在当前线程直接构建 (索引未就绪时的退路)
+    pub fn new(items: &[GameItem]) -> Self {
+        Self::build(items, &AtomicBool::new(false)).unwrap_or_default()
+    }
+
+    /// 取消时返回 None
+    fn build(items: &[GameItem], cancel: &AtomicBool) -> Option<Self> {
+        let mut lookup = Self::default();
+        for (i, item) in items.iter().enumerate() {
+            if i % CANCEL_CHECK_INTERVAL == 0 && cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            lookup.exact.entry(item.name.clone()).or_insert(item.row_id);
+            lookup
+                .lower
+                .entry(item.search_key.clone())
+                .or_insert(item.row_id);
+        }
+        Some(lookup)
+    }
+
+    pub fn find(&self, name: &str) -> Option<u32> {
+        self.exact
+            .get(name)
+            .or_else(|| self.lower.get(name.to_lowercase().as_str()))
+            .copied()
+    }
+}
+
+/// 快速切换中固定不变的候选 (幻化组合会随编辑变化，不在此列)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitcherItem {
+    Page(AppPage),
+    Equipment(u32),
+    Craftable(u32),
+}
+
+/// 快速切换的候选及预先转小写的名称，按页面、装备、可制作物品排列，物品按 row_id 排序
+#[derive(Debug, Default)]
+pub struct SwitcherCandidates {
+    pub entries: Vec<(SwitcherItem, FuzzyText)>,
+}
+
+impl SwitcherCandidates {
+    fn build(input: &BuildInput, cancel: &AtomicBool) -> Option<Self> {
+        let mut entries: Vec<(SwitcherItem, FuzzyText)> = input
+            .pages
+            .iter()
+            .map(|&page| (SwitcherItem::Page(page), FuzzyText::new(page.label())))
+            .collect();
+        let item_entries = input
+            .equipment_rows
+            .iter()
+            .map(|&(row_id, idx)| (SwitcherItem::Equipment(row_id), idx))
+            .chain(
+                input
+                    .craftable_rows
+                    .iter()
+                    .map(|&(row_id, idx)| (SwitcherItem::Craftable(row_id), idx)),
+            );
+        for (i, (item, idx)) in item_entries.enumerate() {
+            if i % CANCEL_CHECK_INTERVAL == 0 && cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            entries.push((item, FuzzyText::new(&input.items[idx].name)));
+        }
+        Some(Self { entries })
+    }
+}
+
+enum StageResult {
+    NameLookup(NameLookup),
+    SwitcherCandidates(SwitcherCandidates),
+}
+
+/// 构建所需的数据 (物品表与 GameState 共享)，语言切换后重建时复用
+struct BuildInput {
+    items: Arc<[GameItem]>,
+    /// 装备 (row_id, all_items 下标)，按 row_id 排序
+    equipment_rows: Vec<(u32, usize)>,
+    /// 有配方的物品 (row_id, all_items 下标)，按 row_id 排序
+    craftable_rows: Vec<(u32, usize)>,
+    pages: Vec<AppPage>,
+}
+
+fn build_stage(
+    stage: DerivedStage,
+    input: &BuildInput,
+    cancel: &AtomicBool,
+) -> Option<StageResult> {
+    match stage {
+        DerivedStage::NameLookup => {
+            NameLookup::build(&input.items, cancel).map(StageResult::NameLookup)
+        }
+        DerivedStage::SwitcherCandidates => {
+            SwitcherCandidates::build(input, cancel).map(StageResult::SwitcherCandidates)
+        }
+    }
+}
+
+/// 后台构建的派生索引及各自的就绪状态
+pub struct DerivedIndexes {
+    input: Arc<BuildInput>,
+    names: Option<NameLookup>,
+    switcher: Option<SwitcherCandidates>,
+    /// 当前一轮构建开始时的界面语言
+    lang: Lang,
+    /// 构建线程的结果通道，全部阶段完成后置为 None
+    rx: Option<Receiver<StageResult>>,
+    /// 当前一轮构建的取消标志，每轮重新创建
+    cancel: Arc<AtomicBool>,
+}
+
+impl DerivedIndexes {
+    /// 记录构建所需的数据并立即开始第一轮构建
+    ///
+    /// - `equipment_indices`: 装备在 `items` 中的下标
+    /// - `craftable_ids`: 有配方的物品 row_id
+    /// - `pages`: 快速切换可跳转的页面
+    pub fn spawn(
+        items: Arc<[GameItem]>,
+        item_id_map: &HashMap<u32, usize>,
+        equipment_indices: &[usize],
+        craftable_ids: impl IntoIterator<Item = u32>,
+        pages: Vec<AppPage>,
+    ) -> Self {
+        let mut equipment_rows: Vec<(u32, usize)> = equipment_indices
+            .iter()
+            .map(|&idx| (items[idx].row_id, idx))
+            .collect();
+        equipment_rows.sort_unstable();
+        let mut craftable_rows: Vec<(u32, usize)> = craftable_ids
+            .into_iter()
+            .filter_map(|row_id| Some((row_id, *item_id_map.get(&row_id)?)))
+            .collect();
+        craftable_rows.sort_unstable();
+        let mut indexes = Self {
+            input: Arc::new(BuildInput {
+                items,
+                equipment_rows,
+                craftable_rows,
+                pages,
+            }),
+            names: None,
+            switcher: None,
+            lang: i18n::current_lang(),
+            rx: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+        };
+        indexes.restart();
+        indexes
+    }
+
+    /// 取消进行中的构建，清空已有结果后重新开始
+    fn restart(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        self.names = None;
+        self.switcher = None;
+        self.lang = i18n::current_lang();
+
+        // 旧线程的结果通道随 rx 一同丢弃，不会混入新一轮的结果
+        let (tx, rx) = mpsc::channel();
+        let input = Arc::clone(&self.input);
+        let cancel = Arc::clone(&self.cancel);
+        let spawned = std::thread::Builder::new()
+            .name("derived-indexes".to_string())
+            .spawn(move || {
+                for stage in DerivedStage::ALL {
+                    let Some(result) = build_stage(stage, &input, &cancel) else {
+                        return;
+                    };
+                    if tx.send(result).is_err() {
+                        return;
+                    }
+                }
+            });
+        match spawned {
+            Ok(_) => self.rx = Some(rx),
+            Err(e) => {
+                eprintln!("无法启动索引构建线程: {}", e);
+                self.rx = None;
+            }
+        }
+    }
+
+    /// 收取已完成的阶段；界面语言变化时重新构建。返回是否仍在构建中
+    pub fn poll(&mut self) -> bool {
+        if self.lang != i18n::current_lang() {
+            self.restart();
+        }
+        let Some(rx) = &self.rx else {
+            return false;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(StageResult::NameLookup(names)) => self.names = Some(names),
+                Ok(StageResult::SwitcherCandidates(switcher)) => self.switcher = Some(switcher),
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => {
+                    self.rx = None;
+                    return false;
+                }
+            }
+        }
+    }
+
+    pub fn is_ready(&self, stage: DerivedStage) -> bool {
+        match stage {
+            DerivedStage::NameLookup => self.names.is_some(),
+            DerivedStage::SwitcherCandidates => self.switcher.is_some(),
+        }
+    }
+
+    /// 构建中时返回 (已完成阶段数, 总阶段数, 正在构建的阶段)
+    pub fn progress(&self) -> Option<(usize, usize, DerivedStage)> {
+        self.rx.as_ref()?;
+        let mut pending = DerivedStage::ALL
+            .into_iter()
+            .filter(|&stage| !self.is_ready(stage));
+        let current = pending.next()?;
+        let total = DerivedStage::ALL.len();
+        Some((total - 1 - pending.count(), total, current))
+    }
+
+    pub fn names(&self) -> Option<&NameLookup> {
+        self.names.as_ref()
+    }
+
+    pub fn switcher(&self) -> Option<&SwitcherCandidates> {
+        self.switcher.as_ref()
+    }
+}
+
+impl Drop for DerivedIndexes {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn spawn_indexes(names: &[&str]) -> DerivedIndexes {
+        let items: Arc<[GameItem]> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| GameItem::named(100 + i as u32, name))
+            .collect();
+        let item_id_map: HashMap<u32, usize> = items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| (item.row_id, idx))
+            .collect();
+        DerivedIndexes::spawn(items, &item_id_map, &[], [101, 100], Vec::new())
+    }
+
+    /// 等待当前一轮构建结束
+    fn finish(indexes: &mut DerivedIndexes) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while indexes.poll() {
+            assert!(Instant::now() < deadline, "索引构建超时");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn name_lookup_prefers_first_row_and_ignores_case() {
+        let items = [
+            GameItem::named(1, "Bronze Ingot"),
+            GameItem::named(2, "Bronze Ingot"),
+            GameItem::named(3, "Iron Ore"),
+        ];
+        let lookup = NameLookup::new(&items);
+        assert_eq!(lookup.find("Bronze Ingot"), Some(1));
+        assert_eq!(lookup.find("IRON ORE"), Some(3));
+        assert_eq!(lookup.find("Copper Ore"), None);
+    }
+
+    #[test]
+    fn cancelled_build_yields_nothing() {
+        let input = BuildInput {
+            items: Arc::from(vec![GameItem::named(1, "a")]),
+            equipment_rows: Vec::new(),
+            craftable_rows: vec![(1, 0)],
+            pages: Vec::new(),
+        };
+        let cancel = AtomicBool::new(true);
+        for stage in DerivedStage::ALL {
+            assert!(build_stage(stage, &input, &cancel).is_none());
+        }
+    }
+
+    #[test]
+    fn completed_build_fills_every_stage() {
+        let mut indexes = spawn_indexes(&["Maple Log", "Ash Log"]);
+        finish(&mut indexes);
+        for stage in DerivedStage::ALL {
+            assert!(indexes.is_ready(stage), "{} 未就绪", stage.label());
+        }
+        assert!(indexes.progress().is_none());
+        assert_eq!(indexes.names().unwrap().find("ash log"), Some(101));
+        let crafted: Vec<SwitcherItem> = indexes
+            .switcher()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|(item, _)| *item)
+            .collect();
+        assert_eq!(
+            crafted,
+            [SwitcherItem::Craftable(100), SwitcherItem::Craftable(101)]
+        );
+    }
+
+    #[test]
+    fn restart_mid_build_discards_stale_results() {
+        let mut indexes = spawn_indexes(&["Maple Log"]);
+        finish(&mut indexes);
+
+        // 模拟上一轮构建尚未结束: 换上一条仍有发送端的通道
+        let (stale_tx, stale_rx) = mpsc::channel();
+        indexes.rx = Some(stale_rx);
+        let old_cancel = Arc::clone(&indexes.cancel);
+        indexes.restart();
+
+        assert!(old_cancel.load(Ordering::Relaxed), "旧一轮构建应被取消");
+        assert!(!indexes.cancel.load(Ordering::Relaxed));
+        assert!(indexes.names().is_none());
+        assert!(indexes.switcher().is_none());
+        // 旧通道的接收端已丢弃，迟到的旧结果无处可去
+        let mut stale = NameLookup::default();
+        stale.exact.insert("stale".to_string(), 1);
+        assert!(stale_tx.send(StageResult::NameLookup(stale)).is_err());
+
+        finish(&mut indexes);
+        let names = indexes.names().unwrap();
+        assert_eq!(names.find("stale"), None);
+        assert_eq!(names.find("Maple Log"), Some(100));
+    }
+
+    #[test]
+    fn dropping_indexes_cancels_build() {
+        let indexes = spawn_indexes(&["Maple Log"]);
+        let cancel = Arc::clone(&indexes.cancel);
+        drop(indexes);
+        assert!(cancel.load(Ordering::Relaxed));
+    }
+}
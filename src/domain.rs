@@ -1,18 +1,51 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
 
 // ── 页面路由 ──
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum AppPage {
+    #[default]
     Browser,
     GlamourManager,
     HousingBrowser,
     CraftingBrowser,
+    CollectionBrowser,
     Toolbox,
     ResourceBrowser,
+    Settings,
     Test,
 }
 
+impl AppPage {
+    /// 可作为启动页的页面 (测试页除外)
+    pub const STARTUP_CHOICES: [AppPage; 7] = [
+        AppPage::Browser,
+        AppPage::GlamourManager,
+        AppPage::HousingBrowser,
+        AppPage::CraftingBrowser,
+        AppPage::CollectionBrowser,
+        AppPage::Toolbox,
+        AppPage::ResourceBrowser,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AppPage::Browser => tr!("page.browser"),
+            AppPage::GlamourManager => tr!("page.glamour"),
+            AppPage::HousingBrowser => tr!("page.housing"),
+            AppPage::CraftingBrowser => tr!("page.crafting"),
+            AppPage::CollectionBrowser => tr!("page.collection"),
+            AppPage::Toolbox => tr!("page.toolbox"),
+            AppPage::ResourceBrowser => tr!("page.resource"),
+            AppPage::Settings => tr!("button.settings"),
+            AppPage::Test => tr!("page.test"),
+        }
+    }
+}
+
 // ── 房屋子标签 ──
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -25,9 +58,9 @@ pub enum HousingSubTab {
 impl HousingSubTab {
     pub fn display_name(&self) -> &'static str {
         match self {
-            Self::Exterior => "外装",
-            Self::Yard => "庭院家具",
-            Self::Indoor => "室内家具",
+            Self::Exterior => tr!("housing_tab.exterior"),
+            Self::Yard => tr!("housing_tab.yard"),
+            Self::Indoor => tr!("housing_tab.indoor"),
         }
     }
 }
@@ -38,6 +71,55 @@ pub const HOUSING_SUB_TABS: [HousingSubTab; 3] = [
     HousingSubTab::Indoor,
 ];
 
+// ── 房屋尺寸 ──
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HousingSize {
+    Small,
+    Medium,
+    Large,
+    /// 无尺寸数据 (家具或通用外装)
+    Unknown,
+}
+
+impl HousingSize {
+    /// 从 HousingExterior.HousingSize 映射 (0 小 / 1 中 / 2 大)
+    pub fn from_raw(value: u8) -> Self {
+        match value {
+            0 => Self::Small,
+            1 => Self::Medium,
+            2 => Self::Large,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// 筛选按钮上的短名称
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Self::Small => "S",
+            Self::Medium => "M",
+            Self::Large => "L",
+            Self::Unknown => tr!("housing_size.unknown"),
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Small => tr!("housing_size.small"),
+            Self::Medium => tr!("housing_size.medium"),
+            Self::Large => tr!("housing_size.large"),
+            Self::Unknown => tr!("housing_size.unknown"),
+        }
+    }
+}
+
+pub const HOUSING_SIZES: [HousingSize; 4] = [
+    HousingSize::Small,
+    HousingSize::Medium,
+    HousingSize::Large,
+    HousingSize::Unknown,
+];
+
 // ── 房屋外装类型 ──
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -93,6 +175,43 @@ pub const EXTERIOR_PART_TYPES: [ExteriorPartType; 8] = [
     ExteriorPartType::Fence,
 ];
 
+// ── 收藏品 ──
+
+/// 收藏品类别: 物品的 AdditionalData 链接到对应的表
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionKind {
+    /// 乐谱 (FilterGroup 32 → Orchestrion)
+    Orchestrion,
+    /// 幻卡 (FilterGroup 29 → TripleTriadCard)
+    TripleTriad,
+}
+
+impl CollectionKind {
+    pub fn filter_group(&self) -> u8 {
+        match self {
+            Self::Orchestrion => 32,
+            Self::TripleTriad => 29,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Orchestrion => tr!("collection.orchestrion"),
+            Self::TripleTriad => tr!("collection.triple_triad"),
+        }
+    }
+}
+
+pub const COLLECTION_KINDS: [CollectionKind; 2] =
+    [CollectionKind::Orchestrion, CollectionKind::TripleTriad];
+
+/// 收藏品在链接表中的名称与说明文本
+#[derive(Debug, Clone)]
+pub struct CollectionInfo {
+    pub name: String,
+    pub description: String,
+}
+
 // ── 视图模式 & 排序 ──
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -106,12 +225,45 @@ pub enum ViewMode {
 impl ViewMode {
     pub fn label(&self) -> &'static str {
         match self {
-            Self::List => "列表",
-            Self::Grid => "图标",
+            Self::List => tr!("view.list"),
+            Self::Grid => tr!("view.grid"),
+        }
+    }
+}
+
+/// 可染色筛选 (三态)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DyeFilter {
+    All,
+    DyeableOnly,
+    NonDyeableOnly,
+}
+
+impl DyeFilter {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::All => tr!("common.all"),
+            Self::DyeableOnly => tr!("dye_filter.dyeable"),
+            Self::NonDyeableOnly => tr!("dye_filter.non_dyeable"),
+        }
+    }
+
+    /// 染色通道数未知的物品在筛选生效时暂不显示，探测完成后再加入
+    pub fn matches(&self, dye_count: Option<u8>) -> bool {
+        match self {
+            Self::All => true,
+            Self::DyeableOnly => dye_count.is_some_and(|n| n > 0),
+            Self::NonDyeableOnly => dye_count == Some(0),
         }
     }
 }
 
+pub const DYE_FILTERS: [DyeFilter; 3] = [
+    DyeFilter::All,
+    DyeFilter::DyeableOnly,
+    DyeFilter::NonDyeableOnly,
+];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SortOrder {
     ByName,
@@ -122,9 +274,9 @@ pub enum SortOrder {
 impl SortOrder {
     pub fn label(&self) -> &'static str {
         match self {
-            Self::ByName => "按名称",
-            Self::BySetId => "按套装",
-            Self::BySlot => "按槽位",
+            Self::ByName => tr!("sort.by_name"),
+            Self::BySetId => tr!("sort.by_set"),
+            Self::BySlot => tr!("sort.by_slot"),
         }
     }
 }
@@ -142,15 +294,40 @@ pub enum EquipSlot {
     Necklace,
     Bracelet,
     Ring,
+    /// 面饰 (眼镜)，不使用 EquipSlotCategory，按 ItemUICategory 识别
+    Facewear,
+}
+
+/// 面饰物品所属 ItemUICategory 的名称，加载时据此在表中查找分类 row_id
+pub const FACEWEAR_UI_CATEGORY_NAME: &str = "面饰";
+
+/// 在 ItemUICategory (row_id -> 名称) 中查找面饰分类
+pub fn facewear_ui_category(ui_category_names: &HashMap<u8, String>) -> Option<u8> {
+    ui_category_names
+        .iter()
+        .find(|(_, name)| name.as_str() == FACEWEAR_UI_CATEGORY_NAME)
+        .map(|(&id, _)| id)
 }
 
+/// 不对应独立装备模型的 set_id: 0 为角色默认小衣 (e0000)，9903 为 NPC 小衣。
+/// "皇帝的新衣" 一类物品的 model_main 也指向这些编号
+pub const SMALLCLOTHES_SET_IDS: [u16; 2] = [0, 9903];
+
+/// Item 表防御/格挡专用列对应的 BaseParam row_id，用于统一按 BaseParam 表取名
+pub const BASE_PARAM_BLOCK_RATE: u8 = 17;
+pub const BASE_PARAM_BLOCK: u8 = 18;
+pub const BASE_PARAM_DEFENSE: u8 = 21;
+pub const BASE_PARAM_MAGIC_DEFENSE: u8 = 24;
+
 impl EquipSlot {
     pub fn from_category(cat: u8) -> Option<Self> {
         match cat {
             3 => Some(Self::Head),
-            4 => Some(Self::Body),
+            // 15/16/19..=22: 占用身体并遮挡其他槽位的服装 (见 GameItem::replaces_body)
+            4 | 15 | 16 | 19..=22 => Some(Self::Body),
             5 => Some(Self::Gloves),
-            7 => Some(Self::Legs),
+            // 18: 遮挡脚部的腿部装备
+            7 | 18 => Some(Self::Legs),
             8 => Some(Self::Feet),
             9 => Some(Self::Earrings),
             10 => Some(Self::Necklace),
@@ -171,32 +348,37 @@ impl EquipSlot {
             Self::Necklace => "nek",
             Self::Bracelet => "wrs",
             Self::Ring => "rir",
+            Self::Facewear => "fwr",
         }
     }
 
     pub fn display_name(&self) -> &'static str {
         match self {
-            Self::Head => "头部",
-            Self::Body => "身体",
-            Self::Gloves => "手部",
-            Self::Legs => "腿部",
-            Self::Feet => "脚部",
-            Self::Earrings => "耳饰",
-            Self::Necklace => "项链",
-            Self::Bracelet => "手镯",
-            Self::Ring => "戒指",
+            Self::Head => tr!("slot.head"),
+            Self::Body => tr!("slot.body"),
+            Self::Gloves => tr!("slot.gloves"),
+            Self::Legs => tr!("slot.legs"),
+            Self::Feet => tr!("slot.feet"),
+            Self::Earrings => tr!("slot.earrings"),
+            Self::Necklace => tr!("slot.necklace"),
+            Self::Bracelet => tr!("slot.bracelet"),
+            Self::Ring => tr!("slot.ring"),
+            Self::Facewear => tr!("slot.facewear"),
         }
     }
 
+    /// 是否属于饰品行 (与 ACCESSORY_SLOTS 一致，包含面饰)
     pub fn is_accessory(&self) -> bool {
-        matches!(
-            self,
-            Self::Earrings | Self::Necklace | Self::Bracelet | Self::Ring
-        )
+        ACCESSORY_SLOTS.contains(self)
+    }
+
+    /// 模型是否位于 accessory 目录 (面饰虽属饰品行，模型在 equipment 目录)
+    pub fn uses_accessory_models(&self) -> bool {
+        self.is_accessory() && *self != Self::Facewear
     }
 }
 
-pub const ALL_SLOTS: [EquipSlot; 9] = [
+pub const ALL_SLOTS: [EquipSlot; 10] = [
     EquipSlot::Head,
     EquipSlot::Body,
     EquipSlot::Gloves,
@@ -206,6 +388,7 @@ pub const ALL_SLOTS: [EquipSlot; 9] = [
     EquipSlot::Necklace,
     EquipSlot::Bracelet,
     EquipSlot::Ring,
+    EquipSlot::Facewear,
 ];
 
 pub const GEAR_SLOTS: [EquipSlot; 5] = [
@@ -216,11 +399,13 @@ pub const GEAR_SLOTS: [EquipSlot; 5] = [
     EquipSlot::Feet,
 ];
 
-pub const ACCESSORY_SLOTS: [EquipSlot; 4] = [
+/// 饰品行 (面饰在 UI 上与饰品并列，但模型位于 equipment 目录)
+pub const ACCESSORY_SLOTS: [EquipSlot; 5] = [
     EquipSlot::Earrings,
     EquipSlot::Necklace,
     EquipSlot::Bracelet,
     EquipSlot::Ring,
+    EquipSlot::Facewear,
 ];
 
 // ── 统一物品 ──
@@ -231,6 +416,8 @@ pub const ACCESSORY_SLOTS: [EquipSlot; 4] = [
 pub struct GameItem {
     pub row_id: u32,
     pub name: String,
+    /// 搜索用的小写名称，加载时计算一次，避免每帧 to_lowercase
+    pub search_key: String,
     pub icon_id: u32,
     /// 物品大类 (1=物理武器, 4=防具, 12=素材, 14=房屋, 15=染料, ...)
     pub filter_group: u8,
@@ -238,24 +425,72 @@ pub struct GameItem {
     pub item_ui_category: u8,
     /// 装备槽位分类 (链接到 EquipSlotCategory 表, 0=非装备)
     pub equip_slot_category: u8,
+    /// 是否为面饰: 面饰不使用 EquipSlotCategory，加载时按 ItemUICategory 名称标记
+    pub facewear: bool,
     /// 主模型数据 (低16位=set_id, 次16位=variant_id)
     pub model_main: u64,
     /// 附加数据 (FilterGroup=14 时链接到 HousingExterior 等)
     pub additional_data: u32,
     /// 物品描述
-    pub description: String,
+    pub description: Arc<str>,
     /// NPC 买入价 (收购价)
     pub price_mid: u32,
     /// NPC 卖出价
     pub price_low: u32,
     /// 市场板搜索分类 (>0 表示可在市场板交易)
     pub item_search_category: u8,
+    /// 单格堆叠上限 (1 = 不可堆叠)
+    pub stack_size: u32,
+    /// Item 表中的染色通道数 (旧版表为 IsDyeable 布尔列，无法读取时为 None)
+    pub sheet_dye_count: Option<u8>,
+    /// 性别/种族装备限制
+    pub equip_restriction: EquipRestriction,
+    /// 修理职业 (ClassJob row_id，0 = 不可修理)
+    pub repair_class_job: u8,
+    /// 修理材料 (ItemRepairResource row_id，0 = 无)
+    pub repair_resource: u8,
+    /// 魔晶石孔数
+    pub materia_slot_count: u8,
+    /// 允许禁断镶嵌
+    pub advanced_melding: bool,
+    /// 精制魔晶石类型 (0 = 不可精制)
+    pub materialize_type: u8,
+    /// 基础属性 (BaseParam row_id, 数值)，已去掉空槽
+    pub base_params: Vec<(u8, i16)>,
+    /// 优质品属性加成 (BaseParam row_id, 加成值)，不可 HQ 或表中缺少该列时为空
+    pub hq_base_params: Vec<(u8, i16)>,
+    /// 物理防御力 (Defense{Phys})
+    pub defense_phys: u16,
+    /// 魔法防御力 (Defense{Mag})
+    pub defense_mag: u16,
+    /// 格挡发动力 (盾牌)
+    pub block_rate: u16,
+    /// 格挡性能 (盾牌)
+    pub block: u16,
 }
 
 impl GameItem {
+    /// 名称是否包含搜索词 (`query_lower` 需已转小写，空串视为匹配)
+    pub fn matches_search(&self, query_lower: &str) -> bool {
+        query_lower.is_empty() || self.search_key.contains(query_lower)
+    }
+
+    /// 数量占用的格数，不可堆叠的物品返回 None
+    pub fn stack_count(&self, amount: u32) -> Option<u32> {
+        (self.stack_size > 1).then(|| amount.div_ceil(self.stack_size))
+    }
+
+    /// 数量后附加的堆叠说明，如 " (1 组)"；不可堆叠的物品为空串
+    pub fn stack_suffix(&self, amount: u32) -> String {
+        self.stack_count(amount)
+            .map(|stacks| format!(" ({} 组)", stacks))
+            .unwrap_or_default()
+    }
+
     /// 获取装备槽位 (仅装备类物品有效)
     pub fn equip_slot(&self) -> Option<EquipSlot> {
         EquipSlot::from_category(self.equip_slot_category)
+            .or_else(|| self.facewear.then_some(EquipSlot::Facewear))
     }
 
     /// 是否为装备类物品
@@ -263,6 +498,32 @@ impl GameItem {
         self.equip_slot().is_some() && self.model_main != 0
     }
 
+    /// 属性列表 (BaseParam row_id, 数值, 优质品数值)，防御/格挡专用列排在最前，值为 0 的项不列出
+    ///
+    /// 优质品数值为 None 表示没有 HQ 加成。
+    pub fn stat_lines(&self) -> Vec<(u8, i16, Option<i16>)> {
+        let special = [
+            (BASE_PARAM_DEFENSE, self.defense_phys),
+            (BASE_PARAM_MAGIC_DEFENSE, self.defense_mag),
+            (BASE_PARAM_BLOCK_RATE, self.block_rate),
+            (BASE_PARAM_BLOCK, self.block),
+        ]
+        .into_iter()
+        .filter(|&(_, v)| v > 0)
+        .map(|(param, v)| (param, i16::try_from(v).unwrap_or(i16::MAX)));
+        special
+            .chain(self.base_params.iter().copied())
+            .map(|(param, value)| {
+                let hq = self
+                    .hq_base_params
+                    .iter()
+                    .find(|&&(p, _)| p == param)
+                    .map(|&(_, bonus)| value.saturating_add(bonus));
+                (param, value, hq)
+            })
+            .collect()
+    }
+
     /// 装备 set_id (从 model_main 提取)
     pub fn set_id(&self) -> u16 {
         (self.model_main & 0xFFFF) as u16
@@ -273,11 +534,38 @@ impl GameItem {
         ((self.model_main >> 16) & 0xFFFF) as u16
     }
 
+    /// 可染色通道数 (0/1/2)，None 表示表中无此信息，需要探测材质
+    pub fn dye_count(&self) -> Option<u8> {
+        self.sheet_dye_count
+    }
+
     /// 是否为饰品
     pub fn is_accessory(&self) -> bool {
         self.equip_slot().map_or(false, |s| s.is_accessory())
     }
 
+    /// 装备 ID 前缀: 模型在 accessory 目录为 "a"，equipment 目录为 "e"
+    pub fn model_prefix(&self) -> &'static str {
+        if self.equip_slot().is_some_and(|s| s.uses_accessory_models()) {
+            "a"
+        } else {
+            "e"
+        }
+    }
+
+    /// 是否为替换身体的服装 (占用身体并遮挡头部/手部/腿部/脚部，如全身时装)
+    pub fn replaces_body(&self) -> bool {
+        matches!(self.equip_slot_category, 15 | 16 | 19..=22)
+    }
+
+    /// 是否没有独立模型而是使用角色身体模型 (小衣、"皇帝的新衣" 等)
+    ///
+    /// 这类物品的 model_main 指向小衣或为空，替换身体的服装也不例外；
+    /// 预览时不加载模型，缩略图与幻化预览中视为该槽位无可见网格
+    pub fn uses_body_model(&self) -> bool {
+        self.equip_slot().is_some() && SMALLCLOTHES_SET_IDS.contains(&self.set_id())
+    }
+
     /// 获取默认模型路径 (装备类物品)
     pub fn model_path(&self) -> Option<String> {
         let slot = self.equip_slot()?;
@@ -285,7 +573,9 @@ impl GameItem {
             return None;
         }
         let set_id = self.set_id();
-        Some(if slot.is_accessory() {
+        Some(if slot == EquipSlot::Facewear {
+            facewear_model_path(set_id, "c0201")
+        } else if slot.is_accessory() {
             format!(
                 "chara/accessory/a{:04}/model/c0101a{:04}_{}.mdl",
                 set_id,
@@ -309,7 +599,9 @@ impl GameItem {
             return None;
         }
         let set_id = self.set_id();
-        Some(if slot.is_accessory() {
+        Some(if slot == EquipSlot::Facewear {
+            facewear_model_path(set_id, race_code)
+        } else if slot.is_accessory() {
             format!(
                 "chara/accessory/a{:04}/model/{}a{:04}_{}.mdl",
                 set_id,
@@ -329,6 +621,7 @@ impl GameItem {
     }
 
     /// 获取所有种族的模型路径列表 (装备类物品)
+    /// 面饰多为跨种族通用模型，同样按 RACE_CODES 顺序回退
     pub fn model_paths(&self) -> Vec<String> {
         RACE_CODES
             .iter()
@@ -352,6 +645,13 @@ impl GameItem {
             && ExteriorPartType::from_ui_category(self.item_ui_category).is_some()
     }
 
+    /// 收藏品类别 (乐谱/幻卡)
+    pub fn collection_kind(&self) -> Option<CollectionKind> {
+        COLLECTION_KINDS
+            .into_iter()
+            .find(|kind| kind.filter_group() == self.filter_group)
+    }
+
     /// 是否为庭院家具 (ItemUICategory 73)
     pub fn is_housing_yard(&self) -> bool {
         self.filter_group == 14 && self.item_ui_category == 73
@@ -386,11 +686,165 @@ impl GameItem {
     }
 }
 
+#[cfg(test)]
+impl GameItem {
+    /// 测试用: 只有 row_id 与名称的普通物品，其余字段取零值
+    pub(crate) fn named(row_id: u32, name: &str) -> Self {
+        Self {
+            row_id,
+            name: name.to_string(),
+            search_key: name.to_lowercase(),
+            icon_id: 0,
+            filter_group: 0,
+            item_ui_category: 0,
+            equip_slot_category: 0,
+            facewear: false,
+            model_main: 0,
+            additional_data: 0,
+            description: Arc::from(""),
+            price_mid: 0,
+            price_low: 0,
+            item_search_category: 0,
+            stack_size: 1,
+            sheet_dye_count: None,
+            equip_restriction: EquipRestriction::Unrestricted,
+            repair_class_job: 0,
+            repair_resource: 0,
+            materia_slot_count: 0,
+            advanced_melding: false,
+            materialize_type: 0,
+            base_params: Vec::new(),
+            hq_base_params: Vec::new(),
+            defense_phys: 0,
+            defense_mag: 0,
+            block_rate: 0,
+            block: 0,
+        }
+    }
+}
+
+/// 面饰模型路径: 位于 equipment 目录，使用 met 后缀
+fn facewear_model_path(set_id: u16, race_code: &str) -> String {
+    format!(
+        "chara/equipment/e{:04}/model/{}e{:04}_met.mdl",
+        set_id, race_code, set_id
+    )
+}
+
 pub const RACE_CODES: &[&str] = &[
     "c0201", "c0101", "c0401", "c0301", "c0801", "c0701", "c0601", "c0501", "c1401", "c1301",
     "c1201", "c1101", "c1001", "c0901", "c1801", "c1701", "c1501",
 ];
 
+/// 种族代码对应的 (Race 表 ID, 是否女性)
+/// c01~c04 为人族 (中原/高地)，之后每个种族占一对男女代码
+pub fn race_of_code(race_code: &str) -> Option<(u8, bool)> {
+    let n: u8 = race_code.get(1..3)?.parse().ok()?;
+    let race = match n {
+        1..=4 => 1,
+        5 | 6 => 2,
+        7 | 8 => 4,
+        9 | 10 => 5,
+        11 | 12 => 3,
+        13 | 14 => 6,
+        15 | 16 => 7,
+        17 | 18 => 8,
+        _ => return None,
+    };
+    Some((race, n % 2 == 0))
+}
+
+/// Race 表 ID 对应的种族名
+pub fn race_name(race: u8) -> &'static str {
+    match race {
+        1 => tr!("race.hyur"),
+        2 => tr!("race.elezen"),
+        3 => tr!("race.lalafell"),
+        4 => tr!("race.miqote"),
+        5 => tr!("race.roegadyn"),
+        6 => tr!("race.au_ra"),
+        7 => tr!("race.hrothgar"),
+        8 => tr!("race.viera"),
+        _ => tr!("common.unknown"),
+    }
+}
+
+/// 种族代码的显示名，如 "人族 ♀ (c0201)"
+pub fn race_code_label(race_code: &str) -> String {
+    match race_of_code(race_code) {
+        Some((race, female)) => format!(
+            "{} {} ({})",
+            race_name(race),
+            if female { "♀" } else { "♂" },
+            race_code
+        ),
+        None => race_code.to_string(),
+    }
+}
+
+// ── 装备限制 ──
+
+/// 装备的性别/种族限制 (Item 表 EquipRestriction 列)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EquipRestriction {
+    /// 所有角色可装备 (0/1)
+    #[default]
+    Unrestricted,
+    Male,
+    Female,
+    /// 限定种族与性别 (race 为 Race 表 ID)
+    Race {
+        race: u8,
+        female: bool,
+    },
+}
+
+impl EquipRestriction {
+    /// 原始值: 1=全部, 2=男性, 3=女性, 4 起按 Race 表顺序每个种族一对男/女
+    pub fn from_raw(value: u8) -> Self {
+        match value {
+            2 => Self::Male,
+            3 => Self::Female,
+            4..=19 => Self::Race {
+                race: (value - 2) / 2,
+                female: value % 2 == 1,
+            },
+            _ => Self::Unrestricted,
+        }
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        *self != Self::Unrestricted
+    }
+
+    /// 显示用的限制说明，无限制时为 None
+    pub fn label(&self) -> Option<String> {
+        match self {
+            Self::Unrestricted => None,
+            Self::Male => Some(tr!("restriction.male").to_string()),
+            Self::Female => Some(tr!("restriction.female").to_string()),
+            Self::Race { race, female } => Some(format!(
+                "{} {}",
+                race_name(*race),
+                if *female { "♀" } else { "♂" }
+            )),
+        }
+    }
+
+    /// 指定种族代码的角色能否装备，无法识别的代码视为可装备
+    pub fn allows_race_code(&self, race_code: &str) -> bool {
+        let Some((code_race, code_female)) = race_of_code(race_code) else {
+            return true;
+        };
+        match *self {
+            Self::Unrestricted => true,
+            Self::Male => !code_female,
+            Self::Female => code_female,
+            Self::Race { race, female } => race == code_race && female == code_female,
+        }
+    }
+}
+
 // ── 套装分组 ──
 
 pub struct EquipmentSet {
@@ -402,6 +856,31 @@ pub struct EquipmentSet {
     pub has_accessory: bool,
 }
 
+impl EquipmentSet {
+    /// 套装的代表物品 (在 all_items 中的下标)，见 representative_item_index
+    pub fn representative_item(&self, items: &[GameItem]) -> Option<usize> {
+        representative_item_index(items, &self.item_indices)
+    }
+}
+
+/// 从一组物品中挑选代表物品: 优先身体，其次腿部、头部，再按槽位顺序取其余装备，
+/// 都没有时取第一件饰品
+pub fn representative_item_index(items: &[GameItem], indices: &[usize]) -> Option<usize> {
+    let priority = |slot: Option<EquipSlot>| match slot {
+        Some(EquipSlot::Body) => 0,
+        Some(EquipSlot::Legs) => 1,
+        Some(EquipSlot::Head) => 2,
+        Some(s) if !s.is_accessory() => 3 + s as usize,
+        Some(s) => 100 + s as usize,
+        None => usize::MAX,
+    };
+    indices
+        .iter()
+        .copied()
+        .filter(|&i| items[i].equip_slot().is_some())
+        .min_by_key(|&i| priority(items[i].equip_slot()))
+}
+
 pub fn longest_common_prefix(strings: &[&str]) -> String {
     if strings.is_empty() {
         return String::new();
@@ -466,23 +945,47 @@ pub struct StainEntry {
     pub id: u32,
     pub name: String,
     pub color: [u8; 3],
+    /// 色板分组 (Stain 表的 Shade 列，加载后按配置中的覆盖重新分配)
     pub shade: u8,
 }
 
+/// 已知分组的显示顺序，表中出现的其他分组排在其后
 pub const SHADE_ORDER: &[u8] = &[2, 4, 5, 6, 7, 8, 9, 10, 1];
 
-pub fn shade_group_name(shade: u8) -> &'static str {
-    match shade {
-        2 => "白/灰/黑",
-        4 => "红/粉",
-        5 => "橙/棕",
-        6 => "黄",
-        7 => "绿",
-        8 => "蓝",
-        9 => "紫",
-        10 => "特殊",
-        1 => "其他",
-        _ => "未知",
+pub fn shade_group_name(shade: u8) -> String {
+    let name = match shade {
+        2 => tr!("shade.mono"),
+        4 => tr!("shade.red"),
+        5 => tr!("shade.orange"),
+        6 => tr!("shade.yellow"),
+        7 => tr!("shade.green"),
+        8 => tr!("shade.blue"),
+        9 => tr!("shade.purple"),
+        10 => tr!("shade.special"),
+        1 => tr!("shade.other"),
+        _ => return format!("{}{}", tr!("shade.group"), shade),
+    };
+    name.to_string()
+}
+
+/// 染料实际出现的分组: 先按 SHADE_ORDER，未知分组按编号追加到末尾，不丢弃任何染料
+pub fn shade_groups(stains: &[StainEntry]) -> Vec<u8> {
+    let present: BTreeSet<u8> = stains.iter().map(|s| s.shade).collect();
+    let mut groups: Vec<u8> = SHADE_ORDER
+        .iter()
+        .copied()
+        .filter(|shade| present.contains(shade))
+        .collect();
+    groups.extend(present.iter().filter(|shade| !SHADE_ORDER.contains(*shade)));
+    groups
+}
+
+/// 按用户配置把指定染料移到其他分组 (stain id -> shade)
+pub fn apply_shade_overrides(stains: &mut [StainEntry], overrides: &HashMap<u32, u8>) {
+    for stain in stains {
+        if let Some(&shade) = overrides.get(&stain.id) {
+            stain.shade = shade;
+        }
     }
 }
 
@@ -521,6 +1024,12 @@ pub struct Recipe {
     pub ingredients: Vec<(u32, u8)>,
     /// 秘籍 ID (链接到 SecretRecipeBook 表，0 表示非秘籍配方)
     pub secret_recipe_book: u32,
+    /// HQ 素材可提供的初期品质比例 (%，0 表示不使用或表中缺少该列)
+    pub material_quality_factor: u8,
+    /// 要求的最低品质 (0 表示无要求)
+    pub required_quality: u32,
+    /// 成品可以是 HQ
+    pub can_hq: bool,
 }
 
 /// 合成树节点
@@ -533,57 +1042,167 @@ pub struct CraftTreeNode {
     pub recipe_idx: Option<usize>,
     /// 子节点 (仅当 recipe_idx.is_some() 时有子节点)
     pub children: Vec<CraftTreeNode>,
+    /// 可制作但超出深度上限，按原始素材处理
+    pub truncated: bool,
+    /// 检测到循环引用时的路径 (从首次出现的该物品到自身)，按原始素材处理
+    pub cycle: Option<Vec<u32>>,
+    /// 可制作但子节点尚未构建 (首次展开时由 expand_node 构建)，汇总时视为折叠节点
+    pub unexpanded: bool,
 }
 
+impl CraftTreeNode {
+    /// 已构建的节点数 (含自身)
+    pub fn node_count(&self) -> usize {
+        1 + self.children.iter().map(Self::node_count).sum::<usize>()
+    }
+}
+
+/// 合成树默认展开深度上限
+pub const DEFAULT_CRAFT_TREE_DEPTH: usize = 6;
+
+/// 默认展开的节点深度，更深的节点初始折叠并延迟构建子节点
+pub const CRAFT_TREE_OPEN_DEPTH: usize = 2;
+
 /// 递归构建合成树
 /// recipes: 全部配方列表
 /// item_to_recipes: item_id -> 配方索引列表 (取第一个)
-/// visited: 防止循环引用
+/// max_depth: 展开深度上限 (根节点深度为 0)，超出的可制作节点视为叶子
 pub fn build_craft_tree(
     item_id: u32,
     amount: u32,
     recipes: &[Recipe],
     item_to_recipes: &HashMap<u32, Vec<usize>>,
-    visited: &mut HashSet<u32>,
+    max_depth: usize,
 ) -> CraftTreeNode {
-    // 查找此物品是否有配方
-    let recipe_idx = if !visited.contains(&item_id) {
-        item_to_recipes
-            .get(&item_id)
-            .and_then(|indices| indices.first().copied())
-    } else {
-        None
-    };
+    let mut path = Vec::new();
+    build_craft_subtree(
+        item_id,
+        amount,
+        recipes,
+        item_to_recipes,
+        max_depth,
+        usize::MAX,
+        &mut path,
+    )
+}
 
-    let children = if let Some(idx) = recipe_idx {
-        visited.insert(item_id);
-        let recipe = &recipes[idx];
-        // 计算需要制作几次 (向上取整)
-        let craft_count = (amount as f64 / recipe.result_amount.max(1) as f64).ceil() as u32;
-        let children = recipe
-            .ingredients
-            .iter()
-            .map(|&(ing_id, ing_amount)| {
-                let total = ing_amount as u32 * craft_count;
-                build_craft_tree(ing_id, total, recipes, item_to_recipes, visited)
-            })
-            .collect();
-        visited.remove(&item_id);
-        children
-    } else {
-        Vec::new()
-    };
+/// 延迟构建合成树: 只构建默认展开的部分 (深度 <= CRAFT_TREE_OPEN_DEPTH 的节点的子节点)，
+/// 更深的可制作节点标记为 unexpanded，展开时再调用 expand_node
+pub fn build_craft_tree_lazy(
+    item_id: u32,
+    amount: u32,
+    recipes: &[Recipe],
+    item_to_recipes: &HashMap<u32, Vec<usize>>,
+    max_depth: usize,
+) -> CraftTreeNode {
+    let mut path = Vec::new();
+    build_craft_subtree(
+        item_id,
+        amount,
+        recipes,
+        item_to_recipes,
+        max_depth,
+        CRAFT_TREE_OPEN_DEPTH + 1,
+        &mut path,
+    )
+}
+
+/// 构建 unexpanded 节点的下一层子节点 (子节点本身仍为 unexpanded)
+/// ancestors: 从根到该节点父级的物品链，用于检测循环引用
+pub fn expand_node(
+    node: &mut CraftTreeNode,
+    ancestors: &[u32],
+    recipes: &[Recipe],
+    item_to_recipes: &HashMap<u32, Vec<usize>>,
+    max_depth: usize,
+) {
+    if !node.unexpanded {
+        return;
+    }
+    let mut path = ancestors.to_vec();
+    *node = build_craft_subtree(
+        node.item_id,
+        node.amount_needed,
+        recipes,
+        item_to_recipes,
+        max_depth,
+        ancestors.len() + 1,
+        &mut path,
+    );
+}
 
-    CraftTreeNode {
+/// path: 从根到当前节点父级的物品链，用于检测循环引用
+/// eager_depth: 深度达到该值的可制作节点不构建子节点，标记为 unexpanded
+fn build_craft_subtree(
+    item_id: u32,
+    amount: u32,
+    recipes: &[Recipe],
+    item_to_recipes: &HashMap<u32, Vec<usize>>,
+    max_depth: usize,
+    eager_depth: usize,
+    path: &mut Vec<u32>,
+) -> CraftTreeNode {
+    let mut node = CraftTreeNode {
         item_id,
         amount_needed: amount,
-        recipe_idx,
-        children,
+        recipe_idx: None,
+        children: Vec::new(),
+        truncated: false,
+        cycle: None,
+        unexpanded: false,
+    };
+
+    // 查找此物品是否有配方
+    let Some(idx) = item_to_recipes
+        .get(&item_id)
+        .and_then(|indices| indices.first().copied())
+    else {
+        return node;
+    };
+    if let Some(start) = path.iter().position(|&id| id == item_id) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(item_id);
+        node.cycle = Some(cycle);
+        return node;
+    }
+    if path.len() >= max_depth {
+        node.truncated = true;
+        return node;
+    }
+    if path.len() >= eager_depth {
+        node.recipe_idx = Some(idx);
+        node.unexpanded = true;
+        return node;
     }
+
+    path.push(item_id);
+    let recipe = &recipes[idx];
+    // 计算需要制作几次 (向上取整)
+    let craft_count = (amount as f64 / recipe.result_amount.max(1) as f64).ceil() as u32;
+    node.children = recipe
+        .ingredients
+        .iter()
+        .map(|&(ing_id, ing_amount)| {
+            let total = ing_amount as u32 * craft_count;
+            build_craft_subtree(
+                ing_id,
+                total,
+                recipes,
+                item_to_recipes,
+                max_depth,
+                eager_depth,
+                path,
+            )
+        })
+        .collect();
+    path.pop();
+    node.recipe_idx = Some(idx);
+    node
 }
 
 /// 感知折叠状态的素材汇总
-/// collapsed 中的 (item_id, depth) 对应的节点视为叶子 (不展开子配方)
+/// collapsed 中的 (item_id, depth) 对应的节点视为叶子 (不展开子配方)，
+/// 尚未构建子节点的 unexpanded 节点同样视为叶子
 pub fn summarize_materials_with_collapsed(
     node: &CraftTreeNode,
     collapsed: &HashSet<(u32, usize)>,
@@ -623,6 +1242,104 @@ pub fn total_amount_in_tree(
     total
 }
 
+/// 勾选了 HQ 的叶子素材的需求量 (感知折叠状态)，按物品汇总
+/// hq_ingredients: (父节点物品 ID, 素材物品 ID)，同一素材在不同配方下可分别勾选
+pub fn summarize_hq_leaves(
+    node: &CraftTreeNode,
+    collapsed: &HashSet<(u32, usize)>,
+    hq_ingredients: &HashSet<(u32, u32)>,
+) -> HashMap<u32, u32> {
+    fn walk(
+        node: &CraftTreeNode,
+        parent_id: u32,
+        depth: usize,
+        collapsed: &HashSet<(u32, usize)>,
+        hq_ingredients: &HashSet<(u32, u32)>,
+        map: &mut HashMap<u32, u32>,
+    ) {
+        if node.children.is_empty() || collapsed.contains(&(node.item_id, depth)) {
+            if hq_ingredients.contains(&(parent_id, node.item_id)) {
+                *map.entry(node.item_id).or_insert(0) += node.amount_needed;
+            }
+        } else {
+            for child in &node.children {
+                walk(
+                    child,
+                    node.item_id,
+                    depth + 1,
+                    collapsed,
+                    hq_ingredients,
+                    map,
+                );
+            }
+        }
+    }
+    let mut map = HashMap::new();
+    if !hq_ingredients.is_empty() {
+        walk(node, 0, 0, collapsed, hq_ingredients, &mut map);
+    }
+    map
+}
+
+/// 制作计划中的一步: 某个中间产物 (或合成目标) 的全部制作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CraftStep {
+    pub item_id: u32,
+    pub recipe_idx: usize,
+    /// 整棵树中对该物品的总需求量
+    pub amount: u32,
+    /// 按配方产出数量换算的制作次数
+    pub crafts: u32,
+}
+
+/// 按制作顺序列出合成树中展开的可制作节点 (感知折叠状态): 同一物品合并为一步，
+/// 先做最深处的中间产物，合成目标在最后
+pub fn craft_plan_steps(
+    node: &CraftTreeNode,
+    collapsed: &HashSet<(u32, usize)>,
+    recipes: &[Recipe],
+) -> Vec<CraftStep> {
+    // item_id -> (recipe_idx, 总需求量, 最大深度)
+    fn walk(
+        node: &CraftTreeNode,
+        depth: usize,
+        collapsed: &HashSet<(u32, usize)>,
+        map: &mut HashMap<u32, (usize, u32, usize)>,
+    ) {
+        let Some(recipe_idx) = node.recipe_idx else {
+            return;
+        };
+        if node.children.is_empty() || collapsed.contains(&(node.item_id, depth)) {
+            return;
+        }
+        let entry = map.entry(node.item_id).or_insert((recipe_idx, 0, depth));
+        entry.1 += node.amount_needed;
+        entry.2 = entry.2.max(depth);
+        for child in &node.children {
+            walk(child, depth + 1, collapsed, map);
+        }
+    }
+    let mut map = HashMap::new();
+    walk(node, 0, collapsed, &mut map);
+    let mut steps: Vec<(usize, CraftStep)> = map
+        .into_iter()
+        .map(|(item_id, (recipe_idx, amount, depth))| {
+            let per_craft = recipes
+                .get(recipe_idx)
+                .map_or(1, |r| r.result_amount.max(1) as u32);
+            let step = CraftStep {
+                item_id,
+                recipe_idx,
+                amount,
+                crafts: amount.div_ceil(per_craft),
+            };
+            (depth, step)
+        })
+        .collect();
+    steps.sort_by(|(da, a), (db, b)| db.cmp(da).then(a.item_id.cmp(&b.item_id)));
+    steps.into_iter().map(|(_, step)| step).collect()
+}
+
 fn collect_leaves_collapsed(
     node: &CraftTreeNode,
     depth: usize,
@@ -641,6 +1358,11 @@ fn collect_leaves_collapsed(
 
 // ── 物品来源 ──
 
+/// 大国防联军 id -> 军票物品 id (1 黑涡团 = 20, 2 双蛇党 = 21, 3 恒辉队 = 22)
+pub fn gc_seal_item_id(gc_id: u8) -> u32 {
+    19 + gc_id as u32
+}
+
 /// 物品获取来源
 #[derive(Debug, Clone)]
 pub enum ItemSource {
@@ -648,10 +1370,15 @@ pub enum ItemSource {
     GilShop {
         shop_name: String,
         npc_location: Option<String>,
+        /// NPC 所在区域中最近的以太之光 (区域内没有以太之光时为 None)
+        nearest_aetheryte: Option<String>,
     },
     /// 特殊兑换 (诗学/军票/代币等)
     SpecialShop {
         shop_name: String,
+        /// 提供该兑换的 NPC (军票商店等没有 NPC 关联时为 None)
+        npc_name: Option<String>,
+        npc_location: Option<String>,
         cost_item_id: u32,
         cost_count: u32,
     },
@@ -662,9 +1389,9 @@ pub enum ItemSource {
 impl ItemSource {
     pub fn label(&self) -> &str {
         match self {
-            Self::GilShop { .. } => "金币商店",
-            Self::SpecialShop { .. } => "兑换",
-            Self::Gathering => "采集",
+            Self::GilShop { .. } => tr!("source.gil_shop"),
+            Self::SpecialShop { .. } => tr!("source.special_shop"),
+            Self::Gathering => tr!("source.gathering"),
         }
     }
 
@@ -736,3 +1463,577 @@ pub fn resolve_source<'a>(
         None => default_source_index(sources).and_then(|i| sources.get(i)),
     }
 }
+
+/// 素材汇总的来源分组 (排序即显示顺序)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MaterialGroupKey {
+    GilShop,
+    /// 兑换，按消耗的代币物品 ID 区分
+    Token(u32),
+    Gathering,
+    Ignored,
+    NoSource,
+}
+
+pub struct MaterialGroup {
+    pub key: MaterialGroupKey,
+    /// (item_id, 数量)
+    pub items: Vec<(u32, u32)>,
+    /// 金币商店为金币总价，兑换为代币总数，其他分组为 0
+    pub subtotal: u64,
+}
+
+/// 按解析后的来源对素材分组，小计规则与汇总费用一致
+///
+/// `unit_price`: item_id -> 金币单价
+pub fn group_materials_by_source(
+    materials: &[(u32, u32)],
+    item_sources: &HashMap<u32, Vec<ItemSource>>,
+    overrides: &HashMap<u32, SourceChoice>,
+    unit_price: impl Fn(u32) -> u32,
+) -> Vec<MaterialGroup> {
+    let mut groups: BTreeMap<MaterialGroupKey, MaterialGroup> = BTreeMap::new();
+    for &(item_id, amount) in materials {
+        let (key, cost) = if matches!(overrides.get(&item_id), Some(SourceChoice::Ignore)) {
+            (MaterialGroupKey::Ignored, 0)
+        } else {
+            let sources = item_sources
+                .get(&item_id)
+                .map(|v| v.as_slice())
+                .unwrap_or(&[]);
+            match resolve_source(item_id, sources, overrides) {
+                Some(ItemSource::GilShop { .. }) => (
+                    MaterialGroupKey::GilShop,
+                    unit_price(item_id) as u64 * amount as u64,
+                ),
+                Some(ItemSource::SpecialShop {
+                    cost_item_id,
+                    cost_count,
+                    ..
+                }) => (
+                    MaterialGroupKey::Token(*cost_item_id),
+                    *cost_count as u64 * amount as u64,
+                ),
+                Some(ItemSource::Gathering) => (MaterialGroupKey::Gathering, 0),
+                None => (MaterialGroupKey::NoSource, 0),
+            }
+        };
+        let group = groups.entry(key).or_insert_with(|| MaterialGroup {
+            key,
+            items: Vec::new(),
+            subtotal: 0,
+        });
+        group.items.push((item_id, amount));
+        group.subtotal += cost;
+    }
+    groups.into_values().collect()
+}
+
+/// 按解析后来源的某个属性聚类素材，按属性排序，没有该属性的素材排在最后
+fn cluster_materials<'a, K: Ord>(
+    items: &[(u32, u32)],
+    item_sources: &'a HashMap<u32, Vec<ItemSource>>,
+    overrides: &HashMap<u32, SourceChoice>,
+    key: impl Fn(&'a ItemSource) -> Option<K>,
+) -> Vec<(Option<K>, Vec<(u32, u32)>)> {
+    let mut clusters: BTreeMap<(bool, Option<K>), Vec<(u32, u32)>> = BTreeMap::new();
+    for &(item_id, amount) in items {
+        let sources = item_sources
+            .get(&item_id)
+            .map(|v| v.as_slice())
+            .unwrap_or(&[]);
+        let k = resolve_source(item_id, sources, overrides).and_then(&key);
+        clusters
+            .entry((k.is_none(), k))
+            .or_default()
+            .push((item_id, amount));
+    }
+    clusters
+        .into_iter()
+        .map(|((_, k), items)| (k, items))
+        .collect()
+}
+
+/// 把金币商店分组的素材按最近传送点聚类，同一传送点附近的购买排在一起
+///
+/// 返回 (传送点名, 素材)，按传送点名排序，没有传送点信息的素材排在最后
+pub fn cluster_by_aetheryte<'a>(
+    items: &[(u32, u32)],
+    item_sources: &'a HashMap<u32, Vec<ItemSource>>,
+    overrides: &HashMap<u32, SourceChoice>,
+) -> Vec<(Option<&'a str>, Vec<(u32, u32)>)> {
+    cluster_materials(items, item_sources, overrides, |source| match source {
+        ItemSource::GilShop {
+            nearest_aetheryte, ..
+        } => nearest_aetheryte.as_deref(),
+        _ => None,
+    })
+}
+
+/// 把兑换分组的素材按兑换 NPC 聚类，同一 NPC 处的兑换排在一起
+///
+/// 返回 ((NPC 名, 所在区域), 素材)，按 NPC 名排序，没有 NPC 信息的素材排在最后
+#[allow(clippy::type_complexity)]
+pub fn cluster_by_shop_npc<'a>(
+    items: &[(u32, u32)],
+    item_sources: &'a HashMap<u32, Vec<ItemSource>>,
+    overrides: &HashMap<u32, SourceChoice>,
+) -> Vec<(Option<(&'a str, Option<&'a str>)>, Vec<(u32, u32)>)> {
+    cluster_materials(items, item_sources, overrides, |source| match source {
+        ItemSource::SpecialShop {
+            npc_name: Some(name),
+            npc_location,
+            ..
+        } => Some((name.as_str(), npc_location.as_deref())),
+        _ => None,
+    })
+}
+
+/// 按默认来源估算的制作成本 (合成树根节点需求量的总成本)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CraftCostEstimate {
+    /// 金币商店素材的金币总价
+    pub gil: u64,
+    /// 是否所有原始素材都能以金币计价 (含采集/兑换/无来源素材时为 false)
+    pub complete: bool,
+}
+
+/// 以默认来源 (不考虑用户覆盖与折叠状态) 估算合成树的金币成本
+///
+/// `unit_price`: item_id -> 金币单价
+pub fn estimate_default_craft_cost(
+    tree: &CraftTreeNode,
+    item_sources: &HashMap<u32, Vec<ItemSource>>,
+    unit_price: impl Fn(u32) -> u32,
+) -> CraftCostEstimate {
+    let materials = summarize_materials_with_collapsed(tree, &HashSet::new());
+    let mut estimate = CraftCostEstimate {
+        gil: 0,
+        complete: true,
+    };
+    for (item_id, amount) in materials {
+        let sources = item_sources
+            .get(&item_id)
+            .map(|v| v.as_slice())
+            .unwrap_or(&[]);
+        match default_source_index(sources).map(|i| &sources[i]) {
+            Some(ItemSource::GilShop { .. }) => {
+                estimate.gil += unit_price(item_id) as u64 * amount as u64;
+            }
+            _ => estimate.complete = false,
+        }
+    }
+    estimate
+}
+
+/// 估算制作成本所需的数据快照: 只含从根物品可达的配方、来源与单价，
+/// 可以移到后台线程构建完整合成树，避免深层配方阻塞界面
+#[derive(Debug, Clone, Default)]
+pub struct CraftCostSnapshot {
+    recipes: Vec<Recipe>,
+    item_to_recipes: HashMap<u32, Vec<usize>>,
+    item_sources: HashMap<u32, Vec<ItemSource>>,
+    prices: HashMap<u32, u32>,
+}
+
+impl CraftCostSnapshot {
+    /// 按物品遍历配方图 (每个物品只访问一次，与合成树的路径数无关)，复制可达部分
+    pub fn collect(
+        root_item_id: u32,
+        recipes: &[Recipe],
+        item_to_recipes: &HashMap<u32, Vec<usize>>,
+        item_sources: &HashMap<u32, Vec<ItemSource>>,
+        unit_price: impl Fn(u32) -> u32,
+    ) -> Self {
+        let mut snapshot = Self::default();
+        let mut visited = HashSet::from([root_item_id]);
+        let mut queue = VecDeque::from([root_item_id]);
+        while let Some(item_id) = queue.pop_front() {
+            if let Some(sources) = item_sources.get(&item_id) {
+                snapshot.item_sources.insert(item_id, sources.clone());
+            }
+            snapshot.prices.insert(item_id, unit_price(item_id));
+            // 合成树只使用每个物品的第一个配方
+            let Some(recipe) = item_to_recipes
+                .get(&item_id)
+                .and_then(|indices| indices.first())
+                .and_then(|&idx| recipes.get(idx))
+            else {
+                continue;
+            };
+            snapshot
+                .item_to_recipes
+                .insert(item_id, vec![snapshot.recipes.len()]);
+            snapshot.recipes.push(recipe.clone());
+            for &(ing_id, _) in &recipe.ingredients {
+                if visited.insert(ing_id) {
+                    queue.push_back(ing_id);
+                }
+            }
+        }
+        snapshot
+    }
+
+    /// 构建完整合成树并按默认来源估算成本，结果与直接在全部数据上计算相同
+    pub fn estimate(&self, root_item_id: u32, amount: u32, max_depth: usize) -> CraftCostEstimate {
+        let tree = build_craft_tree(
+            root_item_id,
+            amount,
+            &self.recipes,
+            &self.item_to_recipes,
+            max_depth,
+        );
+        estimate_default_craft_cost(&tree, &self.item_sources, |id| {
+            self.prices.get(&id).copied().unwrap_or(0)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(result_item_id: u32, result_amount: u8, ingredients: &[(u32, u8)]) -> Recipe {
+        Recipe {
+            row_id: result_item_id,
+            result_item_id,
+            result_amount,
+            craft_type: 0,
+            recipe_level_table_id: 0,
+            ingredients: ingredients.to_vec(),
+            secret_recipe_book: 0,
+            material_quality_factor: 0,
+            required_quality: 0,
+            can_hq: false,
+        }
+    }
+
+    fn recipe_index(recipes: &[Recipe]) -> HashMap<u32, Vec<usize>> {
+        let mut map: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (idx, recipe) in recipes.iter().enumerate() {
+            map.entry(recipe.result_item_id).or_default().push(idx);
+        }
+        map
+    }
+
+    /// 物品 1 -> 2 -> ... -> len+1 的单链配方，每步需要 2 个下一级素材
+    fn chain(len: u32) -> Vec<Recipe> {
+        (1..=len).map(|id| recipe(id, 1, &[(id + 1, 2)])).collect()
+    }
+
+    #[test]
+    fn craft_tree_multiplies_amounts_and_rounds_up_crafts() {
+        let recipes = vec![recipe(1, 3, &[(2, 2), (3, 1)])];
+        let tree = build_craft_tree(1, 4, &recipes, &recipe_index(&recipes), 6);
+        // 产出 3 个，需要 4 个 → 制作 2 次
+        let children: Vec<(u32, u32)> = tree
+            .children
+            .iter()
+            .map(|c| (c.item_id, c.amount_needed))
+            .collect();
+        assert_eq!(children, [(2, 4), (3, 2)]);
+        assert_eq!(
+            summarize_materials_with_collapsed(&tree, &HashSet::new()),
+            [(2, 4), (3, 2)]
+        );
+    }
+
+    #[test]
+    fn craft_tree_records_cycle_path() {
+        // A(1) 需要 B(2)，B 又需要 A
+        let recipes = vec![recipe(1, 1, &[(2, 1)]), recipe(2, 1, &[(1, 1)])];
+        let tree = build_craft_tree(1, 1, &recipes, &recipe_index(&recipes), 6);
+        assert!(tree.cycle.is_none());
+        let b = &tree.children[0];
+        assert_eq!(b.item_id, 2);
+        assert!(b.cycle.is_none());
+        let a_again = &b.children[0];
+        assert_eq!(a_again.item_id, 1);
+        assert_eq!(a_again.cycle.as_deref(), Some(&[1, 2, 1][..]));
+        assert!(a_again.children.is_empty());
+        assert_eq!(
+            summarize_materials_with_collapsed(&tree, &HashSet::new()),
+            [(1, 1)]
+        );
+    }
+
+    #[test]
+    fn craft_tree_stops_at_depth_limit() {
+        let recipes = chain(10);
+        let tree = build_craft_tree(
+            1,
+            1,
+            &recipes,
+            &recipe_index(&recipes),
+            DEFAULT_CRAFT_TREE_DEPTH,
+        );
+        let mut node = &tree;
+        for depth in 0..DEFAULT_CRAFT_TREE_DEPTH {
+            assert!(!node.truncated, "深度 {} 不应截断", depth);
+            assert_eq!(node.children.len(), 1);
+            node = &node.children[0];
+        }
+        // 深度 6 的节点仍可制作，但作为叶子按原始素材统计
+        assert_eq!(node.item_id, 7);
+        assert!(node.truncated);
+        assert!(node.children.is_empty());
+        assert_eq!(
+            summarize_materials_with_collapsed(&tree, &HashSet::new()),
+            [(7, 64)]
+        );
+    }
+
+    #[test]
+    fn lazy_craft_tree_expands_to_same_tree() {
+        let recipes = chain(4);
+        let index = recipe_index(&recipes);
+        let full = build_craft_tree(1, 1, &recipes, &index, DEFAULT_CRAFT_TREE_DEPTH);
+        let mut lazy = build_craft_tree_lazy(1, 1, &recipes, &index, DEFAULT_CRAFT_TREE_DEPTH);
+
+        let mut ancestors = Vec::new();
+        let mut node = &mut lazy;
+        while !node.children.is_empty() || node.unexpanded {
+            expand_node(node, &ancestors, &recipes, &index, DEFAULT_CRAFT_TREE_DEPTH);
+            ancestors.push(node.item_id);
+            node = &mut node.children[0];
+        }
+        assert_eq!(
+            summarize_materials_with_collapsed(&lazy, &HashSet::new()),
+            summarize_materials_with_collapsed(&full, &HashSet::new())
+        );
+    }
+
+    fn gil_shop() -> ItemSource {
+        ItemSource::GilShop {
+            shop_name: "shop".to_string(),
+            npc_location: None,
+            nearest_aetheryte: None,
+        }
+    }
+
+    fn token_shop(cost_item_id: u32, cost_count: u32) -> ItemSource {
+        ItemSource::SpecialShop {
+            shop_name: "exchange".to_string(),
+            npc_name: None,
+            npc_location: None,
+            cost_item_id,
+            cost_count,
+        }
+    }
+
+    #[test]
+    fn materials_group_by_resolved_source() {
+        let item_sources = HashMap::from([
+            (1, vec![token_shop(28, 5), gil_shop()]),
+            (2, vec![gil_shop()]),
+            (3, vec![token_shop(28, 2)]),
+            (4, vec![token_shop(29, 1), ItemSource::Gathering]),
+            (5, vec![gil_shop()]),
+        ]);
+        let overrides = HashMap::from([
+            // 放弃默认的金币商店，改用第 0 个来源 (兑换)
+            (1, SourceChoice::Index(0)),
+            (5, SourceChoice::Ignore),
+        ]);
+        let materials = [(1, 2), (2, 3), (3, 4), (4, 1), (5, 7), (6, 9)];
+        let groups = group_materials_by_source(&materials, &item_sources, &overrides, |id| id * 10);
+
+        let summary: Vec<(MaterialGroupKey, Vec<(u32, u32)>, u64)> = groups
+            .into_iter()
+            .map(|g| (g.key, g.items, g.subtotal))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (MaterialGroupKey::GilShop, vec![(2, 3)], 60),
+                (MaterialGroupKey::Token(28), vec![(1, 2), (3, 4)], 18),
+                (MaterialGroupKey::Gathering, vec![(4, 1)], 0),
+                (MaterialGroupKey::Ignored, vec![(5, 7)], 0),
+                (MaterialGroupKey::NoSource, vec![(6, 9)], 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn out_of_range_override_has_no_source() {
+        let item_sources = HashMap::from([(1, vec![gil_shop()])]);
+        let overrides = HashMap::from([(1, SourceChoice::Index(3))]);
+        let groups = group_materials_by_source(&[(1, 1)], &item_sources, &overrides, |_| 100);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].key, MaterialGroupKey::NoSource);
+        assert_eq!(groups[0].subtotal, 0);
+    }
+
+    fn stain(id: u32, shade: u8) -> StainEntry {
+        StainEntry {
+            id,
+            name: format!("stain {}", id),
+            color: [0, 0, 0],
+            shade,
+        }
+    }
+
+    #[test]
+    fn shade_groups_follow_display_order() {
+        let stains = [
+            stain(1, 1),
+            stain(2, 8),
+            stain(3, 2),
+            stain(4, 8),
+            stain(5, 4),
+        ];
+        assert_eq!(shade_groups(&stains), [2, 4, 8, 1]);
+        assert!(shade_groups(&[]).is_empty());
+    }
+
+    #[test]
+    fn unknown_shades_are_appended_not_dropped() {
+        let stains = [stain(1, 15), stain(2, 2), stain(3, 12), stain(4, 0)];
+        assert_eq!(shade_groups(&stains), [2, 0, 12, 15]);
+    }
+
+    #[test]
+    fn shade_overrides_move_listed_stains_only() {
+        let mut stains = vec![stain(1, 2), stain(2, 4), stain(3, 5)];
+        let overrides = HashMap::from([(2, 10), (99, 7)]);
+        apply_shade_overrides(&mut stains, &overrides);
+        let shades: Vec<u8> = stains.iter().map(|s| s.shade).collect();
+        assert_eq!(shades, [2, 10, 5]);
+        assert_eq!(shade_groups(&stains), [2, 5, 10]);
+    }
+
+    fn equipment(equip_slot_category: u8, model_main: u64) -> GameItem {
+        GameItem {
+            equip_slot_category,
+            model_main,
+            ..GameItem::named(1, "test")
+        }
+    }
+
+    #[test]
+    fn smallclothes_use_body_model() {
+        // 小衣: set 9903 variant 1
+        assert!(equipment(4, 0x0001_26AF).uses_body_model());
+        assert!(equipment(7, 0x0001_26AF).uses_body_model());
+        // "皇帝的新衣": set 0，model_main 只有 variant 或全为 0
+        assert!(equipment(3, 0x0001_0000).uses_body_model());
+        assert!(equipment(8, 0).uses_body_model());
+        // 替换身体的服装同样适用
+        assert!(equipment(15, 0x0001_26AF).uses_body_model());
+    }
+
+    #[test]
+    fn regular_gear_has_own_model() {
+        assert!(!equipment(4, 0x0001_1780).uses_body_model());
+        assert!(!equipment(15, 0x0001_1780).uses_body_model());
+        assert!(!equipment(12, 0x0001_0001).uses_body_model());
+        // 非装备物品不参与判断，即使 model_main 的低位恰好是 0 或 9903
+        assert!(!equipment(0, 0).uses_body_model());
+        assert!(!equipment(0, 0x0001_26AF).uses_body_model());
+    }
+
+    #[test]
+    fn lazy_craft_tree_builds_only_open_levels() {
+        // 二叉配方树: 物品 n 由 2n 与 2n+1 制作，63 个节点共 6 层
+        let recipes: Vec<Recipe> = (1..32)
+            .map(|n| recipe(n, 1, &[(2 * n, 1), (2 * n + 1, 1)]))
+            .collect();
+        let index = recipe_index(&recipes);
+        let full = build_craft_tree(1, 1, &recipes, &index, DEFAULT_CRAFT_TREE_DEPTH);
+        assert_eq!(full.node_count(), 63);
+
+        // 深度 0..=CRAFT_TREE_OPEN_DEPTH 的节点构建子节点，第 3 层的 8 个节点等待展开
+        let mut lazy = build_craft_tree_lazy(1, 1, &recipes, &index, DEFAULT_CRAFT_TREE_DEPTH);
+        assert_eq!(lazy.node_count(), 15);
+        let deepest = &lazy.children[0].children[0].children[0];
+        assert!(deepest.unexpanded && deepest.children.is_empty());
+
+        expand_node(
+            &mut lazy.children[0].children[0].children[0],
+            &[1, 2, 4],
+            &recipes,
+            &index,
+            DEFAULT_CRAFT_TREE_DEPTH,
+        );
+        assert_eq!(lazy.node_count(), 17);
+    }
+
+    #[test]
+    fn facewear_category_is_looked_up_by_name() {
+        let names = HashMap::from([(34, "头部防具".to_string()), (105, "面饰".to_string())]);
+        assert_eq!(facewear_ui_category(&names), Some(105));
+        assert_eq!(facewear_ui_category(&HashMap::new()), None);
+    }
+
+    #[test]
+    fn slot_lists_agree_on_accessories() {
+        for slot in ALL_SLOTS {
+            assert_eq!(
+                slot.is_accessory(),
+                ACCESSORY_SLOTS.contains(&slot),
+                "{slot:?}"
+            );
+            assert_ne!(slot.is_accessory(), GEAR_SLOTS.contains(&slot), "{slot:?}");
+        }
+        assert!(!EquipSlot::Facewear.uses_accessory_models());
+        assert!(EquipSlot::Ring.uses_accessory_models());
+    }
+
+    #[test]
+    fn facewear_models_live_in_equipment_directory() {
+        let glasses = GameItem {
+            facewear: true,
+            ..equipment(0, 0x0001_0010)
+        };
+        assert_eq!(glasses.equip_slot(), Some(EquipSlot::Facewear));
+        assert!(glasses.is_accessory());
+        assert_eq!(glasses.model_prefix(), "e");
+        assert_eq!(
+            glasses.model_path_for_race("c1101").as_deref(),
+            Some("chara/equipment/e0016/model/c1101e0016_met.mdl")
+        );
+        assert_eq!(equipment(12, 0x0001_0010).model_prefix(), "a");
+        assert_eq!(equipment(0, 0x0001_0010).equip_slot(), None);
+    }
+
+    #[test]
+    fn craft_cost_follows_root_quantity_with_rounded_crafts() {
+        // 物品 1 单次产出 3 个，每次需要 2 个物品 2 (商店单价 10)
+        let recipes = vec![recipe(1, 3, &[(2, 2)])];
+        let index = recipe_index(&recipes);
+        let item_sources = HashMap::from([(2, vec![gil_shop()])]);
+        let cost = |quantity| {
+            let tree = build_craft_tree(1, quantity, &recipes, &index, 6);
+            estimate_default_craft_cost(&tree, &item_sources, |_| 10)
+        };
+        assert_eq!(cost(1).gil, 20);
+        assert_eq!(cost(3).gil, 20);
+        // 4 个需要制作 2 次，不是单份成本的 4 倍
+        assert_eq!(cost(4).gil, 40);
+        assert!(cost(4).complete);
+    }
+
+    #[test]
+    fn cost_snapshot_copies_reachable_recipes_only() {
+        // 1 -> (2, 3)，2 -> 3，物品 9 的配方与根物品无关
+        let recipes = vec![
+            recipe(9, 1, &[(3, 1)]),
+            recipe(1, 2, &[(2, 3), (3, 1)]),
+            recipe(2, 1, &[(3, 2)]),
+        ];
+        let index = recipe_index(&recipes);
+        let item_sources = HashMap::from([(3, vec![gil_shop()]), (2, vec![token_shop(28, 5)])]);
+        let price = |id: u32| id * 10;
+        let snapshot = CraftCostSnapshot::collect(1, &recipes, &index, &item_sources, price);
+        assert_eq!(snapshot.recipes.len(), 2);
+        assert!(!snapshot.item_to_recipes.contains_key(&9));
+
+        for quantity in [1, 3, 5] {
+            let tree = build_craft_tree(1, quantity, &recipes, &index, 6);
+            let expected = estimate_default_craft_cost(&tree, &item_sources, price);
+            assert_eq!(snapshot.estimate(1, quantity, 6), expected);
+        }
+        // 深度上限截断时可制作素材按自身来源计价 (兑换，不完整)
+        assert!(!snapshot.estimate(1, 1, 1).complete);
+    }
+}
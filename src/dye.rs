@@ -3,64 +3,463 @@ use std::collections::HashMap;
 use physis::mtrl::{ColorDyeTable, ColorTable};
 use physis::stm::StainingTemplate;
 
-use crate::game::CachedMaterial;
+use tomestone_render::{ModelRenderer, TextureData};
 
+use crate::game::{bake_color_table_texture, resolve_color_table_rows, CachedMaterial};
+
+/// 颜色表单行的颜色 (线性空间)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RowColors {
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    pub emissive: [f32; 3],
+}
+
+/// 染色表单行: 使用的模板、染色通道与受影响的颜色
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowDye {
+    pub template: u16,
+    /// 染色通道 (0/1)，旧版染色表只有通道 0
+    pub channel: usize,
+    pub diffuse: bool,
+    pub specular: bool,
+    pub emissive: bool,
+}
+
+impl RowDye {
+    /// 该行是否有任何颜色受染色影响
+    pub fn affects_any(&self) -> bool {
+        self.diffuse || self.specular || self.emissive
+    }
+}
+
+/// 颜色表单行在给定染料下的结果
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DyedRow {
+    pub undyed: RowColors,
+    pub dyed: RowColors,
+    /// 染色表中对应的行，没有染色表 (或类型不匹配) 时为 None
+    pub dye: Option<RowDye>,
+}
+
+/// 单行染色: 对染色表标记的颜色替换为染料包中的值，染料包缺失时保持原色
+pub fn dye_row(
+    colors: RowColors,
+    dye: Option<&RowDye>,
+    stm: &StainingTemplate,
+    stain_ids: [u32; 2],
+) -> RowColors {
+    dye_row_with(colors, dye, stain_ids, |template, stain_index| {
+        stm_dye_pack(stm, template, stain_index)
+    })
+}
+
+/// 染色模板中某个染料 (Stain row_id - 1) 的颜色
+fn stm_dye_pack(stm: &StainingTemplate, template: u16, stain_index: usize) -> Option<RowColors> {
+    let pack = stm.get_dye_pack(template, stain_index)?;
+    Some(RowColors {
+        diffuse: pack.diffuse,
+        specular: pack.specular,
+        emissive: pack.emissive,
+    })
+}
+
+/// [`dye_row`] 的实现，染料包由 `dye_pack(模板, 染料下标)` 提供
+fn dye_row_with(
+    colors: RowColors,
+    dye: Option<&RowDye>,
+    stain_ids: [u32; 2],
+    dye_pack: impl Fn(u16, usize) -> Option<RowColors>,
+) -> RowColors {
+    let Some(dye) = dye else {
+        return colors;
+    };
+    let stain_id = stain_ids[dye.channel.min(1)];
+    if stain_id == 0 {
+        return colors;
+    }
+    let Some(pack) = dye_pack(dye.template, (stain_id - 1) as usize) else {
+        return colors;
+    };
+    let pick = |dyed: bool, from_pack: [f32; 3], original: [f32; 3]| {
+        if dyed {
+            from_pack
+        } else {
+            original
+        }
+    };
+    RowColors {
+        diffuse: pick(dye.diffuse, pack.diffuse, colors.diffuse),
+        specular: pick(dye.specular, pack.specular, colors.specular),
+        emissive: pick(dye.emissive, pack.emissive, colors.emissive),
+    }
+}
+
+/// 颜色表各行的原始颜色，Opaque 颜色表返回空列表
+pub fn color_table_row_colors(color_table: &ColorTable) -> Vec<RowColors> {
+    match color_table {
+        ColorTable::LegacyColorTable(ct) => ct
+            .rows
+            .iter()
+            .map(|r| RowColors {
+                diffuse: r.diffuse_color,
+                specular: r.specular_color,
+                emissive: r.emissive_color,
+            })
+            .collect(),
+        ColorTable::DawntrailColorTable(ct) => ct
+            .rows
+            .iter()
+            .map(|r| RowColors {
+                diffuse: r.diffuse_color,
+                specular: r.specular_color,
+                emissive: r.emissive_color,
+            })
+            .collect(),
+        ColorTable::OpaqueColorTable(_) => Vec::new(),
+    }
+}
+
+/// 染色表各行，与颜色表版本不匹配时返回空列表 (视为不可染色)
+fn color_dye_table_rows(color_table: &ColorTable, dye_table: &ColorDyeTable) -> Vec<RowDye> {
+    match (color_table, dye_table) {
+        (ColorTable::LegacyColorTable(_), ColorDyeTable::LegacyColorDyeTable(dt)) => dt
+            .rows
+            .iter()
+            .map(|r| RowDye {
+                template: r.template,
+                channel: 0,
+                diffuse: r.diffuse,
+                specular: r.specular,
+                emissive: r.emissive,
+            })
+            .collect(),
+        (ColorTable::DawntrailColorTable(_), ColorDyeTable::DawntrailColorDyeTable(dt)) => dt
+            .rows
+            .iter()
+            .map(|r| RowDye {
+                template: r.template,
+                channel: (r.channel as usize).min(1),
+                diffuse: r.diffuse,
+                specular: r.specular,
+                emissive: r.emissive,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 逐行计算染色前后的颜色 (颜色表检查面板使用，烘焙也基于同一计算)
+pub fn apply_dye_rows(
+    color_table: &ColorTable,
+    dye_table: Option<&ColorDyeTable>,
+    stm: &StainingTemplate,
+    stain_ids: [u32; 2],
+) -> Vec<DyedRow> {
+    let dyes = dye_table.map_or_else(Vec::new, |dt| color_dye_table_rows(color_table, dt));
+    dye_rows_with(
+        color_table_row_colors(color_table),
+        &dyes,
+        stain_ids,
+        |template, stain_index| stm_dye_pack(stm, template, stain_index),
+    )
+}
+
+/// [`apply_dye_rows`] 的实现: 颜色表第 i 行对应染色表第 i 行，超出染色表的行不可染色
+fn dye_rows_with(
+    rows: Vec<RowColors>,
+    dyes: &[RowDye],
+    stain_ids: [u32; 2],
+    dye_pack: impl Fn(u16, usize) -> Option<RowColors>,
+) -> Vec<DyedRow> {
+    rows.into_iter()
+        .enumerate()
+        .map(|(i, undyed)| {
+            let dye = dyes.get(i).copied();
+            DyedRow {
+                undyed,
+                dyed: dye_row_with(undyed, dye.as_ref(), stain_ids, &dye_pack),
+                dye,
+            }
+        })
+        .collect()
+}
+
+/// 染色后各行的 diffuse 颜色，供烘焙与 shader 颜色表使用
 pub fn apply_dye(
     color_table: &ColorTable,
     dye_table: &ColorDyeTable,
     stm: &StainingTemplate,
     stain_ids: [u32; 2],
 ) -> Vec<[f32; 3]> {
+    apply_dye_rows(color_table, Some(dye_table), stm, stain_ids)
+        .into_iter()
+        .map(|row| row.dyed.diffuse)
+        .collect()
+}
+
+pub fn has_dual_dye(materials: &HashMap<u16, CachedMaterial>) -> bool {
+    for mat in materials.values() {
+        if let Some(ColorDyeTable::DawntrailColorDyeTable(dt)) = &mat.color_dye_table {
+            if dt.rows.iter().any(|row| row.channel > 0) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// 染色变更后单个网格需要的更新
+pub enum DyeUpdate {
+    /// 颜色表由 shader 应用: 只替换颜色表行
+    ColorTableRows(Vec<[f32; 3]>),
+    /// 颜色表超出 shader 支持的布局: CPU 重新烘焙的 diffuse
+    Diffuse(TextureData),
+}
+
+/// 计算材质在给定染料下的预览更新，不使用 ColorTable 的材质返回 None
+pub fn dye_update(
+    cached: &CachedMaterial,
+    stain_ids: [u32; 2],
+    stm: &StainingTemplate,
+) -> Option<DyeUpdate> {
+    if !cached.uses_color_table {
+        return None;
+    }
+    let color_table = cached.color_table.as_ref()?;
+    let dyed_colors = if stain_ids[0] > 0 || stain_ids[1] > 0 {
+        cached
+            .color_dye_table
+            .as_ref()
+            .map(|dye_table| apply_dye(color_table, dye_table, stm, stain_ids))
+    } else {
+        None
+    };
+    color_table_update(cached, color_table, dyed_colors.as_ref())
+}
+
+/// 按颜色表行颜色生成更新: shader 支持时替换行，否则 CPU 烘焙 diffuse
+fn color_table_update(
+    cached: &CachedMaterial,
+    color_table: &ColorTable,
+    row_colors: Option<&Vec<[f32; 3]>>,
+) -> Option<DyeUpdate> {
+    if cached.gpu_color_table {
+        return Some(DyeUpdate::ColorTableRows(resolve_color_table_rows(
+            color_table,
+            row_colors,
+        )));
+    }
+    let id_tex = cached.id_texture.as_ref()?;
+    Some(DyeUpdate::Diffuse(bake_color_table_texture(
+        id_tex,
+        color_table,
+        row_colors,
+    )))
+}
+
+/// 染色区域伪彩色: 受通道 1 影响的行偏红，通道 2 偏蓝，不可染色的行为灰度
+///
+/// 保留原色的明暗，便于在模型上分辨同一通道内的不同部件。
+pub fn dye_region_colors(
+    color_table: &ColorTable,
+    dye_table: Option<&ColorDyeTable>,
+) -> Vec<[f32; 3]> {
+    fn tint(base: [f32; 3], channel: Option<usize>) -> [f32; 3] {
+        let luma = 0.2126 * base[0] + 0.7152 * base[1] + 0.0722 * base[2];
+        let shade = 0.3 + 0.7 * luma.clamp(0.0, 1.0);
+        match channel {
+            Some(0) => [shade, shade * 0.15, shade * 0.15],
+            Some(_) => [shade * 0.15, shade * 0.3, shade],
+            None => [luma, luma, luma],
+        }
+    }
+
     match (color_table, dye_table) {
-        (ColorTable::LegacyColorTable(ct), ColorDyeTable::LegacyColorDyeTable(dt)) => {
-            let stain_id = stain_ids[0];
+        (ColorTable::LegacyColorTable(ct), Some(ColorDyeTable::LegacyColorDyeTable(dt))) => ct
+            .rows
+            .iter()
+            .zip(dt.rows.iter())
+            .map(|(row, dye_row)| tint(row.diffuse_color, dye_row.diffuse.then_some(0)))
+            .collect(),
+        (ColorTable::DawntrailColorTable(ct), Some(ColorDyeTable::DawntrailColorDyeTable(dt))) => {
             ct.rows
                 .iter()
                 .zip(dt.rows.iter())
                 .map(|(row, dye_row)| {
-                    if stain_id > 0 && dye_row.diffuse {
-                        let stain_index = (stain_id - 1) as usize;
-                        if let Some(pack) = stm.get_dye_pack(dye_row.template, stain_index) {
-                            return pack.diffuse;
-                        }
-                    }
-                    row.diffuse_color
+                    let channel = (dye_row.channel as usize).min(1);
+                    tint(row.diffuse_color, dye_row.diffuse.then_some(channel))
                 })
                 .collect()
         }
-        (ColorTable::DawntrailColorTable(ct), ColorDyeTable::DawntrailColorDyeTable(dt)) => ct
-            .rows
-            .iter()
-            .zip(dt.rows.iter())
-            .map(|(row, dye_row)| {
-                let ch = (dye_row.channel as usize).min(1);
-                let stain_id = stain_ids[ch];
-                if stain_id > 0 && dye_row.diffuse {
-                    let stain_index = (stain_id - 1) as usize;
-                    if let Some(pack) = stm.get_dye_pack(dye_row.template, stain_index) {
-                        return pack.diffuse;
-                    }
-                }
-                row.diffuse_color
-            })
-            .collect(),
         _ => match color_table {
-            ColorTable::LegacyColorTable(ct) => ct.rows.iter().map(|r| r.diffuse_color).collect(),
-            ColorTable::DawntrailColorTable(ct) => {
-                ct.rows.iter().map(|r| r.diffuse_color).collect()
-            }
+            ColorTable::LegacyColorTable(ct) => ct
+                .rows
+                .iter()
+                .map(|r| tint(r.diffuse_color, None))
+                .collect(),
+            ColorTable::DawntrailColorTable(ct) => ct
+                .rows
+                .iter()
+                .map(|r| tint(r.diffuse_color, None))
+                .collect(),
             ColorTable::OpaqueColorTable(_) => Vec::new(),
         },
     }
 }
 
-pub fn has_dual_dye(materials: &HashMap<u16, CachedMaterial>) -> bool {
-    for mat in materials.values() {
-        if let Some(ColorDyeTable::DawntrailColorDyeTable(dt)) = &mat.color_dye_table {
-            if dt.rows.iter().any(|row| row.channel > 0) {
-                return true;
+/// 染色区域预览的更新，替代 dye_update；不使用 ColorTable 的材质返回 None
+pub fn dye_region_update(cached: &CachedMaterial) -> Option<DyeUpdate> {
+    if !cached.uses_color_table {
+        return None;
+    }
+    let color_table = cached.color_table.as_ref()?;
+    let region_colors = dye_region_colors(color_table, cached.color_dye_table.as_ref());
+    color_table_update(cached, color_table, Some(&region_colors))
+}
+
+/// 把各网格的染色更新提交给渲染器，下标约定与 ModelRenderer::update_textures 相同
+pub fn submit_dye_updates(
+    renderer: &mut ModelRenderer,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    updates: Vec<Option<DyeUpdate>>,
+) {
+    let mut textures = Vec::with_capacity(updates.len());
+    let mut rows = Vec::with_capacity(updates.len());
+    for update in updates {
+        match update {
+            Some(DyeUpdate::ColorTableRows(r)) => {
+                textures.push(None);
+                rows.push(Some(r));
+            }
+            Some(DyeUpdate::Diffuse(tex)) => {
+                textures.push(Some(tex));
+                rows.push(None);
+            }
+            None => {
+                textures.push(None);
+                rows.push(None);
             }
         }
     }
-    false
+    if textures.iter().any(Option::is_some) {
+        renderer.update_textures(device, queue, &textures);
+    }
+    renderer.update_color_tables(queue, &rows);
+}
+
+/// 按染料烘焙使用 ColorTable 的材质的 diffuse，与预览中的烘焙结果一致
+///
+/// 材质不使用 ColorTable 时返回 None，由调用方使用原始 diffuse。
+pub fn bake_dyed_diffuse(
+    cached: &CachedMaterial,
+    stain_ids: [u32; 2],
+    stm: Option<&StainingTemplate>,
+) -> Option<TextureData> {
+    if !cached.uses_color_table {
+        return None;
+    }
+    let (color_table, id_tex) = (cached.color_table.as_ref()?, cached.id_texture.as_ref()?);
+    let dyed_colors = match (stm, &cached.color_dye_table) {
+        (Some(stm), Some(dye_table)) if stain_ids[0] > 0 || stain_ids[1] > 0 => {
+            Some(apply_dye(color_table, dye_table, stm, stain_ids))
+        }
+        _ => None,
+    };
+    Some(bake_color_table_texture(
+        id_tex,
+        color_table,
+        dyed_colors.as_ref(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GREY: RowColors = RowColors {
+        diffuse: [0.5, 0.5, 0.5],
+        specular: [0.2, 0.2, 0.2],
+        emissive: [0.0, 0.0, 0.0],
+    };
+
+    /// 染料包: 模板号写进 diffuse 的 R，染料下标写进 G，便于核对查表参数
+    fn pack(template: u16, stain_index: usize) -> Option<RowColors> {
+        (stain_index < 100).then_some(RowColors {
+            diffuse: [template as f32, stain_index as f32, 1.0],
+            specular: [1.0, 1.0, 1.0],
+            emissive: [0.3, 0.3, 0.3],
+        })
+    }
+
+    fn dye(template: u16, channel: usize, diffuse: bool, specular: bool) -> RowDye {
+        RowDye {
+            template,
+            channel,
+            diffuse,
+            specular,
+            emissive: false,
+        }
+    }
+
+    #[test]
+    fn replaces_only_flagged_colors() {
+        let dyed = dye_row_with(GREY, Some(&dye(7, 0, true, false)), [5, 0], pack);
+        assert_eq!(dyed.diffuse, [7.0, 4.0, 1.0]);
+        assert_eq!(dyed.specular, GREY.specular);
+        assert_eq!(dyed.emissive, GREY.emissive);
+    }
+
+    #[test]
+    fn channel_selects_stain() {
+        let second = dye(3, 1, true, true);
+        let dyed = dye_row_with(GREY, Some(&second), [5, 9], pack);
+        assert_eq!(dyed.diffuse, [3.0, 8.0, 1.0]);
+        assert_eq!(dyed.specular, [1.0, 1.0, 1.0]);
+        // 通道 1 未染色时保持原色，不回退到通道 0 的染料
+        assert_eq!(dye_row_with(GREY, Some(&second), [5, 0], pack), GREY);
+    }
+
+    #[test]
+    fn undyeable_rows_keep_original_colors() {
+        assert_eq!(dye_row_with(GREY, None, [5, 5], pack), GREY);
+        assert_eq!(
+            dye_row_with(GREY, Some(&dye(1, 0, true, true)), [0, 0], pack),
+            GREY
+        );
+        // 染料包缺失
+        assert_eq!(
+            dye_row_with(GREY, Some(&dye(1, 0, true, true)), [500, 0], pack),
+            GREY
+        );
+    }
+
+    #[test]
+    fn rows_pair_with_dye_table_by_index() {
+        let rows = vec![GREY; 3];
+        let dyes = [dye(1, 0, true, false), dye(2, 0, false, false)];
+        let result = dye_rows_with(rows, &dyes, [10, 0], pack);
+        assert_eq!(result.len(), 3);
+
+        assert_eq!(result[0].dye, Some(dyes[0]));
+        assert_eq!(result[0].undyed, GREY);
+        assert_eq!(result[0].dyed.diffuse, [1.0, 9.0, 1.0]);
+
+        assert_eq!(result[1].dye, Some(dyes[1]));
+        assert!(!result[1].dye.unwrap().affects_any());
+        assert_eq!(result[1].dyed, GREY);
+
+        // 超出染色表的行
+        assert_eq!(result[2].dye, None);
+        assert_eq!(result[2].dyed, GREY);
+    }
+
+    #[test]
+    fn no_dye_table_means_no_dyeing() {
+        let result = dye_rows_with(vec![GREY; 2], &[], [10, 10], pack);
+        assert!(result
+            .iter()
+            .all(|row| row.dye.is_none() && row.dyed == GREY));
+    }
 }
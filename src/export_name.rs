@@ -0,0 +1,287 @@
+//! 导出文件命名模板: 由设置中的模板与各导出功能提供的上下文生成文件名
+//!
+//! 占位符: {item} {set_id} {slot} {dye} {date} {time} {race}，导出功能未提供的占位符
+//! 展开为空；未知的占位符原样保留。结果会替换文件系统保留字符，去掉首尾多余的分隔符。
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::domain::StainEntry;
+
+/// 默认的文件名模板
+pub const DEFAULT_EXPORT_NAME_TEMPLATE: &str = "{item}_{slot}";
+
+/// 导出功能提供的命名上下文，未设置的字段展开为空
+#[derive(Debug, Clone, Default)]
+pub struct ExportNameContext {
+    /// 物品名或幻化组合/套装名
+    pub item: Option<String>,
+    pub set_id: Option<u16>,
+    /// 槽位缩写 (top/dwn/...)
+    pub slot: Option<String>,
+    /// 染料名，双染时以 + 连接
+    pub dye: Option<String>,
+    /// 种族代码 (c0201)
+    pub race: Option<String>,
+    /// {date} / {time} 使用的时间，None 时取当前时间
+    pub time: Option<SystemTime>,
+}
+
+impl ExportNameContext {
+    /// 设置页预览用的示例上下文
+    pub fn example() -> Self {
+        Self {
+            item: Some("Augmented Shire Philosopher's Coat".to_string()),
+            set_id: Some(9101),
+            slot: Some("top".to_string()),
+            dye: Some("Snow White".to_string()),
+            race: Some("c0201".to_string()),
+            time: None,
+        }
+    }
+}
+
+/// {dye} 的值: 染料名，双染且两个通道不同时以 + 连接；未染色时为 None
+pub fn dye_label(stains: &[StainEntry], stain_ids: [u32; 2]) -> Option<String> {
+    let name = |id: u32| stains.iter().find(|s| s.id == id).map(|s| s.name.as_str());
+    let mut names: Vec<&str> = Vec::new();
+    for id in stain_ids.into_iter().filter(|&id| id != 0) {
+        if let Some(n) = name(id).filter(|n| !names.contains(n)) {
+            names.push(n);
+        }
+    }
+    (!names.is_empty()).then(|| names.join("+"))
+}
+
+/// 把 Unix 时间转换为 UTC 的 (年, 月, 日, 时, 分, 秒)
+fn utc_fields(time: SystemTime) -> (i64, u32, u32, u32, u32, u32) {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (days, rem) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    // 公历日期换算 (Howard Hinnant 的 civil_from_days)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let rem = rem as u32;
+    (year, month, day, rem / 3600, rem / 60 % 60, rem % 60)
+}
+
+/// 替换文件名中的保留字符与控制字符，去掉首尾空白与末尾的点
+pub fn sanitize_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    name.trim_start().trim_end_matches(['.', ' ']).to_string()
+}
+
+/// 展开模板中的占位符，不做文件名处理
+fn expand_placeholders(template: &str, ctx: &ExportNameContext) -> String {
+    let (year, month, day, hour, minute, second) =
+        utc_fields(ctx.time.unwrap_or_else(SystemTime::now));
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let Some(end) = after.find('}') else {
+            rest = after;
+            break;
+        };
+        let value = match &after[1..end] {
+            "item" => ctx.item.clone().unwrap_or_default(),
+            "set_id" => ctx
+                .set_id
+                .map(|id| format!("{:04}", id))
+                .unwrap_or_default(),
+            "slot" => ctx.slot.clone().unwrap_or_default(),
+            "dye" => ctx.dye.clone().unwrap_or_default(),
+            "race" => ctx.race.clone().unwrap_or_default(),
+            "date" => format!("{:04}-{:02}-{:02}", year, month, day),
+            "time" => format!("{:02}{:02}{:02}", hour, minute, second),
+            _ => after[..=end].to_string(),
+        };
+        out.push_str(&value);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 按模板生成文件名 (不含扩展名)。缺失的占位符留下的多余分隔符会被去掉，
+/// 结果为空时使用 `fallback`
+pub fn format_export_name(template: &str, ctx: &ExportNameContext, fallback: &str) -> String {
+    let expanded = sanitize_file_name(&expand_placeholders(template, ctx));
+    // 缺失的占位符会留下重复或首尾的分隔符，如 "_top" / "coat__c0201"
+    let mut name = String::with_capacity(expanded.len());
+    for c in expanded.chars() {
+        if matches!(c, '_' | '-') && name.ends_with(c) {
+            continue;
+        }
+        name.push(c);
+    }
+    let name = name.trim_matches(['_', '-', ' ']);
+    if name.is_empty() {
+        fallback.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// 目录中不冲突的路径: `stem.ext` 已存在时依次尝试 `stem-1.ext`、`stem-2.ext`…
+/// (`ext` 为空时用于目录名)
+pub fn unique_export_path(dir: &Path, stem: &str, ext: &str) -> PathBuf {
+    let file_name = |suffix: Option<u32>| {
+        let stem = match suffix {
+            Some(n) => format!("{}-{}", stem, n),
+            None => stem.to_string(),
+        };
+        if ext.is_empty() {
+            stem
+        } else {
+            format!("{}.{}", stem, ext)
+        }
+    };
+    std::iter::once(None)
+        .chain((1..).map(Some))
+        .map(|suffix| dir.join(file_name(suffix)))
+        .find(|path| !path.exists())
+        .unwrap_or_else(|| dir.join(file_name(None)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(secs: u64) -> Option<SystemTime> {
+        Some(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn reserved_characters_are_replaced() {
+        assert_eq!(
+            sanitize_file_name(r#"a<b>c:d"e/f\g|h?i*j"#),
+            "a_b_c_d_e_f_g_h_i_j"
+        );
+        assert_eq!(sanitize_file_name("tab\there\nnew"), "tab_here_new");
+        assert_eq!(sanitize_file_name("  name. . "), "name");
+        assert_eq!(sanitize_file_name("trailing..."), "trailing");
+        assert_eq!(sanitize_file_name("最终幻想 XIV"), "最终幻想 XIV");
+    }
+
+    #[test]
+    fn utc_fields_known_dates() {
+        let fields = |secs| utc_fields(at(secs).unwrap());
+        assert_eq!(fields(0), (1970, 1, 1, 0, 0, 0));
+        assert_eq!(fields(951_782_400), (2000, 2, 29, 0, 0, 0));
+        assert_eq!(fields(1_700_000_000), (2023, 11, 14, 22, 13, 20));
+        assert_eq!(fields(4_107_542_399), (2100, 2, 28, 23, 59, 59));
+    }
+
+    #[test]
+    fn expands_every_placeholder() {
+        let ctx = ExportNameContext {
+            time: at(1_700_000_000),
+            ..ExportNameContext::example()
+        };
+        assert_eq!(
+            format_export_name(
+                "{item}_{set_id}_{slot}_{dye}_{race}_{date}_{time}",
+                &ctx,
+                "x"
+            ),
+            "Augmented Shire Philosopher's Coat_9101_top_Snow White_c0201_2023-11-14_221320"
+        );
+        // 未知占位符与未闭合的花括号原样保留
+        assert_eq!(
+            format_export_name("{item}{unknown}", &ctx, "x"),
+            "Augmented Shire Philosopher's Coat{unknown}"
+        );
+        assert_eq!(format_export_name("{slot}_{item", &ctx, "x"), "top_{item");
+    }
+
+    #[test]
+    fn missing_placeholders_leave_no_stray_separators() {
+        let ctx = ExportNameContext {
+            item: Some("coat".to_string()),
+            race: Some("c0201".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_export_name("{item}_{slot}_{race}", &ctx, "x"),
+            "coat_c0201"
+        );
+        assert_eq!(format_export_name("{dye}-{item}", &ctx, "x"), "coat");
+        assert_eq!(
+            format_export_name("{slot}_{dye}", &ctx, "fallback"),
+            "fallback"
+        );
+        assert_eq!(
+            format_export_name(DEFAULT_EXPORT_NAME_TEMPLATE, &Default::default(), "model"),
+            "model"
+        );
+    }
+
+    #[test]
+    fn expanded_values_are_sanitized() {
+        let ctx = ExportNameContext {
+            item: Some("AC/DC: \"Live\"".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(format_export_name("{item}", &ctx, "x"), "AC_DC_ _Live");
+    }
+
+    #[test]
+    fn dye_label_joins_distinct_names() {
+        let stain = |id: u32, name: &str| StainEntry {
+            id,
+            name: name.to_string(),
+            color: [0, 0, 0],
+            shade: 2,
+        };
+        let stains = [stain(1, "Snow White"), stain(2, "Soot Black")];
+        assert_eq!(
+            dye_label(&stains, [1, 2]).as_deref(),
+            Some("Snow White+Soot Black")
+        );
+        assert_eq!(dye_label(&stains, [0, 2]).as_deref(), Some("Soot Black"));
+        assert_eq!(dye_label(&stains, [1, 1]).as_deref(), Some("Snow White"));
+        assert_eq!(dye_label(&stains, [0, 0]), None);
+        assert_eq!(dye_label(&stains, [99, 0]), None);
+    }
+
+    #[test]
+    fn unique_path_appends_counter() {
+        let dir = std::env::temp_dir().join(format!("tomestone-export-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let first = unique_export_path(&dir, "coat", "obj");
+        assert_eq!(first, dir.join("coat.obj"));
+        std::fs::write(&first, b"").unwrap();
+        let second = unique_export_path(&dir, "coat", "obj");
+        assert_eq!(second, dir.join("coat-1.obj"));
+        std::fs::write(&second, b"").unwrap();
+        assert_eq!(
+            unique_export_path(&dir, "coat", "obj"),
+            dir.join("coat-2.obj")
+        );
+
+        std::fs::create_dir(dir.join("kit")).unwrap();
+        assert_eq!(unique_export_path(&dir, "kit", ""), dir.join("kit-1"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,249 @@
+//! 快速切换用的模糊匹配: 查询词的字符按顺序出现在文本中 (子序列) 即匹配，
+//! 按词首位置与连续程度打分，忽略大小写
+
+/// 每个匹配字符的基础分
+const MATCH_SCORE: i32 = 1;
+/// 匹配在词首 (文本开头、分隔符之后、小写到大写的转换处)
+const WORD_START_BONUS: i32 = 8;
+/// 与上一个匹配字符相邻
+const CONSECUTIVE_BONUS: i32 = 5;
+/// 第一个查询字符匹配在文本开头
+const PREFIX_BONUS: i32 = 10;
+/// 匹配之间每跳过一个字符的扣分
+const GAP_PENALTY: i32 = 1;
+/// 第一个匹配之前的字符最多扣这么多分，避免长名称中靠后的好匹配被压得过低
+const MAX_LEADING_PENALTY: i32 = 5;
+
+/// 查询词在文本中的匹配分数，不匹配时返回 None；空查询匹配任何文本 (0 分)
+///
+/// 同一字符可能在文本中出现多次，按动态规划取得分最高的一组位置，
+/// 使 "gm" 在 "GlamourManager" 中匹配到两个词首而不是最早出现的 m。
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    fuzzy_score_prepared(query, &FuzzyText::new(text))
+}
+
+/// 预先拆分并转小写的文本，同一批文本反复打分 (快速切换的候选) 时避免每次重新分配
+#[derive(Debug, Clone, Default)]
+pub struct FuzzyText {
+    chars: Vec<char>,
+    lower: Vec<char>,
+}
+
+impl FuzzyText {
+    pub fn new(text: &str) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let lower = chars
+            .iter()
+            .map(|c| c.to_lowercase().next().unwrap_or(*c))
+            .collect();
+        Self { chars, lower }
+    }
+
+    /// 字符数 (排序时同分的短名称优先)
+    pub fn char_count(&self) -> usize {
+        self.chars.len()
+    }
+}
+
+/// 与 [`fuzzy_score`] 相同，文本已预先处理
+pub fn fuzzy_score_prepared(query: &str, text: &FuzzyText) -> Option<i32> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let (chars, lower) = (&text.chars, &text.lower);
+
+    // 先按最早出现的位置快速判断是否为子序列，大部分候选在这里被排除
+    let mut pending = query.iter().peekable();
+    for c in lower.iter() {
+        if pending.peek() == Some(&c) {
+            pending.next();
+        }
+    }
+    if pending.peek().is_some() {
+        return None;
+    }
+
+    let base: Vec<i32> = (0..chars.len())
+        .map(|i| {
+            let word_start = i == 0 || {
+                let (p, c) = (chars[i - 1], chars[i]);
+                !p.is_alphanumeric() || (p.is_lowercase() && c.is_uppercase())
+            };
+            MATCH_SCORE + if word_start { WORD_START_BONUS } else { 0 }
+        })
+        .collect();
+
+    // prev[i]: 上一个查询字符匹配在 i 处时的最高分
+    let mut prev: Vec<Option<i32>> = (0..chars.len())
+        .map(|i| {
+            (lower[i] == query[0]).then(|| {
+                let lead = if i == 0 {
+                    PREFIX_BONUS
+                } else {
+                    -(GAP_PENALTY * i as i32).min(MAX_LEADING_PENALTY)
+                };
+                base[i] + lead
+            })
+        })
+        .collect();
+    for &q in &query[1..] {
+        let mut cur = vec![None; chars.len()];
+        // 在 i - 1 之前匹配的最高 (分数 + 位置)，跳过的字符数为 i - k - 1
+        let mut best_gapped: Option<i32> = None;
+        for i in 1..chars.len() {
+            if i >= 2 {
+                if let Some(score) = prev[i - 2] {
+                    let candidate = score + GAP_PENALTY * (i as i32 - 2);
+                    best_gapped = Some(best_gapped.map_or(candidate, |b| b.max(candidate)));
+                }
+            }
+            if lower[i] != q {
+                continue;
+            }
+            let consecutive = prev[i - 1].map(|s| s + CONSECUTIVE_BONUS);
+            let gapped = best_gapped.map(|b| b - GAP_PENALTY * (i as i32 - 1));
+            let best = match (consecutive, gapped) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+            cur[i] = best.map(|b| b + base[i]);
+        }
+        prev = cur;
+    }
+    prev.into_iter().flatten().max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 穷举全部匹配位置组合的得分，用来核对动态规划
+    fn brute_force(query: &str, text: &str) -> Option<i32> {
+        let text = FuzzyText::new(text);
+        let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+        fn search(
+            query: &[char],
+            text: &FuzzyText,
+            from: usize,
+            picked: &mut Vec<usize>,
+            best: &mut Option<i32>,
+        ) {
+            let Some((&q, rest)) = query.split_first() else {
+                let score = score_positions(text, picked);
+                *best = Some(best.map_or(score, |b| b.max(score)));
+                return;
+            };
+            for i in from..text.lower.len() {
+                if text.lower[i] == q {
+                    picked.push(i);
+                    search(rest, text, i + 1, picked, best);
+                    picked.pop();
+                }
+            }
+        }
+        fn score_positions(text: &FuzzyText, positions: &[usize]) -> i32 {
+            let chars = &text.chars;
+            let mut score = 0;
+            for (n, &i) in positions.iter().enumerate() {
+                let word_start = i == 0 || {
+                    let (p, c) = (chars[i - 1], chars[i]);
+                    !p.is_alphanumeric() || (p.is_lowercase() && c.is_uppercase())
+                };
+                score += MATCH_SCORE + if word_start { WORD_START_BONUS } else { 0 };
+                if n == 0 {
+                    score += if i == 0 {
+                        PREFIX_BONUS
+                    } else {
+                        -(GAP_PENALTY * i as i32).min(MAX_LEADING_PENALTY)
+                    };
+                } else {
+                    let gap = i - positions[n - 1] - 1;
+                    score += if gap == 0 {
+                        CONSECUTIVE_BONUS
+                    } else {
+                        -GAP_PENALTY * gap as i32
+                    };
+                }
+            }
+            score
+        }
+        if query.is_empty() {
+            return Some(0);
+        }
+        let mut best = None;
+        search(&query, &text, 0, &mut Vec::new(), &mut best);
+        best
+    }
+
+    #[test]
+    fn matches_subsequence_ignoring_case() {
+        assert!(fuzzy_score("gm", "GlamourManager").is_some());
+        assert!(fuzzy_score("GLAM", "glamour").is_some());
+        assert!(fuzzy_score("装备", "装备浏览器").is_some());
+        assert!(fuzzy_score("mg", "GlamourManager").is_some());
+        assert_eq!(fuzzy_score("xyz", "GlamourManager"), None);
+        assert_eq!(fuzzy_score("glamourr", "glamour"), None);
+        assert_eq!(fuzzy_score("a", ""), None);
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert_eq!(fuzzy_score("", ""), Some(0));
+    }
+
+    #[test]
+    fn dynamic_programming_finds_best_positions() {
+        let cases = [
+            ("gm", "GlamourManager"),
+            ("gm", "gem merchant"),
+            ("cr", "Crafting Recipes"),
+            ("ab", "aXbab ab"),
+            ("aaa", "a_aa_aaa"),
+            ("ring", "Bronze Ring of Fending"),
+            ("set", "Resource Browser Settings"),
+            ("ee", "eeeeeeeeee"),
+        ];
+        for (query, text) in cases {
+            assert_eq!(
+                fuzzy_score(query, text),
+                brute_force(query, text),
+                "{} / {}",
+                query,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn ranking_prefers_prefix_word_starts_and_runs() {
+        let score = |text: &str| fuzzy_score("gm", text).unwrap();
+        // 两个词首 > 词中
+        assert!(score("GlamourManager") > score("Sigma"));
+        assert!(score("glamour manager") > score("algmx"));
+
+        let score = |text: &str| fuzzy_score("ring", text).unwrap();
+        // 开头 > 中间的词首 > 词中
+        assert!(score("Ring of Fending") > score("Bronze Ring"));
+        assert!(score("Bronze Ring") > score("Bronze Earring"));
+        // 连续 > 分散
+        assert!(score("Xring") > score("Xrxixnxg"));
+    }
+
+    #[test]
+    fn leading_penalty_is_capped() {
+        let near = fuzzy_score("x", "abcdefx").unwrap();
+        let far = fuzzy_score("x", "abcdefghijklmnopx").unwrap();
+        assert_eq!(near, far);
+        assert_eq!(near, MATCH_SCORE - MAX_LEADING_PENALTY);
+    }
+
+    #[test]
+    fn prepared_text_scores_like_plain_text() {
+        let text = FuzzyText::new("Glamour Manager");
+        assert_eq!(text.char_count(), 15);
+        for query in ["", "gm", "man", "zz"] {
+            assert_eq!(
+                fuzzy_score_prepared(query, &text),
+                fuzzy_score(query, "Glamour Manager")
+            );
+        }
+    }
+}
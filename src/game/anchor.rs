@@ -0,0 +1,199 @@
+//! 饰品预览的参照网格: 在耳饰/项链/手镯/戒指后面放置灰色的耳朵/胸像/手，提供尺度参照
+//!
+//! 网格内嵌于 assets/accessory_anchors.obj，按当前种族骨架的绑定姿势放置并缩放。
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use glam::{Mat4, Quat, Vec3};
+use tomestone_render::{MeshTextures, TextureData, Vertex};
+
+use super::MeshData;
+use crate::domain::{EquipSlot, RACE_CODES};
+
+const ANCHOR_OBJ: &str = include_str!("../../assets/accessory_anchors.obj");
+/// 参照网格的漫反射颜色 (中性灰)
+const ANCHOR_GRAY: [u8; 4] = [150, 150, 150, 255];
+/// 参照网格的顶点颜色: 无高光，漫反射不遮罩
+const ANCHOR_VERTEX_COLOR: [f32; 4] = [0.0, 1.0, 1.0, 1.0];
+/// 参照网格不对应任何材质，重新染色时不会匹配到
+const ANCHOR_MATERIAL_INDEX: u16 = u16::MAX;
+/// 骨架中没有耳朵骨骼时，耳朵相对头部骨骼的横向偏移 (颈→头骨距离的倍数)
+const EAR_FALLBACK_OFFSET: f32 = 0.7;
+
+struct AnchorShape {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    indices: Vec<u16>,
+}
+
+/// 解析内嵌的 OBJ: 只支持 `o` / `v` / `vn` / `f v//vn` 三角面，v 与 vn 一一对应
+fn parse_anchor_obj(src: &str) -> HashMap<String, AnchorShape> {
+    let mut shapes = HashMap::new();
+    let mut current: Option<(String, AnchorShape)> = None;
+    // 之前各对象的顶点总数 (OBJ 下标是全局的)
+    let mut vertex_base = 0usize;
+    let parse_vec3 = |parts: &[&str]| -> Vec3 {
+        let c = |i: usize| parts.get(i).and_then(|s| s.parse().ok()).unwrap_or(0.0);
+        Vec3::new(c(1), c(2), c(3))
+    };
+    for line in src.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        match parts.first().copied() {
+            Some("o") => {
+                if let Some((name, shape)) = current.take() {
+                    vertex_base += shape.positions.len();
+                    shapes.insert(name, shape);
+                }
+                let name = parts.get(1).copied().unwrap_or_default().to_string();
+                let shape = AnchorShape {
+                    positions: Vec::new(),
+                    normals: Vec::new(),
+                    indices: Vec::new(),
+                };
+                current = Some((name, shape));
+            }
+            Some("v") => {
+                if let Some((_, shape)) = current.as_mut() {
+                    shape.positions.push(parse_vec3(&parts));
+                }
+            }
+            Some("vn") => {
+                if let Some((_, shape)) = current.as_mut() {
+                    shape.normals.push(parse_vec3(&parts).normalize_or_zero());
+                }
+            }
+            Some("f") => {
+                if let Some((_, shape)) = current.as_mut() {
+                    let corners: Vec<u16> = parts[1..]
+                        .iter()
+                        .filter_map(|p| p.split('/').next()?.parse::<usize>().ok())
+                        .filter_map(|i| u16::try_from(i.checked_sub(vertex_base + 1)?).ok())
+                        .collect();
+                    if let [a, b, c] = corners[..] {
+                        shape.indices.extend_from_slice(&[a, b, c]);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some((name, shape)) = current {
+        shapes.insert(name, shape);
+    }
+    shapes
+}
+
+fn anchor_shape(name: &str) -> Option<&'static AnchorShape> {
+    static SHAPES: OnceLock<HashMap<String, AnchorShape>> = OnceLock::new();
+    SHAPES
+        .get_or_init(|| parse_anchor_obj(ANCHOR_OBJ))
+        .get(name)
+}
+
+/// 把参照网格按 (缩放, 旋转, 平移) 放到模型空间
+fn place_shape(name: &str, scale: f32, rotation: Quat, translation: Vec3) -> Option<MeshData> {
+    let shape = anchor_shape(name)?;
+    let transform =
+        Mat4::from_scale_rotation_translation(Vec3::splat(scale), rotation, translation);
+    let vertices = shape
+        .positions
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| {
+            let normal = rotation * shape.normals.get(i).copied().unwrap_or(Vec3::Y);
+            Vertex {
+                position: transform.transform_point3(p).to_array(),
+                normal: normal.to_array(),
+                uv: [0.0, 0.0],
+                color: ANCHOR_VERTEX_COLOR,
+                tangent: [1.0, 0.0, 0.0, 1.0],
+            }
+        })
+        .collect();
+    Some(MeshData {
+        vertices,
+        indices: shape.indices.clone(),
+        material_index: ANCHOR_MATERIAL_INDEX,
+        bone_table_index: 0,
+        skin_vertices: Vec::new(),
+        bone_names: Vec::new(),
+    })
+}
+
+/// 该槽位是否有参照网格 (面饰没有)
+pub fn has_accessory_anchor(slot: EquipSlot) -> bool {
+    matches!(
+        slot,
+        EquipSlot::Earrings | EquipSlot::Necklace | EquipSlot::Bracelet | EquipSlot::Ring
+    )
+}
+
+/// 模型路径对应的种族代码 (文件名以种族代码开头，如 c0201a0001_ear.mdl)
+pub fn race_code_of_model_path(path: &str) -> Option<&'static str> {
+    let file = path.rsplit('/').next()?;
+    RACE_CODES.iter().copied().find(|rc| file.starts_with(rc))
+}
+
+/// 按绑定姿势放置的参照网格: 项链 → 胸像，耳饰 → 双耳，手镯 → 双手，戒指 → 右手。
+/// 缺少所需骨骼时返回空列表
+pub fn accessory_anchor_meshes(
+    slot: EquipSlot,
+    bind_pose: &HashMap<String, Mat4>,
+) -> Vec<MeshData> {
+    let bone = |name: &str| bind_pose.get(name).map(|m| m.w_axis.truncate());
+    let mut meshes = Vec::new();
+    match slot {
+        EquipSlot::Necklace | EquipSlot::Earrings => {
+            let (Some(neck), Some(head)) = (bone("j_kubi"), bone("j_kao")) else {
+                return meshes;
+            };
+            let scale = neck.distance(head);
+            if slot == EquipSlot::Necklace {
+                meshes.extend(place_shape("bust", scale, Quat::IDENTITY, neck));
+            } else {
+                for (ear_bone, side) in [("j_mimi_l", 1.0), ("j_mimi_r", -1.0)] {
+                    let ear = bone(ear_bone)
+                        .unwrap_or(head + Vec3::X * side * EAR_FALLBACK_OFFSET * scale);
+                    meshes.extend(place_shape("ear", scale, Quat::IDENTITY, ear));
+                }
+            }
+        }
+        EquipSlot::Bracelet | EquipSlot::Ring => {
+            let hands: &[(&str, &str)] = if slot == EquipSlot::Ring {
+                &[("j_te_r", "j_ude_b_r")]
+            } else {
+                &[("j_te_l", "j_ude_b_l"), ("j_te_r", "j_ude_b_r")]
+            };
+            for &(hand_bone, forearm_bone) in hands {
+                let (Some(wrist), Some(elbow)) = (bone(hand_bone), bone(forearm_bone)) else {
+                    continue;
+                };
+                // 手沿前臂方向伸出，按前臂长度缩放
+                let arm = wrist - elbow;
+                let Some(direction) = arm.try_normalize() else {
+                    continue;
+                };
+                let rotation = Quat::from_rotation_arc(Vec3::NEG_Y, direction);
+                meshes.extend(place_shape("hand", arm.length(), rotation, wrist));
+            }
+        }
+        _ => {}
+    }
+    meshes
+}
+
+/// 参照网格的贴图: 1×1 纯灰
+pub fn anchor_textures() -> MeshTextures {
+    MeshTextures {
+        diffuse: TextureData {
+            rgba: std::sync::Arc::new(ANCHOR_GRAY.to_vec()),
+            width: 1,
+            height: 1,
+        },
+        normal: None,
+        mask: None,
+        emissive: None,
+        color_table: None,
+    }
+}
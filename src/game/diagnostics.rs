@@ -0,0 +1,269 @@
+use physis::Language;
+
+use super::GameData;
+
+/// 自检需要检查的 EXD 表及最少行数 (低于该值视为版本不匹配或读取异常)
+pub const REQUIRED_SHEETS: &[(&str, Language, usize)] = &[
+    ("Item", Language::ChineseSimplified, 30000),
+    ("Stain", Language::ChineseSimplified, 100),
+    ("Recipe", Language::None, 10000),
+    ("RecipeLevelTable", Language::None, 100),
+    ("ItemUICategory", Language::ChineseSimplified, 50),
+    ("GilShop", Language::ChineseSimplified, 100),
+    ("GilShopItem", Language::None, 100),
+    ("SpecialShop", Language::ChineseSimplified, 100),
+    ("GCScripShopItem", Language::None, 10),
+    ("GCScripShopCategory", Language::None, 10),
+    ("GatheringItem", Language::None, 100),
+    ("HousingExterior", Language::None, 100),
+    ("HousingFurniture", Language::None, 100),
+    ("HousingYardObject", Language::None, 50),
+    ("Orchestrion", Language::ChineseSimplified, 100),
+    ("TripleTriadCard", Language::ChineseSimplified, 100),
+];
+
+/// 用于验证模型解析的已知存在路径 (基础内衣)
+pub const KNOWN_GOOD_MDL: &str = "chara/equipment/e0000/model/c0201e0000_top.mdl";
+
+/// 单项检查结果
+#[derive(Clone, Debug)]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub passed: bool,
+    /// 通过时为摘要信息，失败时为底层错误
+    pub detail: String,
+}
+
+impl DiagnosticCheck {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            passed: true,
+            detail: detail.into(),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            passed: false,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// 完整自检报告
+#[derive(Clone, Debug, Default)]
+pub struct DiagnosticReport {
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+impl DiagnosticReport {
+    pub fn failed_count(&self) -> usize {
+        self.checks.iter().filter(|c| !c.passed).count()
+    }
+
+    /// 生成纯文本报告，便于粘贴到问题反馈
+    pub fn to_text(&self) -> String {
+        let mut out = format!(
+            "tomestone 自检报告 (v{}): {}/{} 项通过\n",
+            env!("CARGO_PKG_VERSION"),
+            self.checks.len() - self.failed_count(),
+            self.checks.len()
+        );
+        for check in &self.checks {
+            let mark = if check.passed { "通过" } else { "失败" };
+            out.push_str(&format!("[{}] {}: {}\n", mark, check.name, check.detail));
+        }
+        out
+    }
+}
+
+impl GameData {
+    pub fn check_sqpack_dir(&self) -> DiagnosticCheck {
+        let dir = self.sqpack_dir();
+        if dir.is_dir() {
+            DiagnosticCheck::pass("sqpack 目录", dir.display().to_string())
+        } else {
+            DiagnosticCheck::fail("sqpack 目录", format!("目录不存在: {}", dir.display()))
+        }
+    }
+
+    /// 列出已安装的数据仓库，缺少基础仓库 ffxiv 时失败
+    pub fn check_repositories(&self) -> DiagnosticCheck {
+        let repos = self.repositories();
+        if !repos.iter().any(|r| r == "ffxiv") {
+            return DiagnosticCheck::fail("数据仓库", "未检测到 ffxiv 基础数据");
+        }
+        let missing = self.missing_expansions();
+        let detail = if missing.is_empty() {
+            repos.join(", ")
+        } else {
+            format!("{} (未检测到: {})", repos.join(", "), missing.join(", "))
+        };
+        DiagnosticCheck::pass("数据仓库", detail)
+    }
+
+    /// 检查表的每一页都可读且总行数不低于 min_rows
+    pub fn check_sheet(&self, name: &str, language: Language, min_rows: usize) -> DiagnosticCheck {
+        let label = format!("{} 表", name);
+
+        let mut rows = 0usize;
+        if let Err(e) = self.for_each_row(name, language, |_, _| rows += 1) {
+            return DiagnosticCheck::fail(label, format!("表读取失败: {}", e));
+        }
+        if rows < min_rows {
+            DiagnosticCheck::fail(label, format!("行数 {} 低于预期 {}", rows, min_rows))
+        } else {
+            DiagnosticCheck::pass(label, format!("{} 行", rows))
+        }
+    }
+
+    pub fn check_staining_template(&self) -> DiagnosticCheck {
+        match self
+            .source
+            .staining_template("chara/base_material/stainingtemplate.stm")
+        {
+            Ok(stm) => {
+                DiagnosticCheck::pass("染色模板 (STM)", format!("{} 个模板", stm.entries.len()))
+            }
+            Err(e) => DiagnosticCheck::fail("染色模板 (STM)", e.to_string()),
+        }
+    }
+
+    pub fn check_known_mdl(&self) -> DiagnosticCheck {
+        match super::load_mdl(self, KNOWN_GOOD_MDL) {
+            Ok(r) if !r.meshes.is_empty() => DiagnosticCheck::pass(
+                "模型解析",
+                format!("{}: {} 个网格", KNOWN_GOOD_MDL, r.meshes.len()),
+            ),
+            Ok(_) => DiagnosticCheck::fail("模型解析", format!("{}: 无网格", KNOWN_GOOD_MDL)),
+            Err(e) => DiagnosticCheck::fail("模型解析", format!("{}: {}", KNOWN_GOOD_MDL, e)),
+        }
+    }
+
+    /// 依次运行全部检查
+    pub fn run_self_check(&self) -> DiagnosticReport {
+        let mut checks = vec![self.check_sqpack_dir(), self.check_repositories()];
+        for &(name, language, min_rows) in REQUIRED_SHEETS {
+            checks.push(self.check_sheet(name, language, min_rows));
+        }
+        checks.push(self.check_staining_template());
+        checks.push(self.check_known_mdl());
+        DiagnosticReport { checks }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::game::{GameSource, MockSource, KNOWN_REPOSITORIES};
+
+    fn mock_game(game_dir: PathBuf, source: MockSource, repositories: &[&str]) -> GameData {
+        GameData::with_source(
+            game_dir,
+            Box::new(source),
+            repositories.iter().map(|r| r.to_string()).collect(),
+        )
+    }
+
+    fn default_game() -> GameData {
+        mock_game(
+            PathBuf::from("does-not-exist/game"),
+            MockSource::default(),
+            &["ffxiv"],
+        )
+    }
+
+    #[test]
+    fn sqpack_dir_check() {
+        assert!(!default_game().check_sqpack_dir().passed);
+
+        let game_dir = std::env::temp_dir()
+            .join(format!("tomestone-diag-{}", std::process::id()))
+            .join("game");
+        std::fs::create_dir_all(game_dir.join("sqpack")).unwrap();
+        let game = mock_game(game_dir.clone(), MockSource::default(), &["ffxiv"]);
+        let check = game.check_sqpack_dir();
+        let _ = std::fs::remove_dir_all(game_dir.parent().unwrap());
+        assert!(check.passed, "{}", check.detail);
+    }
+
+    #[test]
+    fn repositories_check() {
+        let none = mock_game(PathBuf::new(), MockSource::default(), &[]);
+        assert!(!none.check_repositories().passed);
+
+        let base_only = default_game().check_repositories();
+        assert!(base_only.passed);
+        assert_eq!(
+            base_only.detail,
+            "ffxiv (未检测到: ex1, ex2, ex3, ex4, ex5)"
+        );
+
+        let all = mock_game(PathBuf::new(), MockSource::default(), &KNOWN_REPOSITORIES);
+        let check = all.check_repositories();
+        assert!(check.passed);
+        assert_eq!(check.detail, KNOWN_REPOSITORIES.join(", "));
+    }
+
+    #[test]
+    fn sheet_check_counts_rows_across_pages() {
+        let game = default_game();
+        let check = game.check_sheet("Item", Language::ChineseSimplified, 40);
+        assert!(check.passed);
+        assert_eq!(check.detail, "40 行");
+
+        let check = game.check_sheet("Item", Language::ChineseSimplified, 41);
+        assert!(!check.passed);
+        assert_eq!(check.detail, "行数 40 低于预期 41");
+
+        let check = game.check_sheet("HousingExterior", Language::None, 1);
+        assert!(!check.passed);
+        assert!(check.detail.starts_with("表读取失败"));
+    }
+
+    #[test]
+    fn sheet_check_fails_on_any_corrupt_page() {
+        let mut source = MockSource::default();
+        let pages = source.sheet_pages("Item").unwrap();
+        source.corrupt_page("Item", pages[2]);
+        let game = mock_game(PathBuf::new(), source, &["ffxiv"]);
+        let check = game.check_sheet("Item", Language::ChineseSimplified, 1);
+        assert!(!check.passed);
+        assert!(check.detail.contains(&format!("Item_{}", pages[2])));
+    }
+
+    #[test]
+    fn staining_template_and_model_checks() {
+        let game = default_game();
+        // 演示数据不含染色模板
+        let stm = game.check_staining_template();
+        assert!(!stm.passed);
+        assert!(stm.detail.contains("染色模板"));
+
+        let mdl = game.check_known_mdl();
+        assert!(mdl.passed, "{}", mdl.detail);
+        assert_eq!(mdl.detail, format!("{}: 1 个网格", KNOWN_GOOD_MDL));
+    }
+
+    #[test]
+    fn report_lists_every_check() {
+        let report = default_game().run_self_check();
+        assert_eq!(report.checks.len(), 2 + REQUIRED_SHEETS.len() + 2);
+
+        let text = report.to_text();
+        let passed = report.checks.len() - report.failed_count();
+        assert!(text.starts_with(&format!(
+            "tomestone 自检报告 (v{}): {}/{} 项通过\n",
+            env!("CARGO_PKG_VERSION"),
+            passed,
+            report.checks.len()
+        )));
+        assert_eq!(text.lines().count(), 1 + report.checks.len());
+        assert!(text.contains("[通过] 模型解析"));
+        assert!(text.contains("[失败] sqpack 目录"));
+    }
+}
@@ -18,6 +18,8 @@ pub struct MeshData {
     pub material_index: u16,
     pub bone_table_index: u16,
     pub skin_vertices: Vec<SkinVertex>,
+    /// 该网格骨骼表解析出的骨骼名，可直接用 blend_indices 索引
+    pub bone_names: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -55,6 +57,19 @@ pub fn compute_bounding_box(meshes: &[MeshData]) -> BoundingBox {
     BoundingBox { min, max }
 }
 
+/// 平移网格使包围盒中心位于原点，返回原始坐标相对新坐标的偏移 (原始 = 新 + 偏移)
+pub fn recenter_meshes(meshes: &mut [MeshData], bbox: &BoundingBox) -> [f32; 3] {
+    let center = bbox.center();
+    for mesh in meshes {
+        for v in &mut mesh.vertices {
+            for i in 0..3 {
+                v.position[i] -= center[i];
+            }
+        }
+    }
+    center
+}
+
 pub fn load_mdl(game: &GameData, path: &str) -> Result<MdlResult, String> {
     let data = game.read_file(path)?;
     parse_mdl(&data)
@@ -430,12 +445,24 @@ fn parse_mdl(data: &[u8]) -> Result<MdlResult, String> {
             indices.push(read_u16(&mut c)?);
         }
 
+        let mesh_bone_names = bone_tables
+            .get(mesh.bone_table_index as usize)
+            .map(|table| {
+                table
+                    .bone_indices
+                    .iter()
+                    .map(|&i| bone_names.get(i as usize).cloned().unwrap_or_default())
+                    .collect()
+            })
+            .unwrap_or_default();
+
         result.push(MeshData {
             vertices,
             indices,
             material_index: mesh.material_index,
             bone_table_index: mesh.bone_table_index,
             skin_vertices,
+            bone_names: mesh_bone_names,
         });
     }
 
@@ -502,10 +529,18 @@ fn half_to_f32(h: u16) -> f32 {
 }
 
 pub fn load_mdl_with_fallback(game: &GameData, paths: &[String]) -> Result<MdlResult, String> {
+    load_mdl_with_fallback_indexed(game, paths).map(|(_, result)| result)
+}
+
+/// 同 load_mdl_with_fallback，同时返回实际加载的路径在 `paths` 中的下标
+pub fn load_mdl_with_fallback_indexed(
+    game: &GameData,
+    paths: &[String],
+) -> Result<(usize, MdlResult), String> {
     let mut last_err = String::from("无候选路径");
-    for path in paths {
+    for (i, path) in paths.iter().enumerate() {
         match load_mdl(game, path) {
-            Ok(result) if !result.meshes.is_empty() => return Ok(result),
+            Ok(result) if !result.meshes.is_empty() => return Ok((i, result)),
             Ok(_) => {
                 last_err = format!("{}: 网格为空", path);
             }
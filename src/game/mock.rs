@@ -0,0 +1,701 @@
+//! 演示模式的合成数据: 没有游戏安装时也能打开全部页面
+//!
+//! 合成的 Excel 表与模型文件按真实格式的列布局/二进制布局提供，
+//! 经由与 sqpack 相同的加载器和 MDL 解析器读取
+
+use std::collections::{HashMap, HashSet};
+
+use physis::excel::Field;
+use physis::exh::EXH;
+use physis::skeleton::Skeleton;
+use physis::stm::StainingTemplate;
+use physis::Language;
+
+use tomestone_render::{TextureData, Vertex};
+
+use super::source::{GameSource, SheetRow};
+use super::ParsedMaterial;
+
+// ── 数据源 ──
+
+/// 一张表的各页: (起始行号, 该页的行)
+type SheetPages = Vec<(u32, Vec<(u32, SheetRow)>)>;
+
+pub struct MockSource {
+    sheets: HashMap<&'static str, SheetPages>,
+    /// 模拟损坏的页 (表名, 起始行号)，读取时返回错误
+    corrupt_pages: HashSet<(String, u32)>,
+}
+
+impl Default for MockSource {
+    fn default() -> Self {
+        Self {
+            sheets: mock_sheets(),
+            corrupt_pages: HashSet::new(),
+        }
+    }
+}
+
+impl MockSource {
+    /// 把某一页标记为损坏，用于测试部分页读取失败
+    #[cfg(test)]
+    pub fn corrupt_page(&mut self, sheet: &str, start_id: u32) {
+        self.corrupt_pages.insert((sheet.to_string(), start_id));
+    }
+}
+
+impl GameSource for MockSource {
+    fn is_synthetic(&self) -> bool {
+        true
+    }
+
+    fn game_version(&self) -> String {
+        "mock".to_string()
+    }
+
+    /// 只"存在"模型文件，内容为按槽位生成的长方体
+    fn read_file(&self, path: &str) -> Option<Vec<u8>> {
+        path.ends_with(".mdl").then(|| mock_mdl(path))
+    }
+
+    fn parsed_tex(&self, path: &str) -> Option<TextureData> {
+        Some(mock_texture(path))
+    }
+
+    fn parsed_mtrl(&self, path: &str) -> Option<ParsedMaterial> {
+        let stem = path.trim_end_matches(".mtrl");
+        Some(ParsedMaterial {
+            texture_paths: vec![format!("{}_d.tex", stem), format!("{}_n.tex", stem)],
+            color_table: None,
+            color_dye_table: None,
+        })
+    }
+
+    fn staining_template(&self, _path: &str) -> Result<StainingTemplate, String> {
+        Err("演示模式不含染色模板".to_string())
+    }
+
+    fn skeleton(&self, _path: &str) -> Option<Skeleton> {
+        None
+    }
+
+    /// 合成表没有表头，不在资源浏览器中列出
+    fn sheet_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn sheet_pages(&self, name: &str) -> Result<Vec<u32>, String> {
+        let pages = self
+            .sheets
+            .get(name)
+            .ok_or_else(|| format!("演示模式不含 Excel 表: {}", name))?;
+        Ok(pages.iter().map(|(start_id, _)| *start_id).collect())
+    }
+
+    fn read_sheet_page(
+        &self,
+        name: &str,
+        start_id: u32,
+        language: Language,
+    ) -> Result<Vec<(u32, SheetRow)>, String> {
+        if language == Language::English {
+            return Err(format!("演示数据只有中文: {}", name));
+        }
+        if self.corrupt_pages.contains(&(name.to_string(), start_id)) {
+            return Err(format!("{}_{} 页数据损坏", name, start_id));
+        }
+        self.sheets
+            .get(name)
+            .and_then(|pages| pages.iter().find(|(id, _)| *id == start_id))
+            .map(|(_, rows)| rows.clone())
+            .ok_or_else(|| format!("{} 表没有起始于 {} 的页", name, start_id))
+    }
+
+    fn read_excel_sheet_header(&self, name: &str) -> Result<EXH, String> {
+        Err(format!("演示模式不含 Excel 表头: {}", name))
+    }
+}
+
+// ── 贴图 ──
+
+/// 路径的稳定哈希 (FNV-1a)，用于给不同物品生成不同颜色
+fn path_hash(path: &str) -> u32 {
+    path.bytes().fold(0x811c9dc5u32, |h, b| {
+        (h ^ b as u32).wrapping_mul(0x01000193)
+    })
+}
+
+fn mock_texture(path: &str) -> TextureData {
+    const SIZE: u32 = 64;
+    // 法线贴图: 平坦法线
+    if path.ends_with("_n.tex") {
+        return TextureData {
+            rgba: [128, 128, 255, 255].repeat((SIZE * SIZE) as usize).into(),
+            width: SIZE,
+            height: SIZE,
+        };
+    }
+
+    let h = path_hash(path);
+    let base = [
+        (h & 0xFF) as u8,
+        ((h >> 8) & 0xFF) as u8,
+        ((h >> 16) & 0xFF) as u8,
+    ];
+    let is_icon = path.starts_with("ui/icon/");
+    let mut rgba = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let border = is_icon && (x < 3 || y < 3 || x >= SIZE - 3 || y >= SIZE - 3);
+            // 纵向渐变，便于在视口中分辨朝向
+            let shade = 0.6 + 0.4 * (1.0 - y as f32 / SIZE as f32);
+            if border {
+                rgba.extend_from_slice(&[40, 40, 40, 255]);
+            } else {
+                rgba.extend_from_slice(&[
+                    (base[0] as f32 * shade) as u8,
+                    (base[1] as f32 * shade) as u8,
+                    (base[2] as f32 * shade) as u8,
+                    255,
+                ]);
+            }
+        }
+    }
+    TextureData {
+        rgba: rgba.into(),
+        width: SIZE,
+        height: SIZE,
+    }
+}
+
+// ── 模型 ──
+
+/// 按槽位缩写放置一个长方体，拼起来大致是人形
+fn slot_box(path: &str) -> ([f32; 3], [f32; 3]) {
+    let abbr = path
+        .trim_end_matches(".mdl")
+        .rsplit('_')
+        .next()
+        .unwrap_or_default();
+    match abbr {
+        "met" => ([0.0, 1.62, 0.0], [0.12, 0.13, 0.12]),
+        "top" => ([0.0, 1.22, 0.0], [0.22, 0.26, 0.13]),
+        "glv" => ([0.0, 0.95, 0.0], [0.34, 0.06, 0.07]),
+        "dwn" => ([0.0, 0.62, 0.0], [0.18, 0.34, 0.11]),
+        "sho" => ([0.0, 0.1, 0.03], [0.17, 0.1, 0.14]),
+        _ => ([0.0, 1.4, 0.0], [0.08, 0.08, 0.08]),
+    }
+}
+
+fn cuboid(center: [f32; 3], half: [f32; 3]) -> (Vec<Vertex>, Vec<u16>) {
+    // (法线, 切线, 面内两个轴)
+    let faces: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ];
+    let mut vertices = Vec::with_capacity(24);
+    let mut indices = Vec::with_capacity(36);
+    for (normal, u_axis, v_axis) in faces {
+        let start = vertices.len() as u16;
+        for (su, sv) in [(-1.0f32, -1.0f32), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            let mut position = [0.0f32; 3];
+            for i in 0..3 {
+                position[i] = center[i] + (normal[i] + u_axis[i] * su + v_axis[i] * sv) * half[i];
+            }
+            vertices.push(Vertex {
+                position,
+                normal,
+                uv: [(su + 1.0) * 0.5, (1.0 - sv) * 0.5],
+                color: [0.3, 0.5, 1.0, 1.0],
+                tangent: [u_axis[0], u_axis[1], u_axis[2], 1.0],
+            });
+        }
+        indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+    (vertices, indices)
+}
+
+/// 按槽位生成长方体并编码为 MDL 文件，材质名取路径末尾的槽位缩写
+fn mock_mdl(path: &str) -> Vec<u8> {
+    let (center, half) = slot_box(path);
+    let (vertices, indices) = cuboid(center, half);
+    let abbr = path
+        .trim_end_matches(".mdl")
+        .rsplit('_')
+        .next()
+        .unwrap_or("mock");
+    encode_mdl(&vertices, &indices, &format!("/mt_mock_{}_a.mtrl", abbr))
+}
+
+/// 0..1 的浮点数量化为 u8 (MDL 的 UByte4n 格式)
+fn unorm8(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// 按 parse_mdl 读取的布局编码单网格、单 LOD、无骨骼的 MDL 文件 (v5)
+///
+/// 顶点只用一个流: 位置/法线 f32×3，UV f32×2，切线/颜色 UByte4n，步长 40
+fn encode_mdl(vertices: &[Vertex], indices: &[u16], material_name: &str) -> Vec<u8> {
+    const STRIDE: u8 = 40;
+    const VERTEX_ELEMENT_SLOTS: usize = 17;
+    const LOD_COUNT: usize = 3;
+    const LOD_SIZE: usize = 60;
+    const MESH_SIZE: usize = 36;
+    // (offset, format, usage): 位置, 法线, UV, 切线, 颜色
+    const ELEMENTS: [(u8, u8, u8); 5] = [(0, 2, 0), (12, 2, 3), (24, 1, 4), (32, 8, 6), (36, 8, 7)];
+
+    let mut out = Vec::new();
+    let put_u16 = |out: &mut Vec<u8>, v: u16| out.extend_from_slice(&v.to_le_bytes());
+    let put_u32 = |out: &mut Vec<u8>, v: u32| out.extend_from_slice(&v.to_le_bytes());
+    let put_f32 = |out: &mut Vec<u8>, v: f32| out.extend_from_slice(&v.to_le_bytes());
+
+    // 文件头: 版本, stack/runtime 大小, 顶点声明数 (每个网格一个), 材质数, 其余保留
+    put_u32(&mut out, 0x0100_0005);
+    put_u32(&mut out, 0);
+    put_u32(&mut out, 0);
+    put_u16(&mut out, 1);
+    put_u16(&mut out, 1);
+    out.resize(out.len() + 52, 0);
+
+    // 顶点声明: 17 个 8 字节槽位，stream = 0xFF 结束
+    let decl_start = out.len();
+    for (offset, format, usage) in ELEMENTS {
+        out.extend_from_slice(&[0, offset, format, usage, 0, 0, 0, 0]);
+    }
+    out.push(0xFF);
+    out.resize(decl_start + VERTEX_ELEMENT_SLOTS * 8, 0);
+
+    // 字符串块: 只有一个材质名
+    let mut strings = material_name.as_bytes().to_vec();
+    strings.push(0);
+    put_u16(&mut out, 1);
+    put_u16(&mut out, 0);
+    put_u32(&mut out, strings.len() as u32);
+    out.extend_from_slice(&strings);
+
+    // 模型头: 半径, 网格/属性/子网格/材质/骨骼/骨骼表/形态键三项计数, LOD 数, 标志位等
+    put_f32(&mut out, 1.0);
+    for count in [1u16, 0, 0, 1, 0, 0, 0, 0, 0] {
+        put_u16(&mut out, count);
+    }
+    out.extend_from_slice(&[1, 0]);
+    put_u16(&mut out, 0);
+    out.extend_from_slice(&[0, 0]);
+    out.resize(out.len() + 8, 0);
+    put_u16(&mut out, 0);
+    put_u16(&mut out, 0);
+    out.resize(out.len() + 16, 0);
+
+    // LOD 与网格之后紧跟材质名偏移 (u32)，然后是顶点数据和索引数据
+    let vertex_data_offset = (out.len() + LOD_COUNT * LOD_SIZE + MESH_SIZE + 4) as u32;
+    let index_data_offset = vertex_data_offset + vertices.len() as u32 * STRIDE as u32;
+    for lod in 0..LOD_COUNT {
+        put_u16(&mut out, 0);
+        put_u16(&mut out, u16::from(lod == 0));
+        out.resize(out.len() + 48, 0);
+        put_u32(&mut out, vertex_data_offset);
+        put_u32(&mut out, index_data_offset);
+    }
+
+    // 网格: 顶点数, 索引数, 材质/子网格/骨骼表, 起始索引, 三个流的偏移与步长, 流数
+    put_u16(&mut out, vertices.len() as u16);
+    put_u16(&mut out, 0);
+    put_u32(&mut out, indices.len() as u32);
+    for v in [0u16, 0, 0, 0] {
+        put_u16(&mut out, v);
+    }
+    put_u32(&mut out, 0);
+    for _ in 0..3 {
+        put_u32(&mut out, 0);
+    }
+    out.extend_from_slice(&[STRIDE, 0, 0, 1]);
+
+    put_u32(&mut out, 0);
+    debug_assert_eq!(out.len() as u32, vertex_data_offset);
+
+    for v in vertices {
+        for x in v.position.iter().chain(&v.normal).chain(&v.uv) {
+            put_f32(&mut out, *x);
+        }
+        for t in v.tangent {
+            out.push(unorm8((t + 1.0) * 0.5));
+        }
+        for c in v.color {
+            out.push(unorm8(c));
+        }
+    }
+    for &i in indices {
+        put_u16(&mut out, i);
+    }
+    out
+}
+
+// ── 表数据 ──
+
+const SET_NAMES: [&str; 6] = ["学徒", "青铜", "白银", "秘银", "精金", "陨铁"];
+
+/// (EquipSlotCategory, ItemUICategory, 名称后缀): 头/身/手/腿/脚
+const MOCK_GEAR: [(u8, u8, &str); 5] = [
+    (3, 34, "头盔"),
+    (4, 35, "铠甲"),
+    (5, 37, "护手"),
+    (7, 36, "护腿"),
+    (8, 38, "战靴"),
+];
+
+const MOCK_ACCESSORIES: [(u8, u8, &str); 4] = [
+    (9, 41, "耳坠"),
+    (10, 40, "项链"),
+    (11, 42, "手镯"),
+    (12, 43, "戒指"),
+];
+
+/// (row_id, 名称, ItemUICategory, 收购价)
+const MOCK_MATERIALS: [(u32, &str, u8, u32); 6] = [
+    (2001, "铜矿", 47, 2),
+    (2002, "锡矿", 47, 3),
+    (2003, "青铜锭", 48, 0),
+    (2004, "麻布", 50, 12),
+    (2005, "兽脂", 50, 8),
+    (2006, "演示代币", 50, 0),
+];
+
+/// Item 表每页的行数，使物品分布在多页上
+const ITEM_PAGE_SIZE: usize = 16;
+
+/// 演示商店: GilShop / SpecialShop 的 shop_id (ENpcData 中的取值)
+const MOCK_GIL_SHOP: u32 = 0x40001;
+const MOCK_SPECIAL_SHOP: u32 = 0x1B0001;
+
+fn text(s: &str) -> Field {
+    Field::String(s.to_string())
+}
+
+fn row_of(columns: Vec<Field>) -> SheetRow {
+    SheetRow { columns }
+}
+
+/// 长度为 `len` 的行，未指定的列填 u8 0
+fn sheet_row(len: usize, cells: impl IntoIterator<Item = (usize, Field)>) -> SheetRow {
+    let mut columns = vec![Field::UInt8(0); len];
+    for (col, value) in cells {
+        columns[col] = value;
+    }
+    SheetRow { columns }
+}
+
+/// 按行数分页，页的起始行号为该页第一行的行号
+fn paginate(rows: Vec<(u32, SheetRow)>, page_size: usize) -> SheetPages {
+    rows.chunks(page_size)
+        .map(|chunk| (chunk[0].0, chunk.to_vec()))
+        .collect()
+}
+
+fn single_page(rows: Vec<(u32, SheetRow)>) -> SheetPages {
+    let start_id = rows.first().map_or(0, |(row_id, _)| *row_id);
+    vec![(start_id, rows)]
+}
+
+/// Item 表的一行 (列布局同 parse_item_row)
+fn item_row(name: &str, filter_group: u8, ui_category: u8, icon_id: u32) -> SheetRow {
+    // 演示装备不可堆叠，其余物品按素材的 999 个一组
+    let stack_size = if filter_group == 4 { 1 } else { 999 };
+    sheet_row(
+        88,
+        [
+            (0, text(name)),
+            (8, text("演示数据")),
+            (10, Field::UInt32(icon_id)),
+            (13, Field::UInt8(filter_group)),
+            (14, Field::UInt32(0)),
+            (15, Field::UInt8(ui_category)),
+            (20, Field::UInt32(stack_size)),
+            (25, Field::UInt32(0)),
+            (26, Field::UInt32(0)),
+            (47, Field::UInt64(0)),
+        ],
+    )
+}
+
+fn mock_item_rows() -> Vec<(u32, SheetRow)> {
+    let mut rows = Vec::new();
+
+    // 6 套防具 × 5 个部位
+    for (set_idx, set_name) in SET_NAMES.iter().enumerate() {
+        let set_id = set_idx as u64 + 1;
+        for (slot_idx, (category, ui_category, suffix)) in MOCK_GEAR.iter().enumerate() {
+            let row_id = 1000 + (set_idx * 10 + slot_idx) as u32;
+            let mut row = item_row(
+                &format!("{}{}", set_name, suffix),
+                4,
+                *ui_category,
+                30000 + row_id,
+            );
+            row.columns[16] = Field::UInt8(1);
+            row.columns[17] = Field::UInt8(*category);
+            row.columns[26] = Field::UInt32(10 * set_id as u32);
+            row.columns[28] = Field::UInt8((set_idx % 3) as u8);
+            row.columns[47] = Field::UInt64(set_id | (1 << 16));
+            rows.push((row_id, row));
+        }
+    }
+
+    // 饰品
+    for (i, (category, ui_category, name)) in MOCK_ACCESSORIES.iter().enumerate() {
+        let row_id = 1100 + i as u32;
+        let mut row = item_row(&format!("演示{}", name), 4, *ui_category, 30000 + row_id);
+        row.columns[17] = Field::UInt8(*category);
+        row.columns[47] = Field::UInt64(1 | (1 << 16));
+        rows.push((row_id, row));
+    }
+
+    // 素材
+    for (row_id, name, ui_category, price_mid) in MOCK_MATERIALS {
+        let mut row = item_row(name, 12, ui_category, 30000 + row_id);
+        row.columns[16] = Field::UInt8(1);
+        row.columns[25] = Field::UInt32(price_mid);
+        rows.push((row_id, row));
+    }
+    rows
+}
+
+fn mock_stain_rows() -> Vec<(u32, SheetRow)> {
+    [
+        (1, 0xE4DFD0, 2, "素雪白"),
+        (2, 0x2B2929, 2, "煤烟黑"),
+        (3, 0xE63E25, 4, "烈焰红"),
+        (4, 0xDAB342, 6, "金雀黄"),
+        (5, 0x487A3E, 8, "森林绿"),
+        (6, 0x6090C7, 9, "天空蓝"),
+    ]
+    .into_iter()
+    .map(|(id, color, shade, name)| {
+        let row = row_of(vec![Field::UInt32(color), Field::UInt8(shade), text(name)]);
+        (id, row)
+    })
+    .collect()
+}
+
+/// Recipe 表的一行 (列布局同 parse_recipe_row)
+fn recipe_row(
+    craft_type: i32,
+    level_table: u16,
+    result: u32,
+    ingredients: &[(u32, u8)],
+    secret_book: i32,
+    quality_factor: u8,
+) -> SheetRow {
+    let mut row = sheet_row(
+        44,
+        [
+            (1, Field::Int32(craft_type)),
+            (2, Field::UInt16(level_table)),
+            (4, Field::Int32(result as i32)),
+            (5, Field::UInt8(1)),
+            (31, Field::UInt8(quality_factor)),
+            (35, Field::UInt32(0)),
+            (40, Field::Int32(secret_book)),
+            (43, Field::Bool(true)),
+        ],
+    );
+    for i in 0..8 {
+        let (id, amount) = ingredients.get(i).copied().unwrap_or((0, 0));
+        row.columns[6 + i * 2] = Field::Int32(id as i32);
+        row.columns[7 + i * 2] = Field::UInt8(amount);
+    }
+    row
+}
+
+fn mock_recipe_rows() -> Vec<(u32, SheetRow)> {
+    // 青铜锭 (锻铁匠) -> 青铜防具 (铸甲匠)，构成两层合成树
+    let mut rows = vec![(1, recipe_row(1, 1, 2003, &[(2001, 3), (2002, 1)], 0, 0))];
+    for slot_idx in 0..MOCK_GEAR.len() {
+        let secret_book = if slot_idx == 1 { 1 } else { 0 };
+        rows.push((
+            2 + slot_idx as u32,
+            recipe_row(
+                2,
+                2,
+                1010 + slot_idx as u32,
+                &[(2003, 2), (2004, 1), (2005, 1)],
+                secret_book,
+                50,
+            ),
+        ));
+    }
+    rows
+}
+
+/// 只有 col[0] 名称的表
+fn name_rows<'a>(names: impl IntoIterator<Item = (u32, &'a str)>) -> Vec<(u32, SheetRow)> {
+    names
+        .into_iter()
+        .map(|(id, name)| (id, row_of(vec![text(name)])))
+        .collect()
+}
+
+/// Level 表的一行: X, Y, Z, Yaw, Radius, Type, Object, Territory, Map
+fn level_row(x: f32, z: f32, obj_type: u8, object: u32, territory: u16) -> SheetRow {
+    row_of(vec![
+        Field::Float32(x),
+        Field::Float32(0.0),
+        Field::Float32(z),
+        Field::Float32(0.0),
+        Field::Float32(1.0),
+        Field::UInt8(obj_type),
+        Field::UInt32(object),
+        Field::UInt16(territory),
+        Field::UInt16(0),
+    ])
+}
+
+/// 演示商店链: NPC (ENpcResident/ENpcBase) -> 位置 (Level/TerritoryType/PlaceName)
+/// -> 最近的以太之光 (Aetheryte)，以及金币商店与兑换商店的商品
+fn mock_shop_sheets(sheets: &mut HashMap<&'static str, SheetPages>) {
+    const TERRITORY: u16 = 100;
+    const NPC_GROCER: u32 = 1001;
+    const NPC_EXCHANGE: u32 = 1002;
+    const AETHERYTE_LEVEL: u32 = 5001;
+
+    sheets.insert(
+        "PlaceName",
+        single_page(name_rows([(1, "演示城"), (2, "演示广场")])),
+    );
+    sheets.insert(
+        "TerritoryType",
+        single_page(vec![(TERRITORY as u32, row_of(vec![Field::UInt16(1)]))]),
+    );
+    sheets.insert(
+        "Aetheryte",
+        single_page(vec![(
+            1,
+            sheet_row(
+                16,
+                [
+                    (8, Field::UInt16(2)),
+                    (11, Field::UInt32(AETHERYTE_LEVEL)),
+                    (15, Field::Bool(true)),
+                ],
+            ),
+        )]),
+    );
+    sheets.insert(
+        "Level",
+        single_page(vec![
+            (5000, level_row(10.0, 10.0, 8, NPC_GROCER, TERRITORY)),
+            (5001, level_row(0.0, 0.0, 0, 0, TERRITORY)),
+            (5002, level_row(-10.0, 5.0, 8, NPC_EXCHANGE, TERRITORY)),
+        ]),
+    );
+    sheets.insert(
+        "ENpcResident",
+        single_page(name_rows([
+            (NPC_GROCER, "演示杂货商"),
+            (NPC_EXCHANGE, "演示兑换员"),
+        ])),
+    );
+    sheets.insert(
+        "ENpcBase",
+        single_page(vec![
+            (NPC_GROCER, row_of(vec![Field::UInt32(MOCK_GIL_SHOP)])),
+            (NPC_EXCHANGE, row_of(vec![Field::UInt32(MOCK_SPECIAL_SHOP)])),
+        ]),
+    );
+
+    // 金币商店: 分类名为空时商店名取 NPC 名；商品为子行，共用商店 id 作行号
+    sheets.insert("GilShop", single_page(name_rows([(MOCK_GIL_SHOP, "")])));
+    sheets.insert(
+        "GilShopItem",
+        single_page(
+            [2004, 2005]
+                .into_iter()
+                .map(|item_id| {
+                    let row = row_of(vec![Field::Int32(item_id)]);
+                    (MOCK_GIL_SHOP, row)
+                })
+                .collect(),
+        ),
+    );
+
+    // 兑换商店: 槽位 0 用 5 个演示代币换陨铁头盔
+    sheets.insert(
+        "SpecialShop",
+        single_page(vec![(
+            MOCK_SPECIAL_SHOP,
+            sheet_row(
+                302,
+                [
+                    (0, text("演示兑换")),
+                    (1, Field::Int32(1050)),
+                    (241, Field::Int32(2006)),
+                    (301, Field::UInt32(5)),
+                ],
+            ),
+        )]),
+    );
+}
+
+/// 演示模式的全部合成表，列布局与对应加载器读取的列一致
+fn mock_sheets() -> HashMap<&'static str, SheetPages> {
+    let mut sheets = HashMap::new();
+    sheets.insert("Item", paginate(mock_item_rows(), ITEM_PAGE_SIZE));
+    sheets.insert("Stain", single_page(mock_stain_rows()));
+    sheets.insert("Recipe", single_page(mock_recipe_rows()));
+    sheets.insert(
+        "RecipeLevelTable",
+        single_page(vec![
+            (1, row_of(vec![Field::UInt8(10)])),
+            (2, row_of(vec![Field::UInt8(15)])),
+        ]),
+    );
+    sheets.insert(
+        "SecretRecipeBook",
+        single_page(vec![(
+            1,
+            row_of(vec![Field::Int32(3001), text("演示秘籍")]),
+        )]),
+    );
+    sheets.insert(
+        "ItemUICategory",
+        single_page(name_rows([
+            (34, "头部防具"),
+            (35, "身体防具"),
+            (36, "腿部防具"),
+            (37, "手部防具"),
+            (38, "脚部防具"),
+            (40, "项链"),
+            (41, "耳饰"),
+            (42, "手镯"),
+            (43, "戒指"),
+            (47, "矿石"),
+            (48, "金属"),
+            (50, "布料"),
+        ])),
+    );
+    sheets.insert(
+        "ClassJob",
+        single_page(name_rows([
+            (8, "刻木匠"),
+            (9, "锻铁匠"),
+            (10, "铸甲匠"),
+            (11, "雕金匠"),
+            (12, "制革匠"),
+            (13, "裁衣匠"),
+            (14, "炼金术士"),
+            (15, "烹调师"),
+        ])),
+    );
+    sheets.insert(
+        "GatheringItem",
+        single_page(vec![
+            (1, row_of(vec![Field::Int32(2001)])),
+            (2, row_of(vec![Field::Int32(2002)])),
+        ]),
+    );
+    mock_shop_sheets(&mut sheets);
+    sheets
+}
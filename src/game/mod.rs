@@ -1,27 +1,50 @@
+mod anchor;
+mod diagnostics;
 mod mdl;
+mod mock;
+mod repository;
 mod sgb;
 mod skeleton;
+mod source;
 mod tex;
 
-pub use mdl::{compute_bounding_box, load_mdl, load_mdl_with_fallback, MdlBoneTable, MeshData};
+pub use anchor::{
+    accessory_anchor_meshes, anchor_textures, has_accessory_anchor, race_code_of_model_path,
+};
+pub use diagnostics::{DiagnosticCheck, DiagnosticReport};
+pub use mdl::{
+    compute_bounding_box, load_mdl, load_mdl_with_fallback, load_mdl_with_fallback_indexed,
+    recenter_meshes, MdlBoneTable, MdlResult, MeshData, SkinVertex,
+};
+pub use repository::{expansion_for_path, KNOWN_REPOSITORIES};
 pub use sgb::extract_mdl_paths_from_sgb;
-pub use skeleton::{apply_skinning, SkeletonCache};
+pub use skeleton::{apply_skinning, place_unskinned_at_bone, SkeletonCache, SkinningReport};
+pub use source::{GameSource, SheetRow};
 pub use tex::{
-    bake_color_table_texture, load_housing_mesh_textures, load_mesh_textures, CachedMaterial,
+    bake_color_table_texture, count_missing, load_housing_mesh_textures, load_mesh_textures,
+    probe_dye_channels, resolve_color_table_rows, CachedMaterial, MaterialDiagnostic,
+    MaterialLoadResult, TextureSource,
 };
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashSet, VecDeque};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use physis::excel::{Field, Row};
+use mock::MockSource;
+use source::SqPackSource;
+
+use physis::excel::Field;
 use physis::mtrl::{ColorDyeTable, ColorTable};
-use physis::resource::{Resource as _, SqPackResource};
 use physis::stm::StainingTemplate;
 use physis::Language;
 
 use tomestone_render::TextureData;
 
-use crate::domain::{GameItem, ItemSource, Recipe, StainEntry};
+use crate::domain::{
+    gc_seal_item_id, CollectionInfo, EquipRestriction, GameItem, HousingSize, ItemSource, Recipe,
+    StainEntry,
+};
 
 pub struct ParsedMaterial {
     pub texture_paths: Vec<String>,
@@ -29,6 +52,68 @@ pub struct ParsedMaterial {
     pub color_dye_table: Option<ColorDyeTable>,
 }
 
+/// 部分页读取失败的表 (其余页的行照常加载)
+#[derive(Clone, Debug)]
+pub struct PartialSheetLoad {
+    pub sheet: String,
+    pub loaded_pages: usize,
+    pub total_pages: usize,
+    /// 失败页的名称 (`表名_起始行号`)
+    pub failed_pages: Vec<String>,
+}
+
+impl PartialSheetLoad {
+    /// 如 "Item 表: 38/40 页加载成功 (失败: Item_12000, Item_12500)"
+    pub fn summary(&self) -> String {
+        format!(
+            "{} 表: {}/{} 页加载成功 (失败: {})",
+            self.sheet,
+            self.loaded_pages,
+            self.total_pages,
+            self.failed_pages.join(", ")
+        )
+    }
+}
+
+/// ENpcData 中各类商店/话题的 id 范围 (高位为事件处理器类型)
+const GILSHOP_ID_MIN: u32 = 0x40000;
+const GILSHOP_ID_MAX: u32 = 0x160000;
+const SPECIALSHOP_ID_MIN: u32 = 0x1B0000;
+const SPECIALSHOP_ID_MAX: u32 = 0x1C0000;
+const TOPIC_ID_MIN: u32 = 0x320000;
+const TOPIC_ID_MAX: u32 = 0x360000;
+
+/// 提供商店的 NPC 及其位置
+#[derive(Debug, Clone)]
+pub struct ShopNpc {
+    pub name: String,
+    pub location: Option<String>,
+    /// NPC 所在区域中最近的以太之光
+    pub nearest_aetheryte: Option<String>,
+}
+
+/// shop_id (GilShop / SpecialShop) -> 提供该商店的 NPC，多个 NPC 时取第一个
+#[derive(Debug, Clone, Default)]
+pub struct ShopNpcIndex {
+    shops: std::collections::HashMap<u32, ShopNpc>,
+}
+
+impl ShopNpcIndex {
+    pub fn get(&self, shop_id: u32) -> Option<&ShopNpc> {
+        self.shops.get(&shop_id)
+    }
+}
+
+/// 从驻留池取出相同内容的字符串，没有则加入
+fn intern(pool: &mut HashSet<Arc<str>>, s: &str) -> Arc<str> {
+    if let Some(existing) = pool.get(s) {
+        return existing.clone();
+    }
+    let interned: Arc<str> = Arc::from(s);
+    pool.insert(interned.clone());
+    interned
+}
+
 pub fn validate_install_dir(install_dir: &Path) -> Result<(), String> {
     let sqpack = install_dir.join("game").join("sqpack");
     if !sqpack.is_dir() {
@@ -37,52 +122,219 @@ pub fn validate_install_dir(install_dir: &Path) -> Result<(), String> {
     Ok(())
 }
 
+/// 缺失路径缓存的容量，超出时淘汰最早记录的路径
+pub const MISSING_PATH_CAPACITY: usize = 8192;
+
+/// 读取方式: 同一路径按原始文件读取成功、按贴图解析失败时，两者分别记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReadKind {
+    File,
+    Tex,
+    Mtrl,
+    Skeleton,
+}
+
+/// 读取失败的路径 (负查找缓存)
+///
+/// 同一 GameData 存续期间文件不会变化，按种族回退的模型路径、缺失的高清图标等
+/// 反复探测的路径第二次起直接跳过数据源。游戏更新后重新加载会重建 GameData，缓存随之清空
+#[derive(Default)]
+struct MissingPaths {
+    /// 按 ReadKind 分开的路径集合
+    paths: [HashSet<String>; 4],
+    /// 记录顺序，用于容量淘汰
+    order: VecDeque<(ReadKind, String)>,
+}
+
+impl MissingPaths {
+    fn contains(&self, kind: ReadKind, path: &str) -> bool {
+        self.paths[kind as usize].contains(path)
+    }
+
+    fn insert(&mut self, kind: ReadKind, path: &str) {
+        if !self.paths[kind as usize].insert(path.to_string()) {
+            return;
+        }
+        self.order.push_back((kind, path.to_string()));
+        while self.order.len() > MISSING_PATH_CAPACITY {
+            if let Some((kind, oldest)) = self.order.pop_front() {
+                self.paths[kind as usize].remove(&oldest);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    fn clear(&mut self) {
+        self.paths.iter_mut().for_each(HashSet::clear);
+        self.order.clear();
+    }
+}
+
+/// 文件读取统计，显示在自检报告中
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadStats {
+    /// 实际交给数据源的路径读取次数
+    pub probes: u64,
+    /// 命中缺失缓存而跳过的次数
+    pub skipped: u64,
+    /// 当前记录的缺失路径数
+    pub missing: usize,
+}
+
 pub struct GameData {
     game_dir: PathBuf,
-    physis: RefCell<SqPackResource>,
+    source: Box<dyn GameSource>,
+    /// 已安装的数据仓库 (ffxiv, ex1, ...)
+    repositories: Vec<String>,
+    /// 贴图缺失时使用棋盘格而非纯白
+    checkerboard_missing: Cell<bool>,
+    /// 加载过程中部分页读取失败的表，由加载线程取走汇报
+    partial_loads: RefCell<Vec<PartialSheetLoad>>,
+    /// 读取失败的路径，再次读取时直接返回失败
+    missing_paths: RefCell<MissingPaths>,
+    /// 实际交给数据源的路径读取次数
+    read_probes: Cell<u64>,
+    /// 命中缺失缓存而跳过的读取次数
+    read_skipped: Cell<u64>,
 }
 
 impl GameData {
     pub fn new(install_dir: &Path) -> Self {
         let game_dir = install_dir.join("game");
-        let physis = RefCell::new(SqPackResource::from_existing(game_dir.to_str().unwrap()));
-        Self { game_dir, physis }
+        let source = Box::new(SqPackSource::new(&game_dir));
+        let repositories = repository::installed_repositories(&game_dir.join("sqpack"));
+        Self::with_source(game_dir, source, repositories)
+    }
+
+    /// 演示模式: 使用内置合成数据，不需要游戏安装
+    pub fn mock() -> Self {
+        Self::with_source(
+            crate::config::data_root().join("mock").join("game"),
+            Box::new(MockSource::default()),
+            vec!["ffxiv".to_string()],
+        )
+    }
+
+    fn with_source(
+        game_dir: PathBuf,
+        source: Box<dyn GameSource>,
+        repositories: Vec<String>,
+    ) -> Self {
+        Self {
+            game_dir,
+            source,
+            repositories,
+            checkerboard_missing: Cell::new(false),
+            partial_loads: RefCell::new(Vec::new()),
+            missing_paths: RefCell::new(MissingPaths::default()),
+            read_probes: Cell::new(0),
+            read_skipped: Cell::new(0),
+        }
+    }
+
+    pub fn is_mock(&self) -> bool {
+        self.source.is_synthetic()
+    }
+
+    pub fn checkerboard_missing_textures(&self) -> bool {
+        self.checkerboard_missing.get()
+    }
+
+    pub fn set_checkerboard_missing_textures(&self, enabled: bool) {
+        self.checkerboard_missing.set(enabled);
+    }
+
+    /// 安装目录 (game 目录的上一级)
+    pub fn install_dir(&self) -> PathBuf {
+        self.game_dir
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| self.game_dir.clone())
     }
 
     pub fn sqpack_dir(&self) -> PathBuf {
         self.game_dir.join("sqpack")
     }
 
+    /// 游戏版本号 (game/ffxivgame.ver)，读取失败返回 "unknown"
+    pub fn game_version(&self) -> String {
+        self.source.game_version()
+    }
+
+    /// 已安装的数据仓库，按资料片顺序
+    pub fn repositories(&self) -> &[String] {
+        &self.repositories
+    }
+
+    /// 未安装的已知资料片仓库
+    pub fn missing_expansions(&self) -> Vec<&'static str> {
+        KNOWN_REPOSITORIES
+            .iter()
+            .copied()
+            .filter(|repo| !self.repositories.iter().any(|r| r == repo))
+            .collect()
+    }
+
+    /// 经过缺失缓存读取: 已知缺失的路径直接返回 None，读取失败时记录该路径
+    fn probe_path<T>(
+        &self,
+        kind: ReadKind,
+        path: &str,
+        read: impl FnOnce() -> Option<T>,
+    ) -> Option<T> {
+        if self.missing_paths.borrow().contains(kind, path) {
+            self.read_skipped.set(self.read_skipped.get() + 1);
+            return None;
+        }
+        self.read_probes.set(self.read_probes.get() + 1);
+        let result = read();
+        if result.is_none() {
+            self.missing_paths.borrow_mut().insert(kind, path);
+        }
+        result
+    }
+
+    pub fn read_stats(&self) -> ReadStats {
+        ReadStats {
+            probes: self.read_probes.get(),
+            skipped: self.read_skipped.get(),
+            missing: self.missing_paths.borrow().len(),
+        }
+    }
+
+    /// 清空缺失路径缓存，之后的读取重新访问数据源
+    pub fn clear_missing_paths(&self) {
+        self.missing_paths.borrow_mut().clear();
+    }
+
     pub fn read_file(&self, path: &str) -> Result<Vec<u8>, String> {
-        self.physis
-            .borrow_mut()
-            .read(path)
-            .ok_or_else(|| format!("physis 无法读取: {}", path))
+        self.probe_path(ReadKind::File, path, || self.source.read_file(path))
+            .ok_or_else(|| {
+                // 资料片未安装时给出明确原因，而不是笼统的读取失败
+                let missing_repo = expansion_for_path(path)
+                    .filter(|ex| !self.repositories.iter().any(|r| r == ex));
+                match missing_repo {
+                    Some(ex) => format!("该文件属于 {}，但未检测到 {} 数据: {}", ex, ex, path),
+                    None => format!("无法读取: {}", path),
+                }
+            })
     }
 
     pub fn parsed_tex(&self, path: &str) -> Option<TextureData> {
-        let tex: physis::tex::Texture = self.physis.borrow_mut().parsed(path).ok()?;
-        Some(TextureData {
-            rgba: tex.rgba.into(),
-            width: tex.width,
-            height: tex.height,
-        })
+        self.probe_path(ReadKind::Tex, path, || self.source.parsed_tex(path))
     }
 
     pub fn parsed_mtrl(&self, path: &str) -> Option<ParsedMaterial> {
-        let mtrl: physis::mtrl::Material = self.physis.borrow_mut().parsed(path).ok()?;
-        Some(ParsedMaterial {
-            texture_paths: mtrl.texture_paths,
-            color_table: mtrl.color_table,
-            color_dye_table: mtrl.color_dye_table,
-        })
+        self.probe_path(ReadKind::Mtrl, path, || self.source.parsed_mtrl(path))
     }
 
     pub fn load_staining_template(&self) -> Option<StainingTemplate> {
-        let stm: StainingTemplate = self
-            .physis
-            .borrow_mut()
-            .parsed("chara/base_material/stainingtemplate.stm")
+        let stm = self
+            .source
+            .staining_template("chara/base_material/stainingtemplate.stm")
             .ok()?;
         println!("STM 加载成功: {} 个模板", stm.entries.len());
         Some(stm)
@@ -93,64 +345,136 @@ impl GameData {
             "chara/human/{}/skeleton/base/b0001/skl_{}b0001.sklb",
             race_code, race_code
         );
-        self.physis.borrow_mut().parsed(&path).ok()
+        self.probe_path(ReadKind::Skeleton, &path, || self.source.skeleton(&path))
     }
 
     pub fn get_all_sheet_names(&self) -> Vec<String> {
-        self.physis
-            .borrow_mut()
-            .get_all_sheet_names()
-            .unwrap_or_default()
+        self.source.sheet_names()
     }
 
+    /// 原始表头 (资源浏览器用)，演示模式下没有
     pub fn read_excel_header(&self, name: &str) -> Option<physis::exh::EXH> {
-        self.physis.borrow_mut().read_excel_sheet_header(name).ok()
+        self.source.read_excel_sheet_header(name).ok()
     }
 
-    pub fn read_excel_sheet(
+    /// 读取整张表的全部行 (资源浏览器、自检用)，任一页失败即返回错误
+    pub fn read_sheet_rows(
         &self,
-        exh: &physis::exh::EXH,
         name: &str,
         language: Language,
-    ) -> Option<physis::excel::Sheet> {
-        self.physis
-            .borrow_mut()
-            .read_excel_sheet(exh, name, language)
-            .ok()
+    ) -> Result<Vec<(u32, SheetRow)>, String> {
+        let mut rows = Vec::new();
+        self.for_each_row(name, language, |row_id, row| {
+            rows.push((row_id, row.clone()))
+        })?;
+        Ok(rows)
     }
 
-    /// 一次性加载 Item 表全部物品，返回统一的 GameItem 列表
-    pub fn load_all_items(&self) -> Vec<GameItem> {
-        let mut physis = self.physis.borrow_mut();
-
-        let exh = match physis.read_excel_sheet_header("Item") {
-            Ok(h) => h,
-            Err(e) => {
-                eprintln!("无法加载 Item 表头: {}", e);
-                return Vec::new();
+    /// 逐页读取整张表: 每页的行处理完即释放该页数据再读下一页，
+    /// 解析结果增长时不与整表原始数据同时驻留。任一页失败即返回错误
+    fn for_each_row(
+        &self,
+        name: &str,
+        language: Language,
+        mut f: impl FnMut(u32, &SheetRow),
+    ) -> Result<(), String> {
+        for start_id in self.source.sheet_pages(name)? {
+            for (row_id, row) in self.source.read_sheet_page(name, start_id, language)? {
+                f(row_id, &row);
             }
-        };
+        }
+        Ok(())
+    }
 
-        let sheet = match physis.read_excel_sheet(&exh, "Item", Language::ChineseSimplified) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("无法加载 Item 表: {}", e);
-                return Vec::new();
+    /// 容错的 for_each_row: 跳过无法解析的页 (如补丁未完成的安装)，其余页的行照常处理，
+    /// 有失败页时记录到 partial_loads；所有页都失败时返回错误
+    fn for_each_row_tolerant(
+        &self,
+        name: &str,
+        language: Language,
+        mut f: impl FnMut(u32, &SheetRow),
+    ) -> Result<(), String> {
+        let pages = self.source.sheet_pages(name)?;
+        let mut failed_pages = Vec::new();
+        let mut last_err = String::new();
+        for &start_id in &pages {
+            match self.source.read_sheet_page(name, start_id, language) {
+                Ok(rows) => {
+                    for (row_id, row) in rows {
+                        f(row_id, &row);
+                    }
+                }
+                Err(e) => {
+                    let page_name = format!("{}_{}", name, start_id);
+                    eprintln!("无法读取 {} 页: {}", page_name, e);
+                    failed_pages.push(page_name);
+                    last_err = e;
+                }
             }
+        }
+        if failed_pages.is_empty() {
+            return Ok(());
+        }
+        if failed_pages.len() == pages.len() {
+            return Err(last_err);
+        }
+
+        let load = PartialSheetLoad {
+            sheet: name.to_string(),
+            loaded_pages: pages.len() - failed_pages.len(),
+            total_pages: pages.len(),
+            failed_pages,
         };
+        eprintln!("{}", load.summary());
+        // 同一张表可能被多个加载器读取，只记录一次
+        let mut loads = self.partial_loads.borrow_mut();
+        if !loads.iter().any(|l| l.sheet == load.sheet) {
+            loads.push(load);
+        }
+        Ok(())
+    }
 
+    /// 取走加载过程中记录的部分失败表
+    pub fn take_partial_loads(&self) -> Vec<PartialSheetLoad> {
+        std::mem::take(&mut *self.partial_loads.borrow_mut())
+    }
+
+    /// 一次性加载 Item 表全部物品，返回统一的 GameItem 列表
+    ///
+    /// `ui_category_names` 为 load_ui_category_names 的结果，用于标记面饰
+    pub fn load_all_items(
+        &self,
+        ui_category_names: &std::collections::HashMap<u8, String>,
+    ) -> Vec<GameItem> {
         let mut items = Vec::new();
-        for page in &sheet.pages {
-            for (row_id, row) in page.into_iter().flatten_subrows() {
-                if let Some(item) = Self::parse_item_row(row_id, row) {
+        let mut descriptions = HashSet::new();
+        let result =
+            self.for_each_row_tolerant("Item", Language::ChineseSimplified, |row_id, row| {
+                if let Some(item) = Self::parse_item_row(row_id, row, &mut descriptions) {
                     items.push(item);
                 }
+            });
+        if let Err(e) = result {
+            eprintln!("无法加载 Item 表: {}", e);
+            return Vec::new();
+        }
+        // 面饰的分类 row_id 随版本变化，按名称在 ItemUICategory 中查找。
+        // 名称匹配依赖各表固定以 Language::ChineseSimplified 读取 (见 load_ui_category_names)
+        if let Some(category) = crate::domain::facewear_ui_category(ui_category_names) {
+            for item in &mut items {
+                item.facewear = item.equip_slot_category == 0 && item.item_ui_category == category;
             }
         }
+        items.shrink_to_fit();
         items
     }
 
-    fn parse_item_row(row_id: u32, row: &Row) -> Option<GameItem> {
+    /// `descriptions` 为描述文本的驻留池，相同描述的物品共享同一份字符串
+    fn parse_item_row(
+        row_id: u32,
+        row: &SheetRow,
+        descriptions: &mut HashSet<Arc<str>>,
+    ) -> Option<GameItem> {
         // Item 表列索引 (通过 debug dump 确认)
         const COL_NAME: usize = 0;
         const COL_DESCRIPTION: usize = 8;
@@ -160,9 +484,28 @@ impl GameData {
         const COL_ITEM_UI_CATEGORY: usize = 15;
         const COL_ITEM_SEARCH_CATEGORY: usize = 16;
         const COL_EQUIP_SLOT_CATEGORY: usize = 17;
+        const COL_STACK_SIZE: usize = 20;
         const COL_PRICE_MID: usize = 25;
         const COL_PRICE_LOW: usize = 26;
+        const COL_DYE_COUNT: usize = 28;
+        const COL_CLASS_JOB_REPAIR: usize = 33;
+        const COL_ITEM_REPAIR: usize = 34;
+        const COL_EQUIP_RESTRICTION: usize = 42;
         const COL_MODEL_MAIN: usize = 47;
+        const COL_BLOCK_RATE: usize = 55;
+        const COL_BLOCK: usize = 56;
+        const COL_DEFENSE_PHYS: usize = 57;
+        const COL_DEFENSE_MAG: usize = 58;
+        // BaseParam[6] / BaseParamValue[6] 交错排列
+        const COL_BASE_PARAM: usize = 59;
+        const COL_ITEM_SPECIAL_BONUS: usize = 71;
+        // BaseParam{Special}[6] / BaseParamValue{Special}[6]，ItemSpecialBonus = 1 时为 HQ 加成
+        const COL_BASE_PARAM_SPECIAL: usize = 73;
+        const BASE_PARAM_COUNT: usize = 6;
+        const SPECIAL_BONUS_HQ: u8 = 1;
+        const COL_MATERIALIZE_TYPE: usize = 85;
+        const COL_MATERIA_SLOT_COUNT: usize = 86;
+        const COL_ADVANCED_MELDING: usize = 87;
 
         let name = match row.columns.get(COL_NAME)? {
             Field::String(s) => {
@@ -175,8 +518,8 @@ impl GameData {
         };
 
         let description = match row.columns.get(COL_DESCRIPTION) {
-            Some(Field::String(s)) => s.clone(),
-            _ => String::new(),
+            Some(Field::String(s)) => intern(descriptions, s),
+            _ => intern(descriptions, ""),
         };
 
         let icon_id = match row.columns.get(COL_ICON) {
@@ -211,6 +554,12 @@ impl GameData {
             _ => 0,
         };
 
+        let stack_size = match row.columns.get(COL_STACK_SIZE) {
+            Some(Field::UInt32(v)) => *v,
+            Some(Field::UInt16(v)) => *v as u32,
+            _ => 1,
+        };
+
         let price_mid = match row.columns.get(COL_PRICE_MID) {
             Some(Field::UInt32(v)) => *v,
             _ => 0,
@@ -221,115 +570,183 @@ impl GameData {
             _ => 0,
         };
 
+        // 7.0 起为 DyeCount (u8)，之前为 IsDyeable (bool)
+        let sheet_dye_count = match row.columns.get(COL_DYE_COUNT) {
+            Some(Field::UInt8(v)) if *v <= 2 => Some(*v),
+            Some(Field::Bool(b)) => Some(*b as u8),
+            _ => None,
+        };
+
+        let equip_restriction = match row.columns.get(COL_EQUIP_RESTRICTION) {
+            Some(Field::UInt8(v)) => EquipRestriction::from_raw(*v),
+            _ => EquipRestriction::Unrestricted,
+        };
+
         let model_main = match row.columns.get(COL_MODEL_MAIN) {
             Some(Field::UInt64(v)) => *v,
             _ => 0,
         };
 
+        let u8_column = |col: usize| match row.columns.get(col) {
+            Some(Field::UInt8(v)) => *v,
+            _ => 0,
+        };
+        let repair_class_job = u8_column(COL_CLASS_JOB_REPAIR);
+        let repair_resource = u8_column(COL_ITEM_REPAIR);
+        let materialize_type = u8_column(COL_MATERIALIZE_TYPE);
+        let materia_slot_count = u8_column(COL_MATERIA_SLOT_COUNT);
+        let advanced_melding = matches!(
+            row.columns.get(COL_ADVANCED_MELDING),
+            Some(Field::Bool(true))
+        );
+
+        let u16_column = |col: usize| match row.columns.get(col) {
+            Some(Field::UInt16(v)) => *v,
+            Some(Field::UInt8(v)) => *v as u16,
+            _ => 0,
+        };
+        let block_rate = u16_column(COL_BLOCK_RATE);
+        let block = u16_column(COL_BLOCK);
+        let defense_phys = u16_column(COL_DEFENSE_PHYS);
+        let defense_mag = u16_column(COL_DEFENSE_MAG);
+
+        // 属性对: 参数为 0 或数值为 0 的槽位视为空；列不存在时 get 返回 None，同样跳过
+        let param_pairs = |start: usize| -> Vec<(u8, i16)> {
+            (0..BASE_PARAM_COUNT)
+                .filter_map(|i| {
+                    let param = match row.columns.get(start + i * 2)? {
+                        Field::UInt8(v) => *v,
+                        _ => return None,
+                    };
+                    let value = match row.columns.get(start + i * 2 + 1)? {
+                        Field::Int16(v) => *v,
+                        Field::UInt16(v) => i16::try_from(*v).ok()?,
+                        _ => return None,
+                    };
+                    (param != 0 && value != 0).then_some((param, value))
+                })
+                .collect()
+        };
+        let base_params = param_pairs(COL_BASE_PARAM);
+        let hq_base_params = if u8_column(COL_ITEM_SPECIAL_BONUS) == SPECIAL_BONUS_HQ {
+            param_pairs(COL_BASE_PARAM_SPECIAL)
+        } else {
+            Vec::new()
+        };
+
         Some(GameItem {
             row_id,
+            search_key: name.to_lowercase(),
             name,
             icon_id,
             filter_group,
             item_ui_category,
             equip_slot_category,
+            facewear: false,
             model_main,
             additional_data,
             description,
             price_mid,
             price_low,
             item_search_category,
+            stack_size,
+            sheet_dye_count,
+            equip_restriction,
+            repair_class_job,
+            repair_resource,
+            materia_slot_count,
+            advanced_melding,
+            materialize_type,
+            base_params,
+            hq_base_params,
+            defense_phys,
+            defense_mag,
+            block_rate,
+            block,
         })
     }
 
     /// 加载 HousingExterior 表的 SGB 路径映射
     /// 返回 HousingExterior row_id -> SGB 路径列表
     pub fn load_housing_sgb_paths(&self) -> std::collections::HashMap<u32, Vec<String>> {
-        let mut physis = self.physis.borrow_mut();
-
-        let ext_exh = match physis.read_excel_sheet_header("HousingExterior") {
-            Ok(h) => h,
-            Err(e) => {
-                eprintln!("无法加载 HousingExterior 表头: {}", e);
-                return std::collections::HashMap::new();
-            }
-        };
-        let ext_sheet = match physis.read_excel_sheet(&ext_exh, "HousingExterior", Language::None) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("无法加载 HousingExterior 表: {}", e);
-                return std::collections::HashMap::new();
-            }
-        };
-
         let mut sgb_paths: std::collections::HashMap<u32, Vec<String>> =
             std::collections::HashMap::new();
-        for page in &ext_sheet.pages {
-            for (row_id, row) in page.into_iter().flatten_subrows() {
-                let mut paths = Vec::new();
-                for col in &row.columns {
-                    if let Field::String(s) = col {
-                        if !s.is_empty() && s.ends_with(".sgb") {
-                            paths.push(s.clone());
-                        }
+        let result = self.for_each_row("HousingExterior", Language::None, |row_id, row| {
+            let mut paths = Vec::new();
+            for col in &row.columns {
+                if let Field::String(s) = col {
+                    if !s.is_empty() && s.ends_with(".sgb") {
+                        paths.push(s.clone());
                     }
                 }
-                if !paths.is_empty() {
-                    sgb_paths.insert(row_id, paths);
-                }
             }
+            if !paths.is_empty() {
+                sgb_paths.insert(row_id, paths);
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("无法加载 HousingExterior 表: {}", e);
+            return std::collections::HashMap::new();
         }
         println!("HousingExterior 表: {} 条有效记录", sgb_paths.len());
         sgb_paths
     }
 
+    /// 加载 HousingExterior 表的尺寸分类
+    /// 返回 HousingExterior row_id -> 适用的房屋尺寸，通用部件不记录
+    pub fn load_housing_exterior_sizes(&self) -> std::collections::HashMap<u32, HousingSize> {
+        // HousingExterior 列布局:
+        // col[2] = HousingSize (UInt8, 0 小 / 1 中 / 2 大，其余为通用)
+        let mut sizes: std::collections::HashMap<u32, HousingSize> =
+            std::collections::HashMap::new();
+        let result = self.for_each_row("HousingExterior", Language::None, |row_id, row| {
+            let size = match row.columns.get(2) {
+                Some(Field::UInt8(v)) => HousingSize::from_raw(*v),
+                _ => return,
+            };
+            if size != HousingSize::Unknown {
+                sizes.insert(row_id, size);
+            }
+        });
+        if result.is_err() {
+            return std::collections::HashMap::new();
+        }
+        println!("HousingExterior 尺寸: {} 条记录", sizes.len());
+        sizes
+    }
+
     /// 加载 HousingFurniture 表的 SGB 路径映射 (室内家具)
     /// 返回 Item.row_id -> SGB 路径 (通过表中的 Item 列反查)
     pub fn load_housing_furniture_sgb_paths(&self) -> std::collections::HashMap<u32, String> {
-        let mut physis = self.physis.borrow_mut();
-
-        let exh = match physis.read_excel_sheet_header("HousingFurniture") {
-            Ok(h) => h,
-            Err(e) => {
-                eprintln!("无法加载 HousingFurniture 表头: {}", e);
-                return std::collections::HashMap::new();
-            }
-        };
-        let sheet = match physis.read_excel_sheet(&exh, "HousingFurniture", Language::None) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("无法加载 HousingFurniture 表: {}", e);
-                return std::collections::HashMap::new();
-            }
-        };
-
         // HousingFurniture 列布局:
         // col[0] = ModelKey (UInt16)
         // col[7] = Item (UInt32, 链接到 Item 表)
         let mut sgb_paths: std::collections::HashMap<u32, String> =
             std::collections::HashMap::new();
-        for page in &sheet.pages {
-            for (_row_id, row) in page.into_iter().flatten_subrows() {
-                let model_key = match row.columns.first() {
-                    Some(Field::UInt16(v)) => *v,
-                    Some(Field::UInt8(v)) => *v as u16,
-                    _ => continue,
-                };
-                if model_key == 0 {
-                    continue;
-                }
-                // col[7] = Item row_id
-                let item_id = match row.columns.get(7) {
-                    Some(Field::UInt32(v)) if *v > 0 => *v,
-                    Some(Field::Int32(v)) if *v > 0 => *v as u32,
-                    _ => continue,
-                };
-                let sgb = format!(
-                    "bgcommon/hou/indoor/general/{:04}/asset/fun_b0_m{:04}.sgb",
-                    model_key, model_key
-                );
-                sgb_paths.insert(item_id, sgb);
+        let result = self.for_each_row("HousingFurniture", Language::None, |_row_id, row| {
+            let model_key = match row.columns.first() {
+                Some(Field::UInt16(v)) => *v,
+                Some(Field::UInt8(v)) => *v as u16,
+                _ => return,
+            };
+            if model_key == 0 {
+                return;
             }
+            // col[7] = Item row_id
+            let item_id = match row.columns.get(7) {
+                Some(Field::UInt32(v)) if *v > 0 => *v,
+                Some(Field::Int32(v)) if *v > 0 => *v as u32,
+                _ => return,
+            };
+            let sgb = format!(
+                "bgcommon/hou/indoor/general/{:04}/asset/fun_b0_m{:04}.sgb",
+                model_key, model_key
+            );
+            sgb_paths.insert(item_id, sgb);
+        });
+        if let Err(e) = result {
+            eprintln!("无法加载 HousingFurniture 表: {}", e);
+            return std::collections::HashMap::new();
         }
         println!("HousingFurniture 表: {} 条有效记录", sgb_paths.len());
         sgb_paths
@@ -338,86 +755,55 @@ impl GameData {
     /// 加载 HousingYardObject 表的 SGB 路径映射 (庭院家具)
     /// 返回 Item.row_id -> SGB 路径 (通过表中的 Item 列反查)
     pub fn load_housing_yard_sgb_paths(&self) -> std::collections::HashMap<u32, String> {
-        let mut physis = self.physis.borrow_mut();
-
-        let exh = match physis.read_excel_sheet_header("HousingYardObject") {
-            Ok(h) => h,
-            Err(e) => {
-                eprintln!("无法加载 HousingYardObject 表头: {}", e);
-                return std::collections::HashMap::new();
-            }
-        };
-        let sheet = match physis.read_excel_sheet(&exh, "HousingYardObject", Language::None) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("无法加载 HousingYardObject 表: {}", e);
-                return std::collections::HashMap::new();
-            }
-        };
-
         // HousingYardObject 列布局:
         // col[0] = ModelKey (UInt16)
         // col[6] = Item (UInt32, 链接到 Item 表)
         let mut sgb_paths: std::collections::HashMap<u32, String> =
             std::collections::HashMap::new();
-        for page in &sheet.pages {
-            for (_row_id, row) in page.into_iter().flatten_subrows() {
-                let model_key = match row.columns.first() {
-                    Some(Field::UInt16(v)) => *v,
-                    Some(Field::UInt8(v)) => *v as u16,
-                    _ => continue,
-                };
-                if model_key == 0 {
-                    continue;
-                }
-                // col[6] = Item row_id
-                let item_id = match row.columns.get(6) {
-                    Some(Field::UInt32(v)) if *v > 0 => *v,
-                    Some(Field::Int32(v)) if *v > 0 => *v as u32,
-                    _ => continue,
-                };
-                let sgb = format!(
-                    "bgcommon/hou/outdoor/general/{:04}/asset/gar_b0_m{:04}.sgb",
-                    model_key, model_key
-                );
-                sgb_paths.insert(item_id, sgb);
+        let result = self.for_each_row("HousingYardObject", Language::None, |_row_id, row| {
+            let model_key = match row.columns.first() {
+                Some(Field::UInt16(v)) => *v,
+                Some(Field::UInt8(v)) => *v as u16,
+                _ => return,
+            };
+            if model_key == 0 {
+                return;
             }
+            // col[6] = Item row_id
+            let item_id = match row.columns.get(6) {
+                Some(Field::UInt32(v)) if *v > 0 => *v,
+                Some(Field::Int32(v)) if *v > 0 => *v as u32,
+                _ => return,
+            };
+            let sgb = format!(
+                "bgcommon/hou/outdoor/general/{:04}/asset/gar_b0_m{:04}.sgb",
+                model_key, model_key
+            );
+            sgb_paths.insert(item_id, sgb);
+        });
+        if let Err(e) = result {
+            eprintln!("无法加载 HousingYardObject 表: {}", e);
+            return std::collections::HashMap::new();
         }
         println!("HousingYardObject 表: {} 条有效记录", sgb_paths.len());
         sgb_paths
     }
 
     pub fn load_stain_list(&self) -> Vec<StainEntry> {
-        let mut physis = self.physis.borrow_mut();
-
-        let exh = match physis.read_excel_sheet_header("Stain") {
-            Ok(h) => h,
-            Err(e) => {
-                eprintln!("无法加载 Stain 表头: {}", e);
-                return Vec::new();
-            }
-        };
-
-        let sheet = match physis.read_excel_sheet(&exh, "Stain", Language::ChineseSimplified) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("无法加载 Stain 表: {}", e);
-                return Vec::new();
-            }
-        };
-
         let mut stains = Vec::new();
-        for page in &sheet.pages {
-            for (row_id, row) in page.into_iter().flatten_subrows() {
-                if let Some(stain) = Self::parse_stain_row(row_id, row) {
-                    stains.push(stain);
-                }
+        let result = self.for_each_row("Stain", Language::ChineseSimplified, |row_id, row| {
+            if let Some(stain) = Self::parse_stain_row(row_id, row) {
+                stains.push(stain);
             }
+        });
+        if let Err(e) = result {
+            eprintln!("无法加载 Stain 表: {}", e);
+            return Vec::new();
         }
         stains
     }
 
-    fn parse_stain_row(row_id: u32, row: &Row) -> Option<StainEntry> {
+    fn parse_stain_row(row_id: u32, row: &SheetRow) -> Option<StainEntry> {
         let color_val = match row.columns.get(0)? {
             Field::UInt32(v) => *v,
             _ => return None,
@@ -460,54 +846,53 @@ impl GameData {
     }
 
     pub fn load_icon(&self, icon_id: u32) -> Option<TextureData> {
+        self.load_icon_variant(icon_id, true)
+            .or_else(|| self.load_icon_variant(icon_id, false))
+    }
+
+    /// 加载指定版本的图标: `hr` 为 true 时读取 `_hr1` 高清版本 (80px)，否则读取标准版本 (40px)，不互相回退
+    pub fn load_icon_variant(&self, icon_id: u32, hr: bool) -> Option<TextureData> {
+        self.try_load_icon_variant(icon_id, hr).ok()
+    }
+
+    /// 同 load_icon_variant，失败时返回原因 (文件不存在或解码失败)
+    pub fn try_load_icon_variant(&self, icon_id: u32, hr: bool) -> Result<TextureData, String> {
         if icon_id == 0 {
-            return None;
+            return Err("无图标".to_string());
         }
         let high = icon_id / 1000 * 1000;
-        let path = format!("ui/icon/{:06}/{:06}_hr1.tex", high, icon_id);
-
+        let suffix = if hr { "_hr1" } else { "" };
+        let path = format!("ui/icon/{:06}/{:06}{}.tex", high, icon_id, suffix);
         if let Some(tex) = self.parsed_tex(&path) {
-            return Some(tex);
+            return Ok(tex);
+        }
+        // 只在失败时再读一次原始文件，区分缺失与格式问题
+        if self.read_file(&path).is_ok() {
+            Err(format!("{} 解码失败", path))
+        } else {
+            Err(format!("{} 不存在", path))
         }
-
-        let fallback_path = format!("ui/icon/{:06}/{:06}.tex", high, icon_id);
-        self.parsed_tex(&fallback_path)
     }
 
     /// 加载 Recipe EXD 表，返回配方列表
     pub fn load_recipes(&self) -> Vec<Recipe> {
-        let mut physis = self.physis.borrow_mut();
-
-        let exh = match physis.read_excel_sheet_header("Recipe") {
-            Ok(h) => h,
-            Err(e) => {
-                eprintln!("无法加载 Recipe 表头: {}", e);
-                return Vec::new();
-            }
-        };
-
-        // Recipe 表不含文本，使用 Language::None
-        let sheet = match physis.read_excel_sheet(&exh, "Recipe", Language::None) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("无法加载 Recipe 表: {}", e);
-                return Vec::new();
-            }
-        };
-
         let mut recipes = Vec::new();
-        for page in &sheet.pages {
-            for (row_id, row) in page.into_iter().flatten_subrows() {
-                if let Some(recipe) = Self::parse_recipe_row(row_id, row) {
-                    recipes.push(recipe);
-                }
+        // Recipe 表不含文本，使用 Language::None
+        let result = self.for_each_row_tolerant("Recipe", Language::None, |row_id, row| {
+            if let Some(recipe) = Self::parse_recipe_row(row_id, row) {
+                recipes.push(recipe);
             }
+        });
+        if let Err(e) = result {
+            eprintln!("无法加载 Recipe 表: {}", e);
+            return Vec::new();
         }
+        recipes.shrink_to_fit();
         println!("Recipe 表: {} 条有效配方", recipes.len());
         recipes
     }
 
-    fn parse_recipe_row(row_id: u32, row: &Row) -> Option<Recipe> {
+    fn parse_recipe_row(row_id: u32, row: &SheetRow) -> Option<Recipe> {
         // Recipe 表实际列布局 (通过 debug dump 确认):
         // col[0]: Number (Int32)
         // col[1]: CraftType (Int32)
@@ -518,14 +903,20 @@ impl GameData {
         // col[6..21]: Ingredient[0..7] 交错排列, 每对 (Int32 item_id, UInt8 amount)
         //   col[6]=Ing0_ID, col[7]=Ing0_Amt, col[8]=Ing1_ID, col[9]=Ing1_Amt, ...
         // col[40]: SecretRecipeBook (Int32, 秘籍 ID，0 表示非秘籍)
+        // col[31]/[35]/[43]: MaterialQualityFactor / RequiredQuality / CanHq，
+        //   按 EXDSchema 的字段顺序相对 SecretRecipeBook 推算，旧版表中可能不存在，
+        //   类型不符时视为缺失
         const COL_CRAFT_TYPE: usize = 1;
         const COL_RECIPE_LEVEL: usize = 2;
         const COL_ITEM_RESULT: usize = 4;
         const COL_AMOUNT_RESULT: usize = 5;
         const COL_INGREDIENT_START: usize = 6; // 每对占 2 列, 共 8 对
         const COL_SECRET_RECIPE_BOOK: usize = 40;
+        const COL_MATERIAL_QUALITY_FACTOR: usize = 31;
+        const COL_REQUIRED_QUALITY: usize = 35;
+        const COL_CAN_HQ: usize = 43;
 
-        fn read_i32_as_u32(row: &Row, col: usize) -> u32 {
+        fn read_i32_as_u32(row: &SheetRow, col: usize) -> u32 {
             match row.columns.get(col) {
                 Some(Field::Int32(v)) => {
                     if *v > 0 {
@@ -585,6 +976,18 @@ impl GameData {
         // 读取秘籍 ID
         let secret_recipe_book = read_i32_as_u32(row, COL_SECRET_RECIPE_BOOK);
 
+        // 品质相关列
+        let material_quality_factor = match row.columns.get(COL_MATERIAL_QUALITY_FACTOR) {
+            Some(Field::UInt8(v)) if *v <= 100 => *v,
+            _ => 0,
+        };
+        let required_quality = match row.columns.get(COL_REQUIRED_QUALITY) {
+            Some(Field::UInt32(v)) => *v,
+            Some(Field::UInt16(v)) => *v as u32,
+            _ => 0,
+        };
+        let can_hq = matches!(row.columns.get(COL_CAN_HQ), Some(Field::Bool(true)));
+
         Some(Recipe {
             row_id,
             result_item_id,
@@ -593,324 +996,421 @@ impl GameData {
             recipe_level_table_id: recipe_level,
             ingredients,
             secret_recipe_book,
+            material_quality_factor,
+            required_quality,
+            can_hq,
         })
     }
 
     /// 加载 ItemUICategory 表, 返回 row_id -> 分类名称
     pub fn load_ui_category_names(&self) -> std::collections::HashMap<u8, String> {
-        let mut physis = self.physis.borrow_mut();
-        let exh = match physis.read_excel_sheet_header("ItemUICategory") {
-            Ok(h) => h,
-            Err(_) => return std::collections::HashMap::new(),
-        };
-        let sheet =
-            match physis.read_excel_sheet(&exh, "ItemUICategory", Language::ChineseSimplified) {
-                Ok(s) => s,
-                Err(_) => return std::collections::HashMap::new(),
-            };
         let mut map = std::collections::HashMap::new();
-        for page in &sheet.pages {
-            for (row_id, row) in page.into_iter().flatten_subrows() {
+        let result = self.for_each_row(
+            "ItemUICategory",
+            Language::ChineseSimplified,
+            |row_id, row| {
                 if let Some(Field::String(name)) = row.columns.first() {
                     if !name.is_empty() && row_id <= 255 {
                         map.insert(row_id as u8, name.clone());
                     }
                 }
+            },
+        );
+        if result.is_err() {
+            return std::collections::HashMap::new();
+        }
+        map
+    }
+
+    /// 加载 ClassJob 表, 返回 row_id -> 职业名称
+    pub fn load_class_job_names(&self) -> std::collections::HashMap<u8, String> {
+        let mut map = std::collections::HashMap::new();
+        let result = self.for_each_row("ClassJob", Language::ChineseSimplified, |row_id, row| {
+            // ClassJob 表: col[0] = Name (String)
+            if let Some(Field::String(name)) = row.columns.first() {
+                if !name.is_empty() && row_id <= 255 {
+                    map.insert(row_id as u8, name.clone());
+                }
             }
+        });
+        if result.is_err() {
+            return std::collections::HashMap::new();
         }
         map
     }
 
-    /// 加载 GilShop 相关表, 构建 NPC 关联, 返回 item_id -> Vec<ItemSource::GilShop>
-    pub fn load_gil_shop_items(&self) -> std::collections::HashMap<u32, Vec<ItemSource>> {
-        let mut physis = self.physis.borrow_mut();
+    /// 加载 BaseParam 表, 返回 row_id -> 属性名称 (物理防御力、力量等)
+    pub fn load_base_param_names(&self) -> std::collections::HashMap<u8, String> {
+        let mut map = std::collections::HashMap::new();
+        let result = self.for_each_row("BaseParam", Language::ChineseSimplified, |row_id, row| {
+            // BaseParam 表: 名称前有 PacketIndex 等数值列，取第一个字符串列
+            let name = row.columns.iter().find_map(|col| match col {
+                Field::String(s) => Some(s),
+                _ => None,
+            });
+            if let Some(name) = name {
+                if !name.is_empty() && row_id <= 255 {
+                    map.insert(row_id as u8, name.clone());
+                }
+            }
+        });
+        if result.is_err() {
+            return std::collections::HashMap::new();
+        }
+        map
+    }
 
-        // 1. 加载 GilShop 表: shop_id -> 商店分类名
-        let mut shop_names: std::collections::HashMap<u32, String> =
-            std::collections::HashMap::new();
-        if let Ok(exh) = physis.read_excel_sheet_header("GilShop") {
-            if let Ok(sheet) = physis.read_excel_sheet(&exh, "GilShop", Language::ChineseSimplified)
-            {
-                for page in &sheet.pages {
-                    for (row_id, row) in page.into_iter().flatten_subrows() {
-                        let name = match row.columns.first() {
-                            Some(Field::String(s)) if !s.is_empty() => s.clone(),
-                            _ => String::new(),
-                        };
-                        shop_names.insert(row_id, name);
-                    }
+    /// 加载英文物品名 (row_id -> Name)，客户端不含英文数据时返回空表
+    pub fn load_english_item_names(&self) -> std::collections::HashMap<u32, String> {
+        let mut map = std::collections::HashMap::new();
+        let result = self.for_each_row("Item", Language::English, |row_id, row| {
+            // Item 表: col[0] = Name
+            if let Some(Field::String(name)) = row.columns.first() {
+                if !name.is_empty() {
+                    map.insert(row_id, name.clone());
                 }
             }
+        });
+        if result.is_err() {
+            return std::collections::HashMap::new();
         }
-        println!("GilShop: {} 个商店", shop_names.len());
+        map
+    }
 
-        // 2. 加载 TopicSelect 表: topic_id -> Vec<shop_id>
+    /// 加载 ItemRepairResource 表, 返回 row_id -> 修理材料物品 ID
+    pub fn load_item_repair_resources(&self) -> std::collections::HashMap<u8, u32> {
+        let mut map = std::collections::HashMap::new();
+        let result = self.for_each_row("ItemRepairResource", Language::None, |row_id, row| {
+            // ItemRepairResource 表: col[0] = Item
+            let item_id = match row.columns.first() {
+                Some(Field::Int32(v)) => *v as u32,
+                Some(Field::UInt32(v)) => *v,
+                _ => 0,
+            };
+            if item_id > 0 && row_id <= 255 {
+                map.insert(row_id as u8, item_id);
+            }
+        });
+        if result.is_err() {
+            return std::collections::HashMap::new();
+        }
+        map
+    }
+
+    /// 加载商店 → NPC 关联 (GilShop 与 SpecialShop 共用) 及 NPC 的位置与最近传送点
+    pub fn load_shop_npcs(&self) -> ShopNpcIndex {
+        // 1. 加载 TopicSelect 表: topic_id -> Vec<shop_id>
         let mut topic_shops: std::collections::HashMap<u32, Vec<u32>> =
             std::collections::HashMap::new();
-        if let Ok(exh) = physis.read_excel_sheet_header("TopicSelect") {
-            if let Ok(sheet) = physis.read_excel_sheet(&exh, "TopicSelect", Language::None) {
-                for page in &sheet.pages {
-                    for (row_id, row) in page.into_iter().flatten_subrows() {
-                        let mut shops = Vec::new();
-                        // Shop[0..9] 从 col[1] 开始 (col[0] 是 Name)
-                        for i in 1..=10 {
-                            match row.columns.get(i) {
-                                Some(Field::Int32(v)) if *v > 0 => shops.push(*v as u32),
-                                Some(Field::UInt32(v)) if *v > 0 => shops.push(*v),
-                                _ => {}
-                            }
-                        }
-                        if !shops.is_empty() {
-                            topic_shops.insert(row_id, shops);
-                        }
-                    }
+        let _ = self.for_each_row_tolerant("TopicSelect", Language::None, |row_id, row| {
+            let mut shops = Vec::new();
+            // Shop[0..9] 从 col[1] 开始 (col[0] 是 Name)
+            for i in 1..=10 {
+                match row.columns.get(i) {
+                    Some(Field::Int32(v)) if *v > 0 => shops.push(*v as u32),
+                    Some(Field::UInt32(v)) if *v > 0 => shops.push(*v),
+                    _ => {}
                 }
             }
-        }
+            if !shops.is_empty() {
+                topic_shops.insert(row_id, shops);
+            }
+        });
         println!("TopicSelect: {} 个话题", topic_shops.len());
 
-        // 3. 加载 ENpcResident 表: npc_id -> npc_name
+        // 2. 加载 ENpcResident 表: npc_id -> npc_name
         let mut npc_names: std::collections::HashMap<u32, String> =
             std::collections::HashMap::new();
-        if let Ok(exh) = physis.read_excel_sheet_header("ENpcResident") {
-            if let Ok(sheet) =
-                physis.read_excel_sheet(&exh, "ENpcResident", Language::ChineseSimplified)
-            {
-                for page in &sheet.pages {
-                    for (row_id, row) in page.into_iter().flatten_subrows() {
-                        let name = match row.columns.first() {
-                            Some(Field::String(s)) if !s.is_empty() => s.clone(),
-                            _ => continue,
-                        };
-                        npc_names.insert(row_id, name);
+        let _ = self.for_each_row_tolerant(
+            "ENpcResident",
+            Language::ChineseSimplified,
+            |row_id, row| {
+                if let Some(Field::String(s)) = row.columns.first() {
+                    if !s.is_empty() {
+                        npc_names.insert(row_id, s.clone());
                     }
                 }
-            }
-        }
+            },
+        );
         println!("ENpcResident: {} 个 NPC", npc_names.len());
 
-        // 4. 加载 ENpcBase 表, 构建 shop_id -> npc_name 反向索引
-        const GILSHOP_MIN: u32 = 0x40000;
-        const GILSHOP_MAX: u32 = 0x160000;
-        const TOPIC_MIN: u32 = 0x320000;
-        const TOPIC_MAX: u32 = 0x360000;
-
-        let mut shop_npcs: std::collections::HashMap<u32, String> =
+        // 3. 加载 ENpcBase 表, 构建 shop_id -> npc_id 反向索引
+        let is_shop = |id: u32| {
+            (GILSHOP_ID_MIN..GILSHOP_ID_MAX).contains(&id)
+                || (SPECIALSHOP_ID_MIN..SPECIALSHOP_ID_MAX).contains(&id)
+        };
+        let mut shop_npc_ids: std::collections::HashMap<u32, u32> =
             std::collections::HashMap::new();
-        if let Ok(exh) = physis.read_excel_sheet_header("ENpcBase") {
-            if let Ok(sheet) = physis.read_excel_sheet(&exh, "ENpcBase", Language::None) {
-                for page in &sheet.pages {
-                    for (npc_id, row) in page.into_iter().flatten_subrows() {
-                        let npc_name = match npc_names.get(&npc_id) {
-                            Some(n) => n.clone(),
-                            None => continue,
-                        };
-                        // ENpcData[0..31] — 需要找到正确的列偏移
-                        // ENpcBase 有很多外观字段在前面，ENpcData 通常在后半部分
-                        // 遍历所有列查找 GilShop/TopicSelect 范围的值
-                        for col in &row.columns {
-                            let val = match col {
-                                Field::Int32(v) if *v > 0 => *v as u32,
-                                Field::UInt32(v) if *v > 0 => *v,
-                                _ => continue,
-                            };
-                            if val >= GILSHOP_MIN && val < GILSHOP_MAX {
-                                // 直接关联 GilShop
-                                shop_npcs.entry(val).or_insert_with(|| npc_name.clone());
-                            } else if val >= TOPIC_MIN && val < TOPIC_MAX {
-                                // 间接关联: TopicSelect -> GilShop
-                                if let Some(shops) = topic_shops.get(&val) {
-                                    for &shop_id in shops {
-                                        if shop_id >= GILSHOP_MIN && shop_id < GILSHOP_MAX {
-                                            shop_npcs
-                                                .entry(shop_id)
-                                                .or_insert_with(|| npc_name.clone());
-                                        }
-                                    }
-                                }
-                            }
+        let _ = self.for_each_row_tolerant("ENpcBase", Language::None, |npc_id, row| {
+            if !npc_names.contains_key(&npc_id) {
+                return;
+            }
+            // ENpcData[0..31] 是 u32 列，前面的外观字段为 u8/u16，
+            // 只看 u32 列可避免外观数值恰好落入商店 id 范围
+            for col in &row.columns {
+                let val = match col {
+                    Field::UInt32(v) if *v > 0 => *v,
+                    _ => continue,
+                };
+                if is_shop(val) {
+                    // 直接关联商店
+                    shop_npc_ids.entry(val).or_insert(npc_id);
+                } else if (TOPIC_ID_MIN..TOPIC_ID_MAX).contains(&val) {
+                    // 间接关联: TopicSelect -> 商店
+                    if let Some(shops) = topic_shops.get(&val) {
+                        for &shop_id in shops.iter().filter(|&&id| is_shop(id)) {
+                            shop_npc_ids.entry(shop_id).or_insert(npc_id);
                         }
                     }
                 }
             }
-        }
-        println!("GilShop→NPC: {} 个商店有 NPC 关联", shop_npcs.len());
+        });
+        println!("商店→NPC: {} 个商店有 NPC 关联", shop_npc_ids.len());
 
-        // 4b. 加载 NPC 位置: npc_id -> 区域名
+        // 4. 加载 NPC 位置: npc_id -> 区域名
         // 先加载 PlaceName 表
         let mut place_names: std::collections::HashMap<u32, String> =
             std::collections::HashMap::new();
-        if let Ok(exh) = physis.read_excel_sheet_header("PlaceName") {
-            if let Ok(sheet) =
-                physis.read_excel_sheet(&exh, "PlaceName", Language::ChineseSimplified)
-            {
-                for page in &sheet.pages {
-                    for (row_id, row) in page.into_iter().flatten_subrows() {
-                        if let Some(Field::String(s)) = row.columns.first() {
-                            if !s.is_empty() {
-                                place_names.insert(row_id, s.clone());
-                            }
-                        }
+        let _ =
+            self.for_each_row_tolerant("PlaceName", Language::ChineseSimplified, |row_id, row| {
+                if let Some(Field::String(s)) = row.columns.first() {
+                    if !s.is_empty() {
+                        place_names.insert(row_id, s.clone());
                     }
                 }
-            }
-        }
+            });
 
         // 加载 TerritoryType 表: territory_id -> place_name_id
         let mut territory_place: std::collections::HashMap<u32, u32> =
             std::collections::HashMap::new();
-        if let Ok(exh) = physis.read_excel_sheet_header("TerritoryType") {
-            if let Ok(sheet) = physis.read_excel_sheet(&exh, "TerritoryType", Language::None) {
-                for page in &sheet.pages {
-                    for (row_id, row) in page.into_iter().flatten_subrows() {
-                        // PlaceName 字段 — 需要找到正确的列
-                        // TerritoryType 的 PlaceName 通常在前几列
-                        for col in row.columns.iter().take(10) {
-                            match col {
-                                Field::UInt16(v) if *v > 0 => {
-                                    if place_names.contains_key(&(*v as u32)) {
-                                        territory_place.insert(row_id, *v as u32);
-                                        break;
-                                    }
-                                }
-                                Field::Int32(v) if *v > 0 => {
-                                    if place_names.contains_key(&(*v as u32)) {
-                                        territory_place.insert(row_id, *v as u32);
-                                        break;
-                                    }
-                                }
-                                _ => {}
-                            }
+        let _ = self.for_each_row_tolerant("TerritoryType", Language::None, |row_id, row| {
+            // PlaceName 字段 — 需要找到正确的列
+            // TerritoryType 的 PlaceName 通常在前几列
+            for col in row.columns.iter().take(10) {
+                match col {
+                    Field::UInt16(v) if *v > 0 => {
+                        if place_names.contains_key(&(*v as u32)) {
+                            territory_place.insert(row_id, *v as u32);
+                            break;
+                        }
+                    }
+                    Field::Int32(v) if *v > 0 => {
+                        if place_names.contains_key(&(*v as u32)) {
+                            territory_place.insert(row_id, *v as u32);
+                            break;
                         }
                     }
+                    _ => {}
                 }
             }
-        }
+        });
 
-        // 加载 Level 表: 筛选 Type=8 (ENpc), 建立 npc_id -> 区域名
-        let mut npc_locations: std::collections::HashMap<u32, String> =
+        // 加载 Aetheryte 表: 只取以太之光 (不含城内以太之晶)，位置来自其 Level 行
+        // Aetheryte 列: 8=PlaceName, 11..=14 Level[4], 15=IsAetheryte
+        let mut aetheryte_levels: std::collections::HashMap<u32, String> =
             std::collections::HashMap::new();
-        if let Ok(exh) = physis.read_excel_sheet_header("Level") {
-            if let Ok(sheet) = physis.read_excel_sheet(&exh, "Level", Language::None) {
-                for page in &sheet.pages {
-                    for (_row_id, row) in page.into_iter().flatten_subrows() {
-                        // Level 表列结构: X, Y, Z, Yaw, Radius, Type, Object, Territory, Map, ...
-                        // 需要确认实际列偏移
-                        let cols = &row.columns;
-                        if cols.len() < 9 {
-                            continue;
-                        }
-                        // Type 字段 (col[5] 或附近)
-                        let obj_type = match &cols[5] {
-                            Field::UInt8(v) => *v,
-                            _ => continue,
-                        };
-                        if obj_type != 8 {
-                            continue; // 只要 ENpc
-                        }
-                        // Object 字段 (col[6])
-                        let npc_id = match &cols[6] {
-                            Field::UInt32(v) => *v,
-                            Field::Int32(v) if *v > 0 => *v as u32,
-                            _ => continue,
-                        };
-                        // Territory 字段 (col[7])
-                        let territory_id = match &cols[7] {
-                            Field::UInt16(v) => *v as u32,
-                            Field::Int32(v) if *v > 0 => *v as u32,
-                            Field::UInt32(v) => *v,
-                            _ => continue,
-                        };
-                        // 查找区域名
-                        if npc_locations.contains_key(&npc_id) {
-                            continue; // 只取第一个位置
-                        }
-                        if let Some(&place_id) = territory_place.get(&territory_id) {
-                            if let Some(name) = place_names.get(&place_id) {
-                                npc_locations.insert(npc_id, name.clone());
-                            }
-                        }
+        let _ = self.for_each_row_tolerant("Aetheryte", Language::None, |_row_id, row| {
+            let cols = &row.columns;
+            if !matches!(cols.get(15), Some(Field::Bool(true))) {
+                return;
+            }
+            let Some(name) = (match cols.get(8) {
+                Some(Field::UInt16(v)) => place_names.get(&(*v as u32)),
+                _ => None,
+            }) else {
+                return;
+            };
+            for col in cols.iter().skip(11).take(4) {
+                if let Field::UInt32(level_id) = col {
+                    if *level_id > 0 {
+                        aetheryte_levels.insert(*level_id, name.clone());
                     }
                 }
             }
-        }
-        println!("NPC 位置: {} 个 NPC 有位置信息", npc_locations.len());
+        });
 
-        // 构建 shop_id -> npc_location (通过 shop_npcs 中的 npc_name 反查 npc_id)
-        // 需要 npc_name -> npc_id 的反向映射
-        let npc_name_to_id: std::collections::HashMap<&str, u32> = npc_names
-            .iter()
-            .map(|(&id, name)| (name.as_str(), id))
-            .collect();
-        let mut shop_locations: std::collections::HashMap<u32, String> =
+        // 加载 Level 表: 筛选 Type=8 (ENpc), 建立 npc_id -> 区域名，
+        // 同时记录 NPC 与以太之光的坐标用于查找最近的传送点
+        let mut npc_locations: std::collections::HashMap<u32, String> =
             std::collections::HashMap::new();
-        for (&shop_id, npc_name) in &shop_npcs {
-            if let Some(&npc_id) = npc_name_to_id.get(npc_name.as_str()) {
-                if let Some(loc) = npc_locations.get(&npc_id) {
-                    shop_locations.insert(shop_id, loc.clone());
+        // npc_id -> (territory_id, x, z)
+        let mut npc_positions: std::collections::HashMap<u32, (u32, f32, f32)> =
+            std::collections::HashMap::new();
+        // territory_id -> [(传送点名, x, z)]
+        let mut territory_aetherytes: std::collections::HashMap<u32, Vec<(String, f32, f32)>> =
+            std::collections::HashMap::new();
+        let _ = self.for_each_row_tolerant("Level", Language::None, |row_id, row| {
+            // Level 表列结构: X, Y, Z, Yaw, Radius, Type, Object, Territory, Map, ...
+            // 需要确认实际列偏移
+            let cols = &row.columns;
+            if cols.len() < 9 {
+                return;
+            }
+            let position = match (&cols[0], &cols[2]) {
+                (Field::Float32(x), Field::Float32(z)) => Some((*x, *z)),
+                _ => None,
+            };
+            if let Some(name) = aetheryte_levels.get(&row_id) {
+                let territory_id = match &cols[7] {
+                    Field::UInt16(v) => *v as u32,
+                    Field::UInt32(v) => *v,
+                    _ => 0,
+                };
+                if let (Some((x, z)), true) = (position, territory_id > 0) {
+                    territory_aetherytes.entry(territory_id).or_default().push((
+                        name.clone(),
+                        x,
+                        z,
+                    ));
                 }
+                return;
+            }
+            // Type 字段 (col[5] 或附近)
+            let obj_type = match &cols[5] {
+                Field::UInt8(v) => *v,
+                _ => return,
+            };
+            if obj_type != 8 {
+                return; // 只要 ENpc
+            }
+            // Object 字段 (col[6])
+            let npc_id = match &cols[6] {
+                Field::UInt32(v) => *v,
+                Field::Int32(v) if *v > 0 => *v as u32,
+                _ => return,
+            };
+            // Territory 字段 (col[7])
+            let territory_id = match &cols[7] {
+                Field::UInt16(v) => *v as u32,
+                Field::Int32(v) if *v > 0 => *v as u32,
+                Field::UInt32(v) => *v,
+                _ => return,
+            };
+            // 查找区域名
+            if npc_locations.contains_key(&npc_id) {
+                return; // 只取第一个位置
             }
+            if let Some((x, z)) = position {
+                npc_positions.insert(npc_id, (territory_id, x, z));
+            }
+            if let Some(&place_id) = territory_place.get(&territory_id) {
+                if let Some(name) = place_names.get(&place_id) {
+                    npc_locations.insert(npc_id, name.clone());
+                }
+            }
+        });
+        println!("NPC 位置: {} 个 NPC 有位置信息", npc_locations.len());
+
+        // npc_id -> 同一区域内水平距离最近的传送点；区域内没有以太之光时不提示
+        let npc_aetherytes: std::collections::HashMap<u32, String> = npc_positions
+            .iter()
+            .filter_map(|(&npc_id, &(territory_id, x, z))| {
+                let nearest = territory_aetherytes
+                    .get(&territory_id)?
+                    .iter()
+                    .min_by(|a, b| {
+                        let da = (a.1 - x).powi(2) + (a.2 - z).powi(2);
+                        let db = (b.1 - x).powi(2) + (b.2 - z).powi(2);
+                        da.total_cmp(&db)
+                    })?;
+                Some((npc_id, nearest.0.clone()))
+            })
+            .collect();
+        println!(
+            "传送点: {} 个区域有以太之光, {} 个 NPC 有最近传送点",
+            territory_aetherytes.len(),
+            npc_aetherytes.len()
+        );
+
+        let mut index = ShopNpcIndex::default();
+        for (shop_id, npc_id) in shop_npc_ids {
+            let Some(name) = npc_names.get(&npc_id) else {
+                continue;
+            };
+            index.shops.insert(
+                shop_id,
+                ShopNpc {
+                    name: name.clone(),
+                    location: npc_locations.get(&npc_id).cloned(),
+                    nearest_aetheryte: npc_aetherytes.get(&npc_id).cloned(),
+                },
+            );
         }
-        println!("GilShop 位置: {} 个商店有位置信息", shop_locations.len());
+        println!(
+            "商店位置: {} 个商店有位置信息",
+            index
+                .shops
+                .values()
+                .filter(|n| n.location.is_some())
+                .count()
+        );
+        index
+    }
 
-        // 5. 加载 GilShopItem 表, 构建 item_id -> Vec<ItemSource::GilShop>
-        let exh = match physis.read_excel_sheet_header("GilShopItem") {
-            Ok(h) => h,
-            Err(_) => return std::collections::HashMap::new(),
-        };
-        let sheet = match physis.read_excel_sheet(&exh, "GilShopItem", Language::None) {
-            Ok(s) => s,
-            Err(_) => return std::collections::HashMap::new(),
-        };
-        let mut map: std::collections::HashMap<u32, Vec<ItemSource>> =
+    /// 加载 GilShop 相关表, 返回 item_id -> Vec<ItemSource::GilShop>
+    pub fn load_gil_shop_items(
+        &self,
+        npcs: &ShopNpcIndex,
+    ) -> std::collections::HashMap<u32, Vec<ItemSource>> {
+        // 1. 加载 GilShop 表: shop_id -> 商店分类名
+        let mut shop_names: std::collections::HashMap<u32, String> =
             std::collections::HashMap::new();
-        for page in &sheet.pages {
-            for (row_id, row) in page.into_iter().flatten_subrows() {
-                let item_id = match row.columns.first() {
-                    Some(Field::Int32(v)) if *v > 0 => *v as u32,
-                    _ => continue,
-                };
-                // 组合显示名: "NPC名 - 商店分类名" 或 "商店分类名"
-                let category = shop_names.get(&row_id).filter(|s| !s.is_empty()).cloned();
-                let npc = shop_npcs.get(&row_id).cloned();
-                let shop_name = match (npc, category) {
-                    (Some(n), Some(c)) => format!("{} - {}", n, c),
-                    (Some(n), None) => n,
-                    (None, Some(c)) => c,
-                    (None, None) => "金币商店".to_string(),
+        let _ =
+            self.for_each_row_tolerant("GilShop", Language::ChineseSimplified, |row_id, row| {
+                let name = match row.columns.first() {
+                    Some(Field::String(s)) if !s.is_empty() => s.clone(),
+                    _ => String::new(),
                 };
-                let npc_location = shop_locations.get(&row_id).cloned();
-                map.entry(item_id).or_default().push(ItemSource::GilShop {
-                    shop_name,
-                    npc_location,
-                });
-            }
+                shop_names.insert(row_id, name);
+            });
+        println!("GilShop: {} 个商店", shop_names.len());
+
+        // 2. 加载 GilShopItem 表, 构建 item_id -> Vec<ItemSource::GilShop>
+        let mut map: std::collections::HashMap<u32, Vec<ItemSource>> =
+            std::collections::HashMap::new();
+        let result = self.for_each_row_tolerant("GilShopItem", Language::None, |row_id, row| {
+            let item_id = match row.columns.first() {
+                Some(Field::Int32(v)) if *v > 0 => *v as u32,
+                _ => return,
+            };
+            // 组合显示名: "NPC名 - 商店分类名" 或 "商店分类名"
+            let category = shop_names.get(&row_id).filter(|s| !s.is_empty()).cloned();
+            let npc = npcs.get(row_id);
+            let shop_name = match (npc.map(|n| n.name.clone()), category) {
+                (Some(n), Some(c)) => format!("{} - {}", n, c),
+                (Some(n), None) => n,
+                (None, Some(c)) => c,
+                (None, None) => "金币商店".to_string(),
+            };
+            map.entry(item_id).or_default().push(ItemSource::GilShop {
+                shop_name,
+                npc_location: npc.and_then(|n| n.location.clone()),
+                nearest_aetheryte: npc.and_then(|n| n.nearest_aetheryte.clone()),
+            });
+        });
+        if result.is_err() {
+            return std::collections::HashMap::new();
         }
         println!("GilShopItem: {} 种商品", map.len());
         map
     }
 
     /// 加载 SpecialShop 表, 返回 item_id -> Vec<ItemSource::SpecialShop>
-    pub fn load_special_shop_sources(&self) -> std::collections::HashMap<u32, Vec<ItemSource>> {
-        let mut physis = self.physis.borrow_mut();
-        let exh = match physis.read_excel_sheet_header("SpecialShop") {
-            Ok(h) => h,
-            Err(_) => return std::collections::HashMap::new(),
-        };
-        let sheet = match physis.read_excel_sheet(&exh, "SpecialShop", Language::ChineseSimplified)
-        {
-            Ok(s) => s,
-            Err(_) => return std::collections::HashMap::new(),
-        };
-
+    pub fn load_special_shop_sources(
+        &self,
+        npcs: &ShopNpcIndex,
+    ) -> std::collections::HashMap<u32, Vec<ItemSource>> {
         let mut map: std::collections::HashMap<u32, Vec<ItemSource>> =
             std::collections::HashMap::new();
-        for page in &sheet.pages {
-            for (_row_id, row) in page.into_iter().flatten_subrows() {
+        let result = self.for_each_row_tolerant(
+            "SpecialShop",
+            Language::ChineseSimplified,
+            |row_id, row| {
                 let shop_name = match row.columns.first() {
                     Some(Field::String(s)) => s.clone(),
                     _ => String::new(),
                 };
+                let npc = npcs.get(row_id);
 
                 // 60 个交易槽位
                 for i in 0..60usize {
@@ -935,63 +1435,164 @@ impl GameData {
                         };
                         let source = ItemSource::SpecialShop {
                             shop_name: shop_name.clone(),
+                            npc_name: npc.map(|n| n.name.clone()),
+                            npc_location: npc.and_then(|n| n.location.clone()),
                             cost_item_id: cost_item,
                             cost_count,
                         };
                         map.entry(receive_item).or_default().push(source);
                     }
                 }
-            }
+            },
+        );
+        if result.is_err() {
+            return std::collections::HashMap::new();
         }
         println!("SpecialShop: {} 种可兑换物品", map.len());
         map
     }
 
-    /// 加载 GatheringItem 表, 返回可采集的 item_id 集合
-    pub fn load_gathering_items(&self) -> std::collections::HashSet<u32> {
-        let mut physis = self.physis.borrow_mut();
-        let exh = match physis.read_excel_sheet_header("GatheringItem") {
-            Ok(h) => h,
-            Err(_) => return std::collections::HashSet::new(),
-        };
-        let sheet = match physis.read_excel_sheet(&exh, "GatheringItem", Language::None) {
-            Ok(s) => s,
-            Err(_) => return std::collections::HashSet::new(),
-        };
-        let mut items = std::collections::HashSet::new();
-        for page in &sheet.pages {
-            for (_row_id, row) in page.into_iter().flatten_subrows() {
+    /// 加载军票商店 (GCScripShopItem → GCScripShopCategory → GrandCompany),
+    /// 返回 item_id -> Vec<ItemSource::SpecialShop>，消耗物品为对应军队的军票
+    pub fn load_gc_shop_sources(&self) -> std::collections::HashMap<u32, Vec<ItemSource>> {
+        // 1. GrandCompany: gc_id -> 军队名
+        let mut gc_names: std::collections::HashMap<u8, String> = std::collections::HashMap::new();
+        let _ = self.for_each_row_tolerant(
+            "GrandCompany",
+            Language::ChineseSimplified,
+            |row_id, row| {
+                if let Some(Field::String(name)) = row.columns.first() {
+                    if !name.is_empty() && row_id <= 255 {
+                        gc_names.insert(row_id as u8, name.clone());
+                    }
+                }
+            },
+        );
+
+        // 2. GCScripShopCategory: category_id -> gc_id
+        let mut category_gc: std::collections::HashMap<u32, u8> = std::collections::HashMap::new();
+        let result =
+            self.for_each_row_tolerant("GCScripShopCategory", Language::None, |row_id, row| {
+                let gc_id = match row.columns.first() {
+                    Some(Field::Int8(v)) if *v > 0 => *v as u8,
+                    Some(Field::UInt8(v)) if *v > 0 => *v,
+                    _ => return,
+                };
+                category_gc.insert(row_id, gc_id);
+            });
+        if result.is_err() {
+            return std::collections::HashMap::new();
+        }
+
+        // 3. GCScripShopItem: 行号为分类 id，子行为商品 (Item, RequiredRank, CostGCSeals, ...)
+        let mut map: std::collections::HashMap<u32, Vec<ItemSource>> =
+            std::collections::HashMap::new();
+        let result =
+            self.for_each_row_tolerant("GCScripShopItem", Language::None, |row_id, row| {
+                let Some(&gc_id) = category_gc.get(&row_id) else {
+                    return;
+                };
                 let item_id = match row.columns.first() {
                     Some(Field::Int32(v)) if *v > 0 => *v as u32,
-                    _ => continue,
+                    _ => return,
+                };
+                let cost_count = match row.columns.get(2) {
+                    Some(Field::UInt32(v)) if *v > 0 => *v,
+                    _ => return,
                 };
-                items.insert(item_id);
+                let shop_name = match gc_names.get(&gc_id) {
+                    Some(name) => format!("{} 军需品交换", name),
+                    None => "军需品交换".to_string(),
+                };
+                map.entry(item_id)
+                    .or_default()
+                    .push(ItemSource::SpecialShop {
+                        shop_name,
+                        npc_name: None,
+                        npc_location: None,
+                        cost_item_id: gc_seal_item_id(gc_id),
+                        cost_count,
+                    });
+            });
+        if result.is_err() {
+            return std::collections::HashMap::new();
+        }
+        println!("GCScripShopItem: {} 种可兑换物品", map.len());
+        map
+    }
+
+    /// 加载 GatheringItem 表, 返回可采集的 item_id 集合
+    pub fn load_gathering_items(&self) -> std::collections::HashSet<u32> {
+        let mut items = std::collections::HashSet::new();
+        let result = self.for_each_row("GatheringItem", Language::None, |_row_id, row| {
+            if let Some(Field::Int32(v)) = row.columns.first() {
+                if *v > 0 {
+                    items.insert(*v as u32);
+                }
             }
+        });
+        if result.is_err() {
+            return std::collections::HashSet::new();
         }
         println!("GatheringItem: {} 种可采集物品", items.len());
         items
     }
 
+    /// 加载 Orchestrion 表 (乐谱)，返回 row_id -> 曲名/说明
+    /// 乐谱物品的 Item.additional_data 即为该表 row_id
+    pub fn load_orchestrion_list(&self) -> std::collections::HashMap<u32, CollectionInfo> {
+        self.load_collection_sheet("Orchestrion")
+    }
+
+    /// 加载 TripleTriadCard 表 (幻卡)，返回 row_id -> 卡名/说明
+    /// 幻卡物品的 Item.additional_data 即为该表 row_id
+    pub fn load_triple_triad_cards(&self) -> std::collections::HashMap<u32, CollectionInfo> {
+        self.load_collection_sheet("TripleTriadCard")
+    }
+
+    /// 收藏品表的公共读取逻辑:
+    /// col[0] = 名称 (String)，说明取最后一个非空的 String 列
+    /// (Orchestrion 为 col[1]，TripleTriadCard 在若干语法列之后)
+    fn load_collection_sheet(
+        &self,
+        sheet_name: &str,
+    ) -> std::collections::HashMap<u32, CollectionInfo> {
+        let mut map = std::collections::HashMap::new();
+        let result = self.for_each_row(sheet_name, Language::ChineseSimplified, |row_id, row| {
+            let name = match row.columns.first() {
+                Some(Field::String(s)) if !s.is_empty() => s.clone(),
+                _ => return,
+            };
+            let description = row
+                .columns
+                .iter()
+                .skip(1)
+                .filter_map(|f| match f {
+                    Field::String(s) if !s.is_empty() => Some(s.clone()),
+                    _ => None,
+                })
+                .last()
+                .unwrap_or_default();
+            map.insert(row_id, CollectionInfo { name, description });
+        });
+        if result.is_err() {
+            return std::collections::HashMap::new();
+        }
+        println!("{}: {} 条记录", sheet_name, map.len());
+        map
+    }
+
     /// 加载 SecretRecipeBook 表, 返回多种键 -> 秘籍名称的映射
     /// 键包括:
     ///   - row_id (1-111)
     ///   - item_id (秘籍物品ID)
     ///   - recipe_col40_value (row_id + 546, 用于直接用 Recipe.col[40] 查找)
     pub fn load_secret_recipe_book_names(&self) -> std::collections::HashMap<u32, String> {
-        let mut physis = self.physis.borrow_mut();
-        let exh = match physis.read_excel_sheet_header("SecretRecipeBook") {
-            Ok(h) => h,
-            Err(_) => return std::collections::HashMap::new(),
-        };
-        let sheet =
-            match physis.read_excel_sheet(&exh, "SecretRecipeBook", Language::ChineseSimplified) {
-                Ok(s) => s,
-                Err(_) => return std::collections::HashMap::new(),
-            };
-
         let mut map = std::collections::HashMap::new();
-        for page in &sheet.pages {
-            for (row_id, row) in page.into_iter().flatten_subrows() {
+        let result = self.for_each_row(
+            "SecretRecipeBook",
+            Language::ChineseSimplified,
+            |row_id, row| {
                 // SecretRecipeBook 表: col[0] = Item (Int32), col[1] = Name (String)
                 if let (Some(Field::Int32(item_id)), Some(Field::String(name))) =
                     (row.columns.first(), row.columns.get(1))
@@ -1006,7 +1607,10 @@ impl GameData {
                         map.insert(row_id + 546, name.clone());
                     }
                 }
-            }
+            },
+        );
+        if result.is_err() {
+            return std::collections::HashMap::new();
         }
         println!("SecretRecipeBook: {} 条秘籍记录（含反向映射和Recipe.col40映射）", map.len());
         map
@@ -1014,28 +1618,245 @@ impl GameData {
 
     /// 加载 RecipeLevelTable 表, 返回 row_id -> 配方等级
     pub fn load_recipe_level_table(&self) -> std::collections::HashMap<u16, u8> {
-        let mut physis = self.physis.borrow_mut();
-        let exh = match physis.read_excel_sheet_header("RecipeLevelTable") {
-            Ok(h) => h,
-            Err(_) => return std::collections::HashMap::new(),
-        };
-        let sheet =
-            match physis.read_excel_sheet(&exh, "RecipeLevelTable", Language::None) {
-                Ok(s) => s,
-                Err(_) => return std::collections::HashMap::new(),
-            };
         let mut map = std::collections::HashMap::new();
-        for page in &sheet.pages {
-            for (row_id, row) in page.into_iter().flatten_subrows() {
-                // RecipeLevelTable 表: col[0] = ClassJobLevel (UInt8, 配方所需职业等级)
-                if let Some(Field::UInt8(level)) = row.columns.first() {
-                    if *level > 0 && row_id <= u16::MAX as u32 {
-                        map.insert(row_id as u16, *level);
-                    }
+        let result = self.for_each_row("RecipeLevelTable", Language::None, |row_id, row| {
+            // RecipeLevelTable 表: col[0] = ClassJobLevel (UInt8, 配方所需职业等级)
+            if let Some(Field::UInt8(level)) = row.columns.first() {
+                if *level > 0 && row_id <= u16::MAX as u32 {
+                    map.insert(row_id as u16, *level);
                 }
             }
+        });
+        if result.is_err() {
+            return std::collections::HashMap::new();
         }
         println!("RecipeLevelTable: {} 条等级记录", map.len());
         map
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_game(source: MockSource) -> GameData {
+        GameData::with_source(
+            PathBuf::from("mock/game"),
+            Box::new(source),
+            vec!["ffxiv".to_string()],
+        )
+    }
+
+    #[test]
+    fn loads_items_from_all_mock_pages() {
+        let game = mock_game(MockSource::default());
+        let items = game.load_all_items(&game.load_ui_category_names());
+        // 30 件防具 + 4 件饰品 + 6 种素材，分布在 3 页
+        assert_eq!(items.len(), 40);
+        assert!(game.take_partial_loads().is_empty());
+
+        let helm = items.iter().find(|i| i.row_id == 1010).unwrap();
+        assert_eq!(helm.name, "青铜头盔");
+        assert_eq!(helm.equip_slot_category, 3);
+        assert_eq!(helm.model_main, 2 | (1 << 16));
+        assert_eq!(helm.price_low, 20);
+        assert_eq!(helm.sheet_dye_count, Some(1));
+        assert_eq!(helm.stack_size, 1);
+
+        let ore = items.iter().find(|i| i.row_id == 2001).unwrap();
+        assert_eq!(ore.name, "铜矿");
+        assert_eq!(ore.price_mid, 2);
+        assert_eq!(ore.stack_size, 999);
+        assert_eq!(&*ore.description, "演示数据");
+    }
+
+    #[test]
+    fn facewear_is_marked_from_given_category_names() {
+        let game = mock_game(MockSource::default());
+        let items = game.load_all_items(&game.load_ui_category_names());
+        assert!(!items.iter().any(|i| i.facewear));
+
+        // 演示数据没有面饰分类，把铜矿的分类当作面饰
+        let ore = items.iter().find(|i| i.row_id == 2001).unwrap();
+        let names = std::collections::HashMap::from([(
+            ore.item_ui_category,
+            crate::domain::FACEWEAR_UI_CATEGORY_NAME.to_string(),
+        )]);
+        let items = game.load_all_items(&names);
+        let ore = items.iter().find(|i| i.row_id == 2001).unwrap();
+        assert!(ore.facewear);
+        let helm = items.iter().find(|i| i.row_id == 1010).unwrap();
+        assert!(!helm.facewear);
+    }
+
+    #[test]
+    fn corrupt_item_page_keeps_other_pages() {
+        let mut source = MockSource::default();
+        let pages = source.sheet_pages("Item").unwrap();
+        assert_eq!(pages.len(), 3);
+        source.corrupt_page("Item", pages[1]);
+        let game = mock_game(source);
+
+        let items = game.load_all_items(&game.load_ui_category_names());
+        // 第 2 页的 16 行丢失，第 1、3 页的行照常加载
+        assert_eq!(items.len(), 40 - 16);
+        assert!(items.iter().any(|i| i.row_id == 1000));
+        assert!(items.iter().any(|i| i.row_id == 2006));
+        assert!(!items.iter().any(|i| i.row_id == pages[1]));
+
+        let loads = game.take_partial_loads();
+        assert_eq!(loads.len(), 1);
+        assert_eq!(loads[0].loaded_pages, 2);
+        assert_eq!(loads[0].total_pages, 3);
+        assert_eq!(
+            loads[0].summary(),
+            format!("Item 表: 2/3 页加载成功 (失败: Item_{})", pages[1])
+        );
+        // 已取走，不会重复汇报
+        assert!(game.take_partial_loads().is_empty());
+    }
+
+    #[test]
+    fn all_pages_corrupt_is_an_error() {
+        let mut source = MockSource::default();
+        for start_id in source.sheet_pages("Item").unwrap() {
+            source.corrupt_page("Item", start_id);
+        }
+        let game = mock_game(source);
+        assert!(game
+            .load_all_items(&game.load_ui_category_names())
+            .is_empty());
+        assert!(game.take_partial_loads().is_empty());
+    }
+
+    #[test]
+    fn loads_stains_and_recipes() {
+        let game = mock_game(MockSource::default());
+
+        let stains = game.load_stain_list();
+        assert_eq!(stains.len(), 6);
+        let red = stains.iter().find(|s| s.id == 3).unwrap();
+        assert_eq!(red.name, "烈焰红");
+        assert_eq!(red.color, [230, 62, 37]);
+        assert_eq!(red.shade, 4);
+
+        let recipes = game.load_recipes();
+        assert_eq!(recipes.len(), 6);
+        let ingot = recipes.iter().find(|r| r.result_item_id == 2003).unwrap();
+        assert_eq!(ingot.ingredients, vec![(2001, 3), (2002, 1)]);
+        assert_eq!(ingot.craft_type, 1);
+        assert!(ingot.can_hq);
+        let body = recipes.iter().find(|r| r.result_item_id == 1011).unwrap();
+        assert_eq!(body.secret_recipe_book, 1);
+        assert_eq!(body.material_quality_factor, 50);
+
+        let levels = game.load_recipe_level_table();
+        assert_eq!(levels.get(&2), Some(&15));
+        let books = game.load_secret_recipe_book_names();
+        assert_eq!(books.get(&1).map(String::as_str), Some("演示秘籍"));
+        assert_eq!(books.get(&547).map(String::as_str), Some("演示秘籍"));
+    }
+
+    #[test]
+    fn resolves_shop_npcs_and_locations() {
+        let game = mock_game(MockSource::default());
+        let npcs = game.load_shop_npcs();
+
+        let gil = game.load_gil_shop_items(&npcs);
+        let Some(
+            [ItemSource::GilShop {
+                shop_name,
+                npc_location,
+                nearest_aetheryte,
+            }],
+        ) = gil.get(&2004).map(Vec::as_slice)
+        else {
+            panic!("麻布应有一个金币商店来源");
+        };
+        assert_eq!(shop_name, "演示杂货商");
+        assert_eq!(npc_location.as_deref(), Some("演示城"));
+        assert_eq!(nearest_aetheryte.as_deref(), Some("演示广场"));
+
+        let special = game.load_special_shop_sources(&npcs);
+        let Some(
+            [ItemSource::SpecialShop {
+                npc_name,
+                cost_item_id,
+                cost_count,
+                ..
+            }],
+        ) = special.get(&1050).map(Vec::as_slice)
+        else {
+            panic!("陨铁头盔应有一个兑换来源");
+        };
+        assert_eq!(npc_name.as_deref(), Some("演示兑换员"));
+        assert_eq!((*cost_item_id, *cost_count), (2006, 5));
+    }
+
+    #[test]
+    fn loads_name_tables() {
+        let game = mock_game(MockSource::default());
+        assert_eq!(
+            game.load_ui_category_names().get(&34).map(String::as_str),
+            Some("头部防具")
+        );
+        assert_eq!(
+            game.load_class_job_names().get(&10).map(String::as_str),
+            Some("铸甲匠")
+        );
+        assert_eq!(game.load_gathering_items(), HashSet::from([2001, 2002]));
+        // 演示数据只有中文，英文表读取失败时返回空表
+        assert!(game.load_english_item_names().is_empty());
+        // 不存在的表
+        assert!(game.load_base_param_names().is_empty());
+    }
+
+    #[test]
+    fn mock_model_goes_through_mdl_parser() {
+        let game = mock_game(MockSource::default());
+        let result = load_mdl(&game, "chara/equipment/e0001/model/c0101e0001_top.mdl").unwrap();
+        assert_eq!(result.meshes.len(), 1);
+        let mesh = &result.meshes[0];
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.indices.len(), 36);
+        assert!(mesh
+            .indices
+            .iter()
+            .all(|&i| (i as usize) < mesh.vertices.len()));
+        assert_eq!(
+            result.material_names,
+            vec!["/mt_mock_top_a.mtrl".to_string()]
+        );
+
+        // 身体长方体中心约在 y = 1.22，法线与切线为单位向量
+        let bbox = compute_bounding_box(&result.meshes);
+        assert!((bbox.center()[1] - 1.22).abs() < 1e-4);
+        for v in &mesh.vertices {
+            let len = v.normal.iter().map(|x| x * x).sum::<f32>().sqrt();
+            assert!((len - 1.0).abs() < 1e-4);
+            assert!(v.tangent[..3].iter().any(|t| t.abs() > 0.9));
+        }
+    }
+
+    #[test]
+    fn read_from_missing_expansion_names_it() {
+        let game = mock_game(MockSource::default());
+        let err = game.read_file("bg/ex4/01_xxx/twn/a.sgb").unwrap_err();
+        assert_eq!(
+            err,
+            "该文件属于 ex4，但未检测到 ex4 数据: bg/ex4/01_xxx/twn/a.sgb"
+        );
+        // 基础游戏与已安装仓库的路径只报告读取失败
+        let err = game.read_file("bg/ffxiv/sea_s1/a.sgb").unwrap_err();
+        assert_eq!(err, "无法读取: bg/ffxiv/sea_s1/a.sgb");
+
+        let game = GameData::with_source(
+            PathBuf::from("mock/game"),
+            Box::new(MockSource::default()),
+            vec!["ffxiv".to_string(), "ex4".to_string()],
+        );
+        let err = game.read_file("bg/ex4/01_xxx/twn/a.sgb").unwrap_err();
+        assert_eq!(err, "无法读取: bg/ex4/01_xxx/twn/a.sgb");
+        assert_eq!(game.missing_expansions(), ["ex1", "ex2", "ex3", "ex5"]);
+    }
+}
@@ -0,0 +1,115 @@
+//! sqpack 数据仓库检测: 基础游戏 (ffxiv) 与各资料片 (ex1..ex5) 是否安装
+
+use std::path::Path;
+
+/// 已知的数据仓库，按资料片顺序
+pub const KNOWN_REPOSITORIES: [&str; 6] = ["ffxiv", "ex1", "ex2", "ex3", "ex4", "ex5"];
+
+/// 列出 sqpack 目录下存在索引文件 (.index / .index2) 的仓库，按名称排序
+///
+/// 通过符号链接放在其他磁盘的资料片目录同样会被识别。
+pub fn installed_repositories(sqpack_dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(sqpack_dir) else {
+        return Vec::new();
+    };
+    let mut repos: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let has_index = std::fs::read_dir(entry.path()).ok()?.flatten().any(|f| {
+                f.path()
+                    .extension()
+                    .is_some_and(|ext| ext == "index" || ext == "index2")
+            });
+            has_index.then_some(name)
+        })
+        .collect();
+    repos.sort_by_key(|name| repository_order(name));
+    repos
+}
+
+fn repository_order(name: &str) -> (usize, String) {
+    let known = KNOWN_REPOSITORIES.iter().position(|r| *r == name);
+    (known.unwrap_or(KNOWN_REPOSITORIES.len()), name.to_string())
+}
+
+/// 游戏路径所属的资料片仓库，属于基础游戏时返回 None
+///
+/// 只有 bg/cut/music 等分类按第二段目录区分仓库 (如 `bg/ex4/...`)，
+/// chara/ui 等分类的文件全部位于 ffxiv 仓库。
+pub fn expansion_for_path(path: &str) -> Option<&str> {
+    let mut segments = path.split('/');
+    let _category = segments.next()?;
+    let repo = segments.next()?;
+    let digits = repo.strip_prefix("ex")?;
+    (!digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())).then_some(repo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expansion_from_second_segment() {
+        let cases = [
+            ("bg/ex4/01_xxx/twn/bgparts/a.mdl", Some("ex4")),
+            ("bg/ex1/01_roc_r2/fld/r2f1/level/bg.lgb", Some("ex1")),
+            ("cut/ex5/sound/a.scd", Some("ex5")),
+            ("music/ex2/BGM_EX2_Field.scd", Some("ex2")),
+            ("bg/ffxiv/sea_s1/twn/s1t1/level/bg.lgb", None),
+            ("chara/equipment/e0001/model/c0101e0001_top.mdl", None),
+            ("ui/icon/000000/000001.tex", None),
+            ("bg/ex/a.mdl", None),
+            ("bg/exa/a.mdl", None),
+            ("bg", None),
+            ("", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(expansion_for_path(path), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn lists_repositories_with_index_files() {
+        let sqpack = std::env::temp_dir()
+            .join(format!("tomestone-repo-{}", std::process::id()))
+            .join("sqpack");
+        let _ = std::fs::remove_dir_all(&sqpack);
+        let touch = |repo: &str, file: &str| {
+            std::fs::create_dir_all(sqpack.join(repo)).unwrap();
+            std::fs::write(sqpack.join(repo).join(file), b"").unwrap();
+        };
+        touch("ex2", "020000.win32.index");
+        touch("ffxiv", "000000.win32.index");
+        touch("ex1", "010000.win32.index2");
+        touch("mod", "990000.win32.index");
+        // 只有数据文件、没有索引的资料片视为未安装
+        touch("ex3", "030000.win32.dat0");
+        std::fs::write(sqpack.join("ex4"), b"").unwrap();
+
+        // 放在其他位置、通过符号链接接入的资料片
+        #[cfg(unix)]
+        {
+            let elsewhere = sqpack.parent().unwrap().join("other-drive-ex5");
+            std::fs::create_dir_all(&elsewhere).unwrap();
+            std::fs::write(elsewhere.join("050000.win32.index"), b"").unwrap();
+            std::os::unix::fs::symlink(&elsewhere, sqpack.join("ex5")).unwrap();
+        }
+
+        let repos = installed_repositories(&sqpack);
+        let _ = std::fs::remove_dir_all(sqpack.parent().unwrap());
+
+        let mut expected = vec!["ffxiv", "ex1", "ex2"];
+        if cfg!(unix) {
+            expected.push("ex5");
+        }
+        expected.push("mod");
+        assert_eq!(repos, expected);
+    }
+
+    #[test]
+    fn missing_sqpack_dir_lists_nothing() {
+        assert!(installed_repositories(Path::new("does-not-exist/sqpack")).is_empty());
+    }
+}
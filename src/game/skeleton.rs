@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use glam::{Mat3, Mat4, Quat, Vec3};
 use physis::skeleton::Skeleton;
@@ -36,12 +36,15 @@ pub fn compute_bind_pose_matrices(skeleton: &Skeleton) -> HashMap<String, Mat4>
 
 pub struct SkeletonCache {
     cache: HashMap<String, HashMap<String, Mat4>>,
+    /// 骨架加载失败的种族，避免重复读取
+    unavailable: HashSet<String>,
 }
 
 impl SkeletonCache {
     pub fn new() -> Self {
         Self {
             cache: HashMap::new(),
+            unavailable: HashSet::new(),
         }
     }
 
@@ -50,13 +53,54 @@ impl SkeletonCache {
         race_code: &str,
         game: &GameData,
     ) -> Option<&HashMap<String, Mat4>> {
+        if self.unavailable.contains(race_code) {
+            return None;
+        }
         if !self.cache.contains_key(race_code) {
-            let skeleton = game.load_skeleton(race_code)?;
+            let Some(skeleton) = game.load_skeleton(race_code) else {
+                self.unavailable.insert(race_code.to_string());
+                return None;
+            };
             let matrices = compute_bind_pose_matrices(&skeleton);
             self.cache.insert(race_code.to_string(), matrices);
         }
         self.cache.get(race_code)
     }
+
+    /// 骨架可以加载的种族代码，按 RACE_CODES 顺序
+    pub fn available_races(&mut self, game: &GameData) -> Vec<&'static str> {
+        crate::domain::RACE_CODES
+            .iter()
+            .copied()
+            .filter(|rc| self.get_bind_pose(rc, game).is_some())
+            .collect()
+    }
+
+    /// 该种族骨架是否包含全部骨骼 (骨架无法加载时为 false)
+    pub fn covers_bones(&mut self, race_code: &str, game: &GameData, bones: &[String]) -> bool {
+        self.get_bind_pose(race_code, game)
+            .is_some_and(|bind| bones.iter().all(|b| bind.contains_key(b)))
+    }
+}
+
+/// 跨种族蒙皮的结果: 源或目标骨架中找不到的骨骼会导致顶点位置错误
+#[derive(Debug, Clone, Default)]
+pub struct SkinningReport {
+    /// 找不到的骨骼名 (去重并排序)
+    pub unmatched_bones: BTreeSet<String>,
+    /// 受影响的顶点数 (至少一个有效权重引用了找不到的骨骼)
+    pub affected_vertices: usize,
+}
+
+impl SkinningReport {
+    pub fn is_empty(&self) -> bool {
+        self.unmatched_bones.is_empty()
+    }
+
+    pub fn merge(&mut self, other: SkinningReport) {
+        self.unmatched_bones.extend(other.unmatched_bones);
+        self.affected_vertices += other.affected_vertices;
+    }
 }
 
 pub fn apply_skinning(
@@ -65,7 +109,8 @@ pub fn apply_skinning(
     bone_tables: &[MdlBoneTable],
     source_bind: &HashMap<String, Mat4>,
     target_bind: &HashMap<String, Mat4>,
-) {
+) -> SkinningReport {
+    let mut report = SkinningReport::default();
     for mesh in meshes.iter_mut() {
         let table = match bone_tables.get(mesh.bone_table_index as usize) {
             Some(t) => t,
@@ -80,6 +125,7 @@ pub fn apply_skinning(
 
             let inv_total = 1.0 / total_weight;
             let mut blended_mat = Mat4::ZERO;
+            let mut affected = false;
 
             for i in 0..4 {
                 let w = skin.blend_weights[i] * inv_total;
@@ -99,19 +145,27 @@ pub fn apply_skinning(
                     None => continue,
                 };
 
-                let source_mat = source_bind
-                    .get(bone_name)
-                    .copied()
-                    .unwrap_or(Mat4::IDENTITY);
-                let target_mat = target_bind
-                    .get(bone_name)
-                    .copied()
-                    .unwrap_or(Mat4::IDENTITY);
+                let (source_mat, target_mat) =
+                    match (source_bind.get(bone_name), target_bind.get(bone_name)) {
+                        (Some(&source), Some(&target)) => (source, target),
+                        (source, target) => {
+                            report.unmatched_bones.insert(bone_name.clone());
+                            affected = true;
+                            (
+                                source.copied().unwrap_or(Mat4::IDENTITY),
+                                target.copied().unwrap_or(Mat4::IDENTITY),
+                            )
+                        }
+                    };
                 let remap = target_mat * source_mat.inverse();
 
                 blended_mat += remap * w;
             }
 
+            if affected {
+                report.affected_vertices += 1;
+            }
+
             let pos = Vec3::from(mesh.vertices[vi].position);
             let new_pos = blended_mat.transform_point3(pos);
             mesh.vertices[vi].position = new_pos.into();
@@ -135,4 +189,121 @@ pub fn apply_skinning(
             ];
         }
     }
+    report
+}
+
+/// 把没有蒙皮权重的顶点随指定骨骼从源骨架移到目标骨架 (返回移动的顶点数)
+///
+/// 面饰的部分网格不带权重，`apply_skinning` 会原样保留，借用其他种族模型时会停在源种族
+/// 的头部高度；这里按 `bone` (面饰用 j_kao) 在两套绑定姿势间的变换整体平移/缩放。
+/// 任一骨架缺少该骨骼时不做处理
+pub fn place_unskinned_at_bone(
+    meshes: &mut [MeshData],
+    bone_tables: &[MdlBoneTable],
+    bone: &str,
+    source_bind: &HashMap<String, Mat4>,
+    target_bind: &HashMap<String, Mat4>,
+) -> usize {
+    let (Some(&source), Some(&target)) = (source_bind.get(bone), target_bind.get(bone)) else {
+        return 0;
+    };
+    let remap = target * source.inverse();
+    let mat3 = Mat3::from_mat4(remap);
+    let mut moved = 0;
+    for mesh in meshes.iter_mut() {
+        let has_table = bone_tables.get(mesh.bone_table_index as usize).is_some();
+        for (vi, vertex) in mesh.vertices.iter_mut().enumerate() {
+            let weighted = has_table
+                && mesh
+                    .skin_vertices
+                    .get(vi)
+                    .is_some_and(|skin| skin.blend_weights.iter().sum::<f32>() >= 1e-6);
+            if weighted {
+                continue;
+            }
+            vertex.position = remap.transform_point3(Vec3::from(vertex.position)).into();
+            vertex.normal = (mat3 * Vec3::from(vertex.normal))
+                .normalize_or_zero()
+                .into();
+            moved += 1;
+        }
+    }
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::SkinVertex;
+    use tomestone_render::Vertex;
+
+    fn vertex(position: [f32; 3]) -> Vertex {
+        Vertex {
+            position,
+            normal: [0.0, 0.0, 1.0],
+            uv: [0.0, 0.0],
+            color: [1.0, 1.0, 1.0, 1.0],
+            tangent: [1.0, 0.0, 0.0, 1.0],
+        }
+    }
+
+    fn mesh(positions: &[[f32; 3]], weights: &[[f32; 4]]) -> MeshData {
+        MeshData {
+            vertices: positions.iter().map(|&p| vertex(p)).collect(),
+            indices: Vec::new(),
+            material_index: 0,
+            bone_table_index: 0,
+            skin_vertices: weights
+                .iter()
+                .map(|&blend_weights| SkinVertex {
+                    blend_weights,
+                    blend_indices: [0; 4],
+                })
+                .collect(),
+            bone_names: Vec::new(),
+        }
+    }
+
+    fn head_at(height: f32) -> HashMap<String, Mat4> {
+        HashMap::from([(
+            "j_kao".to_string(),
+            Mat4::from_translation(Vec3::new(0.0, height, 0.0)),
+        )])
+    }
+
+    #[test]
+    fn unskinned_vertices_follow_head_bone() {
+        let mut meshes = [mesh(
+            &[[0.0, 1.5, 0.1], [0.1, 1.6, 0.1]],
+            &[[0.0; 4], [1.0, 0.0, 0.0, 0.0]],
+        )];
+        let tables = [MdlBoneTable {
+            bone_indices: vec![0],
+        }];
+        let moved =
+            place_unskinned_at_bone(&mut meshes, &tables, "j_kao", &head_at(1.5), &head_at(1.2));
+        assert_eq!(moved, 1);
+        let y: Vec<f32> = meshes[0].vertices.iter().map(|v| v.position[1]).collect();
+        assert!((y[0] - 1.2).abs() < 1e-5);
+        // 带权重的顶点由 apply_skinning 负责，这里不动
+        assert!((y[1] - 1.6).abs() < 1e-5);
+    }
+
+    #[test]
+    fn meshes_without_bone_table_are_moved_entirely() {
+        let mut meshes = [mesh(&[[0.0, 1.5, 0.0]], &[[1.0, 0.0, 0.0, 0.0]])];
+        let moved =
+            place_unskinned_at_bone(&mut meshes, &[], "j_kao", &head_at(1.5), &head_at(1.0));
+        assert_eq!(moved, 1);
+        assert!((meshes[0].vertices[0].position[1] - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn missing_bone_leaves_meshes_untouched() {
+        let mut meshes = [mesh(&[[0.0, 1.5, 0.0]], &[[0.0; 4]])];
+        let moved =
+            place_unskinned_at_bone(&mut meshes, &[], "j_kao", &HashMap::new(), &head_at(1.0));
+        assert_eq!(moved, 0);
+        assert_eq!(meshes[0].vertices[0].position, [0.0, 1.5, 0.0]);
+    }
 }
@@ -0,0 +1,167 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+
+use physis::excel::Field;
+use physis::exh::EXH;
+use physis::resource::{Resource as _, SqPackResource};
+use physis::skeleton::Skeleton;
+use physis::stm::StainingTemplate;
+use physis::Language;
+
+use tomestone_render::TextureData;
+
+use super::ParsedMaterial;
+
+/// Excel 表的一行 (含子行的表每个子行各占一行，共用行号)
+#[derive(Debug, Clone, Default)]
+pub struct SheetRow {
+    pub columns: Vec<Field>,
+}
+
+/// 游戏资源来源: 文件读取、贴图/材质解析与 Excel 表读取
+///
+/// 真实数据来自 sqpack (`SqPackSource`)，无游戏安装时可使用合成数据 (`MockSource`)。
+/// 加载器只通过 [`GameSource::sheet_pages`] / [`GameSource::read_sheet_page`] 逐页读取表，
+/// 两种数据源走同一套解析代码
+pub trait GameSource: Send {
+    /// 是否为合成数据 (演示模式)
+    fn is_synthetic(&self) -> bool {
+        false
+    }
+
+    fn game_version(&self) -> String;
+
+    fn read_file(&self, path: &str) -> Option<Vec<u8>>;
+
+    fn parsed_tex(&self, path: &str) -> Option<TextureData>;
+
+    fn parsed_mtrl(&self, path: &str) -> Option<ParsedMaterial>;
+
+    fn staining_template(&self, path: &str) -> Result<StainingTemplate, String>;
+
+    fn skeleton(&self, path: &str) -> Option<Skeleton>;
+
+    fn sheet_names(&self) -> Vec<String>;
+
+    /// 表各页的起始行号 (按表头中的顺序)
+    fn sheet_pages(&self, name: &str) -> Result<Vec<u32>, String>;
+
+    /// 只读取表中起始于 `start_id` 的一页
+    fn read_sheet_page(
+        &self,
+        name: &str,
+        start_id: u32,
+        language: Language,
+    ) -> Result<Vec<(u32, SheetRow)>, String>;
+
+    /// 原始表头 (资源浏览器显示列类型用)，合成数据没有
+    fn read_excel_sheet_header(&self, name: &str) -> Result<EXH, String>;
+}
+
+/// 基于 physis 的 sqpack 数据源
+pub struct SqPackSource {
+    game_dir: PathBuf,
+    physis: RefCell<SqPackResource>,
+}
+
+impl SqPackSource {
+    pub fn new(game_dir: &Path) -> Self {
+        let physis = RefCell::new(SqPackResource::from_existing(game_dir.to_str().unwrap()));
+        Self {
+            game_dir: game_dir.to_path_buf(),
+            physis,
+        }
+    }
+}
+
+impl GameSource for SqPackSource {
+    /// game/ffxivgame.ver，读取失败返回 "unknown"
+    fn game_version(&self) -> String {
+        std::fs::read_to_string(self.game_dir.join("ffxivgame.ver"))
+            .map(|s| s.trim().to_string())
+            .unwrap_or_else(|_| "unknown".to_string())
+    }
+
+    fn read_file(&self, path: &str) -> Option<Vec<u8>> {
+        self.physis.borrow_mut().read(path)
+    }
+
+    fn parsed_tex(&self, path: &str) -> Option<TextureData> {
+        let tex: physis::tex::Texture = self.physis.borrow_mut().parsed(path).ok()?;
+        Some(TextureData {
+            rgba: tex.rgba.into(),
+            width: tex.width,
+            height: tex.height,
+        })
+    }
+
+    fn parsed_mtrl(&self, path: &str) -> Option<ParsedMaterial> {
+        let mtrl: physis::mtrl::Material = self.physis.borrow_mut().parsed(path).ok()?;
+        Some(ParsedMaterial {
+            texture_paths: mtrl.texture_paths,
+            color_table: mtrl.color_table,
+            color_dye_table: mtrl.color_dye_table,
+        })
+    }
+
+    fn staining_template(&self, path: &str) -> Result<StainingTemplate, String> {
+        self.physis
+            .borrow_mut()
+            .parsed(path)
+            .map_err(|e| e.to_string())
+    }
+
+    fn skeleton(&self, path: &str) -> Option<Skeleton> {
+        self.physis.borrow_mut().parsed(path).ok()
+    }
+
+    fn sheet_names(&self) -> Vec<String> {
+        self.physis
+            .borrow_mut()
+            .get_all_sheet_names()
+            .unwrap_or_default()
+    }
+
+    fn sheet_pages(&self, name: &str) -> Result<Vec<u32>, String> {
+        let exh = self.read_excel_sheet_header(name)?;
+        Ok(exh.pages.iter().map(|page| page.start_id).collect())
+    }
+
+    /// physis 只提供整表读取: 表头中只保留这一页的分页信息，读取时只解析这一页的 EXD
+    fn read_sheet_page(
+        &self,
+        name: &str,
+        start_id: u32,
+        language: Language,
+    ) -> Result<Vec<(u32, SheetRow)>, String> {
+        let mut exh = self.read_excel_sheet_header(name)?;
+        exh.pages.retain(|page| page.start_id == start_id);
+        if exh.pages.is_empty() {
+            return Err(format!("{} 表没有起始于 {} 的页", name, start_id));
+        }
+        let sheet = self
+            .physis
+            .borrow_mut()
+            .read_excel_sheet(&exh, name, language)
+            .map_err(|e| e.to_string())?;
+        let mut rows = Vec::new();
+        for page in &sheet.pages {
+            for (row_id, row) in page.into_iter().flatten_subrows() {
+                rows.push((
+                    row_id,
+                    SheetRow {
+                        columns: row.columns.clone(),
+                    },
+                ));
+            }
+        }
+        Ok(rows)
+    }
+
+    fn read_excel_sheet_header(&self, name: &str) -> Result<EXH, String> {
+        self.physis
+            .borrow_mut()
+            .read_excel_sheet_header(name)
+            .map_err(|e| e.to_string())
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use physis::mtrl::{ColorDyeTable, ColorTable};
-use tomestone_render::{MeshTextures, TextureData};
+use tomestone_render::{ColorTableTexture, MeshTextures, TextureData, MAX_COLOR_TABLE_ROWS};
 
 use super::{GameData, MeshData};
 
@@ -47,18 +47,43 @@ fn extract_diffuse_colors(color_table: &ColorTable) -> Vec<[f32; 3]> {
     }
 }
 
-pub fn bake_color_table_texture(
-    id_tex: &TextureData,
-    color_table: &ColorTable,
-    dyed_colors: Option<&Vec<[f32; 3]>>,
-) -> TextureData {
-    let row_count = match color_table {
+fn color_table_row_count(color_table: &ColorTable) -> usize {
+    match color_table {
         ColorTable::LegacyColorTable(_) => 16,
         ColorTable::DawntrailColorTable(_) => 32,
         ColorTable::OpaqueColorTable(_) => 0,
-    };
+    }
+}
 
+/// 颜色表每行最终的漫反射颜色: 优先染色结果，其次原色，都没有时为白色
+pub fn resolve_color_table_rows(
+    color_table: &ColorTable,
+    dyed_colors: Option<&Vec<[f32; 3]>>,
+) -> Vec<[f32; 3]> {
     let base_colors = extract_diffuse_colors(color_table);
+    (0..color_table_row_count(color_table))
+        .map(|row| {
+            dyed_colors
+                .and_then(|dyed| dyed.get(row))
+                .or_else(|| base_colors.get(row))
+                .copied()
+                .unwrap_or([1.0, 1.0, 1.0])
+        })
+        .collect()
+}
+
+/// 颜色表能否放进 shader 的颜色表 uniform，不能时染色只能走 CPU 烘焙
+fn fits_gpu_color_table(color_table: &ColorTable) -> bool {
+    (1..=MAX_COLOR_TABLE_ROWS).contains(&color_table_row_count(color_table))
+}
+
+pub fn bake_color_table_texture(
+    id_tex: &TextureData,
+    color_table: &ColorTable,
+    dyed_colors: Option<&Vec<[f32; 3]>>,
+) -> TextureData {
+    let row_count = color_table_row_count(color_table);
+    let row_colors = resolve_color_table_rows(color_table, dyed_colors);
 
     let pixel_count = (id_tex.width * id_tex.height) as usize;
     let mut rgba = Vec::with_capacity(pixel_count * 4);
@@ -74,19 +99,7 @@ pub fn bake_color_table_texture(
             0
         };
 
-        let color = if let Some(dyed) = dyed_colors {
-            if row_idx < dyed.len() {
-                dyed[row_idx]
-            } else if row_idx < base_colors.len() {
-                base_colors[row_idx]
-            } else {
-                [1.0, 1.0, 1.0]
-            }
-        } else if row_idx < base_colors.len() {
-            base_colors[row_idx]
-        } else {
-            [1.0, 1.0, 1.0]
-        };
+        let color = row_colors.get(row_idx).copied().unwrap_or([1.0, 1.0, 1.0]);
 
         rgba.push((linear_to_srgb(color[0]).clamp(0.0, 1.0) * 255.0) as u8);
         rgba.push((linear_to_srgb(color[1]).clamp(0.0, 1.0) * 255.0) as u8);
@@ -104,82 +117,97 @@ pub fn bake_color_table_texture(
 fn load_material_textures_from_candidates(
     game: &GameData,
     candidates: &[String],
+    diag: &mut MaterialDiagnostic,
 ) -> Option<(MeshTextures, CachedMaterial)> {
     for material_path in candidates {
         println!("    尝试 MTRL: {}", material_path);
+        diag.tried_paths.push(material_path.clone());
 
         let material = match game.parsed_mtrl(material_path) {
             Some(m) => m,
-            None => continue,
+            None => {
+                diag.failed_paths.push(material_path.clone());
+                continue;
+            }
         };
 
         let normal_tex = find_normal_path(&material.texture_paths).and_then(|p| {
             println!("    法线贴图: {}", p);
-            game.parsed_tex(&p)
+            diag.load_tex(game, &p)
         });
 
         let mask_tex = find_mask_path(&material.texture_paths).and_then(|p| {
             println!("    遮罩贴图: {}", p);
-            game.parsed_tex(&p)
+            diag.load_tex(game, &p)
         });
 
-        let tex_path = find_diffuse_path(&material.texture_paths);
-        match tex_path {
-            Some(p) => {
-                println!("    TEX 路径: {}", p);
-                if let Some(tex_data) = game.parsed_tex(&p) {
-                    println!("    纹理加载成功: {}x{}", tex_data.width, tex_data.height);
+        if let Some(p) = find_diffuse_path(&material.texture_paths) {
+            println!("    TEX 路径: {}", p);
+            if let Some(tex_data) = diag.load_tex(game, &p) {
+                println!("    纹理加载成功: {}x{}", tex_data.width, tex_data.height);
+                let cached = CachedMaterial {
+                    color_table: material.color_table,
+                    color_dye_table: material.color_dye_table,
+                    id_texture: None,
+                    uses_color_table: false,
+                    gpu_color_table: false,
+                };
+                let mesh_tex = MeshTextures {
+                    diffuse: tex_data,
+                    normal: normal_tex,
+                    mask: mask_tex,
+                    emissive: None,
+                    color_table: None,
+                };
+                diag.source = TextureSource::Diffuse;
+                return Some((mesh_tex, cached));
+            }
+            println!("    TEX 解析失败，尝试 ColorTable 烘焙");
+        }
+
+        // diffuse 缺失或解析失败时，只要有 _id.tex 就用 ColorTable 烘焙
+        if let Some(color_table) = &material.color_table {
+            if let Some(id_path) = find_id_texture_path(&material.texture_paths) {
+                println!("    ColorTable 烘焙: {}", id_path);
+                if let Some(id_tex) = diag.load_tex(game, &id_path) {
+                    let baked = bake_color_table_texture(&id_tex, color_table, None);
+                    let emissive = bake_emissive_texture(&id_tex, color_table);
+                    let emissive_opt = if emissive.width > 1 {
+                        Some(emissive)
+                    } else {
+                        None
+                    };
+                    println!("    烘焙成功: {}x{}", baked.width, baked.height);
+                    // 行数在 uniform 容量内的颜色表交给 shader 查表，染色时只更新颜色表行
+                    let gpu_color_table =
+                        fits_gpu_color_table(color_table).then(|| ColorTableTexture {
+                            id_texture: id_tex.clone(),
+                            row_count: color_table_row_count(color_table) as u32,
+                            rows: resolve_color_table_rows(color_table, None),
+                        });
                     let cached = CachedMaterial {
                         color_table: material.color_table,
                         color_dye_table: material.color_dye_table,
-                        id_texture: None,
-                        uses_color_table: false,
+                        id_texture: Some(id_tex),
+                        uses_color_table: true,
+                        gpu_color_table: gpu_color_table.is_some(),
                     };
                     let mesh_tex = MeshTextures {
-                        diffuse: tex_data,
+                        diffuse: baked,
                         normal: normal_tex,
                         mask: mask_tex,
-                        emissive: None,
+                        emissive: emissive_opt,
+                        color_table: gpu_color_table,
                     };
+                    diag.source = TextureSource::ColorTableBake;
                     return Some((mesh_tex, cached));
                 }
-                println!("    TEX 解析失败");
-            }
-            None => {
-                if let Some(color_table) = &material.color_table {
-                    if let Some(id_path) = find_id_texture_path(&material.texture_paths) {
-                        println!("    ColorTable 烘焙: {}", id_path);
-                        if let Some(id_tex) = game.parsed_tex(&id_path) {
-                            let baked = bake_color_table_texture(&id_tex, color_table, None);
-                            let emissive = bake_emissive_texture(&id_tex, color_table);
-                            let emissive_opt = if emissive.width > 1 {
-                                Some(emissive)
-                            } else {
-                                None
-                            };
-                            println!("    烘焙成功: {}x{}", baked.width, baked.height);
-                            let cached = CachedMaterial {
-                                color_table: material.color_table,
-                                color_dye_table: material.color_dye_table,
-                                id_texture: Some(id_tex),
-                                uses_color_table: true,
-                            };
-                            let mesh_tex = MeshTextures {
-                                diffuse: baked,
-                                normal: normal_tex,
-                                mask: mask_tex,
-                                emissive: emissive_opt,
-                            };
-                            return Some((mesh_tex, cached));
-                        }
-                        println!("    _id.tex 解析失败");
-                    } else {
-                        println!("    有 ColorTable 但无 _id.tex");
-                    }
-                } else {
-                    println!("    MTRL 无 diffuse 纹理，也无 ColorTable");
-                }
+                println!("    _id.tex 解析失败");
+            } else {
+                println!("    有 ColorTable 但无 _id.tex");
             }
+        } else {
+            println!("    MTRL 无可用 diffuse 纹理，也无 ColorTable");
         }
     }
     None
@@ -238,11 +266,7 @@ fn extract_emissive_colors(color_table: &ColorTable) -> Vec<[f32; 3]> {
 }
 
 fn bake_emissive_texture(id_tex: &TextureData, color_table: &ColorTable) -> TextureData {
-    let row_count = match color_table {
-        ColorTable::LegacyColorTable(_) => 16,
-        ColorTable::DawntrailColorTable(_) => 32,
-        ColorTable::OpaqueColorTable(_) => 0,
-    };
+    let row_count = color_table_row_count(color_table);
 
     let emissive_colors = extract_emissive_colors(color_table);
 
@@ -297,16 +321,107 @@ fn fallback_white() -> TextureData {
     }
 }
 
+/// 品红/黑棋盘格，用于让缺失贴图在视口中一目了然
+fn fallback_checkerboard() -> TextureData {
+    const SIZE: u32 = 64;
+    const CELL: u32 = 8;
+    let mut rgba = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            if (x / CELL + y / CELL) % 2 == 0 {
+                rgba.extend_from_slice(&[255, 0, 255, 255]);
+            } else {
+                rgba.extend_from_slice(&[0, 0, 0, 255]);
+            }
+        }
+    }
+    TextureData {
+        rgba: rgba.into(),
+        width: SIZE,
+        height: SIZE,
+    }
+}
+
+fn fallback_missing(game: &GameData) -> TextureData {
+    if game.checkerboard_missing_textures() {
+        fallback_checkerboard()
+    } else {
+        fallback_white()
+    }
+}
+
 pub struct CachedMaterial {
     pub color_table: Option<ColorTable>,
     pub color_dye_table: Option<ColorDyeTable>,
     pub id_texture: Option<TextureData>,
     pub uses_color_table: bool,
+    /// 颜色表由 shader 应用 (预览中染色只需更新颜色表行)；
+    /// 为 false 的 ColorTable 材质仍需 CPU 烘焙 diffuse
+    pub gpu_color_table: bool,
+}
+
+/// 材质最终使用的 diffuse 来源
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureSource {
+    Diffuse,
+    /// diffuse 缺失，由 ColorTable + _id.tex 烘焙
+    ColorTableBake,
+    /// 全部失败，使用回退贴图
+    Missing,
+}
+
+/// 单个材质的贴图加载诊断
+#[derive(Clone, Debug)]
+pub struct MaterialDiagnostic {
+    pub material_index: u16,
+    pub material_name: String,
+    /// 依次尝试过的 MTRL / TEX 路径
+    pub tried_paths: Vec<String>,
+    /// 其中加载失败的路径
+    pub failed_paths: Vec<String>,
+    pub source: TextureSource,
+}
+
+impl MaterialDiagnostic {
+    fn new(material_index: u16, material_name: &str) -> Self {
+        Self {
+            material_index,
+            material_name: material_name.to_string(),
+            tried_paths: Vec::new(),
+            failed_paths: Vec::new(),
+            source: TextureSource::Missing,
+        }
+    }
+
+    fn load_tex(&mut self, game: &GameData, path: &str) -> Option<TextureData> {
+        self.tried_paths.push(path.to_string());
+        let tex = game.parsed_tex(path);
+        if tex.is_none() {
+            self.failed_paths.push(path.to_string());
+        }
+        tex
+    }
 }
 
 pub struct MaterialLoadResult {
     pub mesh_textures: Vec<MeshTextures>,
     pub materials: HashMap<u16, CachedMaterial>,
+    /// 每个材质一条，按材质索引排序
+    pub diagnostics: Vec<MaterialDiagnostic>,
+}
+
+impl MaterialLoadResult {
+    /// 贴图完全缺失的材质数
+    pub fn missing_count(&self) -> usize {
+        count_missing(&self.diagnostics)
+    }
+}
+
+pub fn count_missing(diagnostics: &[MaterialDiagnostic]) -> usize {
+    diagnostics
+        .iter()
+        .filter(|d| d.source == TextureSource::Missing)
+        .count()
 }
 
 pub fn load_mesh_textures(
@@ -329,6 +444,38 @@ pub fn load_mesh_textures(
     })
 }
 
+/// 探测装备材质的染色通道数 (0/1/2)，只解析 MDL 与 MTRL，不加载纹理
+pub fn probe_dye_channels(
+    game: &GameData,
+    mdl_paths: &[String],
+    set_id: u16,
+    variant_id: u16,
+) -> u8 {
+    let Ok(result) = super::load_mdl_with_fallback(game, mdl_paths) else {
+        return 0;
+    };
+    let mut channels = 0u8;
+    for short_name in &result.material_names {
+        let mtrl = [variant_id, 1]
+            .iter()
+            .find_map(|&v| game.parsed_mtrl(&resolve_material_path(short_name, set_id, v)));
+        match mtrl.and_then(|m| m.color_dye_table) {
+            Some(ColorDyeTable::LegacyColorDyeTable(dt)) => {
+                if dt.rows.iter().any(|row| row.diffuse) {
+                    channels = channels.max(1);
+                }
+            }
+            Some(ColorDyeTable::DawntrailColorDyeTable(dt)) => {
+                for row in dt.rows.iter().filter(|row| row.diffuse) {
+                    channels = channels.max(if row.channel > 0 { 2 } else { 1 });
+                }
+            }
+            _ => {}
+        }
+    }
+    channels
+}
+
 /// 加载房屋外装模型的纹理
 /// 材质路径格式: bgcommon/hou/outdoor/general/{id:04}/material/...
 pub fn load_housing_mesh_textures(
@@ -372,15 +519,25 @@ fn load_mesh_textures_with_resolver(
 ) -> MaterialLoadResult {
     let mut tex_cache: HashMap<u16, MeshTextures> = HashMap::new();
     let mut mat_cache: HashMap<u16, CachedMaterial> = HashMap::new();
+    let mut diagnostics: Vec<MaterialDiagnostic> = Vec::new();
 
     let mut mesh_textures = Vec::with_capacity(meshes.len());
     for mesh in meshes {
         let mat_idx = mesh.material_index;
         if !tex_cache.contains_key(&mat_idx) {
-            let (mtex, cached_mat) = if let Some(name) = material_names.get(mat_idx as usize) {
+            let missing = || MeshTextures {
+                diffuse: fallback_missing(game),
+                normal: None,
+                mask: None,
+                emissive: None,
+                color_table: None,
+            };
+            let (mtex, cached_mat, diag) = if let Some(name) = material_names.get(mat_idx as usize)
+            {
                 println!("  材质 [{}]: {}", mat_idx, name);
+                let mut diag = MaterialDiagnostic::new(mat_idx, name);
                 let candidates = resolve_fn(name);
-                match load_material_textures_from_candidates(game, &candidates) {
+                match load_material_textures_from_candidates(game, &candidates, &mut diag) {
                     Some((mt, cm)) => {
                         println!(
                             "    纹理加载成功: {}x{} normal={} mask={} emissive={}",
@@ -390,47 +547,34 @@ fn load_mesh_textures_with_resolver(
                             mt.mask.is_some(),
                             mt.emissive.is_some()
                         );
-                        (mt, Some(cm))
+                        (mt, Some(cm), diag)
                     }
                     None => {
-                        println!("    纹理加载失败，使用白色回退");
-                        (
-                            MeshTextures {
-                                diffuse: fallback_white(),
-                                normal: None,
-                                mask: None,
-                                emissive: None,
-                            },
-                            None,
-                        )
+                        println!("    纹理加载失败，使用回退贴图");
+                        (missing(), None, diag)
                     }
                 }
             } else {
                 println!(
-                    "  材质索引 {} 超出范围 (共 {} 个材质名)，使用白色回退",
+                    "  材质索引 {} 超出范围 (共 {} 个材质名)，使用回退贴图",
                     mat_idx,
                     material_names.len()
                 );
-                (
-                    MeshTextures {
-                        diffuse: fallback_white(),
-                        normal: None,
-                        mask: None,
-                        emissive: None,
-                    },
-                    None,
-                )
+                (missing(), None, MaterialDiagnostic::new(mat_idx, "?"))
             };
             tex_cache.insert(mat_idx, mtex);
             if let Some(cm) = cached_mat {
                 mat_cache.insert(mat_idx, cm);
             }
+            diagnostics.push(diag);
         }
         let cached = tex_cache.get(&mat_idx).unwrap();
         mesh_textures.push(cached.clone());
     }
+    diagnostics.sort_by_key(|d| d.material_index);
     MaterialLoadResult {
         mesh_textures,
         materials: mat_cache,
+        diagnostics,
     }
 }
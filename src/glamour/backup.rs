@@ -0,0 +1,309 @@
+//! 幻化组合备份文件夹: 每个组合一个 JSON 文件 (与保存/导出的格式相同)，
+//! 供网盘等工具同步到其它电脑
+//!
+//! 文件名为 `<id>_<名称>.json`，改名后旧文件会被删除。所有文件读写在后台线程进行，
+//! 结果与错误通过 [`GlamourBackup::events`] 取回。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::UNIX_EPOCH;
+
+use super::{glamour_set_json, GlamourSet};
+use crate::export_name::sanitize_file_name;
+
+/// 备份文件夹中比本地新 (或本地不存在) 的组合
+#[derive(Debug, Clone)]
+pub struct BackupCandidate {
+    pub set: GlamourSet,
+    /// 备份文件的修改时间 (毫秒时间戳): 组合的 modified_at，旧数据为 0 时取文件时间
+    pub backup_modified: u64,
+    /// 本地同 id 组合的修改时间，本地不存在时为 None
+    pub local_modified: Option<u64>,
+}
+
+pub enum BackupEvent {
+    Error(String),
+    /// 同步完成: 写入备份文件夹的组合数，以及可导入的组合
+    Synced {
+        exported: usize,
+        candidates: Vec<BackupCandidate>,
+    },
+}
+
+enum BackupJob {
+    Save {
+        dir: PathBuf,
+        id: String,
+        name: String,
+        json: String,
+    },
+    Delete {
+        dir: PathBuf,
+        id: String,
+    },
+    Sync {
+        dir: PathBuf,
+        local: Vec<GlamourSet>,
+    },
+}
+
+/// 备份文件名 (不含目录)
+fn backup_file_name(id: &str, name: &str) -> String {
+    let name = sanitize_file_name(name);
+    if name.is_empty() {
+        format!("{}.json", id)
+    } else {
+        format!("{}_{}.json", id, name)
+    }
+}
+
+/// 文件夹中属于组合 `id` 的备份文件 (组合 id 为十六进制，不含下划线)
+fn files_of_set(dir: &Path, id: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}_", id);
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .filter(|p| {
+            p.file_stem()
+                .and_then(|s| s.to_str())
+                .is_some_and(|stem| stem == id || stem.starts_with(&prefix))
+        })
+        .collect()
+}
+
+/// 写入一个组合的备份文件，并删除改名前的旧文件。先写临时文件再重命名，
+/// 同步工具不会读到写了一半的文件
+fn write_backup(dir: &Path, id: &str, name: &str, json: &str) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("创建备份文件夹失败: {}", e))?;
+    let path = dir.join(backup_file_name(id, name));
+    let tmp = path.with_extension("json.tmp");
+    if let Err(e) = fs::write(&tmp, json).and_then(|()| fs::rename(&tmp, &path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(format!("写入 {} 失败: {}", path.display(), e));
+    }
+    for stale in files_of_set(dir, id).into_iter().filter(|p| *p != path) {
+        fs::remove_file(&stale).map_err(|e| format!("删除 {} 失败: {}", stale.display(), e))?;
+    }
+    Ok(())
+}
+
+fn delete_backup(dir: &Path, id: &str) -> Result<(), String> {
+    for path in files_of_set(dir, id) {
+        fs::remove_file(&path).map_err(|e| format!("删除 {} 失败: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+fn file_modified_millis(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 读取备份文件夹中的全部组合: (组合, 修改时间)。同 id 有多个文件时取较新的
+fn read_backups(dir: &Path) -> Result<Vec<(GlamourSet, u64)>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("读取备份文件夹失败: {}", e))?;
+    let mut sets: Vec<(GlamourSet, u64)> = Vec::new();
+    for path in entries.flatten().map(|e| e.path()) {
+        if !path.extension().is_some_and(|ext| ext == "json") {
+            continue;
+        }
+        let Some(set) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<GlamourSet>(&s).ok())
+        else {
+            eprintln!("跳过无法解析的备份文件: {}", path.display());
+            continue;
+        };
+        let modified = if set.modified_at > 0 {
+            set.modified_at
+        } else {
+            file_modified_millis(&path)
+        };
+        match sets.iter_mut().find(|(s, _)| s.id == set.id) {
+            Some(existing) if existing.1 < modified => *existing = (set, modified),
+            Some(_) => {}
+            None => sets.push((set, modified)),
+        }
+    }
+    Ok(sets)
+}
+
+/// 双向比较: 本地较新或备份中不存在的组合写入文件夹，备份较新或本地不存在的作为导入候选
+fn sync_backups(dir: &Path, local: &[GlamourSet]) -> Result<BackupEvent, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("创建备份文件夹失败: {}", e))?;
+    let backups = read_backups(dir)?;
+    let mut exported = 0;
+    for set in local {
+        let outdated = backups
+            .iter()
+            .find(|(s, _)| s.id == set.id)
+            .is_none_or(|&(_, modified)| set.modified_at > modified);
+        if outdated {
+            write_backup(dir, &set.id, &set.name, &glamour_set_json(set)?)?;
+            exported += 1;
+        }
+    }
+    let candidates = backups
+        .into_iter()
+        .filter_map(|(set, backup_modified)| {
+            let local_modified = local.iter().find(|s| s.id == set.id).map(|s| s.modified_at);
+            local_modified
+                .is_none_or(|local| backup_modified > local)
+                .then_some(BackupCandidate {
+                    set,
+                    backup_modified,
+                    local_modified,
+                })
+        })
+        .collect();
+    Ok(BackupEvent::Synced {
+        exported,
+        candidates,
+    })
+}
+
+fn run_job(job: BackupJob) -> Option<BackupEvent> {
+    let result = match job {
+        BackupJob::Save {
+            dir,
+            id,
+            name,
+            json,
+        } => write_backup(&dir, &id, &name, &json).map(|()| None),
+        BackupJob::Delete { dir, id } => delete_backup(&dir, &id).map(|()| None),
+        BackupJob::Sync { dir, local } => sync_backups(&dir, &local).map(Some),
+    };
+    result.unwrap_or_else(|e| Some(BackupEvent::Error(e)))
+}
+
+/// 备份文件夹的后台写入线程，按提交顺序执行。未设置文件夹时各操作不做任何事
+pub struct GlamourBackup {
+    /// 备份文件夹，App 每帧从配置同步
+    dir: Option<PathBuf>,
+    jobs: Option<mpsc::Sender<BackupJob>>,
+    events: mpsc::Receiver<BackupEvent>,
+    /// 已提交但尚未完成的任务数
+    in_flight: Arc<AtomicUsize>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl GlamourBackup {
+    pub fn new() -> Self {
+        let (job_tx, job_rx) = mpsc::channel::<BackupJob>();
+        let (event_tx, event_rx) = mpsc::channel();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let worker_in_flight = Arc::clone(&in_flight);
+        let handle = std::thread::Builder::new()
+            .name("glamour-backup".to_string())
+            .spawn(move || {
+                for job in job_rx {
+                    // 先送出结果再减少计数，界面看到空闲时结果已可取回
+                    let sent = run_job(job).is_none_or(|event| event_tx.send(event).is_ok());
+                    worker_in_flight.fetch_sub(1, Ordering::AcqRel);
+                    if !sent {
+                        break;
+                    }
+                }
+            })
+            .ok();
+        Self {
+            dir: None,
+            jobs: Some(job_tx),
+            events: event_rx,
+            in_flight,
+            handle,
+        }
+    }
+
+    pub fn set_dir(&mut self, dir: Option<&Path>) {
+        if self.dir.as_deref() != dir {
+            self.dir = dir.map(Path::to_path_buf);
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    fn send(&self, job: BackupJob) {
+        if let Some(jobs) = &self.jobs {
+            self.in_flight.fetch_add(1, Ordering::AcqRel);
+            if jobs.send(job).is_err() {
+                self.in_flight.fetch_sub(1, Ordering::AcqRel);
+            }
+        }
+    }
+
+    /// 写入 (或更新) 一个组合的备份文件
+    pub fn save(&self, set: &GlamourSet) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        match glamour_set_json(set) {
+            Ok(json) => self.send(BackupJob::Save {
+                dir: dir.clone(),
+                id: set.id.clone(),
+                name: set.name.clone(),
+                json,
+            }),
+            Err(e) => eprintln!("备份序列化失败: {}", e),
+        }
+    }
+
+    /// 删除一个组合的备份文件
+    pub fn delete(&self, id: &str) {
+        if let Some(dir) = &self.dir {
+            self.send(BackupJob::Delete {
+                dir: dir.clone(),
+                id: id.to_string(),
+            });
+        }
+    }
+
+    /// 与备份文件夹双向比较，完成后产生 [`BackupEvent::Synced`]
+    pub fn sync(&self, local: &[GlamourSet]) {
+        if let Some(dir) = &self.dir {
+            self.send(BackupJob::Sync {
+                dir: dir.clone(),
+                local: local.to_vec(),
+            });
+        }
+    }
+
+    /// 还有任务在后台执行
+    pub fn is_busy(&self) -> bool {
+        self.in_flight.load(Ordering::Acquire) > 0
+    }
+
+    /// 已完成的同步结果与写入错误 (不阻塞)
+    pub fn events(&self) -> impl Iterator<Item = BackupEvent> + '_ {
+        self.events.try_iter()
+    }
+}
+
+impl Default for GlamourBackup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for GlamourBackup {
+    /// 关闭时等待已提交的写入完成
+    fn drop(&mut self) {
+        self.jobs = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
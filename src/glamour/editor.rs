@@ -1,21 +1,36 @@
 use std::collections::{HashMap, HashSet};
 use std::ops::Range;
+use std::path::PathBuf;
 
 use eframe::egui;
 use physis::stm::StainingTemplate;
+use tomestone_render::SceneSettings;
 
-use super::GlamourSet;
+use super::{GlamourRecovery, GlamourSet, GlamourView};
 use crate::domain::{
-    EquipSlot, EquipmentSet, GameItem, ACCESSORY_SLOTS, ALL_SLOTS, GEAR_SLOTS, RACE_CODES,
+    race_code_label, EquipSlot, EquipmentSet, GameItem, ACCESSORY_SLOTS, ALL_SLOTS, GEAR_SLOTS,
+    RACE_CODES,
 };
-use crate::dye::{apply_dye, has_dual_dye};
+use crate::dye::{
+    bake_dyed_diffuse, dye_region_update, dye_update, has_dual_dye, submit_dye_updates, DyeUpdate,
+};
+use crate::export_name::{format_export_name, ExportNameContext};
 use crate::game::{
-    apply_skinning, bake_color_table_texture, compute_bounding_box, load_mdl, load_mesh_textures,
-    CachedMaterial, GameData, MeshData, SkeletonCache,
+    apply_skinning, compute_bounding_box, load_mdl, load_mdl_with_fallback, load_mesh_textures,
+    place_unskinned_at_bone, CachedMaterial, GameData, MaterialDiagnostic, MeshData, ReadStats,
+    SkeletonCache, SkinningReport,
 };
-use crate::ui::components::dye_palette::show_dye_palette;
+use crate::gltf::{ExportPrimitive, GlbBuilder};
+use crate::ui::components::dye_palette::{show_dye_palette, show_dye_region_toggle};
 use crate::ui::components::equipment_list::{EquipmentListState, HighlightConfig};
-use crate::ui::components::viewport::ViewportState;
+use crate::ui::components::item_detail::{self, show_restriction_badge};
+use crate::ui::components::item_list::{get_or_load_icon, IconCache};
+use crate::ui::components::material_diagnostics::{
+    show_material_diagnostics, show_skinning_report,
+};
+use crate::ui::components::toast::Toasts;
+use crate::ui::components::viewport::{self, ViewportState};
+use crate::viewport_input::ViewportInputConfig;
 
 pub struct AppContext<'a> {
     pub items: &'a [GameItem],
@@ -26,14 +41,23 @@ pub struct AppContext<'a> {
     pub equipment_indices: &'a [usize],
     pub equipment_sets: &'a [EquipmentSet],
     pub set_id_to_set_idx: &'a HashMap<u16, usize>,
-    pub icon_cache: &'a mut HashMap<u32, Option<egui::TextureHandle>>,
+    pub icon_cache: &'a mut IconCache,
+    pub probed_dye_counts: &'a HashMap<u32, u8>,
+    pub toasts: &'a mut Toasts,
+    pub default_preview_stain: u32,
+    /// 导出文件名模板
+    pub export_name_template: &'a str,
+    pub glamour_sets: &'a [GlamourSet],
+    pub glamour_item_usage: &'a HashMap<u32, Vec<usize>>,
+    /// 正在编辑的组合在 glamour_sets 中的索引 (不在 "已用于幻化" 中列出)
+    pub editing_idx: Option<usize>,
 }
 
 struct SlotState {
     loaded_item_id: Option<u32>,
+    /// 该槽位在 GlamourEditor::meshes 中的范围
     mesh_range: Range<usize>,
     cached_materials: HashMap<u16, CachedMaterial>,
-    cached_meshes: Vec<MeshData>,
     is_dual_dye: bool,
 }
 
@@ -43,16 +67,64 @@ impl Default for SlotState {
             loaded_item_id: None,
             mesh_range: 0..0,
             cached_materials: HashMap::new(),
-            cached_meshes: Vec::new(),
             is_dual_dye: false,
         }
     }
 }
 
+/// 整套导出任务: 每帧导出一个槽位，避免长时间阻塞界面
+struct OutfitExportJob {
+    path: PathBuf,
+    builder: GlbBuilder,
+    /// 尚未导出的槽位 (空槽位不在其中)
+    pending: Vec<EquipSlot>,
+    total: usize,
+    /// 写入 glTF extras 的清单
+    manifest: serde_json::Value,
+}
+
+impl OutfitExportJob {
+    fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.total - self.pending.len()) as f32 / self.total as f32
+        }
+    }
+}
+
+/// 合并预览的种族选择结果
+#[derive(Clone, Default)]
+struct RacePlan {
+    /// 没有该种族原生模型、从其他种族模型蒙皮适配的槽位
+    retargeted: Vec<EquipSlot>,
+    /// 该种族无法适配的替换身体服装: 只单独预览该槽位
+    isolated: Option<EquipSlot>,
+}
+
+/// 分帧重建合并预览: 每帧加载一个槽位到暂存列表，全部完成后一次性替换渲染器网格
+struct MeshRebuildJob {
+    unified_race: &'static str,
+    plan: RacePlan,
+    /// 尚未加载的已装备槽位 (空槽位没有网格，不占用帧)
+    pending: Vec<EquipSlot>,
+    meshes: Vec<MeshData>,
+    textures: Vec<tomestone_render::MeshTextures>,
+    /// 完成时替换 GlamourEditor::slot_states (mesh_range 对应新的网格列表)
+    slot_states: HashMap<EquipSlot, SlotState>,
+    diagnostics: Vec<MaterialDiagnostic>,
+    /// 各槽位跨种族蒙皮的汇总
+    skinning: SkinningReport,
+    /// 开始重建时的文件读取统计，完成时输出本次重建的读取次数
+    read_stats: ReadStats,
+}
+
 pub enum GlamourEditorAction {
     None,
     Save,
     Close,
+    /// 打开另一个组合 (glamour_sets 索引)
+    OpenSet(usize),
 }
 
 pub struct GlamourEditor {
@@ -64,30 +136,65 @@ pub struct GlamourEditor {
     selected_stain_ids: HashMap<EquipSlot, [u32; 2]>,
     active_dye_channel: usize,
     selected_shade: u8,
+    /// 两个预览视口以伪彩色显示染色通道区域 (代替染色结果)
+    show_dye_regions: bool,
 
-    viewport: ViewportState,
+    /// 合并预览视口，无 3D 模式时为 None
+    viewport: Option<ViewportState>,
+    /// 首次构建合并网格后应用组合保存的视角 (代替自动对焦)
+    apply_saved_view: bool,
     slot_states: HashMap<EquipSlot, SlotState>,
+    /// 合并预览已上传的网格 (重建完成时从任务移入，染色与导出按 mesh_range 取用)
+    meshes: Vec<MeshData>,
+    /// 加载时的原始贴图，导出时用于不经 ColorTable 烘焙的材质
+    mesh_textures: Vec<tomestone_render::MeshTextures>,
     needs_mesh_rebuild: bool,
+    /// 进行中的分帧重建
+    mesh_rebuild: Option<MeshRebuildJob>,
     needs_rebake: bool,
     pub dirty: bool,
+    /// 合并预览中各材质的贴图加载诊断
+    texture_diagnostics: Vec<MaterialDiagnostic>,
+    /// 合并预览中跨种族蒙皮找不到的骨骼
+    skinning_report: SkinningReport,
 
     skeleton_cache: SkeletonCache,
+    /// 合并预览当前使用的种族代码 (用于装备限制冲突提示)
+    preview_race: &'static str,
+    /// 当前合并预览的蒙皮适配/单独预览情况
+    race_plan: RacePlan,
+
+    /// 对比快照 A 截取时各槽位的 (物品 ID, 染料)，仅在本次编辑中保留
+    snapshot_slots: Option<HashMap<EquipSlot, (u32, [u32; 2])>>,
+    /// 正在显示快照 A: 期间推迟合并预览的重建与染色，避免修改落到快照网格上
+    comparing: bool,
 
     // 右侧详情面板的单件预览视口
-    detail_viewport: ViewportState,
+    detail_viewport: Option<ViewportState>,
     detail_loaded_item_id: Option<u32>,
+    /// 单件预览的物品使用角色身体模型，视口显示说明而不是空白
+    detail_body_model: bool,
     detail_cached_materials: HashMap<u16, CachedMaterial>,
-    detail_cached_meshes: Vec<MeshData>,
+    /// 单件预览各网格的材质下标
+    detail_mesh_materials: Vec<u16>,
     detail_needs_rebuild: bool,
     detail_needs_rebake: bool,
 
+    /// 进行中的整套 glTF 导出
+    export_job: Option<OutfitExportJob>,
+
     // 预览状态: 点击左侧列表时设置，尚未装备
     preview_item_id: Option<u32>,
     preview_stain_ids: [u32; 2],
+    /// 预览染料来自默认预览染料设置 (用户尚未手动选择)
+    preview_stains_from_default: bool,
+    /// 设置中的默认预览染料 (0 = 无)
+    default_preview_stain: u32,
 }
 
 impl GlamourEditor {
-    pub fn new(glamour_set: GlamourSet, render_state: egui_wgpu::RenderState) -> Self {
+    /// `render_state` 为 None (无 3D 模式) 时两个预览视口显示占位，其余编辑功能不受影响
+    pub fn new(glamour_set: GlamourSet, render_state: Option<egui_wgpu::RenderState>) -> Self {
         let mut selected_stain_ids = HashMap::new();
         for slot in &ALL_SLOTS {
             let stain_ids = glamour_set
@@ -97,7 +204,8 @@ impl GlamourEditor {
             selected_stain_ids.insert(*slot, stain_ids);
         }
 
-        let detail_viewport = ViewportState::new(render_state.clone());
+        let detail_viewport = ViewportState::create(render_state.as_ref());
+        let apply_saved_view = glamour_set.view.is_some();
 
         Self {
             glamour_set,
@@ -106,31 +214,330 @@ impl GlamourEditor {
             selected_stain_ids,
             active_dye_channel: 0,
             selected_shade: 2,
-            viewport: ViewportState::new(render_state),
+            show_dye_regions: false,
+            viewport: ViewportState::create(render_state.as_ref()),
+            apply_saved_view,
             slot_states: HashMap::new(),
+            meshes: Vec::new(),
+            mesh_textures: Vec::new(),
             needs_mesh_rebuild: true,
+            mesh_rebuild: None,
+            texture_diagnostics: Vec::new(),
+            skinning_report: SkinningReport::default(),
             needs_rebake: false,
             dirty: false,
             skeleton_cache: SkeletonCache::new(),
+            preview_race: RACE_CODES[0],
+            race_plan: RacePlan::default(),
+            snapshot_slots: None,
+            comparing: false,
             detail_viewport,
             detail_loaded_item_id: None,
+            detail_body_model: false,
             detail_cached_materials: HashMap::new(),
-            detail_cached_meshes: Vec::new(),
+            detail_mesh_materials: Vec::new(),
             detail_needs_rebuild: false,
             detail_needs_rebake: false,
+            export_job: None,
             preview_item_id: None,
             preview_stain_ids: [0, 0],
+            preview_stains_from_default: false,
+            default_preview_stain: 0,
         }
     }
 
-    fn rebuild_merged_meshes(
+    /// 离开幻化页面时释放两个预览视口的 GPU 资源，返回时自动重新上传
+    pub fn on_page_hidden(&mut self) {
+        self.comparing = false;
+        for vp in [&mut self.viewport, &mut self.detail_viewport]
+            .into_iter()
+            .flatten()
+        {
+            vp.release_gpu();
+        }
+    }
+
+    /// 估算的 GPU 内存占用 (字节)
+    pub fn gpu_memory_bytes(&self) -> u64 {
+        [&self.viewport, &self.detail_viewport]
+            .into_iter()
+            .flatten()
+            .map(|vp| vp.gpu_memory_bytes())
+            .sum()
+    }
+
+    /// 自动保存的恢复内容: 编辑中的组合与预览状态
+    pub fn recovery_snapshot(&self) -> GlamourRecovery {
+        GlamourRecovery::new(
+            self.glamour_set.clone(),
+            self.active_slot,
+            self.preview_item_id,
+            self.preview_stain_ids,
+        )
+    }
+
+    /// 从自动保存恢复的编辑器，恢复的内容尚未保存，因此标记为已修改
+    pub fn from_recovery(
+        recovery: GlamourRecovery,
+        render_state: Option<egui_wgpu::RenderState>,
+    ) -> Self {
+        let active_slot = recovery.active_slot();
+        let mut editor = Self::new(recovery.set, render_state);
+        if let Some(slot) = active_slot {
+            editor.active_slot = slot;
+        }
+        editor.preview_item_id = recovery.preview_item_id;
+        editor.preview_stain_ids = recovery.preview_stain_ids;
+        editor.dirty = true;
+        editor
+    }
+
+    /// 标题栏的视角/光照控件: 保存当前视角 (右键清除已保存视角) 与光照调节
+    fn show_view_controls(&mut self, ui: &mut egui::Ui) {
+        let has_view = self.glamour_set.view.is_some();
+        let label = if has_view {
+            "保存当前视角 ✔"
+        } else {
+            "保存当前视角"
+        };
+        let response = ui
+            .button(label)
+            .on_hover_text("记录当前相机角度与光照，下次打开该组合时恢复 (右键可清除)");
+        if response.clicked() {
+            if let Some(vp) = &self.viewport {
+                self.glamour_set.view = Some(GlamourView::capture(&vp.camera, &vp.scene));
+                self.dirty = true;
+            }
+        }
+        response.context_menu(|ui| {
+            if ui
+                .add_enabled(has_view, egui::Button::new("清除已保存视角"))
+                .clicked()
+            {
+                self.glamour_set.view = None;
+                self.dirty = true;
+                if let Some(vp) = &mut self.viewport {
+                    if let Some(bbox) = &vp.last_bbox {
+                        vp.camera.focus_on(bbox);
+                    }
+                    let scene = &vp.scene;
+                    vp.scene = SceneSettings {
+                        background_color: scene.background_color,
+                        ignore_vertex_color_mask: scene.ignore_vertex_color_mask,
+                        ignore_ao: scene.ignore_ao,
+                        ..SceneSettings::default()
+                    };
+                    vp.mark_dirty();
+                }
+                ui.close();
+            }
+        });
+
+        ui.menu_button("光照", |ui| {
+            let Some(vp) = &mut self.viewport else {
+                return;
+            };
+            let scene = &mut vp.scene;
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                ui.label("主光:");
+                for c in &mut scene.light_color {
+                    changed |= ui
+                        .add(egui::DragValue::new(c).speed(0.01).range(0.0..=3.0))
+                        .changed();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("天空环境光:");
+                changed |= ui.color_edit_button_rgb(&mut scene.ambient_sky).changed();
+                ui.label("地面环境光:");
+                changed |= ui.color_edit_button_rgb(&mut scene.ambient_ground).changed();
+            });
+            changed |= ui
+                .add(egui::Slider::new(&mut scene.fresnel_intensity, 0.0..=1.0).text("边缘光"))
+                .changed();
+            if ui.button("恢复默认").clicked() {
+                let defaults = SceneSettings::default();
+                scene.light_color = defaults.light_color;
+                scene.ambient_sky = defaults.ambient_sky;
+                scene.ambient_ground = defaults.ambient_ground;
+                scene.fresnel_intensity = defaults.fresnel_intensity;
+                changed = true;
+            }
+            if changed {
+                vp.mark_dirty();
+            }
+        });
+    }
+
+    /// 当前各已装备槽位的 (物品 ID, 染料)
+    fn current_slot_entries(&self) -> HashMap<EquipSlot, (u32, [u32; 2])> {
+        ALL_SLOTS
+            .iter()
+            .filter_map(|slot| {
+                let gs = self.glamour_set.get_slot(*slot)?;
+                Some((*slot, (gs.item_id, gs.stain_ids)))
+            })
+            .collect()
+    }
+
+    /// 标题栏的对比快照控件: 截取快照 A (右键清除)，按住 "对比" 或 Tab 键临时显示快照 A
+    fn show_snapshot_controls(&mut self, ui: &mut egui::Ui) {
+        // 重建或染色尚未完成时渲染器中不是最终结果
+        let busy = self.needs_mesh_rebuild || self.mesh_rebuild.is_some() || self.needs_rebake;
+        let response = ui
+            .add_enabled(!busy, egui::Button::new("快照"))
+            .on_hover_text(
+                "把当前装备与染色记录为快照 A，按住 \"对比\" 或 Tab 键查看 (右键可清除)",
+            );
+        if response.clicked() {
+            if let Some(vp) = &mut self.viewport {
+                vp.capture_snapshot();
+                self.snapshot_slots = Some(self.current_slot_entries());
+            }
+        }
+        response.context_menu(|ui| {
+            if ui
+                .add_enabled(self.snapshot_slots.is_some(), egui::Button::new("清除快照"))
+                .clicked()
+            {
+                self.snapshot_slots = None;
+                if let Some(vp) = &mut self.viewport {
+                    vp.model_renderer.clear_snapshot();
+                    vp.mark_dirty();
+                }
+                ui.close();
+            }
+        });
+
+        let mut held = false;
+        if let Some(snapshot) = &self.snapshot_slots {
+            let compare = ui
+                .button("对比")
+                .on_hover_text("按住显示快照 A，松开恢复当前状态 (也可按住 Tab 键)");
+            let tab_held =
+                !ui.ctx().wants_keyboard_input() && ui.input(|i| i.key_down(egui::Key::Tab));
+            held = compare.is_pointer_button_down_on() || tab_held;
+
+            let current = self.current_slot_entries();
+            let changed: Vec<&str> = ALL_SLOTS
+                .iter()
+                .filter(|slot| snapshot.get(*slot) != current.get(*slot))
+                .map(|slot| slot.display_name())
+                .collect();
+            let (text, color) = if held {
+                (
+                    "正在显示快照 A".to_string(),
+                    egui::Color32::from_rgb(230, 160, 60),
+                )
+            } else if changed.is_empty() {
+                ("快照 A".to_string(), ui.visuals().weak_text_color())
+            } else {
+                (
+                    format!("快照 A ({} 个槽位不同)", changed.len()),
+                    ui.visuals().text_color(),
+                )
+            };
+            let label = ui.label(egui::RichText::new(text).color(color).strong());
+            if !changed.is_empty() {
+                label.on_hover_text(format!("与快照 A 不同: {}", changed.join("、")));
+            }
+        }
+        self.comparing = held;
+        if let Some(vp) = &mut self.viewport {
+            vp.set_snapshot_visible(held);
+        }
+    }
+
+    /// 开始预览新物品时使用默认预览染料
+    fn reset_preview_stains(&mut self) {
+        let stain = self.default_preview_stain;
+        self.preview_stain_ids = [stain, stain];
+        self.preview_stains_from_default = stain != 0;
+    }
+
+    /// 补全建议: 已装备物品所属套装中，与空装备槽位对应的物品 (slot, all_items 下标)
+    ///
+    /// 参考套装以身体槽位优先，其次按槽位顺序；套装缺少该槽位时不给出建议
+    fn set_completion_suggestions(&self, app: &AppContext<'_>) -> Vec<(EquipSlot, usize)> {
+        let slot_order = std::iter::once(EquipSlot::Body)
+            .chain(GEAR_SLOTS.iter().copied().filter(|s| *s != EquipSlot::Body));
+        let mut ref_sets: Vec<usize> = Vec::new();
+        for slot in slot_order {
+            let set_idx = self
+                .glamour_set
+                .get_slot(slot)
+                .and_then(|s| app.item_id_map.get(&s.item_id))
+                .and_then(|&idx| app.items.get(idx))
+                .and_then(|item| app.set_id_to_set_idx.get(&item.set_id()));
+            if let Some(&set_idx) = set_idx {
+                if !ref_sets.contains(&set_idx) {
+                    ref_sets.push(set_idx);
+                }
+            }
+        }
+
+        let mut suggestions = Vec::new();
+        for slot in GEAR_SLOTS {
+            if self.glamour_set.get_slot(slot).is_some() {
+                continue;
+            }
+            let found = ref_sets.iter().find_map(|&set_idx| {
+                app.equipment_sets[set_idx]
+                    .item_indices
+                    .iter()
+                    .copied()
+                    .find(|&i| app.items[i].equip_slot() == Some(slot))
+            });
+            if let Some(idx) = found {
+                suggestions.push((slot, idx));
+            }
+        }
+        suggestions
+    }
+
+    /// 装备限制标记，预览种族无法装备时追加提示 (预览仍按种族回退加载模型)
+    fn show_restriction_notice(&self, ui: &mut egui::Ui, item: &GameItem) {
+        show_restriction_badge(ui, item);
+        if !item.equip_restriction.allows_race_code(self.preview_race) {
+            ui.label(
+                egui::RichText::new(tr!("restriction.race_conflict"))
+                    .small()
+                    .color(egui::Color32::from_rgb(230, 120, 80)),
+            );
+        }
+    }
+
+    pub fn set_adaptive_resolution(&mut self, enabled: bool) {
+        for vp in [&mut self.viewport, &mut self.detail_viewport]
+            .into_iter()
+            .flatten()
+        {
+            vp.adaptive_resolution = enabled;
+        }
+    }
+
+    pub fn set_viewport_input(&mut self, input: ViewportInputConfig) {
+        for vp in [&mut self.viewport, &mut self.detail_viewport]
+            .into_iter()
+            .flatten()
+        {
+            vp.input = input;
+        }
+    }
+
+    /// 开始分帧重建合并预览: 确定统一种族后逐帧加载各槽位，进行中的重建会被替换
+    fn start_mesh_rebuild(
         &mut self,
         items: &[GameItem],
         item_id_map: &HashMap<u32, usize>,
         game: &GameData,
     ) {
         self.needs_mesh_rebuild = false;
+        let read_stats = game.read_stats();
 
+        // 使用身体模型的物品 (小衣等) 视为该槽位没有可见网格，不参与种族选择与加载；
+        // 加载时出过错的物品同样跳过
         let equipped_items: Vec<(EquipSlot, &GameItem)> = ALL_SLOTS
             .iter()
             .filter_map(|slot| {
@@ -138,149 +545,306 @@ impl GlamourEditor {
                     .get_slot(*slot)
                     .and_then(|gs| item_id_map.get(&gs.item_id))
                     .and_then(|&idx| items.get(idx))
+                    .filter(|item| {
+                        !item.uses_body_model() && !crate::crash::is_item_skipped(item.row_id)
+                    })
                     .map(|item| (*slot, item))
             })
             .collect();
 
-        let unified_race = if equipped_items.is_empty() {
-            RACE_CODES[0]
-        } else {
-            let mut chosen = RACE_CODES[0];
-            for &rc in RACE_CODES {
-                let all_exist = equipped_items.iter().all(|(_, item)| {
-                    item.model_path_for_race(rc)
-                        .map(|path| game.read_file(&path).is_ok())
-                        .unwrap_or(false)
-                });
-                if all_exist {
-                    chosen = rc;
-                    break;
-                }
-            }
-            chosen
+        let (unified_race, plan) = self.choose_preview_race(&equipped_items, game);
+        let pending = match plan.isolated {
+            Some(slot) => vec![slot],
+            None => equipped_items.iter().map(|(slot, _)| *slot).collect(),
         };
+        self.preview_race = unified_race;
+
+        self.mesh_rebuild = Some(MeshRebuildJob {
+            unified_race,
+            plan,
+            pending,
+            meshes: Vec::new(),
+            textures: Vec::new(),
+            slot_states: HashMap::new(),
+            diagnostics: Vec::new(),
+            skinning: SkinningReport::default(),
+            read_stats,
+        });
+    }
 
-        let mut all_meshes: Vec<MeshData> = Vec::new();
-        let mut all_textures: Vec<tomestone_render::MeshTextures> = Vec::new();
-
-        for slot in &ALL_SLOTS {
-            let state = self.slot_states.entry(*slot).or_default();
+    /// 选择合并预览的统一种族
+    ///
+    /// 按 (原生模型且骨架覆盖其骨骼的物品数, 原生模型的物品数) 为各种族评分，同分时按
+    /// RACE_CODES 顺序。面饰多为少数种族的通用模型，不要求原生模型，只看骨架覆盖。
+    /// 替换身体的服装在最佳种族下仍无法适配时，改用它自己的种族单独预览，避免蒙皮错乱
+    fn choose_preview_race(
+        &mut self,
+        equipped_items: &[(EquipSlot, &GameItem)],
+        game: &GameData,
+    ) -> (&'static str, RacePlan) {
+        struct Probe<'a> {
+            slot: EquipSlot,
+            item: &'a GameItem,
+            native: Vec<&'static str>,
+            bones: Vec<String>,
+        }
+        let probes: Vec<Probe> = equipped_items
+            .iter()
+            .map(|&(slot, item)| Probe {
+                slot,
+                item,
+                native: RACE_CODES
+                    .iter()
+                    .copied()
+                    .filter(|rc| {
+                        item.model_path_for_race(rc)
+                            .is_some_and(|path| game.read_file(&path).is_ok())
+                    })
+                    .collect(),
+                bones: load_mdl_with_fallback(game, &item.model_paths())
+                    .map(|result| result.bone_names)
+                    .unwrap_or_default(),
+            })
+            .collect();
+        if probes.is_empty() {
+            return (RACE_CODES[0], RacePlan::default());
+        }
 
-            let slot_item_id = self.glamour_set.get_slot(*slot).map(|s| s.item_id);
+        // 没有任何骨架可用时 (如演示数据) 不检查骨骼覆盖
+        let check_bones = !self.skeleton_cache.available_races(game).is_empty();
+        let mut satisfies = |probe: &Probe, rc: &'static str| {
+            (probe.slot == EquipSlot::Facewear || probe.native.contains(&rc))
+                && (!check_bones || self.skeleton_cache.covers_bones(rc, game, &probe.bones))
+        };
 
-            if slot_item_id.is_none() {
-                state.loaded_item_id = None;
-                state.mesh_range = all_meshes.len()..all_meshes.len();
-                state.cached_materials.clear();
-                state.cached_meshes.clear();
-                state.is_dual_dye = false;
-                continue;
+        let mut best = (RACE_CODES[0], (0usize, 0usize));
+        for &rc in RACE_CODES {
+            let satisfied = probes.iter().filter(|p| satisfies(*p, rc)).count();
+            let native = probes.iter().filter(|p| p.native.contains(&rc)).count();
+            if (satisfied, native) > best.1 {
+                best = (rc, (satisfied, native));
             }
+        }
+        let race = best.0;
 
-            let item_id = slot_item_id.unwrap();
-
-            let item = match item_id_map.get(&item_id).and_then(|&idx| items.get(idx)) {
-                Some(item) => item,
-                None => {
-                    state.loaded_item_id = None;
-                    state.mesh_range = all_meshes.len()..all_meshes.len();
-                    continue;
-                }
+        if let Some(probe) = probes
+            .iter()
+            .find(|p| p.item.replaces_body() && !satisfies(*p, race))
+        {
+            let own_race = probe
+                .native
+                .iter()
+                .copied()
+                .find(|&rc| satisfies(probe, rc))
+                .or_else(|| probe.native.first().copied())
+                .unwrap_or(race);
+            let plan = RacePlan {
+                retargeted: Vec::new(),
+                isolated: Some(probe.slot),
             };
+            return (own_race, plan);
+        }
 
-            let unified_path = match item.model_path_for_race(unified_race) {
-                Some(p) => p,
-                None => {
-                    state.loaded_item_id = None;
-                    state.mesh_range = all_meshes.len()..all_meshes.len();
-                    continue;
-                }
-            };
-            let (load_result_mdl, actual_race) = match load_mdl(game, &unified_path) {
-                Ok(result) if !result.meshes.is_empty() => (Some(result), unified_race.to_string()),
-                _ => {
-                    let mut found = (None, String::new());
-                    for &rc in RACE_CODES {
-                        if let Some(path) = item.model_path_for_race(rc) {
-                            if let Ok(result) = load_mdl(game, &path) {
-                                if !result.meshes.is_empty() {
-                                    found = (Some(result), rc.to_string());
-                                    break;
-                                }
-                            }
-                        }
+        let plan = RacePlan {
+            retargeted: probes
+                .iter()
+                .filter(|p| !p.native.contains(&race))
+                .map(|p| p.slot)
+                .collect(),
+            isolated: None,
+        };
+        (race, plan)
+    }
+
+    /// 合并预览的种族说明: 使用的种族、蒙皮适配的物品与单独预览的服装
+    fn show_race_info(&self, ui: &mut egui::Ui, app: &AppContext<'_>) {
+        if self.slot_states.is_empty() {
+            return;
+        }
+        let item_name = |slot: EquipSlot| {
+            self.glamour_set
+                .get_slot(slot)
+                .and_then(|s| app.item_id_map.get(&s.item_id))
+                .and_then(|&idx| app.items.get(idx))
+                .map_or_else(
+                    || slot.display_name().to_string(),
+                    |item| item.name.to_string(),
+                )
+        };
+        let mut text = format!("预览种族: {}", race_code_label(self.preview_race));
+        if !self.race_plan.retargeted.is_empty() {
+            let names: Vec<String> = self
+                .race_plan
+                .retargeted
+                .iter()
+                .map(|&slot| item_name(slot))
+                .collect();
+            text.push_str(&format!("  ·  蒙皮适配: {}", names.join("、")));
+        }
+        ui.label(egui::RichText::new(text).small().weak());
+        if let Some(slot) = self.race_plan.isolated {
+            ui.label(
+                egui::RichText::new(format!(
+                    "「{}」替换身体模型，无法适配其他装备使用的种族，仅单独预览该服装",
+                    item_name(slot)
+                ))
+                .small()
+                .color(egui::Color32::from_rgb(230, 120, 80)),
+            );
+        }
+    }
+
+    /// 推进重建任务一个槽位；全部完成后上传并返回贴图缺失的材质数
+    fn process_mesh_rebuild(
+        &mut self,
+        ctx: &egui::Context,
+        items: &[GameItem],
+        item_id_map: &HashMap<u32, usize>,
+        game: &GameData,
+    ) -> Option<usize> {
+        let mut job = self.mesh_rebuild.take()?;
+        if !job.pending.is_empty() {
+            let slot = job.pending.remove(0);
+            self.load_rebuild_slot(&mut job, slot, items, item_id_map, game);
+            self.mesh_rebuild = Some(job);
+            ctx.request_repaint();
+            return None;
+        }
+
+        // 全部槽位加载完成，一次性替换渲染器的网格列表
+        let stats = game.read_stats();
+        println!(
+            "合并预览重建: 读取文件 {} 次, 跳过已知缺失路径 {} 次",
+            stats.probes - job.read_stats.probes,
+            stats.skipped - job.read_stats.skipped
+        );
+        self.slot_states = job.slot_states;
+        self.race_plan = job.plan;
+        self.texture_diagnostics = job.diagnostics;
+        let geometry: Vec<(&[tomestone_render::Vertex], &[u16])> = job
+            .meshes
+            .iter()
+            .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
+            .collect();
+        if let Some(vp) = &mut self.viewport {
+            vp.model_renderer.set_mesh_data(
+                &vp.render_state.device,
+                &vp.render_state.queue,
+                &geometry,
+                &job.textures,
+            );
+            vp.set_skin_data(&job.meshes);
+            vp.set_unmatched_bones(job.skinning.unmatched_bones.clone());
+            if !job.meshes.is_empty() {
+                let bbox = compute_bounding_box(&job.meshes);
+                vp.camera.focus_on(&bbox);
+                vp.last_bbox = Some(bbox);
+                if std::mem::take(&mut self.apply_saved_view) {
+                    if let Some(view) = &self.glamour_set.view {
+                        view.apply(&mut vp.camera, &mut vp.scene);
                     }
-                    found
                 }
-            };
+            } else {
+                vp.last_bbox = None;
+            }
+            vp.free_texture();
+        }
+        self.skinning_report = job.skinning;
 
-            match load_result_mdl {
-                Some(mut result) if !result.meshes.is_empty() => {
-                    if actual_race != unified_race {
-                        if let Some(target_bind) =
-                            self.skeleton_cache.get_bind_pose(unified_race, game)
-                        {
-                            let target_bind = target_bind.clone();
-                            if let Some(source_bind) =
-                                self.skeleton_cache.get_bind_pose(&actual_race, game)
-                            {
-                                let source_bind = source_bind.clone();
-                                apply_skinning(
-                                    &mut result.meshes,
-                                    &result.bone_names,
-                                    &result.bone_tables,
-                                    &source_bind,
-                                    &target_bind,
-                                );
+        // 新上传的贴图未染色 (重建期间的染色变更也在此时应用)
+        if self.selected_stain_ids.values().any(|s| *s != [0, 0]) {
+            self.needs_rebake = true;
+        }
+        self.meshes = job.meshes;
+        self.mesh_textures = job.textures;
+        Some(crate::game::count_missing(&self.texture_diagnostics))
+    }
+
+    /// 加载一个槽位的模型 (必要时蒙皮到统一种族) 与贴图，追加到重建任务的暂存列表
+    fn load_rebuild_slot(
+        &mut self,
+        job: &mut MeshRebuildJob,
+        slot: EquipSlot,
+        items: &[GameItem],
+        item_id_map: &HashMap<u32, usize>,
+        game: &GameData,
+    ) {
+        let unified_race = job.unified_race;
+        let state = job.slot_states.entry(slot).or_default();
+        state.mesh_range = job.meshes.len()..job.meshes.len();
+
+        let Some(item_id) = self.glamour_set.get_slot(slot).map(|s| s.item_id) else {
+            return;
+        };
+        let Some(item) = item_id_map.get(&item_id).and_then(|&idx| items.get(idx)) else {
+            return;
+        };
+        let Some(unified_path) = item.model_path_for_race(unified_race) else {
+            return;
+        };
+        let _load = crate::crash::ItemLoadGuard::new(item.row_id);
+        let (load_result_mdl, actual_race) = match load_mdl(game, &unified_path) {
+            Ok(result) if !result.meshes.is_empty() => (Some(result), unified_race.to_string()),
+            _ => {
+                let mut found = (None, String::new());
+                for &rc in RACE_CODES {
+                    if let Some(path) = item.model_path_for_race(rc) {
+                        if let Ok(result) = load_mdl(game, &path) {
+                            if !result.meshes.is_empty() {
+                                found = (Some(result), rc.to_string());
+                                break;
                             }
                         }
                     }
+                }
+                found
+            }
+        };
+        let Some(mut result) = load_result_mdl.filter(|r| !r.meshes.is_empty()) else {
+            return;
+        };
 
-                    let start = all_meshes.len();
-                    let load_result = load_mesh_textures(
-                        game,
-                        &result.material_names,
-                        &result.meshes,
-                        item.set_id(),
-                        item.variant_id(),
+        if actual_race != unified_race {
+            if let Some(target_bind) = self.skeleton_cache.get_bind_pose(unified_race, game) {
+                let target_bind = target_bind.clone();
+                if let Some(source_bind) = self.skeleton_cache.get_bind_pose(&actual_race, game) {
+                    let source_bind = source_bind.clone();
+                    let report = apply_skinning(
+                        &mut result.meshes,
+                        &result.bone_names,
+                        &result.bone_tables,
+                        &source_bind,
+                        &target_bind,
                     );
-                    state.loaded_item_id = Some(item_id);
-                    state.cached_materials = load_result.materials;
-                    state.is_dual_dye = has_dual_dye(&state.cached_materials);
-                    state.cached_meshes = result.meshes.clone();
-                    all_meshes.extend(result.meshes);
-                    all_textures.extend(load_result.mesh_textures);
-                    state.mesh_range = start..all_meshes.len();
-                }
-                _ => {
-                    state.loaded_item_id = None;
-                    state.mesh_range = all_meshes.len()..all_meshes.len();
-                    state.cached_materials.clear();
-                    state.cached_meshes.clear();
+                    job.skinning.merge(report);
+                    if slot == EquipSlot::Facewear {
+                        place_unskinned_at_bone(
+                            &mut result.meshes,
+                            &result.bone_tables,
+                            "j_kao",
+                            &source_bind,
+                            &target_bind,
+                        );
+                    }
                 }
             }
         }
 
-        let geometry: Vec<(&[tomestone_render::Vertex], &[u16])> = all_meshes
-            .iter()
-            .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
-            .collect();
-        self.viewport.model_renderer.set_mesh_data(
-            &self.viewport.render_state.device,
-            &self.viewport.render_state.queue,
-            &geometry,
-            &all_textures,
+        let start = job.meshes.len();
+        let load_result = load_mesh_textures(
+            game,
+            &result.material_names,
+            &result.meshes,
+            item.set_id(),
+            item.variant_id(),
         );
-
-        if !all_meshes.is_empty() {
-            let bbox = compute_bounding_box(&all_meshes);
-            self.viewport.camera.focus_on(&bbox);
-            self.viewport.last_bbox = Some(bbox);
-        } else {
-            self.viewport.last_bbox = None;
-        }
-
-        self.viewport.free_texture();
+        state.loaded_item_id = Some(item_id);
+        job.diagnostics.extend(load_result.diagnostics);
+        state.cached_materials = load_result.materials;
+        state.is_dual_dye = has_dual_dye(&state.cached_materials);
+        job.meshes.extend(result.meshes);
+        job.textures.extend(load_result.mesh_textures);
+        state.mesh_range = start..job.meshes.len();
     }
 
     fn rebake_slot_textures(&mut self, slot: EquipSlot, stm: &StainingTemplate) {
@@ -289,7 +853,11 @@ impl GlamourEditor {
             .get(&slot)
             .copied()
             .unwrap_or([0, 0]);
-        let total_meshes = self.viewport.model_renderer.mesh_count();
+        let Some(vp) = &mut self.viewport else {
+            return;
+        };
+        let total_meshes = vp.model_renderer.mesh_count();
+        let show_regions = self.show_dye_regions;
 
         let state = match self.slot_states.get(&slot) {
             Some(s) => s,
@@ -300,51 +868,47 @@ impl GlamourEditor {
             return;
         }
 
-        let mut new_textures: Vec<Option<tomestone_render::TextureData>> =
-            (0..total_meshes).map(|_| None).collect();
-
-        for (local_idx, mesh) in state.cached_meshes.iter().enumerate() {
-            let global_idx = state.mesh_range.start + local_idx;
-            if global_idx >= total_meshes {
-                break;
+        let update_for = |cached: &CachedMaterial| {
+            if show_regions {
+                dye_region_update(cached)
+            } else {
+                dye_update(cached, stain_ids, stm)
             }
+        };
+        let mut updates: Vec<Option<DyeUpdate>> = (0..total_meshes).map(|_| None).collect();
 
-            let mat_idx = mesh.material_index;
-            if let Some(cached) = state.cached_materials.get(&mat_idx) {
-                if cached.uses_color_table {
-                    if let (Some(color_table), Some(id_tex)) =
-                        (&cached.color_table, &cached.id_texture)
-                    {
-                        let dyed_colors = if stain_ids[0] > 0 || stain_ids[1] > 0 {
-                            if let Some(dye_table) = &cached.color_dye_table {
-                                Some(apply_dye(color_table, dye_table, stm, stain_ids))
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        };
-                        let baked =
-                            bake_color_table_texture(id_tex, color_table, dyed_colors.as_ref());
-                        new_textures[global_idx] = Some(baked);
-                    }
-                }
-            }
+        let range = state.mesh_range.start..state.mesh_range.end.min(total_meshes);
+        for global_idx in range {
+            let Some(mesh) = self.meshes.get(global_idx) else {
+                break;
+            };
+            updates[global_idx] = state
+                .cached_materials
+                .get(&mesh.material_index)
+                .and_then(update_for);
         }
 
-        self.viewport.model_renderer.update_textures(
-            &self.viewport.render_state.device,
-            &self.viewport.render_state.queue,
-            &new_textures,
+        submit_dye_updates(
+            &mut vp.model_renderer,
+            &vp.render_state.device,
+            &vp.render_state.queue,
+            updates,
         );
-        self.viewport.mark_dirty();
+        vp.mark_dirty();
     }
 
-    fn rebuild_detail_viewport(&mut self, item: &GameItem, game: &GameData) {
+    /// 重建单件预览，返回贴图缺失的材质数
+    fn rebuild_detail_viewport(&mut self, item: &GameItem, game: &GameData) -> usize {
         self.detail_needs_rebuild = false;
         self.detail_loaded_item_id = Some(item.row_id);
+        self.detail_body_model = item.uses_body_model();
 
-        let paths = item.model_paths();
+        let paths = if self.detail_body_model || crate::crash::is_item_skipped(item.row_id) {
+            Vec::new()
+        } else {
+            item.model_paths()
+        };
+        let _load = crate::crash::ItemLoadGuard::new(item.row_id);
         let mut loaded = None;
         for path in &paths {
             if let Ok(result) = load_mdl(game, path) {
@@ -364,34 +928,35 @@ impl GlamourEditor {
                     item.set_id(),
                     item.variant_id(),
                 );
+                let missing = load_result.missing_count();
                 let geometry: Vec<(&[tomestone_render::Vertex], &[u16])> = result
                     .meshes
                     .iter()
                     .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
                     .collect();
-                self.detail_viewport.model_renderer.set_mesh_data(
-                    &self.detail_viewport.render_state.device,
-                    &self.detail_viewport.render_state.queue,
-                    &geometry,
-                    &load_result.mesh_textures,
-                );
+                if let Some(vp) = &mut self.detail_viewport {
+                    vp.model_renderer.set_mesh_data(
+                        &vp.render_state.device,
+                        &vp.render_state.queue,
+                        &geometry,
+                        &load_result.mesh_textures,
+                    );
+                    vp.set_skin_data(&result.meshes);
+                    let bbox = compute_bounding_box(&result.meshes);
+                    vp.camera.focus_on(&bbox);
+                    vp.last_bbox = Some(bbox);
+                    vp.free_texture();
+                }
                 self.detail_cached_materials = load_result.materials;
-                self.detail_cached_meshes = result.meshes;
-                let bbox = compute_bounding_box(&self.detail_cached_meshes);
-                self.detail_viewport.camera.focus_on(&bbox);
-                self.detail_viewport.last_bbox = Some(bbox);
-                self.detail_viewport.free_texture();
+                self.detail_mesh_materials =
+                    result.meshes.iter().map(|m| m.material_index).collect();
+                missing
             }
             None => {
-                self.detail_viewport.model_renderer.set_mesh_data(
-                    &self.detail_viewport.render_state.device,
-                    &self.detail_viewport.render_state.queue,
-                    &[],
-                    &[],
-                );
+                self.clear_detail_viewport();
                 self.detail_cached_materials.clear();
-                self.detail_cached_meshes.clear();
-                self.detail_viewport.last_bbox = None;
+                self.detail_mesh_materials.clear();
+                0
             }
         }
     }
@@ -410,46 +975,219 @@ impl GlamourEditor {
                 .unwrap_or([0, 0])
         };
 
-        let mut new_textures: Vec<Option<tomestone_render::TextureData>> = Vec::new();
-        for mesh in &self.detail_cached_meshes {
-            let mat_idx = mesh.material_index;
-            if let Some(cached) = self.detail_cached_materials.get(&mat_idx) {
-                if cached.uses_color_table {
-                    if let (Some(color_table), Some(id_tex)) =
-                        (&cached.color_table, &cached.id_texture)
-                    {
-                        let dyed_colors = if stain_ids[0] > 0 || stain_ids[1] > 0 {
-                            cached
-                                .color_dye_table
-                                .as_ref()
-                                .map(|dye_table| apply_dye(color_table, dye_table, stm, stain_ids))
+        let updates: Vec<Option<DyeUpdate>> = self
+            .detail_mesh_materials
+            .iter()
+            .map(|material_index| {
+                self.detail_cached_materials
+                    .get(material_index)
+                    .and_then(|cached| {
+                        if self.show_dye_regions {
+                            dye_region_update(cached)
                         } else {
-                            None
-                        };
-                        let baked =
-                            bake_color_table_texture(id_tex, color_table, dyed_colors.as_ref());
-                        new_textures.push(Some(baked));
-                        continue;
-                    }
+                            dye_update(cached, stain_ids, stm)
+                        }
+                    })
+            })
+            .collect();
+        if let Some(vp) = &mut self.detail_viewport {
+            submit_dye_updates(
+                &mut vp.model_renderer,
+                &vp.render_state.device,
+                &vp.render_state.queue,
+                updates,
+            );
+            vp.mark_dirty();
+        }
+    }
+
+    /// 清空单件预览视口的网格
+    fn clear_detail_viewport(&mut self) {
+        if let Some(vp) = &mut self.detail_viewport {
+            vp.model_renderer.set_mesh_data(
+                &vp.render_state.device,
+                &vp.render_state.queue,
+                &[],
+                &[],
+            );
+            vp.set_skin_data(&[]);
+            vp.last_bbox = None;
+        }
+    }
+
+    /// 选择保存位置并开始整套导出，只导出已加载模型的槽位
+    fn start_outfit_export(&mut self, app: &AppContext<'_>) -> Result<(), String> {
+        let pending: Vec<EquipSlot> = ALL_SLOTS
+            .iter()
+            .copied()
+            .filter(|slot| {
+                self.slot_states
+                    .get(slot)
+                    .is_some_and(|s| s.loaded_item_id.is_some() && !s.mesh_range.is_empty())
+            })
+            .collect();
+        if pending.is_empty() {
+            return Err("没有可导出的装备".to_string());
+        }
+        let name_ctx = ExportNameContext {
+            item: Some(self.glamour_set.name.clone()),
+            race: Some(self.preview_race.to_string()),
+            ..Default::default()
+        };
+        let file_stem = format_export_name(app.export_name_template, &name_ctx, "outfit");
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("glTF", &["glb"])
+            .set_file_name(format!("{}.glb", file_stem))
+            .save_file()
+        else {
+            return Ok(());
+        };
+
+        let slots: Vec<serde_json::Value> = pending
+            .iter()
+            .filter_map(|slot| {
+                let gs = self.glamour_set.get_slot(*slot)?;
+                let item = app
+                    .item_id_map
+                    .get(&gs.item_id)
+                    .and_then(|&idx| app.items.get(idx));
+                Some(serde_json::json!({
+                    "slot": slot.slot_abbr(),
+                    "item_id": gs.item_id,
+                    "item_name": item.map(|i| i.name.as_str()),
+                    "stain_ids": self.selected_stain_ids.get(slot).copied().unwrap_or([0, 0]),
+                }))
+            })
+            .collect();
+        let manifest = serde_json::json!({
+            "set_name": self.glamour_set.name,
+            "race": self.preview_race,
+            "slots": slots,
+        });
+
+        self.export_job = Some(OutfitExportJob {
+            path,
+            builder: GlbBuilder::new(),
+            total: pending.len(),
+            pending,
+            manifest,
+        });
+        Ok(())
+    }
+
+    /// 导出一个槽位: 顶点取合并预览中已变换到统一种族的网格，贴图按当前染料烘焙
+    fn export_slot(
+        &self,
+        builder: &mut GlbBuilder,
+        slot: EquipSlot,
+        stm: Option<&StainingTemplate>,
+    ) -> Result<(), String> {
+        let Some(state) = self.slot_states.get(&slot) else {
+            return Ok(());
+        };
+        let stain_ids = self
+            .selected_stain_ids
+            .get(&slot)
+            .copied()
+            .unwrap_or([0, 0]);
+        let meshes = self.meshes.get(state.mesh_range.clone()).unwrap_or(&[]);
+        let diffuses: Vec<Option<tomestone_render::TextureData>> = meshes
+            .iter()
+            .zip(state.mesh_range.clone())
+            .map(|(mesh, i)| {
+                state
+                    .cached_materials
+                    .get(&mesh.material_index)
+                    .and_then(|cached| bake_dyed_diffuse(cached, stain_ids, stm))
+                    .or_else(|| self.mesh_textures.get(i).map(|t| t.diffuse.clone()))
+            })
+            .collect();
+        let primitives: Vec<ExportPrimitive<'_>> = meshes
+            .iter()
+            .zip(&diffuses)
+            .zip(state.mesh_range.clone())
+            .map(|((mesh, diffuse), i)| {
+                let tex = self.mesh_textures.get(i);
+                ExportPrimitive {
+                    vertices: &mesh.vertices,
+                    indices: &mesh.indices,
+                    diffuse: diffuse.as_ref(),
+                    normal: tex.and_then(|t| t.normal.as_ref()),
+                    mask: tex.and_then(|t| t.mask.as_ref()),
                 }
+            })
+            .collect();
+        builder.add_node(slot.slot_abbr(), &primitives)
+    }
+
+    /// 推进导出任务 (每帧一个槽位)，完成后写出文件
+    fn process_export_job(&mut self, ctx: &egui::Context, app: &mut AppContext<'_>) {
+        let Some(mut job) = self.export_job.take() else {
+            return;
+        };
+        if let Some(slot) = job.pending.first().copied() {
+            job.pending.remove(0);
+            if let Err(e) = self.export_slot(&mut job.builder, slot, app.stm) {
+                app.toasts.push(format!("导出失败: {}", e));
+                return;
             }
-            new_textures.push(None);
+            self.export_job = Some(job);
+            ctx.request_repaint();
+            return;
+        }
+
+        let node_count = job.builder.node_count();
+        let result = job
+            .builder
+            .finish(&self.glamour_set.name, job.manifest)
+            .and_then(|bytes| std::fs::write(&job.path, bytes).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => app.toasts.push(format!(
+                "已导出 {} 个部位到 {}",
+                node_count,
+                job.path.display()
+            )),
+            Err(e) => app.toasts.push(format!("导出失败: {}", e)),
         }
-        self.detail_viewport.model_renderer.update_textures(
-            &self.detail_viewport.render_state.device,
-            &self.detail_viewport.render_state.queue,
-            &new_textures,
-        );
-        self.detail_viewport.mark_dirty();
     }
 
     pub fn show(&mut self, ctx: &egui::Context, app: &mut AppContext<'_>) -> GlamourEditorAction {
-        if self.needs_mesh_rebuild {
-            self.rebuild_merged_meshes(app.items, app.item_id_map, app.game);
+        // 默认预览染料设置变更时，更新仍使用默认值的预览
+        if app.default_preview_stain != self.default_preview_stain {
+            self.default_preview_stain = app.default_preview_stain;
+            if self.preview_item_id.is_some()
+                && (self.preview_stains_from_default || self.preview_stain_ids == [0, 0])
+            {
+                self.reset_preview_stains();
+                self.detail_needs_rebake = true;
+            }
+        }
+
+        // 显示快照期间渲染器中是快照网格，重建与染色推迟到松开对比之后
+        if self.needs_mesh_rebuild && !self.comparing {
+            self.start_mesh_rebuild(app.items, app.item_id_map, app.game);
+        }
+        let rebuilt = if self.comparing {
+            None
+        } else {
+            self.process_mesh_rebuild(ctx, app.items, app.item_id_map, app.game)
+        };
+        if let Some(missing) = rebuilt {
+            if missing > 0 {
+                app.toasts.push(format!("{} 个材质的贴图缺失", missing));
+            }
+            if !self.skinning_report.is_empty() {
+                app.toasts.push(format!(
+                    "跨种族蒙皮: {} 个骨骼在骨架中缺失，{} 个顶点位置可能错误",
+                    self.skinning_report.unmatched_bones.len(),
+                    self.skinning_report.affected_vertices
+                ));
+            }
             self.detail_needs_rebuild = true;
         }
 
-        if self.needs_rebake {
+        // 重建期间的染色变更保留到重建完成后再烘焙 (mesh_range 此时才与渲染器一致)
+        if self.needs_rebake && self.mesh_rebuild.is_none() && !self.comparing {
             self.needs_rebake = false;
             if let Some(stm) = app.stm {
                 for slot in &ALL_SLOTS {
@@ -471,20 +1209,21 @@ impl GlamourEditor {
             if let Some(item_id) = detail_target_id {
                 if let Some(&idx) = app.item_id_map.get(&item_id) {
                     if let Some(item) = app.items.get(idx) {
-                        self.rebuild_detail_viewport(item, app.game);
+                        let missing = self.rebuild_detail_viewport(item, app.game);
+                        // 新加载的贴图未染色，按当前染料重新烘焙
+                        self.detail_needs_rebake = true;
+                        // 已装备物品的缺失在合并预览中已提示过，只提示预览物品
+                        if missing > 0 && self.preview_item_id == Some(item_id) {
+                            app.toasts.push(format!("{} 个材质的贴图缺失", missing));
+                        }
                     }
                 }
             } else {
                 self.detail_loaded_item_id = None;
-                self.detail_viewport.model_renderer.set_mesh_data(
-                    &self.detail_viewport.render_state.device,
-                    &self.detail_viewport.render_state.queue,
-                    &[],
-                    &[],
-                );
+                self.detail_body_model = false;
+                self.clear_detail_viewport();
                 self.detail_cached_materials.clear();
-                self.detail_cached_meshes.clear();
-                self.detail_viewport.last_bbox = None;
+                self.detail_mesh_materials.clear();
                 self.detail_needs_rebuild = false;
             }
         }
@@ -496,13 +1235,17 @@ impl GlamourEditor {
             }
         }
 
+        self.process_export_job(ctx, app);
+        let mut start_export = false;
+
         let mut action = GlamourEditorAction::None;
+        let mut open_set: Option<usize> = None;
 
         // ── 左侧: 装备列表 (不按槽位筛选) ──
         egui::SidePanel::left("glamour_equip_list")
             .default_width(300.0)
             .show(ctx, |ui| {
-                ui.heading("选择装备");
+                ui.heading(tr!("heading.select_equipment"));
                 ui.separator();
 
                 // 收集所有已装备的 item_id 用于高亮
@@ -514,6 +1257,11 @@ impl GlamourEditor {
                 let highlight = HighlightConfig {
                     highlighted_ids: &equipped_ids,
                     preview_id: self.preview_item_id,
+                    focus_id: self.preview_item_id.or_else(|| {
+                        self.glamour_set
+                            .get_slot(self.active_slot)
+                            .map(|s| s.item_id)
+                    }),
                 };
 
                 if let Some(clicked) = self.equipment_list.show(
@@ -528,9 +1276,11 @@ impl GlamourEditor {
                     app.icon_cache,
                     ctx,
                     app.game,
+                    app.probed_dye_counts,
+                    None,
                 ) {
                     self.preview_item_id = Some(clicked.item_id);
-                    self.preview_stain_ids = [0, 0];
+                    self.reset_preview_stains();
                     self.active_slot = clicked.slot;
                 }
             });
@@ -540,6 +1290,20 @@ impl GlamourEditor {
             .default_width(280.0)
             .show(ctx, |ui| {
                 let slot = self.active_slot;
+                egui::CollapsingHeader::new("组合说明")
+                    .default_open(!self.glamour_set.description.is_empty())
+                    .show(ui, |ui| {
+                        let response = ui.add(
+                            egui::TextEdit::multiline(&mut self.glamour_set.description)
+                                .desired_rows(3)
+                                .desired_width(f32::INFINITY)
+                                .hint_text("记录搭配思路、获取计划等"),
+                        );
+                        if response.changed() {
+                            self.dirty = true;
+                        }
+                    });
+                ui.separator();
                 ui.heading(slot.display_name());
                 ui.separator();
 
@@ -552,13 +1316,22 @@ impl GlamourEditor {
                                     .color(egui::Color32::from_rgb(100, 200, 255)),
                             );
                             ui.label(egui::RichText::new(&item.name).strong().size(14.0));
-                            let prefix = if item.is_accessory() { "a" } else { "e" };
+                            let prefix = item.model_prefix();
                             ui.label(format!(
                                 "{}{:04} v{:04}",
                                 prefix,
                                 item.set_id(),
                                 item.variant_id()
                             ));
+                            self.show_restriction_notice(ui, item);
+                            if let Some(idx) = item_detail::show_glamour_usage(
+                                ui,
+                                app.glamour_item_usage.get(&item.row_id),
+                                app.glamour_sets,
+                                app.editing_idx,
+                            ) {
+                                open_set = Some(idx);
+                            }
 
                             ui.add_space(4.0);
                             ui.horizontal(|ui| {
@@ -568,7 +1341,12 @@ impl GlamourEditor {
                                         Some(s) => s,
                                         None => return,
                                     };
-                                    let stain_ids = self.preview_stain_ids;
+                                    // 默认预览染料只用于预览，不写入组合
+                                    let stain_ids = if self.preview_stains_from_default {
+                                        [0, 0]
+                                    } else {
+                                        self.preview_stain_ids
+                                    };
                                     self.glamour_set
                                         .set_slot(target_slot, item.row_id, stain_ids);
                                     self.selected_stain_ids.insert(target_slot, stain_ids);
@@ -600,8 +1378,14 @@ impl GlamourEditor {
                                     &mut self.active_dye_channel,
                                     &mut self.selected_shade,
                                     is_dual,
+                                    self.preview_stains_from_default,
                                 );
                                 if changed {
+                                    self.preview_stains_from_default = false;
+                                    self.detail_needs_rebake = true;
+                                }
+                                if show_dye_region_toggle(ui, &mut self.show_dye_regions) {
+                                    self.needs_rebake = true;
                                     self.detail_needs_rebake = true;
                                 }
                             } else {
@@ -612,8 +1396,18 @@ impl GlamourEditor {
                             ui.separator();
                             ui.label(egui::RichText::new("单件预览").strong());
                             let vp_size = ui.available_height().max(150.0).min(250.0);
+                            let empty_label = if self.detail_body_model {
+                                tr!("viewport.body_model")
+                            } else {
+                                ""
+                            };
                             ui.allocate_ui(egui::vec2(ui.available_width(), vp_size), |ui| {
-                                self.detail_viewport.show(ui, ctx, "");
+                                viewport::show_viewport(
+                                    &mut self.detail_viewport,
+                                    ui,
+                                    ctx,
+                                    empty_label,
+                                );
                             });
                         }
                     }
@@ -623,15 +1417,39 @@ impl GlamourEditor {
                     if let Some(&idx) = app.item_id_map.get(&item_id) {
                         if let Some(item) = app.items.get(idx) {
                             ui.label(egui::RichText::new(&item.name).strong().size(14.0));
-                            let prefix = if item.is_accessory() { "a" } else { "e" };
+                            let prefix = item.model_prefix();
                             ui.label(format!(
                                 "{}{:04} v{:04}",
                                 prefix,
                                 item.set_id(),
                                 item.variant_id()
                             ));
+                            self.show_restriction_notice(ui, item);
+                            if let Some(idx) = item_detail::show_glamour_usage(
+                                ui,
+                                app.glamour_item_usage.get(&item.row_id),
+                                app.glamour_sets,
+                                app.editing_idx,
+                            ) {
+                                open_set = Some(idx);
+                            }
 
                             ui.add_space(4.0);
+                            if let Some(gslot) =
+                                self.glamour_set.slots.get_mut(super::slot_key_for(slot))
+                            {
+                                ui.horizontal(|ui| {
+                                    ui.label("备注:");
+                                    let response = ui.add(
+                                        egui::TextEdit::singleline(&mut gslot.note)
+                                            .desired_width(f32::INFINITY)
+                                            .hint_text("如获取途径"),
+                                    );
+                                    if response.changed() {
+                                        self.dirty = true;
+                                    }
+                                });
+                            }
                             if ui.button("卸下").clicked() {
                                 self.glamour_set.remove_slot(slot);
                                 self.needs_mesh_rebuild = true;
@@ -685,8 +1503,9 @@ impl GlamourEditor {
                                         if let Some(sib_item) = app.items.get(sib_idx) {
                                             // 同套装兄弟件也走预览流程
                                             self.preview_item_id = Some(sib_item.row_id);
-                                            self.preview_stain_ids = [0, 0];
+                                            self.reset_preview_stains();
                                             self.active_slot = sib_slot;
+                                            self.equipment_list.reveal(sib_item.row_id);
                                         }
                                     }
 
@@ -740,6 +1559,7 @@ impl GlamourEditor {
                                     &mut self.active_dye_channel,
                                     &mut self.selected_shade,
                                     is_dual,
+                                    false,
                                 );
                                 if changed {
                                     let stain_ids = *slot_stains;
@@ -752,6 +1572,10 @@ impl GlamourEditor {
                                     self.needs_rebake = true;
                                     self.detail_needs_rebake = true;
                                 }
+                                if show_dye_region_toggle(ui, &mut self.show_dye_regions) {
+                                    self.needs_rebake = true;
+                                    self.detail_needs_rebake = true;
+                                }
                             } else {
                                 ui.label("此装备不支持染色");
                             }
@@ -760,8 +1584,18 @@ impl GlamourEditor {
                             ui.separator();
                             ui.label(egui::RichText::new("单件预览").strong());
                             let vp_size = ui.available_height().max(150.0).min(250.0);
+                            let empty_label = if self.detail_body_model {
+                                tr!("viewport.body_model")
+                            } else {
+                                ""
+                            };
                             ui.allocate_ui(egui::vec2(ui.available_width(), vp_size), |ui| {
-                                self.detail_viewport.show(ui, ctx, "");
+                                viewport::show_viewport(
+                                    &mut self.detail_viewport,
+                                    ui,
+                                    ctx,
+                                    empty_label,
+                                );
                             });
                         }
                     }
@@ -782,6 +1616,7 @@ impl GlamourEditor {
                     format!("编辑: {}", self.glamour_set.name)
                 };
                 ui.heading(&title);
+                ui.label(egui::RichText::new(self.glamour_set.coverage_text()).weak());
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     if ui.button("返回").clicked() {
@@ -790,11 +1625,30 @@ impl GlamourEditor {
                     if ui.button("保存").clicked() {
                         action = GlamourEditorAction::Save;
                     }
+                    if ui
+                        .add_enabled(self.export_job.is_none(), egui::Button::new("导出 glTF"))
+                        .on_hover_text("按当前预览 (统一种族、当前染色) 导出整套装备")
+                        .clicked()
+                    {
+                        start_export = true;
+                    }
+                    // 无 3D 模式下没有视角与快照可操作
+                    if self.viewport.is_some() {
+                        self.show_view_controls(ui);
+                        ui.separator();
+                        self.show_snapshot_controls(ui);
+                    }
                 });
             });
 
             ui.separator();
 
+            // 分帧重建中尚未加载的槽位
+            let loading_slots: Vec<EquipSlot> = self
+                .mesh_rebuild
+                .as_ref()
+                .map(|job| job.pending.clone())
+                .unwrap_or_default();
             ui.horizontal(|ui| {
                 ui.label("装备:");
                 for slot in &GEAR_SLOTS {
@@ -812,6 +1666,13 @@ impl GlamourEditor {
                         self.preview_item_id = None;
                         self.preview_stain_ids = [0, 0];
                     }
+                    if let Some(note) = self.glamour_set.slot_note(*slot) {
+                        ui.label(egui::RichText::new(egui_phosphor::regular::NOTE_PENCIL).weak())
+                            .on_hover_text(note);
+                    }
+                    if loading_slots.contains(slot) {
+                        ui.add(egui::Spinner::new().size(10.0));
+                    }
                 }
             });
 
@@ -832,14 +1693,83 @@ impl GlamourEditor {
                         self.preview_item_id = None;
                         self.preview_stain_ids = [0, 0];
                     }
+                    if let Some(note) = self.glamour_set.slot_note(*slot) {
+                        ui.label(egui::RichText::new(egui_phosphor::regular::NOTE_PENCIL).weak())
+                            .on_hover_text(note);
+                    }
+                    if loading_slots.contains(slot) {
+                        ui.add(egui::Spinner::new().size(10.0));
+                    }
                 }
             });
 
+            // 补全建议: 每个空槽位一个图标按钮，单击直接装备
+            let suggestions = self.set_completion_suggestions(app);
+            if !suggestions.is_empty() {
+                let mut equip: Option<(EquipSlot, u32)> = None;
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("补全建议:");
+                    for &(slot, idx) in &suggestions {
+                        let item = &app.items[idx];
+                        let icon = get_or_load_icon(app.icon_cache, ctx, app.game, item.icon_id);
+                        let resp = match icon {
+                            Some(icon) => ui.add(egui::Button::image(
+                                egui::load::SizedTexture::new(icon.id(), egui::vec2(24.0, 24.0)),
+                            )),
+                            None => ui.button(slot.display_name()),
+                        };
+                        if resp
+                            .on_hover_text(format!("{}: {}", slot.display_name(), item.name))
+                            .clicked()
+                        {
+                            equip = Some((slot, item.row_id));
+                        }
+                    }
+                });
+                if let Some((slot, item_id)) = equip {
+                    let stain_ids = self
+                        .selected_stain_ids
+                        .get(&slot)
+                        .copied()
+                        .unwrap_or([0, 0]);
+                    self.glamour_set.set_slot(slot, item_id, stain_ids);
+                    self.needs_mesh_rebuild = true;
+                    self.dirty = true;
+                }
+            }
+
             ui.separator();
 
-            self.viewport.show(ui, ctx, "选择装备以预览");
+            self.show_race_info(ui, app);
+            show_material_diagnostics(ui, "glamour_materials", &self.texture_diagnostics);
+            show_skinning_report(ui, "glamour_skinning", &self.skinning_report);
+            viewport::show_viewport(&mut self.viewport, ui, ctx, "选择装备以预览");
         });
 
+        if start_export {
+            if let Err(e) = self.start_outfit_export(app) {
+                app.toasts.push(e);
+            }
+        }
+
+        if let Some(job) = &self.export_job {
+            egui::Window::new("导出 glTF")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(job.path.display().to_string());
+                    ui.add(
+                        egui::ProgressBar::new(job.progress())
+                            .show_percentage()
+                            .desired_width(240.0),
+                    );
+                });
+        }
+
+        if let Some(idx) = open_set {
+            action = GlamourEditorAction::OpenSet(idx);
+        }
         action
     }
 }
@@ -0,0 +1,211 @@
+//! 外部文件导入: 幻化组合 JSON 与 Anamnesis 角色文件 (.chara)
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::GlamourSet;
+use crate::domain::{EquipSlot, GameItem};
+
+/// 单个文件的导入结果
+pub struct ImportOutcome {
+    pub file_name: String,
+    /// 成功时为 (组合名称, 警告与校验问题)
+    pub result: Result<(String, Vec<String>), String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// 本程序保存/导出的幻化组合 JSON
+    GlamourJson,
+    /// Anamnesis 角色文件 (JSON 内容，.chara 扩展名)
+    AnamnesisChara,
+}
+
+/// .chara 中的装备键 -> 槽位 (两个戒指只保留右手，组合中只有一个戒指槽)
+const CHARA_SLOT_KEYS: [(&str, EquipSlot); 10] = [
+    ("HeadGear", EquipSlot::Head),
+    ("Body", EquipSlot::Body),
+    ("Hands", EquipSlot::Gloves),
+    ("Legs", EquipSlot::Legs),
+    ("Feet", EquipSlot::Feet),
+    ("Ears", EquipSlot::Earrings),
+    ("Neck", EquipSlot::Necklace),
+    ("Wrists", EquipSlot::Bracelet),
+    ("RightRing", EquipSlot::Ring),
+    ("LeftRing", EquipSlot::Ring),
+];
+
+/// 按扩展名与内容判断文件格式，无法识别时返回 None
+pub fn detect_format(path: &Path, json: &Value) -> Option<ImportFormat> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    let looks_like_set = json.get("slots").is_some_and(Value::is_object);
+    let looks_like_chara = CHARA_SLOT_KEYS.iter().any(|(key, _)| json.get(key).is_some());
+    match ext.as_deref() {
+        Some("chara") if looks_like_chara => Some(ImportFormat::AnamnesisChara),
+        Some("json") if looks_like_set => Some(ImportFormat::GlamourJson),
+        Some("json") if looks_like_chara => Some(ImportFormat::AnamnesisChara),
+        _ => None,
+    }
+}
+
+/// 读取并解析一个导入文件，返回新组合 (已分配新 id) 与导入警告
+pub fn import_file(
+    path: &Path,
+    bytes: &[u8],
+    all_items: &[GameItem],
+) -> Result<(GlamourSet, Vec<String>), String> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    if ext != "json" && ext != "chara" {
+        return Err("不支持的文件类型，仅支持幻化组合 JSON 与 .chara".to_string());
+    }
+    let text = std::str::from_utf8(bytes).map_err(|_| "不是文本文件".to_string())?;
+    let json: Value = serde_json::from_str(text).map_err(|e| format!("JSON 解析失败: {}", e))?;
+    let name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("导入的组合");
+    match detect_format(path, &json) {
+        Some(ImportFormat::GlamourJson) => {
+            let imported: GlamourSet =
+                serde_json::from_value(json).map_err(|e| format!("幻化组合格式错误: {}", e))?;
+            // 分配新 id，避免覆盖已有的同 id 组合
+            let mut set = GlamourSet::new(imported.name);
+            set.slots = imported.slots;
+            set.view = imported.view;
+            set.description = imported.description;
+            Ok((set, Vec::new()))
+        }
+        Some(ImportFormat::AnamnesisChara) => Ok(import_chara(&json, name, all_items)),
+        None => Err("无法识别的文件格式".to_string()),
+    }
+}
+
+/// 按模型 (ModelBase, ModelVariant) 匹配物品，同模型的多个物品取 row_id 最小者
+fn import_chara(
+    json: &Value,
+    name: &str,
+    all_items: &[GameItem],
+) -> (GlamourSet, Vec<String>) {
+    let mut set = GlamourSet::new(name);
+    let mut warnings = Vec::new();
+    for (key, slot) in CHARA_SLOT_KEYS {
+        if set.get_slot(slot).is_some() {
+            continue;
+        }
+        let Some(entry) = json.get(key) else {
+            continue;
+        };
+        let field = |name: &str| entry.get(name).and_then(Value::as_u64).unwrap_or(0);
+        let (base, variant) = (field("ModelBase"), field("ModelVariant"));
+        if base == 0 {
+            continue;
+        }
+        let model = base | (variant << 16);
+        let item = all_items
+            .iter()
+            .filter(|i| i.equip_slot() == Some(slot) && i.model_main & 0xFFFF_FFFF == model)
+            .min_by_key(|i| i.row_id);
+        match item {
+            Some(item) => {
+                let stains = [field("DyeId") as u32, field("DyeId2") as u32];
+                set.set_slot(slot, item.row_id, stains);
+            }
+            None => warnings.push(format!(
+                "{}: 找不到模型 {}-{} 对应的物品",
+                slot.display_name(),
+                base,
+                variant
+            )),
+        }
+    }
+    (set, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn gear(row_id: u32, equip_slot_category: u8, model_main: u64) -> GameItem {
+        GameItem {
+            equip_slot_category,
+            model_main,
+            ..GameItem::named(row_id, "gear")
+        }
+    }
+
+    #[test]
+    fn detect_format_sniffs_content_per_extension() {
+        let set = json!({ "id": "a", "name": "set", "slots": {} });
+        let chara = json!({ "HeadGear": { "ModelBase": 1 } });
+        let other = json!({ "foo": 1 });
+        let detect = |path: &str, json: &Value| detect_format(Path::new(path), json);
+
+        assert_eq!(detect("a.json", &set), Some(ImportFormat::GlamourJson));
+        assert_eq!(detect("a.JSON", &chara), Some(ImportFormat::AnamnesisChara));
+        assert_eq!(
+            detect("a.chara", &chara),
+            Some(ImportFormat::AnamnesisChara)
+        );
+        assert_eq!(detect("a.chara", &set), None);
+        assert_eq!(detect("a.json", &other), None);
+        assert_eq!(detect("a.txt", &set), None);
+    }
+
+    #[test]
+    fn unknown_files_are_rejected() {
+        let err = import_file(Path::new("a.json"), br#"{"foo": 1}"#, &[]).unwrap_err();
+        assert_eq!(err, "无法识别的文件格式");
+        assert!(import_file(Path::new("a.txt"), b"{}", &[]).is_err());
+        assert!(import_file(Path::new("a.json"), b"not json", &[]).is_err());
+    }
+
+    #[test]
+    fn glamour_json_gets_new_id_and_keeps_description() {
+        let bytes = br#"{
+            "id": "old",
+            "name": "imported",
+            "slots": { "head": { "item_id": 7, "stain_ids": [1, 0] } },
+            "description": "line 1\nline 2"
+        }"#;
+        let (set, warnings) = import_file(Path::new("a.json"), bytes, &[]).unwrap();
+        assert!(warnings.is_empty());
+        assert_ne!(set.id, "old");
+        assert_eq!(set.name, "imported");
+        assert_eq!(set.description, "line 1\nline 2");
+        assert_eq!(set.get_slot(EquipSlot::Head).unwrap().item_id, 7);
+    }
+
+    #[test]
+    fn chara_models_match_lowest_row_id() {
+        // 模型 12-3: model_main 低 16 位为 ModelBase，次 16 位为 ModelVariant
+        let model = 12 | (3 << 16);
+        let items = [
+            gear(20, 3, model),
+            gear(10, 3, model),
+            gear(5, 4, model),
+            gear(1, 3, 12),
+        ];
+        let bytes = br#"{
+            "HeadGear": { "ModelBase": 12, "ModelVariant": 3, "DyeId": 4, "DyeId2": 5 },
+            "Body": { "ModelBase": 99, "ModelVariant": 1 },
+            "Hands": { "ModelBase": 0, "ModelVariant": 0 }
+        }"#;
+        let (set, warnings) = import_file(Path::new("Alice.chara"), bytes, &items).unwrap();
+        assert_eq!(set.name, "Alice");
+        let head = set.get_slot(EquipSlot::Head).unwrap();
+        assert_eq!((head.item_id, head.stain_ids), (10, [4, 5]));
+        assert!(set.get_slot(EquipSlot::Body).is_none());
+        assert!(set.get_slot(EquipSlot::Gloves).is_none());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("99-1"));
+    }
+}
@@ -1,19 +1,25 @@
+pub mod backup;
 mod editor;
+pub mod import;
 
 pub use editor::{AppContext, GlamourEditor, GlamourEditorAction};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
+use tomestone_render::{Camera, SceneSettings};
 
-use crate::domain::EquipSlot;
+use crate::domain::{EquipSlot, GameItem, StainEntry, ACCESSORY_SLOTS, ALL_SLOTS, GEAR_SLOTS};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlamourSlot {
     pub item_id: u32,
     pub stain_ids: [u32; 2],
+    /// 槽位备注 (如获取途径)，更换物品时保留
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,22 +27,90 @@ pub struct GlamourSet {
     pub id: String,
     pub name: String,
     pub slots: HashMap<String, GlamourSlot>,
+    /// 保存的预览视角与光照，未保存时打开组合按模型包围盒自动对焦
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view: Option<GlamourView>,
+    /// 最后一次在编辑器中保存的时间 (毫秒时间戳，旧数据为 0)
+    #[serde(default)]
+    pub modified_at: u64,
+    /// 组合说明 (多行)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+/// 幻化组合的预览视角与光照设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlamourView {
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub target: [f32; 3],
+    pub light_color: [f32; 3],
+    pub ambient_sky: [f32; 3],
+    pub ambient_ground: [f32; 3],
+    pub fresnel_intensity: f32,
+}
+
+impl GlamourView {
+    pub fn capture(camera: &Camera, scene: &SceneSettings) -> Self {
+        Self {
+            distance: camera.distance,
+            yaw: camera.yaw,
+            pitch: camera.pitch,
+            target: camera.target,
+            light_color: scene.light_color,
+            ambient_sky: scene.ambient_sky,
+            ambient_ground: scene.ambient_ground,
+            fresnel_intensity: scene.fresnel_intensity,
+        }
+    }
+
+    /// 应用到相机与场景，缩放距离限制在相机当前场景尺寸允许的范围内
+    pub fn apply(&self, camera: &mut Camera, scene: &mut SceneSettings) {
+        camera.distance = self.distance;
+        camera.zoom(0.0);
+        camera.yaw = self.yaw;
+        camera.pitch = self.pitch;
+        camera.target = self.target;
+        scene.light_color = self.light_color;
+        scene.ambient_sky = self.ambient_sky;
+        scene.ambient_ground = self.ambient_ground;
+        scene.fresnel_intensity = self.fresnel_intensity;
+    }
 }
 
 impl GlamourSet {
     pub fn new(name: impl Into<String>) -> Self {
-        let id = format!(
-            "{:x}",
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_millis()
-        );
+        let now = now_millis();
         Self {
-            id,
+            id: format!("{:x}", now),
             name: name.into(),
             slots: HashMap::new(),
+            view: None,
+            modified_at: now,
+            description: String::new(),
+        }
+    }
+
+    /// 由一组物品与染色新建组合 (浏览页 "存为幻化组合")；同一槽位只取第一件，非装备物品跳过
+    pub fn from_items<'a>(
+        name: impl Into<String>,
+        items: impl IntoIterator<Item = (&'a GameItem, [u32; 2])>,
+    ) -> Self {
+        let mut set = Self::new(name);
+        for (item, stain_ids) in items {
+            if let Some(slot) = item.equip_slot() {
+                if set.get_slot(slot).is_none() {
+                    set.set_slot(slot, item.row_id, stain_ids);
+                }
+            }
         }
+        set
+    }
+
+    /// 标记为刚刚修改
+    pub fn touch(&mut self) {
+        self.modified_at = now_millis();
     }
 
     pub fn get_slot(&self, slot: EquipSlot) -> Option<&GlamourSlot> {
@@ -44,21 +118,120 @@ impl GlamourSet {
     }
 
     pub fn set_slot(&mut self, slot: EquipSlot, item_id: u32, stain_ids: [u32; 2]) {
-        self.slots.insert(
-            slot_key(slot).to_string(),
-            GlamourSlot { item_id, stain_ids },
-        );
+        let entry = self
+            .slots
+            .entry(slot_key(slot).to_string())
+            .or_insert_with(|| GlamourSlot {
+                item_id,
+                stain_ids,
+                note: String::new(),
+            });
+        entry.item_id = item_id;
+        entry.stain_ids = stain_ids;
+    }
+
+    /// 槽位备注，空槽位或没有备注时为 None
+    pub fn slot_note(&self, slot: EquipSlot) -> Option<&str> {
+        self.get_slot(slot)
+            .map(|s| s.note.as_str())
+            .filter(|note| !note.is_empty())
+    }
+
+    /// 名称、说明或任一槽位备注包含搜索词 (`query_lower` 需已转小写，空串视为匹配)
+    pub fn matches_search(&self, query_lower: &str) -> bool {
+        query_lower.is_empty()
+            || self.name.to_lowercase().contains(query_lower)
+            || self.description.to_lowercase().contains(query_lower)
+            || self
+                .slots
+                .values()
+                .any(|s| s.note.to_lowercase().contains(query_lower))
     }
 
     pub fn remove_slot(&mut self, slot: EquipSlot) {
         self.slots.remove(slot_key(slot));
     }
 
-    pub fn slot_count(&self) -> usize {
-        self.slots.len()
+    /// 未填充的装备槽位 (不含饰品)
+    pub fn empty_gear_slots(&self) -> Vec<EquipSlot> {
+        GEAR_SLOTS
+            .iter()
+            .copied()
+            .filter(|slot| self.get_slot(*slot).is_none())
+            .collect()
+    }
+
+    /// 槽位覆盖情况，如 "装备 4/5 · 饰品 2/5"
+    pub fn coverage_text(&self) -> String {
+        let count = |slots: &[EquipSlot]| {
+            slots
+                .iter()
+                .filter(|slot| self.get_slot(**slot).is_some())
+                .count()
+        };
+        format!(
+            "装备 {}/{} · 饰品 {}/{}",
+            count(&GEAR_SLOTS),
+            GEAR_SLOTS.len(),
+            count(&ACCESSORY_SLOTS),
+            ACCESSORY_SLOTS.len()
+        )
+    }
+
+    /// 各槽位物品完全一致 (忽略染色)
+    pub fn same_items(&self, other: &GlamourSet) -> bool {
+        self.slots.len() == other.slots.len()
+            && self.slots.iter().all(|(key, slot)| {
+                other
+                    .slots
+                    .get(key)
+                    .is_some_and(|o| o.item_id == slot.item_id)
+            })
+    }
+
+    /// 各槽位物品与染色均一致
+    pub fn same_items_and_dyes(&self, other: &GlamourSet) -> bool {
+        self.same_items(other)
+            && self.slots.iter().all(|(key, slot)| {
+                other
+                    .slots
+                    .get(key)
+                    .is_some_and(|o| o.stain_ids == slot.stain_ids)
+            })
     }
 }
 
+/// 保存时发现的重复组合
+#[derive(Debug, Clone)]
+pub struct GlamourDuplicate {
+    /// 重复组合的 id，优先列出染色也相同的
+    pub set_ids: Vec<String>,
+    /// 第一个重复组合的染色也完全相同
+    pub with_dyes: bool,
+}
+
+/// 在已保存的组合中查找与 `set` 物品相同的组合 (跳过自身与空组合)
+pub fn find_duplicate(set: &GlamourSet, saved: &[GlamourSet]) -> Option<GlamourDuplicate> {
+    if set.slots.is_empty() {
+        return None;
+    }
+    let mut exact = Vec::new();
+    let mut items_only = Vec::new();
+    for other in saved.iter().filter(|o| o.id != set.id) {
+        if set.same_items_and_dyes(other) {
+            exact.push(other.id.clone());
+        } else if set.same_items(other) {
+            items_only.push(other.id.clone());
+        }
+    }
+    let with_dyes = !exact.is_empty();
+    exact.extend(items_only);
+    (!exact.is_empty()).then_some(GlamourDuplicate {
+        set_ids: exact,
+        with_dyes,
+    })
+}
+
 fn slot_key(slot: EquipSlot) -> &'static str {
     match slot {
         EquipSlot::Head => "head",
@@ -70,6 +243,7 @@ fn slot_key(slot: EquipSlot) -> &'static str {
         EquipSlot::Necklace => "necklace",
         EquipSlot::Bracelet => "bracelet",
         EquipSlot::Ring => "ring",
+        EquipSlot::Facewear => "facewear",
     }
 }
 
@@ -77,15 +251,224 @@ pub fn slot_key_for(slot: EquipSlot) -> &'static str {
     slot_key(slot)
 }
 
+// ── 校验 ──
+
+/// 单个槽位的校验问题
+#[derive(Debug, Clone)]
+pub struct SlotIssue {
+    pub slot: EquipSlot,
+    pub item_id: u32,
+    /// 物品已不存在于 Item 表
+    pub missing_item: bool,
+    /// 已不存在的染料 ID
+    pub missing_stains: Vec<u32>,
+}
+
+/// 幻化组合的校验结果 (只读，不修改组合本身)
+#[derive(Debug, Clone, Default)]
+pub struct GlamourValidation {
+    pub issues: Vec<SlotIssue>,
+}
+
+impl GlamourValidation {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn missing_item_count(&self) -> usize {
+        self.issues.iter().filter(|i| i.missing_item).count()
+    }
+
+    pub fn missing_stain_count(&self) -> usize {
+        self.issues.iter().map(|i| i.missing_stains.len()).sum()
+    }
+
+    /// 列表徽标文本
+    pub fn badge_text(&self) -> String {
+        let items = self.missing_item_count();
+        let stains = self.missing_stain_count();
+        match (items, stains) {
+            (0, 0) => "正常".to_string(),
+            (n, 0) => format!("{} 个物品缺失", n),
+            (0, _) => "染料缺失".to_string(),
+            (n, _) => format!("{} 个物品缺失, 染料缺失", n),
+        }
+    }
+}
+
+/// 检查组合中每个槽位的物品和染料是否仍存在
+pub fn validate_glamour_set(
+    set: &GlamourSet,
+    item_id_map: &HashMap<u32, usize>,
+    stains: &[StainEntry],
+) -> GlamourValidation {
+    let stain_ids: HashSet<u32> = stains.iter().map(|s| s.id).collect();
+    let mut issues = Vec::new();
+    for slot in &ALL_SLOTS {
+        let Some(gslot) = set.get_slot(*slot) else {
+            continue;
+        };
+        let missing_item = !item_id_map.contains_key(&gslot.item_id);
+        let missing_stains: Vec<u32> = gslot
+            .stain_ids
+            .iter()
+            .copied()
+            .filter(|&id| id != 0 && !stain_ids.contains(&id))
+            .collect();
+        if missing_item || !missing_stains.is_empty() {
+            issues.push(SlotIssue {
+                slot: *slot,
+                item_id: gslot.item_id,
+                missing_item,
+                missing_stains,
+            });
+        }
+    }
+    GlamourValidation { issues }
+}
+
+impl GlamourSet {
+    /// 修复一个问题槽位: 物品缺失时清空槽位，仅染料缺失时重置对应染料
+    pub fn clear_issue(&mut self, issue: &SlotIssue) {
+        if issue.missing_item {
+            self.remove_slot(issue.slot);
+        } else if let Some(gslot) = self.slots.get_mut(slot_key(issue.slot)) {
+            for id in &mut gslot.stain_ids {
+                if issue.missing_stains.contains(id) {
+                    *id = 0;
+                }
+            }
+        }
+    }
+}
+
+// ── 统计 ──
+
+/// 每个槽位展示的常用物品数
+const TOP_ITEMS_PER_SLOT: usize = 3;
+
+/// 已保存幻化组合的使用统计
+#[derive(Debug, Clone, Default)]
+pub struct GlamourStats {
+    /// (染料 ID, 使用该染料的组合数)，两个通道合并计数，按组合数降序
+    pub dye_usage: Vec<(u32, usize)>,
+    /// 每个槽位最常用的物品 (物品 ID, 组合数)，跳过无人使用的槽位
+    pub slot_items: Vec<(EquipSlot, Vec<(u32, usize)>)>,
+    /// 装备槽位 (头/身/手/腿/脚) 未填满的组合数
+    pub incomplete_sets: usize,
+}
+
+/// 物品 row_id -> 使用该物品的组合索引 (按组合顺序，同一组合只记一次)
+pub fn build_item_usage_index(sets: &[GlamourSet]) -> HashMap<u32, Vec<usize>> {
+    let mut index: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (set_idx, set) in sets.iter().enumerate() {
+        for slot in set.slots.values() {
+            let users = index.entry(slot.item_id).or_default();
+            if users.last() != Some(&set_idx) {
+                users.push(set_idx);
+            }
+        }
+    }
+    index
+}
+
+/// 汇总全部组合的染料与物品使用情况，同一组合内重复使用只计一次
+pub fn compute_glamour_stats(sets: &[GlamourSet]) -> GlamourStats {
+    let mut dye_counts: HashMap<u32, usize> = HashMap::new();
+    let mut item_counts: HashMap<EquipSlot, HashMap<u32, usize>> = HashMap::new();
+    let mut incomplete_sets = 0;
+
+    for set in sets {
+        let mut set_dyes = HashSet::new();
+        for slot in &ALL_SLOTS {
+            let Some(gslot) = set.get_slot(*slot) else {
+                continue;
+            };
+            *item_counts
+                .entry(*slot)
+                .or_default()
+                .entry(gslot.item_id)
+                .or_default() += 1;
+            set_dyes.extend(gslot.stain_ids.iter().copied().filter(|&id| id != 0));
+        }
+        for id in set_dyes {
+            *dye_counts.entry(id).or_default() += 1;
+        }
+        if GEAR_SLOTS.iter().any(|slot| set.get_slot(*slot).is_none()) {
+            incomplete_sets += 1;
+        }
+    }
+
+    let slot_items = ALL_SLOTS
+        .iter()
+        .filter_map(|slot| {
+            let counts = item_counts.remove(slot)?;
+            let mut top = sorted_by_count(counts);
+            top.truncate(TOP_ITEMS_PER_SLOT);
+            Some((*slot, top))
+        })
+        .collect();
+
+    GlamourStats {
+        dye_usage: sorted_by_count(dye_counts),
+        slot_items,
+        incomplete_sets,
+    }
+}
+
+/// 按计数降序排列，计数相同时按 ID 升序保证顺序稳定
+fn sorted_by_count(counts: HashMap<u32, usize>) -> Vec<(u32, usize)> {
+    let mut list: Vec<(u32, usize)> = counts.into_iter().collect();
+    list.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    list
+}
+
+/// 管理页列表的筛选条件 (点击统计条目设置)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlamourFilter {
+    Dye(u32),
+    Item(u32),
+}
+
+impl GlamourFilter {
+    pub fn matches(&self, set: &GlamourSet) -> bool {
+        match *self {
+            Self::Dye(id) => set.slots.values().any(|s| s.stain_ids.contains(&id)),
+            Self::Item(id) => set.slots.values().any(|s| s.item_id == id),
+        }
+    }
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// 最近编辑的幻化组合 (从未在编辑器中保存过的组合不计入)
+pub fn most_recently_edited(sets: &[GlamourSet]) -> Option<usize> {
+    sets.iter()
+        .enumerate()
+        .filter(|(_, s)| s.modified_at > 0)
+        .max_by_key(|(_, s)| s.modified_at)
+        .map(|(i, _)| i)
+}
+
 fn glamour_dir() -> PathBuf {
     crate::config::glamours_dir()
 }
 
+/// 组合的 JSON 文本，保存、导出与备份文件夹使用同一格式
+pub fn glamour_set_json(set: &GlamourSet) -> Result<String, String> {
+    serde_json::to_string_pretty(set).map_err(|e| format!("序列化失败: {}", e))
+}
+
 pub fn save_glamour_set(set: &GlamourSet) -> Result<(), String> {
     let dir = glamour_dir();
     fs::create_dir_all(&dir).map_err(|e| format!("创建目录失败: {}", e))?;
     let path = dir.join(format!("{}.json", set.id));
-    let json = serde_json::to_string_pretty(set).map_err(|e| format!("序列化失败: {}", e))?;
+    let json = glamour_set_json(set)?;
     fs::write(&path, json).map_err(|e| format!("写入失败: {}", e))?;
     Ok(())
 }
@@ -113,3 +496,320 @@ pub fn delete_glamour_set(id: &str) -> Result<(), String> {
     fs::remove_file(&path).map_err(|e| format!("删除失败: {}", e))?;
     Ok(())
 }
+
+// ── 自动保存 ──
+
+/// 编辑器自动保存的恢复内容: 编辑中的组合与预览状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlamourRecovery {
+    pub set: GlamourSet,
+    /// 当前选中的槽位 (slot_key)
+    pub active_slot: String,
+    /// 预览中尚未装备的物品与染料
+    #[serde(default)]
+    pub preview_item_id: Option<u32>,
+    #[serde(default)]
+    pub preview_stain_ids: [u32; 2],
+    /// 自动保存时间 (毫秒时间戳)
+    pub saved_at: u64,
+}
+
+impl GlamourRecovery {
+    pub fn new(
+        set: GlamourSet,
+        active_slot: EquipSlot,
+        preview_item_id: Option<u32>,
+        preview_stain_ids: [u32; 2],
+    ) -> Self {
+        Self {
+            set,
+            active_slot: slot_key(active_slot).to_string(),
+            preview_item_id,
+            preview_stain_ids,
+            saved_at: now_millis(),
+        }
+    }
+
+    pub fn active_slot(&self) -> Option<EquipSlot> {
+        ALL_SLOTS
+            .iter()
+            .copied()
+            .find(|s| slot_key(*s) == self.active_slot)
+    }
+
+    /// 内容哈希 (不含保存时间)，用于跳过内容未变的自动保存
+    pub fn content_hash(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        // slots 为 HashMap，序列化顺序不稳定，先按槽位排序
+        let mut slots: Vec<_> = self.set.slots.iter().collect();
+        slots.sort_by_key(|(key, _)| *key);
+        for (key, slot) in slots {
+            (key, slot.item_id, slot.stain_ids, &slot.note).hash(&mut hasher);
+        }
+        (&self.set.name, &self.set.description, &self.active_slot).hash(&mut hasher);
+        (self.preview_item_id, self.preview_stain_ids).hash(&mut hasher);
+        if let Some(view) = &self.set.view {
+            serde_json::to_string(view)
+                .unwrap_or_default()
+                .hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+fn recovery_path() -> PathBuf {
+    crate::config::data_root().join("glamour_recovery.json")
+}
+
+/// 写入恢复文件 (不修改组合本身的保存状态)
+pub fn save_recovery(recovery: &GlamourRecovery) -> Result<(), String> {
+    let json = serde_json::to_string(recovery).map_err(|e| format!("序列化失败: {}", e))?;
+    fs::write(recovery_path(), json).map_err(|e| format!("写入失败: {}", e))
+}
+
+pub fn load_recovery() -> Option<GlamourRecovery> {
+    let content = fs::read_to_string(recovery_path()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn delete_recovery() {
+    let path = recovery_path();
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            eprintln!("删除恢复文件失败: {}", e);
+        }
+    }
+}
+
+/// 启动时检查恢复文件: 对应组合仍存在且自动保存晚于其最后一次保存时返回 (组合下标, 恢复内容)，
+/// 否则删除过期的恢复文件
+pub fn pending_recovery(sets: &[GlamourSet]) -> Option<(usize, GlamourRecovery)> {
+    let recovery = load_recovery()?;
+    let idx = sets.iter().position(|s| s.id == recovery.set.id);
+    match idx {
+        Some(idx) if recovery.saved_at > sets[idx].modified_at => Some((idx, recovery)),
+        _ => {
+            delete_recovery();
+            None
+        }
+    }
+}
+
+/// 毫秒时间戳距今的大致时长 (如 "5 分钟前")
+pub fn elapsed_text(millis: u64) -> String {
+    let secs = now_millis().saturating_sub(millis) / 1000;
+    if secs < 60 {
+        "刚刚".to_string()
+    } else if secs < 3600 {
+        format!("{} 分钟前", secs / 60)
+    } else if secs < 86400 {
+        format!("{} 小时前", secs / 3600)
+    } else {
+        format!("{} 天前", secs / 86400)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glamour(id: &str, slots: &[(EquipSlot, u32, [u32; 2])]) -> GlamourSet {
+        let mut set = GlamourSet::new(id);
+        set.id = id.to_string();
+        for &(slot, item_id, stain_ids) in slots {
+            set.set_slot(slot, item_id, stain_ids);
+        }
+        set
+    }
+
+    fn full_gear(id: &str, item_base: u32, stain_ids: [u32; 2]) -> GlamourSet {
+        let slots: Vec<(EquipSlot, u32, [u32; 2])> = GEAR_SLOTS
+            .iter()
+            .enumerate()
+            .map(|(i, &slot)| (slot, item_base + i as u32, stain_ids))
+            .collect();
+        glamour(id, &slots)
+    }
+
+    #[test]
+    fn stats_count_dyes_once_per_set() {
+        let sets = [
+            // 两个通道和多个槽位都用染料 5，只计一次
+            full_gear("a", 100, [5, 5]),
+            full_gear("b", 200, [5, 7]),
+            glamour("c", &[(EquipSlot::Head, 100, [7, 0])]),
+        ];
+        let stats = compute_glamour_stats(&sets);
+        assert_eq!(stats.dye_usage, [(5, 2), (7, 2)]);
+    }
+
+    #[test]
+    fn stats_rank_items_per_slot() {
+        let sets = [
+            glamour(
+                "a",
+                &[(EquipSlot::Head, 1, [0, 0]), (EquipSlot::Ring, 9, [0, 0])],
+            ),
+            glamour("b", &[(EquipSlot::Head, 2, [0, 0])]),
+            glamour("c", &[(EquipSlot::Head, 2, [0, 0])]),
+            glamour("d", &[(EquipSlot::Head, 3, [0, 0])]),
+            glamour("e", &[(EquipSlot::Head, 4, [0, 0])]),
+        ];
+        let stats = compute_glamour_stats(&sets);
+        assert!(stats.dye_usage.is_empty());
+        // 按组合数降序、同数按 ID 升序，每个槽位最多列出 TOP_ITEMS_PER_SLOT 件
+        assert_eq!(
+            stats.slot_items,
+            [
+                (EquipSlot::Head, vec![(2, 2), (1, 1), (3, 1)]),
+                (EquipSlot::Ring, vec![(9, 1)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn stats_count_incomplete_gear_only() {
+        let mut with_accessory = full_gear("a", 100, [0, 0]);
+        with_accessory.set_slot(EquipSlot::Earrings, 500, [0, 0]);
+        let sets = [
+            with_accessory,
+            full_gear("b", 200, [0, 0]),
+            glamour("c", &[(EquipSlot::Earrings, 500, [0, 0])]),
+            glamour("d", &[]),
+        ];
+        assert_eq!(compute_glamour_stats(&sets).incomplete_sets, 2);
+        assert_eq!(compute_glamour_stats(&[]).incomplete_sets, 0);
+    }
+
+    #[test]
+    fn item_usage_index_lists_each_set_once() {
+        let sets = [
+            glamour(
+                "a",
+                &[
+                    (EquipSlot::Ring, 9, [0, 0]),
+                    (EquipSlot::Earrings, 9, [0, 0]),
+                ],
+            ),
+            glamour("b", &[(EquipSlot::Head, 1, [0, 0])]),
+            glamour("c", &[(EquipSlot::Ring, 9, [0, 0])]),
+        ];
+        let index = build_item_usage_index(&sets);
+        assert_eq!(index[&9], [0, 2]);
+        assert_eq!(index[&1], [1]);
+        assert!(!index.contains_key(&2));
+    }
+
+    #[test]
+    fn filter_matches_dye_or_item() {
+        let set = glamour("a", &[(EquipSlot::Body, 42, [0, 7])]);
+        assert!(GlamourFilter::Dye(7).matches(&set));
+        assert!(!GlamourFilter::Dye(8).matches(&set));
+        assert!(GlamourFilter::Item(42).matches(&set));
+        assert!(!GlamourFilter::Item(43).matches(&set));
+    }
+
+    #[test]
+    fn same_items_ignores_dyes() {
+        let a = glamour(
+            "a",
+            &[(EquipSlot::Head, 1, [5, 0]), (EquipSlot::Body, 2, [0, 0])],
+        );
+        let b = glamour(
+            "b",
+            &[(EquipSlot::Head, 1, [7, 0]), (EquipSlot::Body, 2, [0, 0])],
+        );
+        let c = glamour(
+            "c",
+            &[(EquipSlot::Head, 1, [5, 0]), (EquipSlot::Body, 2, [0, 0])],
+        );
+        assert!(a.same_items(&b));
+        assert!(!a.same_items_and_dyes(&b));
+        assert!(a.same_items_and_dyes(&c));
+    }
+
+    #[test]
+    fn same_items_requires_identical_slots() {
+        let a = glamour("a", &[(EquipSlot::Head, 1, [0, 0])]);
+        let extra_slot = glamour(
+            "b",
+            &[(EquipSlot::Head, 1, [0, 0]), (EquipSlot::Ring, 9, [0, 0])],
+        );
+        let other_slot = glamour("c", &[(EquipSlot::Body, 1, [0, 0])]);
+        let other_item = glamour("d", &[(EquipSlot::Head, 2, [0, 0])]);
+        for other in [&extra_slot, &other_slot, &other_item] {
+            assert!(!a.same_items(other), "{}", other.id);
+            assert!(!other.same_items(&a), "{}", other.id);
+            assert!(!a.same_items_and_dyes(other), "{}", other.id);
+        }
+    }
+
+    #[test]
+    fn duplicates_list_dye_matches_first() {
+        let set = glamour("new", &[(EquipSlot::Head, 1, [5, 0])]);
+        let saved = [
+            glamour("items-only", &[(EquipSlot::Head, 1, [7, 0])]),
+            glamour("exact", &[(EquipSlot::Head, 1, [5, 0])]),
+            glamour("different", &[(EquipSlot::Head, 2, [5, 0])]),
+            glamour("new", &[(EquipSlot::Head, 1, [5, 0])]),
+        ];
+        let dup = find_duplicate(&set, &saved).unwrap();
+        assert_eq!(dup.set_ids, ["exact", "items-only"]);
+        assert!(dup.with_dyes);
+
+        let dup = find_duplicate(&set, &saved[..1]).unwrap();
+        assert_eq!(dup.set_ids, ["items-only"]);
+        assert!(!dup.with_dyes);
+
+        assert!(find_duplicate(&set, &saved[2..]).is_none());
+        assert!(find_duplicate(&glamour("empty", &[]), &[glamour("x", &[])]).is_none());
+    }
+
+    fn gear(row_id: u32, equip_slot_category: u8) -> GameItem {
+        GameItem {
+            equip_slot_category,
+            model_main: 1,
+            ..GameItem::named(row_id, "gear")
+        }
+    }
+
+    #[test]
+    fn from_items_keeps_first_item_per_slot() {
+        let head = gear(1, 3);
+        let other_head = gear(2, 3);
+        let body = gear(3, 4);
+        let material = GameItem::named(4, "material");
+        let set = GlamourSet::from_items(
+            "quick",
+            [
+                (&head, [5, 0]),
+                (&other_head, [0, 0]),
+                (&body, [0, 0]),
+                (&material, [0, 0]),
+            ],
+        );
+        assert_eq!(set.name, "quick");
+        assert_eq!(set.slots.len(), 2);
+        let head_slot = set.get_slot(EquipSlot::Head).unwrap();
+        assert_eq!((head_slot.item_id, head_slot.stain_ids), (1, [5, 0]));
+        assert_eq!(set.get_slot(EquipSlot::Body).unwrap().item_id, 3);
+    }
+
+    #[test]
+    fn quick_added_set_is_checked_against_saved_sets() {
+        let head = gear(1, 3);
+        let body = gear(3, 4);
+        let saved = [glamour(
+            "saved",
+            &[(EquipSlot::Head, 1, [7, 0]), (EquipSlot::Body, 3, [0, 0])],
+        )];
+        let quick = GlamourSet::from_items("quick", [(&head, [5, 0]), (&body, [0, 0])]);
+        let dup = find_duplicate(&quick, &saved).unwrap();
+        assert_eq!(dup.set_ids, ["saved"]);
+        assert!(!dup.with_dyes);
+
+        let quick = GlamourSet::from_items("quick", [(&head, [7, 0]), (&body, [0, 0])]);
+        assert!(find_duplicate(&quick, &saved).unwrap().with_dyes);
+    }
+}
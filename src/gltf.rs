@@ -0,0 +1,525 @@
+//! glTF 2.0 导出: 以 GLB (单文件二进制) 形式写出网格与嵌入的贴图
+//!
+//! 只写出预览所需的子集: 位置/法线/UV/切线/顶点色/索引，每个图元一个材质
+//! (diffuse 为 baseColor，法线贴图为 normalTexture，mask 没有对应的标准槽位，
+//! 记录在材质 extras 的 maskTexture 中)，不包含骨骼与动画。
+//! 法线与 mask 贴图按游戏原样写出，不做通道转换。
+
+use std::io::Cursor;
+
+use serde_json::{json, Value};
+use tomestone_render::{TextureData, Vertex};
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const COMPONENT_FLOAT: u32 = 5126;
+const COMPONENT_UNSIGNED_SHORT: u32 = 5123;
+const TARGET_ARRAY_BUFFER: u32 = 34962;
+const TARGET_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// 导出的单个图元 (一个网格 + 它的贴图)
+pub struct ExportPrimitive<'a> {
+    pub vertices: &'a [Vertex],
+    pub indices: &'a [u16],
+    pub diffuse: Option<&'a TextureData>,
+    pub normal: Option<&'a TextureData>,
+    pub mask: Option<&'a TextureData>,
+}
+
+/// 逐个节点累积 glTF 内容，最后一次性打包为 GLB
+#[derive(Default)]
+pub struct GlbBuilder {
+    bin: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    images: Vec<Value>,
+    textures: Vec<Value>,
+    materials: Vec<Value>,
+    meshes: Vec<Value>,
+    nodes: Vec<Value>,
+}
+
+impl GlbBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一个命名节点，节点下的网格包含给定的全部图元 (空图元会被跳过)
+    pub fn add_node(
+        &mut self,
+        name: &str,
+        primitives: &[ExportPrimitive<'_>],
+    ) -> Result<(), String> {
+        let mut gltf_primitives = Vec::new();
+        for prim in primitives {
+            if prim.vertices.is_empty() || prim.indices.is_empty() {
+                continue;
+            }
+            let mut primitive = self.add_geometry(prim.vertices, prim.indices);
+            if let Some(material) = self.material_for(prim)? {
+                primitive["material"] = json!(material);
+            }
+            gltf_primitives.push(primitive);
+        }
+        if gltf_primitives.is_empty() {
+            return Ok(());
+        }
+
+        let mesh_idx = self.meshes.len();
+        self.meshes
+            .push(json!({ "name": name, "primitives": gltf_primitives }));
+        self.nodes.push(json!({ "name": name, "mesh": mesh_idx }));
+        Ok(())
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// 打包为 GLB 字节流，`extras` 写入场景的 extras 字段
+    pub fn finish(self, scene_name: &str, extras: Value) -> Result<Vec<u8>, String> {
+        let node_indices: Vec<usize> = (0..self.nodes.len()).collect();
+        let mut root = json!({
+            "asset": { "version": "2.0", "generator": "tomestone" },
+            "scene": 0,
+            "scenes": [{ "name": scene_name, "nodes": node_indices, "extras": extras }],
+            "nodes": self.nodes,
+            "meshes": self.meshes,
+            "accessors": self.accessors,
+            "bufferViews": self.buffer_views,
+            "buffers": [{ "byteLength": self.bin.len() }],
+        });
+        if !self.materials.is_empty() {
+            root["materials"] = json!(self.materials);
+            root["textures"] = json!(self.textures);
+            root["images"] = json!(self.images);
+            root["samplers"] = json!([{ "magFilter": 9729, "minFilter": 9987 }]);
+        }
+
+        let mut json_bytes = serde_json::to_vec(&root).map_err(|e| e.to_string())?;
+        pad_to_4(&mut json_bytes, b' ');
+        let mut bin = self.bin;
+        pad_to_4(&mut bin, 0);
+
+        let total_len = 12 + 8 + json_bytes.len() + 8 + bin.len();
+        let total_len = u32::try_from(total_len).map_err(|_| "导出文件超过 4GB".to_string())?;
+        let mut out = Vec::with_capacity(total_len as usize);
+        out.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+        out.extend_from_slice(&2u32.to_le_bytes());
+        out.extend_from_slice(&total_len.to_le_bytes());
+        out.extend_from_slice(&(json_bytes.len() as u32).to_le_bytes());
+        out.extend_from_slice(&CHUNK_JSON.to_le_bytes());
+        out.extend_from_slice(&json_bytes);
+        out.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        out.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+        out.extend_from_slice(&bin);
+        Ok(out)
+    }
+
+    /// 写入顶点与索引数据，返回 glTF primitive (不含材质)
+    fn add_geometry(&mut self, vertices: &[Vertex], indices: &[u16]) -> Value {
+        let mut positions = Vec::with_capacity(vertices.len() * 12);
+        let mut normals = Vec::with_capacity(vertices.len() * 12);
+        let mut uvs = Vec::with_capacity(vertices.len() * 8);
+        let mut tangents = Vec::with_capacity(vertices.len() * 16);
+        let mut colors = Vec::with_capacity(vertices.len() * 16);
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for v in vertices {
+            for (i, &p) in v.position.iter().enumerate() {
+                min[i] = min[i].min(p);
+                max[i] = max[i].max(p);
+            }
+            extend_f32(&mut positions, &v.position);
+            extend_f32(&mut normals, &normalize_or_up(v.normal));
+            extend_f32(&mut uvs, &v.uv);
+            extend_f32(&mut tangents, &normalize_tangent(v.tangent));
+            extend_f32(&mut colors, &v.color.map(|c| c.clamp(0.0, 1.0)));
+        }
+        let index_bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+
+        let count = vertices.len();
+        let position = self.add_accessor(
+            &positions,
+            TARGET_ARRAY_BUFFER,
+            count,
+            "VEC3",
+            COMPONENT_FLOAT,
+        );
+        self.accessors[position]["min"] = json!(min);
+        self.accessors[position]["max"] = json!(max);
+        let normal = self.add_accessor(
+            &normals,
+            TARGET_ARRAY_BUFFER,
+            count,
+            "VEC3",
+            COMPONENT_FLOAT,
+        );
+        let uv = self.add_accessor(&uvs, TARGET_ARRAY_BUFFER, count, "VEC2", COMPONENT_FLOAT);
+        let tangent = self.add_accessor(
+            &tangents,
+            TARGET_ARRAY_BUFFER,
+            count,
+            "VEC4",
+            COMPONENT_FLOAT,
+        );
+        let color = self.add_accessor(&colors, TARGET_ARRAY_BUFFER, count, "VEC4", COMPONENT_FLOAT);
+        let index = self.add_accessor(
+            &index_bytes,
+            TARGET_ELEMENT_ARRAY_BUFFER,
+            indices.len(),
+            "SCALAR",
+            COMPONENT_UNSIGNED_SHORT,
+        );
+
+        json!({
+            "attributes": {
+                "POSITION": position,
+                "NORMAL": normal,
+                "TEXCOORD_0": uv,
+                "TANGENT": tangent,
+                "COLOR_0": color,
+            },
+            "indices": index,
+        })
+    }
+
+    fn add_accessor(
+        &mut self,
+        bytes: &[u8],
+        target: u32,
+        count: usize,
+        kind: &str,
+        component_type: u32,
+    ) -> usize {
+        let mut view = self.push_buffer_view(bytes);
+        view["target"] = json!(target);
+        let view_idx = self.buffer_views.len();
+        self.buffer_views.push(view);
+        self.accessors.push(json!({
+            "bufferView": view_idx,
+            "componentType": component_type,
+            "count": count,
+            "type": kind,
+        }));
+        self.accessors.len() - 1
+    }
+
+    /// 追加数据到二进制块 (4 字节对齐)，返回 bufferView 描述
+    fn push_buffer_view(&mut self, bytes: &[u8]) -> Value {
+        pad_to_4(&mut self.bin, 0);
+        let offset = self.bin.len();
+        self.bin.extend_from_slice(bytes);
+        json!({ "buffer": 0, "byteOffset": offset, "byteLength": bytes.len() })
+    }
+
+    /// 为图元的贴图创建材质，没有任何贴图时返回 None
+    fn material_for(&mut self, prim: &ExportPrimitive<'_>) -> Result<Option<usize>, String> {
+        if prim.diffuse.is_none() && prim.normal.is_none() && prim.mask.is_none() {
+            return Ok(None);
+        }
+        let mut material = json!({
+            "pbrMetallicRoughness": {
+                "metallicFactor": 0.0,
+                "roughnessFactor": 1.0,
+            },
+            "alphaMode": "OPAQUE",
+            "doubleSided": true,
+        });
+        if let Some(diffuse) = prim.diffuse {
+            let texture = self.add_texture(diffuse)?;
+            material["pbrMetallicRoughness"]["baseColorTexture"] = json!({ "index": texture });
+        }
+        if let Some(normal) = prim.normal {
+            let texture = self.add_texture(normal)?;
+            material["normalTexture"] = json!({ "index": texture });
+        }
+        if let Some(mask) = prim.mask {
+            let texture = self.add_texture(mask)?;
+            material["extras"] = json!({ "maskTexture": { "index": texture } });
+        }
+        self.materials.push(material);
+        Ok(Some(self.materials.len() - 1))
+    }
+
+    /// 贴图编码为 PNG 嵌入二进制块，返回对应的 texture 下标
+    fn add_texture(&mut self, texture: &TextureData) -> Result<usize, String> {
+        let image = image::RgbaImage::from_raw(
+            texture.width,
+            texture.height,
+            texture.rgba.as_ref().clone(),
+        )
+        .ok_or_else(|| "贴图尺寸与数据长度不符".to_string())?;
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .map_err(|e| format!("PNG 编码失败: {}", e))?;
+
+        let view = self.push_buffer_view(&png);
+        let view_idx = self.buffer_views.len();
+        self.buffer_views.push(view);
+        let image_idx = self.images.len();
+        self.images
+            .push(json!({ "bufferView": view_idx, "mimeType": "image/png" }));
+        self.textures
+            .push(json!({ "source": image_idx, "sampler": 0 }));
+        Ok(self.textures.len() - 1)
+    }
+}
+
+fn extend_f32(out: &mut Vec<u8>, values: &[f32]) {
+    for v in values {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+/// glTF 要求法线为单位向量，退化法线以 +Y 代替
+fn normalize_or_up(n: [f32; 3]) -> [f32; 3] {
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if len > 1e-6 {
+        [n[0] / len, n[1] / len, n[2] / len]
+    } else {
+        [0.0, 1.0, 0.0]
+    }
+}
+
+/// glTF 要求切线 xyz 为单位向量、w 为 ±1，退化切线以 +X 代替
+fn normalize_tangent(t: [f32; 4]) -> [f32; 4] {
+    let len = (t[0] * t[0] + t[1] * t[1] + t[2] * t[2]).sqrt();
+    let w = if t[3] < 0.0 { -1.0 } else { 1.0 };
+    if len > 1e-6 {
+        [t[0] / len, t[1] / len, t[2] / len, w]
+    } else {
+        [1.0, 0.0, 0.0, w]
+    }
+}
+
+fn pad_to_4(bytes: &mut Vec<u8>, fill: u8) {
+    while bytes.len() % 4 != 0 {
+        bytes.push(fill);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// 解析后的 GLB: JSON 块与二进制块
+    fn parse_glb(glb: &[u8]) -> (Value, &[u8]) {
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap());
+        assert_eq!(u32_at(0), GLB_MAGIC);
+        assert_eq!(u32_at(4), 2);
+        assert_eq!(u32_at(8) as usize, glb.len());
+
+        let json_len = u32_at(12) as usize;
+        assert_eq!(u32_at(16), CHUNK_JSON);
+        assert_eq!(json_len % 4, 0);
+        let json: Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+
+        let bin_start = 20 + json_len;
+        let bin_len = u32_at(bin_start) as usize;
+        assert_eq!(u32_at(bin_start + 4), CHUNK_BIN);
+        assert_eq!(bin_len % 4, 0);
+        assert_eq!(bin_start + 8 + bin_len, glb.len());
+        (json, &glb[bin_start + 8..])
+    }
+
+    /// 按 accessor 取出它引用的字节
+    fn accessor_bytes<'a>(json: &Value, bin: &'a [u8], accessor: &Value) -> &'a [u8] {
+        let view = &json["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize];
+        let offset = view["byteOffset"].as_u64().unwrap() as usize;
+        let len = view["byteLength"].as_u64().unwrap() as usize;
+        assert_eq!(offset % 4, 0);
+        &bin[offset..offset + len]
+    }
+
+    fn read_f32s(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect()
+    }
+
+    fn vertex(position: [f32; 3], normal: [f32; 3]) -> Vertex {
+        Vertex {
+            position,
+            normal,
+            uv: [position[0], position[1]],
+            color: [1.5, 0.5, -0.5, 1.0],
+            tangent: [2.0, 0.0, 0.0, -0.3],
+        }
+    }
+
+    fn texture(width: u32, height: u32, fill: [u8; 4]) -> TextureData {
+        TextureData {
+            rgba: Arc::new(fill.repeat((width * height) as usize)),
+            width,
+            height,
+        }
+    }
+
+    /// 不带贴图的图元
+    fn primitive<'a>(vertices: &'a [Vertex], indices: &'a [u16]) -> ExportPrimitive<'a> {
+        ExportPrimitive {
+            vertices,
+            indices,
+            diffuse: None,
+            normal: None,
+            mask: None,
+        }
+    }
+
+    #[test]
+    fn geometry_round_trips() {
+        let vertices = [
+            vertex([0.0, 0.0, 0.0], [0.0, 0.0, 2.0]),
+            vertex([1.0, 0.0, -1.0], [0.0, 0.0, 0.0]),
+            vertex([0.0, 2.0, 0.5], [3.0, 4.0, 0.0]),
+        ];
+        // 奇数个 u16 索引，后续数据必须重新对齐到 4 字节
+        let indices = [0u16, 1, 2];
+        let second = [vertex([5.0, 5.0, 5.0], [0.0, 1.0, 0.0]); 3];
+
+        let mut builder = GlbBuilder::new();
+        builder
+            .add_node(
+                "top",
+                &[
+                    primitive(&vertices, &indices),
+                    primitive(&[], &[]),
+                    primitive(&second, &indices),
+                ],
+            )
+            .unwrap();
+        // 全部图元为空的节点不写出
+        builder.add_node("empty", &[primitive(&[], &[])]).unwrap();
+        assert_eq!(builder.node_count(), 1);
+
+        let glb = builder.finish("scene", json!({ "item": "coat" })).unwrap();
+        let (json, bin) = parse_glb(&glb);
+        assert_eq!(json["asset"]["version"], "2.0");
+        assert_eq!(json["scenes"][0]["nodes"], json!([0]));
+        assert_eq!(json["scenes"][0]["extras"]["item"], "coat");
+        assert_eq!(json["nodes"][0]["name"], "top");
+        assert!(json.get("materials").is_none());
+        assert_eq!(
+            json["buffers"][0]["byteLength"].as_u64().unwrap() as usize,
+            bin.len()
+        );
+
+        let primitives = json["meshes"][0]["primitives"].as_array().unwrap();
+        assert_eq!(primitives.len(), 2);
+        let attribute = |name: &str| {
+            let accessor =
+                &json["accessors"][primitives[0]["attributes"][name].as_u64().unwrap() as usize];
+            assert_eq!(accessor["count"], 3);
+            assert_eq!(accessor["componentType"], COMPONENT_FLOAT);
+            read_f32s(accessor_bytes(&json, bin, accessor))
+        };
+
+        let position =
+            &json["accessors"][primitives[0]["attributes"]["POSITION"].as_u64().unwrap() as usize];
+        assert_eq!(position["min"], json!([0.0, 0.0, -1.0]));
+        assert_eq!(position["max"], json!([1.0, 2.0, 0.5]));
+        assert_eq!(
+            attribute("POSITION"),
+            [0.0, 0.0, 0.0, 1.0, 0.0, -1.0, 0.0, 2.0, 0.5]
+        );
+        // 法线归一化，退化法线换成 +Y
+        assert_eq!(
+            attribute("NORMAL"),
+            [0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.6, 0.8, 0.0]
+        );
+        assert_eq!(attribute("TEXCOORD_0"), [0.0, 0.0, 1.0, 0.0, 0.0, 2.0]);
+        assert_eq!(attribute("TANGENT")[..4], [1.0, 0.0, 0.0, -1.0]);
+        assert_eq!(attribute("COLOR_0")[..4], [1.0, 0.5, 0.0, 1.0]);
+
+        for primitive in primitives {
+            let accessor = &json["accessors"][primitive["indices"].as_u64().unwrap() as usize];
+            assert_eq!(accessor["componentType"], COMPONENT_UNSIGNED_SHORT);
+            assert_eq!(accessor["count"], 3);
+            let bytes = accessor_bytes(&json, bin, accessor);
+            let read: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                .collect();
+            assert_eq!(read, indices);
+        }
+        let second_position =
+            &json["accessors"][primitives[1]["attributes"]["POSITION"].as_u64().unwrap() as usize];
+        assert_eq!(
+            read_f32s(accessor_bytes(&json, bin, second_position))[..3],
+            [5.0, 5.0, 5.0]
+        );
+    }
+
+    #[test]
+    fn textures_are_embedded_as_png() {
+        let vertices = [vertex([0.0; 3], [0.0, 1.0, 0.0]); 3];
+        let diffuse = texture(2, 2, [255, 0, 0, 255]);
+        let normal = texture(1, 1, [128, 128, 255, 255]);
+        let mask = texture(4, 2, [0, 255, 0, 255]);
+        let mut builder = GlbBuilder::new();
+        builder
+            .add_node(
+                "met",
+                &[ExportPrimitive {
+                    vertices: &vertices,
+                    indices: &[0, 1, 2],
+                    diffuse: Some(&diffuse),
+                    normal: Some(&normal),
+                    mask: Some(&mask),
+                }],
+            )
+            .unwrap();
+        let glb = builder.finish("scene", Value::Null).unwrap();
+        let (json, bin) = parse_glb(&glb);
+
+        let material = &json["materials"][json["meshes"][0]["primitives"][0]["material"]
+            .as_u64()
+            .unwrap() as usize];
+        let decode = |texture: &Value| {
+            let texture = &json["textures"][texture["index"].as_u64().unwrap() as usize];
+            let image = &json["images"][texture["source"].as_u64().unwrap() as usize];
+            assert_eq!(image["mimeType"], "image/png");
+            image::load_from_memory(accessor_bytes(&json, bin, image))
+                .unwrap()
+                .to_rgba8()
+        };
+        let base = decode(&material["pbrMetallicRoughness"]["baseColorTexture"]);
+        assert_eq!(base.dimensions(), (2, 2));
+        assert_eq!(base.as_raw().as_slice(), diffuse.rgba.as_slice());
+        assert_eq!(
+            decode(&material["normalTexture"]).as_raw().as_slice(),
+            normal.rgba.as_slice()
+        );
+        let mask_image = decode(&material["extras"]["maskTexture"]);
+        assert_eq!(mask_image.dimensions(), (4, 2));
+    }
+
+    #[test]
+    fn mismatched_texture_size_is_an_error() {
+        let vertices = [vertex([0.0; 3], [0.0, 1.0, 0.0]); 3];
+        let broken = TextureData {
+            rgba: Arc::new(vec![0; 3]),
+            width: 2,
+            height: 2,
+        };
+        let mut builder = GlbBuilder::new();
+        let result = builder.add_node(
+            "glv",
+            &[ExportPrimitive {
+                vertices: &vertices,
+                indices: &[0, 1, 2],
+                diffuse: Some(&broken),
+                normal: None,
+                mask: None,
+            }],
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! 图形后端选择: 配置 / 命令行指定后端，启动前探测可用适配器并回退到 GL
+
+use serde::{Deserialize, Serialize};
+
+/// 图形后端 (修改后重启生效)
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsBackend {
+    /// 由 wgpu 自动选择 (Vulkan/DX12/Metal，均不可用时为 GL)
+    #[default]
+    Auto,
+    Vulkan,
+    Dx12,
+    Metal,
+    Gl,
+}
+
+pub const GRAPHICS_BACKENDS: [GraphicsBackend; 5] = [
+    GraphicsBackend::Auto,
+    GraphicsBackend::Vulkan,
+    GraphicsBackend::Dx12,
+    GraphicsBackend::Metal,
+    GraphicsBackend::Gl,
+];
+
+impl GraphicsBackend {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Auto => "自动",
+            Self::Vulkan => "Vulkan",
+            Self::Dx12 => "DirectX 12",
+            Self::Metal => "Metal",
+            Self::Gl => "OpenGL",
+        }
+    }
+
+    /// 命令行参数名 (--backend vulkan/dx12/metal/gl/auto)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "vulkan" | "vk" => Some(Self::Vulkan),
+            "dx12" | "d3d12" => Some(Self::Dx12),
+            "metal" => Some(Self::Metal),
+            "gl" | "opengl" | "gles" => Some(Self::Gl),
+            _ => None,
+        }
+    }
+
+    pub fn backends(self) -> wgpu::Backends {
+        match self {
+            Self::Auto => wgpu::Backends::PRIMARY | wgpu::Backends::GL,
+            Self::Vulkan => wgpu::Backends::VULKAN,
+            Self::Dx12 => wgpu::Backends::DX12,
+            Self::Metal => wgpu::Backends::METAL,
+            Self::Gl => wgpu::Backends::GL,
+        }
+    }
+}
+
+/// 从命令行参数中读取 `--backend <name>` 或 `--backend=<name>`
+pub fn backend_from_args(args: impl IntoIterator<Item = String>) -> Option<GraphicsBackend> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = if arg == "--backend" {
+            args.next()
+        } else {
+            arg.strip_prefix("--backend=").map(str::to_string)
+        };
+        if let Some(value) = value {
+            let backend = GraphicsBackend::from_name(&value);
+            if backend.is_none() {
+                eprintln!("未知的图形后端: {} (可选 auto/vulkan/dx12/metal/gl)", value);
+            }
+            return backend;
+        }
+    }
+    None
+}
+
+fn has_adapter(backends: wgpu::Backends) -> bool {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends,
+        ..Default::default()
+    });
+    pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).is_ok()
+}
+
+/// 探测指定后端是否有可用适配器，不可用时回退到 GL；均不可用时返回 None
+pub fn select_backends(preferred: GraphicsBackend) -> Option<wgpu::Backends> {
+    let mut candidates = vec![preferred.backends()];
+    if preferred != GraphicsBackend::Gl {
+        candidates.push(wgpu::Backends::GL);
+    }
+    for backends in candidates {
+        if has_adapter(backends) {
+            return Some(backends);
+        }
+        eprintln!("图形后端 {:?} 没有可用的适配器，尝试下一个", backends);
+    }
+    None
+}
+
+/// 依次尝试的启动方式: `Some` 为使用该后端的 wgpu 渲染，`None` 为无 3D 模式 (glow 界面)
+///
+/// 探测到的后端创建窗口失败时再尝试 GL，最后退回无 3D 模式，保证至少能浏览数据
+pub fn launch_attempts(selected: Option<wgpu::Backends>) -> Vec<Option<wgpu::Backends>> {
+    let mut attempts = Vec::new();
+    if let Some(backends) = selected {
+        attempts.push(Some(backends));
+        if backends != wgpu::Backends::GL {
+            attempts.push(Some(wgpu::Backends::GL));
+        }
+    }
+    attempts.push(None);
+    attempts
+}
+
+/// 当前使用的适配器与后端，用于界面显示与自检报告
+pub fn adapter_summary(render_state: Option<&egui_wgpu::RenderState>) -> String {
+    let Some(render_state) = render_state else {
+        return "无 3D 模式 (OpenGL 界面)".to_string();
+    };
+    let info = render_state.adapter.get_info();
+    format!("{:?} · {}", info.backend, info.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launch_falls_back_to_gl_then_no_3d() {
+        assert_eq!(
+            launch_attempts(Some(wgpu::Backends::VULKAN)),
+            vec![Some(wgpu::Backends::VULKAN), Some(wgpu::Backends::GL), None]
+        );
+        assert_eq!(
+            launch_attempts(Some(wgpu::Backends::GL)),
+            vec![Some(wgpu::Backends::GL), None]
+        );
+        // 探测时 GL 也没有适配器，直接以无 3D 模式启动
+        assert_eq!(launch_attempts(None), vec![None]);
+    }
+}
@@ -0,0 +1,152 @@
+//! 界面文本本地化
+//!
+//! 中文表是完整的参考翻译，其他语言缺失的键回退到中文，中文也缺失时直接显示键名。
+//! 游戏数据 (物品名等) 不经过这里，由游戏数据语言决定。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Lang {
+    #[default]
+    Zh,
+    En,
+}
+
+impl Lang {
+    /// 以该语言自身书写的名称，用于语言选择下拉框
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Self::Zh => "中文",
+            Self::En => "English",
+        }
+    }
+}
+
+pub const LANGS: [Lang; 2] = [Lang::Zh, Lang::En];
+
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_lang(lang: Lang) {
+    CURRENT.store(lang as u8, Ordering::Relaxed);
+}
+
+pub fn current_lang() -> Lang {
+    match CURRENT.load(Ordering::Relaxed) {
+        1 => Lang::En,
+        _ => Lang::Zh,
+    }
+}
+
+type Table = HashMap<String, String>;
+
+fn parse_table(name: &str, json: &str) -> Table {
+    serde_json::from_str(json).unwrap_or_else(|e| {
+        eprintln!("翻译表 {} 解析失败: {}", name, e);
+        Table::new()
+    })
+}
+
+fn table(lang: Lang) -> &'static Table {
+    static ZH: OnceLock<Table> = OnceLock::new();
+    static EN: OnceLock<Table> = OnceLock::new();
+    match lang {
+        Lang::Zh => ZH.get_or_init(|| parse_table("zh", include_str!("zh.json"))),
+        Lang::En => EN.get_or_init(|| parse_table("en", include_str!("en.json"))),
+    }
+}
+
+/// 查找当前语言下的文本
+pub fn tr(key: &'static str) -> &'static str {
+    table(current_lang())
+        .get(key)
+        .or_else(|| table(Lang::Zh).get(key))
+        .map(|s| s.as_str())
+        .unwrap_or(key)
+}
+
+/// `tr!("page.browser")` 等价于 `i18n::tr("page.browser")`
+macro_rules! tr {
+    ($key:literal) => {
+        $crate::i18n::tr($key)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// 源码中 `tr!("...")` 调用的全部键 (键, 所在文件)
+    fn keys_in_source(dir: &Path, keys: &mut Vec<(String, String)>) {
+        let pattern = concat!("tr", "!(");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                keys_in_source(&path, keys);
+                continue;
+            }
+            if path.extension().and_then(|e| e.to_str()) != Some("rs") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            for (pos, _) in source.match_indices(pattern) {
+                // 排除 include_str! 等以 tr! 结尾的其他宏
+                let prev = source[..pos].chars().next_back();
+                if prev.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    continue;
+                }
+                let rest = source[pos + pattern.len()..].trim_start();
+                let Some(rest) = rest.strip_prefix('"') else {
+                    continue;
+                };
+                let key = &rest[..rest.find('"').unwrap()];
+                keys.push((key.to_string(), path.display().to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn tables_parse() {
+        for lang in LANGS {
+            assert!(!table(lang).is_empty(), "{:?} 翻译表为空", lang);
+        }
+    }
+
+    #[test]
+    fn every_key_in_source_exists_in_all_tables() {
+        let mut keys = Vec::new();
+        keys_in_source(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+            &mut keys,
+        );
+        assert!(!keys.is_empty());
+        let missing: Vec<String> = keys
+            .iter()
+            .flat_map(|(key, file)| {
+                LANGS
+                    .into_iter()
+                    .filter(|&lang| !table(lang).contains_key(key))
+                    .map(move |lang| format!("{} ({:?}, {})", key, lang, file))
+            })
+            .collect();
+        assert!(missing.is_empty(), "翻译表缺少键:\n{}", missing.join("\n"));
+    }
+
+    #[test]
+    fn tables_have_same_keys() {
+        let zh = table(Lang::Zh);
+        for lang in LANGS {
+            let other = table(lang);
+            let mut extra: Vec<&String> = other.keys().filter(|k| !zh.contains_key(*k)).collect();
+            let mut absent: Vec<&String> = zh.keys().filter(|k| !other.contains_key(*k)).collect();
+            extra.sort();
+            absent.sort();
+            assert!(extra.is_empty(), "{:?} 多出的键: {:?}", lang, extra);
+            assert!(absent.is_empty(), "{:?} 缺少的键: {:?}", lang, absent);
+        }
+    }
+}
@@ -0,0 +1,428 @@
+//! 首次启动时自动查找游戏安装目录
+//!
+//! 依次检查注册表卸载项 (国际服与国服)、常见默认安装路径和 Steam 库目录，
+//! 只保留通过 validate_install_dir 校验的目录。非 Windows 平台跳过注册表与盘符路径。
+
+use std::path::{Path, PathBuf};
+
+use crate::game::validate_install_dir;
+
+/// Steam 版 FFXIV 的 AppID 目录名
+const STEAM_FFXIV_DIR: &str = "FINAL FANTASY XIV Online";
+
+/// 检测到的客户端类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClientKind {
+    /// 国际服 (Square Enix)
+    International,
+    /// 国服 (盛趣 / WeGame)
+    China,
+    /// Steam 版国际服
+    Steam,
+}
+
+impl ClientKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ClientKind::International => tr!("setup.client_international"),
+            ClientKind::China => tr!("setup.client_china"),
+            ClientKind::Steam => tr!("setup.client_steam"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InstallCandidate {
+    pub path: PathBuf,
+    pub kind: ClientKind,
+}
+
+/// 扫描全部已知位置，返回去重后通过校验的安装目录
+pub fn detect_installs() -> Vec<InstallCandidate> {
+    let mut raw: Vec<InstallCandidate> = Vec::new();
+    raw.extend(registry_candidates());
+    raw.extend(default_path_candidates());
+    raw.extend(steam_candidates());
+
+    let mut found: Vec<InstallCandidate> = Vec::new();
+    for candidate in raw {
+        let path = install_root(&candidate.path);
+        if validate_install_dir(&path).is_err() {
+            continue;
+        }
+        let key = path_key(&path);
+        if found.iter().any(|c| path_key(&c.path) == key) {
+            continue;
+        }
+        found.push(InstallCandidate {
+            path,
+            kind: candidate.kind,
+        });
+    }
+    found
+}
+
+/// 注册表或快捷方式常指向 boot/game 子目录，统一换成安装根目录
+fn install_root(path: &Path) -> PathBuf {
+    let is_sub = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.eq_ignore_ascii_case("boot") || n.eq_ignore_ascii_case("game"));
+    match path.parent() {
+        Some(parent) if is_sub => parent.to_path_buf(),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// 去重用的路径键: Windows 路径不区分大小写，忽略末尾分隔符
+fn path_key(path: &Path) -> String {
+    let s = path.to_string_lossy();
+    let s = s.trim_end_matches(['\\', '/']);
+    if cfg!(windows) {
+        s.to_lowercase().replace('/', "\\")
+    } else {
+        s.to_string()
+    }
+}
+
+/// 按卸载项显示名判断客户端类型，不是 FFXIV 时返回 None
+fn client_kind_from_display_name(name: &str) -> Option<ClientKind> {
+    let lower = name.to_lowercase();
+    if lower.contains("最终幻想") {
+        Some(ClientKind::China)
+    } else if lower.contains("final fantasy xiv") {
+        Some(ClientKind::International)
+    } else {
+        None
+    }
+}
+
+/// 解析 `reg query <Uninstall> /s` 的输出，返回 FFXIV 卸载项的 (客户端类型, 安装目录)
+///
+/// 输出格式为键路径行 (HKEY_ 开头) 后跟缩进的 `名称    类型    值` 行，
+/// 缺少 InstallLocation 的项用 DisplayIcon 所在目录代替。
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn parse_reg_uninstall(output: &str) -> Vec<(ClientKind, PathBuf)> {
+    #[derive(Default)]
+    struct Entry {
+        display_name: Option<String>,
+        install_location: Option<String>,
+        display_icon: Option<String>,
+    }
+
+    fn finish(entry: Entry, out: &mut Vec<(ClientKind, PathBuf)>) {
+        let Some(kind) = entry
+            .display_name
+            .as_deref()
+            .and_then(client_kind_from_display_name)
+        else {
+            return;
+        };
+        let location = entry
+            .install_location
+            .filter(|l| !l.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                // DisplayIcon 形如 "C:\...\boot\ffxivboot.exe",0；按反斜杠拆分，
+                // 不依赖当前平台的路径规则
+                let icon = entry.display_icon?;
+                let icon = icon.split(',').next()?.trim_matches('"');
+                icon.rsplit_once(['\\', '/'])
+                    .map(|(dir, _)| PathBuf::from(dir))
+            });
+        if let Some(location) = location {
+            out.push((kind, location));
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut current: Option<Entry> = None;
+    for line in output.lines() {
+        if line.starts_with("HKEY_") {
+            if let Some(entry) = current.take() {
+                finish(entry, &mut out);
+            }
+            current = Some(Entry::default());
+            continue;
+        }
+        let Some(entry) = current.as_mut() else {
+            continue;
+        };
+        // 值行: 名称与类型、类型与值之间都以 4 个空格分隔
+        let Some(type_pos) = line.find("    REG_") else {
+            continue;
+        };
+        let name = line[..type_pos].trim();
+        let rest = &line[type_pos + 4..];
+        let value = rest.split_once("    ").map_or("", |(_, v)| v.trim());
+        let value = Some(value.to_string());
+        if name.eq_ignore_ascii_case("DisplayName") {
+            entry.display_name = value;
+        } else if name.eq_ignore_ascii_case("InstallLocation") {
+            entry.install_location = value;
+        } else if name.eq_ignore_ascii_case("DisplayIcon") {
+            entry.display_icon = value;
+        }
+    }
+    if let Some(entry) = current {
+        finish(entry, &mut out);
+    }
+    out
+}
+
+/// 从 `reg query` 输出中取单个 REG_SZ 值 (如 Steam 的 SteamPath)
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_reg_value(output: &str, name: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let type_pos = line.find("    REG_")?;
+        if !line[..type_pos].trim().eq_ignore_ascii_case(name) {
+            return None;
+        }
+        let (_, value) = line[type_pos + 4..].split_once("    ")?;
+        Some(value.trim().to_string())
+    })
+}
+
+/// 解析 Steam 的 libraryfolders.vdf，返回全部库目录
+///
+/// VDF 为嵌套的 `"键" "值"` / `"键" { ... }` 结构，这里只需要所有 "path" 键的值，
+/// 按顺序扫描带引号的字符串即可，不必完整解析层级。
+pub fn parse_library_folders(vdf: &str) -> Vec<PathBuf> {
+    let mut tokens = Vec::new();
+    let mut chars = vdf.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+        let mut token = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        token.push(escaped);
+                    }
+                }
+                c => token.push(c),
+            }
+        }
+        tokens.push(token);
+    }
+    tokens
+        .windows(2)
+        .filter(|pair| pair[0].eq_ignore_ascii_case("path"))
+        .map(|pair| PathBuf::from(&pair[1]))
+        .collect()
+}
+
+/// 运行 `reg query`；以 UTF-8 代码页执行，避免中文显示名在 GBK 控制台下乱码
+#[cfg(windows)]
+fn reg_query(args: &str) -> Option<String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    // 参数含引号，用 raw_arg 原样传给 cmd，避免被转义成 \"
+    let output = std::process::Command::new("cmd")
+        .arg("/C")
+        .raw_arg(format!("chcp 65001 >nul & reg query {}", args))
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(windows)]
+fn registry_candidates() -> Vec<InstallCandidate> {
+    const UNINSTALL_KEYS: [&str; 3] = [
+        r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
+        r"HKLM\SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall",
+        r"HKCU\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
+    ];
+    UNINSTALL_KEYS
+        .iter()
+        .filter_map(|key| reg_query(&format!("\"{}\" /s", key)))
+        .flat_map(|output| parse_reg_uninstall(&output))
+        .map(|(kind, path)| InstallCandidate { path, kind })
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn registry_candidates() -> Vec<InstallCandidate> {
+    Vec::new()
+}
+
+/// 各盘符下的常见默认安装路径 (国际服安装器、国服盛趣安装器与 WeGame)
+#[cfg(windows)]
+fn default_path_candidates() -> Vec<InstallCandidate> {
+    const RELATIVE_PATHS: [(&str, ClientKind); 6] = [
+        (
+            r"Program Files (x86)\SquareEnix\FINAL FANTASY XIV - A Realm Reborn",
+            ClientKind::International,
+        ),
+        (
+            r"Program Files\SquareEnix\FINAL FANTASY XIV - A Realm Reborn",
+            ClientKind::International,
+        ),
+        (
+            r"Program Files (x86)\上海数龙科技有限公司\最终幻想XIV",
+            ClientKind::China,
+        ),
+        (
+            r"Program Files\上海数龙科技有限公司\最终幻想XIV",
+            ClientKind::China,
+        ),
+        (r"SDO\最终幻想XIV", ClientKind::China),
+        (r"WeGameApps\最终幻想XIV", ClientKind::China),
+    ];
+    let drives: Vec<PathBuf> = ('C'..='Z')
+        .map(|d| PathBuf::from(format!("{}:\\", d)))
+        .filter(|d| d.is_dir())
+        .collect();
+    drives
+        .iter()
+        .flat_map(|drive| {
+            RELATIVE_PATHS.iter().map(|(rel, kind)| InstallCandidate {
+                path: drive.join(rel),
+                kind: *kind,
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn default_path_candidates() -> Vec<InstallCandidate> {
+    Vec::new()
+}
+
+/// Steam 客户端目录: Windows 读注册表 SteamPath，其他平台使用默认位置
+fn steam_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    #[cfg(windows)]
+    {
+        if let Some(path) = reg_query(r#""HKCU\Software\Valve\Steam" /v SteamPath"#)
+            .and_then(|out| parse_reg_value(&out, "SteamPath"))
+        {
+            roots.push(PathBuf::from(path));
+        }
+        roots.push(PathBuf::from(r"C:\Program Files (x86)\Steam"));
+    }
+    #[cfg(not(windows))]
+    {
+        if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+            roots.push(home.join(".steam/steam"));
+            roots.push(home.join(".local/share/Steam"));
+        }
+    }
+    roots
+}
+
+fn steam_candidates() -> Vec<InstallCandidate> {
+    let mut libraries = Vec::new();
+    for root in steam_roots() {
+        let vdf = root.join("steamapps").join("libraryfolders.vdf");
+        if let Ok(text) = std::fs::read_to_string(&vdf) {
+            libraries.extend(parse_library_folders(&text));
+        }
+        // 旧版 vdf 不列出 Steam 自身目录
+        libraries.push(root);
+    }
+    libraries
+        .into_iter()
+        .map(|lib| InstallCandidate {
+            path: lib.join("steamapps").join("common").join(STEAM_FFXIV_DIR),
+            kind: ClientKind::Steam,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `reg query ... /s` 的实际输出样例 (CRLF 换行)
+    const REG_UNINSTALL: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/install_detect/reg_uninstall.txt"
+    ));
+    const LIBRARY_FOLDERS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/install_detect/libraryfolders.vdf"
+    ));
+
+    #[test]
+    fn reg_uninstall_finds_both_clients() {
+        let found = parse_reg_uninstall(REG_UNINSTALL);
+        assert_eq!(
+            found,
+            [
+                // 没有 InstallLocation，取 DisplayIcon 所在目录
+                (
+                    ClientKind::International,
+                    PathBuf::from(
+                        r"C:\Program Files (x86)\SquareEnix\FINAL FANTASY XIV - A Realm Reborn\boot"
+                    ),
+                ),
+                (
+                    ClientKind::China,
+                    PathBuf::from(r"D:\Games\上海数龙科技有限公司\最终幻想XIV"),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reg_uninstall_ignores_empty_output() {
+        assert!(parse_reg_uninstall("").is_empty());
+        assert!(parse_reg_uninstall("    DisplayName    REG_SZ    FINAL FANTASY XIV").is_empty());
+    }
+
+    #[test]
+    fn reg_value_lookup() {
+        let output = "\r\nHKEY_CURRENT_USER\\Software\\Valve\\Steam\r\n    SteamPath    REG_SZ    c:/program files (x86)/steam\r\n";
+        assert_eq!(
+            parse_reg_value(output, "steampath").as_deref(),
+            Some("c:/program files (x86)/steam")
+        );
+        assert_eq!(parse_reg_value(output, "SteamExe"), None);
+    }
+
+    #[test]
+    fn library_folders_lists_every_path() {
+        assert_eq!(
+            parse_library_folders(LIBRARY_FOLDERS),
+            [
+                PathBuf::from(r"C:\Program Files (x86)\Steam"),
+                PathBuf::from(r"D:\SteamLibrary"),
+            ]
+        );
+        assert!(parse_library_folders("").is_empty());
+    }
+
+    #[test]
+    fn display_name_client_kind() {
+        assert_eq!(
+            client_kind_from_display_name("FINAL FANTASY XIV ONLINE"),
+            Some(ClientKind::International)
+        );
+        assert_eq!(
+            client_kind_from_display_name("最终幻想XIV"),
+            Some(ClientKind::China)
+        );
+        assert_eq!(client_kind_from_display_name("FINAL FANTASY XII"), None);
+    }
+
+    #[test]
+    fn boot_and_game_dirs_map_to_install_root() {
+        let root = Path::new("SquareEnix").join("FINAL FANTASY XIV - A Realm Reborn");
+        assert_eq!(install_root(&root.join("boot")), root);
+        assert_eq!(install_root(&root.join("Game")), root);
+        assert_eq!(install_root(&root), root);
+    }
+
+    #[test]
+    fn path_key_ignores_trailing_separator() {
+        assert_eq!(path_key(Path::new("ffxiv/")), path_key(Path::new("ffxiv")));
+    }
+}
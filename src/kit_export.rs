@@ -0,0 +1,209 @@
+//! 套装素材导出: 每个槽位一个子目录，包含 glb 模型与 diffuse/normal/mask 贴图，
+//! 根目录写出 manifest.json 记录物品信息与失败项
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+
+use physis::stm::StainingTemplate;
+use serde_json::json;
+
+use crate::domain::EquipSlot;
+use crate::dye::bake_dyed_diffuse;
+use crate::game::{load_mdl_with_fallback, load_mesh_textures, GameData};
+use crate::gltf::{ExportPrimitive, GlbBuilder};
+
+/// 待导出的一个槽位
+#[derive(Clone)]
+pub struct KitSlot {
+    pub slot: EquipSlot,
+    pub item_id: u32,
+    pub item_name: String,
+    pub set_id: u16,
+    pub variant_id: u16,
+    /// 按种族回退顺序排列的模型路径
+    pub model_paths: Vec<String>,
+}
+
+pub struct KitExportRequest {
+    /// 游戏安装目录，演示数据时为 None
+    pub install_dir: Option<PathBuf>,
+    pub out_dir: PathBuf,
+    pub set_name: String,
+    pub slots: Vec<KitSlot>,
+    /// 烘焙 diffuse 时使用的染料，None 表示不染色
+    pub stain_ids: Option<[u32; 2]>,
+}
+
+pub enum KitExportProgress {
+    /// 开始导出第 index 个槽位 (从 0 开始)
+    Slot { index: usize, item_name: String },
+    /// 全部完成: (manifest 路径, 失败的槽位数)
+    Done(Result<(PathBuf, usize), String>),
+}
+
+/// 在后台线程中运行: GameData 不能跨线程共享，按安装目录重新打开
+pub fn run_kit_export(request: KitExportRequest, tx: Sender<KitExportProgress>) {
+    let result = export_kit(&request, &tx);
+    let _ = tx.send(KitExportProgress::Done(result));
+}
+
+fn export_kit(
+    request: &KitExportRequest,
+    tx: &Sender<KitExportProgress>,
+) -> Result<(PathBuf, usize), String> {
+    std::fs::create_dir_all(&request.out_dir)
+        .map_err(|e| format!("创建 {} 失败: {}", request.out_dir.display(), e))?;
+    let game = match &request.install_dir {
+        Some(dir) => GameData::new(dir),
+        None => GameData::mock(),
+    };
+    let stm = request.stain_ids.and_then(|_| game.load_staining_template());
+    let stain_ids = request.stain_ids.unwrap_or([0, 0]);
+
+    let mut slots = Vec::new();
+    let mut errors = Vec::new();
+    for (index, slot) in request.slots.iter().enumerate() {
+        let _ = tx.send(KitExportProgress::Slot {
+            index,
+            item_name: slot.item_name.clone(),
+        });
+        // 同一套装可能有多个配色版本占用同一槽位，目录名附带物品 ID
+        let slot_dir = request
+            .out_dir
+            .join(format!("{}_{}", slot.slot.slot_abbr(), slot.item_id));
+        match export_kit_slot(&game, slot, stain_ids, stm.as_ref(), &slot_dir) {
+            Ok(entry) => slots.push(entry),
+            Err(e) => errors.push(json!({
+                "slot": slot.slot.slot_abbr(),
+                "item_id": slot.item_id,
+                "item_name": slot.item_name,
+                "error": e,
+            })),
+        }
+    }
+
+    let failed = errors.len();
+    let manifest = json!({
+        "set_name": request.set_name,
+        "stain_ids": request.stain_ids,
+        "slots": slots,
+        "errors": errors,
+    });
+    let path = request.out_dir.join("manifest.json");
+    let text = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    std::fs::write(&path, text).map_err(|e| format!("写入 {} 失败: {}", path.display(), e))?;
+    Ok((path, failed))
+}
+
+fn save_png(path: &Path, tex: &tomestone_render::TextureData) -> Result<(), String> {
+    image::save_buffer(path, &tex.rgba, tex.width, tex.height, image::ColorType::Rgba8)
+        .map_err(|e| format!("写入 {} 失败: {}", path.display(), e))
+}
+
+/// 导出一个槽位，返回 manifest 中的条目；贴图缺失只记录不视为失败
+fn export_kit_slot(
+    game: &GameData,
+    slot: &KitSlot,
+    stain_ids: [u32; 2],
+    stm: Option<&StainingTemplate>,
+    dir: &Path,
+) -> Result<serde_json::Value, String> {
+    let result = load_mdl_with_fallback(game, &slot.model_paths)?;
+    let textures = load_mesh_textures(
+        game,
+        &result.material_names,
+        &result.meshes,
+        slot.set_id,
+        slot.variant_id,
+    );
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建 {} 失败: {}", dir.display(), e))?;
+
+    let diffuses: Vec<Option<tomestone_render::TextureData>> = result
+        .meshes
+        .iter()
+        .enumerate()
+        .map(|(i, mesh)| {
+            textures
+                .materials
+                .get(&mesh.material_index)
+                .and_then(|cached| bake_dyed_diffuse(cached, stain_ids, stm))
+                .or_else(|| textures.mesh_textures.get(i).map(|t| t.diffuse.clone()))
+        })
+        .collect();
+
+    // 贴图按材质写出，多个网格共用同一材质时只写一次
+    let mut materials = Vec::new();
+    for diag in &textures.diagnostics {
+        let Some(mesh_idx) = result
+            .meshes
+            .iter()
+            .position(|m| m.material_index == diag.material_index)
+        else {
+            continue;
+        };
+        let stem = Path::new(&diag.material_name)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("material")
+            .to_string();
+        let mut files = serde_json::Map::new();
+        if let Some(diffuse) = &diffuses[mesh_idx] {
+            let name = format!("{}_diffuse.png", stem);
+            save_png(&dir.join(&name), diffuse)?;
+            files.insert("diffuse".to_string(), json!(name));
+        }
+        if let Some(tex) = textures.mesh_textures.get(mesh_idx) {
+            for (kind, map) in [("normal", &tex.normal), ("mask", &tex.mask)] {
+                if let Some(map) = map {
+                    let name = format!("{}_{}.png", stem, kind);
+                    save_png(&dir.join(&name), map)?;
+                    files.insert(kind.to_string(), json!(name));
+                }
+            }
+        }
+        materials.push(json!({
+            "index": diag.material_index,
+            "name": diag.material_name,
+            "source": format!("{:?}", diag.source),
+            "failed_paths": diag.failed_paths,
+            "files": files,
+        }));
+    }
+
+    let primitives: Vec<ExportPrimitive<'_>> = result
+        .meshes
+        .iter()
+        .zip(&diffuses)
+        .enumerate()
+        .map(|(i, (mesh, diffuse))| {
+            let tex = textures.mesh_textures.get(i);
+            ExportPrimitive {
+                vertices: &mesh.vertices,
+                indices: &mesh.indices,
+                diffuse: diffuse.as_ref(),
+                normal: tex.and_then(|t| t.normal.as_ref()),
+                mask: tex.and_then(|t| t.mask.as_ref()),
+            }
+        })
+        .collect();
+    let node_name = format!("{}_{}", slot.item_name, slot.slot.slot_abbr());
+    let mut builder = GlbBuilder::new();
+    builder.add_node(&node_name, &primitives)?;
+    let extras = json!({ "item_id": slot.item_id, "stain_ids": stain_ids });
+    let glb = builder.finish(&node_name, extras)?;
+    let glb_name = format!("{}.glb", slot.slot.slot_abbr());
+    let glb_path = dir.join(&glb_name);
+    std::fs::write(&glb_path, glb)
+        .map_err(|e| format!("写入 {} 失败: {}", glb_path.display(), e))?;
+
+    Ok(json!({
+        "slot": slot.slot.slot_abbr(),
+        "item_id": slot.item_id,
+        "item_name": slot.item_name,
+        "set_id": slot.set_id,
+        "variant_id": slot.variant_id,
+        "directory": dir.file_name().and_then(|n| n.to_str()),
+        "model": glb_name,
+        "materials": materials,
+    }))
+}
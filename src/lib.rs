@@ -1,16 +1,41 @@
+#[macro_use]
+mod i18n;
+
+pub mod alloc_stats;
 mod app;
 mod auto_craft;
+pub mod cli;
+mod color;
 mod config;
+mod craft_html;
+mod crash;
+mod derived;
 mod domain;
 mod dye;
+mod export_name;
 mod fonts;
+mod fuzzy;
 pub mod game;
 mod glamour;
+mod gltf;
+pub mod gpu;
+mod install_detect;
+mod kit_export;
 mod loading;
+mod nav;
+mod numfmt;
 mod schema;
+mod teamcraft;
 mod template;
+mod thumbnail;
 pub mod ui;
+mod viewport_input;
+
+#[cfg(any(test, feature = "alloc-stats"))]
+#[global_allocator]
+static GLOBAL_ALLOC: alloc_stats::CountingAlloc = alloc_stats::CountingAlloc;
 
 pub use app::App;
+pub use config::load_config;
 pub use fonts::setup_fonts;
 pub use glamour::GlamourSet;
@@ -1,19 +1,25 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use physis::stm::StainingTemplate;
 
+use crate::derived::DerivedIndexes;
 use crate::domain::{
-    build_equipment_sets, EquipmentSet, GameItem, ItemSource, Recipe, StainEntry, ALL_SLOTS,
+    build_equipment_sets, CollectionInfo, CollectionKind, EquipmentSet, GameItem, HousingSize,
+    ItemSource, Recipe, StainEntry, ALL_SLOTS,
 };
-use crate::game::GameData;
+use crate::game::{DiagnosticReport, GameData};
 use crate::glamour;
 use crate::ui::pages::resource::ResourceBrowserState;
 
 pub struct GameState {
     pub game: GameData,
-    /// 全部物品 (统一模型)
-    pub all_items: Vec<GameItem>,
+    /// 全部物品 (统一模型)，后台线程 (染色探测) 共享同一份数据，各索引只记录下标
+    pub all_items: Arc<[GameItem]>,
     /// row_id -> all_items 下标
     pub item_id_map: HashMap<u32, usize>,
 
@@ -28,6 +34,8 @@ pub struct GameState {
     pub housing_ext_indices: Vec<usize>,
     /// HousingExterior additional_data -> SGB 路径列表
     pub housing_sgb_paths: HashMap<u32, Vec<String>>,
+    /// HousingExterior additional_data -> 适用的房屋尺寸
+    pub housing_exterior_sizes: HashMap<u32, HousingSize>,
 
     // ── 房屋家具视图索引 ──
     /// 庭院家具物品在 all_items 中的下标
@@ -43,6 +51,12 @@ pub struct GameState {
     pub stains: Vec<StainEntry>,
     pub stm: Option<StainingTemplate>,
     pub glamour_sets: Vec<glamour::GlamourSet>,
+    /// 幻化组合 id -> 校验结果 (物品/染料是否仍存在)
+    pub glamour_validation: HashMap<String, glamour::GlamourValidation>,
+    /// 幻化组合的染料/物品使用统计，随校验一同刷新
+    pub glamour_stats: glamour::GlamourStats,
+    /// 物品 row_id -> 使用该物品的幻化组合索引，随组合校验一起刷新
+    pub glamour_item_usage: HashMap<u32, Vec<usize>>,
     pub resource_browser: ResourceBrowserState,
 
     // ── 合成数据 ──
@@ -57,11 +71,43 @@ pub struct GameState {
     /// RecipeLevelTable row_id -> 配方等级 (职业等级)
     pub recipe_levels: HashMap<u16, u8>,
 
+    // ── 收藏品 ──
+    /// 乐谱物品在 all_items 中的下标
+    pub orchestrion_indices: Vec<usize>,
+    /// 幻卡物品在 all_items 中的下标
+    pub triad_card_indices: Vec<usize>,
+    /// Orchestrion row_id -> 曲名/说明
+    pub orchestrion_info: HashMap<u32, CollectionInfo>,
+    /// TripleTriadCard row_id -> 卡名/说明
+    pub triad_card_info: HashMap<u32, CollectionInfo>,
+
     // ── 物品来源 ──
     /// item_id -> 获取来源列表
     pub item_sources: HashMap<u32, Vec<ItemSource>>,
     /// ItemUICategory row_id -> 分类名称
     pub ui_category_names: HashMap<u8, String>,
+    /// ClassJob row_id -> 职业名称 (修理职业)
+    pub class_job_names: HashMap<u8, String>,
+    /// BaseParam row_id -> 属性名称
+    pub base_param_names: HashMap<u8, String>,
+    /// ItemRepairResource row_id -> 修理材料物品 ID
+    pub repair_resource_items: HashMap<u8, u32>,
+
+    // ── 染色探测 ──
+    /// 材质探测得到的染色通道数 (item_id -> 0/1/2)，仅用于 Item 表缺少 DyeCount 的物品
+    pub probed_dye_counts: HashMap<u32, u8>,
+    /// 后台探测线程的结果通道，探测结束后置为 None
+    pub dye_probe_rx: Option<Receiver<(u32, u8)>>,
+
+    // ── 派生索引 ──
+    /// 后台构建的名称查找表与快速切换候选，就绪前相关功能逐项计算
+    pub derived: DerivedIndexes,
+
+    // ── 自检 ──
+    /// 加载阶段返回空结果的数据项名称
+    pub load_failures: Vec<String>,
+    /// 部分页读取失败的表 (如 "Item 表: 38/40 页加载成功 (失败: ...)")
+    pub partial_loads: Vec<String>,
 }
 
 pub enum LoadProgress {
@@ -72,15 +118,19 @@ pub enum LoadProgress {
 
 pub struct LoadedData {
     pub game: GameData,
-    pub all_items: Vec<GameItem>,
+    pub all_items: Arc<[GameItem]>,
     pub stains: Vec<StainEntry>,
     pub stm: Option<StainingTemplate>,
     pub all_table_names: Vec<String>,
     pub housing_sgb_paths: HashMap<u32, Vec<String>>,
+    pub housing_exterior_sizes: HashMap<u32, HousingSize>,
     pub housing_furniture_sgb_paths: HashMap<u32, String>,
     pub housing_yard_sgb_paths: HashMap<u32, String>,
     pub recipes: Vec<Recipe>,
     pub ui_category_names: HashMap<u8, String>,
+    pub class_job_names: HashMap<u8, String>,
+    pub base_param_names: HashMap<u8, String>,
+    pub repair_resource_items: HashMap<u8, u32>,
     pub gil_shop_items: std::collections::HashMap<u32, Vec<ItemSource>>,
     pub special_shop_sources: HashMap<u32, Vec<ItemSource>>,
     pub gathering_items: std::collections::HashSet<u32>,
@@ -88,8 +138,19 @@ pub struct LoadedData {
     pub secret_recipe_book_names: HashMap<u32, String>,
     /// RecipeLevelTable row_id -> 配方等级
     pub recipe_levels: HashMap<u16, u8>,
+    pub orchestrion_info: HashMap<u32, CollectionInfo>,
+    pub triad_card_info: HashMap<u32, CollectionInfo>,
+    /// 加载阶段返回空结果的数据项名称
+    pub load_failures: Vec<String>,
+    /// 部分页读取失败的表摘要
+    pub partial_loads: Vec<String>,
+    /// 失败项过多时在后台线程中预先运行的自检报告
+    pub initial_diagnostics: Option<DiagnosticReport>,
 }
 
+/// 加载失败项超过该数量时自动弹出自检报告
+pub const AUTO_DIAGNOSTICS_THRESHOLD: usize = 1;
+
 pub fn load_game_data_thread(install_dir: PathBuf, tx: std::sync::mpsc::Sender<LoadProgress>) {
     if let Err(e) = crate::game::validate_install_dir(&install_dir) {
         let _ = tx.send(LoadProgress::Error(e));
@@ -98,9 +159,22 @@ pub fn load_game_data_thread(install_dir: PathBuf, tx: std::sync::mpsc::Sender<L
 
     let _ = tx.send(LoadProgress::Status("正在初始化游戏数据...".to_string()));
     let game = GameData::new(&install_dir);
+    load_tables(game, &tx);
+}
 
+/// 演示模式: 使用内置合成数据代替游戏安装，走与真实安装相同的加载流程
+pub fn load_mock_data_thread(tx: std::sync::mpsc::Sender<LoadProgress>) {
+    let _ = tx.send(LoadProgress::Status("正在生成演示数据...".to_string()));
+    let game = GameData::mock();
+    load_tables(game, &tx);
+}
+
+/// 从数据源读取全部表并发送加载结果
+fn load_tables(game: GameData, tx: &std::sync::mpsc::Sender<LoadProgress>) {
     let _ = tx.send(LoadProgress::Status("正在加载物品列表...".to_string()));
-    let all_items = game.load_all_items();
+    // ItemUICategory 先于 Item 读取，物品加载时据此标记面饰
+    let ui_category_names = game.load_ui_category_names();
+    let all_items: Arc<[GameItem]> = game.load_all_items(&ui_category_names).into();
 
     let _ = tx.send(LoadProgress::Status("正在加载染料列表...".to_string()));
     let stains = game.load_stain_list();
@@ -114,6 +188,7 @@ pub fn load_game_data_thread(install_dir: PathBuf, tx: std::sync::mpsc::Sender<L
 
     let _ = tx.send(LoadProgress::Status("正在加载房屋外装数据...".to_string()));
     let housing_sgb_paths = game.load_housing_sgb_paths();
+    let housing_exterior_sizes = game.load_housing_exterior_sizes();
 
     let _ = tx.send(LoadProgress::Status("正在加载房屋家具数据...".to_string()));
     let housing_furniture_sgb_paths = game.load_housing_furniture_sgb_paths();
@@ -124,12 +199,81 @@ pub fn load_game_data_thread(install_dir: PathBuf, tx: std::sync::mpsc::Sender<L
     let secret_recipe_book_names = game.load_secret_recipe_book_names();
     let recipe_levels = game.load_recipe_level_table();
 
+    let _ = tx.send(LoadProgress::Status("正在加载收藏品数据...".to_string()));
+    let orchestrion_info = game.load_orchestrion_list();
+    let triad_card_info = game.load_triple_triad_cards();
+
     let _ = tx.send(LoadProgress::Status("正在加载物品来源数据...".to_string()));
-    let ui_category_names = game.load_ui_category_names();
-    let gil_shop_items = game.load_gil_shop_items();
-    let special_shop_sources = game.load_special_shop_sources();
+    let class_job_names = game.load_class_job_names();
+    let base_param_names = game.load_base_param_names();
+    let repair_resource_items = game.load_item_repair_resources();
+    let shop_npcs = game.load_shop_npcs();
+    let gil_shop_items = game.load_gil_shop_items(&shop_npcs);
+    let mut special_shop_sources = game.load_special_shop_sources(&shop_npcs);
+    let gc_shop_sources = game.load_gc_shop_sources();
+    let gc_shop_empty = gc_shop_sources.is_empty();
+    for (item_id, sources) in gc_shop_sources {
+        special_shop_sources
+            .entry(item_id)
+            .or_default()
+            .extend(sources);
+    }
     let gathering_items = game.load_gathering_items();
 
+    // 各加载器失败时只打印日志并返回空集合，这里汇总哪些数据项为空
+    let mut load_failures = Vec::new();
+    for (name, empty) in [
+        ("物品 (Item)", all_items.is_empty()),
+        ("染料 (Stain)", stains.is_empty()),
+        ("染色模板 (STM)", stm.is_none()),
+        ("房屋外装 (HousingExterior)", housing_sgb_paths.is_empty()),
+        (
+            "室内家具 (HousingFurniture)",
+            housing_furniture_sgb_paths.is_empty(),
+        ),
+        (
+            "庭院家具 (HousingYardObject)",
+            housing_yard_sgb_paths.is_empty(),
+        ),
+        ("配方 (Recipe)", recipes.is_empty()),
+        ("配方等级 (RecipeLevelTable)", recipe_levels.is_empty()),
+        ("物品分类 (ItemUICategory)", ui_category_names.is_empty()),
+        ("职业 (ClassJob)", class_job_names.is_empty()),
+        ("属性 (BaseParam)", base_param_names.is_empty()),
+        ("金币商店 (GilShop)", gil_shop_items.is_empty()),
+        ("兑换商店 (SpecialShop)", special_shop_sources.is_empty()),
+        ("军票商店 (GCScripShopItem)", gc_shop_empty),
+        ("采集 (GatheringItem)", gathering_items.is_empty()),
+        ("乐谱 (Orchestrion)", orchestrion_info.is_empty()),
+        ("幻卡 (TripleTriadCard)", triad_card_info.is_empty()),
+    ] {
+        if empty {
+            load_failures.push(name.to_string());
+        }
+    }
+    // 演示数据只合成了部分表 (没有房屋、收藏品、军票商店与染色模板)，空表不算加载失败
+    if game.is_mock() {
+        load_failures.clear();
+    }
+
+    let partial_loads: Vec<String> = game
+        .take_partial_loads()
+        .iter()
+        .map(|load| load.summary())
+        .collect();
+
+    let initial_diagnostics = if load_failures.len() > AUTO_DIAGNOSTICS_THRESHOLD {
+        eprintln!(
+            "{} 个数据项加载失败: {}",
+            load_failures.len(),
+            load_failures.join(", ")
+        );
+        let _ = tx.send(LoadProgress::Status("正在运行自检...".to_string()));
+        Some(game.run_self_check())
+    } else {
+        None
+    };
+
     let _ = tx.send(LoadProgress::Done(Box::new(LoadedData {
         game,
         all_items,
@@ -137,15 +281,24 @@ pub fn load_game_data_thread(install_dir: PathBuf, tx: std::sync::mpsc::Sender<L
         stm,
         all_table_names,
         housing_sgb_paths,
+        housing_exterior_sizes,
         housing_furniture_sgb_paths,
         housing_yard_sgb_paths,
         recipes,
         ui_category_names,
+        class_job_names,
+        base_param_names,
+        repair_resource_items,
         gil_shop_items,
         special_shop_sources,
         gathering_items,
         secret_recipe_book_names,
         recipe_levels,
+        orchestrion_info,
+        triad_card_info,
+        load_failures,
+        partial_loads,
+        initial_diagnostics,
     })));
 }
 
@@ -170,6 +323,7 @@ pub fn glamour_slot_summary(
 
 impl GameState {
     pub fn from_loaded_data(data: LoadedData) -> Self {
+        let allocations_before = crate::alloc_stats::thread_allocations();
         // 构建 item_id_map
         let item_id_map: HashMap<u32, usize> = data
             .all_items
@@ -206,6 +360,22 @@ impl GameState {
             .map(|(i, _)| i)
             .collect();
 
+        // 构建收藏品视图索引 (仅保留能在链接表中找到的物品)
+        let collection_indices = |kind: CollectionKind, info: &HashMap<u32, CollectionInfo>| {
+            data.all_items
+                .iter()
+                .enumerate()
+                .filter(|(_, item)| {
+                    item.collection_kind() == Some(kind) && info.contains_key(&item.additional_data)
+                })
+                .map(|(i, _)| i)
+                .collect::<Vec<usize>>()
+        };
+        let orchestrion_indices =
+            collection_indices(CollectionKind::Orchestrion, &data.orchestrion_info);
+        let triad_card_indices =
+            collection_indices(CollectionKind::TripleTriad, &data.triad_card_info);
+
         // 构建庭院家具视图索引 (直接用 HousingYardObject 表的 Item 列)
         let housing_yard_indices: Vec<usize> = data
             .all_items
@@ -270,6 +440,24 @@ impl GameState {
             sources.retain(|s| seen.insert(s.cost_key()));
         }
 
+        // Item 表缺少 DyeCount 时，后台逐件探测材质的染色表
+        let probe_jobs: Vec<usize> = equipment_indices
+            .iter()
+            .copied()
+            .filter(|&i| data.all_items[i].dye_count().is_none())
+            .collect();
+        // 探测线程会重新打开 sqpack，演示模式下跳过
+        let dye_probe_rx = if probe_jobs.is_empty() || data.game.is_mock() {
+            None
+        } else {
+            println!("Item 表无染色信息, 后台探测 {} 件装备", probe_jobs.len());
+            Some(spawn_dye_probe(
+                data.game.install_dir(),
+                data.all_items.clone(),
+                probe_jobs,
+            ))
+        };
+
         println!(
             "物品总数: {}, 装备: {}, 房屋外装: {}, 庭院家具: {}, 室内家具: {}, 配方: {}, 有来源物品: {}",
             data.all_items.len(),
@@ -281,7 +469,15 @@ impl GameState {
             item_sources.len(),
         );
 
-        Self {
+        let derived = DerivedIndexes::spawn(
+            data.all_items.clone(),
+            &item_id_map,
+            &equipment_indices,
+            item_to_recipes.keys().copied(),
+            crate::ui::pages::quick_switcher::switch_pages().collect(),
+        );
+
+        let mut state = Self {
             game: data.game,
             all_items: data.all_items,
             item_id_map,
@@ -290,6 +486,7 @@ impl GameState {
             set_id_to_set_idx,
             housing_ext_indices,
             housing_sgb_paths: data.housing_sgb_paths,
+            housing_exterior_sizes: data.housing_exterior_sizes,
             housing_yard_indices,
             housing_indoor_indices,
             housing_furniture_sgb_paths: data.housing_furniture_sgb_paths,
@@ -303,8 +500,335 @@ impl GameState {
             craftable_by_type,
             item_sources,
             ui_category_names: data.ui_category_names,
+            class_job_names: data.class_job_names,
+            base_param_names: data.base_param_names,
+            repair_resource_items: data.repair_resource_items,
             secret_recipe_book_names: data.secret_recipe_book_names,
             recipe_levels: data.recipe_levels,
+            orchestrion_indices,
+            triad_card_indices,
+            orchestrion_info: data.orchestrion_info,
+            triad_card_info: data.triad_card_info,
+            probed_dye_counts: HashMap::new(),
+            dye_probe_rx,
+            derived,
+            load_failures: data.load_failures,
+            partial_loads: data.partial_loads,
+            glamour_validation: HashMap::new(),
+            glamour_stats: glamour::GlamourStats::default(),
+            glamour_item_usage: HashMap::new(),
+        };
+        state.revalidate_glamour_sets();
+        if crate::alloc_stats::ENABLED {
+            println!(
+                "构建 GameState: {} 次堆分配 (物品列表以 Arc 共享，不含副本)",
+                crate::alloc_stats::thread_allocations() - allocations_before
+            );
+        }
+        state
+    }
+
+    /// 按配置重新分配染料分组: 先恢复 Stain 表中的原始分组，再应用覆盖 (设置页修改后调用)
+    pub fn reapply_shade_overrides(&mut self, overrides: &HashMap<u32, u8>) {
+        let sheet_shades: HashMap<u32, u8> = self
+            .game
+            .load_stain_list()
+            .iter()
+            .map(|stain| (stain.id, stain.shade))
+            .collect();
+        for stain in &mut self.stains {
+            if let Some(&shade) = sheet_shades.get(&stain.id) {
+                stain.shade = shade;
+            }
+        }
+        crate::domain::apply_shade_overrides(&mut self.stains, overrides);
+    }
+
+    /// 重新校验全部幻化组合并刷新使用统计，返回有问题的组合数
+    pub fn revalidate_glamour_sets(&mut self) -> usize {
+        self.glamour_stats = glamour::compute_glamour_stats(&self.glamour_sets);
+        self.glamour_item_usage = glamour::build_item_usage_index(&self.glamour_sets);
+        self.glamour_validation = self
+            .glamour_sets
+            .iter()
+            .map(|set| {
+                (
+                    set.id.clone(),
+                    glamour::validate_glamour_set(set, &self.item_id_map, &self.stains),
+                )
+            })
+            .collect();
+        let broken = self
+            .glamour_validation
+            .values()
+            .filter(|v| !v.is_ok())
+            .count();
+        if broken > 0 {
+            println!("幻化组合校验: {} 个组合存在缺失物品或染料", broken);
+        }
+        broken
+    }
+
+    /// 按 row_id 查找物品 (选中状态均以 row_id 保存，物品列表重建后仍有效)
+    pub fn item_by_id(&self, row_id: u32) -> Option<&GameItem> {
+        self.item_id_map
+            .get(&row_id)
+            .and_then(|&idx| self.all_items.get(idx))
+    }
+
+    /// 房屋物品的尺寸分类，仅外装有数据
+    pub fn housing_size(&self, item: &GameItem) -> HousingSize {
+        if !item.is_housing_exterior() {
+            return HousingSize::Unknown;
+        }
+        self.housing_exterior_sizes
+            .get(&item.additional_data)
+            .copied()
+            .unwrap_or(HousingSize::Unknown)
+    }
+
+    /// 收藏品物品在链接表中的名称与说明
+    pub fn collection_info(&self, item: &GameItem) -> Option<&CollectionInfo> {
+        match item.collection_kind()? {
+            CollectionKind::Orchestrion => self.orchestrion_info.get(&item.additional_data),
+            CollectionKind::TripleTriad => self.triad_card_info.get(&item.additional_data),
+        }
+    }
+
+    /// 收藏品类别对应的物品下标列表
+    pub fn collection_indices(&self, kind: CollectionKind) -> &[usize] {
+        match kind {
+            CollectionKind::Orchestrion => &self.orchestrion_indices,
+            CollectionKind::TripleTriad => &self.triad_card_indices,
         }
     }
+
+    /// 物品的染色通道数: 优先 Item 表，其次材质探测结果
+    pub fn dye_count(&self, item: &GameItem) -> Option<u8> {
+        item.dye_count()
+            .or_else(|| self.probed_dye_counts.get(&item.row_id).copied())
+    }
+
+    /// 收取后台探测结果，返回是否仍在探测中
+    pub fn poll_dye_probe(&mut self) -> bool {
+        let Some(rx) = &self.dye_probe_rx else {
+            return false;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok((item_id, count)) => {
+                    self.probed_dye_counts.insert(item_id, count);
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => return true,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    println!("染色探测完成: {} 件", self.probed_dye_counts.len());
+                    self.dye_probe_rx = None;
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// 在独立线程中 (使用独立的 GameData 实例) 运行自检，完成后发送报告
+pub fn spawn_self_check(install_dir: PathBuf, mock: bool) -> Receiver<DiagnosticReport> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let game = if mock {
+            GameData::mock()
+        } else {
+            GameData::new(&install_dir)
+        };
+        let _ = tx.send(game.run_self_check());
+    });
+    rx
+}
+
+/// 在独立线程中 (使用独立的 GameData 实例) 探测装备的染色通道数
+fn spawn_dye_probe(
+    install_dir: PathBuf,
+    items: Arc<[GameItem]>,
+    jobs: Vec<usize>,
+) -> Receiver<(u32, u8)> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let game = GameData::new(&install_dir);
+        for idx in jobs {
+            let item = &items[idx];
+            let count = crate::game::probe_dye_channels(
+                &game,
+                &item.model_paths(),
+                item.set_id(),
+                item.variant_id(),
+            );
+            if tx.send((item.row_id, count)).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// 游戏更新检测: 后台线程轮询 game/ffxivgame.ver 的修改时间，变化时通知一次
+pub struct GameVersionWatcher {
+    rx: Receiver<()>,
+    stop: Arc<AtomicBool>,
+}
+
+impl GameVersionWatcher {
+    const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn spawn(install_dir: &Path) -> Self {
+        let ver_path = install_dir.join("game").join("ffxivgame.ver");
+        let (tx, rx) = std::sync::mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        std::thread::spawn(move || {
+            let mtime = |path: &Path| -> Option<SystemTime> {
+                std::fs::metadata(path).and_then(|m| m.modified()).ok()
+            };
+            let initial = mtime(&ver_path);
+            while !stop_flag.load(Ordering::Relaxed) {
+                std::thread::sleep(Self::POLL_INTERVAL);
+                if mtime(&ver_path) != initial {
+                    let _ = tx.send(());
+                    break;
+                }
+            }
+        });
+        Self { rx, stop }
+    }
+
+    /// 是否检测到游戏文件更新 (只返回一次 true)
+    pub fn poll_changed(&self) -> bool {
+        self.rx.try_recv().is_ok()
+    }
+}
+
+impl Drop for GameVersionWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 走一遍演示数据的完整加载流程
+    fn load_mock() -> LoadedData {
+        let (tx, rx) = std::sync::mpsc::channel();
+        load_mock_data_thread(tx);
+        rx.into_iter()
+            .find_map(|progress| match progress {
+                LoadProgress::Done(data) => Some(*data),
+                LoadProgress::Error(e) => panic!("演示数据加载失败: {}", e),
+                LoadProgress::Status(_) => None,
+            })
+            .expect("加载线程未发送结果")
+    }
+
+    /// 模拟重新加载: 用改写后的物品列表重建 GameState
+    fn reload(edit: impl FnOnce(Vec<GameItem>) -> Vec<GameItem>) -> GameState {
+        let mut data = load_mock();
+        data.all_items = edit(data.all_items.to_vec()).into();
+        GameState::from_loaded_data(data)
+    }
+
+    #[test]
+    fn selection_survives_reordered_reload() {
+        let before = GameState::from_loaded_data(load_mock());
+        let old_idx = before.all_items.len() / 2;
+        let selected = before.all_items[old_idx].row_id;
+        let name = before.all_items[old_idx].name.clone();
+
+        let after = reload(|mut items| {
+            items.reverse();
+            items
+        });
+        // 按下标保存的选中项此时会指向别的物品
+        assert_ne!(after.all_items[old_idx].row_id, selected);
+        let item = after.item_by_id(selected).expect("选中物品应仍可找到");
+        assert_eq!(item.row_id, selected);
+        assert_eq!(item.name, name);
+    }
+
+    #[test]
+    fn selection_survives_language_reload() {
+        let before = GameState::from_loaded_data(load_mock());
+        let selected = before.all_items[0].row_id;
+
+        // 切换语言后名称全部变化，排序随之改变
+        let after = reload(|items| {
+            let mut items: Vec<GameItem> = items
+                .into_iter()
+                .map(|mut item| {
+                    item.name = format!("Item #{}", u32::MAX - item.row_id);
+                    item.search_key = item.name.to_lowercase();
+                    item
+                })
+                .collect();
+            items.sort_by(|a, b| a.name.cmp(&b.name));
+            items
+        });
+        let item = after.item_by_id(selected).expect("选中物品应仍可找到");
+        assert_eq!(item.row_id, selected);
+        assert_eq!(item.name, format!("Item #{}", u32::MAX - selected));
+    }
+
+    #[test]
+    fn removed_item_clears_selection_instead_of_shifting() {
+        let before = GameState::from_loaded_data(load_mock());
+        let selected = before.all_items[0].row_id;
+
+        let after = reload(|items| {
+            items
+                .into_iter()
+                .filter(|item| item.row_id != selected)
+                .collect()
+        });
+        assert!(after.item_by_id(selected).is_none());
+        for item in after.all_items.iter() {
+            assert_eq!(after.item_by_id(item.row_id).unwrap().row_id, item.row_id);
+        }
+    }
+
+    #[test]
+    fn sharing_items_does_not_copy_them() {
+        let data = load_mock();
+        let before = crate::alloc_stats::thread_allocations();
+        let shared = Arc::clone(&data.all_items);
+        assert_eq!(crate::alloc_stats::thread_allocations(), before);
+
+        // 对照: 改为 Arc 之前探测线程、派生索引等各持有一份物品列表副本，
+        // 每件物品的名称与搜索键各分配一次
+        let before = crate::alloc_stats::thread_allocations();
+        let copied = shared.to_vec();
+        let copy_allocations = crate::alloc_stats::thread_allocations() - before;
+        assert!(copy_allocations > 2 * copied.len() as u64);
+    }
+
+    #[test]
+    fn game_state_keeps_the_loaded_item_storage() {
+        let data = load_mock();
+        let items = Arc::clone(&data.all_items);
+        let state = GameState::from_loaded_data(data);
+        assert!(Arc::ptr_eq(&state.all_items, &items));
+    }
+
+    #[test]
+    fn shade_overrides_can_be_changed_after_loading() {
+        let mut gs = GameState::from_loaded_data(load_mock());
+        let shade_of =
+            |gs: &GameState, id: u32| gs.stains.iter().find(|s| s.id == id).unwrap().shade;
+        assert_eq!(shade_of(&gs, 3), 4);
+
+        gs.reapply_shade_overrides(&HashMap::from([(3, 2)]));
+        assert_eq!(shade_of(&gs, 3), 2);
+        assert_eq!(shade_of(&gs, 4), 6);
+
+        // 删除覆盖后恢复 Stain 表中的分组
+        gs.reapply_shade_overrides(&HashMap::new());
+        assert_eq!(shade_of(&gs, 3), 4);
+    }
 }
@@ -1,25 +1,79 @@
 use eframe::egui;
 
 fn main() {
-    let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1200.0, 700.0])
-            .with_title("FF14 装备浏览器"),
-        ..Default::default()
-    };
+    // 有子命令时以命令行模式运行，不创建窗口
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match tomestone::cli::parse_args(&args) {
+        Ok(Some(cli)) => {
+            if let Err(e) = tomestone::cli::run(cli) {
+                eprintln!("错误: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, tomestone::cli::USAGE);
+            std::process::exit(2);
+        }
+    }
 
-    eframe::run_native(
-        "tomestone",
-        options,
-        Box::new(|cc| {
-            tomestone::setup_fonts(&cc.egui_ctx);
-            let render_state = cc
-                .wgpu_render_state
-                .as_ref()
-                .expect("需要 wgpu 后端")
-                .clone();
-            Ok(Box::new(tomestone::App::new(render_state)))
-        }),
-    )
-    .unwrap();
+    let config = tomestone::load_config();
+    let preferred = tomestone::gpu::backend_from_args(args).unwrap_or(config.graphics_backend);
+    let selected = tomestone::gpu::select_backends(preferred);
+    for backends in tomestone::gpu::launch_attempts(selected) {
+        let result = eframe::run_native(
+            "tomestone",
+            native_options(backends),
+            Box::new(|cc| {
+                tomestone::setup_fonts(&cc.egui_ctx);
+                // glow 渲染 (无 3D 模式) 时没有 wgpu 状态，App 内各视口显示占位
+                let render_state = cc.wgpu_render_state.clone();
+                Ok(Box::new(tomestone::App::new(render_state)))
+            }),
+        );
+        match (result, backends) {
+            (Ok(()), _) => return,
+            (Err(e), Some(backends)) => {
+                eprintln!("使用图形后端 {:?} 启动失败: {}，尝试下一个", backends, e);
+            }
+            (Err(e), None) => {
+                eprintln!("无 3D 模式启动失败: {}", e);
+            }
+        }
+    }
+
+    rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Error)
+        .set_title("tomestone")
+        .set_description(
+            "无法创建窗口 (已尝试所选后端、OpenGL 与无 3D 模式)。\n\
+             请更新显卡驱动，或使用 --backend vulkan/dx12/gl 指定其他后端。",
+        )
+        .show();
+    std::process::exit(1);
+}
+
+/// 窗口选项: `backends` 为 None 时使用 glow 渲染界面 (无 3D 模式)
+fn native_options(backends: Option<wgpu::Backends>) -> eframe::NativeOptions {
+    let viewport = egui::ViewportBuilder::default()
+        .with_inner_size([1200.0, 700.0])
+        .with_title("FF14 装备浏览器");
+    let Some(backends) = backends else {
+        return eframe::NativeOptions {
+            viewport,
+            renderer: eframe::Renderer::Glow,
+            ..Default::default()
+        };
+    };
+    let mut wgpu_setup = egui_wgpu::WgpuSetupCreateNew::default();
+    wgpu_setup.instance_descriptor.backends = backends;
+    eframe::NativeOptions {
+        viewport,
+        wgpu_options: egui_wgpu::WgpuConfiguration {
+            wgpu_setup: egui_wgpu::WgpuSetup::CreateNew(wgpu_setup),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
 }
@@ -0,0 +1,70 @@
+use crate::domain::AppPage;
+
+/// 历史记录上限，超出后丢弃最早的记录
+const MAX_ENTRIES: usize = 100;
+
+/// 一条导航记录: 页面 + 该页面选中的物品 (row_id)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NavEntry {
+    pub page: AppPage,
+    pub row_id: Option<u32>,
+}
+
+/// 跨页面的前进/后退历史
+#[derive(Default)]
+pub struct NavHistory {
+    entries: Vec<NavEntry>,
+    /// 当前所在记录的下标 (entries 为空时无意义)
+    cursor: usize,
+}
+
+impl NavHistory {
+    pub fn current(&self) -> Option<NavEntry> {
+        self.entries.get(self.cursor).copied()
+    }
+
+    /// 记录一次导航；与当前记录相同时忽略，位于历史中间时丢弃其后的记录
+    pub fn record(&mut self, entry: NavEntry) {
+        if self.current() == Some(entry) {
+            return;
+        }
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.cursor + 1);
+        }
+        self.entries.push(entry);
+        if self.entries.len() > MAX_ENTRIES {
+            let overflow = self.entries.len() - MAX_ENTRIES;
+            self.entries.drain(..overflow);
+        }
+        self.cursor = self.entries.len() - 1;
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.cursor > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.cursor + 1 < self.entries.len()
+    }
+
+    pub fn back(&mut self) -> Option<NavEntry> {
+        if !self.can_go_back() {
+            return None;
+        }
+        self.cursor -= 1;
+        self.current()
+    }
+
+    pub fn forward(&mut self) -> Option<NavEntry> {
+        if !self.can_go_forward() {
+            return None;
+        }
+        self.cursor += 1;
+        self.current()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.cursor = 0;
+    }
+}
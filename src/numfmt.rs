@@ -0,0 +1,135 @@
+//! 金币与大数量的显示格式
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{current_lang, Lang};
+
+/// 数字显示风格
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NumberStyle {
+    /// 千分位分隔 (1,234,567)
+    #[default]
+    Grouped,
+    /// 万/亿分组 (123万4567)，仅中文界面生效，其他语言按千分位显示
+    Wan,
+    /// 不分隔 (1234567)
+    Raw,
+}
+
+impl NumberStyle {
+    /// 设置页面中的示例文本
+    pub fn example(&self) -> &'static str {
+        match self {
+            Self::Grouped => "1,234,567",
+            Self::Wan => "123万4567",
+            Self::Raw => "1234567",
+        }
+    }
+}
+
+pub const NUMBER_STYLES: [NumberStyle; 3] =
+    [NumberStyle::Grouped, NumberStyle::Wan, NumberStyle::Raw];
+
+/// 格式化数量
+pub fn fmt_count(n: u64, style: NumberStyle) -> String {
+    match style {
+        NumberStyle::Raw => n.to_string(),
+        NumberStyle::Wan if current_lang() == Lang::Zh => group_wan(n),
+        NumberStyle::Grouped | NumberStyle::Wan => group_thousands(n),
+    }
+}
+
+/// 格式化金币 (带 G 后缀)
+pub fn fmt_gil(n: u64, style: NumberStyle) -> String {
+    format!("{}G", fmt_count(n, style))
+}
+
+/// 千分位分隔 (12400 -> "12,400")
+fn group_thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// 万/亿分组 (1234567 -> "123万4567", 10000 -> "1万")
+/// 高位之后的段补足四位，避免 "1万5" 这类歧义写法
+fn group_wan(n: u64) -> String {
+    const UNITS: [(u64, &str); 2] = [(100_000_000, "亿"), (10_000, "万")];
+    let mut out = String::new();
+    let mut rest = n;
+    for (unit, name) in UNITS {
+        let part = rest / unit;
+        rest %= unit;
+        if part == 0 {
+            continue;
+        }
+        if out.is_empty() {
+            out.push_str(&part.to_string());
+        } else {
+            out.push_str(&format!("{:04}", part));
+        }
+        out.push_str(name);
+    }
+    if out.is_empty() {
+        rest.to_string()
+    } else if rest > 0 {
+        format!("{}{:04}", out, rest)
+    } else {
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thousands_grouping() {
+        let cases = [
+            (0, "0"),
+            (999, "999"),
+            (1000, "1,000"),
+            (12400, "12,400"),
+            (1234567, "1,234,567"),
+            (u64::MAX, "18,446,744,073,709,551,615"),
+        ];
+        for (n, expected) in cases {
+            assert_eq!(fmt_count(n, NumberStyle::Grouped), expected);
+        }
+    }
+
+    #[test]
+    fn raw_has_no_separator() {
+        assert_eq!(fmt_count(1234567, NumberStyle::Raw), "1234567");
+        assert_eq!(fmt_gil(0, NumberStyle::Raw), "0G");
+    }
+
+    #[test]
+    fn gil_appends_suffix() {
+        assert_eq!(fmt_gil(12400, NumberStyle::Grouped), "12,400G");
+    }
+
+    #[test]
+    fn wan_grouping_pads_lower_segments() {
+        let cases = [
+            (0, "0"),
+            (9999, "9999"),
+            (10000, "1万"),
+            (15000, "1万5000"),
+            (1234567, "123万4567"),
+            (100_000_000, "1亿"),
+            (100_000_005, "1亿0005"),
+            (123_456_789, "1亿2345万6789"),
+            (100_050_000, "1亿0005万"),
+        ];
+        for (n, expected) in cases {
+            assert_eq!(group_wan(n), expected, "{}", n);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! FFXIV Teamcraft 清单导出 (纯文本清单与导入链接)
+
+/// 清单中的一项
+pub struct TeamcraftEntry<'a> {
+    pub item_id: u32,
+    /// 制作该物品使用的配方 (Recipe row_id)，非制作物品为 None
+    pub recipe_id: Option<u32>,
+    pub name: &'a str,
+    pub quantity: u32,
+}
+
+/// "数量x 名称" 纯文本清单，每行一项
+pub fn format_text_list(entries: &[TeamcraftEntry<'_>]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&format!("{}x {}\n", entry.quantity, entry.name));
+    }
+    out
+}
+
+/// Teamcraft 导入链接: `itemId,recipeId,数量` 以分号连接后 base64 编码
+/// (按物品 ID 匹配，不依赖物品名的语言)
+pub fn import_url(entries: &[TeamcraftEntry<'_>]) -> String {
+    let payload = entries
+        .iter()
+        .map(|e| {
+            let recipe = e
+                .recipe_id
+                .map_or_else(|| "null".to_string(), |id| id.to_string());
+            format!("{},{},{}", e.item_id, recipe, e.quantity)
+        })
+        .collect::<Vec<_>>()
+        .join(";");
+    format!("https://ffxivteamcraft.com/import/{}", base64_encode(payload.as_bytes()))
+}
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[((n >> (18 - 6 * i)) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        item_id: u32,
+        recipe_id: Option<u32>,
+        name: &str,
+        quantity: u32,
+    ) -> TeamcraftEntry<'_> {
+        TeamcraftEntry {
+            item_id,
+            recipe_id,
+            name,
+            quantity,
+        }
+    }
+
+    /// 测试用的 base64 解码 (仅标准字母表)
+    fn base64_decode(text: &str) -> Vec<u8> {
+        const TABLE: &[u8; 64] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = Vec::new();
+        for chunk in text.as_bytes().chunks(4) {
+            let mut n = 0u32;
+            let mut len = 0;
+            for &c in chunk {
+                n <<= 6;
+                if c != b'=' {
+                    n |= TABLE.iter().position(|&t| t == c).unwrap() as u32;
+                    len += 1;
+                }
+            }
+            out.extend_from_slice(&n.to_be_bytes()[1..len]);
+        }
+        out
+    }
+
+    #[test]
+    fn base64_rfc4648_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in cases {
+            assert_eq!(base64_encode(plain.as_bytes()), encoded);
+            assert_eq!(base64_decode(encoded), plain.as_bytes());
+        }
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(base64_decode(&base64_encode(&bytes)), bytes);
+    }
+
+    #[test]
+    fn text_list_one_line_per_entry() {
+        let entries = [
+            entry(5057, Some(100), "Bronze Ingot", 3),
+            entry(5106, None, "Copper Ore", 12),
+        ];
+        assert_eq!(
+            format_text_list(&entries),
+            "3x Bronze Ingot\n12x Copper Ore\n"
+        );
+        assert_eq!(format_text_list(&[]), "");
+    }
+
+    #[test]
+    fn import_url_round_trips() {
+        let entries = [
+            entry(5057, Some(100), "铜锭", 3),
+            entry(5106, None, "铜矿", 12),
+        ];
+        let url = import_url(&entries);
+        let encoded = url
+            .strip_prefix("https://ffxivteamcraft.com/import/")
+            .expect("导入链接前缀");
+        let payload = String::from_utf8(base64_decode(encoded)).unwrap();
+        assert_eq!(payload, "5057,100,3;5106,null,12");
+
+        // 按 Teamcraft 的格式解析回来，结果与导出的条目一致
+        let parsed: Vec<(u32, Option<u32>, u32)> = payload
+            .split(';')
+            .map(|item| {
+                let fields: Vec<&str> = item.split(',').collect();
+                assert_eq!(fields.len(), 3, "{}", item);
+                let recipe = (fields[1] != "null").then(|| fields[1].parse().unwrap());
+                (
+                    fields[0].parse().unwrap(),
+                    recipe,
+                    fields[2].parse().unwrap(),
+                )
+            })
+            .collect();
+        let expected: Vec<(u32, Option<u32>, u32)> = entries
+            .iter()
+            .map(|e| (e.item_id, e.recipe_id, e.quantity))
+            .collect();
+        assert_eq!(parsed, expected);
+    }
+}
@@ -0,0 +1,571 @@
+//! 装备模型缩略图: 在后台线程中用独立的离屏渲染器生成，并按 row_id + 游戏版本缓存到磁盘
+//!
+//! 模型加载、渲染、读回与磁盘缓存读写都在后台线程进行，界面线程每帧只收取完成的图像并注册纹理。
+//! 列表行的悬停预览也交给同一线程，与缩略图共享并发配额且优先级最低
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+
+use eframe::egui;
+use tomestone_render::{Camera, HeadlessRenderer, ModelType, SceneSettings, TextureData};
+
+use crate::config;
+use crate::domain::GameItem;
+use crate::game::{compute_bounding_box, load_mdl_with_fallback, load_mesh_textures, GameData};
+
+/// 缩略图边长 (像素)
+pub const THUMBNAIL_SIZE: u32 = 128;
+/// 同时交给后台线程的渲染任务上限: 完成一个再按当前可见顺序补一个，滚出视野的格子不会积压
+const MAX_IN_FLIGHT: usize = 2;
+/// 默认内存中保留的缩略图数量上限
+const DEFAULT_BUDGET: usize = 512;
+/// 悬停预览边长 (像素)
+pub const HOVER_PREVIEW_SIZE: u32 = 200;
+/// 两次悬停预览渲染之间的最小间隔 (秒)，避免快速移动鼠标时连续渲染
+const HOVER_RENDER_INTERVAL: f64 = 0.15;
+
+pub enum ThumbnailStatus {
+    Ready(egui::TextureId),
+    /// 模型加载失败或物品没有独立模型 (使用角色身体模型)，调用方应回退到图标
+    Failed,
+    Pending,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JobKind {
+    Thumbnail,
+    Hover,
+}
+
+/// 交给后台线程的渲染任务
+struct RenderJob {
+    kind: JobKind,
+    item: GameItem,
+}
+
+/// 后台线程的渲染结果，image 为 None 表示加载或渲染失败
+struct RenderResult {
+    kind: JobKind,
+    row_id: u32,
+    image: Option<egui::ColorImage>,
+}
+
+/// 后台渲染线程的收发端，丢弃时任务通道关闭，线程随之退出
+struct Worker {
+    jobs: Sender<RenderJob>,
+    results: Receiver<RenderResult>,
+}
+
+impl Worker {
+    /// 启动后台线程: GameData 不能跨线程共享，按安装目录 (演示模式下为演示数据) 重新打开
+    fn spawn(render_state: &egui_wgpu::RenderState, game: &GameData, ctx: egui::Context) -> Self {
+        let cache_dir = config::thumbnails_dir().join(game.game_version());
+        let install_dir = (!game.is_mock()).then(|| game.install_dir());
+        let device = render_state.device.clone();
+        let queue = render_state.queue.clone();
+        let (job_tx, job_rx) = std::sync::mpsc::channel();
+        let (result_tx, result_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let game = match install_dir {
+                Some(dir) => GameData::new(&dir),
+                None => GameData::mock(),
+            };
+            run_worker(device, queue, &game, &cache_dir, &ctx, job_rx, result_tx);
+        });
+        Self {
+            jobs: job_tx,
+            results: result_rx,
+        }
+    }
+}
+
+/// 后台线程主循环: 逐个处理任务，每完成一个通知界面重绘
+fn run_worker(
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    game: &GameData,
+    cache_dir: &Path,
+    ctx: &egui::Context,
+    jobs: Receiver<RenderJob>,
+    results: Sender<RenderResult>,
+) {
+    let _ = std::fs::create_dir_all(cache_dir);
+    // 悬停预览专用渲染器，尺寸不同，避免与缩略图反复重建渲染目标
+    let renderers = HeadlessRenderer::new(device.clone(), queue.clone())
+        .and_then(|renderer| HeadlessRenderer::new(device, queue).map(|hover| (renderer, hover)));
+    let (mut renderer, mut hover_renderer) = match renderers {
+        Ok(renderers) => renderers,
+        Err(e) => {
+            // 已发出的任务全部记为失败，界面回退到图标
+            eprintln!("缩略图渲染器创建失败: {}", e);
+            for job in jobs {
+                let result = RenderResult {
+                    kind: job.kind,
+                    row_id: job.item.row_id,
+                    image: None,
+                };
+                if results.send(result).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+            return;
+        }
+    };
+    renderer.set_model_type(ModelType::Equipment);
+    hover_renderer.set_model_type(ModelType::Equipment);
+    let mut camera = Camera::default();
+    let scene = SceneSettings::default();
+
+    for job in jobs {
+        // 单个模型出错只记为失败，不能让线程退出
+        let image = crate::crash::guard(|| match job.kind {
+            JobKind::Thumbnail => load_or_render(
+                &mut renderer,
+                &mut camera,
+                &scene,
+                cache_dir,
+                &job.item,
+                game,
+            ),
+            JobKind::Hover => render_pixels(
+                &mut hover_renderer,
+                &mut camera,
+                &scene,
+                &job.item,
+                game,
+                HOVER_PREVIEW_SIZE,
+            )
+            .map(|pixels| pixels_to_image(&pixels)),
+        })
+        .unwrap_or(None);
+        let result = RenderResult {
+            kind: job.kind,
+            row_id: job.item.row_id,
+            image,
+        };
+        if results.send(result).is_err() {
+            break;
+        }
+        ctx.request_repaint();
+    }
+}
+
+/// 按可见顺序挑出可以开始渲染的缩略图: 跳过已在渲染中的，总数不超过 MAX_IN_FLIGHT
+fn next_jobs(queue: &VecDeque<(u32, usize)>, in_flight: &HashSet<u32>) -> Vec<(u32, usize)> {
+    queue
+        .iter()
+        .filter(|(row_id, _)| !in_flight.contains(row_id))
+        .take(MAX_IN_FLIGHT.saturating_sub(in_flight.len()))
+        .copied()
+        .collect()
+}
+
+pub struct ThumbnailCache {
+    /// None 表示没有 3D 渲染 (无 wgpu 的界面模式)，所有缩略图回退到图标
+    render_state: Option<egui_wgpu::RenderState>,
+    /// 后台渲染线程，首次有任务时启动
+    worker: Option<Worker>,
+    /// row_id -> (纹理, 最近使用帧号)
+    textures: HashMap<u32, (egui::TextureHandle, u64)>,
+    failed: HashSet<u32>,
+    /// 本帧可见但尚未生成的 (row_id, all_items 下标)，按绘制顺序
+    queue: VecDeque<(u32, usize)>,
+    queued: HashSet<u32>,
+    /// 已交给后台线程、尚未收到结果的缩略图
+    in_flight: HashSet<u32>,
+    frame: u64,
+    /// 内存中保留的缩略图数量上限，超出时淘汰最久未使用的
+    pub budget: usize,
+    /// 最近一次悬停预览 (row_id, 纹理)，只保留一张
+    hover_texture: Option<(u32, egui::TextureHandle)>,
+    /// 等待渲染的悬停预览 (row_id, all_items 下标)，新请求覆盖旧请求
+    hover_request: Option<(u32, usize)>,
+    /// 正在后台渲染的悬停预览
+    hover_in_flight: Option<u32>,
+    hover_last_render: f64,
+}
+
+impl ThumbnailCache {
+    pub fn new(render_state: Option<egui_wgpu::RenderState>) -> Self {
+        Self {
+            render_state,
+            worker: None,
+            textures: HashMap::new(),
+            failed: HashSet::new(),
+            queue: VecDeque::new(),
+            queued: HashSet::new(),
+            in_flight: HashSet::new(),
+            frame: 0,
+            budget: DEFAULT_BUDGET,
+            hover_texture: None,
+            hover_request: None,
+            hover_in_flight: None,
+            hover_last_render: 0.0,
+        }
+    }
+
+    /// 每帧绘制网格前调用: 清空上一帧的请求队列，只保留当前可见的格子
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+        self.queue.clear();
+        self.queued.clear();
+    }
+
+    /// 查询缩略图，未生成时加入渲染队列
+    pub fn request(&mut self, item: &GameItem, global_idx: usize) -> ThumbnailStatus {
+        if let Some((tex, last_used)) = self.textures.get_mut(&item.row_id) {
+            *last_used = self.frame;
+            return ThumbnailStatus::Ready(tex.id());
+        }
+        // 使用身体模型的物品不排队渲染，也不计入失败记录
+        if self.render_state.is_none()
+            || item.uses_body_model()
+            || crate::crash::is_item_skipped(item.row_id)
+            || self.failed.contains(&item.row_id)
+        {
+            return ThumbnailStatus::Failed;
+        }
+        if self.queued.insert(item.row_id) {
+            self.queue.push_back((item.row_id, global_idx));
+        }
+        ThumbnailStatus::Pending
+    }
+
+    /// 查询悬停预览: 已渲染时返回预览纹理，否则登记渲染请求并返回内存中已有的缩略图
+    /// (不会为缩略图排队)，两者都没有时返回 None，由调用方回退到图标
+    pub fn request_hover(&mut self, item: &GameItem, global_idx: usize) -> Option<egui::TextureId> {
+        if let Some((row_id, tex)) = &self.hover_texture {
+            if *row_id == item.row_id {
+                return Some(tex.id());
+            }
+        }
+        if self.render_state.is_some()
+            && !item.uses_body_model()
+            && !crate::crash::is_item_skipped(item.row_id)
+            && !self.failed.contains(&item.row_id)
+            && self.hover_in_flight != Some(item.row_id)
+        {
+            self.hover_request = Some((item.row_id, global_idx));
+        }
+        self.textures.get(&item.row_id).map(|(tex, _)| tex.id())
+    }
+
+    /// 鼠标离开列表行时取消尚未开始的悬停预览
+    pub fn cancel_hover(&mut self) {
+        self.hover_request = None;
+    }
+
+    /// 当前可见但尚未生成的缩略图数量
+    pub fn pending_count(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// 收取后台线程完成的图像并注册纹理，再按可见顺序补充渲染任务；界面线程不做渲染与读回
+    pub fn process(&mut self, ctx: &egui::Context, all_items: &[GameItem], game: &GameData) {
+        self.receive_results(ctx);
+        if self.queue.is_empty() && self.hover_request.is_none() {
+            return;
+        }
+        let Some(render_state) = &self.render_state else {
+            return;
+        };
+        let worker = self
+            .worker
+            .get_or_insert_with(|| Worker::spawn(render_state, game, ctx.clone()));
+
+        for (row_id, global_idx) in next_jobs(&self.queue, &self.in_flight) {
+            let Some(item) = all_items.get(global_idx) else {
+                continue;
+            };
+            let job = RenderJob {
+                kind: JobKind::Thumbnail,
+                item: item.clone(),
+            };
+            if worker.jobs.send(job).is_err() {
+                // 后台线程已退出，不再重试
+                self.failed.insert(row_id);
+                continue;
+            }
+            self.in_flight.insert(row_id);
+        }
+
+        // 悬停预览优先级最低: 缩略图未占满配额、上一张已完成且距上次足够久时才开始
+        if self.hover_request.is_none()
+            || self.hover_in_flight.is_some()
+            || self.in_flight.len() >= MAX_IN_FLIGHT
+        {
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let wait = HOVER_RENDER_INTERVAL - (now - self.hover_last_render);
+        if wait > 0.0 {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(wait));
+            return;
+        }
+        let Some((row_id, global_idx)) = self.hover_request.take() else {
+            return;
+        };
+        let Some(item) = all_items.get(global_idx) else {
+            return;
+        };
+        let job = RenderJob {
+            kind: JobKind::Hover,
+            item: item.clone(),
+        };
+        if worker.jobs.send(job).is_ok() {
+            self.hover_in_flight = Some(row_id);
+            self.hover_last_render = now;
+        }
+    }
+
+    fn receive_results(&mut self, ctx: &egui::Context) {
+        let Some(worker) = &self.worker else {
+            return;
+        };
+        let results: Vec<RenderResult> = worker.results.try_iter().collect();
+        for result in results {
+            let row_id = result.row_id;
+            match (result.kind, result.image) {
+                (JobKind::Thumbnail, Some(image)) => {
+                    self.in_flight.remove(&row_id);
+                    let handle = ctx.load_texture(
+                        format!("thumb_{}", row_id),
+                        image,
+                        egui::TextureOptions::LINEAR,
+                    );
+                    self.textures.insert(row_id, (handle, self.frame));
+                    self.evict_over_budget();
+                }
+                (JobKind::Hover, Some(image)) => {
+                    self.hover_in_flight = None;
+                    let handle = ctx.load_texture(
+                        format!("hover_preview_{}", row_id),
+                        image,
+                        egui::TextureOptions::LINEAR,
+                    );
+                    self.hover_texture = Some((row_id, handle));
+                }
+                (kind, None) => {
+                    if kind == JobKind::Thumbnail {
+                        self.in_flight.remove(&row_id);
+                    } else {
+                        self.hover_in_flight = None;
+                    }
+                    self.failed.insert(row_id);
+                }
+            }
+        }
+    }
+
+    fn evict_over_budget(&mut self) {
+        while self.textures.len() > self.budget {
+            let Some(oldest) = self
+                .textures
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(id, _)| *id)
+            else {
+                break;
+            };
+            self.textures.remove(&oldest);
+        }
+    }
+}
+
+/// 优先读取磁盘缓存，没有时渲染并写入缓存
+fn load_or_render(
+    renderer: &mut HeadlessRenderer,
+    camera: &mut Camera,
+    scene: &SceneSettings,
+    cache_dir: &Path,
+    item: &GameItem,
+    game: &GameData,
+) -> Option<egui::ColorImage> {
+    let path = cache_dir.join(format!("{}.png", item.row_id));
+    if let Ok(img) = image::open(&path) {
+        let rgba = img.to_rgba8();
+        let size = [rgba.width() as usize, rgba.height() as usize];
+        return Some(egui::ColorImage::from_rgba_unmultiplied(
+            size,
+            rgba.as_raw(),
+        ));
+    }
+
+    let pixels = render_pixels(renderer, camera, scene, item, game, THUMBNAIL_SIZE)?;
+    if let Err(e) = image::save_buffer(
+        &path,
+        &pixels.rgba,
+        pixels.width,
+        pixels.height,
+        image::ColorType::Rgba8,
+    ) {
+        eprintln!("缩略图缓存写入失败 {}: {}", path.display(), e);
+    }
+    Some(pixels_to_image(&pixels))
+}
+
+/// 加载物品模型并离屏渲染一张正方形图像，渲染后释放网格，避免常驻显存
+fn render_pixels(
+    renderer: &mut HeadlessRenderer,
+    camera: &mut Camera,
+    scene: &SceneSettings,
+    item: &GameItem,
+    game: &GameData,
+    size: u32,
+) -> Option<TextureData> {
+    let _load = crate::crash::ItemLoadGuard::new(item.row_id);
+    let result = match load_mdl_with_fallback(game, &item.model_paths()) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("缩略图模型加载失败 [{}] {}: {}", item.row_id, item.name, e);
+            return None;
+        }
+    };
+    let bbox = compute_bounding_box(&result.meshes);
+    let load_result = load_mesh_textures(
+        game,
+        &result.material_names,
+        &result.meshes,
+        item.set_id(),
+        item.variant_id(),
+    );
+    let geometry: Vec<(&[tomestone_render::Vertex], &[u16])> = result
+        .meshes
+        .iter()
+        .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
+        .collect();
+
+    *camera = Camera::default();
+    camera.focus_on(&bbox);
+    renderer.render_to_image(
+        &geometry,
+        &load_result.mesh_textures,
+        camera,
+        scene,
+        [size, size],
+    )
+}
+
+fn pixels_to_image(pixels: &TextureData) -> egui::ColorImage {
+    egui::ColorImage::from_rgba_unmultiplied(
+        [pixels.width as usize, pixels.height as usize],
+        &pixels.rgba,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visible(ids: &[u32]) -> VecDeque<(u32, usize)> {
+        ids.iter().map(|&id| (id, id as usize)).collect()
+    }
+
+    #[test]
+    fn dispatches_visible_items_in_order_up_to_limit() {
+        let queue = visible(&[5, 3, 9]);
+        assert_eq!(next_jobs(&queue, &HashSet::new()), [(5, 5), (3, 3)]);
+        assert_eq!(next_jobs(&queue, &HashSet::from([5])), [(3, 3)]);
+        assert!(next_jobs(&queue, &HashSet::from([5, 3])).is_empty());
+        // 已滚出视野的在途任务仍占用配额
+        assert_eq!(next_jobs(&queue, &HashSet::from([1])), [(5, 5)]);
+        assert!(next_jobs(&queue, &HashSet::from([1, 2])).is_empty());
+        assert!(next_jobs(&VecDeque::new(), &HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn worker_renders_caches_and_survives_failures() {
+        let Ok(headless) = HeadlessRenderer::headless() else {
+            eprintln!("没有可用的 GPU 适配器，跳过");
+            return;
+        };
+        let device = headless.device().clone();
+        let queue = headless.queue().clone();
+        let cache_dir =
+            std::env::temp_dir().join(format!("tomestone-thumbnails-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        std::fs::create_dir_all(&cache_dir).unwrap();
+
+        let game = GameData::mock();
+        let item = game
+            .load_all_items(&game.load_ui_category_names())
+            .into_iter()
+            .find(|i| i.is_equipment() && !i.uses_body_model())
+            .expect("演示数据应包含装备");
+        // 没有任何候选模型路径，加载失败
+        let broken = GameItem {
+            row_id: 900_001,
+            model_main: 0,
+            ..item.clone()
+        };
+        // 同样没有模型，但磁盘缓存中已有缩略图
+        let cached = GameItem {
+            row_id: 900_002,
+            ..broken.clone()
+        };
+        image::save_buffer(
+            cache_dir.join("900002.png"),
+            &[255; 3 * 3 * 4],
+            3,
+            3,
+            image::ColorType::Rgba8,
+        )
+        .unwrap();
+
+        let (job_tx, job_rx) = std::sync::mpsc::channel();
+        let (result_tx, result_rx) = std::sync::mpsc::channel();
+        let dir = cache_dir.clone();
+        let worker = std::thread::spawn(move || {
+            let game = GameData::mock();
+            let ctx = egui::Context::default();
+            run_worker(device, queue, &game, &dir, &ctx, job_rx, result_tx);
+        });
+        for (kind, item) in [
+            (JobKind::Thumbnail, broken),
+            (JobKind::Thumbnail, item.clone()),
+            (JobKind::Hover, item.clone()),
+            (JobKind::Thumbnail, cached),
+        ] {
+            job_tx.send(RenderJob { kind, item }).unwrap();
+        }
+        let results: Vec<RenderResult> = result_rx.iter().take(4).collect();
+
+        assert_eq!(results[0].row_id, 900_001);
+        assert!(results[0].image.is_none());
+        assert!(!cache_dir.join("900001.png").exists());
+
+        assert_eq!(results[1].row_id, item.row_id);
+        let thumbnail = results[1].image.as_ref().expect("缩略图应渲染成功");
+        assert_eq!(thumbnail.size, [THUMBNAIL_SIZE as usize; 2]);
+        assert!(cache_dir.join(format!("{}.png", item.row_id)).is_file());
+
+        assert_eq!(results[2].kind, JobKind::Hover);
+        let hover = results[2].image.as_ref().expect("悬停预览应渲染成功");
+        assert_eq!(hover.size, [HOVER_PREVIEW_SIZE as usize; 2]);
+
+        assert_eq!(results[3].image.as_ref().map(|i| i.size), Some([3, 3]));
+
+        // 丢弃任务通道后线程退出
+        drop(job_tx);
+        worker.join().unwrap();
+        let _ = std::fs::remove_dir_all(&cache_dir);
+    }
+
+    #[test]
+    fn without_renderer_every_item_falls_back_to_icon() {
+        let mut cache = ThumbnailCache::new(None);
+        let item = GameItem {
+            equip_slot_category: 3,
+            model_main: 1,
+            ..GameItem::named(1, "头盔")
+        };
+        cache.begin_frame();
+        assert!(matches!(cache.request(&item, 0), ThumbnailStatus::Failed));
+        assert_eq!(cache.request_hover(&item, 0), None);
+        assert_eq!(cache.pending_count(), 0);
+        cache.process(&egui::Context::default(), &[item], &GameData::mock());
+        assert!(cache.worker.is_none());
+    }
+}
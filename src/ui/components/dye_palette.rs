@@ -1,6 +1,13 @@
-use crate::domain::{shade_group_name, StainEntry, SHADE_ORDER};
+use crate::domain::{shade_group_name, shade_groups, StainEntry};
 use eframe::egui;
 
+/// 染色区域预览开关，返回是否切换
+pub fn show_dye_region_toggle(ui: &mut egui::Ui, show_regions: &mut bool) -> bool {
+    ui.checkbox(show_regions, "染色区域")
+        .on_hover_text("红色: 通道1，蓝色: 通道2，灰色: 不可染色")
+        .changed()
+}
+
 pub fn show_dye_palette(
     ui: &mut egui::Ui,
     stains: &[StainEntry],
@@ -8,6 +15,7 @@ pub fn show_dye_palette(
     active_dye_channel: &mut usize,
     selected_shade: &mut u8,
     is_dual_dye: bool,
+    from_default: bool,
 ) -> bool {
     let prev_stains = *selected_stain_ids;
     let ch = *active_dye_channel;
@@ -27,7 +35,7 @@ pub fn show_dye_palette(
     }
 
     ui.horizontal_wrapped(|ui| {
-        for &shade in SHADE_ORDER {
+        for shade in shade_groups(stains) {
             let label = shade_group_name(shade);
             if ui
                 .selectable_label(*selected_shade == shade, label)
@@ -88,6 +96,8 @@ pub fn show_dye_palette(
         }
     });
 
+    show_color_match(ui, stains, &mut selected_stain_ids[ch]);
+
     ui.horizontal(|ui| {
         let current_id = selected_stain_ids[ch];
         if current_id == 0 {
@@ -98,7 +108,66 @@ pub fn show_dye_palette(
             ui.painter().rect_filled(rect, 2.0, color);
             ui.label(format!("当前: {}", stain.name));
         }
+        if from_default && current_id != 0 {
+            ui.label(egui::RichText::new("默认").small().weak())
+                .on_hover_text("来自设置中的默认预览染料");
+        }
     });
 
     prev_stains != *selected_stain_ids
 }
+
+/// 颜色匹配显示的结果数
+const COLOR_MATCH_COUNT: usize = 8;
+
+/// 按颜色查找染料: 选取目标颜色后列出 CIEDE2000 色差最小的染料，点击即选用
+/// (目标颜色保存在 egui 临时数据中，不影响调用方状态)
+fn show_color_match(ui: &mut egui::Ui, stains: &[StainEntry], stain_id: &mut u32) {
+    let state_id = ui.id().with("dye_color_match");
+    let mut target: Option<[u8; 3]> = ui.data(|d| d.get_temp(state_id)).flatten();
+
+    ui.horizontal(|ui| {
+        ui.label("按颜色匹配:");
+        let mut picked = target.unwrap_or([128, 128, 128]);
+        if ui
+            .color_edit_button_srgb(&mut picked)
+            .on_hover_text("选择目标颜色，查找最接近的染料")
+            .changed()
+        {
+            target = Some(picked);
+        }
+        if target.is_some() && ui.small_button("✕").on_hover_text("清除匹配").clicked() {
+            target = None;
+        }
+    });
+
+    if let Some(color) = target {
+        let colors: Vec<[u8; 3]> = stains.iter().map(|s| s.color).collect();
+        let matches = crate::color::closest_colors(color, &colors, COLOR_MATCH_COUNT);
+        ui.horizontal_wrapped(|ui| {
+            ui.label(egui::RichText::new("匹配结果").small().weak());
+            for (idx, distance) in matches {
+                let stain = &stains[idx];
+                let [r, g, b] = stain.color;
+                let (rect, resp) =
+                    ui.allocate_exact_size(egui::vec2(20.0, 20.0), egui::Sense::click());
+                ui.painter()
+                    .rect_filled(rect, 2.0, egui::Color32::from_rgb(r, g, b));
+                if *stain_id == stain.id {
+                    ui.painter().rect_stroke(
+                        rect,
+                        2.0,
+                        egui::Stroke::new(2.0, egui::Color32::WHITE),
+                        egui::StrokeKind::Outside,
+                    );
+                }
+                if resp.clicked() {
+                    *stain_id = stain.id;
+                }
+                resp.on_hover_text(format!("{} (ΔE {:.1})", stain.name, distance));
+            }
+        });
+    }
+
+    ui.data_mut(|d| d.insert_temp(state_id, target));
+}
@@ -2,18 +2,186 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 
 use eframe::egui;
 
-use super::item_list;
-use crate::domain::{EquipSlot, EquipmentSet, GameItem, SortOrder, ViewMode};
+use super::item_list::{self, FilterCache};
+use crate::domain::{
+    representative_item_index, DyeFilter, EquipSlot, EquipmentSet, GameItem, SortOrder, ViewMode,
+    DYE_FILTERS,
+};
 use crate::game::GameData;
+use crate::thumbnail::{ThumbnailCache, ThumbnailStatus, HOVER_PREVIEW_SIZE};
 
 /// 套装分组装备列表的共享状态
 pub struct EquipmentListState {
     pub search: String,
     pub sort_order: SortOrder,
     pub expanded_sets: HashSet<u16>,
+    /// 开始搜索前的展开状态，清空搜索时恢复
+    expansion_before_search: Option<HashSet<u16>>,
+    /// 已按此搜索词自动展开过匹配套装 (小写)
+    auto_expanded_search: String,
+    /// 匹配套装超过 AUTO_EXPAND_LIMIT，只自动展开了前一部分
+    auto_expand_capped: bool,
     pub view_mode: ViewMode,
-    /// 图标视图中的图标大小 (像素)
+    /// 图标视图中的图标大小 (逻辑点)
     pub icon_size: f32,
+    /// 图标视图中以模型缩略图代替图标
+    pub use_thumbnails: bool,
+    pub dye_filter: DyeFilter,
+    /// 隐藏有性别/种族限制的装备
+    pub hide_restricted: bool,
+    /// 列表视图的套装分组缓存
+    group_cache: FilterCache<FilterKey, Vec<SetGroup>>,
+    /// 图标视图的筛选结果缓存
+    grid_cache: FilterCache<FilterKey, Vec<usize>>,
+    /// 悬停意图: (悬停中的物品下标, 开始悬停的时间)
+    hover_intent: Option<(usize, f64)>,
+    /// 待定位的物品 row_id: 展开所在套装并滚动到该行，完成后清除
+    reveal_id: Option<u32>,
+    /// 套装标题提供 "导出套装素材" 右键菜单 (由使用方开启)
+    pub kit_export_enabled: bool,
+    /// 套装右键菜单请求导出素材: (组名, 物品下标)，由页面取走处理
+    kit_export_request: Option<(String, Vec<usize>)>,
+    /// 点击了列表头的染料色块，由页面取走并清除预览染色
+    clear_dye_requested: bool,
+}
+
+/// 悬停多久后显示模型预览弹窗 (秒)
+const HOVER_PREVIEW_DELAY: f64 = 0.4;
+
+/// 搜索时最多自动展开的套装数，过多时展开反而难以浏览
+const AUTO_EXPAND_LIMIT: usize = 200;
+
+/// 筛选缓存键: (搜索词小写, 槽位, 染色筛选, 隐藏限定装备, 排序, 装备下标列表标识, 已探测染色数)
+/// 后台染色探测会逐步补全数据，因此探测数量变化时也需重新筛选
+type FilterKey = (
+    String,
+    Option<EquipSlot>,
+    DyeFilter,
+    bool,
+    SortOrder,
+    (usize, usize),
+    usize,
+);
+
+/// 套装分组: (set_id, equipment_sets 下标, 含装备, 含饰品, 物品在 all_items 中的下标, 标题图标)
+///
+/// 组名在绘制时由 group_name 取得，不随缓存复制。
+/// 标题图标为按槽位顺序每个槽位取一件物品的 icon_id，最多 GROUP_ICON_LIMIT 个
+type SetGroup = (u16, Option<usize>, bool, bool, Vec<usize>, Vec<u32>);
+
+/// 套装分组的显示名: 套装表中的名称，不在套装表中时取第一件物品的名称
+fn group_name<'a>(
+    set_idx: Option<usize>,
+    items_in_set: &[usize],
+    equipment_sets: &'a [EquipmentSet],
+    all_items: &'a [GameItem],
+) -> &'a str {
+    match set_idx {
+        Some(set_idx) => &equipment_sets[set_idx].display_name,
+        None => items_in_set
+            .first()
+            .map_or("", |&first| all_items[first].name.as_str()),
+    }
+}
+
+/// 套装标题上最多显示的槽位图标数
+const GROUP_ICON_LIMIT: usize = 5;
+/// 套装标题图标尺寸，不超过标题行高
+const GROUP_ICON_SIZE: f32 = 16.0;
+/// 相邻标题图标的水平间距 (小于图标尺寸，叠放显示)
+const GROUP_ICON_STEP: f32 = 10.0;
+/// 折叠套装标题的悬停预览中最多列出的物品数
+const GROUP_PEEK_LIMIT: usize = 10;
+
+/// 按槽位顺序每个槽位取一件物品的图标
+fn group_icon_ids(all_items: &[GameItem], items_in_set: &[usize]) -> Vec<u32> {
+    let mut by_slot: Vec<(EquipSlot, u32)> = items_in_set
+        .iter()
+        .filter_map(|&i| Some((all_items[i].equip_slot()?, all_items[i].icon_id)))
+        .filter(|(_, icon_id)| *icon_id != 0)
+        .collect();
+    by_slot.sort_by_key(|(slot, _)| *slot as usize);
+    by_slot.dedup_by_key(|(slot, _)| *slot);
+    by_slot
+        .into_iter()
+        .take(GROUP_ICON_LIMIT)
+        .map(|(_, icon_id)| icon_id)
+        .collect()
+}
+
+/// 套装标题前的叠放图标条，未加载到的图标以占位方块代替
+fn show_group_icons(
+    ui: &mut egui::Ui,
+    icon_cache: &mut item_list::IconCache,
+    ctx: &egui::Context,
+    game: &GameData,
+    icon_ids: &[u32],
+) {
+    if icon_ids.is_empty() {
+        return;
+    }
+    let width = GROUP_ICON_SIZE + GROUP_ICON_STEP * (icon_ids.len() - 1) as f32;
+    let (rect, _) =
+        ui.allocate_exact_size(egui::vec2(width, GROUP_ICON_SIZE), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    for (i, &icon_id) in icon_ids.iter().enumerate() {
+        let icon_rect = egui::Rect::from_min_size(
+            rect.min + egui::vec2(GROUP_ICON_STEP * i as f32, 0.0),
+            egui::vec2(GROUP_ICON_SIZE, GROUP_ICON_SIZE),
+        );
+        match item_list::get_or_load_icon_sized(icon_cache, ctx, game, icon_id, GROUP_ICON_SIZE) {
+            Some(icon) => {
+                painter.image(
+                    icon.id(),
+                    icon_rect,
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+            }
+            None => {
+                painter.rect_filled(icon_rect, 2.0, ui.visuals().faint_bg_color);
+                if icon_cache.failure(icon_id).is_some() {
+                    item_list::paint_broken_icon(
+                        &painter,
+                        icon_rect,
+                        ui.visuals().weak_text_color(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// 折叠套装标题的悬停预览: 列出套装中的物品 (槽位 + 图标 + 名称)，返回被点击物品的下标
+fn show_group_peek(
+    ui: &mut egui::Ui,
+    icon_cache: &mut item_list::IconCache,
+    ctx: &egui::Context,
+    game: &GameData,
+    all_items: &[GameItem],
+    items_in_set: &[usize],
+) -> Option<usize> {
+    let mut clicked = None;
+    for &global_idx in items_in_set.iter().take(GROUP_PEEK_LIMIT) {
+        let item = &all_items[global_idx];
+        let slot = item.equip_slot().map_or("", |s| s.slot_abbr());
+        let row = ui
+            .horizontal(|ui| {
+                item_list::show_row_icon(ui, icon_cache, ctx, game, item.icon_id, 16.0);
+                ui.selectable_label(false, format!("[{}] {}", slot, item.name))
+            })
+            .inner;
+        if row.clicked() {
+            clicked = Some(global_idx);
+        }
+    }
+    if items_in_set.len() > GROUP_PEEK_LIMIT {
+        let more = items_in_set.len() - GROUP_PEEK_LIMIT;
+        ui.weak(tr!("list.peek_more").replace("{n}", &more.to_string()));
+    }
+    ui.separator();
+    ui.weak(tr!("list.group_double_click"));
+    clicked
 }
 
 impl EquipmentListState {
@@ -22,10 +190,88 @@ impl EquipmentListState {
             search: String::new(),
             sort_order: SortOrder::ByName,
             expanded_sets: HashSet::new(),
+            expansion_before_search: None,
+            auto_expanded_search: String::new(),
+            auto_expand_capped: false,
             view_mode: ViewMode::List,
             icon_size: 48.0,
+            use_thumbnails: false,
+            dye_filter: DyeFilter::All,
+            hide_restricted: false,
+            group_cache: FilterCache::default(),
+            grid_cache: FilterCache::default(),
+            hover_intent: None,
+            reveal_id: None,
+            kit_export_enabled: false,
+            kit_export_request: None,
+            clear_dye_requested: false,
+        }
+    }
+
+    /// 定位到指定物品 (导航跳转、同套装链接等列表外部的选择时调用)
+    pub fn reveal(&mut self, row_id: u32) {
+        self.reveal_id = Some(row_id);
+    }
+
+    /// 取走套装右键菜单中的导出请求
+    pub fn take_kit_export_request(&mut self) -> Option<(String, Vec<usize>)> {
+        self.kit_export_request.take()
+    }
+
+    /// 取走列表头染料色块的清除请求
+    pub fn take_clear_dye_request(&mut self) -> bool {
+        std::mem::take(&mut self.clear_dye_requested)
+    }
+
+    /// 搜索时自动展开匹配的套装，清空搜索后恢复搜索前的展开状态
+    ///
+    /// `set_groups` 为当前筛选后的套装分组
+    fn sync_search_expansion(&mut self, search_lower: &str, set_groups: &[SetGroup]) {
+        if search_lower.trim().is_empty() {
+            if let Some(saved) = self.expansion_before_search.take() {
+                self.expanded_sets = saved;
+            }
+            self.auto_expanded_search.clear();
+            self.auto_expand_capped = false;
+        } else if search_lower != self.auto_expanded_search {
+            let saved = self
+                .expansion_before_search
+                .get_or_insert_with(|| self.expanded_sets.clone());
+            // 每次搜索词变化都从搜索前的状态重新展开，避免逐字输入时不断累积
+            self.expanded_sets = saved.clone();
+            self.expanded_sets.extend(
+                set_groups
+                    .iter()
+                    .take(AUTO_EXPAND_LIMIT)
+                    .map(|(set_id, ..)| *set_id),
+            );
+            self.auto_expand_capped = set_groups.len() > AUTO_EXPAND_LIMIT;
+            self.auto_expanded_search = search_lower.to_string();
         }
     }
+
+    /// 全部展开只作用于当前筛选后仍有匹配物品的套装
+    fn expand_all(&mut self, set_groups: &[SetGroup]) {
+        self.expanded_sets
+            .extend(set_groups.iter().map(|(set_id, ..)| *set_id));
+    }
+
+    fn filter_key(
+        &self,
+        equipment_indices: &[usize],
+        slot_filter: Option<EquipSlot>,
+        probed_dye_counts: &HashMap<u32, u8>,
+    ) -> FilterKey {
+        (
+            self.search.to_lowercase(),
+            slot_filter,
+            self.dye_filter,
+            self.hide_restricted,
+            self.sort_order,
+            item_list::items_identity(equipment_indices),
+            probed_dye_counts.len(),
+        )
+    }
 }
 
 /// 点击物品时返回的信息
@@ -42,6 +288,57 @@ pub struct HighlightConfig<'a> {
     pub highlighted_ids: &'a HashSet<u32>,
     /// 预览中的物品 ID (用不同颜色显示)
     pub preview_id: Option<u32>,
+    /// "定位" 按钮跳转的物品 ID，为 None 时不显示按钮
+    pub focus_id: Option<u32>,
+    /// 当前预览染料的提示色块，为 None 时不显示
+    pub dye_swatch: Option<DyeSwatch>,
+}
+
+/// 列表中提示当前预览染料的色块: 列表头显示 (可点击清除)，预览物品的行尾再显示一份
+pub struct DyeSwatch {
+    /// 两个通道的颜色，未染色的通道为 None
+    pub colors: [Option<egui::Color32>; 2],
+    /// 悬停提示中的染料名称
+    pub names: String,
+    /// 在行尾显示色块的物品 ID
+    pub item_id: Option<u32>,
+}
+
+/// 色块边长与间距 (逻辑点)
+const DYE_SWATCH_SIZE: f32 = 10.0;
+const DYE_SWATCH_GAP: f32 = 2.0;
+
+/// 从 min 起横向绘制已染色通道的色块
+fn paint_dye_swatch(ui: &egui::Ui, min: egui::Pos2, swatch: &DyeSwatch) {
+    for (i, color) in swatch.colors.iter().flatten().enumerate() {
+        let block = egui::Rect::from_min_size(
+            min + egui::vec2((DYE_SWATCH_SIZE + DYE_SWATCH_GAP) * i as f32, 0.0),
+            egui::Vec2::splat(DYE_SWATCH_SIZE),
+        );
+        ui.painter().rect_filled(block, 2.0, *color);
+        ui.painter().rect_stroke(
+            block,
+            2.0,
+            ui.visuals().widgets.noninteractive.fg_stroke,
+            egui::StrokeKind::Inside,
+        );
+    }
+}
+
+/// 在布局中放置色块，返回响应 (没有已染色通道时不占位)
+fn show_dye_swatch(
+    ui: &mut egui::Ui,
+    swatch: &DyeSwatch,
+    sense: egui::Sense,
+) -> Option<egui::Response> {
+    let count = swatch.colors.iter().flatten().count();
+    if count == 0 {
+        return None;
+    }
+    let width = DYE_SWATCH_SIZE * count as f32 + DYE_SWATCH_GAP * (count - 1) as f32;
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(width, DYE_SWATCH_SIZE), sense);
+    paint_dye_swatch(ui, rect.min, swatch);
+    Some(response)
 }
 
 impl<'a> Default for HighlightConfig<'a> {
@@ -49,34 +346,77 @@ impl<'a> Default for HighlightConfig<'a> {
         Self {
             highlighted_ids: &EMPTY_SET,
             preview_id: None,
+            focus_id: None,
+            dye_swatch: None,
         }
     }
 }
 
+/// 列表标题行中的点击
+enum HeaderClick {
+    Locate,
+    ClearDye,
+}
+
+/// 列表标题行: 数量统计 + 预览染料色块 + 定位按钮
+fn show_list_header(
+    ui: &mut egui::Ui,
+    text: String,
+    highlight: &HighlightConfig<'_>,
+) -> Option<HeaderClick> {
+    ui.horizontal(|ui| {
+        ui.label(text);
+        let mut click = None;
+        if let Some(swatch) = &highlight.dye_swatch {
+            let response = show_dye_swatch(ui, swatch, egui::Sense::click());
+            if let Some(response) = response {
+                let hover = format!("{}\n{}", swatch.names, tr!("list.clear_dye"));
+                if response.on_hover_text(hover).clicked() {
+                    click = Some(HeaderClick::ClearDye);
+                }
+            }
+        }
+        if highlight.focus_id.is_some()
+            && ui
+                .small_button(format!(
+                    "{} {}",
+                    egui_phosphor::regular::CROSSHAIR,
+                    tr!("list.locate")
+                ))
+                .clicked()
+        {
+            click = Some(HeaderClick::Locate);
+        }
+        click
+    })
+    .inner
+}
+
 static EMPTY_SET: std::sync::LazyLock<HashSet<u32>> = std::sync::LazyLock::new(HashSet::new);
 
-/// 渲染带图标的物品行
+/// 渲染带图标的物品行，返回名称标签的响应
+#[allow(clippy::too_many_arguments)]
 fn show_item_row(
     ui: &mut egui::Ui,
-    icon_cache: &mut HashMap<u32, Option<egui::TextureHandle>>,
+    icon_cache: &mut item_list::IconCache,
     ctx: &egui::Context,
     game: &GameData,
     icon_id: u32,
     is_selected: bool,
     rich: egui::RichText,
-) -> bool {
+    swatch: Option<&DyeSwatch>,
+) -> egui::Response {
     let response = ui.horizontal(|ui| {
-        if let Some(icon) = item_list::get_or_load_icon(icon_cache, ctx, game, icon_id) {
-            ui.image(egui::load::SizedTexture::new(
-                icon.id(),
-                egui::vec2(20.0, 20.0),
-            ));
-        } else {
-            ui.allocate_space(egui::vec2(20.0, 20.0));
+        item_list::show_row_icon(ui, icon_cache, ctx, game, icon_id, 20.0);
+        let label = ui.selectable_label(is_selected, rich);
+        if let Some(swatch) = swatch {
+            if let Some(response) = show_dye_swatch(ui, swatch, egui::Sense::hover()) {
+                response.on_hover_text(&swatch.names);
+            }
         }
-        ui.selectable_label(is_selected, rich)
+        label
     });
-    response.inner.clicked()
+    response.inner
 }
 
 impl EquipmentListState {
@@ -90,6 +430,8 @@ impl EquipmentListState {
     /// - `icon_cache`: 图标缓存 (split borrow from App)
     /// - `ctx`: egui Context
     /// - `game`: 游戏数据 (用于加载图标)
+    /// - `probed_dye_counts`: 材质探测得到的染色通道数 (Item 表无 DyeCount 时使用)
+    /// - `thumbnails`: 模型缩略图缓存，为 None 时不提供缩略图模式与列表悬停预览
     pub fn show(
         &mut self,
         ui: &mut egui::Ui,
@@ -100,19 +442,31 @@ impl EquipmentListState {
         slot_filter: Option<EquipSlot>,
         highlight: &HighlightConfig<'_>,
         id_salt: &str,
-        icon_cache: &mut HashMap<u32, Option<egui::TextureHandle>>,
+        icon_cache: &mut item_list::IconCache,
         ctx: &egui::Context,
         game: &GameData,
+        probed_dye_counts: &HashMap<u32, u8>,
+        thumbnails: Option<&mut ThumbnailCache>,
     ) -> Option<ItemClicked> {
         // 搜索
         ui.horizontal(|ui| {
-            ui.label("搜索:");
+            ui.label(tr!("filter.search"));
             ui.text_edit_singleline(&mut self.search);
         });
 
+        // 可染色筛选
+        ui.horizontal(|ui| {
+            ui.label(tr!("filter.dye"));
+            for filter in &DYE_FILTERS {
+                ui.selectable_value(&mut self.dye_filter, *filter, filter.label());
+            }
+            ui.separator();
+            ui.checkbox(&mut self.hide_restricted, tr!("filter.hide_restricted"));
+        });
+
         // 排序 + 视图模式
         ui.horizontal(|ui| {
-            ui.label("排序:");
+            ui.label(tr!("filter.sort"));
             egui::ComboBox::from_id_salt(format!("{}_sort", id_salt))
                 .selected_text(self.sort_order.label())
                 .show_ui(ui, |ui| {
@@ -147,13 +501,16 @@ impl EquipmentListState {
         // 图标大小滑块 (仅图标视图)
         if self.view_mode == ViewMode::Grid {
             ui.horizontal(|ui| {
-                ui.label("图标:");
-                ui.add(egui::Slider::new(&mut self.icon_size, 32.0..=128.0).suffix("px"));
+                item_list::icon_size_slider(ui, &mut self.icon_size);
+                if thumbnails.is_some() {
+                    ui.checkbox(&mut self.use_thumbnails, tr!("filter.thumbnails"));
+                }
             });
         }
 
         ui.separator();
 
+        let use_thumbnails = self.use_thumbnails;
         match self.view_mode {
             ViewMode::List => self.show_list_view(
                 ui,
@@ -167,6 +524,8 @@ impl EquipmentListState {
                 icon_cache,
                 ctx,
                 game,
+                probed_dye_counts,
+                thumbnails,
             ),
             ViewMode::Grid => self.show_grid_view(
                 ui,
@@ -178,10 +537,27 @@ impl EquipmentListState {
                 icon_cache,
                 ctx,
                 game,
+                probed_dye_counts,
+                thumbnails.filter(|_| use_thumbnails),
             ),
         }
     }
 
+    /// 可染色筛选: Item 表无 DyeCount 时使用后台探测结果，未探测到的物品暂不显示
+    fn dye_filter_matches(
+        dye_filter: DyeFilter,
+        item: &GameItem,
+        probed_dye_counts: &HashMap<u32, u8>,
+    ) -> bool {
+        if dye_filter == DyeFilter::All {
+            return true;
+        }
+        let count = item
+            .dye_count()
+            .or_else(|| probed_dye_counts.get(&item.row_id).copied());
+        dye_filter.matches(count)
+    }
+
     /// 列表视图: 按套装分组折叠
     fn show_list_view(
         &mut self,
@@ -193,15 +569,20 @@ impl EquipmentListState {
         slot_filter: Option<EquipSlot>,
         highlight: &HighlightConfig<'_>,
         id_salt: &str,
-        icon_cache: &mut HashMap<u32, Option<egui::TextureHandle>>,
+        icon_cache: &mut item_list::IconCache,
         ctx: &egui::Context,
         game: &GameData,
+        probed_dye_counts: &HashMap<u32, u8>,
+        thumbnails: Option<&mut ThumbnailCache>,
     ) -> Option<ItemClicked> {
-        // 构建套装分组
-        let search_lower = self.search.to_lowercase();
-        let mut set_groups: Vec<(u16, String, bool, bool, Vec<(usize, &GameItem)>)> = Vec::new();
-        {
-            let mut by_set: BTreeMap<u16, Vec<(usize, &GameItem)>> = BTreeMap::new();
+        // 构建套装分组 (筛选条件不变时复用缓存)
+        let key = self.filter_key(equipment_indices, slot_filter, probed_dye_counts);
+        let search_lower = key.0.clone();
+        let dye_filter = self.dye_filter;
+        let hide_restricted = self.hide_restricted;
+        let sort_order = self.sort_order;
+        let set_groups = self.group_cache.get_or_compute(key, || {
+            let mut by_set: BTreeMap<u16, Vec<usize>> = BTreeMap::new();
             for &idx in equipment_indices {
                 let item = &all_items[idx];
                 let slot = match item.equip_slot() {
@@ -213,50 +594,99 @@ impl EquipmentListState {
                         continue;
                     }
                 }
-                if !search_lower.is_empty() && !item.name.to_lowercase().contains(&search_lower) {
+                if !item.matches_search(&search_lower) {
+                    continue;
+                }
+                if !Self::dye_filter_matches(dye_filter, item, probed_dye_counts) {
+                    continue;
+                }
+                if hide_restricted && item.equip_restriction.is_restricted() {
                     continue;
                 }
-                by_set.entry(item.set_id()).or_default().push((idx, item));
+                by_set.entry(item.set_id()).or_default().push(idx);
             }
 
+            let mut set_groups: Vec<SetGroup> = Vec::new();
             for (set_id, items_in_set) in by_set {
-                let group_name = if let Some(&set_idx) = set_id_to_set_idx.get(&set_id) {
-                    equipment_sets[set_idx].display_name.clone()
-                } else if let Some((_, first)) = items_in_set.first() {
-                    first.name.clone()
-                } else {
-                    format!("set {:04}", set_id)
-                };
-                let has_gear = items_in_set.iter().any(|(_, item)| !item.is_accessory());
-                let has_acc = items_in_set.iter().any(|(_, item)| item.is_accessory());
-                set_groups.push((set_id, group_name, has_gear, has_acc, items_in_set));
+                let set_idx = set_id_to_set_idx.get(&set_id).copied();
+                let has_gear = items_in_set.iter().any(|&i| !all_items[i].is_accessory());
+                let has_acc = items_in_set.iter().any(|&i| all_items[i].is_accessory());
+                let icon_ids = group_icon_ids(all_items, &items_in_set);
+                set_groups.push((set_id, set_idx, has_gear, has_acc, items_in_set, icon_ids));
             }
-        }
 
-        match self.sort_order {
-            SortOrder::ByName | SortOrder::BySlot => {
-                set_groups.sort_by(|a, b| a.1.cmp(&b.1));
-            }
-            SortOrder::BySetId => {
-                set_groups.sort_by(|a, b| a.0.cmp(&b.0));
+            match sort_order {
+                SortOrder::ByName | SortOrder::BySlot => {
+                    set_groups.sort_by(|a, b| {
+                        let name_a = group_name(a.1, &a.4, equipment_sets, all_items);
+                        let name_b = group_name(b.1, &b.4, equipment_sets, all_items);
+                        name_a.cmp(name_b)
+                    });
+                }
+                SortOrder::BySetId => {
+                    set_groups.sort_by(|a, b| a.0.cmp(&b.0));
+                }
             }
-        }
+            set_groups
+        });
 
         let total_items: usize = set_groups
             .iter()
-            .map(|(_, _, _, _, items)| items.len())
+            .map(|(_, _, _, _, items, _)| items.len())
             .sum();
-        ui.label(format!("{} 组, {} 件", set_groups.len(), total_items));
+        let header = format!("{} 组, {} 件", set_groups.len(), total_items);
+        match show_list_header(ui, header, highlight) {
+            Some(HeaderClick::Locate) => self.reveal_id = highlight.focus_id,
+            Some(HeaderClick::ClearDye) => self.clear_dye_requested = true,
+            None => {}
+        }
+
+        self.sync_search_expansion(&search_lower, &set_groups);
+
+        // 全部展开只作用于当前筛选后仍有匹配物品的套装
+        ui.horizontal(|ui| {
+            if ui.small_button(tr!("list.expand_all")).clicked() {
+                self.expand_all(&set_groups);
+            }
+            if ui.small_button(tr!("list.collapse_all")).clicked() {
+                self.expanded_sets.clear();
+            }
+            if self.auto_expand_capped {
+                ui.weak(format!(
+                    "{} ({}/{})",
+                    tr!("list.auto_expand_capped"),
+                    AUTO_EXPAND_LIMIT,
+                    set_groups.len()
+                ));
+            }
+        });
+
+        // 定位: 展开目标所在的套装，目标被筛选掉时放弃
+        if let Some(target) = self.reveal_id {
+            let group = set_groups
+                .iter()
+                .find(|(_, _, _, _, items, _)| items.iter().any(|&i| all_items[i].row_id == target));
+            match group {
+                Some((set_id, ..)) => {
+                    self.expanded_sets.insert(*set_id);
+                }
+                None => self.reveal_id = None,
+            }
+        }
 
         // 渲染列表
         let mut clicked: Option<ItemClicked> = None;
+        let mut hovered: Option<usize> = None;
 
         let scroll_height = ui.available_height();
         egui::ScrollArea::vertical()
             .id_salt(format!("{}_scroll", id_salt))
             .max_height(scroll_height)
             .show(ui, |ui| {
-                for (set_id, group_name, has_gear, has_acc, items_in_set) in &set_groups {
+                for (set_id, set_idx, has_gear, has_acc, items_in_set, icon_ids) in
+                    set_groups.iter()
+                {
+                    let group_name = group_name(*set_idx, items_in_set, equipment_sets, all_items);
                     let expanded = self.expanded_sets.contains(set_id);
                     let prefix = match (*has_gear, *has_acc) {
                         (true, true) => "e+a",
@@ -275,15 +705,66 @@ impl EquipmentListState {
 
                     let group_has_highlight = items_in_set
                         .iter()
-                        .any(|(_, item)| highlight.highlighted_ids.contains(&item.row_id));
-
-                    if ui
-                        .selectable_label(
-                            group_has_highlight,
-                            egui::RichText::new(&header_text).strong(),
-                        )
-                        .clicked()
-                    {
+                        .any(|&i| highlight.highlighted_ids.contains(&all_items[i].row_id));
+
+                    let header = ui
+                        .horizontal(|ui| {
+                            show_group_icons(ui, icon_cache, ctx, game, icon_ids);
+                            ui.selectable_label(
+                                group_has_highlight,
+                                egui::RichText::new(&header_text).strong(),
+                            )
+                        })
+                        .inner;
+                    // 折叠的套装悬停时预览内容，不必展开 (展开大套装会挤动滚动位置)；
+                    // 滚动中不弹出，避免列表滑过鼠标时闪烁
+                    let scrolling = ui.input(|i| i.smooth_scroll_delta != egui::Vec2::ZERO);
+                    let mut peek_clicked = None;
+                    let header = if expanded || scrolling {
+                        header.on_hover_text(tr!("list.group_double_click"))
+                    } else {
+                        header.on_hover_ui(|ui| {
+                            peek_clicked =
+                                show_group_peek(ui, icon_cache, ctx, game, all_items, items_in_set);
+                        })
+                    };
+                    if let Some(global_idx) = peek_clicked {
+                        let item = &all_items[global_idx];
+                        if let Some(slot) = item.equip_slot() {
+                            clicked = Some(ItemClicked {
+                                global_idx,
+                                item_id: item.row_id,
+                                slot,
+                            });
+                            self.reveal_id = Some(item.row_id);
+                        }
+                        self.expanded_sets.insert(*set_id);
+                    }
+                    if self.kit_export_enabled {
+                        header.context_menu(|ui| {
+                            if ui.button(tr!("list.export_kit")).clicked() {
+                                self.kit_export_request =
+                                    Some((group_name.to_string(), items_in_set.clone()));
+                                ui.close();
+                            }
+                        });
+                    }
+                    if header.double_clicked() {
+                        // 双击: 直接选中代表物品 (身体 > 腿 > 头 > 其余)，并展开套装定位到该行
+                        if let Some(global_idx) = representative_item_index(all_items, items_in_set)
+                        {
+                            let item = &all_items[global_idx];
+                            if let Some(slot) = item.equip_slot() {
+                                clicked = Some(ItemClicked {
+                                    global_idx,
+                                    item_id: item.row_id,
+                                    slot,
+                                });
+                                self.reveal_id = Some(item.row_id);
+                            }
+                        }
+                        self.expanded_sets.insert(*set_id);
+                    } else if header.clicked() {
                         if self.expanded_sets.contains(set_id) {
                             self.expanded_sets.remove(set_id);
                         } else {
@@ -292,21 +773,34 @@ impl EquipmentListState {
                     }
 
                     if expanded {
-                        for (global_idx, item) in items_in_set {
+                        for &global_idx in items_in_set {
+                            let item = &all_items[global_idx];
                             let slot = match item.equip_slot() {
                                 Some(s) => s,
                                 None => continue,
                             };
                             let is_highlighted = highlight.highlighted_ids.contains(&item.row_id);
                             let is_preview = highlight.preview_id == Some(item.row_id);
-                            let label_text = format!("[{}] {}", slot.slot_abbr(), item.name);
+                            if self.reveal_id == Some(item.row_id) {
+                                ui.scroll_to_cursor(Some(egui::Align::Center));
+                                self.reveal_id = None;
+                            }
+                            let mut label_text = format!("[{}] {}", slot.slot_abbr(), item.name);
+                            if item.equip_restriction.is_restricted() {
+                                label_text.push(' ');
+                                label_text.push_str(egui_phosphor::regular::WARNING);
+                            }
                             let rich = if is_preview {
                                 egui::RichText::new(&label_text)
                                     .color(egui::Color32::from_rgb(100, 200, 255))
                             } else {
                                 egui::RichText::new(&label_text)
                             };
-                            if show_item_row(
+                            let swatch = highlight
+                                .dye_swatch
+                                .as_ref()
+                                .filter(|s| s.item_id == Some(item.row_id));
+                            let row = show_item_row(
                                 ui,
                                 icon_cache,
                                 ctx,
@@ -314,9 +808,14 @@ impl EquipmentListState {
                                 item.icon_id,
                                 is_highlighted || is_preview,
                                 rich,
-                            ) {
+                                swatch,
+                            );
+                            if row.hovered() {
+                                hovered = Some(global_idx);
+                            }
+                            if row.clicked() {
                                 clicked = Some(ItemClicked {
-                                    global_idx: *global_idx,
+                                    global_idx,
                                     item_id: item.row_id,
                                     slot,
                                 });
@@ -326,9 +825,76 @@ impl EquipmentListState {
                 }
             });
 
+        if let Some(thumbs) = thumbnails {
+            thumbs.begin_frame();
+            self.show_hover_preview(ctx, hovered, all_items, icon_cache, game, thumbs);
+            thumbs.process(ctx, all_items, game);
+        }
+
         clicked
     }
 
+    /// 悬停列表行一段时间后在鼠标旁显示模型预览，渲染完成前使用缩略图或图标
+    fn show_hover_preview(
+        &mut self,
+        ctx: &egui::Context,
+        hovered: Option<usize>,
+        all_items: &[GameItem],
+        icon_cache: &mut item_list::IconCache,
+        game: &GameData,
+        thumbnails: &mut ThumbnailCache,
+    ) {
+        let Some(idx) = hovered else {
+            self.hover_intent = None;
+            thumbnails.cancel_hover();
+            return;
+        };
+        let now = ctx.input(|i| i.time);
+        let since = match self.hover_intent {
+            Some((prev, since)) if prev == idx => since,
+            _ => {
+                self.hover_intent = Some((idx, now));
+                thumbnails.cancel_hover();
+                now
+            }
+        };
+        let remaining = HOVER_PREVIEW_DELAY - (now - since);
+        if remaining > 0.0 {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(remaining));
+            return;
+        }
+        let Some(pointer) = ctx.pointer_hover_pos() else {
+            return;
+        };
+
+        let item = &all_items[idx];
+        let texture = thumbnails.request_hover(item, idx).or_else(|| {
+            item_list::get_or_load_icon(icon_cache, ctx, game, item.icon_id).map(|t| t.id())
+        });
+        let size = HOVER_PREVIEW_SIZE as f32;
+        egui::Area::new(egui::Id::new("equipment_hover_preview"))
+            .order(egui::Order::Tooltip)
+            .interactable(false)
+            .constrain(true)
+            .fixed_pos(pointer + egui::vec2(16.0, 16.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    match texture {
+                        Some(tex_id) => {
+                            ui.image(egui::load::SizedTexture::new(
+                                tex_id,
+                                egui::vec2(size, size),
+                            ));
+                        }
+                        None => {
+                            ui.allocate_space(egui::vec2(size, size));
+                        }
+                    }
+                    ui.label(&item.name);
+                });
+            });
+    }
+
     /// 图标网格视图: 图标横向排列自动换行，可调大小
     fn show_grid_view(
         &mut self,
@@ -338,29 +904,52 @@ impl EquipmentListState {
         slot_filter: Option<EquipSlot>,
         highlight: &HighlightConfig<'_>,
         id_salt: &str,
-        icon_cache: &mut HashMap<u32, Option<egui::TextureHandle>>,
+        icon_cache: &mut item_list::IconCache,
         ctx: &egui::Context,
         game: &GameData,
+        probed_dye_counts: &HashMap<u32, u8>,
+        mut thumbnails: Option<&mut ThumbnailCache>,
     ) -> Option<ItemClicked> {
-        let search_lower = self.search.to_lowercase();
-        let filtered: Vec<(usize, &GameItem)> = equipment_indices
+        let key = self.filter_key(equipment_indices, slot_filter, probed_dye_counts);
+        let search_lower = key.0.clone();
+        let dye_filter = self.dye_filter;
+        let hide_restricted = self.hide_restricted;
+        let filtered_indices = self.grid_cache.get_or_compute(key, || {
+            equipment_indices
+                .iter()
+                .copied()
+                .filter(|&idx| {
+                    let item = &all_items[idx];
+                    let Some(slot) = item.equip_slot() else {
+                        return false;
+                    };
+                    slot_filter.map_or(true, |sf| slot == sf)
+                        && item.matches_search(&search_lower)
+                        && Self::dye_filter_matches(dye_filter, item, probed_dye_counts)
+                        && !(hide_restricted && item.equip_restriction.is_restricted())
+                })
+                .collect()
+        });
+        let filtered: Vec<(usize, &GameItem)> = filtered_indices
             .iter()
-            .filter_map(|&idx| {
-                let item = &all_items[idx];
-                let slot = item.equip_slot()?;
-                if let Some(sf) = slot_filter {
-                    if slot != sf {
-                        return None;
-                    }
-                }
-                if !search_lower.is_empty() && !item.name.to_lowercase().contains(&search_lower) {
-                    return None;
-                }
-                Some((idx, item))
-            })
+            .map(|&idx| (idx, &all_items[idx]))
             .collect();
 
-        ui.label(format!("{} 件", filtered.len()));
+        if let Some(thumbs) = thumbnails.as_deref_mut() {
+            thumbs.begin_frame();
+        }
+
+        match show_list_header(ui, format!("{} 件", filtered.len()), highlight) {
+            Some(HeaderClick::Locate) => self.reveal_id = highlight.focus_id,
+            Some(HeaderClick::ClearDye) => self.clear_dye_requested = true,
+            None => {}
+        }
+        // 占位: 缩略图进度在网格绘制完成后填入
+        let progress_rect = if thumbnails.is_some() {
+            Some(ui.allocate_space(egui::vec2(ui.available_width(), 16.0)).1)
+        } else {
+            None
+        };
 
         let available_width = ui.available_width();
         let icon_size = self.icon_size;
@@ -373,12 +962,29 @@ impl EquipmentListState {
         // 实际每格宽度: 均分可用宽度
         let actual_cell_width = available_width / cols as f32;
         let total_rows = (filtered.len() + cols - 1) / cols;
+        let ppp = ctx.pixels_per_point();
+
+        let reveal_row = self
+            .reveal_id
+            .take()
+            .and_then(|target| filtered.iter().position(|(_, item)| item.row_id == target))
+            .map(|pos| pos / cols);
 
         let mut clicked: Option<ItemClicked> = None;
 
         egui::ScrollArea::vertical()
             .id_salt(format!("{}_grid_scroll", id_salt))
             .show_rows(ui, cell_height, total_rows, |ui, row_range| {
+                // 定位: 虚拟滚动只绘制可见行，按行号换算目标行的位置再滚动
+                if let Some(row) = reveal_row {
+                    let row_height = cell_height + ui.spacing().item_spacing.y;
+                    let offset = (row as f32 - row_range.start as f32) * row_height;
+                    let rect = egui::Rect::from_min_size(
+                        ui.cursor().min + egui::vec2(0.0, offset),
+                        egui::vec2(actual_cell_width, cell_height),
+                    );
+                    ui.scroll_to_rect(rect, Some(egui::Align::Center));
+                }
                 for row_idx in row_range {
                     ui.horizontal(|ui| {
                         ui.spacing_mut().item_spacing.x = 0.0;
@@ -408,13 +1014,34 @@ impl EquipmentListState {
                             // 图标 (居中在上半部分)
                             let icon_top = rect.top() + cell_padding;
                             let icon_center_x = rect.center().x;
-                            let icon_rect = egui::Rect::from_center_size(
-                                egui::pos2(icon_center_x, icon_top + icon_size / 2.0),
-                                egui::vec2(icon_size, icon_size),
+                            let icon_rect = item_list::snap_icon_rect(
+                                egui::Rect::from_center_size(
+                                    egui::pos2(icon_center_x, icon_top + icon_size / 2.0),
+                                    egui::vec2(icon_size, icon_size),
+                                ),
+                                ppp,
                             );
-                            if let Some(icon) =
-                                item_list::get_or_load_icon(icon_cache, ctx, game, item.icon_id)
-                            {
+                            let thumb = match thumbnails.as_deref_mut() {
+                                Some(thumbs) => thumbs.request(item, *idx),
+                                None => ThumbnailStatus::Failed,
+                            };
+                            if let ThumbnailStatus::Ready(tex_id) = thumb {
+                                ui.painter().image(
+                                    tex_id,
+                                    icon_rect,
+                                    egui::Rect::from_min_max(
+                                        egui::pos2(0.0, 0.0),
+                                        egui::pos2(1.0, 1.0),
+                                    ),
+                                    egui::Color32::WHITE,
+                                );
+                            } else if let Some(icon) = item_list::get_or_load_icon_sized(
+                                icon_cache,
+                                ctx,
+                                game,
+                                item.icon_id,
+                                icon_size,
+                            ) {
                                 ui.painter().image(
                                     icon.id(),
                                     icon_rect,
@@ -449,8 +1076,34 @@ impl EquipmentListState {
                                 text_color,
                             );
 
+                            // 当前预览物品: 左上角显示预览染料色块
+                            if let Some(swatch) = highlight
+                                .dye_swatch
+                                .as_ref()
+                                .filter(|s| s.item_id == Some(item.row_id))
+                            {
+                                paint_dye_swatch(ui, rect.min + egui::vec2(3.0, 3.0), swatch);
+                            }
+
+                            // 限定装备: 右上角警告标记
+                            let restriction = item.equip_restriction.label();
+                            if restriction.is_some() {
+                                ui.painter().text(
+                                    rect.right_top() + egui::vec2(-3.0, 3.0),
+                                    egui::Align2::RIGHT_TOP,
+                                    egui_phosphor::regular::WARNING,
+                                    egui::FontId::proportional(12.0),
+                                    egui::Color32::from_rgb(230, 180, 60),
+                                );
+                            }
+
                             // tooltip
-                            response.clone().on_hover_text(&item.name);
+                            match restriction {
+                                Some(label) => response
+                                    .clone()
+                                    .on_hover_text(format!("{}\n{}", item.name, label)),
+                                None => response.clone().on_hover_text(&item.name),
+                            };
 
                             if response.clicked() {
                                 if let Some(slot) = item.equip_slot() {
@@ -466,6 +1119,115 @@ impl EquipmentListState {
                 }
             });
 
+        // 渲染可见格子的缩略图 (每帧少量)，并显示进度
+        if let Some(thumbs) = thumbnails {
+            let pending = thumbs.pending_count();
+            thumbs.process(ctx, all_items, game);
+            if let Some(rect) = progress_rect {
+                if pending > 0 {
+                    ui.scope_builder(egui::UiBuilder::new().max_rect(rect), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(format!("正在生成缩略图: 剩余 {} 个", pending));
+                        });
+                    });
+                }
+            }
+        }
+
         clicked
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups(set_ids: impl IntoIterator<Item = u16>) -> Vec<SetGroup> {
+        set_ids
+            .into_iter()
+            .map(|id| (id, None, true, false, Vec::new(), Vec::new()))
+            .collect()
+    }
+
+    fn expanded(state: &EquipmentListState) -> Vec<u16> {
+        let mut ids: Vec<u16> = state.expanded_sets.iter().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn search_expands_matches_and_clear_restores() {
+        let mut state = EquipmentListState::new();
+        state.expanded_sets.insert(1);
+
+        state.sync_search_expansion("", &groups([1, 2, 3, 4]));
+        assert_eq!(expanded(&state), [1]);
+
+        state.sync_search_expansion("b", &groups([2, 3]));
+        assert_eq!(expanded(&state), [1, 2, 3]);
+
+        // 搜索词变长、匹配变少时从搜索前的状态重新展开，不累积
+        state.sync_search_expansion("br", &groups([3]));
+        assert_eq!(expanded(&state), [1, 3]);
+
+        // 同一搜索词下手动折叠的套装不会被重新展开
+        state.expanded_sets.remove(&3);
+        state.sync_search_expansion("br", &groups([3]));
+        assert_eq!(expanded(&state), [1]);
+
+        state.sync_search_expansion("", &groups([1, 2, 3, 4]));
+        assert_eq!(expanded(&state), [1]);
+        assert!(state.expansion_before_search.is_none());
+    }
+
+    #[test]
+    fn whitespace_search_counts_as_cleared() {
+        let mut state = EquipmentListState::new();
+        state.sync_search_expansion("b", &groups([2]));
+        state.sync_search_expansion("  ", &groups([1, 2]));
+        assert!(state.expanded_sets.is_empty());
+        assert!(state.auto_expanded_search.is_empty());
+    }
+
+    #[test]
+    fn auto_expansion_is_capped() {
+        let mut state = EquipmentListState::new();
+        let many = groups(0..(AUTO_EXPAND_LIMIT as u16 + 50));
+        state.sync_search_expansion("a", &many);
+        assert_eq!(state.expanded_sets.len(), AUTO_EXPAND_LIMIT);
+        assert!(state.auto_expand_capped);
+
+        state.sync_search_expansion("ab", &groups([7]));
+        assert_eq!(expanded(&state), [7]);
+        assert!(!state.auto_expand_capped);
+
+        state.sync_search_expansion("a", &many);
+        state.sync_search_expansion("", &many);
+        assert!(!state.auto_expand_capped);
+        assert!(state.expanded_sets.is_empty());
+    }
+
+    #[test]
+    fn expand_all_only_touches_matching_sets() {
+        let mut state = EquipmentListState::new();
+        state.expanded_sets.insert(9);
+        state.expand_all(&groups([2, 3]));
+        assert_eq!(expanded(&state), [2, 3, 9]);
+    }
+
+    #[test]
+    fn group_name_resolves_from_set_table_or_first_item() {
+        let items = [GameItem::named(1, "first"), GameItem::named(2, "second")];
+        let sets = [EquipmentSet {
+            set_id: 7,
+            display_name: "Set".to_string(),
+            item_indices: vec![0, 1],
+            has_gear: true,
+            has_accessory: false,
+        }];
+        assert_eq!(group_name(Some(0), &[0, 1], &sets, &items), "Set");
+        assert_eq!(group_name(None, &[1, 0], &sets, &items), "second");
+        assert_eq!(group_name(None, &[], &sets, &items), "");
+    }
+}
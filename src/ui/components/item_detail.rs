@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+
 use eframe::egui;
 
 use crate::domain::GameItem;
+use crate::glamour::GlamourSet;
 
 /// 物品详情头部的显示配置
 pub struct ItemDetailConfig {
@@ -14,6 +17,8 @@ pub struct ItemDetailConfig {
     pub show_description: bool,
     /// 是否显示外部链接
     pub show_links: bool,
+    /// 是否显示装备的修理与魔晶石信息 (show_gear_info)
+    pub show_gear_info: bool,
 }
 
 impl Default for ItemDetailConfig {
@@ -24,6 +29,7 @@ impl Default for ItemDetailConfig {
             show_category: true,
             show_description: true,
             show_links: true,
+            show_gear_info: true,
         }
     }
 }
@@ -37,10 +43,125 @@ impl ItemDetailConfig {
             show_category: true,
             show_description: true,
             show_links: true,
+            show_gear_info: false,
         }
     }
 }
 
+/// 装备信息区块所需的查找表 (由调用方从 GameState 提供)
+pub struct GearInfoLookup<'a> {
+    pub class_job_names: &'a HashMap<u8, String>,
+    pub base_param_names: &'a HashMap<u8, String>,
+    pub repair_resource_items: &'a HashMap<u8, u32>,
+    pub all_items: &'a [GameItem],
+    pub item_id_map: &'a HashMap<u32, usize>,
+}
+
+/// 装备属性 (防御力与基础属性)，两列表格显示，HQ 加成附在数值后；非装备或没有属性时不显示
+pub fn show_item_stats(
+    ui: &mut egui::Ui,
+    item: &GameItem,
+    lookup: &GearInfoLookup<'_>,
+    config: &ItemDetailConfig,
+) {
+    if !config.show_gear_info || item.equip_slot().is_none() {
+        return;
+    }
+    let lines = item.stat_lines();
+    if lines.is_empty() {
+        return;
+    }
+    egui::Grid::new(("item_stats", item.row_id))
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            for (param, value, hq) in lines {
+                let name = lookup
+                    .base_param_names
+                    .get(&param)
+                    .cloned()
+                    .unwrap_or_else(|| format!("#{}", param));
+                ui.label(name);
+                match hq {
+                    Some(hq) if hq != value => {
+                        ui.label(format!("{}  (HQ {})", value, hq));
+                    }
+                    _ => {
+                        ui.label(format!("{}", value));
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    ui.add_space(4.0);
+}
+
+/// 装备的修理职业/材料与魔晶石信息，值为 0 的行不显示；全部为空时不显示任何内容。
+/// 修理材料显示为链接，点击时返回其物品 ID
+pub fn show_gear_info(
+    ui: &mut egui::Ui,
+    item: &GameItem,
+    lookup: &GearInfoLookup<'_>,
+    config: &ItemDetailConfig,
+) -> Option<u32> {
+    if !config.show_gear_info || item.equip_slot().is_none() {
+        return None;
+    }
+    let repair_class = (item.repair_class_job > 0).then(|| {
+        lookup
+            .class_job_names
+            .get(&item.repair_class_job)
+            .cloned()
+            .unwrap_or_else(|| format!("#{}", item.repair_class_job))
+    });
+    let repair_item = lookup
+        .repair_resource_items
+        .get(&item.repair_resource)
+        .and_then(|id| lookup.item_id_map.get(id))
+        .and_then(|&idx| lookup.all_items.get(idx));
+    let meldable = item.materia_slot_count > 0 || item.advanced_melding;
+    let extractable = item.materialize_type > 0;
+    if repair_class.is_none() && repair_item.is_none() && !meldable && !extractable {
+        return None;
+    }
+
+    let mut clicked = None;
+    egui::Grid::new(("gear_info", item.row_id)).show(ui, |ui| {
+        if let Some(class) = &repair_class {
+            ui.label("修理职业:");
+            ui.label(class);
+            ui.end_row();
+        }
+        if let Some(repair) = repair_item {
+            ui.label("修理材料:");
+            if ui.link(&repair.name).clicked() {
+                clicked = Some(repair.row_id);
+            }
+            ui.end_row();
+        }
+        if item.materia_slot_count > 0 {
+            ui.label("魔晶石孔:");
+            ui.label(format!("{}", item.materia_slot_count));
+            ui.end_row();
+        }
+        if meldable {
+            ui.label("镶嵌:");
+            ui.label(if item.advanced_melding {
+                "可镶嵌 (允许禁断)"
+            } else {
+                "可镶嵌"
+            });
+            ui.end_row();
+        }
+        if extractable {
+            ui.label("精制:");
+            ui.label("可精制魔晶石");
+            ui.end_row();
+        }
+    });
+    clicked
+}
+
 /// 显示统一的物品详情头部 (图标 + 名称 + 分类 + 描述 + 外部链接)
 ///
 /// 参数:
@@ -55,11 +176,13 @@ pub fn show_item_detail_header(
 ) {
     // 图标 + 名称
     ui.horizontal(|ui| {
+        let icon_size = egui::vec2(config.icon_size, config.icon_size);
         if let Some(tex) = icon {
-            ui.image(egui::load::SizedTexture::new(
-                tex.id(),
-                egui::vec2(config.icon_size, config.icon_size),
-            ));
+            ui.image(egui::load::SizedTexture::new(tex.id(), icon_size));
+        } else if item.icon_id != 0 {
+            // 图标读取失败时保留占位，标题位置不随图标有无跳动
+            let (rect, _) = ui.allocate_exact_size(icon_size, egui::Sense::hover());
+            ui.painter().rect_filled(rect, 3.0, ui.visuals().faint_bg_color);
         }
         if config.use_heading {
             ui.heading(&item.name);
@@ -75,10 +198,12 @@ pub fn show_item_detail_header(
         }
     }
 
+    show_restriction_badge(ui, item);
+
     // 描述
     if config.show_description && !item.description.is_empty() {
         ui.add_space(2.0);
-        ui.label(egui::RichText::new(&item.description).small().weak());
+        ui.label(egui::RichText::new(item.description.as_ref()).small().weak());
     }
 
     // 外部链接
@@ -108,3 +233,55 @@ pub fn show_item_detail_header(
         });
     }
 }
+
+/// 性别/种族限定装备的警告标记，无限制时不显示
+pub fn show_restriction_badge(ui: &mut egui::Ui, item: &GameItem) {
+    if let Some(label) = item.equip_restriction.label() {
+        ui.label(
+            egui::RichText::new(format!(
+                "{} {}: {}",
+                egui_phosphor::regular::WARNING,
+                tr!("restriction.title"),
+                label
+            ))
+            .color(egui::Color32::from_rgb(230, 180, 60)),
+        );
+    }
+}
+
+/// "已用于幻化: A, B" 行，未被任何组合使用时不显示；点击组合名时返回其索引
+/// `exclude`: 不列出的组合索引 (编辑器中的当前组合)
+pub fn show_glamour_usage(
+    ui: &mut egui::Ui,
+    usage: Option<&Vec<usize>>,
+    sets: &[GlamourSet],
+    exclude: Option<usize>,
+) -> Option<usize> {
+    let users: Vec<usize> = usage
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(|&idx| Some(idx) != exclude && idx < sets.len())
+        .collect();
+    if users.is_empty() {
+        return None;
+    }
+    let mut clicked = None;
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        ui.label(tr!("glamour.used_in"));
+        for (i, &idx) in users.iter().enumerate() {
+            if i > 0 {
+                ui.label(", ");
+            }
+            if ui
+                .link(&sets[idx].name)
+                .on_hover_text(tr!("glamour.used_in_hint"))
+                .clicked()
+            {
+                clicked = Some(idx);
+            }
+        }
+    });
+    clicked
+}
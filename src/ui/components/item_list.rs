@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
 use eframe::egui;
 
@@ -24,11 +25,11 @@ impl ItemListState {
     /// 渲染搜索框 + 视图切换 + 图标大小滑块
     pub fn show_controls(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
-            ui.label("搜索:");
+            ui.label(tr!("filter.search"));
             ui.text_edit_singleline(&mut self.search);
         });
         ui.horizontal(|ui| {
-            ui.label("视图:");
+            ui.label(tr!("filter.view"));
             if ui
                 .selectable_label(self.view_mode == ViewMode::List, ViewMode::List.label())
                 .clicked()
@@ -43,10 +44,7 @@ impl ItemListState {
             }
         });
         if self.view_mode == ViewMode::Grid {
-            ui.horizontal(|ui| {
-                ui.label("图标:");
-                ui.add(egui::Slider::new(&mut self.icon_size, 32.0..=128.0).suffix("px"));
-            });
+            ui.horizontal(|ui| icon_size_slider(ui, &mut self.icon_size));
         }
     }
 
@@ -56,6 +54,37 @@ impl ItemListState {
     }
 }
 
+/// 筛选结果缓存: 键 (搜索词、筛选条件等) 不变时直接复用上次结果，避免每帧重新筛选
+///
+/// 结果以 Arc 共享，命中缓存时只增加引用计数，不复制整个列表
+pub struct FilterCache<K, V> {
+    entry: Option<(K, Arc<V>)>,
+}
+
+impl<K, V> Default for FilterCache<K, V> {
+    fn default() -> Self {
+        Self { entry: None }
+    }
+}
+
+impl<K: PartialEq, V> FilterCache<K, V> {
+    pub fn get_or_compute(&mut self, key: K, compute: impl FnOnce() -> V) -> Arc<V> {
+        match &self.entry {
+            Some((cached_key, value)) if *cached_key == key => Arc::clone(value),
+            _ => {
+                let value = Arc::new(compute());
+                self.entry = Some((key, Arc::clone(&value)));
+                value
+            }
+        }
+    }
+}
+
+/// 物品列表的身份标识: 重新加载游戏数据后 all_items 地址改变，缓存随之失效
+pub fn items_identity<T>(items: &[T]) -> (usize, usize) {
+    (items.as_ptr() as usize, items.len())
+}
+
 /// 用于渲染的物品显示信息
 pub struct DisplayItem<'a> {
     /// 调用方自定义的标识 (点击时原样返回)
@@ -63,6 +92,40 @@ pub struct DisplayItem<'a> {
     pub name: &'a str,
     pub icon_id: u32,
     pub is_selected: bool,
+    /// 附加的小标签 (如合并列表中的外装类型)
+    pub badge: Option<&'a str>,
+}
+
+/// 列表行中的小标签
+fn show_badge(ui: &mut egui::Ui, text: &str) {
+    egui::Frame::new()
+        .fill(ui.visuals().faint_bg_color)
+        .stroke(ui.visuals().widgets.noninteractive.bg_stroke)
+        .corner_radius(3)
+        .inner_margin(egui::Margin::symmetric(4, 0))
+        .show(ui, |ui| {
+            ui.label(egui::RichText::new(text).small());
+        });
+}
+
+/// 网格单元左上角的小标签
+fn paint_grid_badge(ui: &egui::Ui, cell: egui::Rect, text: &str) {
+    let galley = ui.painter().layout_no_wrap(
+        text.to_string(),
+        egui::FontId::proportional(10.0),
+        ui.visuals().text_color(),
+    );
+    let badge_rect = egui::Rect::from_min_size(
+        cell.min + egui::vec2(2.0, 2.0),
+        galley.size() + egui::vec2(6.0, 2.0),
+    );
+    let painter = ui.painter().with_clip_rect(cell);
+    painter.rect_filled(badge_rect, 3.0, ui.visuals().extreme_bg_color);
+    painter.galley(
+        badge_rect.min + egui::vec2(3.0, 1.0),
+        galley,
+        ui.visuals().text_color(),
+    );
 }
 
 /// 渲染一行列表物品 (图标 + 标签)，返回是否被点击
@@ -70,18 +133,14 @@ pub fn show_list_row(
     ui: &mut egui::Ui,
     item: &DisplayItem<'_>,
     label_text: &str,
-    icon_cache: &mut HashMap<u32, Option<egui::TextureHandle>>,
+    icon_cache: &mut IconCache,
     ctx: &egui::Context,
     game: &GameData,
 ) -> bool {
     let response = ui.horizontal(|ui| {
-        if let Some(icon) = get_or_load_icon(icon_cache, ctx, game, item.icon_id) {
-            ui.image(egui::load::SizedTexture::new(
-                icon.id(),
-                egui::vec2(20.0, 20.0),
-            ));
-        } else {
-            ui.allocate_space(egui::vec2(20.0, 20.0));
+        show_row_icon(ui, icon_cache, ctx, game, item.icon_id, 20.0);
+        if let Some(badge) = item.badge {
+            show_badge(ui, badge);
         }
         ui.selectable_label(item.is_selected, label_text)
     });
@@ -94,7 +153,7 @@ pub fn show_grid(
     ui: &mut egui::Ui,
     items: &[DisplayItem<'_>],
     icon_size: f32,
-    icon_cache: &mut HashMap<u32, Option<egui::TextureHandle>>,
+    icon_cache: &mut IconCache,
     ctx: &egui::Context,
     game: &GameData,
 ) -> Option<usize> {
@@ -111,6 +170,7 @@ pub fn show_grid(
     let cols = ((available_width / cell_width).floor() as usize).max(1);
     let actual_cell_width = available_width / cols as f32;
     let total_rows = (items.len() + cols - 1) / cols;
+    let ppp = ctx.pixels_per_point();
 
     let mut clicked: Option<usize> = None;
 
@@ -140,17 +200,24 @@ pub fn show_grid(
                 // 图标
                 let icon_top = rect.top() + cell_padding;
                 let icon_center_x = rect.center().x;
-                let icon_rect = egui::Rect::from_center_size(
-                    egui::pos2(icon_center_x, icon_top + icon_size / 2.0),
-                    egui::vec2(icon_size, icon_size),
+                let icon_rect = snap_icon_rect(
+                    egui::Rect::from_center_size(
+                        egui::pos2(icon_center_x, icon_top + icon_size / 2.0),
+                        egui::vec2(icon_size, icon_size),
+                    ),
+                    ppp,
                 );
-                if let Some(icon) = get_or_load_icon(icon_cache, ctx, game, item.icon_id) {
+                if let Some(icon) =
+                    get_or_load_icon_sized(icon_cache, ctx, game, item.icon_id, icon_size)
+                {
                     ui.painter().image(
                         icon.id(),
                         icon_rect,
                         egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
                         egui::Color32::WHITE,
                     );
+                } else if icon_cache.failure(item.icon_id).is_some() {
+                    paint_broken_icon(ui.painter(), icon_rect, ui.visuals().weak_text_color());
                 }
 
                 // 文字
@@ -164,8 +231,12 @@ pub fn show_grid(
                     egui::FontId::proportional(11.0),
                     text_color,
                 );
+                if let Some(badge) = item.badge {
+                    paint_grid_badge(ui, rect, badge);
+                }
 
                 response.clone().on_hover_text(item.name);
+                icon_failure_menu(&response, icon_cache, item.icon_id);
 
                 if response.clicked() {
                     clicked = Some(item.id);
@@ -184,7 +255,7 @@ pub fn show_grid_scroll(
     items: &[DisplayItem<'_>],
     icon_size: f32,
     id_salt: &str,
-    icon_cache: &mut HashMap<u32, Option<egui::TextureHandle>>,
+    icon_cache: &mut IconCache,
     ctx: &egui::Context,
     game: &GameData,
 ) -> Option<usize> {
@@ -201,6 +272,7 @@ pub fn show_grid_scroll(
     let cols = ((available_width / cell_width).floor() as usize).max(1);
     let actual_cell_width = available_width / cols as f32;
     let total_rows = (items.len() + cols - 1) / cols;
+    let ppp = ctx.pixels_per_point();
 
     let mut clicked: Option<usize> = None;
 
@@ -231,11 +303,16 @@ pub fn show_grid_scroll(
 
                         let icon_top = rect.top() + cell_padding;
                         let icon_center_x = rect.center().x;
-                        let icon_rect = egui::Rect::from_center_size(
-                            egui::pos2(icon_center_x, icon_top + icon_size / 2.0),
-                            egui::vec2(icon_size, icon_size),
+                        let icon_rect = snap_icon_rect(
+                            egui::Rect::from_center_size(
+                                egui::pos2(icon_center_x, icon_top + icon_size / 2.0),
+                                egui::vec2(icon_size, icon_size),
+                            ),
+                            ppp,
                         );
-                        if let Some(icon) = get_or_load_icon(icon_cache, ctx, game, item.icon_id) {
+                        if let Some(icon) =
+                            get_or_load_icon_sized(icon_cache, ctx, game, item.icon_id, icon_size)
+                        {
                             ui.painter().image(
                                 icon.id(),
                                 icon_rect,
@@ -245,6 +322,12 @@ pub fn show_grid_scroll(
                                 ),
                                 egui::Color32::WHITE,
                             );
+                        } else if icon_cache.failure(item.icon_id).is_some() {
+                            paint_broken_icon(
+                                ui.painter(),
+                                icon_rect,
+                                ui.visuals().weak_text_color(),
+                            );
                         }
 
                         let text_top = icon_top + icon_size + cell_padding;
@@ -257,8 +340,12 @@ pub fn show_grid_scroll(
                             egui::FontId::proportional(11.0),
                             text_color,
                         );
+                        if let Some(badge) = item.badge {
+                            paint_grid_badge(ui, rect, badge);
+                        }
 
                         response.clone().on_hover_text(item.name);
+                        icon_failure_menu(&response, icon_cache, item.icon_id);
 
                         if response.clicked() {
                             clicked = Some(item.id);
@@ -271,37 +358,362 @@ pub fn show_grid_scroll(
     clicked
 }
 
-/// 从 icon_cache 获取或加载图标
+/// 图标分辨率版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IconVariant {
+    /// 标准版本 (40px)
+    Base,
+    /// 高清版本 `_hr1` (80px)
+    Hr1,
+}
+
+impl IconVariant {
+    /// 屏幕上实际物理像素超过该值时改用高清版本，避免放大标准图标导致模糊
+    const HR_THRESHOLD_PX: f32 = 44.0;
+
+    /// 按绘制尺寸 (逻辑点) 和当前缩放比例选择图标版本
+    pub fn for_size(size_points: f32, pixels_per_point: f32) -> Self {
+        if size_points * pixels_per_point > Self::HR_THRESHOLD_PX {
+            Self::Hr1
+        } else {
+            Self::Base
+        }
+    }
+}
+
+/// 默认内存中保留的图标纹理数量上限
+const DEFAULT_ICON_CAPACITY: usize = 1024;
+
+/// 图标纹理缓存: (icon_id, 版本) -> 纹理 (None 表示加载失败)
+///
+/// 超出容量时淘汰最久未使用的条目，丢弃 TextureHandle 即释放对应的 egui 纹理
+pub struct IconCache {
+    /// (纹理, 最近使用序号)
+    entries: HashMap<(u32, IconVariant), (Option<egui::TextureHandle>, u64)>,
+    /// 两个版本都加载失败的图标及原因，不受容量淘汰影响，用于占位显示与汇总提示
+    failures: BTreeMap<u32, String>,
+    tick: u64,
+    pub capacity: usize,
+}
+
+impl Default for IconCache {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            failures: BTreeMap::new(),
+            tick: 0,
+            capacity: DEFAULT_ICON_CAPACITY,
+        }
+    }
+}
+
+impl IconCache {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 图标加载失败的原因，未失败或尚未加载时为 None
+    pub fn failure(&self, icon_id: u32) -> Option<&str> {
+        self.failures.get(&icon_id).map(String::as_str)
+    }
+
+    /// 全部加载失败的图标 (按 icon_id 排序)
+    pub fn failures(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.failures
+            .iter()
+            .map(|(&id, reason)| (id, reason.as_str()))
+    }
+
+    pub fn failure_count(&self) -> usize {
+        self.failures.len()
+    }
+
+    /// 清除图标的失败记录，下次显示时重新读取
+    pub fn retry(&mut self, icon_id: u32) {
+        self.failures.remove(&icon_id);
+        for variant in [IconVariant::Base, IconVariant::Hr1] {
+            if matches!(self.entries.get(&(icon_id, variant)), Some((None, _))) {
+                self.entries.remove(&(icon_id, variant));
+            }
+        }
+    }
+
+    /// 查询并刷新使用时间: 外层 None 表示未缓存，内层 None 表示加载失败
+    fn get(&mut self, key: (u32, IconVariant)) -> Option<Option<egui::TextureHandle>> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(&key).map(|(tex, last_used)| {
+            *last_used = tick;
+            tex.clone()
+        })
+    }
+
+    fn insert(&mut self, key: (u32, IconVariant), texture: Option<egui::TextureHandle>) {
+        self.tick += 1;
+        self.entries.insert(key, (texture, self.tick));
+        self.evict_over_capacity();
+    }
+
+    fn remove(&mut self, key: (u32, IconVariant)) {
+        self.entries.remove(&key);
+    }
+
+    fn evict_over_capacity(&mut self) {
+        while self.entries.len() > self.capacity {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+/// 将图标绘制区域对齐到物理像素，避免缩放后采样落在像素之间而发虚
+pub fn snap_icon_rect(rect: egui::Rect, pixels_per_point: f32) -> egui::Rect {
+    let snap = |v: f32| (v * pixels_per_point).round() / pixels_per_point;
+    let min = egui::pos2(snap(rect.min.x), snap(rect.min.y));
+    let size = egui::vec2(snap(rect.width()), snap(rect.height()));
+    egui::Rect::from_min_size(min, size)
+}
+
+/// 图标加载失败时在图标位置绘制的占位符号
+pub fn paint_broken_icon(painter: &egui::Painter, rect: egui::Rect, color: egui::Color32) {
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        egui_phosphor::regular::IMAGE_BROKEN,
+        egui::FontId::proportional(rect.height() * 0.6),
+        color,
+    );
+}
+
+/// 加载失败的图标: 悬停显示原因，右键可清除失败记录重新加载
+pub fn icon_failure_menu(response: &egui::Response, icon_cache: &mut IconCache, icon_id: u32) {
+    let Some(reason) = icon_cache.failure(icon_id) else {
+        return;
+    };
+    let reason = format!("图标 {} 加载失败: {}", icon_id, reason);
+    let response = response.clone().on_hover_text(reason);
+    response.context_menu(|ui| {
+        if ui
+            .button(format!(
+                "{} 重试加载图标",
+                egui_phosphor::regular::ARROW_CLOCKWISE
+            ))
+            .clicked()
+        {
+            icon_cache.retry(icon_id);
+            ui.close();
+        }
+    });
+}
+
+/// 列表行左侧的小图标，加载失败时显示占位符号
+pub fn show_row_icon(
+    ui: &mut egui::Ui,
+    icon_cache: &mut IconCache,
+    ctx: &egui::Context,
+    game: &GameData,
+    icon_id: u32,
+    size: f32,
+) {
+    if let Some(icon) = get_or_load_icon(icon_cache, ctx, game, icon_id) {
+        ui.image(egui::load::SizedTexture::new(
+            icon.id(),
+            egui::vec2(size, size),
+        ));
+        return;
+    }
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::click());
+    if icon_cache.failure(icon_id).is_some() {
+        paint_broken_icon(ui.painter(), rect, ui.visuals().weak_text_color());
+        icon_failure_menu(&response, icon_cache, icon_id);
+    }
+}
+
+/// 图标大小滑块 (逻辑点)，悬停或拖动时以实际大小预览图标框并标注将使用的图标版本
+pub fn icon_size_slider(ui: &mut egui::Ui, icon_size: &mut f32) {
+    ui.label(tr!("filter.icon_size"));
+    let size = *icon_size;
+    let ppp = ui.ctx().pixels_per_point();
+    ui.add(egui::Slider::new(icon_size, 32.0..=128.0).suffix("pt"))
+        .on_hover_ui(|ui| {
+            let (rect, _) =
+                ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
+            ui.painter().rect_stroke(
+                snap_icon_rect(rect, ppp),
+                2.0,
+                ui.visuals().widgets.inactive.fg_stroke,
+                egui::StrokeKind::Inside,
+            );
+            let variant = match IconVariant::for_size(size, ppp) {
+                IconVariant::Base => "40px",
+                IconVariant::Hr1 => "hr1",
+            };
+            ui.label(format!("{:.0}pt × {:.2} = {:.0}px ({})", size, ppp, size * ppp, variant));
+        });
+}
+
+/// 从 icon_cache 获取或加载高清图标 (高清版本缺失时回退到标准版本)
 pub fn get_or_load_icon(
-    icon_cache: &mut HashMap<u32, Option<egui::TextureHandle>>,
+    icon_cache: &mut IconCache,
     ctx: &egui::Context,
     game: &GameData,
     icon_id: u32,
+) -> Option<egui::TextureHandle> {
+    get_or_load_icon_variant(icon_cache, ctx, game, icon_id, IconVariant::Hr1)
+}
+
+/// 按绘制尺寸 (逻辑点) 获取合适版本的图标
+pub fn get_or_load_icon_sized(
+    icon_cache: &mut IconCache,
+    ctx: &egui::Context,
+    game: &GameData,
+    icon_id: u32,
+    size_points: f32,
+) -> Option<egui::TextureHandle> {
+    let variant = IconVariant::for_size(size_points, ctx.pixels_per_point());
+    get_or_load_icon_variant(icon_cache, ctx, game, icon_id, variant)
+}
+
+/// 从 icon_cache 获取或加载指定版本的图标
+///
+/// - 请求标准版本而高清版本已缓存时直接复用高清纹理
+/// - 请求高清版本而只缓存了标准版本时加载高清纹理并替换掉标准条目 (升级)
+/// - 某一版本在游戏文件中不存在时回退到另一版本
+pub fn get_or_load_icon_variant(
+    icon_cache: &mut IconCache,
+    ctx: &egui::Context,
+    game: &GameData,
+    icon_id: u32,
+    variant: IconVariant,
 ) -> Option<egui::TextureHandle> {
     if icon_id == 0 {
         return None;
     }
-    if let Some(cached) = icon_cache.get(&icon_id) {
-        return cached.clone();
+    if let Some(Some(tex)) = icon_cache.get((icon_id, IconVariant::Hr1)) {
+        return Some(tex);
     }
-    let result = game.load_icon(icon_id).map(|tex_data| {
-        let size = [tex_data.width as _, tex_data.height as _];
-        let pixels: Vec<egui::Color32> = tex_data
-            .rgba
-            .chunks_exact(4)
-            .map(|p| egui::Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-            .collect();
-        let color_image = egui::ColorImage {
-            size,
-            pixels,
-            source_size: egui::Vec2::new(40.0, 40.0),
+    if let Some(cached) = icon_cache.get((icon_id, variant)) {
+        return cached;
+    }
+
+    let hr = variant == IconVariant::Hr1;
+    let other = if hr {
+        IconVariant::Base
+    } else {
+        IconVariant::Hr1
+    };
+    // 请求的版本缺失或解码失败时改读另一版本，仍失败才记为失败
+    let loaded = match game.try_load_icon_variant(icon_id, hr) {
+        Ok(tex) => Ok((tex, variant)),
+        Err(first) => game
+            .try_load_icon_variant(icon_id, !hr)
+            .map(|tex| (tex, other))
+            .map_err(|second| format!("{}; {}", first, second)),
+    };
+    let (tex_data, loaded_variant) = match loaded {
+        Ok(loaded) => loaded,
+        Err(reason) => {
+            icon_cache.insert((icon_id, variant), None);
+            icon_cache.failures.insert(icon_id, reason);
+            return None;
+        }
+    };
+
+    let texture = icon_texture(ctx, icon_id, loaded_variant, tex_data);
+    if loaded_variant == IconVariant::Hr1 {
+        // 高清纹理可覆盖所有尺寸，释放标准版本
+        icon_cache.remove((icon_id, IconVariant::Base));
+    }
+    icon_cache.insert((icon_id, loaded_variant), Some(texture.clone()));
+    if loaded_variant != variant {
+        // 记录回退结果，避免每帧重复尝试读取缺失的版本
+        icon_cache.insert((icon_id, variant), Some(texture.clone()));
+    }
+    Some(texture)
+}
+
+fn icon_texture(
+    ctx: &egui::Context,
+    icon_id: u32,
+    variant: IconVariant,
+    tex_data: tomestone_render::TextureData,
+) -> egui::TextureHandle {
+    let size = [tex_data.width as _, tex_data.height as _];
+    let pixels: Vec<egui::Color32> = tex_data
+        .rgba
+        .chunks_exact(4)
+        .map(|p| egui::Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+        .collect();
+    let color_image = egui::ColorImage {
+        size,
+        pixels,
+        source_size: egui::Vec2::new(40.0, 40.0),
+    };
+    let name = match variant {
+        IconVariant::Base => format!("icon_{}", icon_id),
+        IconVariant::Hr1 => format!("icon_{}_hr1", icon_id),
+    };
+    ctx.load_texture(name, color_image, egui::TextureOptions::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_cache_reuses_result_until_key_changes() {
+        let mut cache = FilterCache::default();
+        let mut runs = 0;
+        let mut filter = |key: &str| {
+            cache.get_or_compute(key.to_string(), || {
+                runs += 1;
+                key.len()
+            })
         };
-        ctx.load_texture(
-            format!("icon_{}", icon_id),
-            color_image,
-            egui::TextureOptions::default(),
-        )
-    });
-    icon_cache.insert(icon_id, result.clone());
-    result
+        let first = filter("ring");
+        let again = filter("ring");
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(*filter("rin"), 3);
+        assert_eq!(*filter("ring"), 4);
+        // 只缓存最近一次结果，改回旧查询要重新计算
+        assert_eq!(runs, 3);
+    }
+
+    #[test]
+    fn items_identity_changes_with_list() {
+        let items = vec![1, 2, 3];
+        let reloaded = items.clone();
+        assert_eq!(items_identity(&items), items_identity(&items));
+        assert_ne!(items_identity(&items), items_identity(&reloaded));
+        assert_ne!(items_identity(&items), items_identity(&items[..2]));
+    }
+
+    #[test]
+    fn filter_cache_hit_does_not_allocate() {
+        let mut cache = FilterCache::default();
+        let rows: Vec<(u16, String)> = (0..1000).map(|i| (i, format!("set {i:04}"))).collect();
+        let cached = cache.get_or_compute(1, || rows.clone());
+
+        let before = crate::alloc_stats::thread_allocations();
+        let hit = cache.get_or_compute(1, Vec::new);
+        assert_eq!(crate::alloc_stats::thread_allocations(), before);
+        assert!(Arc::ptr_eq(&cached, &hit));
+
+        // 对照: 改为 Arc 之前命中时复制整个结果，每行的名称各分配一次
+        let before = crate::alloc_stats::thread_allocations();
+        let copied = Vec::clone(&hit);
+        assert!(crate::alloc_stats::thread_allocations() - before > 1000);
+        assert_eq!(copied, rows);
+    }
 }
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use eframe::egui;
+use physis::mtrl::ColorTable;
+use physis::stm::StainingTemplate;
+
+use crate::dye::{self, DyedRow, RowColors};
+use crate::game::{
+    count_missing, CachedMaterial, MaterialDiagnostic, SkinningReport, TextureSource,
+};
+
+fn source_label(source: TextureSource) -> &'static str {
+    match source {
+        TextureSource::Diffuse => "diffuse",
+        TextureSource::ColorTableBake => "ColorTable 烘焙",
+        TextureSource::Missing => "缺失",
+    }
+}
+
+/// 模型材质统计: 每个材质的贴图来源，以及加载失败的路径
+pub fn show_material_diagnostics(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    diagnostics: &[MaterialDiagnostic],
+) {
+    if diagnostics.is_empty() {
+        return;
+    }
+    let missing = count_missing(diagnostics);
+    let title = if missing > 0 {
+        format!("材质 ({} 个, {} 个贴图缺失)", diagnostics.len(), missing)
+    } else {
+        format!("材质 ({} 个)", diagnostics.len())
+    };
+    egui::CollapsingHeader::new(title)
+        .id_salt(id_salt)
+        .default_open(missing > 0)
+        .show(ui, |ui| {
+            for diag in diagnostics {
+                ui.horizontal(|ui| {
+                    ui.label(format!("[{}] {}", diag.material_index, diag.material_name));
+                    let label = source_label(diag.source);
+                    if diag.source == TextureSource::Missing {
+                        ui.colored_label(ui.visuals().error_fg_color, label);
+                    } else {
+                        ui.weak(label);
+                    }
+                });
+                if diag.source == TextureSource::Missing && diag.tried_paths.is_empty() {
+                    ui.weak("    材质索引超出范围");
+                }
+                for path in &diag.failed_paths {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("    失败: {}", path));
+                }
+            }
+        });
+}
+
+/// 跨种族蒙皮统计: 骨架中找不到的骨骼及受影响的顶点数，没有缺失时不显示
+pub fn show_skinning_report(ui: &mut egui::Ui, id_salt: &str, report: &SkinningReport) {
+    if report.is_empty() {
+        return;
+    }
+    let title = format!(
+        "蒙皮 ({} 个骨骼缺失, {} 个顶点受影响)",
+        report.unmatched_bones.len(),
+        report.affected_vertices
+    );
+    egui::CollapsingHeader::new(title)
+        .id_salt(id_salt)
+        .show(ui, |ui| {
+            ui.weak("可在视口的蒙皮调试着色中选择 \"缺失骨骼\" 查看受影响的顶点");
+            for bone in &report.unmatched_bones {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("    {}", bone));
+            }
+        });
+}
+
+/// 颜色表检查: 选中材质的每一行染色前后的漫反射/高光/自发光颜色，染色表标记的行高亮
+///
+/// 每帧按当前染料重新计算，`selected` 为选中的材质索引 (无效时选第一个有颜色表的材质)。
+pub fn show_color_table_inspector(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    materials: &HashMap<u16, CachedMaterial>,
+    diagnostics: &[MaterialDiagnostic],
+    stm: Option<&StainingTemplate>,
+    stain_ids: [u32; 2],
+    selected: &mut Option<u16>,
+) {
+    let mut indices: Vec<u16> = materials
+        .iter()
+        .filter(|(_, m)| {
+            m.color_table
+                .as_ref()
+                .is_some_and(|ct| !matches!(ct, ColorTable::OpaqueColorTable(_)))
+        })
+        .map(|(&i, _)| i)
+        .collect();
+    if indices.is_empty() {
+        return;
+    }
+    indices.sort_unstable();
+    let current = match *selected {
+        Some(i) if indices.contains(&i) => i,
+        _ => indices[0],
+    };
+    *selected = Some(current);
+    let material_label = |idx: u16| match diagnostics.iter().find(|d| d.material_index == idx) {
+        Some(diag) => format!("[{}] {}", idx, diag.material_name),
+        None => format!("[{}]", idx),
+    };
+
+    egui::CollapsingHeader::new("调色板数据")
+        .id_salt(id_salt)
+        .show(ui, |ui| {
+            let Some(stm) = stm else {
+                ui.weak("未加载染色模板 (stainingtemplate)");
+                return;
+            };
+            let material = &materials[&current];
+            let Some(color_table) = &material.color_table else {
+                return;
+            };
+            let rows = dye::apply_dye_rows(
+                color_table,
+                material.color_dye_table.as_ref(),
+                stm,
+                stain_ids,
+            );
+
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt((id_salt, "material"))
+                    .selected_text(material_label(current))
+                    .show_ui(ui, |ui| {
+                        for &idx in &indices {
+                            ui.selectable_value(selected, Some(idx), material_label(idx));
+                        }
+                    });
+                if ui.button("复制为 CSV").clicked() {
+                    ui.ctx().copy_text(color_table_csv(&rows));
+                }
+            });
+            if stain_ids == [0, 0] {
+                ui.weak("未选择染料，染色后与染色前相同");
+            }
+
+            egui::ScrollArea::vertical()
+                .id_salt((id_salt, "rows"))
+                .max_height(320.0)
+                .show(ui, |ui| {
+                    egui::Grid::new((id_salt, "grid"))
+                        .striped(true)
+                        .spacing([6.0, 2.0])
+                        .show(ui, |ui| {
+                            ui.strong("行");
+                            ui.strong("染色前");
+                            ui.strong("染色后");
+                            ui.strong("染色");
+                            ui.end_row();
+                            for (i, row) in rows.iter().enumerate() {
+                                show_color_table_row(ui, i, row);
+                            }
+                        });
+                });
+        });
+}
+
+fn show_color_table_row(ui: &mut egui::Ui, index: usize, row: &DyedRow) {
+    let dye = row.dye.filter(|d| d.affects_any());
+    let index_text = egui::RichText::new(index.to_string());
+    if dye.is_some() {
+        ui.label(
+            index_text
+                .strong()
+                .color(ui.visuals().selection.stroke.color),
+        );
+    } else {
+        ui.weak(index_text);
+    }
+    ui.horizontal(|ui| show_row_swatches(ui, &row.undyed, [false; 3]));
+    let flags = dye.map_or([false; 3], |d| [d.diffuse, d.specular, d.emissive]);
+    ui.horizontal(|ui| show_row_swatches(ui, &row.dyed, flags));
+    match dye {
+        Some(d) => ui.label(format!("通道 {} · 模板 {}", d.channel + 1, d.template)),
+        None => ui.weak("-"),
+    };
+    ui.end_row();
+}
+
+/// 漫反射/高光/自发光三个色块，`dyed` 标记的色块加描边
+fn show_row_swatches(ui: &mut egui::Ui, colors: &RowColors, dyed: [bool; 3]) {
+    let entries = [
+        ("漫反射", colors.diffuse),
+        ("高光", colors.specular),
+        ("自发光", colors.emissive),
+    ];
+    for ((name, c), dyed) in entries.into_iter().zip(dyed) {
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(18.0, 14.0), egui::Sense::hover());
+        let fill = egui::Color32::from(egui::Rgba::from_rgb(c[0], c[1], c[2]));
+        ui.painter().rect_filled(rect, 2.0, fill);
+        if dyed {
+            let stroke = egui::Stroke::new(1.5, ui.visuals().selection.stroke.color);
+            ui.painter()
+                .rect_stroke(rect, 2.0, stroke, egui::StrokeKind::Outside);
+        }
+        response.on_hover_text(format!(
+            "{}: {:.4}, {:.4}, {:.4}{}",
+            name,
+            c[0],
+            c[1],
+            c[2],
+            if dyed { " (染色)" } else { "" }
+        ));
+    }
+}
+
+/// 颜色表的 CSV 文本: 每行染色前后的三种颜色 (线性值) 与染色表信息
+fn color_table_csv(rows: &[DyedRow]) -> String {
+    let mut csv = String::from("row");
+    for state in ["undyed", "dyed"] {
+        for kind in ["diffuse", "specular", "emissive"] {
+            for ch in ["r", "g", "b"] {
+                let _ = write!(csv, ",{}_{}_{}", state, kind, ch);
+            }
+        }
+    }
+    csv.push_str(",template,channel,dye_diffuse,dye_specular,dye_emissive\n");
+    for (i, row) in rows.iter().enumerate() {
+        let _ = write!(csv, "{}", i);
+        for colors in [&row.undyed, &row.dyed] {
+            for c in [colors.diffuse, colors.specular, colors.emissive] {
+                let _ = write!(csv, ",{:.4},{:.4},{:.4}", c[0], c[1], c[2]);
+            }
+        }
+        match row.dye {
+            Some(d) => {
+                let _ = write!(
+                    csv,
+                    ",{},{},{},{},{}",
+                    d.template,
+                    d.channel + 1,
+                    d.diffuse,
+                    d.specular,
+                    d.emissive
+                );
+            }
+            None => csv.push_str(",,,,,"),
+        }
+        csv.push('\n');
+    }
+    csv
+}
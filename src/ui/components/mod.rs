@@ -2,8 +2,10 @@ pub mod dye_palette;
 pub mod equipment_list;
 pub mod item_detail;
 pub mod item_list;
+pub mod material_diagnostics;
 pub mod progress;
 pub mod template_editor;
+pub mod toast;
 pub mod viewport;
 
 pub use progress::{show_progress_bar, ProgressStatus, ProgressTracker, ProgressUnit};
@@ -0,0 +1,47 @@
+use eframe::egui;
+
+/// 提示显示时长 (秒)
+const TOAST_DURATION: f64 = 4.0;
+
+/// 视口右下角的短暂提示
+#[derive(Default)]
+pub struct Toasts {
+    /// (消息, 过期时间; 首次显示时确定)
+    items: Vec<(String, Option<f64>)>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        crate::crash::record_message(&message);
+        self.items.push((message, None));
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if self.items.is_empty() {
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        for (_, expires) in &mut self.items {
+            expires.get_or_insert(now + TOAST_DURATION);
+        }
+        self.items
+            .retain(|(_, expires)| expires.is_some_and(|t| t > now));
+        if self.items.is_empty() {
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                for (message, _) in &self.items {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(message);
+                    });
+                }
+            });
+        ctx.request_repaint_after(std::time::Duration::from_millis(250));
+    }
+}
@@ -1,7 +1,139 @@
+use std::collections::BTreeSet;
+
 use eframe::egui;
 use egui_wgpu::wgpu;
 use tomestone_render::{BoundingBox, Camera, ModelRenderer, SceneSettings};
 
+use crate::game::{MeshData, SkinVertex};
+use crate::viewport_input::ViewportInputConfig;
+
+/// 交互期间的渲染分辨率比例
+const INTERACTIVE_RENDER_SCALE: f32 = 0.5;
+/// 相机停止操作多久后以全分辨率重新渲染 (秒)
+const IDLE_FULL_RES_DELAY: f64 = 0.2;
+
+/// 权重和与 1 的允许误差 (权重以 8 bit 存储，存在量化误差)
+const WEIGHT_SUM_TOLERANCE: f32 = 0.02;
+
+/// 蒙皮调试着色模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkinDebugMode {
+    Off,
+    /// 按权重最大的骨骼着色 (骨骼名哈希取色)
+    DominantBone,
+    /// 按权重和着色: 正常为灰，偏离 1 为红，无蒙皮为蓝
+    WeightSum,
+    /// 跨种族蒙皮时引用了缺失骨骼的顶点标为品红，其余为灰
+    UnmatchedBones,
+}
+
+impl SkinDebugMode {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SkinDebugMode::Off => tr!("skin_debug.off"),
+            SkinDebugMode::DominantBone => tr!("skin_debug.dominant_bone"),
+            SkinDebugMode::WeightSum => tr!("skin_debug.weight_sum"),
+            SkinDebugMode::UnmatchedBones => tr!("skin_debug.unmatched_bones"),
+        }
+    }
+}
+
+const SKIN_DEBUG_MODES: [SkinDebugMode; 4] = [
+    SkinDebugMode::Off,
+    SkinDebugMode::DominantBone,
+    SkinDebugMode::WeightSum,
+    SkinDebugMode::UnmatchedBones,
+];
+
+/// 视口保留的蒙皮数据，与 set_mesh_data 的输入网格一一对应
+struct SkinDebugMesh {
+    vertex_count: usize,
+    skin_vertices: Vec<SkinVertex>,
+    bone_names: Vec<String>,
+}
+
+/// 骨骼名哈希到固定色相，同名骨骼在不同网格间颜色一致
+fn bone_color(name: &str) -> [f32; 4] {
+    let hash = name.bytes().fold(0x811c9dc5u32, |h, b| {
+        (h ^ b as u32).wrapping_mul(0x01000193)
+    });
+    let hue = (hash % 360) as f32 / 360.0;
+    let rgba = egui::Rgba::from(egui::ecolor::Hsva::new(hue, 0.75, 0.9, 1.0));
+    [rgba.r(), rgba.g(), rgba.b(), 1.0]
+}
+
+fn skin_debug_colors(
+    mesh: &SkinDebugMesh,
+    mode: SkinDebugMode,
+    unmatched_bones: &BTreeSet<String>,
+) -> Vec<[f32; 4]> {
+    const UNSKINNED: [f32; 4] = [0.2, 0.35, 0.9, 1.0];
+    (0..mesh.vertex_count)
+        .map(|vi| {
+            let Some(skin) = mesh.skin_vertices.get(vi) else {
+                return UNSKINNED;
+            };
+            let total: f32 = skin.blend_weights.iter().sum();
+            if total < 1e-6 {
+                return UNSKINNED;
+            }
+            match mode {
+                SkinDebugMode::Off => [0.0; 4],
+                SkinDebugMode::DominantBone => {
+                    let dominant = (0..4)
+                        .max_by(|&a, &b| skin.blend_weights[a].total_cmp(&skin.blend_weights[b]))
+                        .unwrap_or(0);
+                    match mesh.bone_names.get(skin.blend_indices[dominant] as usize) {
+                        Some(name) => bone_color(name),
+                        None => [1.0, 0.0, 1.0, 1.0],
+                    }
+                }
+                SkinDebugMode::WeightSum => {
+                    let error = (total - 1.0).abs();
+                    if error <= WEIGHT_SUM_TOLERANCE {
+                        [0.6, 0.6, 0.6, 1.0]
+                    } else {
+                        let t = (error * 4.0).clamp(0.3, 1.0);
+                        [0.6 + 0.4 * t, 0.6 * (1.0 - t), 0.6 * (1.0 - t), 1.0]
+                    }
+                }
+                SkinDebugMode::UnmatchedBones => {
+                    let affected = (0..4).any(|i| {
+                        skin.blend_weights[i] > 1e-6
+                            && mesh
+                                .bone_names
+                                .get(skin.blend_indices[i] as usize)
+                                .is_some_and(|name| unmatched_bones.contains(name))
+                    });
+                    if affected {
+                        [1.0, 0.0, 1.0, 1.0]
+                    } else {
+                        [0.6, 0.6, 0.6, 1.0]
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// 视口背景色: 跟随当前主题的面板色，略向中灰偏移以便与面板区分
+fn themed_background(visuals: &egui::Visuals) -> egui::Color32 {
+    visuals
+        .panel_fill
+        .lerp_to_gamma(egui::Color32::from_gray(128), 0.1)
+}
+
+/// 根据背景亮度选择对比度足够的文字颜色
+fn contrast_text_color(bg: egui::Color32) -> egui::Color32 {
+    let rgba = egui::Rgba::from(bg);
+    let luminance = 0.2126 * rgba.r() + 0.7152 * rgba.g() + 0.0722 * rgba.b();
+    if luminance > 0.18 {
+        egui::Color32::from_gray(40)
+    } else {
+        egui::Color32::from_gray(200)
+    }
+}
+
 pub struct ViewportState {
     pub render_state: egui_wgpu::RenderState,
     pub model_renderer: ModelRenderer,
@@ -9,23 +141,79 @@ pub struct ViewportState {
     pub scene: SceneSettings,
     pub texture_id: Option<egui::TextureId>,
     pub last_bbox: Option<BoundingBox>,
+    /// 测量模式: 左键单击依次拾取两点，显示两点间距离
+    pub measure_enabled: bool,
+    pub measure_a: Option<[f32; 3]>,
+    pub measure_b: Option<[f32; 3]>,
+    /// 显式指定的背景色，None 时跟随界面主题
+    pub background_override: Option<egui::Color32>,
+    /// 自适应分辨率: 拖拽/缩放相机时以半分辨率渲染，停止后恢复全分辨率
+    pub adaptive_resolution: bool,
+    /// 鼠标操作绑定 (由 App 每帧同步设置)
+    pub input: ViewportInputConfig,
+    /// 加载时把模型包围盒中心平移到原点 (None: 该视口不提供此选项)
+    pub recenter_origin: Option<bool>,
+    /// 当前显示的网格被平移的量 (原始坐标 = 显示坐标 + 偏移)，未平移时为 None
+    pub origin_offset: Option<[f32; 3]>,
+    /// 用户切换了重置原点，页面需重新加载模型
+    recenter_toggled: bool,
+    /// 最近一次相机交互的时间
+    last_interaction: f64,
+    /// 上次渲染使用的分辨率比例及对应的 UV 范围
+    rendered_scale: f32,
+    uv_max: egui::Vec2,
+    /// 蒙皮调试着色
+    pub skin_debug: SkinDebugMode,
+    skin_meshes: Vec<SkinDebugMesh>,
+    /// 跨种族蒙皮时找不到的骨骼 (缺失骨骼着色模式使用)
+    unmatched_bones: BTreeSet<String>,
+    /// 鼠标下顶点的骨骼权重说明 (指针位置, 文本)，指针不动时复用
+    skin_hover: Option<(egui::Pos2, Option<String>)>,
     /// 脏标记：仅在相机/模型/尺寸变化时重新渲染
     dirty: bool,
     last_vp_size: [u32; 2],
 }
 
 impl ViewportState {
-    pub fn new(render_state: egui_wgpu::RenderState) -> Self {
-        let model_renderer = ModelRenderer::new(&render_state.device);
-        Self {
+    /// 渲染器创建失败 (后端不支持所需功能) 时返回 Err
+    pub fn new(render_state: egui_wgpu::RenderState) -> Result<Self, String> {
+        let model_renderer = ModelRenderer::new(&render_state.device)?;
+        Ok(Self {
             render_state,
             model_renderer,
             camera: Camera::default(),
             scene: SceneSettings::default(),
             texture_id: None,
             last_bbox: None,
+            measure_enabled: false,
+            measure_a: None,
+            measure_b: None,
+            background_override: None,
+            adaptive_resolution: true,
+            input: ViewportInputConfig::default(),
+            recenter_origin: None,
+            origin_offset: None,
+            recenter_toggled: false,
+            last_interaction: f64::NEG_INFINITY,
+            rendered_scale: 1.0,
+            uv_max: egui::vec2(1.0, 1.0),
+            skin_debug: SkinDebugMode::Off,
+            skin_meshes: Vec::new(),
+            unmatched_bones: BTreeSet::new(),
+            skin_hover: None,
             dirty: true,
             last_vp_size: [0, 0],
+        })
+    }
+
+    /// 没有 wgpu (无 3D 模式) 或渲染器创建失败时返回 None，调用方显示 `show_viewport` 的占位
+    pub fn create(render_state: Option<&egui_wgpu::RenderState>) -> Option<Self> {
+        match Self::new(render_state?.clone()) {
+            Ok(viewport) => Some(viewport),
+            Err(e) => {
+                eprintln!("3D 视口不可用: {}", e);
+                None
+            }
         }
     }
 
@@ -34,7 +222,86 @@ impl ViewportState {
         self.dirty = true;
     }
 
+    /// 保留蒙皮数据供调试着色使用，`meshes` 需与传给 set_mesh_data 的网格顺序一致
+    pub fn set_skin_data(&mut self, meshes: &[MeshData]) {
+        self.skin_meshes = meshes
+            .iter()
+            .map(|m| SkinDebugMesh {
+                vertex_count: m.vertices.len(),
+                skin_vertices: m.skin_vertices.clone(),
+                bone_names: m.bone_names.clone(),
+            })
+            .collect();
+        self.unmatched_bones.clear();
+        self.skin_hover = None;
+        self.upload_skin_debug_colors();
+    }
+
+    /// 设置蒙皮时找不到的骨骼，需在 set_skin_data 之后调用
+    pub fn set_unmatched_bones(&mut self, bones: BTreeSet<String>) {
+        self.unmatched_bones = bones;
+        self.upload_skin_debug_colors();
+    }
+
+    fn upload_skin_debug_colors(&mut self) {
+        let enabled = self.skin_debug != SkinDebugMode::Off;
+        self.model_renderer.set_debug_overlay(enabled);
+        if enabled {
+            let colors: Vec<Vec<[f32; 4]>> = self
+                .skin_meshes
+                .iter()
+                .map(|m| skin_debug_colors(m, self.skin_debug, &self.unmatched_bones))
+                .collect();
+            self.model_renderer
+                .set_debug_colors(&self.render_state.queue, &colors);
+        }
+        self.dirty = true;
+    }
+
+    /// 鼠标下最近顶点的骨骼权重，按权重降序列出
+    fn skin_hover_text(&self, origin: [f32; 3], dir: [f32; 3]) -> Option<String> {
+        let (mesh_idx, vertex_idx) = self.model_renderer.raycast_vertex(origin, dir)?;
+        let mesh = self.skin_meshes.get(mesh_idx)?;
+        let Some(skin) = mesh.skin_vertices.get(vertex_idx) else {
+            return Some(tr!("skin_debug.no_skin").to_string());
+        };
+        let mut influences: Vec<(f32, &str)> = (0..4)
+            .filter(|&i| skin.blend_weights[i] > 1e-6)
+            .map(|i| {
+                let name = mesh
+                    .bone_names
+                    .get(skin.blend_indices[i] as usize)
+                    .map(|s| s.as_str())
+                    .unwrap_or("?");
+                (skin.blend_weights[i], name)
+            })
+            .collect();
+        influences.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let total: f32 = skin.blend_weights.iter().sum();
+        let mut lines: Vec<String> = influences
+            .iter()
+            .map(|(w, name)| format!("{} {:.3}", name, w))
+            .collect();
+        lines.push(format!("{} {:.3}", tr!("skin_debug.weight_sum"), total));
+        Some(lines.join("\n"))
+    }
+
+    /// 用户是否切换了重置原点 (读取后清除)
+    pub fn take_recenter_toggled(&mut self) -> bool {
+        std::mem::take(&mut self.recenter_toggled)
+    }
+
+    pub fn clear_measurement(&mut self) {
+        self.measure_a = None;
+        self.measure_b = None;
+    }
+
     pub fn show(&mut self, ui: &mut egui::Ui, _ctx: &egui::Context, empty_label: &str) {
+        if self.model_renderer.is_gpu_released() {
+            self.model_renderer
+                .restore_gpu(&self.render_state.device, &self.render_state.queue);
+            self.upload_skin_debug_colors();
+        }
         let available = ui.available_size();
         let vp_w = (available.x as u32).max(1);
         let vp_h = (available.y as u32).max(1);
@@ -51,16 +318,22 @@ impl ViewportState {
         );
 
         // 相机交互 — 有变化时标记脏
-        if response.dragged_by(egui::PointerButton::Primary) {
-            let delta = response.drag_delta();
-            self.camera.yaw += delta.x * 0.01;
-            self.camera.pitch = (self.camera.pitch + delta.y * 0.01).clamp(-1.5, 1.5);
+        let mut interacting = false;
+        let shift = ui.input(|i| i.modifiers.shift);
+        let (orbit, pan) = self.input.drag_modes(&response, shift);
+        if orbit {
+            let (yaw, pitch) = self.input.orbit_delta(response.drag_delta());
+            let limit = self.input.pitch_limit();
+            self.camera.yaw += yaw;
+            self.camera.pitch = (self.camera.pitch + pitch).clamp(-limit, limit);
             self.dirty = true;
+            interacting = true;
         }
-        if response.dragged_by(egui::PointerButton::Secondary) {
+        if pan {
             let delta = response.drag_delta();
             self.camera.pan(delta.x, delta.y);
             self.dirty = true;
+            interacting = true;
         }
         if response.double_clicked() {
             if let Some(bbox) = &self.last_bbox {
@@ -73,23 +346,74 @@ impl ViewportState {
         if response.hovered() {
             let scroll = ui.input(|i| i.smooth_scroll_delta.y);
             if scroll != 0.0 {
-                self.camera.distance =
-                    (self.camera.distance - scroll * 0.005).clamp(0.1, self.camera.max_distance);
+                self.camera
+                    .zoom(self.input.zoom_amount(scroll, self.camera.scene_size));
                 self.dirty = true;
+                interacting = true;
             }
         }
 
+        // 自适应分辨率: 交互中降低分辨率，空闲一段时间后以全分辨率补渲染一次
+        let now = ui.input(|i| i.time);
+        if interacting {
+            self.last_interaction = now;
+        }
+        let idle = now - self.last_interaction;
+        let render_scale = if self.adaptive_resolution && idle < IDLE_FULL_RES_DELAY {
+            ui.ctx()
+                .request_repaint_after(std::time::Duration::from_secs_f64(
+                    IDLE_FULL_RES_DELAY - idle,
+                ));
+            INTERACTIVE_RENDER_SCALE
+        } else {
+            1.0
+        };
+        if render_scale != self.rendered_scale {
+            self.dirty = true;
+        }
+
+        // 背景色跟随主题 (或显式覆盖)，变化时重新渲染
+        let background = self
+            .background_override
+            .unwrap_or_else(|| themed_background(ui.visuals()));
+        let clear = egui::Rgba::from(background);
+        let clear = [
+            clear.r() as f64,
+            clear.g() as f64,
+            clear.b() as f64,
+            clear.a() as f64,
+        ];
+        if self.scene.background_color != clear {
+            self.scene.background_color = clear;
+            self.dirty = true;
+        }
+        let text_color = contrast_text_color(background);
+
+        let aspect = vp_w as f32 / vp_h as f32;
+        if self.measure_enabled && self.model_renderer.has_mesh() {
+            self.handle_measure_input(ui, &response, rect, aspect);
+        }
+
+        let skin_hover_text = if self.skin_debug != SkinDebugMode::Off {
+            self.update_skin_hover(&response, rect, aspect)
+        } else {
+            None
+        };
+
         if self.model_renderer.has_mesh() {
             // 仅在脏时重新渲染
             if self.dirty {
-                self.model_renderer.render_offscreen(
+                let uv_max = self.model_renderer.render_offscreen(
                     &self.render_state.device,
                     &self.render_state.queue,
                     vp_w,
                     vp_h,
+                    render_scale,
                     &self.camera,
                     &self.scene,
                 );
+                self.uv_max = egui::vec2(uv_max[0], uv_max[1]);
+                self.rendered_scale = render_scale;
                 self.dirty = false;
 
                 // 渲染后更新 egui 纹理
@@ -121,7 +445,7 @@ impl ViewportState {
                     ui.painter().image(
                         tid,
                         rect,
-                        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                        egui::Rect::from_min_max(egui::Pos2::ZERO, self.uv_max.to_pos2()),
                         egui::Color32::WHITE,
                     );
                 }
@@ -130,35 +454,309 @@ impl ViewportState {
                 ui.painter().image(
                     tid,
                     rect,
-                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Rect::from_min_max(egui::Pos2::ZERO, self.uv_max.to_pos2()),
                     egui::Color32::WHITE,
                 );
             }
 
+            self.paint_measurement(ui, rect, aspect);
+
+            if let (Some(text), Some(pos)) = (&skin_hover_text, response.hover_pos()) {
+                let painter = ui.painter_at(rect);
+                let galley = painter.layout_no_wrap(
+                    text.clone(),
+                    egui::FontId::monospace(12.0),
+                    egui::Color32::WHITE,
+                );
+                let bg = egui::Align2::LEFT_TOP
+                    .anchor_size(pos + egui::vec2(16.0, 16.0), galley.size())
+                    .expand(4.0);
+                painter.rect_filled(bg, 3.0, egui::Color32::from_black_alpha(180));
+                painter.galley(bg.shrink(4.0).min, galley, egui::Color32::WHITE);
+            }
+
+            let measure_btn = ui.put(
+                egui::Rect::from_min_size(
+                    egui::pos2(rect.right() - 76.0, rect.top() + 8.0),
+                    egui::vec2(68.0, 22.0),
+                ),
+                egui::Button::new(format!(
+                    "{} {}",
+                    egui_phosphor::regular::RULER,
+                    tr!("viewport.measure")
+                ))
+                .selected(self.measure_enabled),
+            );
+            if measure_btn.clicked() {
+                self.measure_enabled = !self.measure_enabled;
+                self.clear_measurement();
+            }
+
+            ui.put(
+                egui::Rect::from_min_size(
+                    egui::pos2(rect.right() - 140.0, rect.top() + 8.0),
+                    egui::vec2(28.0, 22.0),
+                ),
+                |ui: &mut egui::Ui| {
+                    let mut changed = false;
+                    let response = ui
+                        .menu_button(egui_phosphor::regular::BONE, |ui| {
+                            ui.label(tr!("viewport.skin_debug"));
+                            for mode in SKIN_DEBUG_MODES {
+                                changed |= ui
+                                    .radio_value(&mut self.skin_debug, mode, mode.display_name())
+                                    .changed();
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr!("viewport.skin_debug"));
+                    if changed {
+                        self.skin_hover = None;
+                        self.upload_skin_debug_colors();
+                    }
+                    response
+                },
+            );
+
+            ui.put(
+                egui::Rect::from_min_size(
+                    egui::pos2(rect.right() - 108.0, rect.top() + 8.0),
+                    egui::vec2(28.0, 22.0),
+                ),
+                |ui: &mut egui::Ui| {
+                    ui.menu_button(egui_phosphor::regular::PALETTE, |ui| {
+                        let mut follow_theme = self.background_override.is_none();
+                        if ui
+                            .checkbox(&mut follow_theme, tr!("viewport.background_follow_theme"))
+                            .changed()
+                        {
+                            self.background_override =
+                                if follow_theme { None } else { Some(background) };
+                        }
+                        if let Some(color) = &mut self.background_override {
+                            ui.horizontal(|ui| {
+                                ui.label(tr!("viewport.background_color"));
+                                ui.color_edit_button_srgba(color);
+                            });
+                        }
+                        // 模型偏暗时排查是否来自顶点色遮罩或 AO 贴图 (仅本次运行有效)
+                        ui.separator();
+                        let scene = &mut self.scene;
+                        let toggled = ui
+                            .checkbox(
+                                &mut scene.ignore_vertex_color_mask,
+                                tr!("viewport.ignore_vertex_color_mask"),
+                            )
+                            .changed()
+                            | ui.checkbox(&mut scene.ignore_ao, tr!("viewport.ignore_ao"))
+                                .changed();
+                        if toggled {
+                            self.dirty = true;
+                        }
+                    })
+                    .response
+                },
+            );
+
+            if let Some(recenter) = self.recenter_origin {
+                let recenter_btn = ui
+                    .put(
+                        egui::Rect::from_min_size(
+                            egui::pos2(rect.right() - 172.0, rect.top() + 8.0),
+                            egui::vec2(28.0, 22.0),
+                        ),
+                        egui::Button::new(egui_phosphor::regular::CROSSHAIR).selected(recenter),
+                    )
+                    .on_hover_text(tr!("viewport.recenter_origin"));
+                if recenter_btn.clicked() {
+                    self.recenter_origin = Some(!recenter);
+                    self.recenter_toggled = true;
+                }
+            }
+
+            let hint = if self.measure_enabled {
+                tr!("viewport.hint_measure")
+            } else {
+                tr!("viewport.hint")
+            };
             ui.painter().text(
                 egui::pos2(rect.left() + 8.0, rect.bottom() - 8.0),
                 egui::Align2::LEFT_BOTTOM,
-                "左键旋转 | 右键平移 | 滚轮缩放 | 双击重置",
+                hint,
                 egui::FontId::proportional(12.0),
-                egui::Color32::from_rgba_premultiplied(180, 180, 180, 160),
+                text_color.gamma_multiply(0.7),
             );
         } else {
-            ui.painter()
-                .rect_filled(rect, 0.0, egui::Color32::from_rgb(30, 30, 36));
+            ui.painter().rect_filled(rect, 0.0, background);
             ui.painter().text(
                 rect.center(),
                 egui::Align2::CENTER_CENTER,
                 empty_label,
                 egui::FontId::default(),
-                egui::Color32::GRAY,
+                text_color,
             );
         }
     }
 
+    /// 指针移动时重新拾取鼠标下的顶点
+    fn update_skin_hover(
+        &mut self,
+        response: &egui::Response,
+        rect: egui::Rect,
+        aspect: f32,
+    ) -> Option<String> {
+        let pos = response.hover_pos()?;
+        // 相机变化 (dirty) 后同一指针位置下的顶点也会改变
+        if let Some((last_pos, text)) = &self.skin_hover {
+            if *last_pos == pos && !self.dirty {
+                return text.clone();
+            }
+        }
+        let ndc = [
+            (pos.x - rect.left()) / rect.width() * 2.0 - 1.0,
+            1.0 - (pos.y - rect.top()) / rect.height() * 2.0,
+        ];
+        let (origin, dir) = self.camera.screen_ray(ndc, aspect);
+        let text = self.skin_hover_text(origin, dir);
+        self.skin_hover = Some((pos, text.clone()));
+        text
+    }
+
+    /// 页面隐藏时释放 GPU 资源 (网格、纹理、渲染目标及注册的 egui 纹理)，
+    /// CPU 端数据保留，下次 show 时自动重新上传
+    pub fn release_gpu(&mut self) {
+        self.model_renderer.release_gpu();
+        if let Some(tid) = self.texture_id.take() {
+            self.render_state.renderer.write().free_texture(&tid);
+        }
+        self.dirty = true;
+    }
+
+    /// 估算的 GPU 内存占用 (字节)
+    pub fn gpu_memory_bytes(&self) -> u64 {
+        self.model_renderer.gpu_memory_bytes()
+    }
+
+    /// 把当前显示的网格保留为对比快照 (见 ModelRenderer::capture_snapshot)
+    pub fn capture_snapshot(&mut self) {
+        self.model_renderer
+            .capture_snapshot(&self.render_state.device, &self.render_state.queue);
+        // 实时网格重新上传后调试顶点颜色需要重新设置
+        self.upload_skin_debug_colors();
+    }
+
+    /// 切换显示对比快照或实时网格
+    pub fn set_snapshot_visible(&mut self, visible: bool) {
+        if self.model_renderer.set_snapshot_visible(visible) {
+            self.skin_hover = None;
+            self.dirty = true;
+        }
+    }
+
     pub fn free_texture(&mut self) {
         if let Some(tid) = self.texture_id.take() {
             self.render_state.renderer.write().free_texture(&tid);
         }
+        // 模型已更换，旧的测量点不再有效
+        self.clear_measurement();
         self.dirty = true;
     }
+
+    // ── 测量 ──
+
+    /// 单击拾取测量点: 第一次设置 A，第二次设置 B，第三次或 Esc 清除
+    fn handle_measure_input(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        rect: egui::Rect,
+        aspect: f32,
+    ) {
+        if self.measure_a.is_some() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.clear_measurement();
+            return;
+        }
+        if !response.clicked_by(egui::PointerButton::Primary) {
+            return;
+        }
+        if self.measure_b.is_some() {
+            self.clear_measurement();
+            return;
+        }
+        let Some(pos) = response.interact_pointer_pos() else {
+            return;
+        };
+        let ndc = [
+            (pos.x - rect.left()) / rect.width() * 2.0 - 1.0,
+            1.0 - (pos.y - rect.top()) / rect.height() * 2.0,
+        ];
+        let (origin, dir) = self.camera.screen_ray(ndc, aspect);
+        let Some(hit) = self.model_renderer.raycast(origin, dir) else {
+            return;
+        };
+        if self.measure_a.is_none() {
+            self.measure_a = Some(hit);
+        } else {
+            self.measure_b = Some(hit);
+        }
+    }
+
+    /// 每帧根据当前相机重新投影测量点并绘制
+    fn paint_measurement(&self, ui: &egui::Ui, rect: egui::Rect, aspect: f32) {
+        let to_screen = |p: [f32; 3]| {
+            self.camera.project(p, aspect).map(|ndc| {
+                egui::pos2(
+                    rect.left() + (ndc[0] + 1.0) * 0.5 * rect.width(),
+                    rect.top() + (1.0 - ndc[1]) * 0.5 * rect.height(),
+                )
+            })
+        };
+        let painter = ui.painter_at(rect);
+        let color = egui::Color32::from_rgb(255, 200, 60);
+
+        let screen_a = self.measure_a.and_then(to_screen);
+        let screen_b = self.measure_b.and_then(to_screen);
+        for pos in [screen_a, screen_b].into_iter().flatten() {
+            painter.circle_filled(pos, 4.0, color);
+        }
+
+        if let (Some(a), Some(b), Some(sa), Some(sb)) =
+            (self.measure_a, self.measure_b, screen_a, screen_b)
+        {
+            painter.line_segment([sa, sb], egui::Stroke::new(2.0, color));
+            let d = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let dist = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+            let label_pos = sa.lerp(sb, 0.5) + egui::vec2(0.0, -10.0);
+            let galley = painter.layout_no_wrap(
+                format!("{:.3} m", dist),
+                egui::FontId::proportional(14.0),
+                egui::Color32::WHITE,
+            );
+            let bg = egui::Align2::CENTER_BOTTOM
+                .anchor_size(label_pos, galley.size())
+                .expand(4.0);
+            painter.rect_filled(bg, 3.0, egui::Color32::from_black_alpha(180));
+            painter.galley(bg.shrink(4.0).min, galley, egui::Color32::WHITE);
+        }
+    }
+}
+
+/// 显示视口；视口不可用 (无 3D 模式) 时显示占位说明
+pub fn show_viewport(
+    viewport: &mut Option<ViewportState>,
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    empty_label: &str,
+) {
+    match viewport {
+        Some(viewport) => viewport.show(ui, ctx, empty_label),
+        None => show_unavailable(ui),
+    }
+}
+
+/// 3D 预览不可用时代替视口的占位
+pub fn show_unavailable(ui: &mut egui::Ui) {
+    ui.centered_and_justified(|ui| {
+        ui.weak(tr!("viewport.unavailable"));
+    });
 }
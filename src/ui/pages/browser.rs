@@ -1,21 +1,118 @@
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
+use std::sync::mpsc::Receiver;
 
 use eframe::egui;
 use physis::stm::StainingTemplate;
 
 use crate::app::App;
-use crate::domain::{GameItem, ACCESSORY_SLOTS, GEAR_SLOTS};
+use crate::crash;
+use crate::domain::{GameItem, StainEntry, ACCESSORY_SLOTS, GEAR_SLOTS};
 use crate::dye;
+use crate::export_name::{self, ExportNameContext};
 use crate::game::{
-    bake_color_table_texture, compute_bounding_box, load_mdl_with_fallback, load_mesh_textures,
+    accessory_anchor_meshes, anchor_textures, compute_bounding_box, has_accessory_anchor,
+    load_mdl_with_fallback, load_mdl_with_fallback_indexed, load_mesh_textures,
+    race_code_of_model_path, MeshData,
 };
+use crate::glamour::GlamourSet;
+use crate::gltf::{ExportPrimitive, GlbBuilder};
+use crate::kit_export::{self, KitExportProgress, KitExportRequest, KitSlot};
 use crate::loading::GameState;
 use crate::ui::components::dye_palette;
-use crate::ui::components::equipment_list::HighlightConfig;
+use crate::ui::components::equipment_list::{DyeSwatch, HighlightConfig};
 use crate::ui::components::item_detail::{self, ItemDetailConfig};
+use crate::ui::components::item_list;
+use crate::ui::components::material_diagnostics;
+use crate::ui::components::viewport;
+
+/// 选中物品变化后延迟加载模型的时间 (秒)，快速切换时中间的物品不上传网格与贴图
+const MODEL_LOAD_DEBOUNCE: f64 = 0.1;
+/// 最近预览条保留的物品数
+const RECENT_ITEMS_CAPACITY: usize = 15;
+/// 最近预览条的图标大小 (逻辑点)
+const RECENT_ICON_SIZE: f32 = 32.0;
+
+/// 最近预览过的物品 row_id，最近的在前且不重复
+#[derive(Debug, Default)]
+pub struct RecentItems {
+    ids: VecDeque<u32>,
+}
+
+impl RecentItems {
+    /// 记录一次预览: 已在列表中的移到最前，超出容量时丢弃最早的
+    pub fn push(&mut self, row_id: u32) {
+        self.remove(row_id);
+        self.ids.push_front(row_id);
+        self.ids.truncate(RECENT_ITEMS_CAPACITY);
+    }
+
+    pub fn remove(&mut self, row_id: u32) {
+        self.ids.retain(|&id| id != row_id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.ids.iter().copied()
+    }
+}
+
+/// 选择防抖: 返回 None 表示现在加载 row_id 的模型，Some(秒) 表示还需等待的时间。
+/// 选择变化时重新计时，pending 只保留最后一次选择
+fn debounce_model_load(pending: &mut Option<(u32, f64)>, row_id: u32, now: f64) -> Option<f64> {
+    let due = match *pending {
+        Some((pending_id, due)) if pending_id == row_id => due,
+        _ => {
+            let due = now + MODEL_LOAD_DEBOUNCE;
+            *pending = Some((row_id, due));
+            due
+        }
+    };
+    if now < due {
+        return Some(due - now);
+    }
+    *pending = None;
+    None
+}
+
+/// 浏览页 "存为幻化组合": 以套装名命名，选中物品使用当前染色且优先占用其槽位，
+/// 同套装的其他装备不染色；物品不属于任何套装时只包含它自己
+fn quick_add_glamour(gs: &GameState, item: &GameItem, stain_ids: [u32; 2]) -> GlamourSet {
+    let eq_set = gs
+        .set_id_to_set_idx
+        .get(&item.set_id())
+        .map(|&idx| &gs.equipment_sets[idx]);
+    let name = eq_set.map_or(item.name.as_str(), |set| set.display_name.as_str());
+    let siblings = eq_set
+        .into_iter()
+        .flat_map(|set| set.item_indices.iter())
+        .map(|&idx| (&gs.all_items[idx], [0, 0]));
+    GlamourSet::from_items(name, std::iter::once((item, stain_ids)).chain(siblings))
+}
+
+/// 套装素材导出选项
+pub struct KitExportDialog {
+    set_name: String,
+    /// (槽位, 是否导出)
+    slots: Vec<(KitSlot, bool)>,
+    /// 烘焙 diffuse 时应用当前预览的染料
+    apply_dye: bool,
+}
+
+/// 后台运行中的套装素材导出
+pub struct KitExportJob {
+    receiver: Receiver<KitExportProgress>,
+    total: usize,
+    /// (正在导出的序号, 物品名)
+    current: (usize, String),
+}
 
 impl App {
     pub fn show_browser_page(&mut self, ctx: &egui::Context, gs: &mut GameState) {
+        #[cfg(feature = "stress-select")]
+        self.drive_selection_stress(ctx, gs);
         if self.needs_rebake {
             self.needs_rebake = false;
             if let Some(stm) = &gs.stm {
@@ -26,21 +123,21 @@ impl App {
         egui::SidePanel::left("equipment_list")
             .default_width(350.0)
             .show(ctx, |ui| {
-                ui.heading("装备浏览器");
+                ui.heading(tr!("page.browser"));
                 ui.separator();
 
                 // 槽位筛选
                 let prev_slot = self.selected_slot;
                 ui.horizontal(|ui| {
                     if ui
-                        .selectable_label(self.selected_slot.is_none(), "全部")
+                        .selectable_label(self.selected_slot.is_none(), tr!("common.all"))
                         .clicked()
                     {
                         self.selected_slot = None;
                     }
                 });
                 ui.horizontal(|ui| {
-                    ui.label("装备:");
+                    ui.label(tr!("browser.gear"));
                     for slot in &GEAR_SLOTS {
                         if ui
                             .selectable_label(
@@ -54,7 +151,7 @@ impl App {
                     }
                 });
                 ui.horizontal(|ui| {
-                    ui.label("饰品:");
+                    ui.label(tr!("browser.accessories"));
                     for slot in &ACCESSORY_SLOTS {
                         if ui
                             .selectable_label(
@@ -69,29 +166,21 @@ impl App {
                 });
                 if self.selected_slot != prev_slot {
                     // 切换槽位时自动展开当前选中物品所在的套装
-                    if let Some(sel_idx) = self.selected_item {
-                        if let Some(item) = gs.all_items.get(sel_idx) {
-                            self.equipment_list.expanded_sets.insert(item.set_id());
-                        }
+                    if let Some(item) = self.selected_item_id.and_then(|id| gs.item_by_id(id)) {
+                        self.equipment_list.expanded_sets.insert(item.set_id());
                     }
                 }
 
                 ui.separator();
 
                 // 高亮当前选中的物品
-                let selected_ids: HashSet<u32> = self
-                    .selected_item
-                    .and_then(|idx| gs.all_items.get(idx))
-                    .map(|item| {
-                        let mut s = HashSet::new();
-                        s.insert(item.row_id);
-                        s
-                    })
-                    .unwrap_or_default();
+                let selected_ids: HashSet<u32> = self.selected_item_id.into_iter().collect();
 
                 let highlight = HighlightConfig {
                     highlighted_ids: &selected_ids,
                     preview_id: None,
+                    focus_id: selected_ids.iter().next().copied(),
+                    dye_swatch: self.preview_dye_swatch(gs),
                 };
 
                 if let Some(clicked) = self.equipment_list.show(
@@ -106,18 +195,254 @@ impl App {
                     &mut self.icon_cache,
                     ctx,
                     &gs.game,
+                    &gs.probed_dye_counts,
+                    Some(&mut self.thumbnails),
                 ) {
-                    self.selected_item = Some(clicked.global_idx);
+                    self.selected_item_id = Some(gs.all_items[clicked.global_idx].row_id);
+                }
+                if let Some((set_name, indices)) = self.equipment_list.take_kit_export_request() {
+                    self.open_kit_export_dialog(set_name, &indices, gs);
+                }
+                if self.equipment_list.take_clear_dye_request() {
+                    self.clear_preview_stain();
                 }
             });
 
         self.show_browser_detail_panel(ctx, gs);
+        self.show_kit_export_windows(ctx, gs);
+    }
+
+    fn open_kit_export_dialog(&mut self, set_name: String, indices: &[usize], gs: &GameState) {
+        let slots: Vec<(KitSlot, bool)> = indices
+            .iter()
+            .filter_map(|&idx| {
+                let item = gs.all_items.get(idx)?;
+                let slot = item.equip_slot()?;
+                Some((
+                    KitSlot {
+                        slot,
+                        item_id: item.row_id,
+                        item_name: item.name.clone(),
+                        set_id: item.set_id(),
+                        variant_id: item.variant_id(),
+                        model_paths: item.model_paths(),
+                    },
+                    true,
+                ))
+            })
+            .collect();
+        if slots.is_empty() {
+            self.toasts.push("该套装没有可导出的装备");
+            return;
+        }
+        self.kit_export_dialog = Some(KitExportDialog {
+            set_name,
+            slots,
+            apply_dye: false,
+        });
+    }
+
+    /// 选择目标文件夹后在后台线程中导出，子目录按文件名模板命名 (套装名作为 {item})
+    fn start_kit_export(&mut self, gs: &GameState) {
+        let Some(parent) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+        let Some(dialog) = self.kit_export_dialog.take() else {
+            return;
+        };
+        let slots: Vec<KitSlot> = dialog
+            .slots
+            .into_iter()
+            .filter_map(|(slot, enabled)| enabled.then_some(slot))
+            .collect();
+        let stain_ids = dialog.apply_dye.then_some(self.selected_stain_ids);
+        let name_ctx = ExportNameContext {
+            item: Some(dialog.set_name.clone()),
+            set_id: slots.first().map(|s| s.set_id),
+            dye: stain_ids.and_then(|ids| export_name::dye_label(&gs.stains, ids)),
+            ..Default::default()
+        };
+        let folder =
+            export_name::format_export_name(self.config.export_name_template(), &name_ctx, "kit");
+        let request = KitExportRequest {
+            install_dir: (!gs.game.is_mock()).then(|| gs.game.install_dir()),
+            out_dir: export_name::unique_export_path(&parent, &folder, ""),
+            set_name: dialog.set_name,
+            stain_ids,
+            slots,
+        };
+        let total = request.slots.len();
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            kit_export::run_kit_export(request, tx);
+        });
+        self.kit_export_job = Some(KitExportJob {
+            receiver: rx,
+            total,
+            current: (0, String::new()),
+        });
+    }
+
+    fn poll_kit_export(&mut self) {
+        let Some(job) = &mut self.kit_export_job else {
+            return;
+        };
+        let mut finished = None;
+        for msg in job.receiver.try_iter() {
+            match msg {
+                KitExportProgress::Slot { index, item_name } => job.current = (index, item_name),
+                KitExportProgress::Done(result) => finished = Some(result),
+            }
+        }
+        let Some(result) = finished else {
+            return;
+        };
+        self.kit_export_job = None;
+        match result {
+            Ok((manifest, 0)) => self
+                .toasts
+                .push(format!("套装素材已导出到 {}", manifest.display())),
+            Ok((manifest, failed)) => self.toasts.push(format!(
+                "{} 个槽位导出失败，详见 {}",
+                failed,
+                manifest.display()
+            )),
+            Err(e) => self.toasts.push(format!("导出套装素材失败: {}", e)),
+        }
+    }
+
+    fn show_kit_export_windows(&mut self, ctx: &egui::Context, gs: &GameState) {
+        self.poll_kit_export();
+
+        if let Some(job) = &self.kit_export_job {
+            let (index, item_name) = &job.current;
+            egui::Window::new("导出套装素材")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(item_name);
+                    ui.add(
+                        egui::ProgressBar::new(*index as f32 / job.total.max(1) as f32)
+                            .text(format!("{}/{}", index + 1, job.total))
+                            .desired_width(240.0),
+                    );
+                });
+            ctx.request_repaint();
+            return;
+        }
+
+        let mut start = false;
+        let mut close = false;
+        let has_dye = self.selected_stain_ids != [0, 0];
+        if let Some(dialog) = &mut self.kit_export_dialog {
+            egui::Window::new("导出套装素材")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(egui::RichText::new(&dialog.set_name).strong());
+                    ui.label("每个槽位导出 glb 模型与 diffuse/normal/mask 贴图");
+                    ui.separator();
+                    for (slot, enabled) in &mut dialog.slots {
+                        ui.checkbox(
+                            enabled,
+                            format!("{} · {}", slot.slot.display_name(), slot.item_name),
+                        );
+                    }
+                    ui.separator();
+                    ui.add_enabled(
+                        has_dye,
+                        egui::Checkbox::new(&mut dialog.apply_dye, "应用当前预览的染色"),
+                    )
+                    .on_disabled_hover_text("当前预览未染色");
+                    ui.horizontal(|ui| {
+                        let any_selected = dialog.slots.iter().any(|(_, enabled)| *enabled);
+                        if ui
+                            .add_enabled(any_selected, egui::Button::new("选择文件夹并导出"))
+                            .clicked()
+                        {
+                            start = true;
+                        }
+                        if ui.button("取消").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            if !has_dye {
+                dialog.apply_dye = false;
+            }
+        }
+        if close {
+            self.kit_export_dialog = None;
+        } else if start {
+            self.start_kit_export(gs);
+        }
+    }
+
+    /// 预览视口下方的最近预览条: 单击重新选中，中键单击移除
+    fn show_recent_items_strip(&mut self, ctx: &egui::Context, gs: &GameState) {
+        if self.config.hide_recent_items || self.recent_items.is_empty() {
+            return;
+        }
+        let mut clicked = None;
+        let mut removed = None;
+        egui::TopBottomPanel::bottom("browser_recent_items").show(ctx, |ui| {
+            egui::ScrollArea::horizontal().show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.weak("最近预览");
+                    let size = egui::vec2(RECENT_ICON_SIZE, RECENT_ICON_SIZE);
+                    let ids: Vec<u32> = self.recent_items.iter().collect();
+                    for row_id in ids {
+                        let Some(item) = gs.item_by_id(row_id) else {
+                            continue;
+                        };
+                        let icon = item_list::get_or_load_icon_sized(
+                            &mut self.icon_cache,
+                            ctx,
+                            &gs.game,
+                            item.icon_id,
+                            RECENT_ICON_SIZE,
+                        );
+                        let button = match &icon {
+                            Some(tex) => {
+                                egui::Button::image(egui::load::SizedTexture::new(tex.id(), size))
+                            }
+                            None => egui::Button::new(egui_phosphor::regular::IMAGE_BROKEN)
+                                .min_size(size),
+                        };
+                        let response = ui
+                            .add(button.selected(self.selected_item_id == Some(row_id)))
+                            .on_hover_text(&item.name);
+                        if response.clicked() {
+                            clicked = Some(row_id);
+                        }
+                        if response.middle_clicked() {
+                            removed = Some(row_id);
+                        }
+                    }
+                });
+            });
+        });
+        if let Some(row_id) = removed {
+            self.recent_items.remove(row_id);
+        }
+        if let Some(item) = clicked.and_then(|row_id| gs.item_by_id(row_id)) {
+            // 直接加载，不等待选择防抖
+            self.selected_item_id = Some(item.row_id);
+            self.equipment_list.reveal(item.row_id);
+            if self.loaded_model_id != Some(item.row_id) {
+                self.load_model_for_item(item, gs);
+            }
+        }
     }
 
     fn show_browser_detail_panel(&mut self, ctx: &egui::Context, gs: &mut GameState) {
+        self.show_recent_items_strip(ctx, gs);
+        let mut quick_add = None;
         egui::CentralPanel::default().show(ctx, |ui| {
-            if let Some(idx) = self.selected_item {
-                if let Some(item) = gs.all_items.get(idx) {
+            if let Some(row_id) = self.selected_item_id {
+                if let Some(item) = gs.item_by_id(row_id) {
                     // 统一物品详情头部
                     let icon = self.get_or_load_icon(ctx, &gs.game, item.icon_id);
                     let cat_name = gs
@@ -131,8 +456,46 @@ impl App {
                         cat_name,
                         &ItemDetailConfig::default(),
                     );
+                    let lookup = item_detail::GearInfoLookup {
+                        class_job_names: &gs.class_job_names,
+                        base_param_names: &gs.base_param_names,
+                        repair_resource_items: &gs.repair_resource_items,
+                        all_items: &gs.all_items,
+                        item_id_map: &gs.item_id_map,
+                    };
+                    item_detail::show_item_stats(ui, item, &lookup, &ItemDetailConfig::default());
+                    if let Some(repair_id) = item_detail::show_gear_info(
+                        ui,
+                        item,
+                        &lookup,
+                        &ItemDetailConfig::default(),
+                    ) {
+                        // 修理材料不是装备，不在本页列表中，打开其 Wiki 页面
+                        if let Some(repair) = gs.item_by_id(repair_id) {
+                            let _ = open::that(format!(
+                                "https://ff14.huijiwiki.com/wiki/物品:{}",
+                                repair.name
+                            ));
+                        }
+                    }
+                    if let Some(idx) = item_detail::show_glamour_usage(
+                        ui,
+                        gs.glamour_item_usage.get(&item.row_id),
+                        &gs.glamour_sets,
+                        None,
+                    ) {
+                        self.open_glamour_request = Some(idx);
+                    }
+                    if item.equip_slot().is_some()
+                        && ui
+                            .button("存为幻化组合")
+                            .on_hover_text("以当前染色新建组合，同套装的其他装备一并加入")
+                            .clicked()
+                    {
+                        quick_add = Some(quick_add_glamour(gs, item, self.selected_stain_ids));
+                    }
                     ui.separator();
-                    let prefix = if item.is_accessory() { "a" } else { "e" };
+                    let prefix = item.model_prefix();
                     egui::Grid::new("item_info").show(ui, |ui| {
                         if let Some(slot) = item.equip_slot() {
                             ui.label("槽位:");
@@ -145,6 +508,13 @@ impl App {
                         ui.label("变体:");
                         ui.label(format!("v{:04}", item.variant_id()));
                         ui.end_row();
+                        ui.label("染色:");
+                        match gs.dye_count(item) {
+                            Some(0) => ui.label("不可染色"),
+                            Some(n) => ui.label(format!("可染×{}", n)),
+                            None => ui.weak("探测中…"),
+                        };
+                        ui.end_row();
                         if let Some(path) = item.model_path() {
                             ui.label("模型路径:");
                             ui.label(path);
@@ -170,7 +540,11 @@ impl App {
                                     let sib = &gs.all_items[i];
                                     let slot_abbr =
                                         sib.equip_slot().map(|s| s.slot_abbr()).unwrap_or("?");
-                                    (i, format!("[{}] {}", slot_abbr, sib.name), i == idx)
+                                    (
+                                        i,
+                                        format!("[{}] {}", slot_abbr, sib.name),
+                                        sib.row_id == row_id,
+                                    )
                                 })
                                 .collect();
                             let mut clicked_sibling: Option<usize> = None;
@@ -186,15 +560,43 @@ impl App {
                                 }
                             });
                             if let Some(sib) = clicked_sibling {
-                                self.selected_item = Some(sib);
+                                let sib_id = gs.all_items[sib].row_id;
+                                self.selected_item_id = Some(sib_id);
+                                self.equipment_list.reveal(sib_id);
                             }
                         }
                     }
 
                     ui.separator();
 
-                    let has_dyeable = self.cached_materials.values().any(|m| m.uses_color_table);
-                    if has_dyeable {
+                    // 选中后立即按 Item 表的染色通道数决定染色区域的布局，避免材质加载完成时跳动；
+                    // 材质的 uses_color_table 仍决定实际能否染色 (烘焙以材质为准)
+                    let materials_ready = self.loaded_model_id == Some(row_id);
+                    let has_dyeable = materials_ready
+                        && self.cached_materials.values().any(|m| m.uses_color_table);
+                    let dye_count = gs.dye_count(item);
+                    if dye_count == Some(0) {
+                        ui.add_enabled(false, egui::Label::new("此装备不可染色"));
+                    } else if !materials_ready && dye_count.is_some() {
+                        // 材质加载前显示灰色的调色板占位，操作不会生效
+                        let default_stain = self.config.default_preview_stain;
+                        let mut stain_ids = [default_stain, default_stain];
+                        let mut channel = 0;
+                        let mut shade = self.selected_shade;
+                        let mut show_regions = self.show_dye_regions;
+                        ui.add_enabled_ui(false, |ui| {
+                            dye_palette::show_dye_palette(
+                                ui,
+                                &gs.stains,
+                                &mut stain_ids,
+                                &mut channel,
+                                &mut shade,
+                                dye_count == Some(2),
+                                default_stain != 0,
+                            );
+                            dye_palette::show_dye_region_toggle(ui, &mut show_regions);
+                        });
+                    } else if has_dyeable {
                         let changed = dye_palette::show_dye_palette(
                             ui,
                             &gs.stains,
@@ -202,34 +604,149 @@ impl App {
                             &mut self.active_dye_channel,
                             &mut self.selected_shade,
                             self.is_dual_dye,
+                            self.stains_from_default,
                         );
                         if changed {
+                            self.stains_from_default = false;
+                            self.needs_rebake = true;
+                        }
+                        if dye_palette::show_dye_region_toggle(ui, &mut self.show_dye_regions) {
                             self.needs_rebake = true;
                         }
                     }
+                    if ui
+                        .add_enabled(
+                            materials_ready
+                                && self.viewport.is_some()
+                                && !self.cached_mesh_materials.is_empty(),
+                            egui::Button::new("导出 glTF"),
+                        )
+                        .on_hover_text("把当前预览的模型与染色后的贴图导出为 .glb")
+                        .clicked()
+                    {
+                        self.export_preview_gltf(item, gs);
+                    }
 
-                    if self.loaded_model_idx != Some(idx) {
-                        self.load_model_for_item(idx, item, gs);
+                    if self.loaded_model_id != Some(row_id) && self.model_load_due(ctx, row_id) {
+                        self.load_model_for_item(item, gs);
+                    }
+                    if item.equip_slot().is_some_and(has_accessory_anchor)
+                        && ui
+                            .checkbox(&mut self.show_accessory_anchor, "参照模型")
+                            .on_hover_text("在饰品后面显示灰色的胸像/耳朵/手，便于判断大小")
+                            .changed()
+                    {
+                        // 重新上传网格，染色在下一帧重新应用
+                        self.upload_item_model(item, gs);
+                        self.needs_rebake = true;
+                    }
+                    if self.show_race_compare_controls(ui) {
+                        self.upload_item_model(item, gs);
+                        self.needs_rebake = true;
+                    }
+                    material_diagnostics::show_material_diagnostics(
+                        ui,
+                        "browser_materials",
+                        &self.texture_diagnostics,
+                    );
+                    material_diagnostics::show_color_table_inspector(
+                        ui,
+                        "browser_color_table",
+                        &self.cached_materials,
+                        &self.texture_diagnostics,
+                        gs.stm.as_ref(),
+                        self.selected_stain_ids,
+                        &mut self.color_table_material,
+                    );
+                    let empty_label = if item.uses_body_model() {
+                        tr!("viewport.body_model")
+                    } else {
+                        tr!("viewport.load_failed")
+                    };
+                    if self.race_compare.as_ref().is_some_and(|c| !c.merged) {
+                        self.show_race_compare_viewports(ui, ctx, empty_label);
+                    } else {
+                        viewport::show_viewport(&mut self.viewport, ui, ctx, empty_label);
                     }
-                    self.viewport.show(ui, ctx, "模型加载失败");
                 } else {
                     ui.label("选择一件装备查看详情");
                 }
             } else {
                 ui.centered_and_justified(|ui| {
-                    ui.label("← 从左侧列表选择一件装备");
+                    ui.label(tr!("browser.empty_hint"));
                 });
             }
         });
+        if let Some(set) = quick_add {
+            self.request_glamour_quick_add(gs, set);
+        }
+        if self.glamour_duplicate.is_some() {
+            self.show_glamour_duplicate_dialog(ctx, gs);
+        }
     }
 
-    fn load_model_for_item(&mut self, idx: usize, item: &GameItem, gs: &GameState) {
-        self.loaded_model_idx = Some(idx);
-        self.selected_stain_ids = [0, 0];
+    /// 选中的物品是否该加载模型: 选择稳定 MODEL_LOAD_DEBOUNCE 秒后才加载，期间视口继续显示
+    /// 上一个模型。更新的选择会替换等待中的加载，被跳过的物品不会上传任何 GPU 资源
+    fn model_load_due(&mut self, ctx: &egui::Context, row_id: u32) -> bool {
+        let now = ctx.input(|i| i.time);
+        match debounce_model_load(&mut self.pending_model_load, row_id, now) {
+            Some(wait) => {
+                ctx.request_repaint_after(std::time::Duration::from_secs_f64(wait));
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn load_model_for_item(&mut self, item: &GameItem, gs: &GameState) {
+        self.loaded_model_id = Some(item.row_id);
+        self.recent_items.push(item.row_id);
+        let default_stain = self.config.default_preview_stain;
+        self.selected_stain_ids = [default_stain, default_stain];
+        self.stains_from_default = default_stain != 0;
         self.active_dye_channel = 0;
-        let paths = item.model_paths();
-        match load_mdl_with_fallback(&gs.game, &paths) {
-            Ok(result) if !result.meshes.is_empty() => {
+        self.upload_item_model(item, gs);
+    }
+
+    /// 饰品的参照网格 (关闭或骨架不可用时为空)，按实际加载的模型种族放置
+    fn accessory_anchors(
+        &mut self,
+        item: &GameItem,
+        model_path: &str,
+        gs: &GameState,
+    ) -> Vec<MeshData> {
+        if !self.show_accessory_anchor {
+            return Vec::new();
+        }
+        let Some(slot) = item.equip_slot().filter(|&s| has_accessory_anchor(s)) else {
+            return Vec::new();
+        };
+        let Some(race) = race_code_of_model_path(model_path) else {
+            return Vec::new();
+        };
+        match self.skeleton_cache.get_bind_pose(race, &gs.game) {
+            Some(bind_pose) => accessory_anchor_meshes(slot, bind_pose),
+            None => Vec::new(),
+        }
+    }
+
+    /// 加载物品模型与贴图并上传到预览视口 (不改动染料选择)
+    fn upload_item_model(&mut self, item: &GameItem, gs: &GameState) {
+        if self.race_compare.is_some() {
+            self.upload_race_compare(item, gs);
+            return;
+        }
+        // 使用身体模型的物品不加载 (小衣模型只会显示成一块皮肤)，视口显示说明；
+        // 加载时出过错的物品同样跳过
+        let paths = if item.uses_body_model() || crash::is_item_skipped(item.row_id) {
+            Vec::new()
+        } else {
+            item.model_paths()
+        };
+        let _load = crash::ItemLoadGuard::new(item.row_id);
+        match load_mdl_with_fallback_indexed(&gs.game, &paths) {
+            Ok((path_idx, result)) if !result.meshes.is_empty() => {
+                // 参照网格不参与对焦与染色
                 let bbox = compute_bounding_box(&result.meshes);
                 println!(
                     "加载纹理: {} 个材质, {} 个网格",
@@ -243,77 +760,290 @@ impl App {
                     item.set_id(),
                     item.variant_id(),
                 );
+                let anchors = self.accessory_anchors(item, &paths[path_idx], gs);
                 let geometry: Vec<(&[tomestone_render::Vertex], &[u16])> = result
                     .meshes
                     .iter()
+                    .chain(&anchors)
                     .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
                     .collect();
-                let vp = &mut self.viewport;
-                vp.model_renderer.set_mesh_data(
-                    &vp.render_state.device,
-                    &vp.render_state.queue,
-                    &geometry,
-                    &load_result.mesh_textures,
-                );
+                let missing = load_result.missing_count();
+                let mut mesh_textures = load_result.mesh_textures;
+                mesh_textures.extend(anchors.iter().map(|_| anchor_textures()));
+                if let Some(vp) = &mut self.viewport {
+                    vp.model_renderer.set_mesh_data(
+                        &vp.render_state.device,
+                        &vp.render_state.queue,
+                        &geometry,
+                        &mesh_textures,
+                    );
+                    vp.set_skin_data(&result.meshes);
+                    vp.camera.focus_on(&bbox);
+                    vp.last_bbox = Some(bbox);
+                    vp.free_texture();
+                }
+                if missing > 0 {
+                    self.toasts.push(format!("{} 个材质的贴图缺失", missing));
+                }
+                self.texture_diagnostics = load_result.diagnostics;
                 self.cached_materials = load_result.materials;
                 self.is_dual_dye = dye::has_dual_dye(&self.cached_materials);
-                self.cached_meshes = result.meshes;
-                self.viewport.camera.focus_on(&bbox);
-                self.viewport.last_bbox = Some(bbox);
-                self.viewport.free_texture();
+                self.cached_mesh_materials =
+                    result.meshes.iter().map(|m| m.material_index).collect();
+                if self.stains_from_default || self.show_dye_regions {
+                    if let Some(stm) = &gs.stm {
+                        self.rebake_textures(stm);
+                    }
+                }
             }
             _ => {
-                eprintln!(
-                    "模型加载失败 e{:04} v{:04}: {:?}",
-                    item.set_id(),
-                    item.variant_id(),
-                    load_mdl_with_fallback(&gs.game, &paths).err()
-                );
-                let vp = &mut self.viewport;
-                vp.model_renderer.set_mesh_data(
+                if !item.uses_body_model() {
+                    eprintln!(
+                        "模型加载失败 e{:04} v{:04}: {:?}",
+                        item.set_id(),
+                        item.variant_id(),
+                        load_mdl_with_fallback(&gs.game, &paths).err()
+                    );
+                }
+                if let Some(vp) = &mut self.viewport {
+                    vp.model_renderer.set_mesh_data(
+                        &vp.render_state.device,
+                        &vp.render_state.queue,
+                        &[],
+                        &[],
+                    );
+                    vp.set_skin_data(&[]);
+                    vp.last_bbox = None;
+                }
+                self.texture_diagnostics.clear();
+                self.cached_mesh_materials.clear();
+            }
+        }
+    }
+
+    /// 把当前预览的网格导出为 glb: 每个网格一个图元，diffuse 按当前染料烘焙
+    fn export_preview_gltf(&mut self, item: &GameItem, gs: &GameState) {
+        let mut stain_ids = self.selected_stain_ids;
+        if !self.is_dual_dye {
+            stain_ids[1] = 0;
+        }
+        let name_ctx = ExportNameContext {
+            item: Some(item.name.clone()),
+            set_id: Some(item.set_id()),
+            slot: item.equip_slot().map(|s| s.slot_abbr().to_string()),
+            dye: export_name::dye_label(&gs.stains, stain_ids),
+            ..Default::default()
+        };
+        let file_stem =
+            export_name::format_export_name(self.config.export_name_template(), &name_ctx, "model");
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("glTF", &["glb"])
+            .set_file_name(format!("{}.glb", file_stem))
+            .save_file()
+        else {
+            return;
+        };
+
+        let Some(vp) = &self.viewport else {
+            return;
+        };
+        let retained = vp.model_renderer.retained_mesh_data();
+        // 渲染器中依次为当前模型与饰品锚点 (或合并显示的种族 B)，只导出当前模型的网格
+        let count = self.cached_mesh_materials.len().min(retained.len());
+        let diffuses: Vec<Option<tomestone_render::TextureData>> = self.cached_mesh_materials
+            [..count]
+            .iter()
+            .enumerate()
+            .map(|(i, material_index)| {
+                self.cached_materials
+                    .get(material_index)
+                    .and_then(|cached| dye::bake_dyed_diffuse(cached, stain_ids, gs.stm.as_ref()))
+                    .or_else(|| retained.textures(i).map(|t| t.diffuse.clone()))
+            })
+            .collect();
+        let primitives: Vec<ExportPrimitive<'_>> = diffuses
+            .iter()
+            .enumerate()
+            .filter_map(|(i, diffuse)| {
+                let (vertices, indices) = retained.geometry(i)?;
+                let tex = retained.textures(i);
+                Some(ExportPrimitive {
+                    vertices,
+                    indices,
+                    diffuse: diffuse.as_ref(),
+                    normal: tex.and_then(|t| t.normal.as_ref()),
+                    mask: tex.and_then(|t| t.mask.as_ref()),
+                })
+            })
+            .collect();
+        let mut builder = GlbBuilder::new();
+        let extras = serde_json::json!({
+            "item_id": item.row_id,
+            "item_name": item.name,
+            "set_id": item.set_id(),
+            "variant_id": item.variant_id(),
+            "stain_ids": stain_ids,
+        });
+        let result = builder
+            .add_node(&item.name, &primitives)
+            .and_then(|()| builder.finish(&item.name, extras))
+            .and_then(|bytes| std::fs::write(&path, bytes).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => self.toasts.push(format!(
+                "已导出 {} 个网格到 {}",
+                primitives.len(),
+                path.display()
+            )),
+            Err(e) => self.toasts.push(format!("导出失败: {}", e)),
+        }
+    }
+
+    /// 列表中提示当前预览染料的色块，未染色时为 None
+    fn preview_dye_swatch(&self, gs: &GameState) -> Option<DyeSwatch> {
+        let item_id = self.loaded_model_id?;
+        let mut stain_ids = self.selected_stain_ids;
+        if !self.is_dual_dye {
+            stain_ids[1] = 0;
+        }
+        let stains = stain_ids.map(|id| gs.stains.iter().find(|s| id != 0 && s.id == id));
+        if stains.iter().all(Option::is_none) {
+            return None;
+        }
+        let names: Vec<&str> = stains.iter().flatten().map(|s| s.name.as_str()).collect();
+        let color = |s: &StainEntry| {
+            let [r, g, b] = s.color;
+            egui::Color32::from_rgb(r, g, b)
+        };
+        Some(DyeSwatch {
+            colors: stains.map(|s| s.map(color)),
+            names: names.join(" / "),
+            item_id: Some(item_id),
+        })
+    }
+
+    /// 清除预览染色: 设置了默认预览染料时恢复为默认值
+    fn clear_preview_stain(&mut self) {
+        let default_stain = self.config.default_preview_stain;
+        self.selected_stain_ids = [default_stain, default_stain];
+        self.stains_from_default = default_stain != 0;
+        self.needs_rebake = true;
+    }
+
+    /// 默认预览染料设置变更后，更新当前仍使用默认值 (或未染色) 的模型
+    pub fn apply_default_preview_stain(&mut self) {
+        if self.loaded_model_id.is_none() {
+            return;
+        }
+        if self.stains_from_default || self.selected_stain_ids == [0, 0] {
+            let default_stain = self.config.default_preview_stain;
+            self.selected_stain_ids = [default_stain, default_stain];
+            self.stains_from_default = default_stain != 0;
+            self.needs_rebake = true;
+        }
+    }
+
+    pub fn rebake_textures(&mut self, stm: &StainingTemplate) {
+        let mut updates: Vec<Option<dye::DyeUpdate>> = self
+            .cached_mesh_materials
+            .iter()
+            .map(|material_index| {
+                self.cached_materials
+                    .get(material_index)
+                    .and_then(|cached| {
+                        if self.show_dye_regions {
+                            dye::dye_region_update(cached)
+                        } else {
+                            dye::dye_update(cached, self.selected_stain_ids, stm)
+                        }
+                    })
+            })
+            .collect();
+        // 种族对比: 合并显示时种族 B 的网格接在种族 A 之后，分屏时单独染色
+        if let Some(compare) = &mut self.race_compare {
+            let compare_updates =
+                compare.dye_updates(self.selected_stain_ids, self.show_dye_regions, stm);
+            if compare.merged {
+                updates.extend(compare_updates);
+            } else {
+                let vp = &mut compare.viewport;
+                dye::submit_dye_updates(
+                    &mut vp.model_renderer,
                     &vp.render_state.device,
                     &vp.render_state.queue,
-                    &[],
-                    &[],
+                    compare_updates,
                 );
-                self.viewport.last_bbox = None;
+                vp.mark_dirty();
             }
         }
+        if let Some(vp) = &mut self.viewport {
+            dye::submit_dye_updates(
+                &mut vp.model_renderer,
+                &vp.render_state.device,
+                &vp.render_state.queue,
+                updates,
+            );
+            vp.mark_dirty();
+        }
     }
+}
 
-    pub fn rebake_textures(&mut self, stm: &StainingTemplate) {
-        let mut new_textures: Vec<Option<tomestone_render::TextureData>> = Vec::new();
-        for mesh in &self.cached_meshes {
-            let mat_idx = mesh.material_index;
-            if let Some(cached) = self.cached_materials.get(&mat_idx) {
-                if cached.uses_color_table {
-                    if let (Some(color_table), Some(id_tex)) =
-                        (&cached.color_table, &cached.id_texture)
-                    {
-                        let dyed_colors = if self.selected_stain_ids[0] > 0
-                            || self.selected_stain_ids[1] > 0
-                        {
-                            cached.color_dye_table.as_ref().map(|dye_table| {
-                                dye::apply_dye(color_table, dye_table, stm, self.selected_stain_ids)
-                            })
-                        } else {
-                            None
-                        };
-                        let baked =
-                            bake_color_table_texture(id_tex, color_table, dyed_colors.as_ref());
-                        new_textures.push(Some(baked));
-                        continue;
-                    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 以 60 fps 逐帧运行选择防抖，返回实际加载过模型的物品
+    fn run_frames(selections: &[(f64, u32)], until: f64) -> Vec<u32> {
+        let mut pending = None;
+        let mut loaded = None;
+        let mut loads = Vec::new();
+        let mut frame = 0;
+        loop {
+            let now = frame as f64 / 60.0;
+            if now > until {
+                return loads;
+            }
+            let selected = selections
+                .iter()
+                .take_while(|&&(at, _)| at <= now)
+                .last()
+                .map(|&(_, row_id)| row_id);
+            if let Some(row_id) = selected.filter(|&id| loaded != Some(id)) {
+                if debounce_model_load(&mut pending, row_id, now).is_none() {
+                    loaded = Some(row_id);
+                    loads.push(row_id);
                 }
             }
-            new_textures.push(None);
+            frame += 1;
         }
-        let vp = &mut self.viewport;
-        vp.model_renderer.update_textures(
-            &vp.render_state.device,
-            &vp.render_state.queue,
-            &new_textures,
+    }
+
+    #[test]
+    fn rapid_selection_loads_only_the_last_item() {
+        // 每 30ms 切换一次，共 40 件
+        let selections: Vec<(f64, u32)> = (0..40)
+            .map(|i| (i as f64 * 0.03, 100 + i))
+            .collect();
+        assert_eq!(run_frames(&selections, 2.0), [139]);
+    }
+
+    #[test]
+    fn slow_selection_loads_every_item() {
+        let selections = [(0.0, 1), (0.5, 2), (1.0, 3)];
+        assert_eq!(run_frames(&selections, 2.0), [1, 2, 3]);
+    }
+
+    #[test]
+    fn debounce_restarts_when_selection_changes() {
+        let mut pending = None;
+        assert_eq!(
+            debounce_model_load(&mut pending, 1, 0.0),
+            Some(MODEL_LOAD_DEBOUNCE)
         );
-        self.viewport.mark_dirty();
+        assert!(debounce_model_load(&mut pending, 1, 0.05).is_some());
+        // 换了物品，重新计时
+        assert!(debounce_model_load(&mut pending, 2, 0.08).is_some());
+        assert!(debounce_model_load(&mut pending, 2, 0.15).is_some());
+        assert_eq!(debounce_model_load(&mut pending, 2, 0.2), None);
+        assert_eq!(pending, None);
     }
 }
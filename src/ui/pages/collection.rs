@@ -0,0 +1,215 @@
+use eframe::egui;
+
+use crate::app::App;
+use crate::domain::{GameItem, ItemSource, ViewMode, COLLECTION_KINDS};
+use crate::loading::GameState;
+use crate::numfmt::{fmt_gil, NumberStyle};
+use crate::ui::components::item_detail::{self, ItemDetailConfig};
+use crate::ui::components::item_list::{self, DisplayItem};
+
+impl App {
+    pub fn show_collection_page(&mut self, ctx: &egui::Context, gs: &GameState) {
+        egui::SidePanel::left("collection_list")
+            .default_width(350.0)
+            .show(ctx, |ui| {
+                ui.heading(tr!("page.collection"));
+                ui.separator();
+
+                // 类别切换
+                let prev_kind = self.collection_kind;
+                ui.horizontal(|ui| {
+                    for kind in COLLECTION_KINDS {
+                        let label = format!(
+                            "{}({})",
+                            kind.display_name(),
+                            gs.collection_indices(kind).len()
+                        );
+                        if ui
+                            .selectable_label(self.collection_kind == kind, label)
+                            .clicked()
+                        {
+                            self.collection_kind = kind;
+                        }
+                    }
+                });
+                if self.collection_kind != prev_kind {
+                    self.collection_selected_item_id = None;
+                }
+                ui.separator();
+
+                self.collection_list.show_controls(ui);
+
+                // 同时匹配物品名与链接表中的曲名/卡名
+                let search_lower = self.collection_list.search_lower();
+                let filtered: Vec<(usize, &GameItem)> = gs
+                    .collection_indices(self.collection_kind)
+                    .iter()
+                    .map(|&idx| (idx, &gs.all_items[idx]))
+                    .filter(|(_, item)| {
+                        item.matches_search(&search_lower)
+                            || gs.collection_info(item).is_some_and(|info| {
+                                info.name.to_lowercase().contains(&search_lower)
+                            })
+                    })
+                    .collect();
+
+                ui.label(format!("{} 件物品", filtered.len()));
+                ui.separator();
+
+                match self.collection_list.view_mode {
+                    ViewMode::Grid => {
+                        let display_items: Vec<DisplayItem<'_>> = filtered
+                            .iter()
+                            .map(|&(idx, item)| DisplayItem {
+                                id: idx,
+                                name: &item.name,
+                                icon_id: item.icon_id,
+                                is_selected: self.collection_selected_item_id == Some(item.row_id),
+                                badge: None,
+                            })
+                            .collect();
+                        if let Some(clicked) = item_list::show_grid_scroll(
+                            ui,
+                            &display_items,
+                            self.collection_list.icon_size,
+                            "collection",
+                            &mut self.icon_cache,
+                            ctx,
+                            &gs.game,
+                        ) {
+                            self.collection_selected_item_id = Some(gs.all_items[clicked].row_id);
+                        }
+                    }
+                    ViewMode::List => {
+                        let row_height = 28.0;
+                        egui::ScrollArea::vertical().show_rows(
+                            ui,
+                            row_height,
+                            filtered.len(),
+                            |ui, row_range| {
+                                for i in row_range {
+                                    let (idx, item) = filtered[i];
+                                    let di = DisplayItem {
+                                        id: idx,
+                                        name: &item.name,
+                                        icon_id: item.icon_id,
+                                        is_selected: self.collection_selected_item_id
+                                            == Some(item.row_id),
+                                        badge: None,
+                                    };
+                                    if item_list::show_list_row(
+                                        ui,
+                                        &di,
+                                        &item.name,
+                                        &mut self.icon_cache,
+                                        ctx,
+                                        &gs.game,
+                                    ) {
+                                        self.collection_selected_item_id = Some(item.row_id);
+                                    }
+                                }
+                            },
+                        );
+                    }
+                }
+            });
+
+        self.show_collection_detail_panel(ctx, gs);
+    }
+
+    fn show_collection_detail_panel(&mut self, ctx: &egui::Context, gs: &GameState) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let Some(item) = self
+                .collection_selected_item_id
+                .and_then(|row_id| gs.item_by_id(row_id))
+            else {
+                ui.centered_and_justified(|ui| {
+                    ui.label(tr!("collection.empty_hint"));
+                });
+                return;
+            };
+
+            let icon = self.get_or_load_icon(ctx, &gs.game, item.icon_id);
+            let cat_name = gs
+                .ui_category_names
+                .get(&item.item_ui_category)
+                .map(|s| s.as_str());
+            item_detail::show_item_detail_header(
+                ui,
+                item,
+                icon.as_ref(),
+                cat_name,
+                &ItemDetailConfig::default(),
+            );
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                if let Some(info) = gs.collection_info(item) {
+                    ui.label(egui::RichText::new(&info.name).strong());
+                    if !info.description.is_empty() {
+                        ui.label(&info.description);
+                    }
+                    ui.separator();
+                }
+
+                ui.label(egui::RichText::new("获取来源").strong());
+                match gs.item_sources.get(&item.row_id) {
+                    Some(sources) if !sources.is_empty() => {
+                        for source in sources {
+                            match source_detail(source, item, gs, self.config.number_style) {
+                                Some(detail) => {
+                                    ui.label(format!("[{}] {}", source.label(), detail))
+                                }
+                                None => ui.label(format!("[{}]", source.label())),
+                            };
+                        }
+                    }
+                    _ => {
+                        ui.weak("无已知商店或采集来源");
+                    }
+                }
+            });
+        });
+    }
+}
+
+/// 来源的具体说明 (商店名 + 消耗)
+fn source_detail(
+    source: &ItemSource,
+    item: &GameItem,
+    gs: &GameState,
+    style: NumberStyle,
+) -> Option<String> {
+    match source {
+        ItemSource::GilShop {
+            shop_name,
+            npc_location,
+            ..
+        } => {
+            let price = fmt_gil(item.price_mid as u64, style);
+            Some(match npc_location {
+                Some(loc) => format!("{} @ {} ({})", shop_name, loc, price),
+                None => format!("{} ({})", shop_name, price),
+            })
+        }
+        ItemSource::SpecialShop {
+            shop_name,
+            npc_location,
+            cost_item_id,
+            cost_count,
+            ..
+        } => {
+            let cost_name = gs
+                .item_id_map
+                .get(cost_item_id)
+                .and_then(|&i| gs.all_items.get(i))
+                .map(|c| c.name.as_str())
+                .unwrap_or("?");
+            Some(match npc_location {
+                Some(loc) => format!("{} @ {} ({} x{})", shop_name, loc, cost_name, cost_count),
+                None => format!("{} ({} x{})", shop_name, cost_name, cost_count),
+            })
+        }
+        ItemSource::Gathering => None,
+    }
+}
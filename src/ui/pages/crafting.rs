@@ -1,34 +1,177 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::mpsc::{Receiver, TryRecvError};
 
 use eframe::egui;
 
 use crate::app::App;
+use crate::craft_html::{
+    encode_icon_png, render_craft_plan_html, CraftPlanDoc, PlanMaterialGroup, PlanMaterialRow,
+    PlanStepRow, PlanTreeNode,
+};
+use crate::derived::NameLookup;
 use crate::domain::{
-    build_craft_tree, resolve_source, summarize_materials_with_collapsed, total_amount_in_tree,
-    CraftTreeNode, ItemSource, Recipe, SourceChoice, ViewMode, CRAFT_TYPE_ABBRS, CRAFT_TYPE_NAMES,
+    build_craft_tree_lazy, cluster_by_aetheryte, cluster_by_shop_npc, craft_plan_steps,
+    expand_node, group_materials_by_source, resolve_source, summarize_hq_leaves,
+    summarize_materials_with_collapsed, total_amount_in_tree, CraftCostEstimate, CraftCostSnapshot,
+    CraftTreeNode, GameItem, ItemSource, MaterialGroup, MaterialGroupKey, Recipe, SourceChoice,
+    ViewMode, CRAFT_TREE_OPEN_DEPTH, CRAFT_TYPE_ABBRS, CRAFT_TYPE_NAMES,
 };
+use crate::export_name::{format_export_name, ExportNameContext};
 use crate::loading::GameState;
+use crate::numfmt::{fmt_count, fmt_gil, NumberStyle};
+use crate::teamcraft::{self, TeamcraftEntry};
 use crate::ui::components::item_detail::{self, ItemDetailConfig};
 use crate::ui::components::item_list::{self, DisplayItem};
 
-/// 根据解析后的来源返回淡色背景
-fn source_bg_color(source: Option<&ItemSource>, visuals: &egui::Visuals) -> Option<egui::Color32> {
-    let tag = source?.color_tag();
-    let alpha = if visuals.dark_mode { 25 } else { 40 };
-    match tag {
-        1 => Some(egui::Color32::from_rgba_unmultiplied(255, 200, 60, alpha)), // 金币商店: 淡金
-        2 => Some(egui::Color32::from_rgba_unmultiplied(180, 130, 255, alpha)), // 兑换: 淡紫
-        3 => Some(egui::Color32::from_rgba_unmultiplied(80, 200, 80, alpha)),  // 采集: 淡绿
-        _ => None,
+/// 后台计算中的默认来源制作成本
+pub struct CraftCostJob {
+    /// (根物品 row_id, 制作数量, 展开深度)
+    key: (u32, u32, usize),
+    receiver: Receiver<CraftCostEstimate>,
+}
+
+/// Teamcraft 导出方式
+#[derive(Clone, Copy)]
+enum TeamcraftExport {
+    Text { english: bool },
+    Url,
+}
+
+/// 素材汇总的多选状态，只对当前合成根物品有效
+#[derive(Default)]
+pub struct MaterialSelection {
+    root_item_id: Option<u32>,
+    items: HashSet<u32>,
+    /// shift 范围选择的起点
+    anchor: Option<u32>,
+}
+
+impl MaterialSelection {
+    /// 合成根物品变化时清空选择
+    fn sync_root(&mut self, root_item_id: u32) {
+        if self.root_item_id != Some(root_item_id) {
+            self.root_item_id = Some(root_item_id);
+            self.clear();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.items.clear();
+        self.anchor = None;
+    }
+
+    fn toggle(&mut self, item_id: u32) {
+        if !self.items.remove(&item_id) {
+            self.items.insert(item_id);
+        }
+        self.anchor = Some(item_id);
+    }
+
+    /// 选中 anchor 到 item_id 之间 (按显示顺序) 的所有行
+    fn select_range(&mut self, order: &[u32], item_id: u32) {
+        let end = order.iter().position(|&id| id == item_id);
+        let start = self
+            .anchor
+            .and_then(|anchor| order.iter().position(|&id| id == anchor))
+            .or(end);
+        if let (Some(start), Some(end)) = (start, end) {
+            let (lo, hi) = if start <= end {
+                (start, end)
+            } else {
+                (end, start)
+            };
+            self.items.extend(&order[lo..=hi]);
+        }
+        if self.anchor.is_none() {
+            self.anchor = Some(item_id);
+        }
+    }
+}
+
+/// 背包清单导入对话框状态
+#[derive(Default)]
+pub struct InventoryImport {
+    open: bool,
+    text: String,
+    /// 上次导入中未能匹配的行
+    unmatched: Vec<String>,
+    /// 上次导入的结果摘要
+    summary: Option<String>,
+}
+
+/// 解析背包导出文本 ("物品名\t数量"，每行一项)，返回 (名称, 数量) 与无法解析的行
+///
+/// 没有制表符时取最后一段空白分隔的内容作为数量；数量中的千分位逗号会被忽略
+fn parse_inventory_dump(text: &str) -> (Vec<(String, u32)>, Vec<String>) {
+    let mut entries = Vec::new();
+    let mut invalid = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let split = line
+            .split_once('\t')
+            .or_else(|| line.rsplit_once(char::is_whitespace));
+        let parsed = split.and_then(|(name, count)| {
+            let count: u32 = count.trim().replace(',', "").parse().ok()?;
+            let name = name.trim();
+            (!name.is_empty()).then(|| (name.to_string(), count))
+        });
+        match parsed {
+            Some(entry) => entries.push(entry),
+            None => invalid.push(line.to_string()),
+        }
     }
+    (entries, invalid)
+}
+
+/// 素材批量操作
+#[derive(Clone, Copy)]
+enum MaterialBatchAction {
+    MarkOwned,
+    Unignore,
+    UseGathering,
+    UseGilShop,
+}
+
+/// 来源的标识色 (按 `ItemSource::color_tag`)，`alpha` 为不透明度。
+/// 色盲友好模式使用 Okabe-Ito 配色中的橙/蓝/蓝绿，红绿色盲也易于区分
+fn source_color(color_tag: u8, colorblind: bool, alpha: u8) -> Option<egui::Color32> {
+    let (r, g, b) = match (color_tag, colorblind) {
+        (1, false) => (230, 185, 50),  // 金币商店: 金
+        (2, false) => (170, 125, 240), // 兑换: 紫
+        (3, false) => (80, 190, 80),   // 采集: 绿
+        (1, true) => (230, 159, 0),    // 金币商店: 橙
+        (2, true) => (0, 114, 178),    // 兑换: 蓝
+        (3, true) => (0, 158, 115),    // 采集: 蓝绿
+        _ => return None,
+    };
+    Some(egui::Color32::from_rgba_unmultiplied(r, g, b, alpha))
 }
 
-/// 来源简短标签
-fn source_tag_text(source: Option<&ItemSource>) -> &'static str {
+/// 根据解析后的来源返回淡色背景，色盲友好模式下底色更深
+fn source_bg_color(
+    source: Option<&ItemSource>,
+    visuals: &egui::Visuals,
+    colorblind: bool,
+) -> Option<egui::Color32> {
+    let alpha = match (visuals.dark_mode, colorblind) {
+        (true, false) => 25,
+        (false, false) => 40,
+        (true, true) => 50,
+        (false, true) => 70,
+    };
+    source_color(source?.color_tag(), colorblind, alpha)
+}
+
+/// 来源简短标签。`always` 为真时未解析到来源也返回占位标签
+fn source_tag_text(source: Option<&ItemSource>, always: bool) -> &'static str {
     match source {
         Some(ItemSource::GilShop { .. }) => "商",
         Some(ItemSource::SpecialShop { .. }) => "换",
         Some(ItemSource::Gathering) => "采",
+        None if always => "无",
         None => "",
     }
 }
@@ -41,15 +184,99 @@ fn get_recipe_level(recipe: &Recipe, gs: &GameState) -> u8 {
         .unwrap_or(1)
 }
 
+/// 合成树展开深度的可调上限
+const MAX_CRAFT_TREE_DEPTH: usize = 20;
+
+/// 数量旁的快捷按钮 (另有按物品堆叠上限的 "×1组")
+const QUANTITY_PRESETS: [u32; 3] = [1, 3, 10];
+
+/// 循环路径的显示文本: A → B → A
+fn cycle_path_text(cycle: &[u32], gs: &GameState) -> String {
+    cycle
+        .iter()
+        .map(|&id| gs.item_by_id(id).map_or("???", |item| item.name.as_str()))
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+/// 合成树节点折叠状态的 ID (同一物品在不同深度各自独立)
+fn craft_tree_state_id(item_id: u32, depth: usize) -> egui::Id {
+    egui::Id::new(("craft_tree", item_id, depth))
+}
+
+/// 合成树节点的折叠状态 (深度超过 CRAFT_TREE_OPEN_DEPTH 的节点默认折叠)
+fn load_craft_node_state(
+    ctx: &egui::Context,
+    item_id: u32,
+    depth: usize,
+) -> egui::collapsing_header::CollapsingState {
+    egui::collapsing_header::CollapsingState::load_with_default_open(
+        ctx,
+        craft_tree_state_id(item_id, depth),
+        depth <= CRAFT_TREE_OPEN_DEPTH,
+    )
+}
+
+/// 改写合成树中单个节点的折叠状态，其他节点不受影响
+fn set_craft_node_open(ctx: &egui::Context, item_id: u32, depth: usize, open: bool) {
+    let mut state = load_craft_node_state(ctx, item_id, depth);
+    state.set_open(open);
+    state.store(ctx);
+}
+
+/// 来源分组的标题 (含小计)
+fn material_group_header(group: &MaterialGroup, gs: &GameState, style: NumberStyle) -> String {
+    let count = group.items.len();
+    match group.key {
+        MaterialGroupKey::GilShop => format!(
+            "金币商店 ({}种) – 小计 {}",
+            count,
+            fmt_gil(group.subtotal, style)
+        ),
+        MaterialGroupKey::Token(token_id) => {
+            let token_name = gs
+                .item_id_map
+                .get(&token_id)
+                .and_then(|&i| gs.all_items.get(i))
+                .map(|i| i.name.as_str())
+                .unwrap_or("???");
+            format!(
+                "兑换: {} ({}种) – 小计 {}",
+                token_name,
+                count,
+                fmt_count(group.subtotal, style)
+            )
+        }
+        MaterialGroupKey::Gathering => format!("采集 ({}种)", count),
+        MaterialGroupKey::Ignored => format!("已忽略 ({}种)", count),
+        MaterialGroupKey::NoSource => format!("无来源 ({}种)", count),
+    }
+}
+
 impl App {
     pub fn show_crafting_page(&mut self, ctx: &egui::Context, gs: &mut GameState) {
         // ── 左侧: 可制作物品列表 ──
         egui::SidePanel::left("crafting_list")
             .default_width(300.0)
             .show(ctx, |ui| {
-                ui.heading("合成检索");
+                ui.horizontal(|ui| {
+                    ui.heading(tr!("page.crafting"));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        self.show_craft_type_visibility_menu(ui);
+                    });
+                });
                 ui.separator();
 
+                let visible_types: Vec<u8> = (0u8..8)
+                    .filter(|ct| !self.config.hidden_craft_types.contains(ct))
+                    .collect();
+                if self
+                    .crafting_selected_craft_type
+                    .is_some_and(|ct| !visible_types.contains(&ct))
+                {
+                    self.crafting_selected_craft_type = None;
+                }
+
                 // 职业筛选按钮
                 ui.horizontal_wrapped(|ui| {
                     if ui
@@ -58,7 +285,7 @@ impl App {
                     {
                         self.crafting_selected_craft_type = None;
                     }
-                    for ct in 0u8..8 {
+                    for &ct in &visible_types {
                         if ui
                             .selectable_label(
                                 self.crafting_selected_craft_type == Some(ct),
@@ -84,28 +311,48 @@ impl App {
                 let craft_types: Vec<u8> = if let Some(ct) = self.crafting_selected_craft_type {
                     vec![ct]
                 } else {
-                    (0u8..8).collect()
+                    visible_types.clone()
                 };
 
+                // 按职业筛选，搜索词不变时复用缓存
+                let key = (
+                    search_lower.clone(),
+                    item_list::items_identity(&gs.all_items),
+                );
+                let filtered_by_type = self.crafting_filter_cache.get_or_compute(key, || {
+                    gs.craftable_by_type
+                        .iter()
+                        .map(|entries| {
+                            entries
+                                .iter()
+                                .filter(|&&(item_idx, _)| {
+                                    gs.all_items[item_idx].matches_search(&search_lower)
+                                })
+                                .copied()
+                                .collect()
+                        })
+                        .collect::<Vec<Vec<(usize, usize)>>>()
+                });
+
                 // 统计总数
                 let total_count: usize = craft_types
                     .iter()
-                    .map(|&ct| {
-                        gs.craftable_by_type[ct as usize]
-                            .iter()
-                            .filter(|&&(item_idx, _)| {
-                                if search_lower.is_empty() {
-                                    return true;
-                                }
-                                gs.all_items[item_idx]
-                                    .name
-                                    .to_lowercase()
-                                    .contains(&search_lower)
-                            })
-                            .count()
-                    })
+                    .map(|&ct| filtered_by_type[ct as usize].len())
                     .sum();
-                ui.label(format!("{} 件可制作物品", total_count));
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} 件可制作物品", total_count));
+                    if self.crafting_selected_item_id.is_some()
+                        && ui
+                            .small_button(format!(
+                                "{} {}",
+                                egui_phosphor::regular::CROSSHAIR,
+                                tr!("list.locate")
+                            ))
+                            .clicked()
+                    {
+                        self.reveal_crafting_selection(gs);
+                    }
+                });
                 ui.separator();
 
                 // 按职业分组显示
@@ -113,19 +360,7 @@ impl App {
                     .id_salt("crafting_item_scroll")
                     .show(ui, |ui| {
                         for &ct in &craft_types {
-                            let entries: Vec<(usize, usize)> = gs.craftable_by_type[ct as usize]
-                                .iter()
-                                .filter(|&&(item_idx, _)| {
-                                    if search_lower.is_empty() {
-                                        return true;
-                                    }
-                                    gs.all_items[item_idx]
-                                        .name
-                                        .to_lowercase()
-                                        .contains(&search_lower)
-                                })
-                                .copied()
-                                .collect();
+                            let entries = &filtered_by_type[ct as usize];
 
                             if entries.is_empty() {
                                 continue;
@@ -134,14 +369,32 @@ impl App {
                             let header =
                                 format!("{} ({})", CRAFT_TYPE_NAMES[ct as usize], entries.len());
                             let default_open = self.crafting_selected_craft_type.is_some();
+                            // 导航跳转时强制展开目标物品所在的分组
+                            let reveal = self.crafting_reveal_craft_type == Some(ct);
                             egui::CollapsingHeader::new(&header)
                                 .id_salt(format!("craft_group_{}", ct))
                                 .default_open(default_open)
+                                .open(reveal.then_some(true))
                                 .show(ui, |ui| {
-                                    self.show_crafting_item_list(ui, ctx, gs, &entries);
+                                    self.show_crafting_item_list(ui, ctx, gs, entries);
                                 });
                         }
                     });
+                self.crafting_reveal_craft_type = None;
+                // 选中物品被搜索或职业筛选排除时放弃滚动
+                let selected_idx = self
+                    .crafting_selected_item_id
+                    .and_then(|row_id| gs.item_id_map.get(&row_id).copied());
+                let selected_visible = selected_idx.is_some_and(|sel| {
+                    craft_types.iter().any(|&ct| {
+                        filtered_by_type[ct as usize]
+                            .iter()
+                            .any(|&(item_idx, _)| item_idx == sel)
+                    })
+                });
+                if !selected_visible {
+                    self.crafting_scroll_to_selected = false;
+                }
             });
 
         // ── 右侧: 选中节点详情 ──
@@ -155,8 +408,8 @@ impl App {
 
         // ── 中央: 图标+名称 + 两列(合成树 | 材料统计) ──
         egui::CentralPanel::default().show(ctx, |ui| {
-            if let Some(item_idx) = self.crafting_selected_item {
-                if let Some(item) = gs.all_items.get(item_idx) {
+            if let Some(row_id) = self.crafting_selected_item_id {
+                if let Some(item) = gs.item_by_id(row_id) {
                     // 顶部: 图标 + 名称 + 配方来源
                     ui.horizontal(|ui| {
                         if let Some(icon) = self.get_or_load_icon(ctx, &gs.game, item.icon_id) {
@@ -201,17 +454,23 @@ impl App {
                             }
                         }
                     });
+                    self.show_cost_comparison(ui, gs, item);
                     ui.separator();
 
-                    // 构建合成树
-                    let mut visited = HashSet::new();
-                    let tree = build_craft_tree(
-                        item.row_id,
-                        1,
-                        &gs.recipes,
-                        &gs.item_to_recipes,
-                        &mut visited,
-                    );
+                    // 构建合成树 (深层节点在首次展开时构建)，根节点需求量即制作数量
+                    let quantity = self.crafting_quantity.max(1);
+                    let tree_key = (item.row_id, quantity, self.crafting_tree_max_depth);
+                    let mut tree = match self.crafting_tree.take() {
+                        Some((key, tree)) if key == tree_key => tree,
+                        _ => build_craft_tree_lazy(
+                            item.row_id,
+                            quantity,
+                            &gs.recipes,
+                            &gs.item_to_recipes,
+                            self.crafting_tree_max_depth,
+                        ),
+                    };
+                    self.expand_opened_nodes(ctx, gs, &mut tree, &mut Vec::new());
 
                     // 收集折叠状态
                     let mut collapsed = HashSet::new();
@@ -234,14 +493,28 @@ impl App {
 
                     // 左侧剩余: 合成树
                     egui::CentralPanel::default().show_inside(ui, |ui| {
-                        ui.label(egui::RichText::new("合成树").strong());
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("合成树").strong());
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| {
+                                    ui.add(
+                                        egui::DragValue::new(&mut self.crafting_tree_max_depth)
+                                            .range(1..=MAX_CRAFT_TREE_DEPTH),
+                                    )
+                                    .on_hover_text("超出该深度的可制作素材按原始素材统计");
+                                    ui.label(egui::RichText::new("展开深度").small());
+                                },
+                            );
+                        });
                         ui.separator();
                         egui::ScrollArea::vertical()
                             .id_salt("craft_tree_scroll")
                             .show(ui, |ui| {
-                                self.show_craft_tree_node(ui, ctx, gs, &tree, 0);
+                                self.show_craft_tree_node(ui, ctx, gs, &tree, 0, None);
                             });
                     });
+                    self.crafting_tree = Some((tree_key, tree));
                 }
             } else {
                 ui.centered_and_justified(|ui| {
@@ -251,6 +524,70 @@ impl App {
         });
     }
 
+    /// 标题栏的职业显示设置弹出菜单 (至少保留一个职业)
+    fn show_craft_type_visibility_menu(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+        ui.menu_button(egui_phosphor::regular::FUNNEL, |ui| {
+            ui.label(egui::RichText::new("显示的职业").strong());
+            let hidden = &mut self.config.hidden_craft_types;
+            for ct in 0u8..8 {
+                let mut visible = !hidden.contains(&ct);
+                let is_last_visible = visible && hidden.len() >= 7;
+                let resp = ui.add_enabled(
+                    !is_last_visible,
+                    egui::Checkbox::new(&mut visible, CRAFT_TYPE_NAMES[ct as usize]),
+                );
+                if resp.changed() {
+                    if visible {
+                        hidden.retain(|&h| h != ct);
+                    } else {
+                        hidden.push(ct);
+                        hidden.sort_unstable();
+                    }
+                    changed = true;
+                }
+            }
+            ui.separator();
+            if ui
+                .add_enabled(!hidden.is_empty(), egui::Button::new("全部显示"))
+                .clicked()
+            {
+                hidden.clear();
+                changed = true;
+            }
+        })
+        .response
+        .on_hover_text("选择列表中显示的职业");
+        if changed {
+            self.schedule_config_save(ui.ctx());
+        }
+    }
+
+    /// 展开选中物品所在的职业分组，并在下一次绘制列表时滚动到该物品
+    pub fn reveal_crafting_selection(&mut self, gs: &GameState) {
+        let Some(i) = self
+            .crafting_selected_item_id
+            .and_then(|row_id| gs.item_id_map.get(&row_id).copied())
+        else {
+            return;
+        };
+        let craft_type = gs
+            .craftable_by_type
+            .iter()
+            .position(|entries| entries.iter().any(|&(item_idx, _)| item_idx == i))
+            .map(|ct| ct as u8);
+        if let Some(ct) = craft_type {
+            if self
+                .crafting_selected_craft_type
+                .is_some_and(|sel| sel != ct)
+            {
+                self.crafting_selected_craft_type = Some(ct);
+            }
+            self.crafting_reveal_craft_type = Some(ct);
+            self.crafting_scroll_to_selected = true;
+        }
+    }
+
     fn show_crafting_item_list(
         &mut self,
         ui: &mut egui::Ui,
@@ -262,12 +599,17 @@ impl App {
             ViewMode::List => {
                 for &(item_idx, _recipe_idx) in entries {
                     let item = &gs.all_items[item_idx];
-                    let is_selected = self.crafting_selected_item == Some(item_idx);
+                    let is_selected = self.crafting_selected_item_id == Some(item.row_id);
+                    if is_selected && self.crafting_scroll_to_selected {
+                        ui.scroll_to_cursor(Some(egui::Align::Center));
+                        self.crafting_scroll_to_selected = false;
+                    }
                     let di = DisplayItem {
                         id: item_idx,
                         name: &item.name,
                         icon_id: item.icon_id,
                         is_selected,
+                        badge: None,
                     };
                     if item_list::show_list_row(
                         ui,
@@ -277,13 +619,23 @@ impl App {
                         ctx,
                         &gs.game,
                     ) {
-                        self.crafting_selected_item = Some(item_idx);
+                        self.crafting_selected_item_id = Some(item.row_id);
                         self.crafting_selected_node_item = None;
                         self.crafting_source_overrides.clear();
+                        self.crafting_owned_amounts.clear();
+                        self.crafting_hq_ingredients.clear();
                     }
                 }
             }
             ViewMode::Grid => {
+                // 网格不逐项定位，滚动到所在分组的开头
+                let contains_selected = entries.iter().any(|&(item_idx, _)| {
+                    self.crafting_selected_item_id == Some(gs.all_items[item_idx].row_id)
+                });
+                if contains_selected && self.crafting_scroll_to_selected {
+                    ui.scroll_to_cursor(Some(egui::Align::Min));
+                    self.crafting_scroll_to_selected = false;
+                }
                 let display_items: Vec<DisplayItem<'_>> = entries
                     .iter()
                     .map(|&(item_idx, _)| {
@@ -292,7 +644,8 @@ impl App {
                             id: item_idx,
                             name: &item.name,
                             icon_id: item.icon_id,
-                            is_selected: self.crafting_selected_item == Some(item_idx),
+                            is_selected: self.crafting_selected_item_id == Some(item.row_id),
+                            badge: None,
                         }
                     })
                     .collect();
@@ -304,15 +657,18 @@ impl App {
                     ctx,
                     &gs.game,
                 ) {
-                    self.crafting_selected_item = Some(clicked_idx);
+                    self.crafting_selected_item_id = Some(gs.all_items[clicked_idx].row_id);
                     self.crafting_selected_node_item = None;
                     self.crafting_source_overrides.clear();
+                    self.crafting_owned_amounts.clear();
+                    self.crafting_hq_ingredients.clear();
                 }
             }
         }
     }
 
     /// 递归渲染合成树节点
+    /// parent_recipe: 父节点的配方索引 (叶子的 HQ 勾选以父节点物品区分)，根节点为 None
     fn show_craft_tree_node(
         &mut self,
         ui: &mut egui::Ui,
@@ -320,6 +676,7 @@ impl App {
         gs: &GameState,
         node: &CraftTreeNode,
         depth: usize,
+        parent_recipe: Option<usize>,
     ) {
         let item_name = gs
             .item_id_map
@@ -337,7 +694,7 @@ impl App {
 
         let is_selected = self.crafting_selected_node_item == Some(node.item_id);
 
-        if node.children.is_empty() {
+        if node.children.is_empty() && !node.unexpanded {
             // 叶子节点: 原始素材
             let sources = gs
                 .item_sources
@@ -345,7 +702,8 @@ impl App {
                 .map(|v| v.as_slice())
                 .unwrap_or(&[]);
             let resolved = resolve_source(node.item_id, sources, &self.crafting_source_overrides);
-            let bg = source_bg_color(resolved, ui.visuals());
+            let colorblind = self.config.colorblind_friendly;
+            let bg = source_bg_color(resolved, ui.visuals(), colorblind);
             let response = ui.horizontal(|ui| {
                 // 缩进对齐 (三角形占位)
                 ui.allocate_space(egui::vec2(14.0, 14.0));
@@ -358,13 +716,31 @@ impl App {
                     ui.allocate_space(egui::vec2(18.0, 18.0));
                 }
                 // 来源标签
-                let tag = source_tag_text(resolved);
+                let tag = source_tag_text(resolved, colorblind);
                 let label = if tag.is_empty() {
                     format!("{} x{}", item_name, node.amount_needed)
                 } else {
                     format!("{} x{} [{}]", item_name, node.amount_needed, tag)
                 };
-                ui.selectable_label(is_selected, label)
+                let resp = ui.selectable_label(is_selected, label);
+                // 被截断或循环引用的可制作素材
+                if node.truncated {
+                    ui.label(egui::RichText::new("…").weak())
+                        .on_hover_text("超出展开深度，按原始素材统计");
+                } else if let Some(cycle) = &node.cycle {
+                    ui.label(
+                        egui::RichText::new(egui_phosphor::regular::ARROWS_CLOCKWISE)
+                            .color(egui::Color32::from_rgb(230, 160, 60)),
+                    )
+                    .on_hover_text(format!(
+                        "配方循环引用，按原始素材统计:\n{}",
+                        cycle_path_text(cycle, gs)
+                    ));
+                }
+                if let Some(parent) = parent_recipe.map(|idx| &gs.recipes[idx]) {
+                    self.show_hq_ingredient_toggle(ui, parent, node.item_id);
+                }
+                resp
             });
             // 绘制来源背景色
             if let Some(color) = bg {
@@ -386,7 +762,7 @@ impl App {
                 let level = get_recipe_level(recipe, gs);
 
                 // 只有当 secret_recipe_book > 0 且在表中找到名称时才显示秘籍名
-                if recipe.secret_recipe_book > 0 {
+                let text = if recipe.secret_recipe_book > 0 {
                     if let Some(name) = gs.secret_recipe_book_names.get(&recipe.secret_recipe_book) {
                         name.clone()
                     } else {
@@ -395,17 +771,17 @@ impl App {
                     }
                 } else {
                     format!("Lv.{}", level)
+                };
+                if recipe.required_quality > 0 {
+                    format!("{} · 品质 ≥{}", text, recipe.required_quality)
+                } else {
+                    text
                 }
             } else {
                 String::new()
             };
 
-            let state_id = egui::Id::new(("craft_tree", node.item_id, depth));
-            let mut state = egui::collapsing_header::CollapsingState::load_with_default_open(
-                ui.ctx(),
-                state_id,
-                true,
-            );
+            let mut state = load_craft_node_state(ui.ctx(), node.item_id, depth);
 
             // 绘制 header 行: 三角形 + 图标 + 可选中标签
             let header_response = ui.horizontal(|ui| {
@@ -477,7 +853,7 @@ impl App {
             // 子节点
             state.show_body_indented(&header_response.response, ui, |ui| {
                 for child in &node.children {
-                    self.show_craft_tree_node(ui, ctx, gs, child, depth + 1);
+                    self.show_craft_tree_node(ui, ctx, gs, child, depth + 1, node.recipe_idx);
                 }
             });
 
@@ -485,7 +861,80 @@ impl App {
         }
     }
 
-    /// 收集合成树中被折叠的节点 (item_id, depth)
+    /// 叶子素材的 HQ 勾选 (不改变数量，只在素材汇总与导出的清单中标注)
+    fn show_hq_ingredient_toggle(&mut self, ui: &mut egui::Ui, parent: &Recipe, item_id: u32) {
+        let key = (parent.result_item_id, item_id);
+        let mut hq = self.crafting_hq_ingredients.contains(&key);
+        let mut hover = String::from("使用 HQ 素材 (只在清单中标注，不改变数量)");
+        if parent.material_quality_factor > 0 {
+            hover.push_str(&format!(
+                "\nHQ 素材最多提供 {}% 的初期品质",
+                parent.material_quality_factor
+            ));
+        }
+        if !parent.can_hq {
+            hover.push_str("\n该配方的成品不能为 HQ");
+        }
+        if ui
+            .checkbox(&mut hq, egui::RichText::new("HQ").small())
+            .on_hover_text(hover)
+            .changed()
+        {
+            if hq {
+                self.crafting_hq_ingredients.insert(key);
+            } else {
+                self.crafting_hq_ingredients.remove(&key);
+            }
+        }
+    }
+
+    /// 数量文本，有 HQ 需求时 HQ 与 NQ 分开显示，如 "x3 (HQ) + x5"
+    /// (amount 为扣除持有数后的剩余需求，持有的数量优先抵扣 NQ)
+    fn hq_amount_text(&self, mat_id: u32, amount: u32) -> String {
+        let hq = self
+            .crafting_hq_amounts
+            .get(&mat_id)
+            .copied()
+            .unwrap_or(0)
+            .min(amount);
+        let nq = amount - hq;
+        match (hq, nq) {
+            (0, _) => format!("x{}", amount),
+            (_, 0) => format!("x{} (HQ)", hq),
+            _ => format!("x{} (HQ) + x{}", hq, nq),
+        }
+    }
+
+    /// 为已被打开但尚未构建子节点的节点构建下一层 (延迟构建)
+    /// path: 从根到当前节点父级的物品链
+    fn expand_opened_nodes(
+        &self,
+        ctx: &egui::Context,
+        gs: &GameState,
+        node: &mut CraftTreeNode,
+        path: &mut Vec<u32>,
+    ) {
+        if node.children.is_empty() && !node.unexpanded {
+            return;
+        }
+        if !load_craft_node_state(ctx, node.item_id, path.len()).is_open() {
+            return;
+        }
+        expand_node(
+            node,
+            path,
+            &gs.recipes,
+            &gs.item_to_recipes,
+            self.crafting_tree_max_depth,
+        );
+        path.push(node.item_id);
+        for child in &mut node.children {
+            self.expand_opened_nodes(ctx, gs, child, path);
+        }
+        path.pop();
+    }
+
+    /// 收集合成树中被折叠的节点 (item_id, depth)，包括尚未构建子节点的节点
     fn collect_collapsed_nodes(
         &self,
         ctx: &egui::Context,
@@ -493,13 +942,11 @@ impl App {
         depth: usize,
         collapsed: &mut HashSet<(u32, usize)>,
     ) {
-        if node.children.is_empty() {
+        if node.children.is_empty() && !node.unexpanded {
             return;
         }
-        let state_id = egui::Id::new(("craft_tree", node.item_id, depth));
-        let state =
-            egui::collapsing_header::CollapsingState::load_with_default_open(ctx, state_id, true);
-        if !state.is_open() {
+        let state = load_craft_node_state(ctx, node.item_id, depth);
+        if !state.is_open() || node.unexpanded {
             collapsed.insert((node.item_id, depth));
         } else {
             for child in &node.children {
@@ -508,6 +955,148 @@ impl App {
         }
     }
 
+    /// 后台计算默认来源制作成本: 完整合成树可能很大，只在界面线程复制可达的配方数据
+    fn start_craft_cost_job(
+        &mut self,
+        ctx: &egui::Context,
+        gs: &GameState,
+        key: (u32, u32, usize),
+    ) {
+        let (root_item_id, quantity, depth) = key;
+        let snapshot = CraftCostSnapshot::collect(
+            root_item_id,
+            &gs.recipes,
+            &gs.item_to_recipes,
+            &gs.item_sources,
+            |id| gs.item_by_id(id).map(|i| i.price_mid).unwrap_or(0),
+        );
+        let (tx, rx) = std::sync::mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            // 已被新的任务替换时接收端已丢弃，结果直接作废
+            let estimate = snapshot.estimate(root_item_id, quantity, depth);
+            if tx.send(estimate).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+        self.crafting_cost_job = Some(CraftCostJob { key, receiver: rx });
+    }
+
+    /// 取回后台成本计算的结果并写入缓存
+    fn poll_craft_cost_job(&mut self) {
+        let Some(job) = &self.crafting_cost_job else {
+            return;
+        };
+        match job.receiver.try_recv() {
+            Ok(estimate) => {
+                self.crafting_cost_cache.insert(job.key, estimate);
+                self.crafting_cost_job = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.crafting_cost_job = None,
+        }
+    }
+
+    /// 制作 / NPC 购买价格比较，最便宜的一项标绿
+    /// (制作成本按制作数量构建的合成树在后台计算并缓存，配方单次产出多个时不是线性关系；缺失的来源不显示)
+    fn show_cost_comparison(&mut self, ui: &mut egui::Ui, gs: &GameState, item: &GameItem) {
+        let quantity = self.crafting_quantity.max(1);
+        let key = (item.row_id, quantity, self.crafting_tree_max_depth);
+        self.poll_craft_cost_job();
+        let estimate = self.crafting_cost_cache.get(&key).copied();
+        if estimate.is_none()
+            && self
+                .crafting_cost_job
+                .as_ref()
+                .is_none_or(|job| job.key != key)
+        {
+            self.start_craft_cost_job(ui.ctx(), gs, key);
+        }
+        let estimate_complete = estimate.is_some_and(|e| e.complete);
+        let quantity = quantity as u64;
+        let craft_gil = estimate.and_then(|e| (e.gil > 0 || e.complete).then_some(e.gil));
+        let vendor_gil = gs
+            .item_sources
+            .get(&item.row_id)
+            .is_some_and(|s| s.iter().any(|s| matches!(s, ItemSource::GilShop { .. })))
+            .then(|| item.price_mid as u64 * quantity);
+
+        // 只有完整计价的数字参与比较
+        let mut comparable: Vec<u64> = Vec::new();
+        if let Some(gil) = craft_gil.filter(|_| estimate_complete) {
+            comparable.push(gil);
+        }
+        comparable.extend(vendor_gil);
+        let cheapest = (comparable.len() > 1).then(|| comparable.iter().copied().min()).flatten();
+
+        let style = self.config.number_style;
+        let green = egui::Color32::from_rgb(80, 200, 100);
+        let figure = |label: &str, gil: u64, comparable: bool| {
+            let text = format!("{} {}", label, fmt_gil(gil, style));
+            let text = if comparable { text } else { format!("{}+", text) };
+            let rich = egui::RichText::new(text);
+            if comparable && cheapest == Some(gil) {
+                rich.color(green).strong()
+            } else if comparable {
+                rich
+            } else {
+                rich.weak()
+            }
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("数量");
+            ui.add(egui::DragValue::new(&mut self.crafting_quantity).range(1..=9999));
+            for preset in QUANTITY_PRESETS {
+                if ui
+                    .selectable_label(self.crafting_quantity == preset, format!("×{}", preset))
+                    .clicked()
+                {
+                    self.crafting_quantity = preset;
+                }
+            }
+            if item.stack_size > 1 {
+                let stack = item.stack_size.min(9999);
+                if ui
+                    .selectable_label(self.crafting_quantity == stack, "×1组")
+                    .on_hover_text(format!("{} 个", stack))
+                    .clicked()
+                {
+                    self.crafting_quantity = stack;
+                }
+            }
+            ui.label(
+                egui::RichText::new(format!(
+                    "{} 个{}",
+                    self.crafting_quantity,
+                    item.stack_suffix(self.crafting_quantity)
+                ))
+                .weak(),
+            );
+            ui.separator();
+            match craft_gil {
+                _ if estimate.is_none() => {
+                    ui.spinner();
+                    ui.label(egui::RichText::new("制作 计算中").weak());
+                }
+                Some(gil) => {
+                    let resp = ui.label(figure("制作", gil, estimate_complete));
+                    if !estimate_complete {
+                        resp.on_hover_text("部分素材来自采集或兑换，未计入金币成本");
+                    }
+                }
+                None => {
+                    ui.label(egui::RichText::new("制作 无金币成本").weak())
+                        .on_hover_text("全部素材来自采集或兑换");
+                }
+            }
+            if let Some(gil) = vendor_gil {
+                ui.separator();
+                ui.label(figure("NPC", gil, true));
+            }
+        });
+    }
+
     /// 右侧详情面板: 选中节点物品信息
     fn show_crafting_detail_panel(
         &mut self,
@@ -525,6 +1114,7 @@ impl App {
         let Some(item) = gs.all_items.get(idx) else {
             return;
         };
+        let style = self.config.number_style;
 
         // 统一物品详情头部
         {
@@ -546,32 +1136,25 @@ impl App {
 
         // 计算整棵树中该物品的总需求量
         let total_need = self
-            .crafting_selected_item
-            .and_then(|item_idx| {
-                let root_item = gs.all_items.get(item_idx)?;
-                let mut visited = HashSet::new();
-                let tree = build_craft_tree(
-                    root_item.row_id,
-                    1,
-                    &gs.recipes,
-                    &gs.item_to_recipes,
-                    &mut visited,
-                );
+            .crafting_tree
+            .as_ref()
+            .filter(|((root_id, _, _), _)| self.crafting_selected_item_id == Some(*root_id))
+            .map(|(_, tree)| {
                 let mut collapsed = HashSet::new();
-                self.collect_collapsed_nodes(ctx, &tree, 0, &mut collapsed);
-                Some(total_amount_in_tree(&tree, node_item_id, 0, &collapsed))
+                self.collect_collapsed_nodes(ctx, tree, 0, &mut collapsed);
+                total_amount_in_tree(tree, node_item_id, 0, &collapsed)
             })
             .unwrap_or(self.crafting_selected_node_amount);
 
         egui::Grid::new("node_item_info").show(ui, |ui| {
             ui.label("需求数量:");
-            ui.label(format!("{}", total_need));
+            ui.label(fmt_count(total_need as u64, style));
             ui.end_row();
 
             // 收购价格 (NPC 回收价)
             if item.price_low > 0 {
                 ui.label("收购价格:");
-                ui.label(format!("{} Gil", item.price_low));
+                ui.label(fmt_gil(item.price_low as u64, style));
                 ui.end_row();
             }
 
@@ -632,15 +1215,17 @@ impl App {
                     let is_active =
                         !is_ignored && resolved.map(|r| std::ptr::eq(r, source)).unwrap_or(false);
                     let alpha = if is_active { 255 } else { 120 };
+                    let colorblind = self.config.colorblind_friendly;
 
                     match source {
                         ItemSource::GilShop {
                             shop_name,
                             npc_location,
+                            nearest_aetheryte,
                         } => {
                             ui.horizontal(|ui| {
-                                let color =
-                                    egui::Color32::from_rgba_unmultiplied(220, 180, 40, alpha);
+                                let color = source_color(source.color_tag(), colorblind, alpha)
+                                    .unwrap_or_default();
                                 ui.label(
                                     egui::RichText::new(format!(
                                         "{} 金币",
@@ -649,7 +1234,11 @@ impl App {
                                     .color(color)
                                     .strong(),
                                 );
-                                let text = format!("{} ({}G)", shop_name, item.price_mid);
+                                let text = format!(
+                                    "{} ({})",
+                                    shop_name,
+                                    fmt_gil(item.price_mid as u64, style)
+                                );
                                 if is_active {
                                     ui.label(text);
                                 } else {
@@ -667,9 +1256,22 @@ impl App {
                                     .weak(),
                                 );
                             }
+                            if let Some(aetheryte) = nearest_aetheryte {
+                                ui.label(
+                                    egui::RichText::new(format!(
+                                        "    {} 传送点: {}",
+                                        egui_phosphor::regular::NAVIGATION_ARROW,
+                                        aetheryte
+                                    ))
+                                    .small()
+                                    .weak(),
+                                );
+                            }
                         }
                         ItemSource::SpecialShop {
                             shop_name,
+                            npc_name,
+                            npc_location,
                             cost_item_id,
                             cost_count,
                         } => {
@@ -680,8 +1282,8 @@ impl App {
                                 .map(|i| i.name.as_str())
                                 .unwrap_or("???");
                             ui.horizontal(|ui| {
-                                let color =
-                                    egui::Color32::from_rgba_unmultiplied(160, 120, 230, alpha);
+                                let color = source_color(source.color_tag(), colorblind, alpha)
+                                    .unwrap_or_default();
                                 ui.label(
                                     egui::RichText::new(format!(
                                         "{} 兑换",
@@ -697,11 +1299,27 @@ impl App {
                                     ui.label(egui::RichText::new(text).weak());
                                 }
                             });
+                            let npc = match (npc_name, npc_location) {
+                                (Some(name), Some(loc)) => Some(format!("{} @ {}", name, loc)),
+                                (Some(name), None) => Some(name.clone()),
+                                _ => None,
+                            };
+                            if let Some(npc) = npc {
+                                ui.label(
+                                    egui::RichText::new(format!(
+                                        "    {} {}",
+                                        egui_phosphor::regular::MAP_PIN,
+                                        npc
+                                    ))
+                                    .small()
+                                    .weak(),
+                                );
+                            }
                         }
                         ItemSource::Gathering => {
                             ui.horizontal(|ui| {
-                                let color =
-                                    egui::Color32::from_rgba_unmultiplied(80, 180, 80, alpha);
+                                let color = source_color(source.color_tag(), colorblind, alpha)
+                                    .unwrap_or_default();
                                 ui.label(
                                     egui::RichText::new(format!(
                                         "{} 采集",
@@ -732,7 +1350,27 @@ impl App {
         tree: &CraftTreeNode,
         collapsed: &HashSet<(u32, usize)>,
     ) {
-        let materials = summarize_materials_with_collapsed(tree, collapsed);
+        let needed = summarize_materials_with_collapsed(tree, collapsed);
+        self.crafting_hq_amounts =
+            summarize_hq_leaves(tree, collapsed, &self.crafting_hq_ingredients);
+        self.crafting_material_selection.sync_root(tree.item_id);
+
+        if self.crafting_inventory_import.open {
+            self.show_inventory_import_window(ctx, gs, &needed);
+        }
+
+        // 扣除部分持有的数量，费用、分组与导出的清单都基于剩余需求
+        let materials: Vec<(u32, u32)> = needed
+            .iter()
+            .map(|&(mat_id, amount)| {
+                let owned = self
+                    .crafting_owned_amounts
+                    .get(&mat_id)
+                    .copied()
+                    .unwrap_or(0);
+                (mat_id, amount.saturating_sub(owned))
+            })
+            .collect();
 
         if materials.is_empty() {
             ui.label("无原始素材");
@@ -789,10 +1427,15 @@ impl App {
         }
 
         // ── 总计区 ──
+        let style = self.config.number_style;
         if total_gil > 0 {
             ui.label(
-                egui::RichText::new(format!("{} {}G", egui_phosphor::regular::COINS, total_gil))
-                    .strong(),
+                egui::RichText::new(format!(
+                    "{} {}",
+                    egui_phosphor::regular::COINS,
+                    fmt_gil(total_gil, style)
+                ))
+                .strong(),
             );
         }
         for (&token_id, &count) in &token_costs {
@@ -807,7 +1450,7 @@ impl App {
                     "{} {} x{}",
                     egui_phosphor::regular::SWAP,
                     token_name,
-                    count
+                    fmt_count(count, style)
                 ))
                 .strong(),
             );
@@ -839,139 +1482,914 @@ impl App {
         ui.separator();
 
         // ── 素材列表 ──
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.crafting_group_by_source, "按来源分组");
+            if ui
+                .button(format!("{} 复制清单", egui_phosphor::regular::COPY))
+                .clicked()
+            {
+                let text = self.material_summary_text(gs, &materials);
+                ctx.copy_text(text);
+            }
+            ui.menu_button(format!("{} Teamcraft", egui_phosphor::regular::EXPORT), |ui| {
+                let export = if ui.button("复制文本清单").clicked() {
+                    Some(TeamcraftExport::Text { english: false })
+                } else if ui
+                    .button("复制文本清单 (英文名)")
+                    .on_hover_text("Teamcraft 按英文名匹配物品，需要客户端含英文数据")
+                    .clicked()
+                {
+                    Some(TeamcraftExport::Text { english: true })
+                } else if ui
+                    .button("复制导入链接")
+                    .on_hover_text("按物品 ID 导入，只包含合成目标，素材由 Teamcraft 展开")
+                    .clicked()
+                {
+                    Some(TeamcraftExport::Url)
+                } else {
+                    None
+                };
+                if let Some(export) = export {
+                    let root = (tree.item_id, tree.amount_needed);
+                    let text = self.teamcraft_export_text(gs, root, &materials, export);
+                    ctx.copy_text(text);
+                    self.toasts.push("已复制到剪贴板");
+                }
+            });
+            ui.menu_button(
+                format!("{} 导出 HTML", egui_phosphor::regular::FILE_HTML),
+                |ui| {
+                    let open_after = if ui.button("保存...").clicked() {
+                        Some(false)
+                    } else if ui.button("保存并在浏览器中打开").clicked() {
+                        Some(true)
+                    } else {
+                        None
+                    };
+                    if let Some(open_after) = open_after {
+                        ui.close();
+                        self.export_craft_plan_html(gs, tree, collapsed, &materials, open_after);
+                    }
+                },
+            )
+            .response
+            .on_hover_text("制作步骤、按来源分组的素材与合成树，适合打印");
+            if ui
+                .button(format!(
+                    "{} 导入背包",
+                    egui_phosphor::regular::CLIPBOARD_TEXT
+                ))
+                .on_hover_text("粘贴背包工具导出的 \"物品名<Tab>数量\" 清单，批量标记已持有")
+                .clicked()
+            {
+                self.crafting_inventory_import.open = true;
+            }
+        });
+
+        // 显示顺序，用于 shift 范围选择
+        let groups = if self.crafting_group_by_source {
+            Some(self.material_groups(gs, &materials))
+        } else {
+            None
+        };
+        let order: Vec<u32> = match &groups {
+            Some(groups) => groups
+                .iter()
+                .flat_map(|g| g.items.iter().map(|&(id, _)| id))
+                .collect(),
+            None => materials.iter().map(|&(id, _)| id).collect(),
+        };
+
+        // ── 多选 ──
+        let selection = &mut self.crafting_material_selection;
+        let selected_count = order
+            .iter()
+            .filter(|id| selection.items.contains(id))
+            .count();
+        let mut all_selected = selected_count == order.len();
+        ui.horizontal(|ui| {
+            if ui
+                .add(
+                    egui::Checkbox::new(&mut all_selected, "全选")
+                        .indeterminate(selected_count > 0 && selected_count < order.len()),
+                )
+                .on_hover_text("Ctrl+单击切换选中，Shift+单击选择范围")
+                .changed()
+            {
+                selection.clear();
+                if all_selected {
+                    selection.items.extend(&order);
+                }
+            }
+        });
+        if selected_count > 0 {
+            let mut action = None;
+            ui.horizontal_wrapped(|ui| {
+                ui.label(egui::RichText::new(format!("已选 {} 项", selected_count)).small());
+                if ui.small_button("标记已持有").clicked() {
+                    action = Some(MaterialBatchAction::MarkOwned);
+                }
+                if ui.small_button("取消忽略").clicked() {
+                    action = Some(MaterialBatchAction::Unignore);
+                }
+                if ui.small_button("全部选用采集").clicked() {
+                    action = Some(MaterialBatchAction::UseGathering);
+                }
+                if ui.small_button("全部选用商店").clicked() {
+                    action = Some(MaterialBatchAction::UseGilShop);
+                }
+            });
+            if let Some(action) = action {
+                self.apply_material_batch_action(gs, action);
+            }
+        }
+
         egui::ScrollArea::vertical()
             .id_salt("material_summary_scroll")
             .show(ui, |ui| {
-                for &(mat_id, amount) in &materials {
-                    let (mat_name, mat_icon, mat_price) = gs
-                        .item_id_map
-                        .get(&mat_id)
-                        .and_then(|&idx| gs.all_items.get(idx))
-                        .map(|i| (i.name.as_str(), i.icon_id, i.price_mid))
-                        .unwrap_or(("???", 0, 0));
-
-                    let sources = gs
-                        .item_sources
-                        .get(&mat_id)
-                        .map(|v| v.as_slice())
-                        .unwrap_or(&[]);
-                    let is_ignored = matches!(
+                if let Some(groups) = &groups {
+                    for group in groups {
+                        let header = material_group_header(group, gs, style);
+                        egui::CollapsingHeader::new(egui::RichText::new(header).strong())
+                            .id_salt(("material_group", group.key))
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                for &(mat_id, amount) in &group.items {
+                                    self.show_material_row(
+                                        ui, ctx, gs, mat_id, amount, &order, collapsed,
+                                    );
+                                }
+                            });
+                    }
+                } else {
+                    for &(mat_id, amount) in &materials {
+                        self.show_material_row(ui, ctx, gs, mat_id, amount, &order, collapsed);
+                    }
+                }
+            });
+    }
+
+    /// 背包清单导入窗口: 粘贴文本 → 匹配物品 → 标记已持有 / 记录部分持有
+    fn show_inventory_import_window(
+        &mut self,
+        ctx: &egui::Context,
+        gs: &GameState,
+        needed: &[(u32, u32)],
+    ) {
+        let mut open = true;
+        let mut do_import = false;
+        let import = &mut self.crafting_inventory_import;
+        egui::Window::new("导入背包清单")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    egui::RichText::new("每行一项: 物品名<Tab>数量，例如从背包工具复制的表格")
+                        .small()
+                        .weak(),
+                );
+                egui::ScrollArea::vertical()
+                    .id_salt("inventory_import_text")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut import.text)
+                                .desired_rows(8)
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!import.text.trim().is_empty(), egui::Button::new("导入"))
+                        .clicked()
+                    {
+                        do_import = true;
+                    }
+                    if ui.button("清空").clicked() {
+                        import.text.clear();
+                        import.unmatched.clear();
+                        import.summary = None;
+                    }
+                });
+                if let Some(summary) = &import.summary {
+                    ui.separator();
+                    ui.label(summary);
+                }
+                if !import.unmatched.is_empty() {
+                    ui.label(
+                        egui::RichText::new(format!("未匹配 {} 行:", import.unmatched.len()))
+                            .color(ui.visuals().warn_fg_color),
+                    );
+                    egui::ScrollArea::vertical()
+                        .id_salt("inventory_import_unmatched")
+                        .max_height(120.0)
+                        .show(ui, |ui| {
+                            for line in &import.unmatched {
+                                ui.label(egui::RichText::new(line).small());
+                            }
+                        });
+                }
+            });
+        if !open {
+            self.crafting_inventory_import.open = false;
+        }
+        if do_import {
+            self.import_inventory(gs, needed);
+        }
+    }
+
+    /// 按名称匹配背包清单 (先精确匹配，再忽略大小写)，对当前汇总中的素材:
+    /// 持有数足够时标记已持有，否则记录部分持有数量
+    fn import_inventory(&mut self, gs: &GameState, needed: &[(u32, u32)]) {
+        let (entries, mut unmatched) = parse_inventory_dump(&self.crafting_inventory_import.text);
+
+        // 派生索引还在后台构建时临时建一份查找表
+        let local_lookup;
+        let lookup = match gs.derived.names() {
+            Some(lookup) => lookup,
+            None => {
+                local_lookup = NameLookup::new(&gs.all_items);
+                &local_lookup
+            }
+        };
+
+        // 同一物品出现在多行时累加
+        let mut owned: HashMap<u32, u32> = HashMap::new();
+        for (name, count) in entries {
+            match lookup.find(&name) {
+                Some(row_id) => *owned.entry(row_id).or_insert(0) += count,
+                None => unmatched.push(format!("{}\t{}", name, count)),
+            }
+        }
+
+        let (mut full, mut partial, mut unused) = (0, 0, 0);
+        for (row_id, count) in owned {
+            let Some(&(_, need)) = needed.iter().find(|&&(id, _)| id == row_id) else {
+                unused += 1;
+                continue;
+            };
+            if count >= need {
+                self.crafting_source_overrides
+                    .insert(row_id, SourceChoice::Ignore);
+                self.crafting_owned_amounts.remove(&row_id);
+                full += 1;
+            } else if count > 0 {
+                self.crafting_owned_amounts.insert(row_id, count);
+                partial += 1;
+            }
+        }
+
+        let import = &mut self.crafting_inventory_import;
+        import.summary = Some(format!(
+            "已持有 {} 种，部分持有 {} 种，{} 种不在当前素材中",
+            full, partial, unused
+        ));
+        import.unmatched = unmatched;
+    }
+
+    /// 对选中的素材批量写入来源选择，跳过没有对应来源的素材，完成后清空选择
+    fn apply_material_batch_action(&mut self, gs: &GameState, action: MaterialBatchAction) {
+        let selected: Vec<u32> = self
+            .crafting_material_selection
+            .items
+            .iter()
+            .copied()
+            .collect();
+        for mat_id in selected {
+            let sources = gs
+                .item_sources
+                .get(&mat_id)
+                .map(|v| v.as_slice())
+                .unwrap_or(&[]);
+            match action {
+                MaterialBatchAction::MarkOwned => {
+                    self.crafting_source_overrides
+                        .insert(mat_id, SourceChoice::Ignore);
+                }
+                MaterialBatchAction::Unignore => {
+                    if matches!(
                         self.crafting_source_overrides.get(&mat_id),
                         Some(SourceChoice::Ignore)
-                    );
-                    let is_selected = self.crafting_selected_node_item == Some(mat_id);
-
-                    // 当前选中的来源索引
-                    let current_choice = self.crafting_source_overrides.get(&mat_id).copied();
-                    let active_idx = match current_choice {
-                        Some(SourceChoice::Index(i)) => Some(i),
-                        Some(SourceChoice::Ignore) => None,
-                        None => crate::domain::default_source_index(sources),
-                    };
+                    ) {
+                        self.crafting_source_overrides.remove(&mat_id);
+                    }
+                }
+                MaterialBatchAction::UseGathering => {
+                    if let Some(i) = sources
+                        .iter()
+                        .position(|s| matches!(s, ItemSource::Gathering))
+                    {
+                        self.crafting_source_overrides
+                            .insert(mat_id, SourceChoice::Index(i));
+                    }
+                }
+                MaterialBatchAction::UseGilShop => {
+                    if let Some(i) = sources
+                        .iter()
+                        .position(|s| matches!(s, ItemSource::GilShop { .. }))
+                    {
+                        self.crafting_source_overrides
+                            .insert(mat_id, SourceChoice::Index(i));
+                    }
+                }
+            }
+        }
+        self.crafting_material_selection.clear();
+    }
 
-                    // 背景色
-                    let resolved = active_idx.and_then(|i| sources.get(i));
-                    let bg = if is_ignored {
-                        None
-                    } else {
-                        source_bg_color(resolved, ui.visuals())
-                    };
+    /// 按当前来源选择对素材分组
+    fn material_groups(&self, gs: &GameState, materials: &[(u32, u32)]) -> Vec<MaterialGroup> {
+        group_materials_by_source(
+            materials,
+            &gs.item_sources,
+            &self.crafting_source_overrides,
+            |item_id| {
+                gs.item_id_map
+                    .get(&item_id)
+                    .and_then(|&i| gs.all_items.get(i))
+                    .map(|i| i.price_mid)
+                    .unwrap_or(0)
+            },
+        )
+    }
 
-                    let resp = ui.horizontal(|ui| {
-                        // 图标
-                        if let Some(icon) = self.get_or_load_icon(ctx, &gs.game, mat_icon) {
-                            ui.image(egui::load::SizedTexture::new(
-                                icon.id(),
-                                egui::vec2(18.0, 18.0),
-                            ));
-                        } else {
-                            ui.allocate_space(egui::vec2(18.0, 18.0));
+    /// 导出素材清单文本，分组开关打开时按来源分组输出
+    fn material_summary_text(&self, gs: &GameState, materials: &[(u32, u32)]) -> String {
+        let item_name = |id: u32| {
+            gs.item_id_map
+                .get(&id)
+                .and_then(|&i| gs.all_items.get(i))
+                .map(|i| i.name.as_str())
+                .unwrap_or("???")
+        };
+        // 数量与堆叠格数，如 "x198 (1 组)"，HQ 需求分开标注
+        let amount_text = |id: u32, amount: u32| {
+            let stacks = gs
+                .item_by_id(id)
+                .map(|i| i.stack_suffix(amount))
+                .unwrap_or_default();
+            format!("{}{}", self.hq_amount_text(id, amount), stacks)
+        };
+        let style = self.config.number_style;
+        let mut out = String::new();
+        if self.crafting_group_by_source {
+            for group in self.material_groups(gs, materials) {
+                out.push_str(&format!("{}\n", material_group_header(&group, gs, style)));
+                if group.key == MaterialGroupKey::GilShop {
+                    let clusters = cluster_by_aetheryte(
+                        &group.items,
+                        &gs.item_sources,
+                        &self.crafting_source_overrides,
+                    );
+                    // 只有一个聚类且没有传送点信息时不额外分层
+                    if !matches!(clusters.as_slice(), [(None, _)]) {
+                        for (aetheryte, items) in clusters {
+                            out.push_str(&format!("  传送点: {}\n", aetheryte.unwrap_or("未知")));
+                            for (mat_id, amount) in items {
+                                out.push_str(&format!(
+                                    "    {} {}\n",
+                                    item_name(mat_id),
+                                    amount_text(mat_id, amount)
+                                ));
+                            }
                         }
-
-                        // 名称 + 数量 (可点击选中)
-                        let name_text = format!("{} x{}", mat_name, amount);
-                        let rt = if is_ignored {
-                            egui::RichText::new(&name_text).strikethrough().weak()
-                        } else {
-                            egui::RichText::new(&name_text)
-                        };
-                        if ui.selectable_label(is_selected, rt).clicked() {
-                            self.crafting_selected_node_item = Some(mat_id);
-                            self.crafting_selected_node_amount = amount;
+                        continue;
+                    }
+                }
+                if let MaterialGroupKey::Token(_) = group.key {
+                    let clusters = cluster_by_shop_npc(
+                        &group.items,
+                        &gs.item_sources,
+                        &self.crafting_source_overrides,
+                    );
+                    if !matches!(clusters.as_slice(), [(None, _)]) {
+                        for (npc, items) in clusters {
+                            let npc = match npc {
+                                Some((name, Some(loc))) => format!("{} @ {}", name, loc),
+                                Some((name, None)) => name.to_string(),
+                                None => "未知".to_string(),
+                            };
+                            out.push_str(&format!("  兑换处: {}\n", npc));
+                            for (mat_id, amount) in items {
+                                out.push_str(&format!(
+                                    "    {} {}\n",
+                                    item_name(mat_id),
+                                    amount_text(mat_id, amount)
+                                ));
+                            }
                         }
+                        continue;
+                    }
+                }
+                for &(mat_id, amount) in &group.items {
+                    out.push_str(&format!(
+                        "  {} {}\n",
+                        item_name(mat_id),
+                        amount_text(mat_id, amount)
+                    ));
+                }
+            }
+        } else {
+            for &(mat_id, amount) in materials {
+                out.push_str(&format!(
+                    "{} {}\n",
+                    item_name(mat_id),
+                    amount_text(mat_id, amount)
+                ));
+            }
+        }
+        out
+    }
 
-                        // 来源选择按钮 (右对齐)
-                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                            // "已持有" 按钮
-                            let own_label = if is_ignored {
-                                egui::RichText::new(egui_phosphor::regular::CHECK_CIRCLE).strong()
-                            } else {
-                                egui::RichText::new(egui_phosphor::regular::CHECK_CIRCLE).weak()
-                            };
-                            if ui
-                                .selectable_label(is_ignored, own_label)
-                                .on_hover_text("已持有/忽略")
-                                .clicked()
-                            {
-                                if is_ignored {
-                                    // 取消忽略 → 恢复默认
-                                    self.crafting_source_overrides.remove(&mat_id);
-                                } else {
-                                    self.crafting_source_overrides
-                                        .insert(mat_id, SourceChoice::Ignore);
+    /// 整理导出 HTML 的合成计划: 制作步骤与合成树按当前折叠状态，
+    /// 素材 (已扣除持有数) 按当前来源选择分组
+    fn craft_plan_doc(
+        &self,
+        gs: &GameState,
+        tree: &CraftTreeNode,
+        collapsed: &HashSet<(u32, usize)>,
+        materials: &[(u32, u32)],
+    ) -> CraftPlanDoc {
+        let style = self.config.number_style;
+        let item_name = |id: u32| {
+            gs.item_by_id(id)
+                .map_or("???", |i| i.name.as_str())
+                .to_string()
+        };
+        // 同一图标只编码一次；优先标准尺寸，文件更小
+        let mut icon_cache: HashMap<u32, Option<String>> = HashMap::new();
+        let mut icon = |id: u32| -> Option<String> {
+            let icon_id = gs.item_by_id(id)?.icon_id;
+            icon_cache
+                .entry(icon_id)
+                .or_insert_with(|| {
+                    let tex = gs
+                        .game
+                        .load_icon_variant(icon_id, false)
+                        .or_else(|| gs.game.load_icon(icon_id))?;
+                    encode_icon_png(&tex.rgba, tex.width, tex.height)
+                })
+                .clone()
+        };
+        let job_name = |recipe: &Recipe| CRAFT_TYPE_NAMES[recipe.craft_type.min(7) as usize];
+
+        let stacks = gs
+            .item_by_id(tree.item_id)
+            .map(|i| i.stack_suffix(tree.amount_needed))
+            .unwrap_or_default();
+        let mut meta = vec![format!(
+            "目标数量 {}{}",
+            fmt_count(tree.amount_needed as u64, style),
+            stacks
+        )];
+        if let Some(recipe) = tree.recipe_idx.and_then(|idx| gs.recipes.get(idx)) {
+            meta.push(format!(
+                "{} Lv.{}",
+                job_name(recipe),
+                get_recipe_level(recipe, gs)
+            ));
+        }
+
+        let steps = craft_plan_steps(tree, collapsed, &gs.recipes)
+            .into_iter()
+            .map(|step| PlanStepRow {
+                name: item_name(step.item_id),
+                icon: icon(step.item_id),
+                job: gs
+                    .recipes
+                    .get(step.recipe_idx)
+                    .map(job_name)
+                    .unwrap_or_default()
+                    .to_string(),
+                amount: fmt_count(step.amount as u64, style),
+                crafts: step.crafts,
+            })
+            .collect();
+
+        let groups = self
+            .material_groups(gs, materials)
+            .into_iter()
+            .map(|group| {
+                let rows = group
+                    .items
+                    .iter()
+                    .map(|&(mat_id, amount)| {
+                        let sources = gs
+                            .item_sources
+                            .get(&mat_id)
+                            .map(|v| v.as_slice())
+                            .unwrap_or(&[]);
+                        let cost = match group.key {
+                            MaterialGroupKey::GilShop => {
+                                let price = gs.item_by_id(mat_id).map_or(0, |i| i.price_mid);
+                                fmt_gil(price as u64 * amount as u64, style)
+                            }
+                            MaterialGroupKey::Token(token_id) => {
+                                match resolve_source(
+                                    mat_id,
+                                    sources,
+                                    &self.crafting_source_overrides,
+                                ) {
+                                    Some(ItemSource::SpecialShop { cost_count, .. }) => format!(
+                                        "{} x{}",
+                                        item_name(token_id),
+                                        fmt_count(*cost_count as u64 * amount as u64, style)
+                                    ),
+                                    _ => item_name(token_id),
                                 }
                             }
+                            MaterialGroupKey::Gathering => "采集".to_string(),
+                            MaterialGroupKey::Ignored => "已持有".to_string(),
+                            MaterialGroupKey::NoSource => "-".to_string(),
+                        };
+                        let stacks = gs
+                            .item_by_id(mat_id)
+                            .map(|i| i.stack_suffix(amount))
+                            .unwrap_or_default();
+                        PlanMaterialRow {
+                            name: item_name(mat_id),
+                            icon: icon(mat_id),
+                            amount: format!("{}{}", self.hq_amount_text(mat_id, amount), stacks),
+                            cost,
+                        }
+                    })
+                    .collect();
+                PlanMaterialGroup {
+                    header: material_group_header(&group, gs, style),
+                    rows,
+                }
+            })
+            .collect();
+
+        fn plan_tree(
+            node: &CraftTreeNode,
+            depth: usize,
+            collapsed: &HashSet<(u32, usize)>,
+            gs: &GameState,
+        ) -> PlanTreeNode {
+            let folded = node.unexpanded
+                || (!node.children.is_empty() && collapsed.contains(&(node.item_id, depth)));
+            let note = if node.cycle.is_some() {
+                Some("循环引用")
+            } else if node.truncated {
+                Some("超出深度上限")
+            } else if folded {
+                Some("已折叠")
+            } else {
+                None
+            };
+            PlanTreeNode {
+                name: gs
+                    .item_by_id(node.item_id)
+                    .map_or("???", |i| i.name.as_str())
+                    .to_string(),
+                amount: node.amount_needed,
+                note: note.map(str::to_string),
+                children: if folded {
+                    Vec::new()
+                } else {
+                    node.children
+                        .iter()
+                        .map(|child| plan_tree(child, depth + 1, collapsed, gs))
+                        .collect()
+                },
+            }
+        }
 
-                            // 各来源按钮 (反向遍历因为 right_to_left)
-                            for (i, source) in sources.iter().enumerate().rev() {
-                                let is_active = !is_ignored && active_idx == Some(i);
-                                let btn_text =
-                                    self.source_button_label(source, mat_price, amount, gs);
-                                let rt = if is_active {
-                                    egui::RichText::new(&btn_text).small().strong()
-                                } else {
-                                    egui::RichText::new(&btn_text).small().weak()
-                                };
-                                if ui.selectable_label(is_active, rt).clicked() {
-                                    self.crafting_source_overrides
-                                        .insert(mat_id, SourceChoice::Index(i));
+        CraftPlanDoc {
+            title: item_name(tree.item_id),
+            icon: icon(tree.item_id),
+            meta,
+            steps,
+            groups,
+            tree: plan_tree(tree, 0, collapsed, gs),
+        }
+    }
+
+    /// 选择保存位置并写入 HTML 合成计划，`open_after` 时用系统默认浏览器打开
+    fn export_craft_plan_html(
+        &mut self,
+        gs: &GameState,
+        tree: &CraftTreeNode,
+        collapsed: &HashSet<(u32, usize)>,
+        materials: &[(u32, u32)],
+        open_after: bool,
+    ) {
+        let doc = self.craft_plan_doc(gs, tree, collapsed, materials);
+        let name_ctx = ExportNameContext {
+            item: Some(doc.title.clone()),
+            ..Default::default()
+        };
+        let file_stem =
+            format_export_name(self.config.export_name_template(), &name_ctx, "craft_plan");
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("HTML", &["html"])
+            .set_file_name(format!("{}.html", file_stem))
+            .save_file()
+        else {
+            return;
+        };
+        if let Err(e) = std::fs::write(&path, render_craft_plan_html(&doc)) {
+            self.toasts
+                .push(format!("写入 {} 失败: {}", path.display(), e));
+            return;
+        }
+        self.toasts.push(format!("已导出 {}", path.display()));
+        if open_after {
+            if let Err(e) = open::that(&path) {
+                self.toasts.push(format!("无法打开浏览器: {}", e));
+            }
+        }
+    }
+
+    /// Teamcraft 清单: 合成目标 (物品, 制作数量) + 折叠感知的素材汇总 (已扣除持有数)
+    fn teamcraft_export_text(
+        &mut self,
+        gs: &GameState,
+        root: (u32, u32),
+        materials: &[(u32, u32)],
+        export: TeamcraftExport,
+    ) -> String {
+        let recipe_of = |id: u32| {
+            gs.item_to_recipes
+                .get(&id)
+                .and_then(|r| r.first())
+                .map(|&idx| gs.recipes[idx].row_id)
+        };
+        let root = std::iter::once(root);
+        let items: Vec<(u32, u32)> = match export {
+            TeamcraftExport::Url => root.collect(),
+            TeamcraftExport::Text { .. } => root
+                .chain(materials.iter().copied().filter(|&(_, amount)| amount > 0))
+                .collect(),
+        };
+
+        let english = matches!(export, TeamcraftExport::Text { english: true });
+        if english && self.crafting_english_names.is_none() {
+            self.crafting_english_names = Some(gs.game.load_english_item_names());
+        }
+        let english_names = self
+            .crafting_english_names
+            .as_ref()
+            .filter(|names| english && !names.is_empty());
+        if english && english_names.is_none() {
+            self.toasts.push("客户端不含英文物品名，已使用中文名导出");
+        }
+
+        let entries: Vec<TeamcraftEntry<'_>> = items
+            .iter()
+            .map(|&(item_id, quantity)| {
+                let name = english_names
+                    .and_then(|names| names.get(&item_id))
+                    .map(|s| s.as_str())
+                    .or_else(|| gs.item_by_id(item_id).map(|i| i.name.as_str()))
+                    .unwrap_or("???");
+                TeamcraftEntry {
+                    item_id,
+                    recipe_id: recipe_of(item_id),
+                    name,
+                    quantity,
+                }
+            })
+            .collect();
+        match export {
+            TeamcraftExport::Url => teamcraft::import_url(&entries),
+            TeamcraftExport::Text { .. } => teamcraft::format_text_list(&entries),
+        }
+    }
+
+    /// 素材汇总中的单行: 图标、名称数量、来源选择按钮
+    ///
+    /// 素材是合成树中被折叠的可制作节点时额外提供 "自制" 选项
+    #[allow(clippy::too_many_arguments)]
+    fn show_material_row(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        gs: &GameState,
+        mat_id: u32,
+        amount: u32,
+        order: &[u32],
+        collapsed: &HashSet<(u32, usize)>,
+    ) {
+        let mat_item = gs.item_by_id(mat_id);
+        let (mat_name, mat_icon, mat_price) = mat_item
+            .map(|i| (i.name.as_str(), i.icon_id, i.price_mid))
+            .unwrap_or(("???", 0, 0));
+
+        let sources = gs
+            .item_sources
+            .get(&mat_id)
+            .map(|v| v.as_slice())
+            .unwrap_or(&[]);
+        let is_ignored = matches!(
+            self.crafting_source_overrides.get(&mat_id),
+            Some(SourceChoice::Ignore)
+        );
+        let is_selected = self.crafting_selected_node_item == Some(mat_id);
+        // 该素材在合成树中被折叠的位置 (深度)
+        let collapsed_depths: Vec<usize> = collapsed
+            .iter()
+            .filter(|&&(id, _)| id == mat_id)
+            .map(|&(_, depth)| depth)
+            .collect();
+        let mut craft_self = false;
+
+        // 当前选中的来源索引
+        let current_choice = self.crafting_source_overrides.get(&mat_id).copied();
+        let active_idx = match current_choice {
+            Some(SourceChoice::Index(i)) => Some(i),
+            Some(SourceChoice::Ignore) => None,
+            None => crate::domain::default_source_index(sources),
+        };
+
+        // 背景色
+        let resolved = active_idx.and_then(|i| sources.get(i));
+        let colorblind = self.config.colorblind_friendly;
+        let bg = if is_ignored {
+            None
+        } else {
+            source_bg_color(resolved, ui.visuals(), colorblind)
+        };
+
+        let resp = ui.horizontal(|ui| {
+            // 图标
+            if let Some(icon) = self.get_or_load_icon(ctx, &gs.game, mat_icon) {
+                ui.image(egui::load::SizedTexture::new(
+                    icon.id(),
+                    egui::vec2(18.0, 18.0),
+                ));
+            } else {
+                ui.allocate_space(egui::vec2(18.0, 18.0));
+            }
+
+            // 名称 + 数量 (可点击选中)，部分持有时数量为扣除后的剩余需求
+            let stacks = mat_item.map(|i| i.stack_suffix(amount)).unwrap_or_default();
+            let amount_text = self.hq_amount_text(mat_id, amount);
+            let mut name_text = match self.crafting_owned_amounts.get(&mat_id) {
+                Some(owned) => format!("{} {}{} (已持有 {})", mat_name, amount_text, stacks, owned),
+                None => format!("{} {}{}", mat_name, amount_text, stacks),
+            };
+            // 来源标签，不只依赖背景色区分
+            let tag = source_tag_text(resolved, colorblind);
+            if !is_ignored && !tag.is_empty() {
+                name_text.push_str(&format!(" [{}]", tag));
+            }
+            let rt = if is_ignored {
+                egui::RichText::new(&name_text).strikethrough().weak()
+            } else {
+                egui::RichText::new(&name_text)
+            };
+            if ui.selectable_label(is_selected, rt).clicked() {
+                let modifiers = ui.input(|i| i.modifiers);
+                let selection = &mut self.crafting_material_selection;
+                if modifiers.shift {
+                    selection.select_range(order, mat_id);
+                } else if modifiers.command {
+                    selection.toggle(mat_id);
+                } else {
+                    selection.clear();
+                    selection.anchor = Some(mat_id);
+                    self.crafting_selected_node_item = Some(mat_id);
+                    self.crafting_selected_node_amount = amount;
+                }
+            }
+
+            // 来源选择按钮 (右对齐)
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                // "已持有" 按钮
+                let own_label = if is_ignored {
+                    egui::RichText::new(egui_phosphor::regular::CHECK_CIRCLE).strong()
+                } else {
+                    egui::RichText::new(egui_phosphor::regular::CHECK_CIRCLE).weak()
+                };
+                if ui
+                    .selectable_label(is_ignored, own_label)
+                    .on_hover_text("已持有/忽略")
+                    .clicked()
+                {
+                    if is_ignored {
+                        // 取消忽略 → 恢复默认
+                        self.crafting_source_overrides.remove(&mat_id);
+                    } else {
+                        self.crafting_source_overrides
+                            .insert(mat_id, SourceChoice::Ignore);
+                    }
+                }
+
+                // 各来源按钮 (反向遍历因为 right_to_left)
+                for (i, source) in sources.iter().enumerate().rev() {
+                    let is_active = !is_ignored && active_idx == Some(i);
+                    let (btn_icon, btn_text) =
+                        self.source_button_label(ctx, source, mat_price, amount, gs);
+                    let rt = if is_active {
+                        egui::RichText::new(&btn_text).small().strong()
+                    } else {
+                        egui::RichText::new(&btn_text).small().weak()
+                    };
+                    let resp = match btn_icon {
+                        // 有代币图标时显示图标 + 数量，悬停显示兑换处
+                        Some(icon) => {
+                            let image =
+                                egui::load::SizedTexture::new(icon.id(), egui::vec2(14.0, 14.0));
+                            let resp = ui.add(
+                                egui::Button::image_and_text(image, rt)
+                                    .selected(is_active)
+                                    .frame(is_active),
+                            );
+                            match source {
+                                ItemSource::SpecialShop {
+                                    shop_name,
+                                    npc_name: Some(npc),
+                                    ..
+                                } => resp.on_hover_text(format!("{}\n{}", shop_name, npc)),
+                                ItemSource::SpecialShop { shop_name, .. } => {
+                                    resp.on_hover_text(shop_name)
                                 }
+                                _ => resp,
                             }
-                        });
-                    });
-
-                    if let Some(color) = bg {
-                        ui.painter().rect_filled(resp.response.rect, 2.0, color);
+                        }
+                        None => ui.selectable_label(is_active, rt),
+                    };
+                    if resp.clicked() {
+                        self.crafting_source_overrides
+                            .insert(mat_id, SourceChoice::Index(i));
                     }
                 }
+
+                // "自制": 不改动来源选择，重新折叠后恢复原来的来源
+                if !collapsed_depths.is_empty()
+                    && ui
+                        .selectable_label(false, egui::RichText::new("自制").small().weak())
+                        .on_hover_text("在合成树中展开该节点，改为统计其配方素材")
+                        .clicked()
+                {
+                    craft_self = true;
+                }
             });
+        });
+
+        if craft_self {
+            for &depth in &collapsed_depths {
+                set_craft_node_open(ctx, mat_id, depth, true);
+            }
+            ctx.request_repaint();
+        }
+
+        if let Some(color) = bg {
+            ui.painter().rect_filled(resp.response.rect, 2.0, color);
+        }
+        if self.crafting_material_selection.items.contains(&mat_id) {
+            ui.painter().rect_stroke(
+                resp.response.rect,
+                2.0,
+                ui.visuals().selection.stroke,
+                egui::StrokeKind::Inside,
+            );
+        }
     }
 
-    /// 来源按钮的显示文本
+    /// 来源按钮的图标与显示文本
+    ///
+    /// 兑换来源的代币有图标时返回图标，文本只保留数量；否则使用截短的代币名
     fn source_button_label(
-        &self,
+        &mut self,
+        ctx: &egui::Context,
         source: &ItemSource,
         unit_price: u32,
         amount: u32,
         gs: &GameState,
-    ) -> String {
+    ) -> (Option<egui::TextureHandle>, String) {
         match source {
             ItemSource::GilShop { .. } => {
                 let total = unit_price as u64 * amount as u64;
-                format!("{} {}G", egui_phosphor::regular::COINS, total)
+                (
+                    None,
+                    format!(
+                        "{} {}",
+                        egui_phosphor::regular::COINS,
+                        fmt_gil(total, self.config.number_style)
+                    ),
+                )
             }
             ItemSource::SpecialShop {
                 cost_item_id,
                 cost_count,
                 ..
             } => {
-                let cost_name = gs
+                let cost_item = gs
                     .item_id_map
                     .get(cost_item_id)
-                    .and_then(|&i| gs.all_items.get(i))
-                    .map(|i| i.name.as_str())
-                    .unwrap_or("???");
-                let total = *cost_count as u64 * amount as u64;
+                    .and_then(|&i| gs.all_items.get(i));
+                let total = fmt_count(*cost_count as u64 * amount as u64, self.config.number_style);
+                if let Some(icon) = cost_item
+                    .filter(|i| i.icon_id > 0)
+                    .and_then(|i| self.get_or_load_icon(ctx, &gs.game, i.icon_id))
+                {
+                    return (Some(icon), format!("x{}", total));
+                }
+                let cost_name = cost_item.map(|i| i.name.as_str()).unwrap_or("???");
                 // 截短代币名 (最多6字符)
                 let char_count = cost_name.chars().count();
                 let short_name: String = if char_count > 6 {
@@ -981,11 +2399,12 @@ impl App {
                 } else {
                     cost_name.to_string()
                 };
-                format!("{} {} x{}", egui_phosphor::regular::SWAP, short_name, total)
-            }
-            ItemSource::Gathering => {
-                format!("{} 采集", egui_phosphor::regular::LEAF)
+                (
+                    None,
+                    format!("{} {} x{}", egui_phosphor::regular::SWAP, short_name, total),
+                )
             }
+            ItemSource::Gathering => (None, format!("{} 采集", egui_phosphor::regular::LEAF)),
         }
     }
 }
@@ -0,0 +1,183 @@
+use eframe::egui;
+
+use crate::app::App;
+use crate::loading::{spawn_self_check, GameState};
+
+impl App {
+    pub fn show_diagnostics_window(&mut self, ctx: &egui::Context, gs: &GameState) {
+        // 自检会逐页读取整张表，在后台线程运行，完成前显示进度
+        if let Some(rx) = &self.diagnostics_rx {
+            match rx.try_recv() {
+                Ok(report) => {
+                    self.diagnostics_report = Some(report);
+                    self.diagnostics_rx = None;
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => ctx.request_repaint(),
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.diagnostics_rx = None,
+            }
+        } else if self.diagnostics_report.is_none() {
+            self.diagnostics_rx = Some(spawn_self_check(gs.game.install_dir(), gs.game.is_mock()));
+            ctx.request_repaint();
+        }
+
+        let mut open = self.diagnostics_open;
+        let mut rerun = false;
+        egui::Window::new("自检报告")
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                let Some(report) = &self.diagnostics_report else {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("正在运行自检...");
+                    });
+                    return;
+                };
+
+                if !gs.load_failures.is_empty() {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(230, 160, 60),
+                        format!("加载时为空的数据: {}", gs.load_failures.join(", ")),
+                    );
+                    ui.add_space(4.0);
+                }
+                for summary in &gs.partial_loads {
+                    ui.colored_label(egui::Color32::from_rgb(230, 160, 60), summary);
+                }
+
+                let adapter = crate::gpu::adapter_summary(self.render_state.as_ref());
+                ui.label(format!("图形后端: {}", adapter));
+
+                let failed = report.failed_count();
+                ui.label(format!(
+                    "{}/{} 项通过",
+                    report.checks.len() - failed,
+                    report.checks.len()
+                ));
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(360.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("diagnostics_grid")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for check in &report.checks {
+                                    if check.passed {
+                                        ui.colored_label(
+                                            egui::Color32::from_rgb(90, 190, 90),
+                                            "通过",
+                                        );
+                                    } else {
+                                        ui.colored_label(
+                                            egui::Color32::from_rgb(220, 80, 80),
+                                            "失败",
+                                        );
+                                    }
+                                    ui.label(&check.name);
+                                    ui.label(&check.detail);
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                ui.separator();
+                egui::CollapsingHeader::new("显存占用 (估算)").show(ui, |ui| {
+                    let browser_bytes =
+                        self.viewport.as_ref().map_or(0, |vp| vp.gpu_memory_bytes())
+                            + self
+                                .race_compare
+                                .as_ref()
+                                .map_or(0, |c| c.viewport.gpu_memory_bytes());
+                    let editor_bytes = self
+                        .glamour_editor
+                        .as_ref()
+                        .map_or(0, |e| e.gpu_memory_bytes());
+                    egui::Grid::new("gpu_memory_grid")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            for (name, bytes) in [
+                                ("装备浏览", browser_bytes),
+                                ("幻化编辑器", editor_bytes),
+                                (
+                                    "房屋浏览",
+                                    self.housing_viewport
+                                        .as_ref()
+                                        .map_or(0, |vp| vp.gpu_memory_bytes()),
+                                ),
+                            ] {
+                                ui.label(name);
+                                ui.label(format_mib(bytes));
+                                ui.end_row();
+                            }
+                            ui.label("图标缓存");
+                            ui.label(format!(
+                                "{} / {} 个",
+                                self.icon_cache.len(),
+                                self.icon_cache.capacity
+                            ));
+                            ui.end_row();
+                        });
+                });
+
+                egui::CollapsingHeader::new("文件读取").show(ui, |ui| {
+                    let stats = gs.game.read_stats();
+                    egui::Grid::new("read_stats_grid")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label("数据源读取");
+                            ui.label(format!("{} 次", stats.probes));
+                            ui.end_row();
+                            ui.label("跳过已知缺失");
+                            ui.label(format!("{} 次", stats.skipped));
+                            ui.end_row();
+                            ui.label("已知缺失路径");
+                            ui.label(format!(
+                                "{} / {} 个",
+                                stats.missing,
+                                crate::game::MISSING_PATH_CAPACITY
+                            ));
+                            ui.end_row();
+                        });
+                    if ui
+                        .small_button("清空缺失记录")
+                        .on_hover_text("之后的读取重新访问游戏文件")
+                        .clicked()
+                    {
+                        gs.game.clear_missing_paths();
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("复制报告").clicked() {
+                        let mut text = report.to_text();
+                        text.push_str(&format!("图形后端: {}\n", adapter));
+                        if !gs.load_failures.is_empty() {
+                            text.push_str(&format!(
+                                "加载时为空的数据: {}\n",
+                                gs.load_failures.join(", ")
+                            ));
+                        }
+                        for summary in &gs.partial_loads {
+                            text.push_str(&format!("{}\n", summary));
+                        }
+                        ctx.copy_text(text);
+                    }
+                    if ui.button("重新检查").clicked() {
+                        rerun = true;
+                    }
+                });
+            });
+
+        if rerun {
+            self.diagnostics_report = None;
+        }
+        self.diagnostics_open = open;
+    }
+}
+
+fn format_mib(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
@@ -1,10 +1,40 @@
 use eframe::egui;
 
-use crate::app::App;
+use crate::app::{App, PendingLeave};
+use crate::domain::{EquipSlot, ALL_SLOTS};
 use crate::glamour;
-use crate::glamour::{AppContext, GlamourEditor};
+use crate::glamour::backup::BackupEvent;
+use crate::glamour::import::ImportOutcome;
+use crate::glamour::{AppContext, GlamourEditor, GlamourFilter};
 use crate::loading::{glamour_slot_summary, GameState};
 
+/// 保存时的空装备槽位提示状态
+pub struct EmptySlotWarning {
+    pub slots: Vec<EquipSlot>,
+    pub dont_ask_again: bool,
+}
+
+/// 幻化管理中编辑组合说明与槽位备注的草稿，确定后写回并保存
+pub struct GlamourNotesEdit {
+    pub idx: usize,
+    pub description: String,
+    /// 已填充槽位的备注
+    pub notes: Vec<(EquipSlot, String)>,
+}
+
+impl GlamourNotesEdit {
+    fn new(idx: usize, set: &glamour::GlamourSet) -> Self {
+        Self {
+            idx,
+            description: set.description.clone(),
+            notes: ALL_SLOTS
+                .iter()
+                .filter_map(|&slot| Some((slot, set.get_slot(slot)?.note.clone())))
+                .collect(),
+        }
+    }
+}
+
 impl App {
     pub fn show_glamour_manager_page(&mut self, ctx: &egui::Context, gs: &mut GameState) {
         if let Some(mut editor) = self.glamour_editor.take() {
@@ -18,32 +48,56 @@ impl App {
                 equipment_sets: &gs.equipment_sets,
                 set_id_to_set_idx: &gs.set_id_to_set_idx,
                 icon_cache: &mut self.icon_cache,
+                probed_dye_counts: &gs.probed_dye_counts,
+                toasts: &mut self.toasts,
+                default_preview_stain: self.config.default_preview_stain,
+                export_name_template: self.config.export_name_template(),
+                glamour_sets: &gs.glamour_sets,
+                glamour_item_usage: &gs.glamour_item_usage,
+                editing_idx: self.editing_glamour_idx,
             };
             let action = editor.show(ctx, &mut app_ctx);
             match action {
                 glamour::GlamourEditorAction::Save => {
-                    if let Some(idx) = self.editing_glamour_idx {
-                        gs.glamour_sets[idx] = editor.glamour_set.clone();
-                        if let Err(e) = glamour::save_glamour_set(&gs.glamour_sets[idx]) {
-                            eprintln!("保存失败: {}", e);
-                        }
-                    }
-                    editor.dirty = false;
+                    let empty_slots = editor.glamour_set.empty_gear_slots();
                     self.glamour_editor = Some(editor);
+                    if !empty_slots.is_empty() && !self.config.skip_empty_slot_warning {
+                        self.glamour_empty_slot_warning = Some(EmptySlotWarning {
+                            slots: empty_slots,
+                            dont_ask_again: false,
+                        });
+                    } else {
+                        self.request_glamour_save(gs);
+                    }
                 }
                 glamour::GlamourEditorAction::Close => {
-                    self.glamour_editor = None;
-                    self.editing_glamour_idx = None;
+                    if editor.dirty {
+                        self.glamour_editor = Some(editor);
+                        self.pending_leave = Some(PendingLeave::CloseEditor);
+                    } else {
+                        self.glamour_editor = None;
+                        self.editing_glamour_idx = None;
+                    }
+                }
+                glamour::GlamourEditorAction::OpenSet(idx) => {
+                    self.glamour_editor = Some(editor);
+                    self.open_glamour_request = Some(idx);
                 }
                 glamour::GlamourEditorAction::None => {
                     self.glamour_editor = Some(editor);
                 }
             }
+            if self.glamour_empty_slot_warning.is_some() {
+                self.show_empty_slot_warning(ctx, gs);
+            }
+            if self.glamour_duplicate.is_some() {
+                self.show_glamour_duplicate_dialog(ctx, gs);
+            }
             return;
         }
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("幻化管理");
+            ui.heading(tr!("page.glamour"));
             ui.separator();
 
             ui.horizontal(|ui| {
@@ -51,12 +105,23 @@ impl App {
                 ui.text_edit_singleline(&mut self.new_glamour_name);
                 if ui.button("新建").clicked() && !self.new_glamour_name.trim().is_empty() {
                     let new_gs = glamour::GlamourSet::new(self.new_glamour_name.trim());
-                    if let Err(e) = glamour::save_glamour_set(&new_gs) {
-                        eprintln!("保存失败: {}", e);
+                    match glamour::save_glamour_set(&new_gs) {
+                        Ok(()) => self.glamour_backup.save(&new_gs),
+                        Err(e) => eprintln!("保存失败: {}", e),
                     }
                     gs.glamour_sets.push(new_gs);
+                    gs.revalidate_glamour_sets();
                     self.new_glamour_name.clear();
                 }
+                if ui.button("校验全部").clicked() {
+                    let broken = gs.revalidate_glamour_sets();
+                    if broken > 0 {
+                        self.toasts
+                            .push(format!("{} 个幻化组合存在缺失物品或染料", broken));
+                    } else {
+                        self.toasts.push("全部幻化组合校验通过");
+                    }
+                }
             });
 
             ui.separator();
@@ -66,25 +131,50 @@ impl App {
                 return;
             }
 
+            self.show_glamour_stats(ui, gs);
+            ui.separator();
+
+            if let Some(filter) = self.glamour_filter {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("glamour.filter.active"));
+                    ui.label(egui::RichText::new(glamour_filter_label(filter, gs)).strong());
+                    if ui.small_button(tr!("glamour.filter.clear")).clicked() {
+                        self.glamour_filter = None;
+                    }
+                });
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("搜索:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.glamour_search)
+                        .hint_text("名称、说明或备注"),
+                );
+                if !self.glamour_search.is_empty() && ui.small_button("清除").clicked() {
+                    self.glamour_search.clear();
+                }
+            });
+
             let mut delete_idx: Option<usize> = None;
             let mut edit_idx: Option<usize> = None;
+            let mut start_notes: Option<usize> = None;
+            let mut confirm_notes = false;
             let mut confirm_rename: Option<usize> = None;
             let mut start_rename: Option<(usize, String)> = None;
+            let mut open_issues: Option<String> = None;
 
-            let summaries: Vec<(String, usize, String)> = gs
-                .glamour_sets
-                .iter()
-                .map(|glamour_set| {
-                    (
-                        glamour_set.name.clone(),
-                        glamour_set.slot_count(),
-                        glamour_slot_summary(&gs.all_items, &gs.item_id_map, glamour_set),
-                    )
-                })
+            let filter = self.glamour_filter;
+            let query = self.glamour_search.trim().to_lowercase();
+            let visible: Vec<usize> = (0..gs.glamour_sets.len())
+                .filter(|&i| filter.is_none_or(|f| f.matches(&gs.glamour_sets[i])))
+                .filter(|&i| gs.glamour_sets[i].matches_search(&query))
                 .collect();
+            if visible.is_empty() {
+                ui.weak(tr!("glamour.filter.empty"));
+            }
 
             egui::ScrollArea::vertical().show(ui, |ui| {
-                for i in 0..summaries.len() {
+                for i in visible {
                     ui.horizontal(|ui| {
                         if self.renaming_glamour_idx == Some(i) {
                             ui.text_edit_singleline(&mut self.rename_buffer);
@@ -97,12 +187,32 @@ impl App {
                                 self.renaming_glamour_idx = None;
                             }
                         } else {
-                            let (name, slot_count, slot_summary) = &summaries[i];
+                            // 只为可见的行生成摘要，名称直接引用组合本身
+                            let glamour_set = &gs.glamour_sets[i];
+                            let name = &glamour_set.name;
                             ui.label(egui::RichText::new(name).strong());
-                            ui.label(format!("({}/5 槽位)", slot_count));
+                            ui.label(egui::RichText::new(glamour_set.coverage_text()).weak());
+                            let slot_summary =
+                                glamour_slot_summary(&gs.all_items, &gs.item_id_map, glamour_set);
                             if !slot_summary.is_empty() {
                                 ui.label(slot_summary);
                             }
+                            let badge = gs
+                                .glamour_validation
+                                .get(&glamour_set.id)
+                                .filter(|v| !v.is_ok())
+                                .map(|v| v.badge_text());
+                            if let Some(badge) = badge {
+                                let text = egui::RichText::new(format!(
+                                    "{} {}",
+                                    egui_phosphor::regular::WARNING,
+                                    badge
+                                ))
+                                .color(ui.visuals().warn_fg_color);
+                                if ui.small_button(text).on_hover_text("查看详情").clicked() {
+                                    open_issues = Some(gs.glamour_sets[i].id.clone());
+                                }
+                            }
 
                             ui.with_layout(
                                 egui::Layout::right_to_left(egui::Align::Center),
@@ -113,6 +223,9 @@ impl App {
                                     if ui.small_button("重命名").clicked() {
                                         start_rename = Some((i, name.clone()));
                                     }
+                                    if ui.small_button("说明").clicked() {
+                                        start_notes = Some(i);
+                                    }
                                     if ui.small_button("编辑").clicked() {
                                         edit_idx = Some(i);
                                     }
@@ -120,10 +233,69 @@ impl App {
                             );
                         }
                     });
+                    let editing_notes =
+                        self.glamour_notes_edit.as_ref().is_some_and(|e| e.idx == i);
+                    let description = &gs.glamour_sets[i].description;
+                    if !description.is_empty() && !editing_notes {
+                        ui.label(egui::RichText::new(description).weak());
+                    }
+                    if let Some(edit) = self.glamour_notes_edit.as_mut().filter(|e| e.idx == i) {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut edit.description)
+                                .desired_rows(2)
+                                .desired_width(f32::INFINITY)
+                                .hint_text("组合说明"),
+                        );
+                        egui::Grid::new(("glamour_notes", i))
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                for (slot, note) in &mut edit.notes {
+                                    ui.label(slot.display_name());
+                                    ui.add(
+                                        egui::TextEdit::singleline(note)
+                                            .desired_width(240.0)
+                                            .hint_text("备注"),
+                                    );
+                                    ui.end_row();
+                                }
+                            });
+                        ui.horizontal(|ui| {
+                            if ui.button("确定").clicked() {
+                                confirm_notes = true;
+                            }
+                            if ui.button("取消").clicked() {
+                                self.glamour_notes_edit = None;
+                            }
+                        });
+                    }
                     ui.separator();
                 }
             });
 
+            if let Some(idx) = start_notes {
+                self.glamour_notes_edit = Some(GlamourNotesEdit::new(idx, &gs.glamour_sets[idx]));
+            }
+
+            if confirm_notes {
+                if let Some(edit) = self.glamour_notes_edit.take() {
+                    let set = &mut gs.glamour_sets[edit.idx];
+                    set.description = edit.description.trim_end().to_string();
+                    for (slot, note) in edit.notes {
+                        if let Some(gslot) = set.slots.get_mut(glamour::slot_key_for(slot)) {
+                            gslot.note = note.trim().to_string();
+                        }
+                    }
+                    match glamour::save_glamour_set(set) {
+                        Ok(()) => self.glamour_backup.save(set),
+                        Err(e) => eprintln!("保存失败: {}", e),
+                    }
+                }
+            }
+
+            if open_issues.is_some() {
+                self.glamour_issue_dialog = open_issues;
+            }
+
             if let Some((idx, name)) = start_rename {
                 self.renaming_glamour_idx = Some(idx);
                 self.rename_buffer = name;
@@ -133,8 +305,9 @@ impl App {
                 let new_name = self.rename_buffer.trim().to_string();
                 if !new_name.is_empty() {
                     gs.glamour_sets[idx].name = new_name;
-                    if let Err(e) = glamour::save_glamour_set(&gs.glamour_sets[idx]) {
-                        eprintln!("保存失败: {}", e);
+                    match glamour::save_glamour_set(&gs.glamour_sets[idx]) {
+                        Ok(()) => self.glamour_backup.save(&gs.glamour_sets[idx]),
+                        Err(e) => eprintln!("保存失败: {}", e),
                     }
                 }
                 self.renaming_glamour_idx = None;
@@ -145,10 +318,13 @@ impl App {
                 if let Err(e) = glamour::delete_glamour_set(&id) {
                     eprintln!("删除失败: {}", e);
                 }
+                self.glamour_backup.delete(&id);
                 gs.glamour_sets.remove(idx);
+                gs.revalidate_glamour_sets();
                 if self.renaming_glamour_idx == Some(idx) {
                     self.renaming_glamour_idx = None;
                 }
+                self.glamour_notes_edit = None;
             }
 
             if let Some(idx) = edit_idx {
@@ -158,5 +334,821 @@ impl App {
                 self.editing_glamour_idx = Some(idx);
             }
         });
+
+        if self.glamour_issue_dialog.is_some() {
+            self.show_glamour_issue_dialog(ctx, gs);
+        }
+        if self.glamour_duplicate.is_some() {
+            self.show_glamour_duplicate_dialog(ctx, gs);
+        }
+    }
+
+    /// 依次导入拖放到窗口的文件，成功导入任意组合后跳转到幻化管理
+    pub fn import_dropped_glamour_files(&mut self, files: &[egui::DroppedFile], gs: &mut GameState) {
+        let mut outcomes = Vec::new();
+        for file in files {
+            let path = file
+                .path
+                .clone()
+                .unwrap_or_else(|| std::path::PathBuf::from(&file.name));
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| file.name.clone());
+            let bytes = match (&file.bytes, &file.path) {
+                (Some(bytes), _) => Ok(bytes.to_vec()),
+                (None, Some(path)) => std::fs::read(path).map_err(|e| format!("读取失败: {}", e)),
+                (None, None) => Err("无法读取文件内容".to_string()),
+            };
+            let result = bytes
+                .and_then(|bytes| glamour::import::import_file(&path, &bytes, &gs.all_items))
+                .and_then(|(set, mut warnings)| {
+                    // 与已保存组合重复时同快速新建一样交给确认框，一次只能询问一个
+                    if let Some(duplicate) = glamour::find_duplicate(&set, &gs.glamour_sets) {
+                        let existing = gs
+                            .glamour_sets
+                            .iter()
+                            .find(|s| duplicate.set_ids.first() == Some(&s.id))
+                            .map(|s| s.name.clone())
+                            .unwrap_or_default();
+                        if self.glamour_duplicate.is_some() {
+                            return Err(format!("与已保存的组合「{}」重复，未导入", existing));
+                        }
+                        self.glamour_duplicate = Some(duplicate);
+                        self.glamour_quick_add = Some(set);
+                        return Err(format!(
+                            "与已保存的组合「{}」重复，请在确认框中选择",
+                            existing
+                        ));
+                    }
+                    glamour::save_glamour_set(&set)?;
+                    self.glamour_backup.save(&set);
+                    let validation =
+                        glamour::validate_glamour_set(&set, &gs.item_id_map, &gs.stains);
+                    if !validation.is_ok() {
+                        warnings.push(validation.badge_text());
+                    }
+                    let name = set.name.clone();
+                    gs.glamour_sets.push(set);
+                    Ok((name, warnings))
+                });
+            outcomes.push(ImportOutcome { file_name, result });
+        }
+
+        let imported = outcomes.iter().filter(|o| o.result.is_ok()).count();
+        let failed = outcomes.len() - imported;
+        if imported > 0 {
+            gs.revalidate_glamour_sets();
+        }
+        if imported > 0 || self.glamour_quick_add.is_some() {
+            self.current_page = crate::domain::AppPage::GlamourManager;
+        }
+        // 单个文件导入失败只提示，不弹出结果窗口
+        if let [ImportOutcome {
+            file_name,
+            result: Err(e),
+        }] = outcomes.as_slice()
+        {
+            self.toasts.push(format!("无法导入 {}: {}", file_name, e));
+            return;
+        }
+        if failed == 0 {
+            self.toasts.push(format!("已导入 {} 个幻化组合", imported));
+        } else {
+            self.toasts.push(format!(
+                "已导入 {} 个幻化组合，{} 个文件失败",
+                imported, failed
+            ));
+        }
+        self.glamour_import_report = Some(outcomes);
+    }
+
+    /// 拖放导入的逐文件结果
+    pub fn show_glamour_import_dialog(&mut self, ctx: &egui::Context) {
+        let Some(outcomes) = &self.glamour_import_report else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("导入结果")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("glamour_import_report")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for outcome in outcomes {
+                            ui.label(&outcome.file_name);
+                            match &outcome.result {
+                                Ok((name, warnings)) => {
+                                    ui.vertical(|ui| {
+                                        ui.label(format!("已导入为新组合「{}」", name));
+                                        for warning in warnings {
+                                            ui.label(
+                                                egui::RichText::new(warning)
+                                                    .small()
+                                                    .color(ui.visuals().warn_fg_color),
+                                            );
+                                        }
+                                    });
+                                }
+                                Err(e) => {
+                                    ui.colored_label(ui.visuals().error_fg_color, e);
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        if !open {
+            self.glamour_import_report = None;
+        }
+    }
+
+    /// 保存编辑器内容，与其他已保存组合重复时先询问
+    fn request_glamour_save(&mut self, gs: &mut GameState) {
+        let Some(editor) = &self.glamour_editor else {
+            return;
+        };
+        let duplicate = glamour::find_duplicate(&editor.glamour_set, &gs.glamour_sets);
+        if duplicate.is_some() {
+            self.glamour_duplicate = duplicate;
+        } else if let Err(e) = self.save_glamour_editor(gs) {
+            eprintln!("保存失败: {}", e);
+        }
+    }
+
+    /// 保存浏览页快速新建的组合，与已保存组合重复时先询问
+    pub fn request_glamour_quick_add(&mut self, gs: &mut GameState, set: glamour::GlamourSet) {
+        if let Some(duplicate) = glamour::find_duplicate(&set, &gs.glamour_sets) {
+            self.glamour_duplicate = Some(duplicate);
+            self.glamour_quick_add = Some(set);
+        } else if let Err(e) = self.save_new_glamour(gs, set) {
+            self.toasts.push(format!("保存失败: {}", e));
+        }
+    }
+
+    /// 将新组合写入磁盘并加入列表
+    fn save_new_glamour(
+        &mut self,
+        gs: &mut GameState,
+        set: glamour::GlamourSet,
+    ) -> Result<(), String> {
+        glamour::save_glamour_set(&set)?;
+        self.glamour_backup.save(&set);
+        self.toasts.push(format!("已新建幻化组合「{}」", set.name));
+        gs.glamour_sets.push(set);
+        gs.revalidate_glamour_sets();
+        Ok(())
+    }
+
+    /// 用快速新建的组合的槽位覆盖重复的组合
+    fn overwrite_glamour_slots(
+        &mut self,
+        gs: &mut GameState,
+        target_id: &str,
+        set: glamour::GlamourSet,
+    ) -> Result<(), String> {
+        let Some(target) = gs.glamour_sets.iter_mut().find(|s| s.id == target_id) else {
+            return Ok(());
+        };
+        target.slots = set.slots;
+        target.touch();
+        glamour::save_glamour_set(target)?;
+        self.glamour_backup.save(target);
+        self.toasts
+            .push(format!("已覆盖幻化组合「{}」", target.name));
+        gs.revalidate_glamour_sets();
+        Ok(())
+    }
+
+    /// 空装备槽位提示 (非模态)：仍然保存 / 取消，可选不再提示
+    fn show_empty_slot_warning(&mut self, ctx: &egui::Context, gs: &mut GameState) {
+        let Some(warning) = &mut self.glamour_empty_slot_warning else {
+            return;
+        };
+        let mut save = false;
+        let mut cancel = false;
+        egui::Window::new("存在空装备槽位")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                let names: Vec<&str> = warning.slots.iter().map(|s| s.display_name()).collect();
+                ui.label(format!("以下槽位为空: {}", names.join("、")));
+                ui.checkbox(&mut warning.dont_ask_again, "不再提示");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("仍然保存").clicked() {
+                        save = true;
+                    }
+                    if ui.button("取消").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        if !save && !cancel {
+            return;
+        }
+        if save && warning.dont_ask_again {
+            self.config.skip_empty_slot_warning = true;
+            self.schedule_config_save(ctx);
+        }
+        self.glamour_empty_slot_warning = None;
+        if save {
+            self.request_glamour_save(gs);
+        }
+    }
+
+    /// 将编辑器内容写回对应组合、保存到磁盘并重新校验
+    /// (编辑器的保存按钮与未保存修改确认框共用)
+    pub fn save_glamour_editor(&mut self, gs: &mut GameState) -> Result<(), String> {
+        let Some(editor) = self.glamour_editor.as_mut() else {
+            return Ok(());
+        };
+        if let Some(idx) = self.editing_glamour_idx {
+            editor.glamour_set.touch();
+            gs.glamour_sets[idx] = editor.glamour_set.clone();
+            let result = glamour::save_glamour_set(&gs.glamour_sets[idx]);
+            if result.is_ok() {
+                self.glamour_backup.save(&gs.glamour_sets[idx]);
+            }
+            gs.revalidate_glamour_sets();
+            result?;
+        }
+        editor.dirty = false;
+        self.discard_glamour_autosave();
+        // 与组合一同落盘尚未写入的配置 (如不再提示空槽位)
+        self.flush_config();
+        Ok(())
+    }
+
+    /// 编辑器有未保存修改时按设置的间隔写入恢复文件，内容与上次自动保存相同时跳过
+    ///
+    /// 自动保存不修改组合本身，编辑器仍保持未保存状态。
+    pub fn autosave_glamour_editor(&mut self, ctx: &egui::Context) {
+        // 崩溃时由 panic 钩子写入未保存的修改，不受自动保存间隔限制
+        crate::crash::set_recovery_snapshot(
+            self.glamour_editor
+                .as_ref()
+                .filter(|e| e.dirty)
+                .map(|e| e.recovery_snapshot()),
+        );
+        let Some(editor) = &self.glamour_editor else {
+            self.glamour_autosave_due = None;
+            return;
+        };
+        if !editor.dirty {
+            self.glamour_autosave_due = None;
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let interval = self.config.glamour_autosave_interval() as f64;
+        let Some(due) = self.glamour_autosave_due else {
+            self.glamour_autosave_due = Some(now + interval);
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(interval));
+            return;
+        };
+        if now < due {
+            return;
+        }
+        self.glamour_autosave_due = None;
+        let recovery = editor.recovery_snapshot();
+        let hash = recovery.content_hash();
+        if self.glamour_autosave_hash == Some(hash) {
+            return;
+        }
+        match glamour::save_recovery(&recovery) {
+            Ok(()) => self.glamour_autosave_hash = Some(hash),
+            Err(e) => eprintln!("自动保存失败: {}", e),
+        }
+    }
+
+    /// 保存或放弃修改后删除恢复文件
+    pub fn discard_glamour_autosave(&mut self) {
+        glamour::delete_recovery();
+        self.glamour_autosave_due = None;
+        self.glamour_autosave_hash = None;
+    }
+
+    /// 打开启动时发现的自动保存
+    pub fn restore_glamour_recovery(&mut self) {
+        if let Some((idx, recovery)) = self.glamour_recovery_offer.take() {
+            self.glamour_editor = Some(GlamourEditor::from_recovery(
+                recovery,
+                self.render_state.clone(),
+            ));
+            self.editing_glamour_idx = Some(idx);
+            self.current_page = crate::domain::AppPage::GlamourManager;
+        }
+    }
+
+    /// 启动时发现自动保存: 恢复到编辑器 / 放弃
+    pub fn show_glamour_recovery_dialog(&mut self, ctx: &egui::Context, gs: &GameState) {
+        let Some((idx, recovery)) = &self.glamour_recovery_offer else {
+            return;
+        };
+        let idx = *idx;
+        let last_saved = gs.glamour_sets.get(idx).map(|s| s.modified_at).unwrap_or(0);
+
+        let mut restore = false;
+        let mut discard = false;
+        egui::Modal::new(egui::Id::new("glamour_recovery")).show(ctx, |ui| {
+            ui.heading(tr!("recovery.title"));
+            ui.label(tr!("recovery.message"));
+            ui.label(egui::RichText::new(&recovery.set.name).strong());
+            ui.label(format!(
+                "{}: {}",
+                tr!("recovery.autosaved_at"),
+                glamour::elapsed_text(recovery.saved_at)
+            ));
+            ui.label(format!(
+                "{}: {}",
+                tr!("recovery.last_saved_at"),
+                if last_saved > 0 {
+                    glamour::elapsed_text(last_saved)
+                } else {
+                    tr!("recovery.never_saved").to_string()
+                }
+            ));
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button(tr!("recovery.restore")).clicked() {
+                    restore = true;
+                }
+                if ui
+                    .button(tr!("recovery.discard"))
+                    .on_hover_text(tr!("recovery.discard_hint"))
+                    .clicked()
+                {
+                    discard = true;
+                }
+            });
+        });
+
+        if restore {
+            self.restore_glamour_recovery();
+        } else if discard {
+            self.glamour_recovery_offer = None;
+            self.discard_glamour_autosave();
+        }
+    }
+
+    /// 取回备份文件夹的同步结果与写入错误，后台仍有任务时继续刷新
+    pub fn poll_glamour_backup(&mut self, ctx: &egui::Context) {
+        let events: Vec<BackupEvent> = self.glamour_backup.events().collect();
+        for event in events {
+            match event {
+                BackupEvent::Error(e) => self.toasts.push(format!("幻化组合备份失败: {}", e)),
+                BackupEvent::Synced {
+                    exported,
+                    candidates,
+                } => {
+                    if exported > 0 {
+                        self.toasts
+                            .push(format!("已将 {} 个幻化组合写入备份文件夹", exported));
+                    }
+                    if candidates.is_empty() {
+                        continue;
+                    }
+                    let offer = self.glamour_backup_offer.get_or_insert_with(Vec::new);
+                    for candidate in candidates {
+                        offer.retain(|(c, _)| c.set.id != candidate.set.id);
+                        offer.push((candidate, true));
+                    }
+                }
+            }
+        }
+        if self.glamour_backup.is_busy() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+    }
+
+    /// 备份文件夹中有较新或本地没有的组合: 勾选后导入，覆盖本地同一组合
+    pub fn show_glamour_backup_dialog(&mut self, ctx: &egui::Context, gs: &mut GameState) {
+        let Some(offer) = &mut self.glamour_backup_offer else {
+            return;
+        };
+
+        let mut import = false;
+        let mut skip = false;
+        egui::Modal::new(egui::Id::new("glamour_backup")).show(ctx, |ui| {
+            ui.heading(tr!("backup.title"));
+            ui.label(tr!("backup.message"));
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(320.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("glamour_backup_candidates")
+                        .num_columns(3)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (candidate, selected) in offer.iter_mut() {
+                                ui.checkbox(selected, &candidate.set.name);
+                                ui.label(format!(
+                                    "{}: {}",
+                                    tr!("backup.backup_time"),
+                                    glamour::elapsed_text(candidate.backup_modified)
+                                ));
+                                let local = match candidate.local_modified {
+                                    None => tr!("backup.local_missing").to_string(),
+                                    Some(0) => format!(
+                                        "{}: {}",
+                                        tr!("backup.local_time"),
+                                        tr!("recovery.never_saved")
+                                    ),
+                                    Some(t) => format!(
+                                        "{}: {}",
+                                        tr!("backup.local_time"),
+                                        glamour::elapsed_text(t)
+                                    ),
+                                };
+                                ui.label(local);
+                                ui.end_row();
+                            }
+                        });
+                });
+            ui.separator();
+            ui.horizontal(|ui| {
+                let any_selected = offer.iter().any(|(_, selected)| *selected);
+                if ui
+                    .add_enabled(any_selected, egui::Button::new(tr!("backup.import")))
+                    .clicked()
+                {
+                    import = true;
+                }
+                if ui.button(tr!("backup.skip")).clicked() {
+                    skip = true;
+                }
+            });
+        });
+
+        if skip {
+            self.glamour_backup_offer = None;
+        }
+        if !import {
+            return;
+        }
+        let Some(offer) = self.glamour_backup_offer.take() else {
+            return;
+        };
+        let editing_id = self
+            .editing_glamour_idx
+            .filter(|_| self.glamour_editor.is_some())
+            .and_then(|idx| gs.glamour_sets.get(idx))
+            .map(|s| s.id.clone());
+        let mut imported = 0;
+        for (candidate, _) in offer.into_iter().filter(|(_, selected)| *selected) {
+            let set = candidate.set;
+            // 正在编辑的组合不覆盖，避免编辑器内容与列表不一致
+            if editing_id.as_deref() == Some(set.id.as_str()) {
+                self.toasts
+                    .push(format!("「{}」正在编辑，未从备份导入", set.name));
+                continue;
+            }
+            if let Err(e) = glamour::save_glamour_set(&set) {
+                self.toasts.push(format!("导入「{}」失败: {}", set.name, e));
+                continue;
+            }
+            match gs.glamour_sets.iter_mut().find(|s| s.id == set.id) {
+                Some(existing) => *existing = set,
+                None => gs.glamour_sets.push(set),
+            }
+            imported += 1;
+        }
+        if imported > 0 {
+            gs.revalidate_glamour_sets();
+            self.toasts
+                .push(format!("已从备份导入 {} 个幻化组合", imported));
+        }
+    }
+
+    /// 重复组合确认框: 覆盖原有 / 另存为新 / 取消
+    pub fn show_glamour_duplicate_dialog(&mut self, ctx: &egui::Context, gs: &mut GameState) {
+        let Some(duplicate) = self.glamour_duplicate.clone() else {
+            return;
+        };
+        let matches: Vec<&glamour::GlamourSet> = duplicate
+            .set_ids
+            .iter()
+            .filter_map(|id| gs.glamour_sets.iter().find(|s| &s.id == id))
+            .collect();
+        let Some(target) = matches.first() else {
+            self.glamour_duplicate = None;
+            self.glamour_quick_add = None;
+            return;
+        };
+        let target_id = target.id.clone();
+        let target_name = target.name.clone();
+
+        let mut overwrite = false;
+        let mut save_new = false;
+        let mut cancel = false;
+        let modal = egui::Modal::new(egui::Id::new("glamour_duplicate")).show(ctx, |ui| {
+            ui.heading(tr!("glamour.duplicate.title"));
+            ui.label(if duplicate.with_dyes {
+                tr!("glamour.duplicate.same_dyes")
+            } else {
+                tr!("glamour.duplicate.same_items")
+            });
+            for set in &matches {
+                ui.label(egui::RichText::new(&set.name).strong());
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui
+                    .button(tr!("glamour.duplicate.overwrite"))
+                    .on_hover_text(format!(
+                        "{}「{}」",
+                        tr!("glamour.duplicate.overwrite_hint"),
+                        target_name
+                    ))
+                    .clicked()
+                {
+                    overwrite = true;
+                }
+                if ui.button(tr!("glamour.duplicate.save_new")).clicked() {
+                    save_new = true;
+                }
+                if ui.button(tr!("button.cancel")).clicked() {
+                    cancel = true;
+                }
+            });
+        });
+
+        let quick_add = if overwrite || save_new || cancel || modal.should_close() {
+            self.glamour_quick_add.take()
+        } else {
+            return;
+        };
+        let result = match (quick_add, overwrite, save_new) {
+            (Some(set), true, _) => self.overwrite_glamour_slots(gs, &target_id, set),
+            (Some(set), _, true) => self.save_new_glamour(gs, set),
+            (None, true, _) => self.overwrite_duplicate_glamour(gs, &target_id),
+            (None, _, true) => self.save_glamour_editor(gs),
+            _ => Ok(()),
+        };
+        self.glamour_duplicate = None;
+        if let Err(e) = result {
+            eprintln!("保存失败: {}", e);
+            self.toasts.push(format!("保存失败: {}", e));
+        }
+    }
+
+    /// 用编辑器内容覆盖重复的组合，并删除当前编辑的组合，编辑器改为编辑被覆盖的组合
+    fn overwrite_duplicate_glamour(
+        &mut self,
+        gs: &mut GameState,
+        target_id: &str,
+    ) -> Result<(), String> {
+        let Some(editor) = self.glamour_editor.as_mut() else {
+            return Ok(());
+        };
+        let Some(target_idx) = gs.glamour_sets.iter().position(|s| s.id == target_id) else {
+            return Ok(());
+        };
+        gs.glamour_sets[target_idx].slots = editor.glamour_set.slots.clone();
+        let saved = glamour::save_glamour_set(&gs.glamour_sets[target_idx]);
+        if saved.is_ok() {
+            self.glamour_backup.save(&gs.glamour_sets[target_idx]);
+            if let Some(idx) = self.editing_glamour_idx {
+                if let Err(e) = glamour::delete_glamour_set(&gs.glamour_sets[idx].id) {
+                    eprintln!("删除失败: {}", e);
+                }
+                self.glamour_backup.delete(&gs.glamour_sets[idx].id);
+                gs.glamour_sets.remove(idx);
+            }
+            let target_idx = gs.glamour_sets.iter().position(|s| s.id == target_id);
+            if let Some(idx) = target_idx {
+                editor.glamour_set = gs.glamour_sets[idx].clone();
+                editor.dirty = false;
+            }
+            self.editing_glamour_idx = target_idx;
+            self.discard_glamour_autosave();
+        }
+        gs.revalidate_glamour_sets();
+        saved
+    }
+
+    /// 统计面板: 染料使用情况与各槽位常用物品，点击条目筛选下方列表
+    fn show_glamour_stats(&mut self, ui: &mut egui::Ui, gs: &GameState) {
+        let stats = &gs.glamour_stats;
+        let total = gs.glamour_sets.len();
+        let mut clicked: Option<GlamourFilter> = None;
+
+        egui::CollapsingHeader::new(tr!("glamour.stats"))
+            .id_salt("glamour_stats")
+            .show(ui, |ui| {
+                ui.label(format!(
+                    "{} {}/{}",
+                    tr!("glamour.stats.incomplete"),
+                    stats.incomplete_sets,
+                    total
+                ));
+
+                ui.label(egui::RichText::new(tr!("glamour.stats.dyes")).strong());
+                if stats.dye_usage.is_empty() {
+                    ui.weak(tr!("glamour.stats.no_dyes"));
+                } else {
+                    egui::ScrollArea::vertical()
+                        .id_salt("glamour_stats_dyes")
+                        .max_height(STATS_LIST_HEIGHT)
+                        .show(ui, |ui| {
+                            egui::Grid::new("glamour_stats_dye_grid")
+                                .num_columns(3)
+                                .show(ui, |ui| {
+                                    for &(stain_id, count) in &stats.dye_usage {
+                                        let stain = gs.stains.iter().find(|s| s.id == stain_id);
+                                        let name = stain
+                                            .map(|s| s.name.clone())
+                                            .unwrap_or_else(|| format!("#{}", stain_id));
+                                        let swatch = stain.map(|s| {
+                                            egui::Color32::from_rgb(
+                                                s.color[0], s.color[1], s.color[2],
+                                            )
+                                        });
+                                        let filter = GlamourFilter::Dye(stain_id);
+                                        if stat_row(
+                                            ui,
+                                            swatch,
+                                            &name,
+                                            count,
+                                            total,
+                                            self.glamour_filter == Some(filter),
+                                        ) {
+                                            clicked = Some(filter);
+                                        }
+                                    }
+                                });
+                        });
+                }
+
+                ui.label(egui::RichText::new(tr!("glamour.stats.items")).strong());
+                egui::Grid::new("glamour_stats_item_grid")
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        for (slot, items) in &stats.slot_items {
+                            for &(item_id, count) in items {
+                                let name = gs
+                                    .item_id_map
+                                    .get(&item_id)
+                                    .and_then(|&idx| gs.all_items.get(idx))
+                                    .map(|item| item.name.as_str())
+                                    .unwrap_or("???");
+                                let label = format!("[{}] {}", slot.slot_abbr(), name);
+                                let filter = GlamourFilter::Item(item_id);
+                                if stat_row(
+                                    ui,
+                                    None,
+                                    &label,
+                                    count,
+                                    total,
+                                    self.glamour_filter == Some(filter),
+                                ) {
+                                    clicked = Some(filter);
+                                }
+                            }
+                        }
+                    });
+            });
+
+        if let Some(filter) = clicked {
+            // 再次点击当前筛选条目即取消
+            self.glamour_filter = if self.glamour_filter == Some(filter) {
+                None
+            } else {
+                Some(filter)
+            };
+        }
+    }
+
+    /// 校验问题详情: 列出问题槽位，仅在用户确认后清除
+    fn show_glamour_issue_dialog(&mut self, ctx: &egui::Context, gs: &mut GameState) {
+        let Some(set_id) = self.glamour_issue_dialog.clone() else {
+            return;
+        };
+        let Some(set_idx) = gs.glamour_sets.iter().position(|s| s.id == set_id) else {
+            self.glamour_issue_dialog = None;
+            return;
+        };
+        let issues = gs
+            .glamour_validation
+            .get(&set_id)
+            .map(|v| v.issues.clone())
+            .unwrap_or_default();
+
+        let mut open = true;
+        let mut clear: Vec<glamour::SlotIssue> = Vec::new();
+        egui::Window::new(format!("校验结果 - {}", gs.glamour_sets[set_idx].name))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if issues.is_empty() {
+                    ui.label("所有槽位均正常");
+                    return;
+                }
+                egui::Grid::new("glamour_issues")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for issue in &issues {
+                            ui.label(issue.slot.display_name());
+                            let mut problems = Vec::new();
+                            if issue.missing_item {
+                                problems.push(format!("物品 #{} 已不存在", issue.item_id));
+                            }
+                            for stain_id in &issue.missing_stains {
+                                problems.push(format!("染料 #{} 已不存在", stain_id));
+                            }
+                            ui.label(problems.join(", "));
+                            let hint = if issue.missing_item {
+                                "清空该槽位"
+                            } else {
+                                "移除缺失的染料"
+                            };
+                            if ui.small_button("清除").on_hover_text(hint).clicked() {
+                                clear.push(issue.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+                ui.separator();
+                if ui.button("全部清除").clicked() {
+                    clear = issues.clone();
+                }
+            });
+
+        if !clear.is_empty() {
+            let set = &mut gs.glamour_sets[set_idx];
+            for issue in &clear {
+                set.clear_issue(issue);
+            }
+            match glamour::save_glamour_set(set) {
+                Ok(()) => self.glamour_backup.save(set),
+                Err(e) => eprintln!("保存失败: {}", e),
+            }
+            gs.revalidate_glamour_sets();
+        }
+        if !open {
+            self.glamour_issue_dialog = None;
+        }
+    }
+}
+
+/// 统计列表的最大高度
+const STATS_LIST_HEIGHT: f32 = 160.0;
+/// 统计比例条宽度
+const STATS_BAR_WIDTH: f32 = 120.0;
+
+/// 统计表格的一行: (色块 +) 名称 / 比例条 / 组合数，返回名称是否被点击
+fn stat_row(
+    ui: &mut egui::Ui,
+    swatch: Option<egui::Color32>,
+    label: &str,
+    count: usize,
+    total: usize,
+    selected: bool,
+) -> bool {
+    let clicked = ui
+        .horizontal(|ui| {
+            if let Some(color) = swatch {
+                let (rect, _) =
+                    ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+                ui.painter().rect_filled(rect, 2.0, color);
+            }
+            ui.selectable_label(selected, label).clicked()
+        })
+        .inner;
+
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(STATS_BAR_WIDTH, 8.0), egui::Sense::hover());
+    ui.painter()
+        .rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    let mut fill = rect;
+    fill.set_width(rect.width() * count as f32 / total.max(1) as f32);
+    let fill_color = swatch.unwrap_or(ui.visuals().selection.bg_fill);
+    ui.painter().rect_filled(fill, 2.0, fill_color);
+
+    ui.label(count.to_string());
+    ui.end_row();
+    clicked
+}
+
+/// 当前筛选条件的显示名称
+fn glamour_filter_label(filter: GlamourFilter, gs: &GameState) -> String {
+    match filter {
+        GlamourFilter::Dye(id) => gs
+            .stains
+            .iter()
+            .find(|s| s.id == id)
+            .map(|s| s.name.clone())
+            .unwrap_or_else(|| format!("#{}", id)),
+        GlamourFilter::Item(id) => gs
+            .item_id_map
+            .get(&id)
+            .and_then(|&idx| gs.all_items.get(idx))
+            .map(|item| item.name.clone())
+            .unwrap_or_else(|| format!("#{}", id)),
     }
 }
@@ -4,16 +4,30 @@ use eframe::egui;
 use physis::stm::StainingTemplate;
 
 use crate::app::App;
-use crate::domain::{GameItem, HousingSubTab, ViewMode, EXTERIOR_PART_TYPES, HOUSING_SUB_TABS};
+use crate::domain::{
+    ExteriorPartType, GameItem, HousingSize, HousingSubTab, ViewMode, EXTERIOR_PART_TYPES,
+    HOUSING_SIZES, HOUSING_SUB_TABS,
+};
 use crate::dye;
 use crate::game::{
-    bake_color_table_texture, compute_bounding_box, extract_mdl_paths_from_sgb,
-    load_housing_mesh_textures, load_mdl, MeshData,
+    compute_bounding_box, extract_mdl_paths_from_sgb, load_housing_mesh_textures, load_mdl,
+    recenter_meshes, MeshData,
 };
 use crate::loading::GameState;
 use crate::ui::components::dye_palette;
 use crate::ui::components::item_detail::{self, ItemDetailConfig};
 use crate::ui::components::item_list::{self, DisplayItem};
+use crate::ui::components::viewport;
+
+/// 房屋列表筛选缓存键: (搜索词, 子标签, 外装类型, 物品分类, 尺寸, 下标列表标识)
+pub type HousingFilterKey = (
+    String,
+    HousingSubTab,
+    Option<ExteriorPartType>,
+    Option<u8>,
+    Option<HousingSize>,
+    (usize, usize),
+);
 
 impl App {
     pub fn show_housing_page(&mut self, ctx: &egui::Context, gs: &mut GameState) {
@@ -28,7 +42,7 @@ impl App {
         egui::SidePanel::left("housing_list")
             .default_width(350.0)
             .show(ctx, |ui| {
-                ui.heading("房屋浏览器");
+                ui.heading(tr!("heading.housing"));
                 ui.separator();
 
                 // 子标签切换
@@ -46,10 +60,11 @@ impl App {
 
                 // 切换子标签时清除选中
                 if self.housing_sub_tab != prev_sub_tab {
-                    self.housing_selected_item = None;
-                    self.housing_loaded_model_idx = None;
+                    self.housing_selected_item_id = None;
+                    self.housing_loaded_model_id = None;
                     self.housing_selected_part_type = None;
                     self.housing_selected_ui_category = None;
+                    self.housing_selected_size = None;
                 }
 
                 ui.separator();
@@ -67,7 +82,10 @@ impl App {
                         // 外装: 用 ExteriorPartType 筛选
                         ui.horizontal_wrapped(|ui| {
                             if ui
-                                .selectable_label(self.housing_selected_part_type.is_none(), "全部")
+                                .selectable_label(
+                                    self.housing_selected_part_type.is_none(),
+                                    "全部类型",
+                                )
                                 .clicked()
                             {
                                 self.housing_selected_part_type = None;
@@ -128,45 +146,116 @@ impl App {
                     }
                 }
 
+                // 尺寸筛选: 仅在当前列表有尺寸数据时显示
+                let mut size_counts: HashMap<HousingSize, usize> = HashMap::new();
+                for &idx in indices {
+                    *size_counts
+                        .entry(gs.housing_size(&gs.all_items[idx]))
+                        .or_default() += 1;
+                }
+                if size_counts.keys().any(|s| *s != HousingSize::Unknown) {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("尺寸:");
+                        if ui
+                            .selectable_label(self.housing_selected_size.is_none(), "全部")
+                            .clicked()
+                        {
+                            self.housing_selected_size = None;
+                        }
+                        for size in &HOUSING_SIZES {
+                            let count = size_counts.get(size).copied().unwrap_or(0);
+                            if count == 0 {
+                                continue;
+                            }
+                            let label = format!("{}({})", size.short_name(), count);
+                            if ui
+                                .selectable_label(self.housing_selected_size == Some(*size), label)
+                                .clicked()
+                            {
+                                self.housing_selected_size = Some(*size);
+                            }
+                        }
+                    });
+                    ui.separator();
+                }
+
                 // 搜索框 + 视图模式 + 图标大小
                 self.housing_list.show_controls(ui);
 
+                // 筛选条件不变时复用上次结果
                 let search_lower = self.housing_list.search_lower();
-                let filtered: Vec<(usize, &GameItem)> = indices
-                    .iter()
-                    .filter_map(|&idx| {
-                        let item = &gs.all_items[idx];
-                        // 外装类型筛选
-                        if self.housing_sub_tab == HousingSubTab::Exterior {
-                            if let Some(pt) = self.housing_selected_part_type {
-                                if item.exterior_part_type() != Some(pt) {
-                                    return None;
+                let sub_tab = self.housing_sub_tab;
+                let part_type = self.housing_selected_part_type;
+                let ui_category = self.housing_selected_ui_category;
+                let selected_size = self.housing_selected_size;
+                let key = (
+                    search_lower.clone(),
+                    sub_tab,
+                    part_type,
+                    ui_category,
+                    selected_size,
+                    item_list::items_identity(indices),
+                );
+                let filtered_indices = self.housing_filter_cache.get_or_compute(key, || {
+                    let mut result = indices
+                        .iter()
+                        .copied()
+                        .filter(|&idx| {
+                            let item = &gs.all_items[idx];
+                            // 外装类型筛选
+                            if sub_tab == HousingSubTab::Exterior {
+                                if let Some(pt) = part_type {
+                                    if item.exterior_part_type() != Some(pt) {
+                                        return false;
+                                    }
                                 }
                             }
-                        }
-                        // 庭院/室内分类筛选
-                        if matches!(
-                            self.housing_sub_tab,
-                            HousingSubTab::Yard | HousingSubTab::Indoor
-                        ) {
-                            if let Some(cat) = self.housing_selected_ui_category {
-                                if item.item_ui_category != cat {
-                                    return None;
+                            // 庭院/室内分类筛选
+                            if matches!(sub_tab, HousingSubTab::Yard | HousingSubTab::Indoor) {
+                                if let Some(cat) = ui_category {
+                                    if item.item_ui_category != cat {
+                                        return false;
+                                    }
                                 }
                             }
-                        }
-                        if !search_lower.is_empty()
-                            && !item.name.to_lowercase().contains(&search_lower)
-                        {
-                            return None;
-                        }
-                        Some((idx, item))
-                    })
+                            // 尺寸筛选
+                            if let Some(size) = selected_size {
+                                if gs.housing_size(item) != size {
+                                    return false;
+                                }
+                            }
+                            item.matches_search(&search_lower)
+                        })
+                        .collect::<Vec<usize>>();
+                    // 全部类型: 按外装类型再按名称排序
+                    if sub_tab == HousingSubTab::Exterior && part_type.is_none() {
+                        result.sort_by_cached_key(|&idx| {
+                            let item = &gs.all_items[idx];
+                            let type_order = item
+                                .exterior_part_type()
+                                .and_then(|pt| EXTERIOR_PART_TYPES.iter().position(|p| *p == pt))
+                                .unwrap_or(usize::MAX);
+                            (type_order, item.name.clone())
+                        });
+                    }
+                    result
+                });
+                let filtered: Vec<(usize, &GameItem)> = filtered_indices
+                    .iter()
+                    .map(|&idx| (idx, &gs.all_items[idx]))
                     .collect();
 
                 ui.label(format!("{} 件物品", filtered.len()));
                 ui.separator();
 
+                // 全部类型的合并列表中用小标签标出外装类型
+                let show_part_badge = sub_tab == HousingSubTab::Exterior && part_type.is_none();
+                let part_badge = |item: &GameItem| {
+                    item.exterior_part_type()
+                        .filter(|_| show_part_badge)
+                        .map(|pt| pt.display_name())
+                };
+
                 // 构建 DisplayItem 列表
                 let display_items: Vec<DisplayItem<'_>> = filtered
                     .iter()
@@ -174,7 +263,8 @@ impl App {
                         id: idx,
                         name: &item.name,
                         icon_id: item.icon_id,
-                        is_selected: self.housing_selected_item == Some(idx),
+                        is_selected: self.housing_selected_item_id == Some(item.row_id),
+                        badge: part_badge(item),
                     })
                     .collect();
 
@@ -189,7 +279,7 @@ impl App {
                             ctx,
                             &gs.game,
                         ) {
-                            self.housing_selected_item = Some(clicked);
+                            self.housing_selected_item_id = Some(gs.all_items[clicked].row_id);
                         }
                     }
                     ViewMode::List => {
@@ -202,22 +292,23 @@ impl App {
                             |ui, row_range| {
                                 for i in row_range {
                                     let (idx, item) = &filtered[i];
-                                    let label = self.housing_list_label(item);
                                     let di = DisplayItem {
                                         id: *idx,
                                         name: &item.name,
                                         icon_id: item.icon_id,
-                                        is_selected: self.housing_selected_item == Some(*idx),
+                                        is_selected: self.housing_selected_item_id
+                                            == Some(item.row_id),
+                                        badge: part_badge(item),
                                     };
                                     if item_list::show_list_row(
                                         ui,
                                         &di,
-                                        &label,
+                                        &item.name,
                                         &mut self.icon_cache,
                                         ctx,
                                         &gs.game,
                                     ) {
-                                        self.housing_selected_item = Some(*idx);
+                                        self.housing_selected_item_id = Some(item.row_id);
                                     }
                                 }
                             },
@@ -229,23 +320,10 @@ impl App {
         self.show_housing_detail_panel(ctx, gs);
     }
 
-    fn housing_list_label(&self, item: &GameItem) -> String {
-        match self.housing_sub_tab {
-            HousingSubTab::Exterior => {
-                let part_name = item
-                    .exterior_part_type()
-                    .map(|pt| pt.display_name())
-                    .unwrap_or("?");
-                format!("[{}] {}", part_name, item.name)
-            }
-            _ => item.name.clone(),
-        }
-    }
-
     fn show_housing_detail_panel(&mut self, ctx: &egui::Context, gs: &mut GameState) {
         egui::CentralPanel::default().show(ctx, |ui| {
-            if let Some(idx) = self.housing_selected_item {
-                if let Some(item) = gs.all_items.get(idx) {
+            if let Some(row_id) = self.housing_selected_item_id {
+                if let Some(item) = gs.item_by_id(row_id) {
                     // 统一物品详情头部
                     let icon = self.get_or_load_icon(ctx, &gs.game, item.icon_id);
                     let cat_name = gs
@@ -269,6 +347,9 @@ impl App {
                             ui.label(pt.display_name());
                             ui.end_row();
                         }
+                        ui.label("尺寸:");
+                        ui.label(gs.housing_size(item).display_name());
+                        ui.end_row();
                         ui.label("SGB:");
                         ui.label(&sgb_display);
                         ui.end_row();
@@ -289,6 +370,7 @@ impl App {
                             &mut self.housing_active_dye_channel,
                             &mut self.housing_selected_shade,
                             self.housing_is_dual_dye,
+                            false,
                         );
                         if changed {
                             self.housing_needs_rebake = true;
@@ -296,10 +378,17 @@ impl App {
                     }
 
                     // 加载模型
-                    if self.housing_loaded_model_idx != Some(idx) {
-                        self.load_housing_model(idx, item, gs);
+                    if self.housing_loaded_model_id != Some(row_id) {
+                        self.load_housing_model(item, gs);
+                    }
+                    viewport::show_viewport(&mut self.housing_viewport, ui, ctx, "模型加载失败");
+                    if self
+                        .housing_viewport
+                        .as_mut()
+                        .is_some_and(|vp| vp.take_recenter_toggled())
+                    {
+                        self.housing_loaded_model_id = None;
                     }
-                    self.housing_viewport.show(ui, ctx, "模型加载失败");
                 } else {
                     ui.label("选择一件物品查看详情");
                 }
@@ -335,8 +424,8 @@ impl App {
         }
     }
 
-    fn load_housing_model(&mut self, idx: usize, item: &GameItem, gs: &GameState) {
-        self.housing_loaded_model_idx = Some(idx);
+    fn load_housing_model(&mut self, item: &GameItem, gs: &GameState) {
+        self.housing_loaded_model_id = Some(item.row_id);
         self.housing_stain_ids = [0, 0];
         self.housing_active_dye_channel = 0;
 
@@ -413,74 +502,72 @@ impl App {
         let load_result =
             load_housing_mesh_textures(&gs.game, &all_material_names, &all_meshes, mdl_path_ref);
 
-        let geometry: Vec<(&[tomestone_render::Vertex], &[u16])> = all_meshes
-            .iter()
-            .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
-            .collect();
-
-        let vp = &mut self.housing_viewport;
-        vp.model_renderer
-            .set_model_type(tomestone_render::ModelType::Background);
-        vp.model_renderer.set_mesh_data(
-            &vp.render_state.device,
-            &vp.render_state.queue,
-            &geometry,
-            &load_result.mesh_textures,
-        );
+        if let Some(vp) = &mut self.housing_viewport {
+            // 重置原点直接平移待上传的网格 (之后只保留材质下标，不需要原始坐标)
+            let recenter = vp.recenter_origin == Some(true);
+            vp.origin_offset = recenter.then(|| recenter_meshes(&mut all_meshes, &bbox));
+            let bbox = match vp.origin_offset {
+                Some(offset) => bbox.translated(offset.map(|c| -c)),
+                None => bbox,
+            };
+            let geometry: Vec<(&[tomestone_render::Vertex], &[u16])> = all_meshes
+                .iter()
+                .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
+                .collect();
+
+            vp.model_renderer
+                .set_model_type(tomestone_render::ModelType::Background);
+            vp.model_renderer.set_mesh_data(
+                &vp.render_state.device,
+                &vp.render_state.queue,
+                &geometry,
+                &load_result.mesh_textures,
+            );
+            vp.camera.focus_on(&bbox);
+            vp.last_bbox = Some(bbox);
+            vp.free_texture();
+        }
 
         // 缓存材质用于染色
         self.housing_cached_materials = load_result.materials;
         self.housing_is_dual_dye = dye::has_dual_dye(&self.housing_cached_materials);
-        self.housing_cached_meshes = all_meshes;
-
-        self.housing_viewport.camera.focus_on(&bbox);
-        self.housing_viewport.last_bbox = Some(bbox);
-        self.housing_viewport.free_texture();
+        self.housing_cached_mesh_materials = all_meshes.iter().map(|m| m.material_index).collect();
     }
 
     fn clear_housing_model(&mut self) {
-        let vp = &mut self.housing_viewport;
-        vp.model_renderer
-            .set_mesh_data(&vp.render_state.device, &vp.render_state.queue, &[], &[]);
-        self.housing_viewport.last_bbox = None;
+        if let Some(vp) = &mut self.housing_viewport {
+            vp.model_renderer.set_mesh_data(
+                &vp.render_state.device,
+                &vp.render_state.queue,
+                &[],
+                &[],
+            );
+            vp.last_bbox = None;
+            vp.origin_offset = None;
+        }
         self.housing_cached_materials = HashMap::new();
-        self.housing_cached_meshes = Vec::new();
+        self.housing_cached_mesh_materials = Vec::new();
         self.housing_is_dual_dye = false;
     }
 
     pub fn rebake_housing_textures(&mut self, stm: &StainingTemplate) {
-        let mut new_textures: Vec<Option<tomestone_render::TextureData>> = Vec::new();
-        for mesh in &self.housing_cached_meshes {
-            let mat_idx = mesh.material_index;
-            if let Some(cached) = self.housing_cached_materials.get(&mat_idx) {
-                if cached.uses_color_table {
-                    if let (Some(color_table), Some(id_tex)) =
-                        (&cached.color_table, &cached.id_texture)
-                    {
-                        let dyed_colors = if self.housing_stain_ids[0] > 0
-                            || self.housing_stain_ids[1] > 0
-                        {
-                            cached.color_dye_table.as_ref().map(|dye_table| {
-                                dye::apply_dye(color_table, dye_table, stm, self.housing_stain_ids)
-                            })
-                        } else {
-                            None
-                        };
-                        let baked =
-                            bake_color_table_texture(id_tex, color_table, dyed_colors.as_ref());
-                        new_textures.push(Some(baked));
-                        continue;
-                    }
-                }
-            }
-            new_textures.push(None);
+        let updates: Vec<Option<dye::DyeUpdate>> = self
+            .housing_cached_mesh_materials
+            .iter()
+            .map(|material_index| {
+                self.housing_cached_materials
+                    .get(material_index)
+                    .and_then(|cached| dye::dye_update(cached, self.housing_stain_ids, stm))
+            })
+            .collect();
+        if let Some(vp) = &mut self.housing_viewport {
+            dye::submit_dye_updates(
+                &mut vp.model_renderer,
+                &vp.render_state.device,
+                &vp.render_state.queue,
+                updates,
+            );
+            vp.mark_dirty();
         }
-        let vp = &mut self.housing_viewport;
-        vp.model_renderer.update_textures(
-            &vp.render_state.device,
-            &vp.render_state.queue,
-            &new_textures,
-        );
-        self.housing_viewport.mark_dirty();
     }
 }
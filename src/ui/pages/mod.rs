@@ -1,7 +1,14 @@
 pub mod browser;
+pub mod collection;
 pub mod crafting;
+pub mod diagnostics;
 pub mod glamour;
 pub mod housing;
+pub mod quick_switcher;
+pub mod race_compare;
 pub mod resource;
+pub mod settings;
+#[cfg(any(test, feature = "stress-select"))]
+pub mod selection_stress;
 pub mod setup;
 pub mod toolbox;
@@ -0,0 +1,244 @@
+//! 快速切换 (Ctrl+P): 模糊搜索页面、装备、可制作物品与幻化组合，回车跳转
+
+use eframe::egui;
+
+use crate::app::{App, PendingLeave};
+use crate::derived::SwitcherItem;
+use crate::domain::AppPage;
+use crate::fuzzy::{fuzzy_score, fuzzy_score_prepared};
+use crate::loading::GameState;
+use crate::nav::NavEntry;
+
+/// 最多显示的结果数
+const MAX_RESULTS: usize = 50;
+
+/// 可跳转的目标，物品以 row_id 记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SwitchTarget {
+    Page(AppPage),
+    Equipment(u32),
+    Craftable(u32),
+    /// gs.glamour_sets 索引
+    Glamour(usize),
+}
+
+impl SwitchTarget {
+    fn tag(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Page(_) => (egui_phosphor::regular::SQUARES_FOUR, tr!("switcher.page")),
+            Self::Equipment(_) => (egui_phosphor::regular::T_SHIRT, tr!("switcher.equipment")),
+            Self::Craftable(_) => (egui_phosphor::regular::HAMMER, tr!("switcher.craftable")),
+            Self::Glamour(_) => (egui_phosphor::regular::COAT_HANGER, tr!("switcher.glamour")),
+        }
+    }
+
+    fn name(self, gs: &GameState) -> &str {
+        match self {
+            Self::Page(page) => page.label(),
+            Self::Equipment(row_id) | Self::Craftable(row_id) => {
+                gs.item_by_id(row_id).map_or("", |item| item.name.as_str())
+            }
+            Self::Glamour(idx) => gs.glamour_sets.get(idx).map_or("", |s| s.name.as_str()),
+        }
+    }
+
+    fn sort_key(self) -> (u8, u32) {
+        match self {
+            Self::Page(page) => (0, page as u32),
+            Self::Equipment(row_id) => (1, row_id),
+            Self::Craftable(row_id) => (2, row_id),
+            Self::Glamour(idx) => (3, idx as u32),
+        }
+    }
+
+    fn leave(self) -> PendingLeave {
+        match self {
+            Self::Page(page) => PendingLeave::Page(page),
+            Self::Equipment(row_id) => PendingLeave::Navigate(NavEntry {
+                page: AppPage::Browser,
+                row_id: Some(row_id),
+            }),
+            Self::Craftable(row_id) => PendingLeave::Navigate(NavEntry {
+                page: AppPage::CraftingBrowser,
+                row_id: Some(row_id),
+            }),
+            Self::Glamour(idx) => PendingLeave::OpenGlamour(idx),
+        }
+    }
+}
+
+impl From<SwitcherItem> for SwitchTarget {
+    fn from(item: SwitcherItem) -> Self {
+        match item {
+            SwitcherItem::Page(page) => Self::Page(page),
+            SwitcherItem::Equipment(row_id) => Self::Equipment(row_id),
+            SwitcherItem::Craftable(row_id) => Self::Craftable(row_id),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct QuickSwitcherState {
+    query: String,
+    /// results 对应的查询词，与 query 不同时重新打分
+    results_query: Option<String>,
+    results: Vec<SwitchTarget>,
+    selected: usize,
+    /// 键盘移动了选中项，本帧滚动到该项
+    scroll_to_selected: bool,
+}
+
+/// 可跳转的页面 (测试页除外)
+pub fn switch_pages() -> impl Iterator<Item = AppPage> {
+    AppPage::STARTUP_CHOICES
+        .into_iter()
+        .chain(std::iter::once(AppPage::Settings))
+}
+
+/// 对全部候选打分，保留最高的 MAX_RESULTS 个；空查询只列出页面
+///
+/// 派生索引中的候选已预先转小写，索引就绪前逐项处理名称
+fn rank_candidates(query: &str, gs: &GameState) -> Vec<SwitchTarget> {
+    if query.trim().is_empty() {
+        return switch_pages().map(SwitchTarget::Page).collect();
+    }
+    let query = query.trim();
+    let score_by_name = |target: SwitchTarget| {
+        let name = target.name(gs);
+        let score = fuzzy_score(query, name)?;
+        Some((score, name.chars().count(), target))
+    };
+    let glamours = (0..gs.glamour_sets.len()).map(SwitchTarget::Glamour);
+
+    let mut scored: Vec<(i32, usize, SwitchTarget)> = match gs.derived.switcher() {
+        Some(candidates) => candidates
+            .entries
+            .iter()
+            .filter_map(|(item, text)| {
+                let score = fuzzy_score_prepared(query, text)?;
+                Some((score, text.char_count(), SwitchTarget::from(*item)))
+            })
+            .chain(glamours.filter_map(score_by_name))
+            .collect(),
+        None => {
+            let pages = switch_pages().map(SwitchTarget::Page);
+            let equipment = gs
+                .equipment_indices
+                .iter()
+                .map(|&i| SwitchTarget::Equipment(gs.all_items[i].row_id));
+            let craftable = gs
+                .item_to_recipes
+                .keys()
+                .map(|&id| SwitchTarget::Craftable(id));
+            pages
+                .chain(equipment)
+                .chain(craftable)
+                .chain(glamours)
+                .filter_map(score_by_name)
+                .collect()
+        }
+    };
+    // 同分时名称短的优先，再按目标排序使结果稳定 (item_to_recipes 的遍历顺序不固定)
+    let order = |a: &(i32, usize, SwitchTarget), b: &(i32, usize, SwitchTarget)| {
+        b.0.cmp(&a.0)
+            .then(a.1.cmp(&b.1))
+            .then(a.2.sort_key().cmp(&b.2.sort_key()))
+    };
+    if scored.len() > MAX_RESULTS {
+        scored.select_nth_unstable_by(MAX_RESULTS - 1, order);
+        scored.truncate(MAX_RESULTS);
+    }
+    scored.sort_by(order);
+    scored.into_iter().map(|(_, _, target)| target).collect()
+}
+
+impl App {
+    /// Ctrl+P 打开/关闭快速切换，返回选中的跳转 (由调用方按未保存修改规则执行)
+    pub fn show_quick_switcher(
+        &mut self,
+        ctx: &egui::Context,
+        gs: &GameState,
+    ) -> Option<PendingLeave> {
+        let shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::P);
+        if ctx.input_mut(|i| i.consume_shortcut(&shortcut)) {
+            self.quick_switcher = match self.quick_switcher {
+                Some(_) => None,
+                None => Some(QuickSwitcherState::default()),
+            };
+        }
+        let state = self.quick_switcher.as_mut()?;
+
+        if state.results_query.as_deref() != Some(state.query.as_str()) {
+            state.results = rank_candidates(&state.query, gs);
+            state.results_query = Some(state.query.clone());
+            state.selected = 0;
+        }
+
+        let (up, down, enter) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            )
+        });
+        let count = state.results.len();
+        if count > 0 && (up || down) {
+            state.selected = if up {
+                (state.selected + count - 1) % count
+            } else {
+                (state.selected + 1) % count
+            };
+            state.scroll_to_selected = true;
+        }
+
+        let mut chosen = enter
+            .then(|| state.results.get(state.selected).copied())
+            .flatten();
+        let modal = egui::Modal::new(egui::Id::new("quick_switcher")).show(ctx, |ui| {
+            ui.set_width(480.0);
+            let edit = ui.add(
+                egui::TextEdit::singleline(&mut state.query)
+                    .hint_text(tr!("switcher.hint"))
+                    .desired_width(f32::INFINITY),
+            );
+            edit.request_focus();
+            ui.separator();
+            if state.results.is_empty() {
+                ui.weak(tr!("switcher.no_results"));
+                return;
+            }
+            egui::ScrollArea::vertical()
+                .max_height(360.0)
+                .show(ui, |ui| {
+                    for (i, target) in state.results.iter().enumerate() {
+                        let (icon, tag) = target.tag();
+                        let response = ui
+                            .horizontal(|ui| {
+                                let row = ui.selectable_label(
+                                    i == state.selected,
+                                    format!("{} {}", icon, target.name(gs)),
+                                );
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| ui.weak(tag),
+                                );
+                                row
+                            })
+                            .inner;
+                        if i == state.selected && state.scroll_to_selected {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            chosen = Some(*target);
+                        }
+                    }
+                });
+            state.scroll_to_selected = false;
+        });
+
+        if chosen.is_some() || modal.should_close() {
+            self.quick_switcher = None;
+        }
+        chosen.map(SwitchTarget::leave)
+    }
+}
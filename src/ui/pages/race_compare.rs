@@ -0,0 +1,388 @@
+//! 装备浏览的种族对比: 同一件装备按两个种族加载，左右分屏 (共享相机) 或合并到同一视口并排显示
+//!
+//! 种族 A 使用主视口与主视口的材质缓存 (染色、材质诊断照常工作)，种族 B 的网格、材质与
+//! 分屏视口由 [`RaceCompareState`] 单独持有，关闭对比时一并释放。
+
+use std::collections::HashMap;
+
+use eframe::egui;
+use tomestone_render::{BoundingBox, Camera, MeshTextures, Vertex};
+
+use crate::app::App;
+use crate::domain::{race_code_label, GameItem, RACE_CODES};
+use crate::dye;
+use crate::game::{
+    apply_skinning, compute_bounding_box, load_mdl, load_mdl_with_fallback_indexed,
+    load_mesh_textures, race_code_of_model_path, CachedMaterial, MaterialLoadResult, MeshData,
+    SkeletonCache,
+};
+use crate::loading::GameState;
+use crate::ui::components::viewport::{self, ViewportState};
+
+/// 合并显示时两个模型之间的间隔 (较宽模型宽度的比例)
+const MERGED_GAP_RATIO: f32 = 0.1;
+
+/// 相机的可比较参数 (距离, 偏航, 俯仰, 目标点, 场景尺寸)
+type CameraParams = (f32, f32, f32, [f32; 3], f32);
+
+pub struct RaceCompareState {
+    pub race_a: &'static str,
+    pub race_b: &'static str,
+    /// 合并到主视口单次渲染: 种族 B 的网格沿 +X 平移到种族 A 右侧
+    pub merged: bool,
+    /// 缺少原生模型时的回退说明
+    pub notices: Vec<String>,
+    /// 种族 B 的材质与各网格的材质索引 (染色用)
+    pub materials: HashMap<u16, CachedMaterial>,
+    pub mesh_materials: Vec<u16>,
+    /// 分屏时种族 B 的视口
+    pub viewport: ViewportState,
+    /// 上一帧同步后的相机参数，用于判断本帧是哪一侧的相机被操作
+    camera_sync: Option<CameraParams>,
+}
+
+impl RaceCompareState {
+    pub fn new(render_state: egui_wgpu::RenderState) -> Result<Self, String> {
+        Ok(Self {
+            race_a: RACE_CODES[0],
+            race_b: RACE_CODES[1],
+            merged: false,
+            notices: Vec::new(),
+            materials: HashMap::new(),
+            mesh_materials: Vec::new(),
+            viewport: ViewportState::new(render_state)?,
+            camera_sync: None,
+        })
+    }
+
+    /// 种族 B 的染色更新，与 `mesh_materials` 一一对应
+    pub fn dye_updates(
+        &self,
+        stain_ids: [u32; 2],
+        show_regions: bool,
+        stm: &physis::stm::StainingTemplate,
+    ) -> Vec<Option<dye::DyeUpdate>> {
+        self.mesh_materials
+            .iter()
+            .map(|material_index| {
+                let cached = self.materials.get(material_index)?;
+                if show_regions {
+                    dye::dye_region_update(cached)
+                } else {
+                    dye::dye_update(cached, stain_ids, stm)
+                }
+            })
+            .collect()
+    }
+
+    fn clear_viewport(&mut self) {
+        let vp = &mut self.viewport;
+        vp.model_renderer
+            .set_mesh_data(&vp.render_state.device, &vp.render_state.queue, &[], &[]);
+        vp.set_skin_data(&[]);
+        vp.last_bbox = None;
+        vp.free_texture();
+    }
+}
+
+/// 按种族加载的模型
+struct RaceModel {
+    meshes: Vec<MeshData>,
+    textures: MaterialLoadResult,
+    /// 使用了其他种族的模型时的说明
+    notice: Option<String>,
+}
+
+/// 加载物品在指定种族下的模型。没有该种族的原生模型时按 RACE_CODES 顺序回退，
+/// 两个种族的骨架都可用时把回退模型蒙皮适配到目标种族。使用身体模型或加载出过错的物品不加载
+fn load_race_model(
+    item: &GameItem,
+    race: &'static str,
+    gs: &GameState,
+    skeleton_cache: &mut SkeletonCache,
+) -> Option<RaceModel> {
+    if item.uses_body_model() || crate::crash::is_item_skipped(item.row_id) {
+        return None;
+    }
+    let _load = crate::crash::ItemLoadGuard::new(item.row_id);
+    let native = item
+        .model_path_for_race(race)
+        .and_then(|path| load_mdl(&gs.game, &path).ok())
+        .filter(|result| !result.meshes.is_empty());
+    let (mut result, notice) = match native {
+        Some(result) => (result, None),
+        None => {
+            let paths = item.model_paths();
+            let (idx, mut result) = load_mdl_with_fallback_indexed(&gs.game, &paths).ok()?;
+            let source = race_code_of_model_path(&paths[idx]).unwrap_or(RACE_CODES[0]);
+            let target_bind = skeleton_cache.get_bind_pose(race, &gs.game).cloned();
+            let source_bind = skeleton_cache.get_bind_pose(source, &gs.game).cloned();
+            let adapted = match (source_bind, target_bind) {
+                (Some(source_bind), Some(target_bind)) => {
+                    apply_skinning(
+                        &mut result.meshes,
+                        &result.bone_names,
+                        &result.bone_tables,
+                        &source_bind,
+                        &target_bind,
+                    );
+                    "，已按骨架适配"
+                }
+                _ => "，骨架不可用，未适配",
+            };
+            let notice = format!(
+                "{} 没有原生模型，使用 {} 的模型{}",
+                race_code_label(race),
+                race_code_label(source),
+                adapted
+            );
+            (result, Some(notice))
+        }
+    };
+    let textures = load_mesh_textures(
+        &gs.game,
+        &result.material_names,
+        &result.meshes,
+        item.set_id(),
+        item.variant_id(),
+    );
+    Some(RaceModel {
+        meshes: std::mem::take(&mut result.meshes),
+        textures,
+        notice,
+    })
+}
+
+fn union_bbox(a: &BoundingBox, b: &BoundingBox) -> BoundingBox {
+    BoundingBox {
+        min: std::array::from_fn(|i| a.min[i].min(b.min[i])),
+        max: std::array::from_fn(|i| a.max[i].max(b.max[i])),
+    }
+}
+
+fn translate_x(meshes: &mut [MeshData], dx: f32) {
+    for v in meshes.iter_mut().flat_map(|m| &mut m.vertices) {
+        v.position[0] += dx;
+    }
+}
+
+fn camera_params(camera: &Camera) -> CameraParams {
+    (
+        camera.distance,
+        camera.yaw,
+        camera.pitch,
+        camera.target,
+        camera.scene_size,
+    )
+}
+
+fn set_camera_params(camera: &mut Camera, params: CameraParams) {
+    (
+        camera.distance,
+        camera.yaw,
+        camera.pitch,
+        camera.target,
+        camera.scene_size,
+    ) = params;
+}
+
+fn upload_meshes(vp: &mut ViewportState, meshes: &[MeshData], textures: &[MeshTextures]) {
+    let geometry: Vec<(&[Vertex], &[u16])> = meshes
+        .iter()
+        .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
+        .collect();
+    vp.model_renderer.set_mesh_data(
+        &vp.render_state.device,
+        &vp.render_state.queue,
+        &geometry,
+        textures,
+    );
+    vp.set_skin_data(meshes);
+    vp.free_texture();
+}
+
+impl App {
+    /// 详情面板中的种族对比开关、种族选择与回退说明，返回是否需要重新加载模型
+    pub fn show_race_compare_controls(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut reload = false;
+        let mut enabled = self.race_compare.is_some();
+        // 无 3D 模式下没有主视口，种族对比不可用
+        let available = self.viewport.is_some();
+        if ui
+            .add_enabled(available, egui::Checkbox::new(&mut enabled, "种族对比"))
+            .on_hover_text("按两个种族加载同一件装备，左右并排比较")
+            .changed()
+        {
+            match self.race_compare.take() {
+                Some(mut state) => state.viewport.release_gpu(),
+                None => {
+                    if let Some(render_state) = &self.render_state {
+                        match RaceCompareState::new(render_state.clone()) {
+                            Ok(state) => self.race_compare = Some(state),
+                            Err(e) => self.toasts.push(format!("种族对比不可用: {}", e)),
+                        }
+                    }
+                }
+            }
+            reload = true;
+        }
+        let Some(state) = &mut self.race_compare else {
+            return reload;
+        };
+        ui.horizontal(|ui| {
+            for (label, race) in [("A:", &mut state.race_a), ("B:", &mut state.race_b)] {
+                ui.label(label);
+                egui::ComboBox::from_id_salt(("race_compare", label))
+                    .selected_text(race_code_label(race))
+                    .show_ui(ui, |ui| {
+                        for &rc in RACE_CODES {
+                            reload |= ui.selectable_value(race, rc, race_code_label(rc)).changed();
+                        }
+                    });
+            }
+            reload |= ui
+                .checkbox(&mut state.merged, "合并显示")
+                .on_hover_text("在同一视口中渲染，种族 B 放在种族 A 右侧")
+                .changed();
+        });
+        for notice in &state.notices {
+            ui.label(egui::RichText::new(notice).small().weak());
+        }
+        reload
+    }
+
+    /// 种族对比模式下加载两个种族的模型 (代替普通加载，不改动染料选择)
+    pub fn upload_race_compare(&mut self, item: &GameItem, gs: &GameState) {
+        let Some(state) = &self.race_compare else {
+            return;
+        };
+        let (race_a, race_b, merged) = (state.race_a, state.race_b, state.merged);
+        let model_a = load_race_model(item, race_a, gs, &mut self.skeleton_cache);
+        let model_b = load_race_model(item, race_b, gs, &mut self.skeleton_cache);
+        let Some(state) = &mut self.race_compare else {
+            return;
+        };
+        state.notices = [&model_a, &model_b]
+            .into_iter()
+            .filter_map(|m| m.as_ref()?.notice.clone())
+            .collect();
+        state.camera_sync = None;
+
+        let (Some(a), Some(mut b)) = (model_a, model_b) else {
+            if !item.uses_body_model() {
+                eprintln!(
+                    "模型加载失败 e{:04} v{:04}",
+                    item.set_id(),
+                    item.variant_id()
+                );
+            }
+            state.clear_viewport();
+            state.materials.clear();
+            state.mesh_materials.clear();
+            if let Some(vp) = &mut self.viewport {
+                vp.model_renderer.set_mesh_data(
+                    &vp.render_state.device,
+                    &vp.render_state.queue,
+                    &[],
+                    &[],
+                );
+                vp.set_skin_data(&[]);
+                vp.last_bbox = None;
+            }
+            self.texture_diagnostics.clear();
+            self.cached_mesh_materials.clear();
+            return;
+        };
+
+        let bbox_a = compute_bounding_box(&a.meshes);
+        let mut bbox_b = compute_bounding_box(&b.meshes);
+        if merged {
+            let width = (bbox_a.max[0] - bbox_a.min[0]).max(bbox_b.max[0] - bbox_b.min[0]);
+            translate_x(
+                &mut b.meshes,
+                bbox_a.max[0] - bbox_b.min[0] + width * MERGED_GAP_RATIO,
+            );
+            bbox_b = compute_bounding_box(&b.meshes);
+        }
+        let bbox = union_bbox(&bbox_a, &bbox_b);
+
+        if let Some(vp) = &mut self.viewport {
+            if merged {
+                let meshes: Vec<MeshData> = a.meshes.iter().chain(&b.meshes).cloned().collect();
+                let textures: Vec<_> = a
+                    .textures
+                    .mesh_textures
+                    .iter()
+                    .chain(&b.textures.mesh_textures)
+                    .cloned()
+                    .collect();
+                upload_meshes(vp, &meshes, &textures);
+                state.clear_viewport();
+            } else {
+                upload_meshes(vp, &a.meshes, &a.textures.mesh_textures);
+                upload_meshes(&mut state.viewport, &b.meshes, &b.textures.mesh_textures);
+                state.viewport.camera.focus_on(&bbox);
+                state.viewport.last_bbox = Some(bbox_b);
+            }
+            vp.camera.focus_on(&bbox);
+            vp.last_bbox = Some(bbox);
+        }
+
+        let missing = a.textures.missing_count() + b.textures.missing_count();
+        if missing > 0 {
+            self.toasts.push(format!("{} 个材质的贴图缺失", missing));
+        }
+        state.materials = b.textures.materials;
+        state.mesh_materials = b.meshes.iter().map(|m| m.material_index).collect();
+        self.texture_diagnostics = a.textures.diagnostics;
+        self.cached_materials = a.textures.materials;
+        self.cached_mesh_materials = a.meshes.iter().map(|m| m.material_index).collect();
+        self.is_dual_dye =
+            dye::has_dual_dye(&self.cached_materials) || dye::has_dual_dye(&state.materials);
+        if self.stains_from_default || self.show_dye_regions {
+            if let Some(stm) = &gs.stm {
+                self.rebake_textures(stm);
+            }
+        }
+    }
+
+    /// 分屏显示两个种族的视口，任一侧的相机操作同步到另一侧
+    pub fn show_race_compare_viewports(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        empty_label: &str,
+    ) {
+        let (Some(state), Some(viewport_a)) = (&mut self.race_compare, &mut self.viewport) else {
+            viewport::show_unavailable(ui);
+            return;
+        };
+        ui.columns(2, |columns| {
+            let [left, right] = columns else {
+                return;
+            };
+            for (ui, race, vp) in [
+                (left, state.race_a, &mut *viewport_a),
+                (right, state.race_b, &mut state.viewport),
+            ] {
+                ui.label(egui::RichText::new(race_code_label(race)).strong());
+                vp.show(ui, ctx, empty_label);
+            }
+        });
+
+        let a = camera_params(&viewport_a.camera);
+        let b = camera_params(&state.viewport.camera);
+        if a != b {
+            // 上一帧同步后哪一侧变化了，就以哪一侧为准
+            if state.camera_sync == Some(a) {
+                set_camera_params(&mut viewport_a.camera, b);
+                viewport_a.mark_dirty();
+            } else {
+                set_camera_params(&mut state.viewport.camera, a);
+                state.viewport.mark_dirty();
+            }
+            ctx.request_repaint();
+        }
+        state.camera_sync = Some(camera_params(&viewport_a.camera));
+    }
+}
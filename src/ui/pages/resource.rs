@@ -183,7 +183,7 @@ impl ResourceBrowserState {
     }
 
     fn show_left_panel(&mut self, ui: &mut egui::Ui, game: &GameData) {
-        ui.heading("EXD 表");
+        ui.heading(tr!("heading.exd_tables"));
         ui.separator();
 
         ui.horizontal(|ui| {
@@ -494,12 +494,14 @@ impl ResourceBrowserState {
             Language::None
         };
 
-        if let Some(sheet) = game.read_excel_sheet(&exh, name, lang) {
-            for page in &sheet.pages {
-                for (row_id, row) in page.into_iter().flatten_subrows() {
-                    self.flat_rows.push((row_id, row.columns.clone()));
-                }
+        match game.read_sheet_rows(name, lang) {
+            Ok(rows) => {
+                self.flat_rows = rows
+                    .into_iter()
+                    .map(|(row_id, row)| (row_id, row.columns))
+                    .collect();
             }
+            Err(e) => eprintln!("无法读取 {} 表: {}", name, e),
         }
 
         self.loaded_exh = Some(exh);
@@ -0,0 +1,162 @@
+//! `--stress-select` 选择压力测试 (需以 --features stress-select 编译):
+//! 进入浏览页后脚本化地快速切换装备，验证选择防抖下只加载最后一件的模型
+
+/// 压力测试切换选中物品的间隔 (秒)，接近按住方向键时的重复速度
+const SELECTION_STRESS_INTERVAL: f64 = 0.03;
+
+/// `--stress-select`: 进入浏览页后按固定间隔连续切换选中的装备 (模拟快速点击或按住方向键)，
+/// 每次切换输出模型加载次数与视口显存估算，最后一件加载完成后输出汇总。
+/// 选择防抖生效时，整轮只应加载最后一件的模型
+#[derive(Debug, Default)]
+pub struct SelectionStress {
+    /// 依次选中的物品 row_id，首次运行时从装备列表取
+    queue: Vec<u32>,
+    next: usize,
+    next_at: f64,
+    /// 上一帧已加载模型的物品，变化一次计为一次加载
+    last_loaded: Option<u32>,
+    loads: u32,
+    peak_bytes: u64,
+}
+
+/// 压力测试在一帧中的动作
+#[derive(Debug, PartialEq)]
+enum StressStep {
+    Select(u32),
+    Wait,
+    Done,
+}
+
+impl SelectionStress {
+    fn new(queue: Vec<u32>, loaded: Option<u32>) -> Self {
+        Self {
+            queue,
+            last_loaded: loaded,
+            ..Default::default()
+        }
+    }
+
+    /// 记录本帧的加载状态与显存，按间隔给出下一件要选中的物品；
+    /// 全部切换完后等待最后一件的模型加载完成
+    fn step(&mut self, now: f64, loaded: Option<u32>, gpu_bytes: u64) -> StressStep {
+        self.peak_bytes = self.peak_bytes.max(gpu_bytes);
+        if loaded != self.last_loaded {
+            self.last_loaded = loaded;
+            if loaded.is_some() {
+                self.loads += 1;
+            }
+        }
+        if let Some(&row_id) = self.queue.get(self.next) {
+            if now < self.next_at {
+                return StressStep::Wait;
+            }
+            self.next += 1;
+            self.next_at = now + SELECTION_STRESS_INTERVAL;
+            return StressStep::Select(row_id);
+        }
+        if loaded.is_some() && loaded == self.queue.last().copied() {
+            StressStep::Done
+        } else {
+            StressStep::Wait
+        }
+    }
+}
+
+#[cfg(feature = "stress-select")]
+mod driver {
+    use eframe::egui;
+
+    use super::{SelectionStress, StressStep};
+    use crate::app::App;
+    use crate::loading::GameState;
+
+    /// 压力测试依次选中的装备件数
+    const SELECTION_STRESS_COUNT: usize = 40;
+
+    fn format_mib(bytes: u64) -> String {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+
+    impl App {
+        /// 推进 `--stress-select` 压力测试 (未启用时什么也不做)
+        pub fn drive_selection_stress(&mut self, ctx: &egui::Context, gs: &GameState) {
+            let Some(stress) = &mut self.selection_stress else {
+                return;
+            };
+            if stress.queue.is_empty() {
+                let queue: Vec<u32> = gs
+                    .equipment_indices
+                    .iter()
+                    .map(|&idx| gs.all_items[idx].row_id)
+                    .filter(|&row_id| self.loaded_model_id != Some(row_id))
+                    .take(SELECTION_STRESS_COUNT)
+                    .collect();
+                if queue.is_empty() {
+                    println!("[选择压力测试] 没有可切换的装备");
+                    self.selection_stress = None;
+                    return;
+                }
+                *stress = SelectionStress::new(queue, self.loaded_model_id);
+            }
+
+            let now = ctx.input(|i| i.time);
+            let bytes = self.viewport.as_ref().map_or(0, |vp| vp.gpu_memory_bytes());
+            match stress.step(now, self.loaded_model_id, bytes) {
+                StressStep::Select(row_id) => {
+                    println!(
+                        "[选择压力测试] {}/{} 选中 {}, 已加载模型 {} 次, 视口显存 {}",
+                        stress.next,
+                        stress.queue.len(),
+                        row_id,
+                        stress.loads,
+                        format_mib(bytes)
+                    );
+                    self.selected_item_id = Some(row_id);
+                    self.equipment_list.reveal(row_id);
+                    ctx.request_repaint();
+                }
+                StressStep::Wait => ctx.request_repaint(),
+                StressStep::Done => {
+                    println!(
+                        "[选择压力测试] 完成: 切换 {} 次, 加载模型 {} 次, 视口显存峰值 {}, 结束时 {}",
+                        stress.queue.len(),
+                        stress.loads,
+                        format_mib(stress.peak_bytes),
+                        format_mib(bytes)
+                    );
+                    self.selection_stress = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stress_selects_at_interval_then_waits_for_last_load() {
+        let mut stress = SelectionStress::new(vec![10, 11, 12], Some(5));
+        assert_eq!(stress.step(0.0, Some(5), 100), StressStep::Select(10));
+        assert_eq!(stress.step(0.01, Some(5), 100), StressStep::Wait);
+        assert_eq!(stress.step(0.03, Some(5), 100), StressStep::Select(11));
+        assert_eq!(stress.step(0.06, Some(5), 100), StressStep::Select(12));
+        assert_eq!(stress.step(0.09, Some(5), 300), StressStep::Wait);
+        assert_eq!(stress.loads, 0);
+
+        assert_eq!(stress.step(0.2, Some(12), 200), StressStep::Done);
+        assert_eq!(stress.loads, 1);
+        assert_eq!(stress.peak_bytes, 300);
+    }
+
+    #[test]
+    fn stress_counts_intermediate_loads() {
+        let mut stress = SelectionStress::new(vec![10, 11], None);
+        assert_eq!(stress.step(0.0, None, 0), StressStep::Select(10));
+        assert_eq!(stress.step(0.03, Some(10), 0), StressStep::Select(11));
+        assert_eq!(stress.step(0.06, Some(10), 0), StressStep::Wait);
+        assert_eq!(stress.step(0.2, Some(11), 0), StressStep::Done);
+        assert_eq!(stress.loads, 2);
+    }
+}
@@ -0,0 +1,765 @@
+//! 设置页: 左侧分区列表 + 搜索，设置项由 `setting_defs` 声明式描述
+//!
+//! 修改立即生效，配置文件由后台线程合并写入，每秒最多一次 (见 `App::schedule_config_save`)。
+
+use std::path::PathBuf;
+
+use eframe::egui;
+
+use crate::app::App;
+use crate::config::AppConfig;
+use crate::domain::{shade_group_name, shade_groups, AppPage};
+use crate::export_name::{format_export_name, ExportNameContext};
+use crate::gpu::GRAPHICS_BACKENDS;
+use crate::i18n::{self, LANGS};
+use crate::loading::GameState;
+use crate::numfmt::NUMBER_STYLES;
+use crate::viewport_input::{DRAG_BUTTONS, INPUT_PRESETS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SettingsSection {
+    #[default]
+    General,
+    Appearance,
+    Viewport,
+    Crafting,
+    Keymap,
+    Advanced,
+}
+
+pub const SETTINGS_SECTIONS: [SettingsSection; 6] = [
+    SettingsSection::General,
+    SettingsSection::Appearance,
+    SettingsSection::Viewport,
+    SettingsSection::Crafting,
+    SettingsSection::Keymap,
+    SettingsSection::Advanced,
+];
+
+impl SettingsSection {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::General => tr!("settings.section.general"),
+            Self::Appearance => tr!("settings.section.appearance"),
+            Self::Viewport => tr!("settings.section.viewport"),
+            Self::Crafting => tr!("settings.section.crafting"),
+            Self::Keymap => tr!("settings.section.keymap"),
+            Self::Advanced => tr!("settings.section.advanced"),
+        }
+    }
+}
+
+/// 设置项的编辑方式
+pub enum SettingKind {
+    Toggle {
+        get: fn(&AppConfig) -> bool,
+        set: fn(&mut AppConfig, bool),
+    },
+    /// 下拉选择，选项为 (值, 显示文本)
+    Choice {
+        options: fn(&GameState) -> Vec<(u32, String)>,
+        get: fn(&AppConfig) -> u32,
+        set: fn(&mut AppConfig, u32),
+    },
+    /// 数值滑块，`suffix` 显示在数值后
+    Slider {
+        range: std::ops::RangeInclusive<f32>,
+        suffix: &'static str,
+        get: fn(&AppConfig) -> f32,
+        set: fn(&mut AppConfig, f32),
+    },
+    /// 单行文本，`preview` 在输入框下方显示按当前值生成的示例
+    Text {
+        get: fn(&AppConfig) -> String,
+        set: fn(&mut AppConfig, String),
+        preview: Option<fn(&AppConfig) -> String>,
+    },
+    /// 自定义编辑控件 (如列表)，`show` 返回是否修改，`reset` 恢复默认并返回是否改变
+    Custom {
+        show: fn(&mut egui::Ui, &mut AppConfig, &GameState) -> bool,
+        reset: fn(&mut AppConfig) -> bool,
+    },
+    /// 执行一次操作 (不对应单个配置值，不参与恢复默认)
+    Action {
+        button: &'static str,
+        run: fn(&mut App),
+    },
+}
+
+/// 一个设置项
+pub struct SettingDef {
+    pub section: SettingsSection,
+    pub label: &'static str,
+    pub hint: Option<&'static str>,
+    pub kind: SettingKind,
+    /// 值变化后同步运行时状态 (如切换界面语言)
+    pub apply: Option<fn(&mut App, &GameState)>,
+}
+
+impl SettingDef {
+    fn matches(&self, query_lower: &str) -> bool {
+        self.label.to_lowercase().contains(query_lower)
+            || self
+                .hint
+                .is_some_and(|h| h.to_lowercase().contains(query_lower))
+    }
+
+    /// 恢复为默认值，返回值是否改变
+    fn reset(&self, config: &mut AppConfig) -> bool {
+        let default = AppConfig::default();
+        match &self.kind {
+            SettingKind::Toggle { get, set } => {
+                let changed = get(config) != get(&default);
+                set(config, get(&default));
+                changed
+            }
+            SettingKind::Choice { get, set, .. } => {
+                let changed = get(config) != get(&default);
+                set(config, get(&default));
+                changed
+            }
+            SettingKind::Slider { get, set, .. } => {
+                let changed = get(config) != get(&default);
+                set(config, get(&default));
+                changed
+            }
+            SettingKind::Text { get, set, .. } => {
+                let changed = get(config) != get(&default);
+                set(config, get(&default));
+                changed
+            }
+            SettingKind::Custom { reset, .. } => reset(config),
+            SettingKind::Action { .. } => false,
+        }
+    }
+}
+
+/// 枚举值在选项数组中的位置，作为 Choice 的值
+fn index_of<T: PartialEq>(list: &[T], value: &T) -> u32 {
+    list.iter().position(|v| v == value).unwrap_or(0) as u32
+}
+
+/// 全部设置项 (每帧构建，文本随界面语言变化)
+pub fn setting_defs() -> Vec<SettingDef> {
+    use SettingsSection::*;
+    vec![
+        SettingDef {
+            section: General,
+            label: tr!("settings.install_dir"),
+            hint: Some(tr!("settings.install_dir_hint")),
+            kind: SettingKind::Action {
+                button: tr!("settings.change"),
+                run: |app| app.setup_request = true,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: General,
+            label: tr!("settings.language"),
+            hint: None,
+            kind: SettingKind::Choice {
+                options: |_| {
+                    LANGS
+                        .iter()
+                        .map(|l| (index_of(&LANGS, l), l.native_name().to_string()))
+                        .collect()
+                },
+                get: |c| index_of(&LANGS, &c.language),
+                set: |c, v| c.language = LANGS[v as usize],
+            },
+            apply: Some(|app, _| i18n::set_lang(app.config.language)),
+        },
+        SettingDef {
+            section: General,
+            label: tr!("settings.startup_page"),
+            hint: None,
+            kind: SettingKind::Choice {
+                options: |_| {
+                    AppPage::STARTUP_CHOICES
+                        .iter()
+                        .map(|p| {
+                            (index_of(&AppPage::STARTUP_CHOICES, p), p.label().to_string())
+                        })
+                        .collect()
+                },
+                get: |c| index_of(&AppPage::STARTUP_CHOICES, &c.startup_page),
+                set: |c, v| c.startup_page = AppPage::STARTUP_CHOICES[v as usize],
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: General,
+            label: tr!("setup.reopen_last_glamour"),
+            hint: Some(tr!("setup.reopen_last_glamour_hint")),
+            kind: SettingKind::Toggle {
+                get: |c| c.reopen_last_glamour,
+                set: |c, v| c.reopen_last_glamour = v,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: General,
+            label: tr!("settings.glamour_autosave"),
+            hint: Some(tr!("settings.glamour_autosave_hint")),
+            kind: SettingKind::Choice {
+                options: |_| {
+                    [30, 60, 120, 300]
+                        .into_iter()
+                        .map(|secs| (secs, format!("{} {}", secs, tr!("settings.seconds"))))
+                        .collect()
+                },
+                get: |c| c.glamour_autosave_interval(),
+                set: |c, v| c.glamour_autosave_secs = v,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: General,
+            label: tr!("settings.empty_slot_warning"),
+            hint: None,
+            kind: SettingKind::Toggle {
+                get: |c| !c.skip_empty_slot_warning,
+                set: |c, v| c.skip_empty_slot_warning = !v,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: General,
+            label: tr!("settings.export_name"),
+            hint: Some(tr!("settings.export_name_hint")),
+            kind: SettingKind::Text {
+                get: |c| c.export_name_template().to_string(),
+                set: |c, v| c.export_name_template = v,
+                preview: Some(|c| {
+                    let name = format_export_name(
+                        c.export_name_template(),
+                        &ExportNameContext::example(),
+                        "export",
+                    );
+                    format!("{}: {}.glb", tr!("settings.export_name_preview"), name)
+                }),
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: General,
+            label: tr!("settings.glamour_backup_dir"),
+            hint: Some(tr!("settings.glamour_backup_dir_hint")),
+            kind: SettingKind::Text {
+                get: |c| {
+                    c.glamour_backup_dir
+                        .as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default()
+                },
+                set: |c, v| c.glamour_backup_dir = (!v.is_empty()).then(|| PathBuf::from(v)),
+                preview: None,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: General,
+            label: tr!("settings.glamour_backup_sync"),
+            hint: Some(tr!("settings.glamour_backup_sync_hint")),
+            kind: SettingKind::Action {
+                button: tr!("settings.glamour_backup_sync_now"),
+                run: |app| app.glamour_backup_sync_requested = true,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Appearance,
+            label: tr!("settings.number_style"),
+            hint: None,
+            kind: SettingKind::Choice {
+                options: |_| {
+                    NUMBER_STYLES
+                        .iter()
+                        .map(|s| (index_of(&NUMBER_STYLES, s), s.example().to_string()))
+                        .collect()
+                },
+                get: |c| index_of(&NUMBER_STYLES, &c.number_style),
+                set: |c, v| c.number_style = NUMBER_STYLES[v as usize],
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Appearance,
+            label: tr!("settings.colorblind_mode"),
+            hint: Some(tr!("settings.colorblind_mode_hint")),
+            kind: SettingKind::Toggle {
+                get: |c| c.colorblind_friendly,
+                set: |c, v| c.colorblind_friendly = v,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Appearance,
+            label: tr!("settings.recent_items_strip"),
+            hint: Some(tr!("settings.recent_items_strip_hint")),
+            kind: SettingKind::Toggle {
+                get: |c| !c.hide_recent_items,
+                set: |c, v| c.hide_recent_items = !v,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Appearance,
+            label: tr!("settings.default_stain"),
+            hint: Some(tr!("settings.default_stain_hint")),
+            kind: SettingKind::Choice {
+                options: |gs| {
+                    std::iter::once((0, tr!("common.none").to_string()))
+                        .chain(gs.stains.iter().map(|s| (s.id, s.name.clone())))
+                        .collect()
+                },
+                get: |c| c.default_preview_stain,
+                set: |c, v| c.default_preview_stain = v,
+            },
+            apply: Some(|app, _| app.apply_default_preview_stain()),
+        },
+        SettingDef {
+            section: Appearance,
+            label: tr!("settings.shade_overrides"),
+            hint: Some(tr!("settings.shade_overrides_hint")),
+            kind: SettingKind::Custom {
+                show: show_shade_overrides,
+                reset: |c| !std::mem::take(&mut c.stain_shade_overrides).is_empty(),
+            },
+            apply: Some(|app, _| app.shade_overrides_changed = true),
+        },
+        SettingDef {
+            section: Viewport,
+            label: tr!("setup.adaptive_resolution"),
+            hint: None,
+            kind: SettingKind::Toggle {
+                get: |c| !c.disable_adaptive_resolution,
+                set: |c, v| c.disable_adaptive_resolution = !v,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Viewport,
+            label: tr!("setup.checkerboard"),
+            hint: None,
+            kind: SettingKind::Toggle {
+                get: |c| c.checkerboard_missing_textures,
+                set: |c, v| c.checkerboard_missing_textures = v,
+            },
+            apply: Some(|app, gs| {
+                gs.game
+                    .set_checkerboard_missing_textures(app.config.checkerboard_missing_textures)
+            }),
+        },
+        SettingDef {
+            section: Viewport,
+            label: tr!("settings.viewport_preset"),
+            hint: Some(tr!("settings.viewport_preset_hint")),
+            kind: SettingKind::Choice {
+                options: |_| {
+                    INPUT_PRESETS
+                        .iter()
+                        .map(|p| (index_of(&INPUT_PRESETS, p), p.label().to_string()))
+                        .chain(std::iter::once((
+                            INPUT_PRESETS.len() as u32,
+                            tr!("settings.viewport_preset_custom").to_string(),
+                        )))
+                        .collect()
+                },
+                get: |c| match c.viewport_input.preset() {
+                    Some(preset) => index_of(&INPUT_PRESETS, &preset),
+                    None => INPUT_PRESETS.len() as u32,
+                },
+                set: |c, v| {
+                    // 选择 "自定义" 不改变当前绑定
+                    if let Some(preset) = INPUT_PRESETS.get(v as usize) {
+                        c.viewport_input.apply_preset(*preset);
+                    }
+                },
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Viewport,
+            label: tr!("settings.orbit_button"),
+            hint: None,
+            kind: SettingKind::Choice {
+                options: |_| {
+                    DRAG_BUTTONS
+                        .iter()
+                        .map(|b| (index_of(&DRAG_BUTTONS, b), b.label().to_string()))
+                        .collect()
+                },
+                get: |c| index_of(&DRAG_BUTTONS, &c.viewport_input.orbit_button),
+                set: |c, v| c.viewport_input.orbit_button = DRAG_BUTTONS[v as usize],
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Viewport,
+            label: tr!("settings.pan_button"),
+            hint: None,
+            kind: SettingKind::Choice {
+                options: |_| {
+                    DRAG_BUTTONS
+                        .iter()
+                        .map(|b| (index_of(&DRAG_BUTTONS, b), b.label().to_string()))
+                        .collect()
+                },
+                get: |c| index_of(&DRAG_BUTTONS, &c.viewport_input.pan_button),
+                set: |c, v| c.viewport_input.pan_button = DRAG_BUTTONS[v as usize],
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Viewport,
+            label: tr!("settings.shift_pans"),
+            hint: None,
+            kind: SettingKind::Toggle {
+                get: |c| c.viewport_input.shift_pans,
+                set: |c, v| c.viewport_input.shift_pans = v,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Viewport,
+            label: tr!("settings.invert_zoom"),
+            hint: None,
+            kind: SettingKind::Toggle {
+                get: |c| c.viewport_input.invert_zoom,
+                set: |c, v| c.viewport_input.invert_zoom = v,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Viewport,
+            label: tr!("settings.orbit_sensitivity"),
+            hint: None,
+            kind: SettingKind::Slider {
+                range: 0.1..=5.0,
+                suffix: "×",
+                get: |c| c.viewport_input.orbit_sensitivity,
+                set: |c, v| c.viewport_input.orbit_sensitivity = v,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Viewport,
+            label: tr!("settings.zoom_sensitivity"),
+            hint: None,
+            kind: SettingKind::Slider {
+                range: 0.1..=5.0,
+                suffix: "×",
+                get: |c| c.viewport_input.zoom_sensitivity,
+                set: |c, v| c.viewport_input.zoom_sensitivity = v,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Viewport,
+            label: tr!("settings.pitch_limit"),
+            hint: Some(tr!("settings.pitch_limit_hint")),
+            kind: SettingKind::Slider {
+                range: 10.0..=89.0,
+                suffix: "°",
+                get: |c| c.viewport_input.pitch_limit_deg,
+                set: |c, v| c.viewport_input.pitch_limit_deg = v,
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Crafting,
+            label: tr!("settings.hidden_craft_types"),
+            hint: Some(tr!("settings.hidden_craft_types_hint")),
+            kind: SettingKind::Action {
+                button: tr!("settings.show_all"),
+                run: |app| app.config.hidden_craft_types.clear(),
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Advanced,
+            label: tr!("settings.graphics_backend"),
+            hint: Some(tr!("setup.restart_required")),
+            kind: SettingKind::Choice {
+                options: |_| {
+                    GRAPHICS_BACKENDS
+                        .iter()
+                        .map(|b| (index_of(&GRAPHICS_BACKENDS, b), b.label().to_string()))
+                        .collect()
+                },
+                get: |c| index_of(&GRAPHICS_BACKENDS, &c.graphics_backend),
+                set: |c, v| c.graphics_backend = GRAPHICS_BACKENDS[v as usize],
+            },
+            apply: None,
+        },
+        SettingDef {
+            section: Advanced,
+            label: tr!("settings.diagnostics"),
+            hint: None,
+            kind: SettingKind::Action {
+                button: tr!("setup.run_diagnostics"),
+                run: |app| {
+                    app.diagnostics_report = None;
+                    app.diagnostics_open = true;
+                },
+            },
+            apply: None,
+        },
+    ]
+}
+
+/// 设置页状态
+#[derive(Default)]
+pub struct SettingsPageState {
+    pub section: SettingsSection,
+    pub search: String,
+}
+
+impl App {
+    pub fn show_settings_page(&mut self, ctx: &egui::Context, gs: &GameState) {
+        let defs = setting_defs();
+        let query = self.settings_page.search.trim().to_lowercase();
+
+        egui::SidePanel::left("settings_sections")
+            .resizable(false)
+            .default_width(160.0)
+            .show(ctx, |ui| {
+                ui.heading(tr!("button.settings"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.settings_page.search)
+                        .hint_text(tr!("settings.search")),
+                );
+                ui.separator();
+                for section in SETTINGS_SECTIONS {
+                    let count = defs
+                        .iter()
+                        .filter(|d| d.section == section && d.matches(&query))
+                        .count();
+                    let selected = query.is_empty() && self.settings_page.section == section;
+                    let text = if query.is_empty() {
+                        section.label().to_string()
+                    } else {
+                        format!("{} ({})", section.label(), count)
+                    };
+                    let button = ui.add_enabled(
+                        query.is_empty() || count > 0,
+                        egui::Button::selectable(selected, text),
+                    );
+                    if button.clicked() {
+                        self.settings_page.section = section;
+                        self.settings_page.search.clear();
+                    }
+                }
+            });
+
+        let mut changed: Vec<usize> = Vec::new();
+        let mut actions: Vec<fn(&mut App)> = Vec::new();
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                // 搜索时跨分区列出匹配项，否则只显示当前分区
+                let sections: Vec<SettingsSection> = if query.is_empty() {
+                    vec![self.settings_page.section]
+                } else {
+                    SETTINGS_SECTIONS.to_vec()
+                };
+                for section in sections {
+                    let indices: Vec<usize> = (0..defs.len())
+                        .filter(|&i| defs[i].section == section && defs[i].matches(&query))
+                        .collect();
+                    if indices.is_empty() && !query.is_empty() {
+                        continue;
+                    }
+                    ui.horizontal(|ui| {
+                        ui.heading(section.label());
+                        let resettable = indices
+                            .iter()
+                            .any(|&i| !matches!(defs[i].kind, SettingKind::Action { .. }));
+                        if resettable && ui.button(tr!("settings.restore_defaults")).clicked() {
+                            for &i in &indices {
+                                if defs[i].reset(&mut self.config) {
+                                    changed.push(i);
+                                }
+                            }
+                        }
+                    });
+                    ui.separator();
+                    if indices.is_empty() {
+                        ui.weak(tr!("settings.empty_section"));
+                    }
+                    for &i in &indices {
+                        if show_setting(ui, &defs[i], &mut self.config, gs, &mut actions) {
+                            changed.push(i);
+                        }
+                    }
+                    ui.add_space(12.0);
+                }
+                if !query.is_empty() && defs.iter().all(|d| !d.matches(&query)) {
+                    ui.weak(tr!("settings.no_match"));
+                }
+            });
+        });
+
+        // 操作可能修改配置 (如清空隐藏的职业)，同样触发保存
+        let save = !changed.is_empty() || !actions.is_empty();
+        for run in actions {
+            run(self);
+        }
+        for &i in &changed {
+            if let Some(apply) = defs[i].apply {
+                apply(self, gs);
+            }
+        }
+        if save {
+            self.schedule_config_save(ctx);
+        }
+    }
+}
+
+/// 绘制一个设置项，返回配置值是否被修改；按钮操作推入 `actions`，由调用方执行
+fn show_setting(
+    ui: &mut egui::Ui,
+    def: &SettingDef,
+    config: &mut AppConfig,
+    gs: &GameState,
+    actions: &mut Vec<fn(&mut App)>,
+) -> bool {
+    let mut changed = false;
+    match &def.kind {
+        SettingKind::Toggle { get, set } => {
+            let mut value = get(config);
+            if ui.checkbox(&mut value, def.label).changed() {
+                set(config, value);
+                changed = true;
+            }
+        }
+        SettingKind::Choice { options, get, set } => {
+            let options = options(gs);
+            let mut value = get(config);
+            let selected_text = options
+                .iter()
+                .find(|(v, _)| *v == value)
+                .map(|(_, text)| text.as_str())
+                .unwrap_or_default();
+            ui.horizontal(|ui| {
+                ui.label(def.label);
+                egui::ComboBox::from_id_salt(def.label)
+                    .selected_text(selected_text)
+                    .height(300.0)
+                    .show_ui(ui, |ui| {
+                        for (v, text) in &options {
+                            changed |= ui.selectable_value(&mut value, *v, text).changed();
+                        }
+                    });
+            });
+            if changed {
+                set(config, value);
+            }
+        }
+        SettingKind::Slider {
+            range,
+            suffix,
+            get,
+            set,
+        } => {
+            let mut value = get(config);
+            ui.horizontal(|ui| {
+                ui.label(def.label);
+                changed = ui
+                    .add(egui::Slider::new(&mut value, range.clone()).suffix(*suffix))
+                    .changed();
+            });
+            if changed {
+                set(config, value);
+            }
+        }
+        SettingKind::Text { get, set, preview } => {
+            let mut value = get(config);
+            ui.horizontal(|ui| {
+                ui.label(def.label);
+                changed = ui.text_edit_singleline(&mut value).changed();
+            });
+            if changed {
+                set(config, value);
+            }
+            if let Some(preview) = preview {
+                ui.weak(preview(config));
+            }
+        }
+        SettingKind::Custom { show, .. } => {
+            ui.label(def.label);
+            changed = show(ui, config, gs);
+        }
+        SettingKind::Action { button, run } => {
+            ui.horizontal(|ui| {
+                ui.label(def.label);
+                if ui.button(*button).clicked() {
+                    actions.push(*run);
+                }
+            });
+        }
+    }
+    if let Some(hint) = def.hint {
+        ui.label(egui::RichText::new(hint).small().weak());
+    }
+    changed
+}
+
+/// 染料分组覆盖列表: 每行一种染料及其显示分组，可删除；末尾下拉添加新的染料
+///
+/// 保存在配置文件的 `stain_shade_overrides` (染料 ID -> 分组编号)
+fn show_shade_overrides(ui: &mut egui::Ui, config: &mut AppConfig, gs: &GameState) -> bool {
+    let groups = shade_groups(&gs.stains);
+    let stain_name = |id: u32| {
+        gs.stains
+            .iter()
+            .find(|s| s.id == id)
+            .map_or_else(|| format!("#{}", id), |s| s.name.clone())
+    };
+    let mut ids: Vec<u32> = config.stain_shade_overrides.keys().copied().collect();
+    ids.sort_unstable();
+
+    let mut changed = false;
+    let mut removed = None;
+    for id in ids {
+        let Some(shade) = config.stain_shade_overrides.get_mut(&id) else {
+            continue;
+        };
+        ui.horizontal(|ui| {
+            ui.label(stain_name(id));
+            ui.label("→");
+            egui::ComboBox::from_id_salt(("shade_override", id))
+                .selected_text(shade_group_name(*shade))
+                .show_ui(ui, |ui| {
+                    for &group in &groups {
+                        changed |= ui
+                            .selectable_value(shade, group, shade_group_name(group))
+                            .changed();
+                    }
+                });
+            if ui.small_button("✕").clicked() {
+                removed = Some(id);
+            }
+        });
+    }
+    if let Some(id) = removed {
+        config.stain_shade_overrides.remove(&id);
+        changed = true;
+    }
+
+    egui::ComboBox::from_id_salt("shade_override_add")
+        .selected_text(tr!("settings.shade_overrides_add"))
+        .height(300.0)
+        .show_ui(ui, |ui| {
+            for stain in &gs.stains {
+                if config.stain_shade_overrides.contains_key(&stain.id) {
+                    continue;
+                }
+                if ui.selectable_label(false, stain.name.as_str()).clicked() {
+                    config.stain_shade_overrides.insert(stain.id, stain.shade);
+                    changed = true;
+                }
+            }
+        });
+    changed
+}
@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use eframe::egui;
 
 use crate::app::{App, AppPhase};
+use crate::i18n::{self, LANGS};
 
 impl App {
     pub fn show_setup_ui(&mut self, ctx: &egui::Context) {
@@ -14,58 +15,118 @@ impl App {
         let mut new_dir_input = dir_input;
         let mut confirm = false;
         let mut cancel = false;
+        let mut run_diagnostics = false;
+        let mut use_mock = false;
         let has_game_state = self.game_state.is_some();
+        let mut language = self.config.language;
+        let mut rescan = false;
+        let candidates = self
+            .install_candidates
+            .get_or_insert_with(crate::install_detect::detect_installs)
+            .clone();
 
         egui::CentralPanel::default().show(ctx, |ui| {
             let panel_width = 500.0_f32;
-            let panel_height = 120.0_f32;
+            let panel_height = 350.0_f32 + 24.0 * candidates.len() as f32;
             let center = ui.max_rect().center();
             let rect = egui::Rect::from_center_size(center, egui::vec2(panel_width, panel_height));
             ui.scope_builder(egui::UiBuilder::new().max_rect(rect), |ui| {
                 ui.vertical_centered(|ui| {
-                    ui.heading("请选择 FF14 安装目录");
+                    ui.heading(tr!("setup.heading"));
                     ui.add_space(16.0);
 
                     if let Some(err) = &error {
                         ui.colored_label(egui::Color32::RED, err);
+                        if ui
+                            .button(tr!("setup.use_mock"))
+                            .on_hover_text(tr!("setup.use_mock_hint"))
+                            .clicked()
+                        {
+                            use_mock = true;
+                        }
                         ui.add_space(8.0);
                     }
 
+                    // 自动检测到的安装目录，点击即使用该目录
+                    ui.horizontal(|ui| {
+                        ui.label(tr!("setup.detected"));
+                        if ui.small_button(tr!("setup.rescan")).clicked() {
+                            rescan = true;
+                        }
+                    });
+                    if candidates.is_empty() {
+                        ui.label(egui::RichText::new(tr!("setup.detect_none")).small().weak());
+                    }
+                    for candidate in &candidates {
+                        let text =
+                            format!("[{}] {}", candidate.kind.label(), candidate.path.display());
+                        if ui.selectable_label(false, text).clicked() {
+                            new_dir_input = candidate.path.display().to_string();
+                            confirm = true;
+                        }
+                    }
+                    ui.add_space(8.0);
+
                     ui.horizontal(|ui| {
-                        ui.label("安装目录:");
+                        ui.label(tr!("setup.install_dir"));
                         ui.add_sized(
                             [ui.available_width() - 60.0, 20.0],
                             egui::TextEdit::singleline(&mut new_dir_input),
                         );
-                        if ui.button("浏览...").clicked() {
+                        if ui.button(tr!("button.browse")).clicked() {
                             if let Some(folder) = rfd::FileDialog::new().pick_folder() {
                                 new_dir_input = folder.display().to_string();
                             }
                         }
                     });
 
+                    ui.horizontal(|ui| {
+                        ui.label(tr!("setup.language"));
+                        egui::ComboBox::from_id_salt("ui_language")
+                            .selected_text(language.native_name())
+                            .show_ui(ui, |ui| {
+                                for lang in LANGS {
+                                    ui.selectable_value(&mut language, lang, lang.native_name());
+                                }
+                            });
+                    });
+                    ui.label(egui::RichText::new(tr!("setup.more_in_settings")).small().weak());
+
                     ui.add_space(8.0);
 
                     ui.horizontal(|ui| {
-                        if ui.button("确定").clicked() {
+                        if ui.button(tr!("button.ok")).clicked() {
                             confirm = true;
                         }
-                        if has_game_state && ui.button("取消").clicked() {
+                        if has_game_state && ui.button(tr!("button.cancel")).clicked() {
                             cancel = true;
                         }
+                        if has_game_state && ui.button(tr!("setup.run_diagnostics")).clicked() {
+                            run_diagnostics = true;
+                        }
                     });
                 });
             });
         });
 
-        if confirm {
+        if rescan {
+            self.install_candidates = None;
+        }
+
+        if language != self.config.language {
+            self.config.language = language;
+            self.schedule_config_save(ctx);
+            i18n::set_lang(language);
+        }
+
+        if use_mock {
+            self.start_mock_loading();
+        } else if confirm {
             let path = PathBuf::from(&new_dir_input);
             match crate::game::validate_install_dir(&path) {
                 Ok(()) => {
                     self.config.game_install_dir = Some(path.clone());
-                    if let Err(e) = crate::config::save_config(&self.config) {
-                        eprintln!("保存配置失败: {}", e);
-                    }
+                    self.flush_config();
                     self.start_loading(path);
                 }
                 Err(e) => {
@@ -77,6 +138,11 @@ impl App {
             }
         } else if cancel {
             self.phase = AppPhase::Ready;
+        } else if run_diagnostics {
+            // 回到主界面并重新运行自检
+            self.diagnostics_report = None;
+            self.diagnostics_open = true;
+            self.phase = AppPhase::Ready;
         } else if let AppPhase::Setup { dir_input, .. } = &mut self.phase {
             *dir_input = new_dir_input;
         }
@@ -0,0 +1,148 @@
+//! 视口鼠标操作绑定: 旋转/平移使用的按键、缩放方向与灵敏度、俯仰角限制
+//!
+//! 所有 3D 视口共用同一份配置，App 每帧同步到各视口，修改后立即生效。
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+/// 拖拽使用的鼠标按键
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DragButton {
+    Primary,
+    Secondary,
+    Middle,
+}
+
+pub const DRAG_BUTTONS: [DragButton; 3] = [
+    DragButton::Primary,
+    DragButton::Secondary,
+    DragButton::Middle,
+];
+
+impl DragButton {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Primary => tr!("mouse.primary"),
+            Self::Secondary => tr!("mouse.secondary"),
+            Self::Middle => tr!("mouse.middle"),
+        }
+    }
+
+    pub fn pointer_button(self) -> egui::PointerButton {
+        match self {
+            Self::Primary => egui::PointerButton::Primary,
+            Self::Secondary => egui::PointerButton::Secondary,
+            Self::Middle => egui::PointerButton::Middle,
+        }
+    }
+}
+
+/// 旋转灵敏度为 1 时每像素转过的弧度
+const ORBIT_RADIANS_PER_PIXEL: f32 = 0.01;
+/// 缩放灵敏度为 1 时每滚动单位的步长 (再乘以场景尺寸)
+const ZOOM_STEP_PER_SCROLL: f32 = 0.005;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewportInputConfig {
+    pub orbit_button: DragButton,
+    pub pan_button: DragButton,
+    /// 按住 Shift 时旋转键改为平移 (Blender 的 Shift+中键)
+    pub shift_pans: bool,
+    /// 反转滚轮缩放方向
+    pub invert_zoom: bool,
+    /// 旋转灵敏度倍率
+    pub orbit_sensitivity: f32,
+    /// 缩放灵敏度倍率
+    pub zoom_sensitivity: f32,
+    /// 俯仰角上下限 (度)，避免越过头顶后画面翻转
+    pub pitch_limit_deg: f32,
+}
+
+impl Default for ViewportInputConfig {
+    fn default() -> Self {
+        Self {
+            orbit_button: DragButton::Primary,
+            pan_button: DragButton::Secondary,
+            shift_pans: false,
+            invert_zoom: false,
+            orbit_sensitivity: 1.0,
+            zoom_sensitivity: 1.0,
+            pitch_limit_deg: 1.5f32.to_degrees(),
+        }
+    }
+}
+
+/// 预设: 只覆盖按键布局，灵敏度与俯仰角限制保持用户的设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputPreset {
+    Default,
+    Blender,
+}
+
+pub const INPUT_PRESETS: [InputPreset; 2] = [InputPreset::Default, InputPreset::Blender];
+
+impl InputPreset {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Default => tr!("settings.viewport_preset_default"),
+            Self::Blender => tr!("settings.viewport_preset_blender"),
+        }
+    }
+
+    fn apply(self, config: &mut ViewportInputConfig) {
+        let (orbit, pan, shift_pans) = match self {
+            Self::Default => (DragButton::Primary, DragButton::Secondary, false),
+            Self::Blender => (DragButton::Middle, DragButton::Secondary, true),
+        };
+        config.orbit_button = orbit;
+        config.pan_button = pan;
+        config.shift_pans = shift_pans;
+        config.invert_zoom = false;
+    }
+}
+
+impl ViewportInputConfig {
+    /// 当前按键布局对应的预设，手动调整过时为 None
+    pub fn preset(&self) -> Option<InputPreset> {
+        INPUT_PRESETS.into_iter().find(|preset| {
+            let mut expected = *self;
+            preset.apply(&mut expected);
+            expected == *self
+        })
+    }
+
+    pub fn apply_preset(&mut self, preset: InputPreset) {
+        preset.apply(self);
+    }
+
+    /// 本帧的旋转与平移拖拽: (是否旋转, 是否平移)
+    pub fn drag_modes(&self, response: &egui::Response, shift: bool) -> (bool, bool) {
+        let orbit = self.orbit_button.pointer_button();
+        let pan = self.pan_button.pointer_button();
+        let orbit_dragged = response.dragged_by(orbit);
+        let shift_pan = self.shift_pans && shift && orbit_dragged;
+        // 旋转与平移绑定同一按键时旋转优先
+        let pan_dragged = (pan != orbit && response.dragged_by(pan)) || shift_pan;
+        (orbit_dragged && !shift_pan, pan_dragged)
+    }
+
+    /// 拖拽位移对应的 (偏航, 俯仰) 增量 (弧度)
+    pub fn orbit_delta(&self, delta: egui::Vec2) -> (f32, f32) {
+        let scale = ORBIT_RADIANS_PER_PIXEL * self.orbit_sensitivity;
+        (delta.x * scale, delta.y * scale)
+    }
+
+    /// 俯仰角限制 (弧度)
+    pub fn pitch_limit(&self) -> f32 {
+        self.pitch_limit_deg.to_radians()
+    }
+
+    /// 滚轮滚动量对应的缩放量，`scene_size` 为当前场景尺寸
+    pub fn zoom_amount(&self, scroll: f32, scene_size: f32) -> f32 {
+        // 缩放步长随场景尺寸放大，超大模型也能较快拉远/拉近
+        let step = ZOOM_STEP_PER_SCROLL * self.zoom_sensitivity * (scene_size / 2.0).max(1.0);
+        let direction = if self.invert_zoom { -1.0 } else { 1.0 };
+        scroll * step * direction
+    }
+}